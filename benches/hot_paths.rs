@@ -0,0 +1,154 @@
+//! Criterion benchmarks for the loop's hot paths, driven from Rust only.
+//!
+//!     cargo bench --features bench
+//!
+//! See benches/README.md for comparing against a saved baseline.
+
+use std::hint::black_box;
+use std::os::fd::AsRawFd;
+use std::sync::Arc;
+
+use _veloxloop::bench::{
+    BufferPool, IoCallback, IoHandles, LoopPoller, ReaderDriver, Timers, poll_cycle, socketpair,
+    uring_echo,
+};
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+const MS: u64 = 1_000_000;
+const TIMER_COUNT: u64 = 1_000_000;
+const HANDLE_CYCLES: u64 = 1_000_000;
+
+fn poller(c: &mut Criterion) {
+    let mut group = c.benchmark_group("poller");
+    for fds in [1usize, 64] {
+        let mut poller = LoopPoller::new().unwrap();
+        let pairs: Vec<_> = (0..fds).map(|_| socketpair().unwrap()).collect();
+        group.throughput(Throughput::Elements(fds as u64));
+        group.bench_with_input(BenchmarkId::new("register_modify_poll", fds), &fds, |b, _| {
+            b.iter(|| poll_cycle(&mut poller, &pairs).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn uring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("uring");
+    let mut poller = LoopPoller::new().unwrap();
+    let (a, b) = socketpair().unwrap();
+    for size in [64usize, 16 * 1024] {
+        let payload = vec![0x5a; size];
+        let mut buf = vec![0u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("send_recv", size), &size, |bench, _| {
+            bench.iter(|| {
+                uring_echo(&mut poller, a.as_raw_fd(), b.as_raw_fd(), &payload, &mut buf).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Expiries spread over one second, so every wheel level gets entries
+fn filled_timers() -> (Timers<u64>, Vec<u64>) {
+    let mut timers = Timers::new();
+    let ids = (0..TIMER_COUNT)
+        .map(|i| timers.insert((i * 7919) % 1000 * MS, i, Vec::new(), None, 0))
+        .collect();
+    (timers, ids)
+}
+
+fn timers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("timers");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(TIMER_COUNT));
+    group.bench_function("insert_1m", |b| b.iter(filled_timers));
+    group.bench_function("cancel_1m", |b| {
+        b.iter_batched(
+            filled_timers,
+            |(mut timers, ids)| {
+                for id in ids {
+                    black_box(timers.cancel(id));
+                }
+                timers
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("pop_1m", |b| {
+        b.iter_batched(
+            filled_timers,
+            |(mut timers, _)| {
+                assert_eq!(timers.pop_expired(1000 * MS, 0).len(), TIMER_COUNT as usize);
+                timers
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+fn stream_reader(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stream_reader");
+    let mut request = Vec::new();
+    for i in 0..64 {
+        request.extend_from_slice(format!("X-Header-{i}: some header value\r\n").as_bytes());
+    }
+    request.extend_from_slice(b"\r\n");
+    let lines = request.split(|&b| b == b'\n').count() - 1;
+
+    group.throughput(Throughput::Bytes(request.len() as u64));
+    for chunk in [16usize, 4096] {
+        group.bench_with_input(BenchmarkId::new("feed_readline", chunk), &chunk, |b, &chunk| {
+            let reader = ReaderDriver::new(None);
+            b.iter(|| {
+                let mut parsed = 0;
+                for part in request.chunks(chunk) {
+                    reader.feed(part);
+                    while reader.readuntil(b"\n").is_some() {
+                        parsed += 1;
+                    }
+                }
+                assert_eq!(parsed, lines);
+            })
+        });
+    }
+    group.bench_function("feed_readuntil_crlfcrlf", |b| {
+        let reader = ReaderDriver::new(None);
+        b.iter(|| {
+            reader.feed(&request);
+            black_box(reader.readuntil(b"\r\n\r\n").unwrap());
+        })
+    });
+    group.finish();
+}
+
+fn buffer_pool(c: &mut Criterion) {
+    let mut group = c.benchmark_group("buffer_pool");
+    for size in [4 * 1024usize, 128 * 1024] {
+        group.bench_with_input(BenchmarkId::new("acquire_release", size), &size, |b, &size| {
+            b.iter(|| BufferPool::release(black_box(BufferPool::acquire_sized(size))))
+        });
+    }
+    group.finish();
+}
+
+fn io_handles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("io_handles");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(HANDLE_CYCLES));
+    let callback = IoCallback::Native(Arc::new(|_| Ok(())));
+    let mut handles = IoHandles::with_capacity(256);
+    group.bench_function("add_remove_reader", |b| {
+        b.iter(|| {
+            for i in 0..HANDLE_CYCLES {
+                let fd = (i % 256) as i32;
+                handles.add_reader(fd, callback.clone());
+                black_box(handles.remove_reader(fd));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, poller, uring, timers, stream_reader, buffer_pool, io_handles);
+criterion_main!(benches);
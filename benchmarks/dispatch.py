@@ -0,0 +1,212 @@
+"""Protocol dispatch microbenchmark: data_received calls per second.
+
+The receiving transport reads in 1 KB chunks, so every read event fans out into
+many data_received calls and the rate is dominated by the per-call dispatch
+cost rather than by syscalls. With --udp, datagram_received calls are counted
+instead while a thread floods the endpoint with small datagrams; --udp-echo
+measures echoed 64-byte datagrams per second with 32 in flight. --large reads
+4 MB chunks instead and reports throughput together with the longest a
+second Python thread was kept waiting for the GIL meanwhile.
+
+Usage:
+    python dispatch.py [--mbytes 256] [--rounds 5]
+    python dispatch.py --udp [--seconds 2] [--rounds 5]
+    python dispatch.py --udp-echo [--seconds 2] [--rounds 5]
+    python dispatch.py --large [--mbytes 256] [--rounds 5]
+"""
+
+import argparse
+import asyncio
+import socket
+import threading
+import time
+
+import veloxloop
+
+CHUNK = 1024
+LARGE_CHUNK = 4 * 1024 * 1024
+
+
+class Counting(asyncio.Protocol):
+    def __init__(self, expected, done, chunk=CHUNK):
+        self.chunk = chunk
+        self.expected = expected
+        self.received = 0
+        self.calls = 0
+        self.done = done
+
+    def connection_made(self, transport):
+        transport.set_read_chunk_size(self.chunk)
+
+    def data_received(self, data):
+        self.calls += 1
+        self.received += len(data)
+        if self.received >= self.expected and not self.done.done():
+            self.done.set_result(None)
+
+
+def send_blob(port, total):
+    payload = b'x' * (1024 * 1024)
+    with socket.create_connection(('127.0.0.1', port)) as sock:
+        sent = 0
+        while sent < total:
+            sock.sendall(payload)
+            sent += len(payload)
+
+
+async def run_round(total, chunk=CHUNK):
+    loop = asyncio.get_running_loop()
+    done = loop.create_future()
+    protocols = []
+
+    def factory():
+        protocol = Counting(total, done, chunk)
+        protocols.append(protocol)
+        return protocol
+
+    server = await loop.create_server(factory, '127.0.0.1', 0)
+    port = server.sockets[0].getsockname()[1]
+    sender = threading.Thread(target=send_blob, args=(port, total))
+    start = time.perf_counter()
+    sender.start()
+    await done
+    elapsed = time.perf_counter() - start
+    sender.join()
+    server.close()
+    return protocols[0].calls / elapsed
+
+
+def tick(stop, stalls):
+    """Wake every 100 µs, recording the longest gap between wakeups"""
+    last = time.perf_counter()
+    while not stop.is_set():
+        time.sleep(0.0001)
+        now = time.perf_counter()
+        stalls[0] = max(stalls[0], now - last)
+        last = now
+
+
+async def run_large_round(total):
+    stop = threading.Event()
+    stalls = [0.0]
+    ticker = threading.Thread(target=tick, args=(stop, stalls))
+    ticker.start()
+    start = time.perf_counter()
+    await run_round(total, LARGE_CHUNK)
+    elapsed = time.perf_counter() - start
+    stop.set()
+    ticker.join()
+    return total / elapsed / (1024 * 1024), stalls[0]
+
+
+class CountingDatagrams(asyncio.DatagramProtocol):
+    def __init__(self):
+        self.calls = 0
+
+    def datagram_received(self, data, addr):
+        self.calls += 1
+
+
+def flood(port, stop):
+    payload = b'x' * 64
+    with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as sock:
+        while not stop.is_set():
+            for _ in range(256):
+                sock.sendto(payload, ('127.0.0.1', port))
+
+
+async def run_udp_round(seconds):
+    loop = asyncio.get_running_loop()
+    transport, protocol = await loop.create_datagram_endpoint(
+        CountingDatagrams, local_addr=('127.0.0.1', 0)
+    )
+    port = transport.get_extra_info('sockname')[1]
+    stop = threading.Event()
+    sender = threading.Thread(target=flood, args=(port, stop))
+    sender.start()
+    await asyncio.sleep(seconds)
+    stop.set()
+    sender.join()
+    transport.close()
+    return protocol.calls / seconds
+
+
+class EchoDatagrams(asyncio.DatagramProtocol):
+    def connection_made(self, transport):
+        self.transport = transport
+
+    def datagram_received(self, data, addr):
+        self.transport.sendto(data, addr)
+
+
+def ping(port, seconds, replies):
+    payload = b'x' * 64
+    window = 32
+    with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as sock:
+        sock.settimeout(1)
+        deadline = time.perf_counter() + seconds
+        while time.perf_counter() < deadline:
+            for _ in range(window):
+                sock.sendto(payload, ('127.0.0.1', port))
+            try:
+                for _ in range(window):
+                    sock.recv(64)
+                    replies[0] += 1
+            except TimeoutError:
+                pass
+
+
+async def run_udp_echo_round(seconds):
+    loop = asyncio.get_running_loop()
+    transport, _ = await loop.create_datagram_endpoint(
+        EchoDatagrams, local_addr=('127.0.0.1', 0)
+    )
+    port = transport.get_extra_info('sockname')[1]
+    replies = [0]
+    sender = threading.Thread(target=ping, args=(port, seconds, replies))
+    start = time.perf_counter()
+    sender.start()
+    await loop.run_in_executor(None, sender.join)
+    elapsed = time.perf_counter() - start
+    transport.close()
+    return replies[0] / elapsed
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument('--mbytes', type=int, default=256)
+    parser.add_argument('--rounds', type=int, default=5)
+    parser.add_argument('--udp', action='store_true')
+    parser.add_argument('--udp-echo', action='store_true')
+    parser.add_argument('--large', action='store_true')
+    parser.add_argument('--seconds', type=float, default=2.0)
+    args = parser.parse_args()
+
+    veloxloop.install()
+    if args.large:
+        total = args.mbytes * 1024 * 1024
+        results = [asyncio.run(run_large_round(total)) for _ in range(args.rounds)]
+        rate = max(rate for rate, _ in results)
+        stall = min(stall for _, stall in results)
+        print(
+            f'4 MB data_received: {rate:,.0f} MB/s, other thread stalled '
+            f'{stall * 1e6:,.0f} µs at most (best of {args.rounds})'
+        )
+        return
+    if args.udp_echo:
+        name = 'udp echo'
+        rates = [
+            asyncio.run(run_udp_echo_round(args.seconds)) for _ in range(args.rounds)
+        ]
+    elif args.udp:
+        name = 'datagram_received'
+        rates = [asyncio.run(run_udp_round(args.seconds)) for _ in range(args.rounds)]
+    else:
+        name = 'data_received'
+        total = args.mbytes * 1024 * 1024
+        rates = [asyncio.run(run_round(total)) for _ in range(args.rounds)]
+    print(f'{name}: {max(rates):,.0f} calls/s (best of {args.rounds})')
+
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,81 @@
+"""Fan-out wakeup microbenchmark: many readline() waiters on one StreamReader.
+
+A native start_server() connection has --waiters readline() futures pending on
+its reader, each with a done callback; the client then sends one line per
+waiter in a single write, so one read satisfies every waiter. Reported are the
+time until every callback ran and the longest stretch the loop went without
+getting back to a ticker callback, which is how long other connections would
+have waited for a poll.
+
+Usage:
+    python fanout.py [--waiters 10000] [--rounds 3]
+"""
+
+import argparse
+import asyncio
+import time
+
+import veloxloop
+
+
+async def run_round(waiters):
+    loop = asyncio.get_running_loop()
+    readers = loop.create_future()
+    all_resumed = loop.create_future()
+    stalls = []
+    resumed = 0
+    running = True
+
+    def tick(last):
+        now = time.perf_counter()
+        stalls.append(now - last)
+        if running:
+            loop.call_soon(tick, now)
+
+    def on_line(_):
+        nonlocal resumed
+        resumed += 1
+        if resumed == waiters:
+            all_resumed.set_result(None)
+
+    async def handler(reader, writer):
+        readers.set_result(reader)
+        await asyncio.sleep(3600)
+
+    server = await loop.start_server(handler, '127.0.0.1', 0)
+    port = server.sockets[0].getsockname()[1]
+    _, writer = await loop.open_connection('127.0.0.1', port)
+    reader = await readers
+    for _ in range(waiters):
+        reader.readline().add_done_callback(on_line)
+    await asyncio.sleep(0.05)
+
+    payload = b''.join(b'%d\n' % i for i in range(waiters))
+    loop.call_soon(tick, time.perf_counter())
+    start = time.perf_counter()
+    writer.write(payload)
+    await all_resumed
+    elapsed = time.perf_counter() - start
+    running = False
+    writer.close()
+    server.close()
+    return elapsed, max(stalls), len(stalls)
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument('--waiters', type=int, default=10000)
+    parser.add_argument('--rounds', type=int, default=3)
+    args = parser.parse_args()
+
+    veloxloop.install()
+    for _ in range(args.rounds):
+        elapsed, stall, ticks = asyncio.run(run_round(args.waiters))
+        print(
+            f'{args.waiters} waiters resumed in {elapsed * 1000:.1f} ms over {ticks} '
+            f'iterations, longest loop stall {stall * 1000:.2f} ms'
+        )
+
+
+if __name__ == '__main__':
+    main()
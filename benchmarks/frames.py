@@ -0,0 +1,72 @@
+"""Large-frame read microbenchmark: readexactly() against readexactly_into().
+
+A thread streams fixed-size frames into a native start_server() connection and
+the handler reads them back one frame at a time. readexactly() returns a fresh
+bytes object per frame; readexactly_into() fills one preallocated bytearray,
+reading straight from the socket once the buffered prefix is used up.
+
+Usage:
+    python frames.py [--mbytes 1024] [--frame-mbytes 8] [--rounds 3]
+"""
+
+import argparse
+import asyncio
+import socket
+import threading
+import time
+
+import veloxloop
+
+
+def send_frames(port, total, frame):
+    payload = b'x' * frame
+    with socket.create_connection(('127.0.0.1', port)) as sock:
+        sent = 0
+        while sent < total:
+            sock.sendall(payload)
+            sent += frame
+
+
+async def run_round(total, frame, into):
+    loop = asyncio.get_running_loop()
+    done = loop.create_future()
+
+    async def handler(reader, writer):
+        target = bytearray(frame)
+        for _ in range(total // frame):
+            # The native reader answers at once when the frame is buffered
+            result = reader.readexactly_into(target) if into else reader.readexactly(frame)
+            if not isinstance(result, (int, bytes)):
+                await result
+        done.set_result(None)
+        writer.close()
+
+    server = await loop.start_server(handler, '127.0.0.1', 0)
+    port = server.sockets[0].getsockname()[1]
+    sender = threading.Thread(target=send_frames, args=(port, total, frame))
+    start = time.perf_counter()
+    sender.start()
+    await done
+    elapsed = time.perf_counter() - start
+    await loop.run_in_executor(None, sender.join)
+    server.close()
+    return total / elapsed / (1024 * 1024)
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument('--mbytes', type=int, default=1024)
+    parser.add_argument('--frame-mbytes', type=int, default=8)
+    parser.add_argument('--rounds', type=int, default=3)
+    args = parser.parse_args()
+
+    veloxloop.install()
+    total = args.mbytes * 1024 * 1024
+    frame = args.frame_mbytes * 1024 * 1024
+    for name, into in (('readexactly', False), ('readexactly_into', True)):
+        rates = [asyncio.run(run_round(total, frame, into)) for _ in range(args.rounds)]
+        print(f'{name}: {max(rates):,.0f} MB/s (best of {args.rounds})')
+
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,104 @@
+"""sock_recv()/sock_sendall() ping-pong: messages per second, and the poller
+operations each message cost.
+
+Two tasks bounce a 64-byte message over a socketpair. The poller counts from
+loop.get_stats() are divided by the number of round trips: a wait costs one
+re-arm, and registering or cancelling a poll per wait would show up in the
+other two columns. --timeout instead retries a sock_recv that keeps timing
+out, the pattern a parked poll is reused by.
+
+Usage:
+    python sock_pingpong.py [--messages 20000] [--rounds 5]
+    python sock_pingpong.py --timeout [--messages 2000] [--rounds 5]
+"""
+
+import argparse
+import asyncio
+import socket
+import time
+
+import veloxloop
+
+MESSAGE = b'p' * 64
+POLLER_STATS = ('poller_registers', 'poller_rearms', 'poller_deletes')
+
+
+async def bounce(sock, count, serve):
+    loop = asyncio.get_running_loop()
+    for _ in range(count):
+        if not serve:
+            await loop.sock_sendall(sock, MESSAGE)
+        received = 0
+        while received < len(MESSAGE):
+            received += len(await loop.sock_recv(sock, len(MESSAGE) - received))
+        if serve:
+            await loop.sock_sendall(sock, MESSAGE)
+
+
+async def run_round(count):
+    loop = asyncio.get_running_loop()
+    a, b = socket.socketpair()
+    a.setblocking(False)
+    b.setblocking(False)
+    before = loop.get_stats()
+    start = time.perf_counter()
+    await asyncio.gather(bounce(a, count, False), bounce(b, count, True))
+    elapsed = time.perf_counter() - start
+    after = loop.get_stats()
+    a.close()
+    b.close()
+    return count / elapsed, [(after[k] - before[k]) / count for k in POLLER_STATS]
+
+
+async def run_timeout_round(count):
+    loop = asyncio.get_running_loop()
+    a, b = socket.socketpair()
+    a.setblocking(False)
+    b.setblocking(False)
+    before = loop.get_stats()
+    start = time.perf_counter()
+    for _ in range(count):
+        try:
+            await asyncio.wait_for(loop.sock_recv(a, 64), 0.0001)
+        except asyncio.TimeoutError:
+            pass
+    elapsed = time.perf_counter() - start
+    after = loop.get_stats()
+    a.close()
+    b.close()
+    reused = (after['sock_op_reuses'] - before['sock_op_reuses']) / count
+    costs = [(after[k] - before[k]) / count for k in POLLER_STATS]
+    return count / elapsed, costs, reused
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument('--messages', type=int, default=None)
+    parser.add_argument('--rounds', type=int, default=5)
+    parser.add_argument('--timeout', action='store_true')
+    args = parser.parse_args()
+
+    veloxloop.install()
+    if args.timeout:
+        count = args.messages or 2000
+        results = [asyncio.run(run_timeout_round(count)) for _ in range(args.rounds)]
+        rate, costs, reused = max(results)
+        print(
+            f'timed out sock_recv: {rate:,.0f} retries/s, per retry '
+            f'{costs[0]:.2f} registers / {costs[1]:.2f} rearms / '
+            f'{costs[2]:.2f} deletes, {reused:.2f} parked polls reused '
+            f'(best of {args.rounds})'
+        )
+        return
+    count = args.messages or 20000
+    results = [asyncio.run(run_round(count)) for _ in range(args.rounds)]
+    rate, costs = max(results)
+    print(
+        f'sock ping-pong: {rate:,.0f} round trips/s, per round trip '
+        f'{costs[0]:.2f} registers / {costs[1]:.2f} rearms / '
+        f'{costs[2]:.2f} deletes (best of {args.rounds})'
+    )
+
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,119 @@
+//! Rust-only drivers for the loop's hot paths, compiled with the `bench`
+//! feature and used by `benches/`. Nothing here touches the Python
+//! interpreter, so `cargo bench --features bench` runs without one.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+use crate::constants::DEFAULT_READ_CHUNK_SIZE;
+use crate::streams::StreamReader;
+
+pub use crate::buffer_pool::BufferPool;
+pub use crate::handles::{IoCallback, IoHandles};
+pub use crate::poller::{IoToken, LoopPoller, PlatformEvent, PollerEvent};
+pub use crate::timers::{TimerEntry, Timers};
+pub use crate::utils::{VeloxError, VeloxResult};
+
+/// Non-blocking AF_UNIX stream socket pair
+pub fn socketpair() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0 as RawFd; 2];
+    let kind = libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC;
+    if unsafe { libc::socketpair(libc::AF_UNIX, kind, 0, fds.as_mut_ptr()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+/// One readiness round over `pairs`: register each first socket for reads,
+/// re-arm it through `modify`, make it readable by writing a byte from the
+/// other end, then poll until every fd has fired and drain it.
+/// Returns the number of readable events seen.
+pub fn poll_cycle(poller: &mut LoopPoller, pairs: &[(OwnedFd, OwnedFd)]) -> VeloxResult<usize> {
+    for (reader, writer) in pairs {
+        poller.register(reader.as_raw_fd(), PollerEvent::readable())?;
+        poller.modify(reader.as_raw_fd(), PollerEvent::readable())?;
+        write_all(writer.as_raw_fd(), b"x")?;
+    }
+
+    let mut seen = 0;
+    while seen < pairs.len() {
+        for event in poller.poll_native(None)? {
+            if event.readable {
+                let mut byte = [0u8; 1];
+                unsafe { libc::read(event.fd, byte.as_mut_ptr() as *mut _, 1) };
+                seen += 1;
+            }
+        }
+    }
+    Ok(seen)
+}
+
+/// Send `payload` from `a` with `submit_send` and read it back on `b` with
+/// `submit_recv`, waiting on each completion. Returns the bytes received.
+pub fn uring_echo(
+    poller: &mut LoopPoller,
+    a: RawFd,
+    b: RawFd,
+    payload: &[u8],
+    buf: &mut [u8],
+) -> VeloxResult<usize> {
+    let mut sent = 0;
+    while sent < payload.len() {
+        let token = poller.submit_send(a, &payload[sent..], 0)?;
+        sent += completion_len(poller.wait_completion(token)?)?;
+    }
+
+    let mut received = 0;
+    while received < payload.len() {
+        let want = buf.len().min(payload.len() - received);
+        let token = poller.submit_recv(b, &mut buf[..want], 0)?;
+        received += completion_len(poller.wait_completion(token)?)?;
+    }
+    Ok(received)
+}
+
+/// Native `StreamReader` driven without an interpreter: feed raw chunks and
+/// parse them the way `readuntil`/`readline` do
+pub struct ReaderDriver {
+    reader: StreamReader,
+}
+
+impl ReaderDriver {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            reader: StreamReader::with_chunk_size(limit, DEFAULT_READ_CHUNK_SIZE),
+        }
+    }
+
+    pub fn feed(&self, data: &[u8]) {
+        self.reader.inner.borrow_mut().feed_data(data);
+    }
+
+    /// Next chunk ending in `separator`, or None until more data arrives
+    pub fn readuntil(&self, separator: &[u8]) -> Option<Vec<u8>> {
+        let found = self.reader.inner.borrow_mut().readuntil(&[separator]);
+        found.ok().flatten().map(|(data, _)| data)
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.reader.inner.borrow().buffer.len()
+    }
+}
+
+fn completion_len(result: i32) -> VeloxResult<usize> {
+    if result < 0 {
+        return Err(io::Error::from_raw_os_error(-result).into());
+    }
+    Ok(result as usize)
+}
+
+fn write_all(fd: RawFd, mut data: &[u8]) -> io::Result<()> {
+    while !data.is_empty() {
+        let n = unsafe { libc::write(fd, data.as_ptr() as *const _, data.len()) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        data = &data[n as usize..];
+    }
+    Ok(())
+}
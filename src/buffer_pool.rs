@@ -1,42 +1,249 @@
 use bytes::BytesMut;
-use std::cell::RefCell;
+use pyo3::prelude::*;
+use std::cell::{Cell, RefCell};
 
-/// Default buffer size for the pool (128 KB)
+use crate::constants::{
+    MAX_READ_CHUNK_SIZE, MIN_READ_CHUNK_SIZE, SHRINK_FACTOR, SHRINK_WINDOW_TICKS,
+};
+
+/// Buffer size the pool's byte caps are counted in (128 KB)
 const BUFFER_SIZE: usize = 128 * 1024;
-/// Maximum number of buffers to keep in the pool per thread
+/// Maximum number of buffers to keep in the pool per thread and size class
 const MAX_POOL_SIZE: usize = 64;
+/// Upper bound on pooled bytes per size class, so large buckets keep fewer buffers
+const MAX_BUCKET_BYTES: usize = MAX_POOL_SIZE * BUFFER_SIZE;
+/// Upper bound on pooled bytes per thread across all size classes
+const MAX_POOL_BYTES: usize = 2 * MAX_BUCKET_BYTES;
+
+const MIN_SHIFT: u32 = MIN_READ_CHUNK_SIZE.trailing_zeros();
+const MAX_SHIFT: u32 = MAX_READ_CHUNK_SIZE.trailing_zeros();
+/// One bucket per power of two between the smallest and largest read chunk
+const BUCKETS: usize = (MAX_SHIFT - MIN_SHIFT + 1) as usize;
 
 thread_local! {
-    static POOL: RefCell<Vec<BytesMut>> = RefCell::new(Vec::with_capacity(MAX_POOL_SIZE));
+    static POOL: RefCell<[Vec<BytesMut>; BUCKETS]> =
+        RefCell::new(std::array::from_fn(|_| Vec::new()));
+    static POOL_BYTES: Cell<usize> = const { Cell::new(0) };
 }
 
 /// A simple thread-local buffer pool for managing BytesMut buffers.
+/// Buffers are bucketed by power-of-two capacity, so transports configured
+/// with different read chunk sizes don't trade buffers of the wrong size.
 pub struct BufferPool;
 
 impl BufferPool {
-    /// Acquire a buffer from the pool or create a new one.
-    pub fn acquire() -> BytesMut {
+    /// Acquire a buffer with at least `size` bytes of capacity.
+    pub fn acquire_sized(size: usize) -> BytesMut {
+        let cap = size.max(MIN_READ_CHUNK_SIZE).next_power_of_two();
+        if cap > MAX_READ_CHUNK_SIZE {
+            return BytesMut::with_capacity(size);
+        }
+        let bucket = (cap.trailing_zeros() - MIN_SHIFT) as usize;
         POOL.with(|p| {
-            let mut pool = p.borrow_mut();
-            if let Some(mut buf) = pool.pop() {
+            if let Some(mut buf) = p.borrow_mut()[bucket].pop() {
+                POOL_BYTES.with(|b| b.set(b.get() - buf.capacity()));
                 buf.clear();
                 buf
             } else {
-                BytesMut::with_capacity(BUFFER_SIZE)
+                BytesMut::with_capacity(cap)
             }
         })
     }
 
     /// Release a buffer back to the pool.
     pub fn release(buf: BytesMut) {
-        // Only pool buffers that have enough capacity but aren't excessively large
-        if buf.capacity() >= BUFFER_SIZE && buf.capacity() <= BUFFER_SIZE * 2 {
+        // Bucket by the largest power of two the capacity covers; anything
+        // outside the configurable chunk range isn't worth keeping
+        let cap = buf.capacity();
+        if !(MIN_READ_CHUNK_SIZE..MAX_READ_CHUNK_SIZE * 2).contains(&cap) {
+            return;
+        }
+        let shift = usize::BITS - 1 - cap.leading_zeros();
+        let bucket = (shift - MIN_SHIFT) as usize;
+        let max_buffers = (MAX_BUCKET_BYTES >> shift).clamp(1, MAX_POOL_SIZE);
+        POOL_BYTES.with(|bytes| {
+            if bytes.get() + cap > MAX_POOL_BYTES {
+                return;
+            }
             POOL.with(|p| {
                 let mut pool = p.borrow_mut();
-                if pool.len() < MAX_POOL_SIZE {
-                    pool.push(buf);
+                if pool[bucket].len() < max_buffers {
+                    bytes.set(bytes.get() + cap);
+                    pool[bucket].push(buf);
                 }
             });
+        });
+    }
+
+    /// Allocate buffers of `size` into this thread's pool until it holds
+    /// `count` of them, or as many as the pool's limits allow
+    pub fn prefill(size: usize, count: usize) {
+        let cap = size.max(MIN_READ_CHUNK_SIZE).next_power_of_two();
+        if cap > MAX_READ_CHUNK_SIZE {
+            return;
+        }
+        let bucket = (cap.trailing_zeros() - MIN_SHIFT) as usize;
+        let held = POOL.with(|p| p.borrow()[bucket].len());
+        for _ in held..count {
+            let before = Self::retained_bytes();
+            Self::release(BytesMut::with_capacity(cap));
+            if Self::retained_bytes() == before {
+                break;
+            }
+        }
+    }
+
+    /// Bytes held by this thread's pool
+    pub fn retained_bytes() -> usize {
+        POOL_BYTES.with(Cell::get)
+    }
+}
+
+/// Rolling high-water mark for a Vec that is drained and reused every loop
+/// iteration. Once per window, a Vec holding more than `SHRINK_FACTOR` times
+/// the window's peak is shrunk back, so one spike doesn't pin its memory.
+pub(crate) struct BufferTrim {
+    floor: usize,
+    high_water: usize,
+    ticks: usize,
+    capacity: usize,
+}
+
+impl BufferTrim {
+    /// `floor` is the capacity the buffer starts with and is never shrunk below
+    pub(crate) const fn new(floor: usize) -> Self {
+        Self {
+            floor,
+            high_water: 0,
+            ticks: 0,
+            capacity: floor,
+        }
+    }
+
+    /// Record how full the buffer got this iteration
+    pub(crate) fn note<T>(&mut self, buf: &[T]) {
+        self.high_water = self.high_water.max(buf.len());
+    }
+
+    /// Capacity of the buffer as of its last tick. The buffer itself may be
+    /// taken out of its cell while callbacks run, so this is what stats report.
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Count an iteration; at the end of a window shrink `buf` if it's oversized
+    pub(crate) fn tick<T>(&mut self, buf: &mut Vec<T>) {
+        self.ticks += 1;
+        if self.ticks >= SHRINK_WINDOW_TICKS {
+            let keep = self.floor.max(self.high_water * 2);
+            if buf.capacity() > keep && buf.capacity() > SHRINK_FACTOR * self.high_water {
+                buf.shrink_to(keep);
+            }
+            self.ticks = 0;
+            self.high_water = 0;
+        }
+        self.capacity = buf.capacity();
+    }
+}
+
+/// Validate a read chunk size: a power of two between 1 KB and 4 MB
+pub fn check_read_chunk_size(size: usize) -> PyResult<usize> {
+    if !size.is_power_of_two() || !(MIN_READ_CHUNK_SIZE..=MAX_READ_CHUNK_SIZE).contains(&size) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "read chunk size must be a power of two between {} and {}, got {}",
+            MIN_READ_CHUNK_SIZE, MAX_READ_CHUNK_SIZE, size
+        )));
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn released_buffer_is_reused_cleared() {
+        let mut buf = BufferPool::acquire_sized(3000);
+        assert_eq!(buf.capacity(), 4096);
+        buf.extend_from_slice(b"stale");
+        let ptr = buf.as_ptr();
+        BufferPool::release(buf);
+
+        let again = BufferPool::acquire_sized(4096);
+        assert_eq!(again.as_ptr(), ptr);
+        assert!(again.is_empty());
+    }
+
+    #[test]
+    fn size_classes_are_kept_apart() {
+        let small = BufferPool::acquire_sized(MIN_READ_CHUNK_SIZE);
+        let ptr = small.as_ptr();
+        BufferPool::release(small);
+        let large = BufferPool::acquire_sized(64 * 1024);
+        assert_ne!(large.as_ptr(), ptr);
+        assert!(large.capacity() >= 64 * 1024);
+    }
+
+    #[test]
+    fn oversized_buffers_are_not_pooled() {
+        let huge = BufferPool::acquire_sized(MAX_READ_CHUNK_SIZE * 4);
+        let ptr = huge.as_ptr();
+        BufferPool::release(huge);
+        assert_ne!(BufferPool::acquire_sized(MAX_READ_CHUNK_SIZE).as_ptr(), ptr);
+    }
+
+    #[test]
+    fn pool_bytes_are_bounded() {
+        let bufs: Vec<_> = (0..MAX_POOL_BYTES / MAX_READ_CHUNK_SIZE + 4)
+            .map(|_| BufferPool::acquire_sized(MAX_READ_CHUNK_SIZE))
+            .collect();
+        for buf in bufs {
+            BufferPool::release(buf);
+        }
+        assert!(BufferPool::retained_bytes() <= MAX_POOL_BYTES);
+        let held = BufferPool::retained_bytes();
+        let buf = BufferPool::acquire_sized(MAX_READ_CHUNK_SIZE);
+        assert_eq!(BufferPool::retained_bytes(), held - buf.capacity());
+    }
+
+    #[test]
+    fn prefill_tops_up_within_limits() {
+        BufferPool::prefill(8192, 3);
+        assert_eq!(BufferPool::retained_bytes(), 3 * 8192);
+        BufferPool::prefill(8000, 3);
+        assert_eq!(BufferPool::retained_bytes(), 3 * 8192);
+        BufferPool::prefill(MAX_READ_CHUNK_SIZE, 1000);
+        assert!(BufferPool::retained_bytes() <= MAX_POOL_BYTES);
+    }
+
+    #[test]
+    fn trim_shrinks_after_spike_window() {
+        let mut trim = BufferTrim::new(16);
+        let mut buf: Vec<u64> = Vec::with_capacity(16);
+        buf.resize(100_000, 0);
+        trim.note(&buf);
+        buf.clear();
+        // The spike's own window keeps it
+        for _ in 0..SHRINK_WINDOW_TICKS {
+            trim.tick(&mut buf);
         }
+        assert!(buf.capacity() >= 100_000);
+        // A quiet window gives it back
+        for _ in 0..SHRINK_WINDOW_TICKS {
+            trim.note(&[0u64; 3]);
+            trim.tick(&mut buf);
+        }
+        assert_eq!(trim.capacity(), buf.capacity());
+        assert!(buf.capacity() < 64);
+        assert!(buf.capacity() >= 16);
+    }
+
+    #[test]
+    fn read_chunk_size_bounds() {
+        assert_eq!(check_read_chunk_size(MIN_READ_CHUNK_SIZE).ok(), Some(MIN_READ_CHUNK_SIZE));
+        assert_eq!(check_read_chunk_size(MAX_READ_CHUNK_SIZE).ok(), Some(MAX_READ_CHUNK_SIZE));
+        assert!(check_read_chunk_size(1000).is_err());
+        assert!(check_read_chunk_size(512).is_err());
+        assert!(check_read_chunk_size(MAX_READ_CHUNK_SIZE * 2).is_err());
     }
 }
@@ -1,16 +1,20 @@
+use parking_lot::Mutex;
+use pyo3::buffer::PyBuffer;
 use pyo3::prelude::*;
 use pyo3::types::PyTuple;
-use std::os::fd::{AsRawFd, RawFd};
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::os::fd::{AsRawFd, IntoRawFd, RawFd};
+use std::sync::{Arc, Weak};
 
 use crate::concurrent::ConcurrentCallbackQueue;
-use crate::constants::{STACK_BUF_SIZE, get_socket};
+use crate::constants::{SENDALL_BUDGET, STACK_BUF_SIZE, get_socket};
 use crate::event_loop::VeloxLoop;
 use crate::ffi_utils;
+use crate::poller::PollerWaker;
 
 use crate::transports::future::PendingFuture;
 use crate::transports::ssl::SSLContext;
-use crate::transports::{DefaultTransportFactory, TransportFactory};
+use crate::transports::TransportFactory;
 
 pub struct Callback {
     pub callback: Py<PyAny>,
@@ -18,36 +22,64 @@ pub struct Callback {
 
     #[allow(dead_code)] // For future use
     pub context: Option<Py<PyAny>>,
+
+    /// Enqueue sequence number, stamped by `CallbackQueue::push`
+    pub seq: u64,
+}
+
+impl Callback {
+    #[inline]
+    pub fn new(callback: Py<PyAny>, args: Vec<Py<PyAny>>, context: Option<Py<PyAny>>) -> Self {
+        Self {
+            callback,
+            args,
+            context,
+            seq: 0,
+        }
+    }
 }
 
-/// High-performance lock-free callback queue using crossbeam channels.
+/// High-performance callback queue using crossbeam channels.
 ///
-/// MPMC queue for better scalability in multi-threaded scenarios.
-/// Uses crossbeam-channel internally for efficient lock-free operations.
+/// MPMC queue shared by `call_soon` and `call_soon_threadsafe`.
+/// Every pushed callback gets a monotonically increasing sequence number,
+/// and callbacks are dispatched strictly in that order (FIFO, as asyncio
+/// documents), no matter which thread scheduled them.
 pub struct CallbackQueue {
-    /// Lock-free concurrent queue using crossbeam channels
+    /// Concurrent queue using crossbeam channels
     pub inner: ConcurrentCallbackQueue<Callback>,
+    /// Next sequence number. Stamping and sending happen under this lock so
+    /// that channel order always equals sequence order; the lock is
+    /// uncontended unless several threads push at the same instant.
+    next_seq: Mutex<u64>,
 }
 
 impl CallbackQueue {
     pub fn new() -> Self {
         Self {
             inner: ConcurrentCallbackQueue::new(),
+            next_seq: Mutex::new(0),
         }
     }
 
-    /// Push a callback to the queue (lock-free, thread-safe)
+    /// Stamp the callback with the next sequence number and push it (thread-safe)
     #[inline]
-    pub fn push(&self, callback: Callback) {
+    pub fn push(&self, mut callback: Callback) {
+        let mut next_seq = self.next_seq.lock();
+        callback.seq = *next_seq;
+        *next_seq += 1;
         self.inner.push(callback);
     }
 
-    /// Drain all callbacks into a target vector (lock-free)
-    /// This is more efficient than swap for concurrent access
+    /// Drain all callbacks into a target vector, in sequence order.
+    ///
+    /// This is the single merge point per loop iteration: everything pushed
+    /// before the drain (from any thread) runs in this batch, everything
+    /// pushed while the batch is being dispatched runs in the next one.
     #[inline]
-    #[allow(dead_code)]
     pub fn swap_into(&self, target: &mut Vec<Callback>) {
         self.inner.drain_into(target);
+        debug_assert!(target.windows(2).all(|w| w[0].seq < w[1].seq));
     }
 
     /// Check if the queue is empty (approximate, lock-free)
@@ -55,6 +87,46 @@ impl CallbackQueue {
     pub fn is_empty(&self) -> bool {
         self.inner.is_empty()
     }
+
+    /// Number of queued callbacks (approximate, lock-free)
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// `call_soon_threadsafe` for threads that must not keep the loop alive.
+/// Holds the loop's queue and waker weakly; once the loop is dropped,
+/// `call_soon` refuses and the caller discards the callback.
+#[derive(Clone)]
+pub struct ThreadsafeHandle {
+    callbacks: Weak<CallbackQueue>,
+    waker: Weak<PollerWaker>,
+}
+
+impl ThreadsafeHandle {
+    pub fn new(callbacks: &Arc<CallbackQueue>, waker: &Arc<PollerWaker>) -> Self {
+        Self {
+            callbacks: Arc::downgrade(callbacks),
+            waker: Arc::downgrade(waker),
+        }
+    }
+
+    /// Whether the loop that handed out this handle still exists
+    pub fn is_alive(&self) -> bool {
+        self.callbacks.strong_count() > 0
+    }
+
+    /// Queue `callback` and wake the loop; false if the loop is gone
+    pub fn call_soon(&self, callback: Callback) -> bool {
+        let (Some(callbacks), Some(waker)) = (self.callbacks.upgrade(), self.waker.upgrade())
+        else {
+            return false;
+        };
+        callbacks.push(callback);
+        let _ = waker.notify();
+        true
+    }
 }
 
 /// Callback for async TCP connection establishment
@@ -67,6 +139,28 @@ pub struct AsyncConnectCallback {
     fd: RawFd,
     ssl_context: Option<Py<SSLContext>>,
     server_hostname: Option<String>,
+    /// Peer being connected to, named in the OSError if the connect fails
+    addr: Option<SocketAddr>,
+    /// Connect-phase timer armed by `create_connection(timeout=...)`
+    timer: Option<u64>,
+    ssl_handshake_timeout: Option<f64>,
+    /// Set by `open_connection()`: settle with a `(reader, writer)` pair of
+    /// this buffer limit instead of calling `protocol_factory`
+    stream_limit: Option<usize>,
+}
+
+/// OSError for a failed connect, carrying the errno and the peer address
+/// the way asyncio words it
+pub fn connect_error(py: Python<'_>, e: &std::io::Error, addr: Option<SocketAddr>) -> PyErr {
+    let shown = addr
+        .and_then(|addr| crate::utils::ipv6::socket_addr_to_tuple(py, addr).ok())
+        .and_then(|tuple| tuple.bind(py).repr().ok().map(|r| r.to_string()));
+    match shown {
+        Some(shown) => {
+            crate::utils::os_error_with_message(e, format!("Connect call failed {}: {}", shown, e))
+        }
+        None => crate::utils::os_error_with_message(e, e.to_string()),
+    }
 }
 
 #[pymethods]
@@ -77,6 +171,9 @@ impl AsyncConnectCallback {
         // Unregister writer (ourselves) using VeloxLoop directly
         let loop_ref = self.loop_.bind(py);
         loop_ref.borrow().remove_writer(py, fd)?;
+        if let Some(timer) = self.timer.take() {
+            loop_ref.borrow()._cancel_timer(timer)?;
+        }
 
         // Take stream
         if let Some(stream) = self.stream.take() {
@@ -84,15 +181,21 @@ impl AsyncConnectCallback {
             let res = stream.take_error();
             match res {
                 Ok(None) => {
+                    // open_connection() wants streams, not a protocol
+                    if let Some(limit) = self.stream_limit {
+                        return self.settle_streams(py, stream, limit);
+                    }
                     // Connected! Create protocol
                     let protocol_res = self.protocol_factory.call0(py);
                     match protocol_res {
                         Ok(protocol) => {
-                            // Use the transport factory to create transports
-                            let factory = DefaultTransportFactory;
+                            // Use the loop's transport factory to create transports
+                            let factory = self.loop_.bind(py).borrow().transport_factory(py);
                             let loop_py = self.loop_.clone_ref(py).into_any();
 
-                            let transport_result: PyResult<(Py<PyAny>, Py<PyAny>)> =
+                            // None: an SSL transport settles the future itself once
+                            // its handshake finishes
+                            let transport_result: PyResult<Option<(Py<PyAny>, Py<PyAny>)>> =
                                 if let Some(ssl_ctx) = &self.ssl_context {
                                     // Create SSL transport using factory
                                     let transport_py = factory.create_ssl(
@@ -145,7 +248,14 @@ impl AsyncConnectCallback {
                                         .borrow()
                                         .add_writer_native(fd, write_callback)?;
 
-                                    Ok((transport_py, protocol.clone_ref(py)))
+                                    crate::transports::ssl::SSLTransport::wait_for_handshake(
+                                        transport_py
+                                            .bind(py)
+                                            .cast::<crate::transports::ssl::SSLTransport>()?,
+                                        self.future.clone_ref(py),
+                                        self.ssl_handshake_timeout,
+                                    )?;
+                                    Ok(None)
                                 } else {
                                     // Create regular TCP transport using factory
                                     let transport_py = factory.create_tcp(
@@ -178,26 +288,21 @@ impl AsyncConnectCallback {
                                         (transport_py.clone_ref(py),),
                                     )?;
 
-                                    // Add reader (native path)
-                                    let transport_clone = transport_py.clone_ref(py);
-                                    let read_callback = Arc::new(move |py: Python<'_>| {
-                                        let b = transport_clone.bind(py);
-                                        let tcp = b
-                                            .cast::<crate::transports::tcp::TcpTransport>()
-                                            .map_err(|_| {
-                                                PyErr::new::<pyo3::exceptions::PyTypeError, _>(
-                                                    "Expected TcpTransport",
-                                                )
-                                            })?;
-                                        crate::transports::tcp::TcpTransport::_read_ready(tcp)
-                                    });
-                                    loop_ref.borrow().add_reader_native(fd, read_callback)?;
+                                    // Add reader (native path unless the factory returned
+                                    // its own transport)
+                                    crate::transports::factory::start_reading(
+                                        py,
+                                        &self.loop_,
+                                        transport_py.bind(py),
+                                        fd,
+                                    )?;
 
-                                    Ok((transport_py, protocol.clone_ref(py)))
+                                    Ok(Some((transport_py, protocol.clone_ref(py))))
                                 };
 
                             match transport_result {
-                                Ok((transport_py, protocol)) => {
+                                Ok(None) => {}
+                                Ok(Some((transport_py, protocol))) => {
                                     // Set result: (transport, protocol)
                                     let res =
                                         PyTuple::new(py, &[transport_py, protocol])?.into_any();
@@ -217,7 +322,7 @@ impl AsyncConnectCallback {
                 }
                 Ok(Some(e)) | Err(e) => {
                     // Error connecting
-                    let py_err = PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string());
+                    let py_err = connect_error(py, &e, self.addr);
                     let exc_val = py_err.value(py).as_any().clone().unbind();
                     self.future.bind(py).borrow().set_exception(py, exc_val)?;
                 }
@@ -243,6 +348,10 @@ impl AsyncConnectCallback {
             fd,
             ssl_context: None,
             server_hostname: None,
+            addr: None,
+            timer: None,
+            ssl_handshake_timeout: None,
+            stream_limit: None,
         }
     }
 
@@ -253,6 +362,7 @@ impl AsyncConnectCallback {
         stream: std::net::TcpStream,
         ssl_context: Option<Py<SSLContext>>,
         server_hostname: Option<String>,
+        addr: Option<SocketAddr>,
     ) -> Self {
         let fd = stream.as_raw_fd();
         Self {
@@ -263,7 +373,93 @@ impl AsyncConnectCallback {
             fd,
             ssl_context,
             server_hostname,
+            addr,
+            timer: None,
+            ssl_handshake_timeout: None,
+            stream_limit: None,
+        }
+    }
+
+    /// Fail the TLS phase if the handshake takes longer than `timeout` seconds
+    pub fn with_ssl_handshake_timeout(mut self, timeout: Option<f64>) -> Self {
+        self.ssl_handshake_timeout = timeout;
+        self
+    }
+
+    /// Settle with `open_connection()`'s stream pair rather than a transport
+    /// and protocol
+    pub fn for_streams(mut self, limit: usize) -> Self {
+        self.stream_limit = Some(limit);
+        self
+    }
+
+    fn settle_streams(
+        &self,
+        py: Python<'_>,
+        stream: std::net::TcpStream,
+        limit: usize,
+    ) -> PyResult<()> {
+        let streams = crate::transports::stream_server::StreamTransport::open_client(
+            py,
+            &self.loop_,
+            stream,
+            limit,
+        );
+        let future = self.future.bind(py).borrow();
+        match streams {
+            Ok(streams) => future.set_result(py, streams.into_any()),
+            Err(e) => future.set_exception(py, e.into_value(py).into_any()),
+        }
+    }
+
+    /// Give up on the connect after `timeout` seconds (see `expire`)
+    pub(crate) fn arm_timeout(slf: &Bound<'_, Self>, timeout: f64) -> PyResult<()> {
+        let py = slf.py();
+        let expiry = Py::new(
+            py,
+            ConnectTimeout {
+                callback: slf.clone().unbind(),
+            },
+        )?;
+        let timer = slf.borrow().loop_.bind(py).borrow().call_later(
+            timeout,
+            expiry.into_any(),
+            Vec::new(),
+            None,
+        )?;
+        slf.borrow_mut().timer = Some(timer);
+        Ok(())
+    }
+
+    /// The connect timer fired first: stop watching the socket, close it and
+    /// fail the future with ETIMEDOUT, as a kernel-side timeout would
+    fn expire(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.timer = None;
+        let Some(stream) = self.stream.take() else {
+            return Ok(());
+        };
+        self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
+        drop(stream);
+        let err = std::io::Error::from_raw_os_error(libc::ETIMEDOUT);
+        let exc = connect_error(py, &err, self.addr).into_value(py).into_any();
+        let future = self.future.bind(py).borrow();
+        if !future.done() {
+            future.set_exception(py, exc)?;
         }
+        Ok(())
+    }
+}
+
+/// Timer callback for `create_connection(timeout=...)`
+#[pyclass(module = "veloxloop._veloxloop")]
+struct ConnectTimeout {
+    callback: Py<AsyncConnectCallback>,
+}
+
+#[pymethods]
+impl ConnectTimeout {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        self.callback.bind(py).borrow_mut().expire(py)
     }
 }
 
@@ -279,85 +475,54 @@ pub struct SockAcceptCallback {
 impl SockAcceptCallback {
     fn __call__(&self, py: Python<'_>) -> PyResult<()> {
         // Try to accept when readable
-        unsafe {
-            let mut addr: libc::sockaddr_storage = std::mem::zeroed();
-            let mut addr_len: libc::socklen_t =
-                std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
-
-            let client_fd = libc::accept(
-                self.fd,
-                &mut addr as *mut _ as *mut libc::sockaddr,
-                &mut addr_len,
-            );
-
-            if client_fd >= 0 {
-                // Set non-blocking on client socket
-                let flags = libc::fcntl(client_fd, libc::F_GETFL, 0);
-                if flags >= 0 {
-                    libc::fcntl(client_fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
-                }
-
-                // Create Python socket object using socket.fromfd()
+        match crate::socket::accept_nonblocking(self.fd) {
+            Ok((client_fd, addr, addr_len)) => {
+                // Adopt the fd with the listener's real family (AF_INET6 / AF_UNIX too)
                 let socket_module = get_socket(py).bind(py);
-                let py_socket = socket_module.call_method1("fromfd", (client_fd, 2, 1))?; // AF_INET=2, SOCK_STREAM=1
-
-                // Parse address using C API for tuple creation
-                let addr_tuple_ptr = if addr_len as usize >= std::mem::size_of::<libc::sockaddr_in>() {
-                    let addr_in = &*((&addr) as *const _ as *const libc::sockaddr_in);
-                    let is_ipv4 = addr_in.sin_family == libc::AF_INET as u16;
-                    if is_ipv4 {
-                        let ip = u32::from_be(addr_in.sin_addr.s_addr);
-                        let port = u16::from_be(addr_in.sin_port);
-                        let ip_str = format!(
-                            "{}.{}.{}.{}",
-                            (ip >> 24) & 0xff,
-                            (ip >> 16) & 0xff,
-                            (ip >> 8) & 0xff,
-                            ip & 0xff
-                        );
-                        ffi_utils::tuple2(
-                            ffi_utils::string_from_str(&ip_str),
-                            ffi_utils::long_from_u16(port),
-                        )
-                    } else {
-                        ffi_utils::tuple2(
-                            ffi_utils::string_from_str(""),
-                            ffi_utils::long_from_i32(0),
-                        )
-                    }
-                } else {
-                    ffi_utils::tuple2(
-                        ffi_utils::string_from_str(""),
-                        ffi_utils::long_from_i32(0),
-                    )
-                };
+                let py_socket = socket_module.call_method1(
+                    "socket",
+                    (
+                        addr.ss_family as i32,
+                        libc::SOCK_STREAM,
+                        0,
+                        client_fd.as_raw_fd(),
+                    ),
+                )?;
+                // Owned by the Python socket now
+                let _ = client_fd.into_raw_fd();
+
+                let addr_tuple_ptr =
+                    crate::utils::ipv6::parse_sockaddr_storage(py, &addr, addr_len)?.into_ptr();
 
                 // Return tuple (socket, address) using C API
-                let result_ptr = ffi_utils::tuple2(
-                    {
-                        // Borrow the py_socket ptr with an INCREF since tuple steals ref
-                        pyo3::ffi::Py_INCREF(py_socket.as_ptr());
-                        py_socket.as_ptr()
-                    },
-                    addr_tuple_ptr,
-                );
-                let result: Py<PyAny> = pyo3::Bound::from_owned_ptr(py, result_ptr).unbind();
+                let result: Py<PyAny> = unsafe {
+                    let result_ptr = ffi_utils::tuple2(
+                        {
+                            // Borrow the py_socket ptr with an INCREF since tuple steals ref
+                            pyo3::ffi::Py_INCREF(py_socket.as_ptr());
+                            py_socket.as_ptr()
+                        },
+                        addr_tuple_ptr,
+                    );
+                    pyo3::Bound::from_owned_ptr(py, result_ptr).unbind()
+                };
 
                 self.future
                     .bind(py)
                     .borrow()
                     .set_result(py, result)?;
                 self.loop_.bind(py).borrow().remove_reader(py, self.fd)?;
-            } else {
-                let err = std::io::Error::last_os_error();
-                if err.kind() != std::io::ErrorKind::WouldBlock
-                    && err.raw_os_error() != Some(libc::EAGAIN)
-                {
-                    let py_err = PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string());
-                    let exc_val = py_err.value(py).as_any().clone().unbind();
-                    self.future.bind(py).borrow().set_exception(py, exc_val)?;
-                    self.loop_.bind(py).borrow().remove_reader(py, self.fd)?;
-                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(err) => {
+                let py_err = if crate::event_loop::is_fd_exhaustion(&err) {
+                    self.loop_.bind(py).borrow().fd_error("sock_accept()", err)
+                } else {
+                    crate::utils::os_error_to_pyerr(err)
+                };
+                let exc_val = py_err.value(py).as_any().clone().unbind();
+                self.future.bind(py).borrow().set_exception(py, exc_val)?;
+                self.loop_.bind(py).borrow().remove_reader(py, self.fd)?;
             }
         }
         Ok(())
@@ -406,7 +571,7 @@ impl SockRecvCallback {
                     if err.kind() != std::io::ErrorKind::WouldBlock
                         && err.raw_os_error() != Some(libc::EAGAIN)
                     {
-                        let py_err = PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string());
+                        let py_err = crate::utils::os_error_to_pyerr(err);
                         let exc_val = py_err.value(py).as_any().clone().unbind();
                         self.future.bind(py).borrow().set_exception(py, exc_val)?;
                         self.loop_.bind(py).borrow().remove_reader(py, self.fd)?;
@@ -434,7 +599,7 @@ impl SockRecvCallback {
                     if err.kind() != std::io::ErrorKind::WouldBlock
                         && err.raw_os_error() != Some(libc::EAGAIN)
                     {
-                        let py_err = PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string());
+                        let py_err = crate::utils::os_error_to_pyerr(err);
                         let exc_val = py_err.value(py).as_any().clone().unbind();
                         self.future.bind(py).borrow().set_exception(py, exc_val)?;
                         self.loop_.bind(py).borrow().remove_reader(py, self.fd)?;
@@ -457,90 +622,378 @@ impl SockRecvCallback {
     }
 }
 
-/// Callback for sock_sendall
-#[pyclass(module = "veloxloop._veloxloop")]
+/// Send as much of `data` as the socket takes, up to `budget` bytes.
+/// Returns the number of bytes sent; stops early on EWOULDBLOCK.
+pub(crate) fn send_some(fd: RawFd, data: &[u8], budget: usize) -> std::io::Result<usize> {
+    let end = data.len().min(budget);
+    let mut sent = 0;
+    while sent < end {
+        let n = unsafe {
+            libc::send(
+                fd,
+                data[sent..end].as_ptr() as *const libc::c_void,
+                end - sent,
+                0,
+            )
+        };
+        if n >= 0 {
+            sent += n as usize;
+            continue;
+        }
+        let err = std::io::Error::last_os_error();
+        match err.kind() {
+            std::io::ErrorKind::WouldBlock => break,
+            std::io::ErrorKind::Interrupted => continue,
+            _ => return Err(err),
+        }
+    }
+    Ok(sent)
+}
+
+/// Borrow the bytes behind a contiguous buffer
+pub(crate) fn buffer_bytes(buf: &PyBuffer<u8>) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(buf.buf_ptr() as *const u8, buf.len_bytes()) }
+}
+
+/// Done callback of a sock_recv()/sock_sendall() future that had to wait: a
+/// cancelled operation's handle goes and its poll is parked for a retry, see
+/// `VeloxLoop::park_sock_op`
+#[cfg(target_os = "linux")]
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+pub struct SockOpParker {
+    loop_: Py<VeloxLoop>,
+    sock: Py<PyAny>,
+    fd: RawFd,
+    reader: bool,
+    generation: u64,
+}
+
+#[cfg(target_os = "linux")]
+#[pymethods]
+impl SockOpParker {
+    fn __call__(&self, py: Python<'_>, _fut: Py<PyAny>) -> PyResult<()> {
+        self.loop_.bind(py).borrow().park_sock_op(
+            py,
+            self.fd,
+            self.reader,
+            self.generation,
+            self.sock.clone_ref(py),
+        )
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl SockOpParker {
+    pub fn new(
+        loop_: Py<VeloxLoop>,
+        sock: Py<PyAny>,
+        fd: RawFd,
+        reader: bool,
+        generation: u64,
+    ) -> Self {
+        Self {
+            loop_,
+            sock,
+            fd,
+            reader,
+            generation,
+        }
+    }
+}
+
+/// Writer callback finishing a sock_sendall that didn't complete synchronously.
+/// Holds the caller's buffer (no copy) plus an offset into it, and is also the
+/// future's done callback so cancelling the future drops the buffer (and the
+/// writer, which on Linux a `SockOpParker` parks instead).
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
 pub struct SockSendallCallback {
     future: Py<PendingFuture>,
     loop_: Py<VeloxLoop>,
     fd: RawFd,
-    data: Vec<u8>,
-    sent: usize,
+    /// (data, bytes already sent); None once finished, failed or cancelled
+    data: Mutex<Option<(PyBuffer<u8>, usize)>>,
 }
 
 #[pymethods]
 impl SockSendallCallback {
-    fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
-        while self.sent < self.data.len() {
-            unsafe {
-                let n = libc::send(
-                    self.fd,
-                    self.data[self.sent..].as_ptr() as *const libc::c_void,
-                    self.data.len() - self.sent,
-                    0,
-                );
+    /// Done callback: a no-op if the send finished, a cleanup if it was cancelled
+    fn __call__(&self, py: Python<'_>, _fut: Py<PyAny>) -> PyResult<()> {
+        let cancelled = self.data.lock().take().is_some();
+        #[cfg(not(target_os = "linux"))]
+        if cancelled {
+            self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
+        }
+        #[cfg(target_os = "linux")]
+        let _ = (cancelled, py);
+        Ok(())
+    }
+}
 
-                if n > 0 {
-                    self.sent += n as usize;
-                } else {
-                    let err = std::io::Error::last_os_error();
-                    match err.kind() {
-                        std::io::ErrorKind::WouldBlock => return Ok(()),
-                        _ if err.raw_os_error() == Some(libc::EAGAIN) => return Ok(()),
-                        _ => {
-                            let py_err =
-                                PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string());
-                            let exc_val = py_err.value(py).as_any().clone().unbind();
-                            self.future.bind(py).borrow().set_exception(py, exc_val)?;
-                            self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
-                            return Ok(());
-                        }
-                    }
-                }
+impl SockSendallCallback {
+    pub fn new(
+        loop_: Py<VeloxLoop>,
+        future: Py<PendingFuture>,
+        fd: RawFd,
+        data: PyBuffer<u8>,
+        sent: usize,
+    ) -> Self {
+        Self {
+            future,
+            loop_,
+            fd,
+            data: Mutex::new(Some((data, sent))),
+        }
+    }
+
+    /// Socket is writable: send the next `SENDALL_BUDGET` bytes at most, so one
+    /// large payload can't keep the loop from running anything else
+    pub fn on_writable(&self, py: Python<'_>) -> PyResult<()> {
+        let mut guard = self.data.lock();
+        let Some((buf, sent)) = guard.as_mut() else {
+            return Ok(());
+        };
+        let data = buffer_bytes(buf);
+        let result = send_some(self.fd, &data[*sent..], SENDALL_BUDGET);
+        let finished = match result {
+            Ok(n) => {
+                *sent += n;
+                *sent == data.len()
             }
+            Err(_) => true,
+        };
+        if !finished {
+            return Ok(());
         }
+        // Release the caller's buffer before resolving the future
+        guard.take();
+        drop(guard);
 
-        // All sent
-        self.future.bind(py).borrow().set_result(py, py.None())?;
         self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
+        let future = self.future.bind(py).borrow();
+        match result {
+            Ok(_) => future.set_result(py, py.None()),
+            Err(e) => future.set_exception(
+                py,
+                crate::utils::os_error_to_pyerr(e).into_value(py).into_any(),
+            ),
+        }
+    }
+}
+
+/// `sendfile()` from `in_fd` at `offset` into the socket, up to `budget` bytes.
+/// Returns (bytes sent, whether the file ended); stops early on EWOULDBLOCK.
+/// Always offset-based, so the file's own position is neither used nor moved
+pub(crate) fn sendfile_some(
+    out_fd: RawFd,
+    in_fd: RawFd,
+    offset: i64,
+    budget: usize,
+) -> std::io::Result<(usize, bool)> {
+    let mut sent = 0;
+    while sent < budget {
+        let mut off = (offset + sent as i64) as libc::off_t;
+        let n = unsafe { libc::sendfile(out_fd, in_fd, &mut off, budget - sent) };
+        if n > 0 {
+            sent += n as usize;
+            continue;
+        }
+        if n == 0 {
+            return Ok((sent, true));
+        }
+        let err = std::io::Error::last_os_error();
+        match err.kind() {
+            std::io::ErrorKind::WouldBlock => break,
+            std::io::ErrorKind::Interrupted => continue,
+            _ => return Err(err),
+        }
+    }
+    Ok((sent, false))
+}
+
+/// The error a failed sock_sendfile reports, worded like asyncio's: a failure
+/// before anything was sent means sendfile isn't usable, so the caller can
+/// fall back to plain sends
+pub(crate) fn sendfile_error(py: Python<'_>, err: std::io::Error, total_sent: usize) -> PyErr {
+    if err.raw_os_error() == Some(libc::ENOTCONN) {
+        return PyErr::new::<pyo3::exceptions::PyConnectionError, _>((
+            libc::ENOTCONN,
+            "socket is not connected",
+        ));
+    }
+    if total_sent == 0 {
+        return sendfile_not_available(py, "os.sendfile call failed");
+    }
+    crate::utils::os_error_to_pyerr(err)
+}
+
+/// asyncio.SendfileNotAvailableError(`message`)
+pub(crate) fn sendfile_not_available(py: Python<'_>, message: &str) -> PyErr {
+    match crate::constants::get_asyncio(py)
+        .bind(py)
+        .getattr("SendfileNotAvailableError")
+        .and_then(|cls| cls.call1((message,)))
+    {
+        Ok(exc) => PyErr::from_value(exc),
+        Err(e) => e,
+    }
+}
+
+/// Progress of a sock_sendfile: next file offset, bytes left, bytes sent
+struct SendfileProgress {
+    offset: i64,
+    remaining: usize,
+    sent: usize,
+}
+
+/// Writer callback finishing a sock_sendfile that didn't complete
+/// synchronously. Like `SockSendallCallback` it is also the future's done
+/// callback, so cancelling the future stops sending and drops the writer.
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+pub struct SockSendfileCallback {
+    future: Py<PendingFuture>,
+    loop_: Py<VeloxLoop>,
+    out_fd: RawFd,
+    in_fd: RawFd,
+    /// None once finished, failed or cancelled
+    progress: Mutex<Option<SendfileProgress>>,
+}
+
+#[pymethods]
+impl SockSendfileCallback {
+    /// Done callback: a no-op if the send finished, a cleanup if it was cancelled
+    fn __call__(&self, py: Python<'_>, _fut: Py<PyAny>) -> PyResult<()> {
+        if self.progress.lock().take().is_some() {
+            self.loop_
+                .bind(py)
+                .borrow()
+                .remove_writer(py, self.out_fd)?;
+        }
         Ok(())
     }
 }
 
-impl SockSendallCallback {
+impl SockSendfileCallback {
     pub fn new(
         loop_: Py<VeloxLoop>,
         future: Py<PendingFuture>,
-        fd: RawFd,
-        data: Vec<u8>,
+        out_fd: RawFd,
+        in_fd: RawFd,
+        offset: i64,
+        remaining: usize,
         sent: usize,
     ) -> Self {
         Self {
             future,
             loop_,
-            fd,
-            data,
-            sent,
+            out_fd,
+            in_fd,
+            progress: Mutex::new(Some(SendfileProgress {
+                offset,
+                remaining,
+                sent,
+            })),
+        }
+    }
+
+    /// Socket is writable: send the next `SENDALL_BUDGET` bytes of the file at
+    /// most, resolving the future with the total once the range is sent
+    pub fn on_writable(&self, py: Python<'_>) -> PyResult<()> {
+        let mut guard = self.progress.lock();
+        let Some(progress) = guard.as_mut() else {
+            return Ok(());
+        };
+        let budget = progress.remaining.min(SENDALL_BUDGET);
+        let result = sendfile_some(self.out_fd, self.in_fd, progress.offset, budget);
+        let finished = match result {
+            Ok((n, eof)) => {
+                progress.offset += n as i64;
+                progress.remaining -= n;
+                progress.sent += n;
+                eof || progress.remaining == 0
+            }
+            Err(_) => true,
+        };
+        if !finished {
+            return Ok(());
+        }
+        let sent = progress.sent;
+        guard.take();
+        drop(guard);
+
+        self.loop_
+            .bind(py)
+            .borrow()
+            .remove_writer(py, self.out_fd)?;
+        let future = self.future.bind(py).borrow();
+        match result {
+            Ok(_) => future.set_result(py, sent.into_pyobject(py)?.into_any().unbind()),
+            Err(e) => {
+                future.set_exception(py, sendfile_error(py, e, sent).into_value(py).into_any())
+            }
         }
     }
 }
 
 #[pyclass]
 pub struct SockConnectCallback {
-    future: Py<PendingFuture>,
+    /// asyncio future, so cancelling the awaiting task reaches it
+    future: Py<PyAny>,
+    fd: RawFd,
+    loop_: Py<VeloxLoop>,
+    addr: Option<SocketAddr>,
 }
 
 #[pymethods]
 impl SockConnectCallback {
     fn __call__(&self, py: Python<'_>) -> PyResult<()> {
-        // Call Rust method directly instead of going through Python dispatch
-        self.future.bind(py).borrow().set_result(py, py.None())?;
+        // Writable only says the connect finished; SO_ERROR says how
+        self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
+        let future = self.future.bind(py);
+        if future.call_method0("done")?.is_truthy()? {
+            return Ok(());
+        }
+        let mut err: libc::c_int = 0;
+        let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_ERROR,
+                &mut err as *mut _ as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        let error = if ret != 0 {
+            Some(std::io::Error::last_os_error())
+        } else if err != 0 {
+            Some(std::io::Error::from_raw_os_error(err))
+        } else {
+            None
+        };
+        match error {
+            None => future.call_method1("set_result", (py.None(),))?,
+            Some(e) => {
+                let exc = connect_error(py, &e, self.addr).into_value(py);
+                future.call_method1("set_exception", (exc,))?
+            }
+        };
         Ok(())
     }
 }
 
 impl SockConnectCallback {
-    pub fn new(future: Py<PendingFuture>) -> Self {
-        Self { future }
+    pub fn new(
+        future: Py<PyAny>,
+        fd: RawFd,
+        loop_: Py<VeloxLoop>,
+        addr: Option<SocketAddr>,
+    ) -> Self {
+        Self {
+            future,
+            fd,
+            loop_,
+            addr,
+        }
     }
 }
 
@@ -617,8 +1070,7 @@ impl SendfileCallback {
                         std::io::ErrorKind::WouldBlock => return Ok(()),
                         _ if err.raw_os_error() == Some(libc::EAGAIN) => return Ok(()),
                         _ => {
-                            let py_err =
-                                PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string());
+                            let py_err = crate::utils::os_error_to_pyerr(err);
                             let exc_val = py_err.value(py).as_any().clone().unbind();
                             self.future.bind(py).borrow().set_exception(py, exc_val)?;
                             self.loop_
@@ -60,6 +60,12 @@ impl<T> ConcurrentCallbackQueue<T> {
     pub fn is_empty(&self) -> bool {
         self.len.load(Ordering::Relaxed) == 0
     }
+
+    /// Number of queued items (approximate while other threads push)
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
 }
 
 impl<T> Default for ConcurrentCallbackQueue<T> {
@@ -113,6 +119,11 @@ impl<V> ConcurrentIntMap<V> {
     pub fn entry(&self, key: i32) -> dashmap::Entry<'_, i32, V> {
         self.inner.entry(key)
     }
+
+    /// Number of keys
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
 }
 
 impl<V> Default for ConcurrentIntMap<V> {
@@ -1,9 +1,12 @@
 use std::sync::OnceLock;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::transports::TransportState;
 
 pub const DEFAULT_LIMIT: usize = 128 * 1024; // 128 KB default - increased for better large message perf
-pub const DEFAULT_HIGH: usize = 128 * 1024; // 128 KB
-pub const DEFAULT_LOW: usize = 32 * 1024; // 32 KB
+pub const DEFAULT_HIGH: usize = 64 * 1024; // 64 KiB, same as asyncio
+pub const DEFAULT_LOW: usize = 16 * 1024; // 16 KiB
 // Use constants directly since libc may not export them on all platforms
 pub const NI_MAXHOST: usize = 1025;
 pub const NI_MAXSERV: usize = 32;
@@ -21,6 +24,45 @@ pub const POLLER_BATCH_THRESHOLD: usize = 32; // Batch size for processing callb
 
 pub const RECV_BUF_SIZE: usize = 262144; // 256KB — matches uvloop, reads 100KB in one syscall
 
+pub const DETACH_COPY_BYTES: usize = 1024 * 1024; // PyBytes at least this large are filled with the GIL released
+
+pub const DEFAULT_READ_CHUNK_SIZE: usize = RECV_BUF_SIZE; // per-read size for transports, see set_read_buffer_size
+pub const MIN_READ_CHUNK_SIZE: usize = 1024; // 1 KB
+pub const MAX_READ_CHUNK_SIZE: usize = 4 * 1024 * 1024; // 4 MB
+
+pub const ACCEPT_BATCH: usize = 64; // connections a listener accepts per readiness event
+pub const ACCEPT_RETRY_DELAY: f64 = 1.0; // seconds a listener pauses when even the spare fd cannot drain it
+
+pub const UDP_RECV_BATCH: usize = 32; // datagrams a UDP transport reads per readiness event
+pub const MAX_DATAGRAM_SIZE: usize = 65536; // default max_datagram_size, no UDP/IPv4 datagram is longer
+
+pub const SENDALL_BUDGET: usize = 1024 * 1024; // bytes sock_sendall sends per loop iteration
+
+pub const SPLICE_PIPE_SIZE: usize = 1024 * 1024; // splice_to pipe capacity (the default pipe-max-size)
+pub const SPLICE_BUDGET: usize = 4 * 1024 * 1024; // bytes splice_to moves per readiness event
+
+pub const WAKEUP_BATCH: usize = 64; // StreamReader waiters settled per loop iteration when many become ready at once
+
+pub const SHRINK_WINDOW_TICKS: usize = 256; // loop iterations per high-water window of the reused buffers
+pub const SHRINK_FACTOR: usize = 8; // shrink a reused buffer once it holds this many times its recent peak
+
+pub const DEFAULT_COALESCE_DELAY_US: u64 = 100; // write coalescing: max age of a held-back write
+pub const DEFAULT_COALESCE_BYTES: usize = 16384; // write coalescing: flush once this much is queued
+
+pub const PACING_INTERVAL_NS: u64 = 10_000_000; // rate limiting: longest wait for the token bucket to refill
+pub const DEFAULT_RATE_LIMIT_BURST_NS: u64 = 100_000_000; // rate limiting: default burst, as this much time at the rate
+pub const FLUSH_POLL_INTERVAL: f64 = 0.005; // seconds between transport.flush() checks of the kernel send queue
+pub const IDLE_TIMEOUT_TICK_NS: u64 = 100_000_000; // transport.set_timeouts(): granularity of the idle timeout wheel
+pub const IDLE_TIMEOUT_SLOTS: usize = 1024; // transport.set_timeouts(): wheel slots, one lap is this many ticks
+
+pub const SSL_HANDSHAKE_TIMEOUT: f64 = 60.0; // seconds, asyncio's default ssl_handshake_timeout
+pub const LISTEN_BACKLOG: i32 = 128; // listen() backlog when create_server/start_server get no backlog=
+
+pub const DEFAULT_SLOW_CALLBACK_DURATION: f64 = 0.1; // seconds, as asyncio's slow_callback_duration
+pub const SOCK_OP_PARK_ITERATIONS: u8 = 8; // loop iterations a cancelled sock_recv/sock_sendall poll stays armed for a retry
+pub const DEBUG_STACK_DEPTH: i32 = 10; // coroutine origin tracking depth in debug mode, as asyncio's
+pub const DEFAULT_LOOKUP_LIMIT: usize = 16; // getaddrinfo()/getnameinfo() calls on pool threads at once, the rest queue on the loop
+
 static ASYNCIO: OnceLock<Py<PyModule>> = OnceLock::new();
 static SOCKET: OnceLock<Py<PyModule>> = OnceLock::new();
 
@@ -31,3 +73,41 @@ pub fn get_asyncio(py: Python<'_>) -> &Py<PyModule> {
 pub fn get_socket(py: Python<'_>) -> &Py<PyModule> {
     SOCKET.get_or_init(|| py.import("socket").unwrap().into())
 }
+
+/// `veloxloop._veloxloop.constants`: the defaults worth knowing when tuning,
+/// and the bits behind the names a transport's `get_state()` returns
+pub(crate) fn add_module(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = parent.py();
+    let m = PyModule::new(py, "constants")?;
+    m.add("DEFAULT_HIGH", DEFAULT_HIGH)?;
+    m.add("DEFAULT_LOW", DEFAULT_LOW)?;
+    m.add("DEFAULT_LIMIT", DEFAULT_LIMIT)?;
+    m.add("DEFAULT_READ_CHUNK_SIZE", DEFAULT_READ_CHUNK_SIZE)?;
+    m.add("MIN_READ_CHUNK_SIZE", MIN_READ_CHUNK_SIZE)?;
+    m.add("MAX_READ_CHUNK_SIZE", MAX_READ_CHUNK_SIZE)?;
+    m.add("DEFAULT_LOOKUP_LIMIT", DEFAULT_LOOKUP_LIMIT)?;
+    #[cfg(target_os = "linux")]
+    {
+        m.add("SQ_SIZE", crate::poller::SQ_SIZE)?;
+        m.add("CQ_SIZE", crate::poller::CQ_SIZE)?;
+    }
+
+    let bits = PyDict::new(py);
+    for (name, flag) in TransportState::all().iter_names() {
+        bits.set_item(name, flag.bits())?;
+    }
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("module", "veloxloop._veloxloop.constants")?;
+    let flags = py
+        .import("enum")?
+        .getattr("IntFlag")?
+        .call(("TransportState", &bits), Some(&kwargs))?;
+    m.add("TRANSPORT_STATE_BITS", bits)?;
+    m.add("TransportState", flags)?;
+
+    parent.add_submodule(&m)?;
+    // Importable by name too, not only reachable as an attribute
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("veloxloop._veloxloop.constants", m)
+}
@@ -0,0 +1,196 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple, PyWeakrefMethods, PyWeakrefReference};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::event_loop::{ExceptionContext, VeloxLoop};
+use crate::transports::future::PendingFuture;
+
+/// Drives one `agen.aclose()` coroutine for `shutdown_asyncgens` without
+/// asyncio.gather/Task, so it needs nothing but this loop to make progress.
+/// Used as its own call_soon callback and future done-callback.
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+pub(crate) struct AcloseRunner {
+    loop_: Py<VeloxLoop>,
+    agen: Py<PyAny>,
+    coro: Py<PyAny>,
+    /// Runners still going; the last one to finish resolves `done`
+    remaining: Arc<AtomicUsize>,
+    done: Py<PendingFuture>,
+}
+
+#[pymethods]
+impl AcloseRunner {
+    #[pyo3(signature = (*_args))]
+    fn __call__(slf: &Bound<'_, Self>, _args: &Bound<'_, PyTuple>) -> PyResult<()> {
+        Self::step(slf)
+    }
+}
+
+impl AcloseRunner {
+    fn step(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.get();
+        match this.coro.bind(py).call_method1("send", (py.None(),)) {
+            Ok(yielded) => {
+                // An asyncio-style future: resume once it is done, as Task would
+                if yielded.hasattr("add_done_callback")? {
+                    if yielded.hasattr("_asyncio_future_blocking")? {
+                        yielded.setattr("_asyncio_future_blocking", false)?;
+                    }
+                    yielded.call_method1("add_done_callback", (slf,))?;
+                } else {
+                    // Bare yield (or a Rust future still pending): try again next tick
+                    this.loop_
+                        .bind(py)
+                        .borrow()
+                        .call_soon(slf.clone().into_any().unbind(), Vec::new(), None);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                if !e.is_instance_of::<pyo3::exceptions::PyStopIteration>(py) {
+                    report_aclose_error(py, &this.loop_, this.agen.bind(py), e)?;
+                }
+                this.finish(py)
+            }
+        }
+    }
+
+    fn finish(&self, py: Python<'_>) -> PyResult<()> {
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.done.bind(py).borrow().set_result(py, py.None())?;
+        }
+        Ok(())
+    }
+}
+
+/// Same context shape asyncio's shutdown_asyncgens reports with
+fn report_aclose_error(
+    py: Python<'_>,
+    loop_: &Py<VeloxLoop>,
+    agen: &Bound<'_, PyAny>,
+    err: PyErr,
+) -> PyResult<()> {
+    ExceptionContext::new(format!(
+        "an error occurred during closing of asynchronous generator {}",
+        agen.repr()?
+    ))
+    .exception(err.value(py))
+    .with("asyncgen", agen)
+    .report(py, &loop_.bind(py).borrow())
+}
+
+impl VeloxLoop {
+    // Async generator tracking methods.
+    // Generators are held weakly, like asyncio's WeakSet, so tracking one
+    // doesn't keep it (and its finalizer hook) from running.
+    pub fn _track_async_generator(&self, py: Python<'_>, agen: Py<PyAny>) -> PyResult<()> {
+        let weak = PyWeakrefReference::new(agen.bind(py))?;
+        let mut generators = self.async_generators.borrow_mut();
+        generators.retain(|g| g.bind(py).upgrade().is_some());
+        generators.push(weak.unbind());
+        Ok(())
+    }
+
+    pub fn _untrack_async_generator(&self, py: Python<'_>, agen: Py<PyAny>) {
+        self.async_generators.borrow_mut().retain(|g| {
+            g.bind(py)
+                .upgrade()
+                .is_some_and(|g| !g.is(agen.bind(py)))
+        });
+    }
+
+    /// `sys.set_asyncgen_hooks` firstiter hook installed while the loop runs
+    pub fn _asyncgen_firstiter_hook(&self, py: Python<'_>, agen: Py<PyAny>) -> PyResult<()> {
+        if self.asyncgens_shutdown_called.get() {
+            let warnings = py.import("warnings")?;
+            let message = format!(
+                "asynchronous generator {} was scheduled after loop.shutdown_asyncgens() call",
+                agen.bind(py).repr()?
+            );
+            let category = py.get_type::<pyo3::exceptions::PyResourceWarning>();
+            warnings.call_method1("warn", (message, category))?;
+        }
+        self._track_async_generator(py, agen)
+    }
+
+    /// `sys.set_asyncgen_hooks` finalizer hook: schedule `aclose()` as a task
+    pub fn _asyncgen_finalizer_hook(slf: &Bound<'_, Self>, agen: Py<PyAny>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.borrow();
+        this._untrack_async_generator(py, agen.clone_ref(py));
+        if !this.is_closed() {
+            let create_task = slf.getattr("create_task")?.unbind();
+            let aclose = agen.call_method0(py, "aclose")?;
+            let _ = this.call_soon_threadsafe(create_task, vec![aclose], None);
+        }
+        Ok(())
+    }
+
+    /// Point `sys.set_asyncgen_hooks` at this loop; returns the hooks to restore
+    pub(crate) fn install_asyncgen_hooks(slf: &Bound<'_, Self>) -> PyResult<Py<PyAny>> {
+        let sys = slf.py().import("sys")?;
+        let old_hooks = sys.call_method0("get_asyncgen_hooks")?;
+        let kwargs = PyDict::new(slf.py());
+        kwargs.set_item("firstiter", slf.getattr("_asyncgen_firstiter_hook")?)?;
+        kwargs.set_item("finalizer", slf.getattr("_asyncgen_finalizer_hook")?)?;
+        sys.call_method("set_asyncgen_hooks", (), Some(&kwargs))?;
+        Ok(old_hooks.unbind())
+    }
+
+    pub(crate) fn restore_asyncgen_hooks(py: Python<'_>, old_hooks: Py<PyAny>) -> PyResult<()> {
+        let sys = py.import("sys")?;
+        let hooks = old_hooks.bind(py).cast::<PyTuple>()?;
+        sys.call_method1("set_asyncgen_hooks", hooks)?;
+        Ok(())
+    }
+
+    /// Close every tracked generator. Each `aclose()` runs to completion on
+    /// this loop and failures go to the exception handler; the returned
+    /// future resolves with None once all of them have been attempted.
+    pub fn shutdown_asyncgens(slf: &Bound<'_, Self>) -> PyResult<Py<PendingFuture>> {
+        let py = slf.py();
+        let this = slf.borrow();
+        this.asyncgens_shutdown_called.set(true);
+
+        let tracked = std::mem::take(&mut *this.async_generators.borrow_mut());
+        let generators: Vec<Bound<'_, PyAny>> =
+            tracked.iter().filter_map(|g| g.bind(py).upgrade()).collect();
+
+        let done = this.create_future(py)?;
+        if generators.is_empty() {
+            done.bind(py).borrow().set_result(py, py.None())?;
+            return Ok(done);
+        }
+
+        let remaining = Arc::new(AtomicUsize::new(generators.len()));
+        let loop_: Py<VeloxLoop> = slf.clone().unbind();
+        let mut runners = Vec::with_capacity(generators.len());
+        for agen in generators {
+            match agen.call_method0("aclose") {
+                Ok(coro) => runners.push(Py::new(
+                    py,
+                    AcloseRunner {
+                        loop_: loop_.clone_ref(py),
+                        agen: agen.unbind(),
+                        coro: coro.unbind(),
+                        remaining: remaining.clone(),
+                        done: done.clone_ref(py),
+                    },
+                )?),
+                Err(e) => {
+                    report_aclose_error(py, &loop_, &agen, e)?;
+                    if remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+                        done.bind(py).borrow().set_result(py, py.None())?;
+                    }
+                }
+            }
+        }
+        // First steps run from the loop, like tasks created by gather()
+        for runner in runners {
+            this.call_soon(runner.into_any(), Vec::new(), None);
+        }
+        Ok(done)
+    }
+}
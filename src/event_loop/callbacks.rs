@@ -1,51 +1,52 @@
 use crate::callbacks::Callback;
 use crate::event_loop::VeloxLoop;
 use crate::transports::future::PendingFuture;
+use crate::utils::VeloxResult;
 use pyo3::prelude::*;
+use std::time::Duration;
+
+/// Seconds to whole nanoseconds, rounding any remainder up so a deadline is
+/// never early; negative and NaN become 0
+fn secs_to_ns_ceil(secs: f64) -> u64 {
+    (secs * 1_000_000_000.0).ceil().max(0.0) as u64
+}
 
 impl VeloxLoop {
-    /// Schedule a callback to be called on the next iteration (lock-free).
-    /// Uses crossbeam-channel internally for efficient MPMC queue operations.
+    /// Schedule a callback to be called on the next iteration.
+    /// Callbacks run in FIFO order of their `call_soon`/`call_soon_threadsafe` calls.
     pub fn call_soon(&self, callback: Py<PyAny>, args: Vec<Py<PyAny>>, context: Option<Py<PyAny>>) {
-        self.callbacks.push(Callback {
-            callback,
-            args,
-            context,
-        });
+        self.callbacks.push(Callback::new(callback, args, context));
     }
 
-    /// Schedule a callback from another thread (lock-free, thread-safe).
-    /// Uses crossbeam-channel internally - safe to call from any thread.
+    /// Schedule a callback from another thread (thread-safe).
+    /// Shares the sequence numbering with `call_soon`, so ordering is global.
+    /// Fails once the loop is closed; the flag is checked again after the push
+    /// so a close() racing with us can't leave the callback silently stranded
     pub fn call_soon_threadsafe(
         &self,
         callback: Py<PyAny>,
         args: Vec<Py<PyAny>>,
         context: Option<Py<PyAny>>,
-    ) {
-        // Lock-free push via crossbeam channel - safe from any thread!
-        self.callbacks.push(Callback {
-            callback,
-            args,
-            context,
-        });
+    ) -> VeloxResult<()> {
+        self.check_closed()?;
+        self.callbacks.push(Callback::new(callback, args, context));
+        self.check_closed()?;
         // Always notify the waker to wake up the event loop (thread-safe)
         let _ = self.waker.notify();
+        Ok(())
     }
 
-
+    /// Schedule a callback `delay` seconds from now; the deadline is rounded
+    /// up to the next nanosecond so it never fires early
     pub fn call_later(
         &self,
         delay: f64,
         callback: Py<PyAny>,
         args: Vec<Py<PyAny>>,
         context: Option<Py<PyAny>>,
-    ) -> u64 {
-        let now = (self.time() * 1_000_000_000.0) as u64;
-        let delay_ns = (delay * 1_000_000_000.0) as u64;
-        let when = now + delay_ns;
-        self.timers
-            .borrow_mut()
-            .insert(when, callback, args, context, 0)
+    ) -> PyResult<u64> {
+        let when = self.now_ns().saturating_add(secs_to_ns_ceil(delay));
+        Ok(self.timers_mut()?.insert(when, callback, args, context, 0))
     }
 
     pub fn call_at(
@@ -54,19 +55,85 @@ impl VeloxLoop {
         callback: Py<PyAny>,
         args: Vec<Py<PyAny>>,
         context: Option<Py<PyAny>>,
-    ) -> u64 {
-        let when_ns = (when * 1_000_000_000.0) as u64;
-        self.timers
-            .borrow_mut()
-            .insert(when_ns, callback, args, context, 0)
+    ) -> PyResult<u64> {
+        Ok(self
+            .timers_mut()?
+            .insert(secs_to_ns_ceil(when), callback, args, context, 0))
     }
 
-    pub fn _cancel_timer(&self, timer_id: u64) {
-        self.timers.borrow_mut().cancel(timer_id);
+    /// Loop time a pending timer fires at, as `loop.time()` will report it
+    pub fn timer_when(&self, timer_id: u64) -> PyResult<Option<f64>> {
+        Ok(self
+            .timers_mut()?
+            .deadline(timer_id)
+            .map(|ns| Duration::from_nanos(ns).as_secs_f64()))
+    }
+
+    pub fn _cancel_timer(&self, timer_id: u64) -> PyResult<()> {
+        self.timers_mut()?.cancel(timer_id);
+        Ok(())
+    }
+
+    /// `advance_time()` of a test-mode loop. Due timers run here rather than on
+    /// the next iteration, through the same call path `_run_once` uses; what
+    /// they schedule with call_soon still waits for the loop to run.
+    pub fn advance_time(&self, seconds: f64) -> PyResult<usize> {
+        let Some(now) = &self.virtual_now else {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "advance_time() needs a loop created with test_mode=True",
+            ));
+        };
+        if seconds.is_nan() || seconds < 0.0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "seconds must be a non-negative number, got {seconds}"
+            )));
+        }
+        self.check_closed()?;
+        now.set(now.get().saturating_add(secs_to_ns_ceil(seconds)));
+        let mut expired = self.timers_mut()?.pop_expired(now.get(), 0);
+        // A jump can span many wheel slots; keep asyncio's deadline order
+        expired.sort_by_key(|entry| entry.expires_at);
+        for entry in &expired {
+            unsafe {
+                crate::ffi_utils::call_callback_ignore_err(entry.callback.as_ptr(), &entry.args);
+            }
+        }
+        Ok(expired.len())
     }
 
     // Create a Rust-based PendingFuture
     pub fn create_future(&self, py: Python<'_>) -> PyResult<Py<PendingFuture>> {
+        if let Ok(mut pool) = self.future_pool.try_borrow_mut()
+            && pool.is_enabled()
+        {
+            return pool.acquire(py);
+        }
         Py::new(py, PendingFuture::new())
     }
+
+    /// A loop future already holding `value`. Every API that can finish
+    /// synchronously returns one of these then, so callers get the same type
+    /// as when the operation has to wait.
+    pub(crate) fn resolved_future(&self, py: Python<'_>, value: Py<PyAny>) -> PyResult<Py<PyAny>> {
+        let future = self.create_future(py)?;
+        future.bind(py).borrow().set_result(py, value)?;
+        Ok(future.into_any())
+    }
+
+    /// Recycle pooled futures nothing outside the pool references any more
+    pub(crate) fn reclaim_futures(&self, py: Python<'_>) {
+        let reclaimed = match self.future_pool.try_borrow_mut() {
+            Ok(mut pool) if pool.is_enabled() => pool.take_unreferenced(),
+            _ => return,
+        };
+        if reclaimed.is_empty() {
+            return;
+        }
+        // Resetting drops old results, which may run arbitrary __del__ code,
+        // so do it without holding the pool borrow
+        for future in &reclaimed {
+            future.bind(py).borrow().reset();
+        }
+        self.future_pool.borrow_mut().release(reclaimed);
+    }
 }
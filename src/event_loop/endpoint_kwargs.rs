@@ -0,0 +1,296 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict};
+use std::os::fd::RawFd;
+
+use crate::transports::ssl::SSLContext;
+
+/// Keywords of `create_connection()`: asyncio's, then our own. The Python
+/// wrapper takes family/proto/flags/all_errors/happy_eyeballs_delay/interleave
+/// before the call reaches the native method.
+pub(super) const CREATE_CONNECTION: &[&str] = &[
+    "ssl",
+    "sock",
+    "local_addr",
+    "server_hostname",
+    "ssl_handshake_timeout",
+    "ssl_shutdown_timeout",
+    "timeout",
+    "keepalive",
+    "fastopen",
+    "transparent",
+    "freebind",
+];
+
+/// Keywords of `open_connection()`, which mirror create_connection()'s
+pub(super) const OPEN_CONNECTION: &[&str] = CREATE_CONNECTION;
+
+/// Keywords of `create_server()` and `start_server()`: asyncio's, then our own
+pub(super) const CREATE_SERVER: &[&str] = &[
+    "family",
+    "flags",
+    "sock",
+    "backlog",
+    "ssl",
+    "reuse_address",
+    "reuse_port",
+    "keep_alive",
+    "ssl_handshake_timeout",
+    "ssl_shutdown_timeout",
+    "start_serving",
+    "tcp_defer_accept",
+    "tcp_fastopen",
+    "keepalive",
+    "transparent",
+    "freebind",
+];
+
+/// Keywords of `create_datagram_endpoint()`: asyncio's, then our own
+pub(super) const CREATE_DATAGRAM_ENDPOINT: &[&str] = &[
+    "family",
+    "proto",
+    "flags",
+    "reuse_address",
+    "reuse_port",
+    "allow_broadcast",
+    "sock",
+    "max_datagram_size",
+];
+
+/// Keywords that shape a socket we create, refused alongside `sock=`
+pub(super) const CONNECT_MODIFIERS: &[&str] =
+    &["local_addr", "fastopen", "transparent", "freebind"];
+pub(super) const SERVER_MODIFIERS: &[&str] = &[
+    "family",
+    "reuse_address",
+    "reuse_port",
+    "transparent",
+    "freebind",
+];
+pub(super) const DATAGRAM_MODIFIERS: &[&str] = &[
+    "family",
+    "proto",
+    "flags",
+    "reuse_address",
+    "reuse_port",
+    "allow_broadcast",
+];
+
+/// TypeError for the first keyword `method` doesn't take, worded like CPython's
+pub(super) fn check_kwargs(
+    method: &str,
+    kwargs: Option<&Bound<'_, PyDict>>,
+    allowed: &[&str],
+) -> PyResult<()> {
+    let Some(kwargs) = kwargs else {
+        return Ok(());
+    };
+    for key in kwargs.keys() {
+        let key = key.extract::<String>()?;
+        if !allowed.contains(&key.as_str()) {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                "{method}() got an unexpected keyword argument '{key}'"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `check_kwargs()` for an endpoint method given by name
+pub(super) fn check_method_kwargs(method: &str, kwargs: &Bound<'_, PyDict>) -> PyResult<()> {
+    let allowed = match method {
+        "create_connection" => CREATE_CONNECTION,
+        "open_connection" => OPEN_CONNECTION,
+        "create_server" | "start_server" => CREATE_SERVER,
+        "create_datagram_endpoint" => CREATE_DATAGRAM_ENDPOINT,
+        _ => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "no keyword arguments are known for {method}()"
+            )));
+        }
+    };
+    check_kwargs(method, Some(kwargs), allowed)
+}
+
+/// A keyword's value; None counts as not given
+pub(super) fn kwarg<'py>(
+    kwargs: Option<&Bound<'py, PyDict>>,
+    name: &str,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    Ok(kwargs
+        .map(|kw| kw.get_item(name))
+        .transpose()?
+        .flatten()
+        .filter(|v| !v.is_none()))
+}
+
+/// A keyword read as a flag, `default` when not given
+pub(super) fn flag_kwarg(
+    kwargs: Option<&Bound<'_, PyDict>>,
+    name: &str,
+    default: bool,
+) -> PyResult<bool> {
+    kwarg(kwargs, name)?.map_or(Ok(default), |v| v.is_truthy())
+}
+
+/// A timeout in seconds given as a keyword argument: None, or a positive number
+pub(super) fn seconds_kwarg(
+    kwargs: Option<&Bound<'_, PyDict>>,
+    name: &str,
+) -> PyResult<Option<f64>> {
+    let Some(value) = kwarg(kwargs, name)? else {
+        return Ok(None);
+    };
+    let seconds = value.extract::<f64>()?;
+    if seconds.is_nan() || seconds <= 0.0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "{} should be a positive number, got {}",
+            name,
+            value.repr()?
+        )));
+    }
+    Ok(Some(seconds))
+}
+
+/// `ssl=` of a client connection: None/False for plain TCP, True for a default
+/// client context, or our SSLContext. Anything else, a stdlib `ssl.SSLContext`
+/// included, is refused instead of quietly connecting in plaintext.
+pub(super) fn client_ssl_kwarg(
+    py: Python<'_>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Option<Py<SSLContext>>> {
+    let Some(value) = kwarg(kwargs, "ssl")? else {
+        return Ok(None);
+    };
+    if let Ok(enabled) = value.cast::<PyBool>() {
+        return match enabled.is_true() {
+            true => SSLContext::create_client_context(py).map(Some),
+            false => Ok(None),
+        };
+    }
+    match value.extract::<Py<SSLContext>>() {
+        Ok(context) => Ok(Some(context)),
+        Err(_) => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "ssl must be a veloxloop SSLContext, True or None, got {}",
+            value.repr()?
+        ))),
+    }
+}
+
+/// The handshake timeout, after checking both TLS timeouts the way asyncio
+/// does: positive, and only together with ssl. The shutdown timeout needs no
+/// more than that, as closing a TLS transport never waits on the peer.
+pub(super) fn ssl_timeouts(kwargs: Option<&Bound<'_, PyDict>>, ssl: bool) -> PyResult<Option<f64>> {
+    let handshake = seconds_kwarg(kwargs, "ssl_handshake_timeout")?;
+    let shutdown = seconds_kwarg(kwargs, "ssl_shutdown_timeout")?;
+    for (name, value) in [
+        ("ssl_handshake_timeout", handshake),
+        ("ssl_shutdown_timeout", shutdown),
+    ] {
+        if value.is_some() && !ssl {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "{name} is only meaningful with ssl"
+            )));
+        }
+    }
+    Ok(handshake)
+}
+
+/// `server_hostname=` checked against `ssl` and `host` like asyncio does
+pub(super) fn server_hostname_kwarg(
+    kwargs: Option<&Bound<'_, PyDict>>,
+    ssl: bool,
+    host: Option<&str>,
+) -> PyResult<Option<String>> {
+    let server_hostname = kwarg(kwargs, "server_hostname")?
+        .map(|v| v.extract::<String>())
+        .transpose()?;
+    if server_hostname.is_some() && !ssl {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "server_hostname is only meaningful with ssl",
+        ));
+    }
+    if ssl && server_hostname.is_none() && host.is_none() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "You must set server_hostname when using ssl without a host",
+        ));
+    }
+    Ok(server_hostname.or_else(|| host.filter(|_| ssl).map(str::to_string)))
+}
+
+/// The fd of `sock=`, after checking it is a socket of `sock_type` and that
+/// no address was given with it. asyncio's wording throughout.
+pub(super) fn sock_kwarg(
+    kwargs: Option<&Bound<'_, PyDict>>,
+    sock_type: libc::c_int,
+    addressed: bool,
+) -> PyResult<Option<RawFd>> {
+    let Some(sock) = kwarg(kwargs, "sock")? else {
+        return Ok(None);
+    };
+    if addressed {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "host/port and sock can not be specified at the same time",
+        ));
+    }
+    let fd = sock.call_method0("fileno")?.extract::<RawFd>()?;
+    if crate::socket::socket_type(fd).ok() != Some(sock_type) {
+        let kind = if sock_type == libc::SOCK_DGRAM {
+            "UDP"
+        } else {
+            "Stream"
+        };
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "A {kind} Socket was expected, got {}",
+            sock.repr()?
+        )));
+    }
+    Ok(Some(fd))
+}
+
+/// ValueError naming the `modifiers` given along with `sock=`: they shape a
+/// socket we create, and a passed-in socket is used as it is
+pub(super) fn check_sock_modifiers(
+    kwargs: Option<&Bound<'_, PyDict>>,
+    modifiers: &[&str],
+    mut problems: Vec<String>,
+) -> PyResult<()> {
+    for name in modifiers {
+        if let Some(value) = kwarg(kwargs, name)?
+            && value.is_truthy()?
+        {
+            problems.push(format!("{name}={}", value.str()?));
+        }
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+        "socket modifier keyword arguments can not be used when sock is specified. ({})",
+        problems.join(", ")
+    )))
+}
+
+/// `reuse_port=`, refused where the platform lacks SO_REUSEPORT
+pub(super) fn reuse_port_kwarg(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<bool> {
+    let reuse_port = flag_kwarg(kwargs, "reuse_port", false)?;
+    if reuse_port && !crate::socket::HAS_REUSE_PORT {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "reuse_port not supported by socket module",
+        ));
+    }
+    Ok(reuse_port)
+}
+
+/// `family=` of a TCP/UDP endpoint: 0 (either), AF_INET or AF_INET6
+pub(super) fn family_kwarg(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<libc::c_int> {
+    let Some(value) = kwarg(kwargs, "family")? else {
+        return Ok(libc::AF_UNSPEC);
+    };
+    match value.extract::<libc::c_int>()? {
+        family @ (libc::AF_UNSPEC | libc::AF_INET | libc::AF_INET6) => Ok(family),
+        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "unsupported address family {}",
+            value.repr()?
+        ))),
+    }
+}
@@ -0,0 +1,58 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::event_loop::VeloxLoop;
+
+/// The context dict handed to `loop.call_exception_handler()` by the loop's
+/// own error paths. `message` is always set; the other asyncio keys only when
+/// the reporting site has them.
+pub(crate) struct ExceptionContext {
+    message: String,
+    items: Vec<(&'static str, Py<PyAny>)>,
+}
+
+impl ExceptionContext {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            items: Vec::new(),
+        }
+    }
+
+    pub(crate) fn exception(self, exc: &Bound<'_, impl Sized>) -> Self {
+        self.with("exception", exc)
+    }
+
+    pub(crate) fn transport(self, transport: &Bound<'_, impl Sized>) -> Self {
+        self.with("transport", transport)
+    }
+
+    pub(crate) fn protocol(self, protocol: &Bound<'_, impl Sized>) -> Self {
+        self.with("protocol", protocol)
+    }
+
+    #[allow(dead_code)] // For handle-based callbacks, none of which report yet
+    pub(crate) fn handle(self, handle: &Bound<'_, impl Sized>) -> Self {
+        self.with("handle", handle)
+    }
+
+    /// Any other key, e.g. `asyncgen` or `future`
+    pub(crate) fn with(mut self, key: &'static str, value: &Bound<'_, impl Sized>) -> Self {
+        self.items.push((key, value.clone().into_any().unbind()));
+        self
+    }
+
+    pub(crate) fn build(self, py: Python<'_>) -> PyResult<Py<PyDict>> {
+        let context = PyDict::new(py);
+        context.set_item("message", self.message)?;
+        for (key, value) in self.items {
+            context.set_item(key, value)?;
+        }
+        Ok(context.unbind())
+    }
+
+    /// Build the context and pass it to `loop_`'s exception handler
+    pub(crate) fn report(self, py: Python<'_>, loop_: &VeloxLoop) -> PyResult<()> {
+        loop_.call_exception_handler(py, self.build(py)?)
+    }
+}
@@ -1,20 +1,316 @@
-use crate::constants::{NI_MAXHOST, NI_MAXSERV};
-use crate::event_loop::VeloxLoop;
+use crate::callbacks::{Callback, ThreadsafeHandle};
+use crate::constants::{DEFAULT_LOOKUP_LIMIT, NI_MAXHOST, NI_MAXSERV, get_socket};
+use crate::event_loop::{ExceptionContext, VeloxLoop};
 use crate::executor::ThreadPoolExecutor;
 use crate::ffi_utils;
+use crate::transports::future::PendingFuture;
+use std::collections::VecDeque;
 use std::ffi::{CStr, CString};
 use std::mem;
-use std::net::{IpAddr, SocketAddr};
+use std::net::{Ipv6Addr, SocketAddr};
 use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyString, PyTuple};
+use pyo3::types::{PyBytes, PyInt, PyString, PyTuple};
+
+/// Links a `concurrent.futures.Future` from a Python executor to the loop
+/// future returned by `run_in_executor`, in both directions.
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+pub(crate) struct ExecutorFutureBridge {
+    loop_: Py<VeloxLoop>,
+    future: Py<PendingFuture>,
+    concurrent: Py<PyAny>,
+}
+
+#[pymethods]
+impl ExecutorFutureBridge {
+    /// Done callback of the concurrent future, run on the worker thread:
+    /// the loop future is only touched from the loop, via call_soon_threadsafe.
+    /// Like asyncio, a result arriving after the loop closed is dropped
+    fn __call__(slf: &Bound<'_, Self>, concurrent: &Bound<'_, PyAny>) -> PyResult<()> {
+        let copy_state = slf.getattr("_copy_state")?.unbind();
+        let loop_ = slf.get().loop_.bind(slf.py()).borrow();
+        if loop_
+            .call_soon_threadsafe(copy_state, Vec::new(), None)
+            .is_err()
+        {
+            // The loop closed while the job ran: nobody awaits the result any
+            // more, but a failure should not vanish silently
+            if !concurrent.call_method0("cancelled")?.is_truthy()? {
+                let exc = concurrent.call_method0("exception")?;
+                if !exc.is_none() {
+                    ExceptionContext::new("Executor job failed after the event loop was closed")
+                        .exception(&exc)
+                        .with("future", concurrent)
+                        .report(slf.py(), &loop_)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn _copy_state(&self, py: Python<'_>) -> PyResult<()> {
+        let future = self.future.bind(py).borrow();
+        if future.done() {
+            return Ok(());
+        }
+        let concurrent = self.concurrent.bind(py);
+        if concurrent.call_method0("cancelled")?.is_truthy()? {
+            future.cancel(py)?;
+            return Ok(());
+        }
+        let exc = concurrent.call_method0("exception")?;
+        if exc.is_none() {
+            future.set_result(py, concurrent.call_method0("result")?.unbind())
+        } else {
+            future.set_exception(py, exc.unbind())
+        }
+    }
+
+    /// Done callback of the loop future: cancelling it cancels the job
+    #[pyo3(signature = (*_args))]
+    fn _loop_future_done(&self, py: Python<'_>, _args: &Bound<'_, PyTuple>) -> PyResult<()> {
+        if self.future.bind(py).borrow().cancelled() {
+            self.concurrent.call_method0(py, "cancel")?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of an internal-pool job, run on the loop thread to resolve the
+/// future returned for it
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+pub(crate) struct ExecutorResult {
+    future: Py<PendingFuture>,
+    outcome: Result<Py<PyAny>, Py<PyAny>>,
+}
+
+#[pymethods]
+impl ExecutorResult {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let future = self.future.bind(py).borrow();
+        if future.done() {
+            return Ok(());
+        }
+        match &self.outcome {
+            Ok(result) => future.set_result(py, result.clone_ref(py)),
+            Err(exc) => future.set_exception(py, exc.clone_ref(py)),
+        }
+    }
+}
+
+/// Wrap `job` for a pool thread. The outcome reaches `future` through the
+/// loop's queue; if the loop has been dropped the job is skipped or its
+/// outcome discarded, and nothing touches Python once it is finalizing.
+fn pool_job<F>(
+    handle: ThreadsafeHandle,
+    future: Py<PendingFuture>,
+    job: F,
+) -> impl FnOnce() + Send + 'static
+where
+    F: FnOnce(Python<'_>) -> PyResult<Py<PyAny>> + Send + 'static,
+{
+    move || {
+        if ffi_utils::is_finalizing() {
+            // Even releasing these references would need the GIL
+            mem::forget((future, job));
+            return;
+        }
+        Python::attach(move |py| {
+            if !handle.is_alive() {
+                return;
+            }
+            let outcome = job(py).map_err(|e| e.value(py).clone().into_any().unbind());
+            if let Ok(result) = Py::new(py, ExecutorResult { future, outcome }) {
+                handle.call_soon(Callback::new(result.into_any(), Vec::new(), None));
+            }
+        });
+    }
+}
+
+/// A blocking name lookup, run on a pool thread
+type LookupJob = Box<dyn FnOnce(Python<'_>) -> PyResult<Py<PyAny>> + Send>;
+
+/// getaddrinfo()/getnameinfo() calls for the internal pool. At most `limit`
+/// run on its threads at once; the rest wait here, where cancelling one
+/// drops it before it ever reaches a thread. A lookup stuck in libc can't be
+/// interrupted, so it keeps its slot until the call returns.
+pub(crate) struct Lookups {
+    limit: usize,
+    /// Lookups on pool threads, abandoned ones still stuck there included
+    in_flight: usize,
+    queue: VecDeque<QueuedLookup>,
+    /// Seconds after which a lookup fails with TimeoutError, see
+    /// `set_resolver_timeout`
+    timeout: Option<f64>,
+    timed_out: u64,
+    next_id: u64,
+    /// Test hook called as `hook(host, port)` on the pool thread instead of
+    /// libc getaddrinfo, see `_set_lookup_hook`
+    hook: Option<Py<PyAny>>,
+    /// Test stall before each libc lookup, see `_set_lookup_stall`
+    stall: Option<Duration>,
+}
+
+impl Default for Lookups {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_LOOKUP_LIMIT,
+            in_flight: 0,
+            queue: VecDeque::new(),
+            timeout: None,
+            timed_out: 0,
+            next_id: 0,
+            hook: None,
+            stall: None,
+        }
+    }
+}
+
+struct QueuedLookup {
+    future: Py<PendingFuture>,
+    /// Set once the future is done, so a job not yet started skips the lookup
+    abandoned: Arc<AtomicBool>,
+    id: u64,
+    job: LookupJob,
+}
+
+/// Done callback of a lookup's future, on the loop thread: a lookup that
+/// was cancelled or timed out leaves the queue, or has its job skip the call
+/// if it hasn't started yet
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+struct LookupDone {
+    loop_: Py<VeloxLoop>,
+    abandoned: Arc<AtomicBool>,
+    id: u64,
+    timer: Option<u64>,
+}
+
+#[pymethods]
+impl LookupDone {
+    #[pyo3(signature = (*_args))]
+    fn __call__(&self, py: Python<'_>, _args: &Bound<'_, PyTuple>) -> PyResult<()> {
+        self.abandoned.store(true, Ordering::Release);
+        let loop_ = self.loop_.bind(py).borrow();
+        let mut lookups = loop_.lookups.borrow_mut();
+        lookups.queue.retain(|queued| queued.id != self.id);
+        drop(lookups);
+        if let Some(timer) = self.timer {
+            let _ = loop_._cancel_timer(timer);
+        }
+        Ok(())
+    }
+}
+
+/// Fails a lookup that `set_resolver_timeout` seconds didn't see finish
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+struct LookupTimeout {
+    loop_: Py<VeloxLoop>,
+    future: Py<PendingFuture>,
+}
+
+#[pymethods]
+impl LookupTimeout {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let future = self.future.bind(py).borrow();
+        if future.done() {
+            return Ok(());
+        }
+        self.loop_.bind(py).borrow().lookups.borrow_mut().timed_out += 1;
+        let exc = pyo3::exceptions::PyTimeoutError::new_err("name lookup timed out");
+        future.set_exception(py, exc.into_value(py).into_any())
+    }
+}
+
+/// Outcome of a lookup, run on the loop thread: resolves its future unless
+/// that was abandoned, then hands the slot to the next queued lookup
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+struct LookupFinished {
+    loop_: Py<VeloxLoop>,
+    future: Py<PendingFuture>,
+    /// None when the job skipped a lookup abandoned before it started
+    outcome: Option<Result<Py<PyAny>, Py<PyAny>>>,
+}
+
+#[pymethods]
+impl LookupFinished {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let settled = {
+            let future = self.future.bind(py).borrow();
+            match &self.outcome {
+                Some(_) if future.done() => Ok(()),
+                Some(Ok(result)) => future.set_result(py, result.clone_ref(py)),
+                Some(Err(exc)) => future.set_exception(py, exc.clone_ref(py)),
+                None => Ok(()),
+            }
+        };
+        let loop_ = self.loop_.bind(py);
+        loop_.borrow().lookups.borrow_mut().in_flight -= 1;
+        VeloxLoop::start_lookups(loop_);
+        settled
+    }
+}
 
 impl VeloxLoop {
+    /// Run `func(*args)` on `executor`, the default executor set with
+    /// `set_default_executor`, or else the internal thread pool
     pub fn run_in_executor(
+        slf: &Bound<'_, Self>,
+        executor: Option<Py<PyAny>>,
+        func: Py<PyAny>,
+        args: &Bound<'_, PyTuple>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let this = slf.borrow();
+        // The internal pool's threads don't survive fork()
+        this.owner.check()?;
+        let executor = executor.or_else(|| {
+            this.default_executor
+                .borrow()
+                .as_ref()
+                .map(|e| e.clone_ref(py))
+        });
+        if let Some(executor) = executor {
+            return Self::submit_to_executor(slf, executor.bind(py), func, args);
+        }
+        this.run_in_internal_pool(py, func, args)
+    }
+
+    fn submit_to_executor(
+        slf: &Bound<'_, Self>,
+        executor: &Bound<'_, PyAny>,
+        func: Py<PyAny>,
+        args: &Bound<'_, PyTuple>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let mut submit_args = vec![func.into_bound(py)];
+        submit_args.extend(args.iter());
+        let concurrent =
+            executor.call_method1("submit", PyTuple::new(py, submit_args)?)?;
+
+        let future = slf.borrow().create_future(py)?;
+        let bridge = Bound::new(
+            py,
+            ExecutorFutureBridge {
+                loop_: slf.clone().unbind(),
+                future: future.clone_ref(py),
+                concurrent: concurrent.clone().unbind(),
+            },
+        )?;
+        future
+            .bind(py)
+            .borrow()
+            .add_done_callback(py, bridge.getattr("_loop_future_done")?.unbind())?;
+        concurrent.call_method1("add_done_callback", (bridge,))?;
+        Ok(future.into_any())
+    }
+
+    fn run_in_internal_pool(
         &self,
         py: Python<'_>,
-        _executor: Option<Py<PyAny>>,
         func: Py<PyAny>,
         args: &Bound<'_, PyTuple>,
     ) -> PyResult<Py<PyAny>> {
@@ -27,25 +323,11 @@ impl VeloxLoop {
         let future = self.create_future(py)?;
         let future_clone = future.clone_ref(py);
 
-        let func_clone = func.clone_ref(py);
-        let args_clone: Py<PyTuple> = args.clone().unbind();
+        let args: Py<PyTuple> = args.clone().unbind();
 
         // Use spawn for fire-and-forget task execution
-        executor_ref.spawn(move || {
-            let _ = Python::attach(move |py| {
-                let result = func_clone.call1(py, args_clone.bind(py));
-
-                match result {
-                    Ok(val) => {
-                        let _ = future_clone.bind(py).borrow().set_result(py, val);
-                    }
-                    Err(e) => {
-                        let exc: Py<PyAny> = e.value(py).clone().unbind().into();
-                        let _ = future_clone.bind(py).borrow().set_exception(py, exc);
-                    }
-                }
-            });
-        });
+        let job = move |py: Python<'_>| func.call1(py, args.bind(py));
+        executor_ref.spawn(pool_job(self.threadsafe_handle(), future_clone, job));
 
         Ok(future.into_any())
     }
@@ -57,6 +339,7 @@ impl VeloxLoop {
         F: FnOnce() -> R + Send + 'static,
         R: Send + 'static,
     {
+        self.owner.check()?;
         if self.executor.borrow().is_none() {
             *self.executor.borrow_mut() = Some(ThreadPoolExecutor::new()?);
         }
@@ -70,14 +353,70 @@ impl VeloxLoop {
         Ok(handle.join())
     }
 
-    pub fn set_default_executor(&self, _executor: Option<Py<PyAny>>) -> PyResult<()> {
-        *self.executor.borrow_mut() = Some(ThreadPoolExecutor::new()?);
+    /// Use `executor` for `run_in_executor(None, ...)`; None goes back to the
+    /// internal pool. Anything with a `submit()` method is accepted.
+    pub fn set_default_executor(&self, py: Python<'_>, executor: Option<Py<PyAny>>) -> PyResult<()> {
+        if let Some(executor) = executor.as_ref()
+            && !executor.bind(py).hasattr("submit")?
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "executor must have a submit() method",
+            ));
+        }
+        *self.default_executor.borrow_mut() = executor;
         Ok(())
     }
 
-    pub fn getaddrinfo(
+    /// Shut down the internal pool, letting queued jobs finish first. Workers
+    /// are joined on a separate thread; the future resolves once they are gone.
+    /// An executor given to `set_default_executor` belongs to the caller and
+    /// is left running.
+    pub fn shutdown_default_executor(&self, py: Python<'_>) -> PyResult<Py<PendingFuture>> {
+        let future = self.create_future(py)?;
+        let Some(pool) = self.executor.borrow_mut().take() else {
+            future.bind(py).borrow().set_result(py, py.None())?;
+            return Ok(future);
+        };
+        // Queued behind the results of the jobs the pool finishes first
+        let done = pool_job(self.threadsafe_handle(), future.clone_ref(py), |py| {
+            Ok(py.None())
+        });
+        std::thread::Builder::new()
+            .name("veloxloop-executor-shutdown".to_string())
+            .spawn(move || {
+                drop(pool);
+                done();
+            })?;
+        Ok(future)
+    }
+
+    /// Resolve names with `resolver.resolve(host, port, family)` instead of
+    /// libc; None restores the default. With `fallback`, a resolver that
+    /// raises or finds nothing hands the lookup back to libc.
+    pub fn set_resolver(
         &self,
         py: Python<'_>,
+        resolver: Option<Py<PyAny>>,
+        fallback: bool,
+    ) -> PyResult<()> {
+        if let Some(resolver) = resolver.as_ref()
+            && !resolver.bind(py).hasattr("resolve")?
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "resolver must have a resolve() method",
+            ));
+        }
+        self.resolver_fallback.set(fallback && resolver.is_some());
+        *self.resolver.borrow_mut() = resolver;
+        Ok(())
+    }
+
+    pub fn get_resolver(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.resolver.borrow().as_ref().map(|r| r.clone_ref(py))
+    }
+
+    pub fn getaddrinfo(
+        slf: &Bound<'_, Self>,
         host: Option<Bound<'_, PyAny>>,
         port: Option<Bound<'_, PyAny>>,
         family: i32,
@@ -85,6 +424,7 @@ impl VeloxLoop {
         proto: i32,
         flags: i32,
     ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
         let host_str = match host {
             Some(h) => {
                 if let Ok(s) = h.cast::<PyString>() {
@@ -106,8 +446,9 @@ impl VeloxLoop {
                     Some(s.to_string())
                 } else if let Ok(b) = p.cast::<PyBytes>() {
                     Some(String::from_utf8_lossy(b.as_bytes()).to_string())
-                } else if let Ok(i) = p.extract::<i32>() {
-                    Some(i.to_string())
+                } else if let Ok(i) = p.cast::<PyInt>() {
+                    // Any int, bool included: False is service "0" like CPython
+                    Some(i.extract::<i64>()?.to_string())
                 } else {
                     return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
                         "port must be str, bytes, int, or None",
@@ -117,74 +458,253 @@ impl VeloxLoop {
             None => None,
         };
 
-        if self.executor.borrow().is_none() {
-            *self.executor.borrow_mut() = Some(ThreadPoolExecutor::new()?);
-        }
-        let executor_bind = self.executor.borrow();
-        let executor_ref = executor_bind.as_ref().unwrap();
+        let this = slf.borrow();
+        let (hook, stall) = {
+            let lookups = this.lookups.borrow();
+            (
+                lookups.hook.as_ref().map(|h| h.clone_ref(py)),
+                lookups.stall,
+            )
+        };
+        drop(this);
+        let job: LookupJob = match hook {
+            Some(hook) => Box::new(move |py| hook.call1(py, (host_str, port_str))),
+            None => Box::new(move |py| {
+                perform_getaddrinfo(py, host_str, port_str, family, r#type, proto, flags, stall)
+            }),
+        };
+        Self::lookup(slf, job)
+    }
 
-        let future = self.create_future(py)?;
-        let future_clone = future.clone_ref(py);
+    pub fn getnameinfo(
+        slf: &Bound<'_, Self>,
+        sockaddr: Bound<'_, PyTuple>,
+        flags: i32,
+    ) -> PyResult<Py<PyAny>> {
+        let sock_addr = crate::utils::ipv6::parse_address_tuple(&sockaddr, libc::AF_UNSPEC)?;
+        let stall = slf.borrow().lookups.borrow().stall;
+        Self::lookup(
+            slf,
+            Box::new(move |py| perform_getnameinfo(py, sock_addr, flags, stall)),
+        )
+    }
 
-        executor_ref.spawn_blocking(move || {
-            let _ = Python::attach(move |py| {
-                let result =
-                    perform_getaddrinfo(py, host_str, port_str, family, r#type, proto, flags);
+    /// Run a blocking name lookup on the internal pool once one of the
+    /// `limit` lookup slots is free, failing it after the resolver timeout
+    fn lookup(slf: &Bound<'_, Self>, job: LookupJob) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let future = this.create_future(py)?;
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let (id, timeout) = {
+            let mut lookups = this.lookups.borrow_mut();
+            lookups.next_id += 1;
+            (lookups.next_id, lookups.timeout)
+        };
+        let timer = match timeout {
+            Some(timeout) => {
+                let expire = LookupTimeout {
+                    loop_: slf.clone().unbind(),
+                    future: future.clone_ref(py),
+                };
+                Some(this.call_later(timeout, Py::new(py, expire)?.into_any(), Vec::new(), None)?)
+            }
+            None => None,
+        };
+        let done = LookupDone {
+            loop_: slf.clone().unbind(),
+            abandoned: abandoned.clone(),
+            id,
+            timer,
+        };
+        future
+            .bind(py)
+            .borrow()
+            .add_done_callback(py, Py::new(py, done)?.into_any())?;
+        this.lookups.borrow_mut().queue.push_back(QueuedLookup {
+            future: future.clone_ref(py),
+            abandoned,
+            id,
+            job,
+        });
+        drop(this);
+        Self::start_lookups(slf);
+        Ok(future.into_any())
+    }
 
-                match result {
-                    Ok(val) => {
-                        let _ = future_clone.bind(py).borrow().set_result(py, val);
-                    }
+    /// Move queued lookups onto pool threads while slots are free
+    fn start_lookups(slf: &Bound<'_, Self>) {
+        let py = slf.py();
+        let this = slf.borrow();
+        loop {
+            let queued = {
+                let mut lookups = this.lookups.borrow_mut();
+                if lookups.in_flight >= lookups.limit {
+                    return;
+                }
+                let Some(queued) = lookups.queue.pop_front() else {
+                    return;
+                };
+                lookups.in_flight += 1;
+                queued
+            };
+            if this.executor.borrow().is_none() {
+                match ThreadPoolExecutor::new() {
+                    Ok(pool) => *this.executor.borrow_mut() = Some(pool),
                     Err(e) => {
-                        let exc: Py<PyAny> = e.value(py).clone().unbind().into();
-                        let _ = future_clone.bind(py).borrow().set_exception(py, exc);
+                        this.lookups.borrow_mut().in_flight -= 1;
+                        let _ = queued
+                            .future
+                            .bind(py)
+                            .borrow()
+                            .set_exception(py, e.into_value(py).into_any());
+                        continue;
                     }
                 }
-            });
-        });
+            }
+            let handle = this.threadsafe_handle();
+            let loop_ = slf.clone().unbind();
+            let QueuedLookup {
+                future,
+                abandoned,
+                job,
+                ..
+            } = queued;
+            let run = move || {
+                if ffi_utils::is_finalizing() {
+                    // Even releasing these references would need the GIL
+                    mem::forget((loop_, future, job));
+                    return;
+                }
+                Python::attach(move |py| {
+                    if !handle.is_alive() {
+                        return;
+                    }
+                    let outcome = (!abandoned.load(Ordering::Acquire))
+                        .then(|| job(py).map_err(|e| e.value(py).clone().into_any().unbind()));
+                    let finished = LookupFinished {
+                        loop_,
+                        future,
+                        outcome,
+                    };
+                    if let Ok(finished) = Py::new(py, finished) {
+                        handle.call_soon(Callback::new(finished.into_any(), Vec::new(), None));
+                    }
+                });
+            };
+            if let Some(pool) = this.executor.borrow().as_ref() {
+                pool.spawn_blocking(run);
+            }
+        }
+    }
 
-        Ok(future.into_any())
+    /// Let at most `limit` name lookups use pool threads at once
+    pub fn set_lookup_limit(slf: &Bound<'_, Self>, limit: usize) -> PyResult<()> {
+        if limit == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "lookup limit must be at least 1",
+            ));
+        }
+        slf.borrow().lookups.borrow_mut().limit = limit;
+        Self::start_lookups(slf);
+        Ok(())
     }
 
-    pub fn getnameinfo(
-        &self,
-        py: Python<'_>,
-        sockaddr: Bound<'_, PyTuple>,
-        flags: i32,
-    ) -> PyResult<Py<PyAny>> {
-        if self.executor.borrow().is_none() {
-            *self.executor.borrow_mut() = Some(ThreadPoolExecutor::new()?);
+    /// Fail name lookups with TimeoutError after `timeout` seconds, even if
+    /// their pool thread is still stuck in libc; None waits for as long as
+    /// the call takes. Applies to lookups started from now on.
+    pub fn set_resolver_timeout(&self, timeout: Option<f64>) -> PyResult<()> {
+        if timeout.is_some_and(|t| t.is_nan() || t <= 0.0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "resolver timeout must be a positive number or None",
+            ));
         }
-        let executor_bind = self.executor.borrow();
-        let executor_ref = executor_bind.as_ref().unwrap();
+        self.lookups.borrow_mut().timeout = timeout;
+        Ok(())
+    }
 
-        let addr_str: String = sockaddr.get_item(0)?.extract()?;
-        let port: u16 = sockaddr.get_item(1)?.extract()?;
+    pub fn get_resolver_timeout(&self) -> Option<f64> {
+        self.lookups.borrow().timeout
+    }
 
-        let future = self.create_future(py)?;
-        let future_clone = future.clone_ref(py);
+    pub(crate) fn set_lookup_hook(&self, hook: Option<Py<PyAny>>) {
+        self.lookups.borrow_mut().hook = hook;
+    }
 
-        executor_ref.spawn_blocking(move || {
-            let _ = Python::attach(move |py| {
-                let result = perform_getnameinfo(py, &addr_str, port, flags);
+    pub(crate) fn set_lookup_stall(&self, stall: Option<Duration>) {
+        self.lookups.borrow_mut().stall = stall;
+    }
 
-                match result {
-                    Ok(val) => {
-                        let _ = future_clone.bind(py).borrow().set_result(py, val);
-                    }
-                    Err(e) => {
-                        let exc: Py<PyAny> = e.value(py).clone().unbind().into();
-                        let _ = future_clone.bind(py).borrow().set_exception(py, exc);
-                    }
-                }
-            });
-        });
+    /// `(in_flight, queued, timed_out)` name lookups
+    pub(crate) fn lookup_counts(&self) -> (usize, usize, u64) {
+        let lookups = self.lookups.borrow();
+        (lookups.in_flight, lookups.queue.len(), lookups.timed_out)
+    }
+}
 
-        Ok(future.into_any())
+/// A family/type/proto/flags argument to `getaddrinfo`: a plain int or one of
+/// the socket module's IntEnum members. Omitted or None means 0
+pub(crate) fn socket_constant(value: Option<&Bound<'_, PyAny>>, name: &str) -> PyResult<i32> {
+    let Some(value) = value.filter(|v| !v.is_none()) else {
+        return Ok(0);
+    };
+    match value.cast::<PyInt>() {
+        Ok(int) => int.extract(),
+        Err(_) => Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "{} must be an integer, not {}",
+            name,
+            value.get_type().name()?
+        ))),
+    }
+}
+
+/// `cls(value)` for one of the socket module's enums, or the plain int when
+/// the enum has no such member. Returns a new reference
+unsafe fn socket_enum(cls: &Bound<'_, PyAny>, value: i32) -> *mut pyo3::ffi::PyObject {
+    match cls.call1((value,)) {
+        Ok(member) => member.into_ptr(),
+        Err(_) => unsafe { ffi_utils::long_from_i32(value) },
+    }
+}
+
+/// The list getaddrinfo() allocated, freed on drop. Only ever moved from the
+/// detached call to the thread that reads it
+#[cfg(unix)]
+struct AddrInfoList(*mut libc::addrinfo);
+
+#[cfg(unix)]
+unsafe impl Send for AddrInfoList {}
+
+#[cfg(unix)]
+impl Drop for AddrInfoList {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { libc::freeaddrinfo(self.0) };
+        }
+    }
+}
+
+/// The error CPython's socket module raises for a failed lookup: the errno's
+/// OSError for EAI_SYSTEM, otherwise `socket.gaierror(code, gai_strerror(code))`
+#[cfg(unix)]
+fn lookup_error(py: Python<'_>, code: i32, os_error: Option<std::io::Error>) -> PyErr {
+    if let Some(os_error) = os_error {
+        return crate::utils::os_error_to_pyerr(os_error);
+    }
+    let message = unsafe { CStr::from_ptr(libc::gai_strerror(code)) }
+        .to_string_lossy()
+        .into_owned();
+    match get_socket(py).bind(py).getattr("gaierror") {
+        Ok(gaierror) => match gaierror.call1((code, message)) {
+            Ok(exc) => PyErr::from_value(exc),
+            Err(e) => e,
+        },
+        Err(e) => e,
     }
 }
 
 #[cfg(unix)]
+#[allow(clippy::too_many_arguments)]
 fn perform_getaddrinfo(
     py: Python<'_>,
     host: Option<String>,
@@ -193,43 +713,45 @@ fn perform_getaddrinfo(
     socktype: i32,
     protocol: i32,
     flags: i32,
+    stall: Option<Duration>,
 ) -> PyResult<Py<PyAny>> {
-    unsafe {
-        let mut hints: libc::addrinfo = mem::zeroed();
+    let socket_module = get_socket(py).bind(py);
+    let address_family = socket_module.getattr("AddressFamily")?;
+    let socket_kind = socket_module.getattr("SocketKind")?;
+
+    let c_host = host.and_then(|h| CString::new(h).ok());
+    let c_port = port.and_then(|p| CString::new(p).ok());
+
+    // The resolver may take as long as it likes: without the GIL, so the
+    // loop and its lookup timeout keep running meanwhile
+    let (ret, list, os_error) = py.detach(move || {
+        if let Some(stall) = stall {
+            std::thread::sleep(stall);
+        }
+        let mut hints: libc::addrinfo = unsafe { mem::zeroed() };
         hints.ai_family = family;
         hints.ai_socktype = socktype;
         hints.ai_protocol = protocol;
         hints.ai_flags = flags;
 
-        let c_host = host
-            .as_ref()
-            .map(|h| CString::new(h.as_str()).ok())
-            .flatten();
-        let c_port = port
-            .as_ref()
-            .map(|p| CString::new(p.as_str()).ok())
-            .flatten();
-
         let host_ptr = c_host.as_ref().map_or(ptr::null(), |s| s.as_ptr());
         let port_ptr = c_port.as_ref().map_or(ptr::null(), |s| s.as_ptr());
 
         let mut res: *mut libc::addrinfo = ptr::null_mut();
-        let ret = libc::getaddrinfo(host_ptr, port_ptr, &hints, &mut res);
+        let ret = unsafe { libc::getaddrinfo(host_ptr, port_ptr, &hints, &mut res) };
+        // errno belongs to this thread and this moment
+        let os_error = (ret == libc::EAI_SYSTEM).then(std::io::Error::last_os_error);
+        (ret, AddrInfoList(res), os_error)
+    });
 
+    unsafe {
         if ret != 0 {
-            let error_msg = if ret == libc::EAI_SYSTEM {
-                format!("getaddrinfo failed: {}", std::io::Error::last_os_error())
-            } else {
-                let err_str = libc::gai_strerror(ret);
-                let c_str = CStr::from_ptr(err_str);
-                format!("getaddrinfo failed: {}", c_str.to_string_lossy())
-            };
-            return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(error_msg));
+            return Err(lookup_error(py, ret, os_error));
         }
 
         // Use C API to build the result list - avoids dozens of PyO3 wrapper calls
         let py_list = ffi_utils::list_new(0);
-        let mut current = res;
+        let mut current = list.0;
 
         while !current.is_null() {
             let info = &*current;
@@ -260,8 +782,8 @@ fn perform_getaddrinfo(
                     ffi_utils::long_from_u16(port),
                 );
                 let entry = ffi_utils::tuple5(
-                    ffi_utils::long_from_i32(fam),
-                    ffi_utils::long_from_i32(stype),
+                    socket_enum(&address_family, fam),
+                    socket_enum(&socket_kind, stype),
                     ffi_utils::long_from_i32(proto),
                     ffi_utils::string_from_str(&canonname),
                     addr_tuple,
@@ -271,18 +793,8 @@ fn perform_getaddrinfo(
                 pyo3::ffi::Py_DECREF(entry);
             } else if info.ai_family == libc::AF_INET6 {
                 let addr = &*(info.ai_addr as *const libc::sockaddr_in6);
-                let ip_bytes = addr.sin6_addr.s6_addr;
-                let ip = format!(
-                    "{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}",
-                    u16::from_be_bytes([ip_bytes[0], ip_bytes[1]]),
-                    u16::from_be_bytes([ip_bytes[2], ip_bytes[3]]),
-                    u16::from_be_bytes([ip_bytes[4], ip_bytes[5]]),
-                    u16::from_be_bytes([ip_bytes[6], ip_bytes[7]]),
-                    u16::from_be_bytes([ip_bytes[8], ip_bytes[9]]),
-                    u16::from_be_bytes([ip_bytes[10], ip_bytes[11]]),
-                    u16::from_be_bytes([ip_bytes[12], ip_bytes[13]]),
-                    u16::from_be_bytes([ip_bytes[14], ip_bytes[15]]),
-                );
+                // Compressed like inet_ntop, so "::1" rather than "0:0:0:0:0:0:0:1"
+                let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr).to_string();
                 let port = u16::from_be(addr.sin6_port);
                 let flowinfo = addr.sin6_flowinfo;
                 let scope_id = addr.sin6_scope_id;
@@ -294,8 +806,8 @@ fn perform_getaddrinfo(
                     ffi_utils::long_from_u32(scope_id),
                 );
                 let entry = ffi_utils::tuple5(
-                    ffi_utils::long_from_i32(fam),
-                    ffi_utils::long_from_i32(stype),
+                    socket_enum(&address_family, fam),
+                    socket_enum(&socket_kind, stype),
                     ffi_utils::long_from_i32(proto),
                     ffi_utils::string_from_str(&canonname),
                     addr_tuple,
@@ -308,70 +820,45 @@ fn perform_getaddrinfo(
             current = info.ai_next;
         }
 
-        libc::freeaddrinfo(res);
-
         Ok(pyo3::Bound::from_owned_ptr(py, py_list).unbind())
     }
 }
 
 #[cfg(unix)]
-fn perform_getnameinfo(py: Python<'_>, addr: &str, port: u16, flags: i32) -> PyResult<Py<PyAny>> {
-    let ip_addr: IpAddr = addr.parse().map_err(|e| {
-        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid IP address: {}", e))
-    })?;
-
-    let sock_addr = SocketAddr::new(ip_addr, port);
+fn perform_getnameinfo(
+    py: Python<'_>,
+    sock_addr: SocketAddr,
+    flags: i32,
+    stall: Option<Duration>,
+) -> PyResult<Py<PyAny>> {
+    let sock_addr = socket2::SockAddr::from(sock_addr);
 
-    unsafe {
+    // Reverse lookups can hang on DNS too: run them without the GIL
+    let (ret, host, serv, os_error) = py.detach(move || {
+        if let Some(stall) = stall {
+            std::thread::sleep(stall);
+        }
         let mut host = vec![0u8; NI_MAXHOST];
         let mut serv = vec![0u8; NI_MAXSERV];
 
-        let ret = match sock_addr {
-            SocketAddr::V4(v4_addr) => {
-                let mut sa: libc::sockaddr_in = mem::zeroed();
-                sa.sin_family = libc::AF_INET as _;
-                sa.sin_port = v4_addr.port().to_be();
-                sa.sin_addr.s_addr = u32::from_ne_bytes(v4_addr.ip().octets());
-
-                libc::getnameinfo(
-                    &sa as *const libc::sockaddr_in as *const libc::sockaddr,
-                    mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
-                    host.as_mut_ptr() as *mut libc::c_char,
-                    host.len() as libc::socklen_t,
-                    serv.as_mut_ptr() as *mut libc::c_char,
-                    serv.len() as libc::socklen_t,
-                    flags,
-                )
-            }
-            SocketAddr::V6(v6_addr) => {
-                let mut sa: libc::sockaddr_in6 = mem::zeroed();
-                sa.sin6_family = libc::AF_INET6 as _;
-                sa.sin6_port = v6_addr.port().to_be();
-                sa.sin6_addr.s6_addr = v6_addr.ip().octets();
-                sa.sin6_flowinfo = v6_addr.flowinfo();
-                sa.sin6_scope_id = v6_addr.scope_id();
-
-                libc::getnameinfo(
-                    &sa as *const libc::sockaddr_in6 as *const libc::sockaddr,
-                    mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t,
-                    host.as_mut_ptr() as *mut libc::c_char,
-                    host.len() as libc::socklen_t,
-                    serv.as_mut_ptr() as *mut libc::c_char,
-                    serv.len() as libc::socklen_t,
-                    flags,
-                )
-            }
+        let ret = unsafe {
+            libc::getnameinfo(
+                sock_addr.as_ptr() as *const libc::sockaddr,
+                sock_addr.len(),
+                host.as_mut_ptr() as *mut libc::c_char,
+                host.len() as libc::socklen_t,
+                serv.as_mut_ptr() as *mut libc::c_char,
+                serv.len() as libc::socklen_t,
+                flags,
+            )
         };
+        let os_error = (ret == libc::EAI_SYSTEM).then(std::io::Error::last_os_error);
+        (ret, host, serv, os_error)
+    });
 
+    unsafe {
         if ret != 0 {
-            let error_msg = if ret == libc::EAI_SYSTEM {
-                format!("getnameinfo failed: {}", std::io::Error::last_os_error())
-            } else {
-                let err_str = libc::gai_strerror(ret);
-                let c_str = CStr::from_ptr(err_str);
-                format!("getnameinfo failed: {}", c_str.to_string_lossy())
-            };
-            return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(error_msg));
+            return Err(lookup_error(py, ret, os_error));
         }
 
         let hostname = CStr::from_ptr(host.as_ptr() as *const libc::c_char)
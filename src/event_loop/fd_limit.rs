@@ -0,0 +1,82 @@
+use pyo3::prelude::*;
+use std::io;
+use std::os::fd::{FromRawFd, OwnedFd, RawFd};
+
+use crate::event_loop::VeloxLoop;
+
+/// The process (EMFILE) or the system (ENFILE) is out of file descriptors
+pub(crate) fn is_fd_exhaustion(err: &io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(libc::EMFILE | libc::ENFILE))
+}
+
+/// The reserved fd; `None` when even that cannot be opened
+pub(super) fn open_spare_fd() -> Option<std::fs::File> {
+    std::fs::File::open("/dev/null").ok()
+}
+
+impl VeloxLoop {
+    /// `(registered, soft_limit)`: fds with a reader or writer on this loop,
+    /// and RLIMIT_NOFILE's soft limit (`u64::MAX` when unlimited)
+    pub(crate) fn fd_usage(&self) -> (usize, u64) {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        let soft = if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } == 0 {
+            limit.rlim_cur
+        } else {
+            u64::MAX
+        };
+        (self.handles.borrow().fd_count(), soft)
+    }
+
+    /// `err` from `op` as an OSError. Running out of fds says which operation
+    /// hit it and how many of them the loop itself holds.
+    pub(crate) fn fd_error(&self, op: &str, err: io::Error) -> PyErr {
+        if !is_fd_exhaustion(&err) {
+            return crate::utils::os_error_to_pyerr(err);
+        }
+        let (registered, soft_limit) = self.fd_usage();
+        let errno = err.raw_os_error().unwrap_or(libc::EMFILE);
+        PyErr::new::<pyo3::exceptions::PyOSError, _>((
+            errno,
+            format!(
+                "{op} failed: {err}; {registered} fds are registered with the event loop \
+                 and the RLIMIT_NOFILE soft limit is {soft_limit}"
+            ),
+        ))
+    }
+
+    /// Accept one pending connection on `listener` and close it at once, using
+    /// the spare fd as room. Lets a listener that hit EMFILE drain its backlog
+    /// instead of waking up again and again for the same connection.
+    pub(crate) fn shed_connection(&self, listener: RawFd) -> Shed {
+        let mut spare = self.spare_fd.borrow_mut();
+        if spare.take().is_none() {
+            return Shed::NoSpare;
+        }
+        let conn = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+        let shed = if conn >= 0 {
+            drop(unsafe { OwnedFd::from_raw_fd(conn) });
+            Shed::Dropped
+        } else if is_fd_exhaustion(&io::Error::last_os_error()) {
+            // Someone else took the freed fd first
+            Shed::NoSpare
+        } else {
+            Shed::Drained
+        };
+        // Failing to reopen means the next shed pauses the listener instead
+        *spare = open_spare_fd();
+        shed
+    }
+}
+
+/// What `shed_connection` did
+pub(crate) enum Shed {
+    /// Closed one pending connection; there may be more
+    Dropped,
+    /// Nothing was pending
+    Drained,
+    /// No spare fd to make room with
+    NoSpare,
+}
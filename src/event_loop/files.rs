@@ -0,0 +1,414 @@
+//! Async file I/O: `open_file()` and the `AsyncFile` it returns. Each call is
+//! one io_uring operation (OpenAt, Read, Write, Fsync or Close) whose CQE
+//! settles a future; where the ring lacks those opcodes the same calls run in
+//! the executor instead.
+
+use bytes::BytesMut;
+use pyo3::IntoPyObjectExt;
+use pyo3::buffer::PyBuffer;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyTuple};
+use std::ffi::{CStr, CString};
+use std::os::fd::RawFd;
+
+use super::VeloxLoop;
+use crate::buffer_pool::BufferPool;
+use crate::poller::IoToken;
+use crate::transports::future::PendingFuture;
+
+/// Largest transfer a single read or write asks for, as Linux caps it anyway
+const MAX_IO_BYTES: usize = 0x7fff_f000;
+
+/// What an in-flight operation keeps alive for the kernel until its CQE
+/// arrives, even if its future was cancelled meanwhile
+pub(crate) enum FileOpKind {
+    Open { path: CString, loop_: Py<VeloxLoop> },
+    Read { buf: BytesMut },
+    Write { buf: BytesMut },
+    Fsync,
+    Close,
+}
+
+/// An operation in flight and the loop future (an asyncio Future, so that
+/// cancelling the awaiting task reaches it) it settles
+pub(crate) struct FileOp {
+    future: Py<PyAny>,
+    kind: FileOpKind,
+}
+
+impl FileOp {
+    /// Resolve the future from the CQE result, or just release what the
+    /// operation held if it was cancelled
+    fn settle(self, py: Python<'_>, result: i32) -> PyResult<()> {
+        let future = self.future.bind(py);
+        if future.call_method0("done")?.is_truthy()? {
+            match self.kind {
+                // The open won the race with the cancel: nobody gets the fd
+                FileOpKind::Open { .. } if result >= 0 => unsafe {
+                    libc::close(result);
+                },
+                FileOpKind::Read { buf } | FileOpKind::Write { buf } => BufferPool::release(buf),
+                _ => {}
+            }
+            return Ok(());
+        }
+        if result < 0 {
+            let err = match &self.kind {
+                FileOpKind::Open { path, .. } => os_error(py, -result, Some(path)),
+                _ => std::io::Error::from_raw_os_error(-result).into(),
+            };
+            if let FileOpKind::Read { buf } | FileOpKind::Write { buf } = self.kind {
+                BufferPool::release(buf);
+            }
+            future.call_method1("set_exception", (err.into_value(py),))?;
+            return Ok(());
+        }
+        let value = match self.kind {
+            FileOpKind::Open { path, loop_ } => Py::new(
+                py,
+                AsyncFile {
+                    loop_,
+                    fd: result,
+                    name: path.to_string_lossy().into_owned(),
+                    native: true,
+                },
+            )?
+            .into_any(),
+            FileOpKind::Read { buf } => {
+                let data = PyBytes::new(py, &buf[..result as usize]).into_any().unbind();
+                BufferPool::release(buf);
+                data
+            }
+            FileOpKind::Write { buf } => {
+                BufferPool::release(buf);
+                result.into_py_any(py)?
+            }
+            FileOpKind::Fsync | FileOpKind::Close => py.None(),
+        };
+        future.call_method1("set_result", (value,))?;
+        Ok(())
+    }
+}
+
+/// `OSError(errno, strerror[, filename])`, which Python narrows to the
+/// matching subclass such as `FileNotFoundError`
+fn os_error(py: Python<'_>, errno: i32, path: Option<&CStr>) -> PyErr {
+    let message = crate::utils::strerror(errno);
+    match path {
+        Some(path) => {
+            let name = PyBytes::new(py, path.to_bytes());
+            let name = py
+                .import("os")
+                .and_then(|os| os.call_method1("fsdecode", (name,)))
+                .map(Bound::unbind)
+                .unwrap_or_else(|_| py.None());
+            pyo3::exceptions::PyOSError::new_err((errno, message, name))
+        }
+        None => pyo3::exceptions::PyOSError::new_err((errno, message)),
+    }
+}
+
+/// Done callback on a file operation's future: a cancelled future asks the
+/// kernel to abort the operation with AsyncCancel
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+pub(crate) struct FileOpCancel {
+    loop_: Py<VeloxLoop>,
+    token: u64,
+}
+
+#[pymethods]
+impl FileOpCancel {
+    fn __call__(&self, py: Python<'_>, future: &Bound<'_, PyAny>) -> PyResult<()> {
+        let loop_ = self.loop_.bind(py).borrow();
+        let in_flight = loop_.file_ops.borrow().contains_key(&self.token);
+        if in_flight && future.call_method0("cancelled")?.is_truthy()? {
+            loop_
+                .poller
+                .borrow_mut()
+                .request_cancel(IoToken(self.token))?;
+        }
+        Ok(())
+    }
+}
+
+/// Done callback on an executor `os.open()`: wraps the fd in an `AsyncFile`
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+pub(crate) struct OpenedInExecutor {
+    loop_: Py<VeloxLoop>,
+    inner: Py<PendingFuture>,
+    future: Py<PyAny>,
+    name: String,
+}
+
+#[pymethods]
+impl OpenedInExecutor {
+    #[pyo3(signature = (*_args))]
+    fn __call__(&self, py: Python<'_>, _args: &Bound<'_, PyTuple>) -> PyResult<()> {
+        let future = self.future.bind(py);
+        let done = future.call_method0("done")?.is_truthy()?;
+        let outcome = self.inner.bind(py).borrow().result(py);
+        match outcome {
+            Ok(fd) if done => {
+                py.import("os")?.call_method1("close", (fd,))?;
+                Ok(())
+            }
+            Ok(fd) => {
+                let file = AsyncFile {
+                    loop_: self.loop_.clone_ref(py),
+                    fd: fd.extract(py)?,
+                    name: self.name.clone(),
+                    native: false,
+                };
+                future.call_method1("set_result", (Py::new(py, file)?,))?;
+                Ok(())
+            }
+            Err(_) if done => Ok(()),
+            Err(e) => {
+                future.call_method1("set_exception", (e.into_value(py),))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A file opened with `loop.open_file()`. Reads and writes take an optional
+/// offset (the file position is used and advanced without one) and may be
+/// short, so callers loop; each returns a future.
+#[pyclass(module = "veloxloop._veloxloop")]
+pub struct AsyncFile {
+    loop_: Py<VeloxLoop>,
+    fd: RawFd,
+    name: String,
+    /// Operations go through io_uring rather than the executor
+    native: bool,
+}
+
+impl AsyncFile {
+    fn check_open(&self) -> PyResult<RawFd> {
+        if self.fd < 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "I/O operation on closed file",
+            ));
+        }
+        Ok(self.fd)
+    }
+
+    /// Run `os.<func>(*args)` in the loop's executor
+    fn in_executor<'py>(
+        &self,
+        py: Python<'py>,
+        func: &str,
+        args: Bound<'py, PyTuple>,
+    ) -> PyResult<Py<PyAny>> {
+        let func = py.import("os")?.getattr(func)?.unbind();
+        VeloxLoop::run_in_executor(self.loop_.bind(py), None, func, &args)
+    }
+}
+
+#[pymethods]
+impl AsyncFile {
+    /// Read up to `n` bytes; b'' at end of file
+    #[pyo3(signature = (n, offset=None))]
+    fn read(&self, py: Python<'_>, n: usize, offset: Option<u64>) -> PyResult<Py<PyAny>> {
+        let fd = self.check_open()?;
+        let n = n.min(MAX_IO_BYTES);
+        if !self.native {
+            return match offset {
+                Some(offset) => self.in_executor(py, "pread", (fd, n, offset).into_pyobject(py)?),
+                None => self.in_executor(py, "read", (fd, n).into_pyobject(py)?),
+            };
+        }
+        let mut buf = BufferPool::acquire_sized(n);
+        buf.resize(n, 0);
+        let loop_ = self.loop_.bind(py);
+        let future = loop_.call_method0("create_future")?;
+        let submitted = loop_.borrow().submit_async_read(fd, &mut buf, offset);
+        VeloxLoop::track_file_op(loop_, future, submitted, FileOpKind::Read { buf })
+    }
+
+    /// Write some of `data`; resolves to the number of bytes written
+    #[pyo3(signature = (data, offset=None))]
+    fn write(
+        &self,
+        py: Python<'_>,
+        data: &Bound<'_, PyAny>,
+        offset: Option<u64>,
+    ) -> PyResult<Py<PyAny>> {
+        let fd = self.check_open()?;
+        let view = PyBuffer::<u8>::get(data)?;
+        // Copied so the caller may reuse `data` while the write is in flight
+        let mut buf = BufferPool::acquire_sized(view.len_bytes());
+        buf.resize(view.len_bytes(), 0);
+        view.copy_to_slice(py, &mut buf)?;
+        buf.truncate(MAX_IO_BYTES);
+        if !self.native {
+            let data = PyBytes::new(py, &buf);
+            BufferPool::release(buf);
+            return match offset {
+                Some(offset) => {
+                    self.in_executor(py, "pwrite", (fd, data, offset).into_pyobject(py)?)
+                }
+                None => self.in_executor(py, "write", (fd, data).into_pyobject(py)?),
+            };
+        }
+        let loop_ = self.loop_.bind(py);
+        let future = loop_.call_method0("create_future")?;
+        let submitted = loop_.borrow().submit_async_write(fd, &buf, offset);
+        VeloxLoop::track_file_op(loop_, future, submitted, FileOpKind::Write { buf })
+    }
+
+    /// Flush the file's data and metadata to disk
+    fn fsync(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let fd = self.check_open()?;
+        if !self.native {
+            return self.in_executor(py, "fsync", (fd,).into_pyobject(py)?);
+        }
+        let loop_ = self.loop_.bind(py);
+        let future = loop_.call_method0("create_future")?;
+        let submitted = loop_.borrow().submit_async_fsync(fd);
+        VeloxLoop::track_file_op(loop_, future, submitted, FileOpKind::Fsync)
+    }
+
+    /// Close the file; further calls fail at once, operations already
+    /// submitted still complete
+    fn close(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let fd = self.check_open()?;
+        if !self.native {
+            let closing = self.in_executor(py, "close", (fd,).into_pyobject(py)?)?;
+            self.fd = -1;
+            return Ok(closing);
+        }
+        let loop_ = self.loop_.bind(py);
+        let future = loop_.call_method0("create_future")?;
+        let submitted = loop_.borrow().submit_async_close(fd);
+        // Only a queued close owns the fd now; otherwise drop still closes it
+        if submitted.is_ok() {
+            self.fd = -1;
+        }
+        VeloxLoop::track_file_op(loop_, future, submitted, FileOpKind::Close)
+    }
+
+    fn fileno(&self) -> PyResult<RawFd> {
+        self.check_open()
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.fd < 0
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<AsyncFile name={:?} fd={}>", self.name, self.fd)
+    }
+}
+
+/// A file dropped without `close()` still gives its fd back
+impl Drop for AsyncFile {
+    fn drop(&mut self) {
+        if self.fd >= 0 {
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+impl VeloxLoop {
+    /// Whether file operations can go through the ring rather than the executor
+    fn native_file_io(&self) -> bool {
+        let caps = self.poller.borrow().capabilities();
+        caps.has_openat && caps.has_read_write && caps.has_fsync && caps.has_close && caps.has_async_cancel
+    }
+
+    /// Open `path` (str, bytes or path-like) with `os.open` flags; resolves
+    /// to an `AsyncFile`
+    pub fn open_file(
+        slf: &Bound<'_, Self>,
+        path: &Bound<'_, PyAny>,
+        flags: i32,
+        mode: u32,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let os = py.import("os")?;
+        let encoded = os.call_method1("fsencode", (path,))?;
+        let name = os.call_method1("fsdecode", (&encoded,))?.extract::<String>()?;
+        let path = CString::new(encoded.extract::<Vec<u8>>()?).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("embedded null byte")
+        })?;
+
+        if !slf.borrow().native_file_io() {
+            let func = os.getattr("open")?.unbind();
+            let args = (name.as_str(), flags | libc::O_CLOEXEC, mode).into_pyobject(py)?;
+            let inner = Self::run_in_executor(slf, None, func, &args)?;
+            let inner = inner.bind(py).cast::<PendingFuture>()?.clone().unbind();
+            let future = slf.call_method0("create_future")?.unbind();
+            let opened = OpenedInExecutor {
+                loop_: slf.clone().unbind(),
+                inner: inner.clone_ref(py),
+                future: future.clone_ref(py),
+                name,
+            };
+            inner
+                .bind(py)
+                .borrow()
+                .add_done_callback(py, Py::new(py, opened)?.into_any())?;
+            return Ok(future);
+        }
+
+        let future = slf.call_method0("create_future")?;
+        let submitted = slf.borrow().submit_async_openat(&path, flags, mode);
+        let kind = FileOpKind::Open {
+            path,
+            loop_: slf.clone().unbind(),
+        };
+        Self::track_file_op(slf, future, submitted, kind)
+    }
+
+    /// Remember a submitted operation until its CQE and return `future`,
+    /// which the CQE settles
+    fn track_file_op<'py>(
+        slf: &Bound<'py, Self>,
+        future: Bound<'py, PyAny>,
+        submitted: PyResult<IoToken>,
+        kind: FileOpKind,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let token = match submitted {
+            Ok(token) => token,
+            Err(e) => {
+                if let FileOpKind::Read { buf } | FileOpKind::Write { buf } = kind {
+                    BufferPool::release(buf);
+                }
+                return Err(e);
+            }
+        };
+        slf.borrow().file_ops.borrow_mut().insert(
+            token.0,
+            FileOp {
+                future: future.clone().unbind(),
+                kind,
+            },
+        );
+        let cancel = FileOpCancel {
+            loop_: slf.clone().unbind(),
+            token: token.0,
+        };
+        future.call_method1("add_done_callback", (Py::new(py, cancel)?,))?;
+        Ok(future.unbind())
+    }
+
+    /// Settle the file operations whose CQEs the last poll collected
+    pub(crate) fn settle_file_ops(&self, py: Python<'_>) -> PyResult<()> {
+        let done = self.poller.borrow_mut().take_completions();
+        for (token, result) in done {
+            let op = self.file_ops.borrow_mut().remove(&token.0);
+            if let Some(op) = op {
+                op.settle(py, result)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::event_loop::VeloxLoop;
+use crate::event_loop::{ExceptionContext, VeloxLoop};
 use crate::handles::IoCallback;
 use crate::poller::PollerEvent;
 use pyo3::prelude::*;
@@ -8,6 +8,20 @@ use std::sync::Arc;
 #[cfg(target_os = "linux")]
 use crate::poller::IoToken;
 
+/// A sock_recv()/sock_sendall() cancelled while it waited. Its handle is gone
+/// but its poll stays armed for `SOCK_OP_PARK_ITERATIONS` loop iterations, so
+/// the same operation retried on the same socket meanwhile (a timeout loop)
+/// only swaps in a new handle
+#[cfg(target_os = "linux")]
+pub(crate) struct ParkedSockOp {
+    /// The socket object: an fd closed and reused by another socket must
+    /// not inherit a poll that watches the old file
+    sock: Py<PyAny>,
+    reader: bool,
+    /// End-of-iteration purges left before the poll is cancelled
+    ttl: u8,
+}
+
 impl VeloxLoop {
     pub fn add_reader_native(
         &self,
@@ -20,20 +34,23 @@ impl VeloxLoop {
     pub(crate) fn add_reader_internal(&self, fd: RawFd, callback: IoCallback) -> PyResult<()> {
         // Track I/O operation
         self.track_io_operation();
-        
-        let mut handles = self.handles.borrow_mut();
+        #[cfg(target_os = "linux")]
+        self.unpark_sock_op(fd);
+
+        let mut handles = self.handles_mut()?;
         let (reader_exists, writer_exists) = handles.get_states(fd);
 
         // Add or modify
         handles.add_reader(fd, callback);
 
-        // Use PollerEvent::new for combined readable + writable interest
-        let ev = PollerEvent::new(fd as usize, true, writer_exists);
+        // Combined readable + writable interest (none for an error monitor)
+        let (readable, writable) = handles.interest(fd).unwrap_or_default();
+        let ev = PollerEvent::new(readable, writable);
 
         if reader_exists || writer_exists {
-            self.poller.borrow_mut().modify(fd, ev)?;
+            self.poller_mut()?.modify(fd, ev)?;
         } else {
-            self.poller.borrow_mut().register(fd, ev)?;
+            self.poller_mut()?.register(fd, ev)?;
         }
         Ok(())
     }
@@ -45,16 +62,21 @@ impl VeloxLoop {
         fd: RawFd,
         callback: Arc<dyn Fn(Python<'_>) -> PyResult<()> + Send + Sync>,
     ) -> PyResult<()> {
-        let mut handles = self.handles.borrow_mut();
+        let mut handles = self.handles_mut()?;
         handles.add_reader(fd, IoCallback::Native(callback));
+        // A writer already registered keeps its interest
+        let (_, writable) = handles.interest(fd).unwrap_or_default();
         drop(handles);
 
-        let ev = PollerEvent::readable(fd as usize);
+        let ev = PollerEvent {
+            writable,
+            ..PollerEvent::readable()
+        };
 
         // Check if this FD is in the disabled-oneshot set
         let in_oneshot_set = self.oneshot_disabled.borrow_mut().remove(&fd);
 
-        let mut poller = self.poller.borrow_mut();
+        let mut poller = self.poller_mut()?;
         if in_oneshot_set {
             // FD is registered but disabled - rearm with MOD (1 syscall)
             if let Err(e) = poller.rearm_oneshot(fd, ev) {
@@ -86,11 +108,130 @@ impl VeloxLoop {
     pub fn cleanup_oneshot(&self, fd: RawFd) -> PyResult<()> {
         if self.oneshot_disabled.borrow_mut().remove(&fd) {
             // FD was in disabled state - need to delete it
-            self.poller.borrow_mut().delete(fd)?;
+            self.poller_mut()?.delete(fd)?;
+        }
+        Ok(())
+    }
+
+    /// Register the callback of a sock_recv() (`reader`) or sock_sendall()
+    /// waiting on `sock`'s `fd`. A poll parked by the same operation on the
+    /// same socket is taken over as is, with no poller operation at all.
+    /// Returns the handle's generation for `park_sock_op`
+    #[cfg(target_os = "linux")]
+    pub(crate) fn add_sock_op(
+        &self,
+        fd: RawFd,
+        sock: &Py<PyAny>,
+        reader: bool,
+        callback: Arc<dyn Fn(Python<'_>) -> PyResult<()> + Send + Sync>,
+    ) -> PyResult<u64> {
+        let parked = self.parked_sock_ops.borrow_mut().remove(&fd);
+        if let Some(parked) = parked
+            && parked.reader == reader
+            && parked.sock.is(sock)
+            && self.poller_mut()?.is_armed(fd)
+        {
+            let mut handles = self.handles_mut()?;
+            if handles.interest(fd).is_none() {
+                if reader {
+                    handles.add_reader(fd, IoCallback::Native(callback));
+                } else {
+                    handles.add_writer(fd, IoCallback::Native(callback));
+                }
+                self.sock_op_reuses.set(self.sock_op_reuses.get() + 1);
+                return Ok(handles.generation(fd, reader).unwrap_or_default());
+            }
+        }
+        if reader {
+            self.add_reader_oneshot(fd, callback)?;
+        } else {
+            self.add_writer_native(fd, callback)?;
+        }
+        Ok(self.handles_mut()?.generation(fd, reader).unwrap_or_default())
+    }
+
+    /// A sock_* operation's future is done. If its handle (`generation`) is
+    /// still registered the operation never ran, so it was cancelled: the
+    /// handle goes, and the poll stays armed for a retry on `sock` unless the
+    /// fd has another handler still
+    #[cfg(target_os = "linux")]
+    pub(crate) fn park_sock_op(
+        &self,
+        py: Python<'_>,
+        fd: RawFd,
+        reader: bool,
+        generation: u64,
+        sock: Py<PyAny>,
+    ) -> PyResult<()> {
+        let mut handles = self.handles_mut()?;
+        if handles.generation(fd, reader) != Some(generation) {
+            return Ok(());
+        }
+        if handles.generation(fd, !reader).is_some() {
+            drop(handles);
+            if reader {
+                self.remove_reader(py, fd)?;
+            } else {
+                self.remove_writer(py, fd)?;
+            }
+            return Ok(());
+        }
+        if reader {
+            handles.remove_reader(fd);
+        } else {
+            handles.remove_writer(fd);
+        }
+        drop(handles);
+        self.oneshot_disabled.borrow_mut().remove(&fd);
+        let parked = ParkedSockOp {
+            sock,
+            reader,
+            ttl: crate::constants::SOCK_OP_PARK_ITERATIONS,
+        };
+        // Dropped outside the borrow: the socket it held may be finalized
+        let replaced = self.parked_sock_ops.borrow_mut().insert(fd, parked);
+        drop(replaced);
+        Ok(())
+    }
+
+    /// Forget the poll parked for `fd`, if any, when something else registers
+    /// it or the user removes its handlers; their poller calls replace the poll
+    #[cfg(target_os = "linux")]
+    fn unpark_sock_op(&self, fd: RawFd) {
+        let parked = self.parked_sock_ops.borrow_mut().remove(&fd);
+        drop(parked);
+    }
+
+    /// Cancel the poll parked for `fd`, if any, unless a handler has been
+    /// registered since
+    #[cfg(target_os = "linux")]
+    fn drop_parked_sock_op(&self, fd: RawFd) -> PyResult<()> {
+        let parked = self.parked_sock_ops.borrow_mut().remove(&fd);
+        if parked.is_some() && self.handles_mut()?.interest(fd).is_none() {
+            self.poller_mut()?.delete(fd)?;
         }
         Ok(())
     }
 
+    /// End of an iteration: cancel the parked polls nothing took over in time.
+    /// Besides the SQE, an armed poll pins the file: a socket closed meanwhile
+    /// only really closes once its poll is gone
+    #[cfg(target_os = "linux")]
+    pub(crate) fn purge_parked_sock_ops(&self) {
+        let expired: Vec<RawFd> = self
+            .parked_sock_ops
+            .borrow_mut()
+            .iter_mut()
+            .filter_map(|(&fd, op)| {
+                op.ttl = op.ttl.saturating_sub(1);
+                (op.ttl == 0).then_some(fd)
+            })
+            .collect();
+        for fd in expired {
+            let _ = self.drop_parked_sock_op(fd);
+        }
+    }
+
     pub fn add_writer_native(
         &self,
         fd: RawFd,
@@ -102,20 +243,23 @@ impl VeloxLoop {
     pub(crate) fn add_writer_internal(&self, fd: RawFd, callback: IoCallback) -> PyResult<()> {
         // Track I/O operation
         self.track_io_operation();
-        
-        let mut handles = self.handles.borrow_mut();
+        #[cfg(target_os = "linux")]
+        self.unpark_sock_op(fd);
+
+        let mut handles = self.handles_mut()?;
         let (reader_exists, writer_exists) = handles.get_states(fd);
 
         // Add or modify
         handles.add_writer(fd, callback);
 
         // Use PollerEvent::new for combined readable + writable interest
-        let ev = PollerEvent::new(fd as usize, reader_exists, true);
+        let (readable, writable) = handles.interest(fd).unwrap_or_default();
+        let ev = PollerEvent::new(readable, writable);
 
         if reader_exists || writer_exists {
-            self.poller.borrow_mut().modify(fd, ev)?;
+            self.poller_mut()?.modify(fd, ev)?;
         } else {
-            self.poller.borrow_mut().register(fd, ev)?;
+            self.poller_mut()?.register(fd, ev)?;
         }
         Ok(())
     }
@@ -128,6 +272,24 @@ impl VeloxLoop {
         self.add_reader_internal(fd, IoCallback::TcpRead(transport))
     }
 
+    pub fn add_udp_reader(
+        &self,
+        fd: RawFd,
+        transport: Py<crate::transports::udp::UdpTransport>,
+    ) -> PyResult<()> {
+        self.add_reader_internal(fd, IoCallback::UdpRead(transport))
+    }
+
+    /// Watch a paused transport's socket for POLLERR/POLLHUP only, in the
+    /// reader slot; `add_tcp_reader` replaces it when reading resumes
+    pub fn add_error_monitor(
+        &self,
+        fd: RawFd,
+        transport: Py<crate::transports::tcp::TcpTransport>,
+    ) -> PyResult<()> {
+        self.add_reader_internal(fd, IoCallback::TcpError(transport))
+    }
+
     pub fn add_tcp_writer(
         &self,
         fd: RawFd,
@@ -135,25 +297,89 @@ impl VeloxLoop {
     ) -> PyResult<()> {
         self.add_writer_internal(fd, IoCallback::TcpWrite(transport))
     }
+
+    /// Register a transport for the end-of-iteration coalesced write flush
+    pub(crate) fn add_coalesced_writer(&self, transport: Py<crate::transports::tcp::TcpTransport>) {
+        self.coalesced_writers.borrow_mut().push(transport);
+    }
+
+    /// Flush every transport holding coalesced writes (runs at the end of `_run_once`)
+    pub(crate) fn flush_coalesced_writers(&self, py: Python<'_>) -> PyResult<()> {
+        if self.coalesced_writers.borrow().is_empty() {
+            return Ok(());
+        }
+
+        let pending = std::mem::take(&mut *self.coalesced_writers.borrow_mut());
+        for transport in pending {
+            if let Err(e) = crate::transports::tcp::TcpTransport::_flush_coalesced(transport.bind(py)) {
+                ExceptionContext::new("Exception flushing coalesced writes")
+                    .exception(e.value(py))
+                    .transport(transport.bind(py))
+                    .report(py, self)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl VeloxLoop {
+    /// `loop.add_reader()`/`add_writer()`: register `callback` as a user
+    /// handler. Like asyncio refusing fds owned by a transport, a reader or
+    /// writer the loop registered itself can't be replaced from outside
+    pub fn add_user_handler(
+        &self,
+        _py: Python<'_>,
+        fd: RawFd,
+        reader: bool,
+        callback: Py<PyAny>,
+    ) -> PyResult<()> {
+        if self.handles_mut()?.owner(fd, reader) == Some(false) {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+                "File descriptor {} is used by the event loop",
+                fd
+            )));
+        }
+        if reader {
+            self.add_reader_internal(fd, IoCallback::Python(callback))?;
+        } else {
+            self.add_writer_internal(fd, IoCallback::Python(callback))?;
+        }
+        self.handles_mut()?.mark_user(fd, reader);
+        Ok(())
+    }
+
+    /// `loop.remove_reader()`/`remove_writer()`: false unless `fd` has a user
+    /// handler, so servers and transports never lose theirs
+    pub fn remove_user_handler(&self, py: Python<'_>, fd: RawFd, reader: bool) -> PyResult<bool> {
+        // A poll parked by a cancelled sock_* call goes too
+        #[cfg(target_os = "linux")]
+        self.drop_parked_sock_op(fd)?;
+        if self.handles_mut()?.owner(fd, reader) != Some(true) {
+            return Ok(false);
+        }
+        if reader {
+            self.remove_reader(py, fd)
+        } else {
+            self.remove_writer(py, fd)
+        }
+    }
+
     pub fn add_reader(&self, _py: Python<'_>, fd: RawFd, callback: Py<PyAny>) -> PyResult<()> {
         self.add_reader_internal(fd, IoCallback::Python(callback))
     }
 
     pub fn remove_reader(&self, _py: Python<'_>, fd: RawFd) -> PyResult<bool> {
-        let mut handles = self.handles.borrow_mut();
+        let mut handles = self.handles_mut()?;
         if handles.remove_reader(fd) {
             let writer_exists = handles.get_writer(fd).is_some();
 
             if writer_exists {
                 // Downgrade to W only
-                let ev = PollerEvent::writable(fd as usize);
-                self.poller.borrow_mut().modify(fd, ev)?;
+                let ev = PollerEvent::writable();
+                self.poller_mut()?.modify(fd, ev)?;
             } else {
                 // Remove
-                self.poller.borrow_mut().delete(fd)?;
+                self.poller_mut()?.delete(fd)?;
             }
             #[cfg(target_os = "linux")]
             self.oneshot_disabled.borrow_mut().remove(&fd);
@@ -164,22 +390,26 @@ impl VeloxLoop {
         }
     }
 
+    /// Dispatch `fd` ahead of ordinary ready fds each tick (or stop doing so);
+    /// false if nothing is registered for it
+    pub fn set_fd_priority(&self, fd: RawFd, high: bool) -> PyResult<bool> {
+        Ok(self.handles_mut()?.set_priority(fd, high))
+    }
+
     pub fn add_writer(&self, _py: Python<'_>, fd: RawFd, callback: Py<PyAny>) -> PyResult<()> {
         self.add_writer_internal(fd, IoCallback::Python(callback))
     }
 
     pub fn remove_writer(&self, _py: Python<'_>, fd: RawFd) -> PyResult<bool> {
-        let mut handles = self.handles.borrow_mut();
+        let mut handles = self.handles_mut()?;
         if handles.remove_writer(fd) {
-            let reader_exists = handles.get_reader(fd).is_some();
-
-            if reader_exists {
-                // Downgrade to R only
-                let ev = PollerEvent::readable(fd as usize);
-                self.poller.borrow_mut().modify(fd, ev)?;
+            if let Some((readable, _)) = handles.interest(fd) {
+                // Downgrade to R only (or just errors for a monitor)
+                let ev = PollerEvent::new(readable, false);
+                self.poller_mut()?.modify(fd, ev)?;
             } else {
                 // Remove
-                self.poller.borrow_mut().delete(fd)?;
+                self.poller_mut()?.delete(fd)?;
             }
             #[cfg(target_os = "linux")]
             self.oneshot_disabled.borrow_mut().remove(&fd);
@@ -282,6 +512,29 @@ impl VeloxLoop {
             .map_err(|e| e.into())
     }
 
+    /// Submit an async openat via io-uring; `path` must outlive the operation
+    #[inline]
+    pub fn submit_async_openat(
+        &self,
+        path: &std::ffi::CStr,
+        flags: i32,
+        mode: u32,
+    ) -> PyResult<IoToken> {
+        self.poller
+            .borrow_mut()
+            .submit_openat(path, flags, mode)
+            .map_err(|e| e.into())
+    }
+
+    /// Submit an async fsync via io-uring
+    #[inline]
+    pub fn submit_async_fsync(&self, fd: RawFd) -> PyResult<IoToken> {
+        self.poller
+            .borrow_mut()
+            .submit_fsync(fd)
+            .map_err(|e| e.into())
+    }
+
     /// Submit an async sendfile/splice operation via io-uring
     /// Uses kernel-side zero-copy file transfer
     #[inline]
@@ -306,4 +559,89 @@ impl VeloxLoop {
             .cancel_operation(token)
             .map_err(|e| e.into())
     }
-}
\ No newline at end of file
+}
+
+/// asyncio's `_fileobj_to_fd`: an int or an object with `fileno()`, never negative.
+/// Kept as i64 so lookups of out-of-range fds can simply miss.
+pub(crate) fn fileobj_to_fd(fileobj: &Bound<'_, PyAny>) -> PyResult<i64> {
+    let fd = if fileobj.is_instance_of::<pyo3::types::PyInt>() {
+        fileobj.extract::<i64>()?
+    } else {
+        fileobj
+            .call_method0("fileno")
+            .and_then(|fd| fd.extract::<i64>())
+            .map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid file object: {}",
+                    fileobj.repr().map(|r| r.to_string()).unwrap_or_default()
+                ))
+            })?
+    };
+    if fd < 0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Invalid file descriptor: {}",
+            fd
+        )));
+    }
+    Ok(fd)
+}
+
+/// Narrow an fd for registration, rejecting ones that aren't open as
+/// epoll_ctl would (io_uring would only report it on the next poll)
+pub(crate) fn checked_open_fd(fd: i64) -> PyResult<RawFd> {
+    let fd = RawFd::try_from(fd).map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyOverflowError, _>(format!(
+            "file descriptor {} is out of range",
+            fd
+        ))
+    })?;
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(fd)
+}
+
+/// What an fd refers to, which decides how readiness on it can be watched
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FdKind {
+    /// A pipe or named FIFO; the writer closing is a hangup, read as EOF
+    Fifo,
+    /// A tty, pty or other character device; short reads are normal
+    CharDevice,
+    /// Always readable and writable, so polling it means nothing
+    RegularFile,
+    Socket,
+    Other,
+}
+
+/// fstat(2) `fd` for its kind
+pub(crate) fn fd_kind(fd: RawFd) -> std::io::Result<FdKind> {
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(match stat.st_mode & libc::S_IFMT {
+        libc::S_IFIFO => FdKind::Fifo,
+        libc::S_IFCHR => FdKind::CharDevice,
+        libc::S_IFREG => FdKind::RegularFile,
+        libc::S_IFSOCK => FdKind::Socket,
+        _ => FdKind::Other,
+    })
+}
+
+/// `checked_open_fd` for add_reader()/add_writer(), also refusing regular
+/// files with the EPERM epoll_ctl gives them, since a poll on one is always
+/// ready at once
+pub(crate) fn checked_pollable_fd(fd: i64) -> PyResult<RawFd> {
+    let fd = checked_open_fd(fd)?;
+    if fd_kind(fd)? == FdKind::RegularFile {
+        return Err(PyErr::new::<pyo3::exceptions::PyPermissionError, _>((
+            libc::EPERM,
+            format!(
+                "File descriptor {fd} is a regular file, which can't be watched for \
+                 readiness; use loop.open_file() for asynchronous file I/O"
+            ),
+        )));
+    }
+    Ok(fd)
+}
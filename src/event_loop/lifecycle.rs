@@ -1,35 +1,44 @@
-use crate::constants::get_asyncio;
-use crate::event_loop::VeloxLoop;
-use crate::event_loop::poll::PlatformEvents;
+use crate::constants::DEBUG_STACK_DEPTH;
+use crate::event_loop::{ExceptionContext, HotState, VeloxLoop};
+use crate::transports::factory::LoopTransportFactory;
 use crate::utils::{VeloxError, VeloxResult};
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyTuple};
+use std::os::fd::RawFd;
+use std::time::{Duration, Instant};
 
 impl VeloxLoop {
     pub fn run_forever(&self, py: Python<'_>) -> VeloxResult<()> {
-        // Set state using both RefCell (for compatibility) and atomic (for hot paths)
-        {
-            let mut state = self.state.borrow_mut();
-            state.running = true;
-            state.stopped = false;
+        self.check_closed()?;
+        if self.atomic_state.is_running() {
+            return Err(VeloxError::RuntimeError(
+                "This event loop is already running".to_string(),
+            ));
         }
+        self.state.borrow_mut().stopped = false;
         self.atomic_state.set_running(true);
         self.atomic_state.set_stopped(false);
 
-        let mut events = PlatformEvents::new();
+        let result = self.run_loop(py);
+
+        // Cleared on errors too (e.g. KeyboardInterrupt), or the loop could never run again
+        self.atomic_state.set_running(false);
+        result
+    }
 
+    fn run_loop(&self, py: Python<'_>) -> VeloxResult<()> {
         loop {
             // Use atomic state for hot path check (lock-free)
             if !self.atomic_state.is_running() || self.atomic_state.is_stopped() {
-                break;
+                return Ok(());
             }
 
-            self._run_once(py, &mut events)?;
+            self._run_once(py)?;
 
             // Check stopped after run_once (callbacks may have called stop())
             // Use atomic for lock-free check
             if self.atomic_state.is_stopped() {
-                break;
+                return Ok(());
             }
 
             // Check Python signals (Ctrl+C)
@@ -37,19 +46,81 @@ impl VeloxLoop {
                 return Err(VeloxError::Python(e));
             }
         }
+    }
 
-        self.state.borrow_mut().running = false;
-        self.atomic_state.set_running(false);
+    /// Do ahead of time what the first iterations would otherwise pay for:
+    /// fill this thread's read buffer pool, cycle a no-op through the ring,
+    /// and wake the loop once so its eventfd poll completes and is re-armed.
+    /// The backend itself is built with the loop and kept until `close()`.
+    pub fn warm_up(&self, py: Python<'_>) -> VeloxResult<()> {
+        self.check_closed()?;
+        if self.atomic_state.is_running() {
+            return Err(VeloxError::RuntimeError(
+                "warm_up() must be called before the loop runs".to_string(),
+            ));
+        }
+        crate::buffer_pool::BufferPool::prefill(
+            self.read_chunk_size.get(),
+            self.expected_connections.max(1),
+        );
+        self.waker.notify()?;
+        self.poller_mut()?.warm_up()?;
+        // Readiness collected along the way is dispatched, not lost: fd polls
+        // are oneshot
+        let mut poller = self.poller_mut()?;
+        let mut events = std::mem::take(&mut *self.event_buffer.borrow_mut());
+        let polled = poller.poll_into(Some(Duration::ZERO), &mut events);
+        drop(poller);
+        let result = polled.and_then(|()| self._process_native_events(py, &events));
+        events.clear();
+        *self.event_buffer.borrow_mut() = events;
+        result
+    }
+
+    /// Point `signal.set_wakeup_fd` at the self-pipe while the loop runs, so
+    /// a signal caught on any thread cuts the poll short and `check_signals`
+    /// sees it right away. Only done from the main thread and only when no
+    /// one else owns the wakeup fd; returns our fd when it was installed.
+    pub(crate) fn install_signal_wakeup(&self, py: Python<'_>) -> PyResult<Option<RawFd>> {
+        let threading = py.import("threading")?;
+        if !threading
+            .call_method0("current_thread")?
+            .is(&threading.call_method0("main_thread")?)
+        {
+            return Ok(None);
+        }
+        let fd = self.poller_mut()?.wakeup_fd()?;
+        let signal = py.import("signal")?;
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("warn_on_full_buffer", false)?;
+        let previous: RawFd = signal
+            .call_method("set_wakeup_fd", (fd,), Some(&kwargs))?
+            .extract()?;
+        if previous != -1 && previous != fd {
+            // Someone else's wakeup fd (e.g. another loop's signal handlers)
+            signal.call_method1("set_wakeup_fd", (previous,))?;
+            return Ok(None);
+        }
+        Ok(Some(fd))
+    }
+
+    pub(crate) fn restore_signal_wakeup(py: Python<'_>, fd: RawFd) -> PyResult<()> {
+        let signal = py.import("signal")?;
+        let current: RawFd = signal.call_method1("set_wakeup_fd", (-1,))?.extract()?;
+        if current != fd {
+            // Replaced while we ran; leave the new owner in place
+            signal.call_method1("set_wakeup_fd", (current,))?;
+        }
         Ok(())
     }
 
-    pub fn stop(&self) {
-        let mut state = self.state.borrow_mut();
-        state.stopped = true;
-        state.running = false;
+    /// Ask the loop to return after the current iteration. Like asyncio,
+    /// `is_running()` stays true until `run_forever` actually returns.
+    pub fn stop(&self) -> PyResult<()> {
+        self.state_mut()?.stopped = true;
         // Update atomic state for lock-free access
         self.atomic_state.set_stopped(true);
-        self.atomic_state.set_running(false);
+        Ok(())
     }
 
     pub fn is_running(&self) -> bool {
@@ -62,21 +133,239 @@ impl VeloxLoop {
         self.atomic_state.is_closed()
     }
 
-    pub fn get_debug(&self) -> bool {
+    /// asyncio's `_check_closed`: scheduling on a closed loop is an error, as
+    /// is any use of a loop inherited through fork(). Reads only atomics, so
+    /// any thread may call it
+    pub fn check_closed(&self) -> VeloxResult<()> {
+        self.owner.check()?;
+        if self.atomic_state.is_closed() {
+            return Err(VeloxError::RuntimeError("Event loop is closed".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The one check every debug-mode feature goes through
+    #[inline]
+    pub(crate) fn debug_enabled(&self) -> bool {
         self.state.borrow().debug
     }
 
-    pub fn set_debug(&self, enabled: bool) {
-        self.state.borrow_mut().debug = enabled;
+    pub fn get_debug(&self) -> bool {
+        self.debug_enabled()
+    }
+
+    /// Takes effect on coroutine origin tracking at once if the loop is
+    /// running, else the next time it runs
+    pub fn set_debug(&self, py: Python<'_>, enabled: bool) -> PyResult<()> {
+        self.state_mut()?.debug = enabled;
+        if self.atomic_state.is_running() {
+            self.set_coroutine_origin_tracking(py, enabled)?;
+        }
+        Ok(())
     }
 
-    pub fn close(&self) {
-        let mut state = self.state.borrow_mut();
-        state.closed = true;
-        state.running = false;
-        // Update atomic state
+    /// While a debug-mode loop runs, coroutines record where they were
+    /// created, as asyncio does, so "never awaited" warnings point at the
+    /// caller. Turning it off puts the previous tracking depth back.
+    pub(crate) fn set_coroutine_origin_tracking(
+        &self,
+        py: Python<'_>,
+        enabled: bool,
+    ) -> PyResult<()> {
+        let sys = py.import("sys")?;
+        match (enabled, self.saved_origin_depth.get()) {
+            (true, None) => {
+                let depth = sys
+                    .call_method0("get_coroutine_origin_tracking_depth")?
+                    .extract()?;
+                sys.call_method1("set_coroutine_origin_tracking_depth", (DEBUG_STACK_DEPTH,))?;
+                self.saved_origin_depth.set(Some(depth));
+            }
+            (false, Some(depth)) => {
+                sys.call_method1("set_coroutine_origin_tracking_depth", (depth,))?;
+                self.saved_origin_depth.set(None);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Close the loop and tear its backend down, see `LoopPoller::shutdown`
+    pub fn close(&self) -> VeloxResult<()> {
+        if self.atomic_state.is_running() {
+            return Err(VeloxError::RuntimeError(
+                "Cannot close a running event loop".to_string(),
+            ));
+        }
         self.atomic_state.set_closed(true);
-        self.atomic_state.set_running(false);
+        // Wakeups first, so none is written while the backend goes away
+        self.waker.shutdown();
+        if let Ok(mut poller) = self.poller.try_borrow_mut() {
+            poller.shutdown();
+        }
+        *self.spare_fd.borrow_mut() = None;
+        Ok(())
+    }
+
+    /// asyncio.run()'s teardown for a loop that isn't running: cancel every
+    /// task and run until they finish, then shutdown_asyncgens(),
+    /// shutdown_default_executor() and close(). Tasks still pending after
+    /// `timeout` seconds are abandoned and reported to the exception handler.
+    pub fn shutdown(slf: &Bound<'_, Self>, timeout: Option<f64>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.borrow();
+        if this.is_running() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Cannot call shutdown() from a running event loop",
+            ));
+        }
+        this.check_closed()?;
+        let timeout = timeout
+            .map(|t| {
+                Duration::try_from_secs_f64(t).map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                        "timeout must be a non-negative number",
+                    )
+                })
+            })
+            .transpose()?;
+
+        // What run_forever() sets up, so tasks see a running loop
+        let events = py.import("asyncio.events")?;
+        let old_hooks = Self::install_asyncgen_hooks(slf)?;
+        let signal_wakeup = match this.install_signal_wakeup(py) {
+            Ok(fd) => fd,
+            Err(e) => {
+                let _ = Self::restore_asyncgen_hooks(py, old_hooks);
+                return Err(e);
+            }
+        };
+        events.call_method1("_set_running_loop", (slf,))?;
+        this.state.borrow_mut().stopped = false;
+        this.atomic_state.set_stopped(false);
+        this.atomic_state.set_running(true);
+
+        let result = this
+            .set_coroutine_origin_tracking(py, this.debug_enabled())
+            .and_then(|()| Self::run_shutdown(slf, timeout));
+
+        this.atomic_state.set_running(false);
+        let tracking = this.set_coroutine_origin_tracking(py, false);
+        let wakeup = signal_wakeup.map_or(Ok(()), |fd| Self::restore_signal_wakeup(py, fd));
+        let running = events
+            .call_method1("_set_running_loop", (py.None(),))
+            .map(drop);
+        let hooks = Self::restore_asyncgen_hooks(py, old_hooks);
+        result.and(tracking).and(wakeup).and(running).and(hooks)?;
+        Ok(this.close()?)
+    }
+
+    fn run_shutdown(slf: &Bound<'_, Self>, timeout: Option<Duration>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.borrow();
+
+        let tasks: Vec<Bound<'_, PyAny>> = py
+            .import("asyncio")?
+            .call_method1("all_tasks", (slf,))?
+            .try_iter()?
+            .collect::<PyResult<_>>()?;
+        for task in &tasks {
+            task.call_method0("cancel")?;
+        }
+
+        // A blocked poll only returns for I/O or timers, so one is set at the
+        // deadline; the stop() it calls is only there to wake the poll
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let wakeup = match timeout {
+            Some(t) => Some(this.call_later(
+                t.as_secs_f64(),
+                slf.getattr("stop")?.unbind(),
+                Vec::new(),
+                None,
+            )?),
+            None => None,
+        };
+        let mut pending = tasks.clone();
+        this.run_until(py, deadline, |_| {
+            let mut still_pending = Vec::with_capacity(pending.len());
+            for task in pending.drain(..) {
+                if !task.call_method0("done")?.is_truthy()? {
+                    still_pending.push(task);
+                }
+            }
+            pending = still_pending;
+            Ok(pending.is_empty())
+        })?;
+        if let Some(timer_id) = wakeup {
+            this._cancel_timer(timer_id)?;
+        }
+
+        for task in &tasks {
+            if !task.call_method0("done")?.is_truthy()? {
+                // Reported here, so not again when it is garbage collected
+                task.setattr("_log_destroy_pending", false)?;
+                let seconds = timeout.unwrap_or_default().as_secs_f64();
+                ExceptionContext::new(format!(
+                    "Task was abandoned by loop.shutdown() after {seconds} seconds"
+                ))
+                .with("task", task)
+                .report(py, &this)?;
+            } else if !task.call_method0("cancelled")?.is_truthy()? {
+                let exc = task.call_method0("exception")?;
+                if !exc.is_none() {
+                    ExceptionContext::new("unhandled exception during loop.shutdown()")
+                        .exception(&exc)
+                        .with("task", task)
+                        .report(py, &this)?;
+                }
+            }
+        }
+
+        let asyncgens = Self::shutdown_asyncgens(slf)?;
+        this.run_until(py, None, |py| Ok(asyncgens.bind(py).borrow().done()))?;
+        let executor = this.shutdown_default_executor(py)?;
+        this.run_until(py, None, |py| Ok(executor.bind(py).borrow().done()))?;
+        Ok(())
+    }
+
+    /// `_run_once` until `finished` says so; false when `deadline` came first
+    fn run_until(
+        &self,
+        py: Python<'_>,
+        deadline: Option<Instant>,
+        mut finished: impl FnMut(Python<'_>) -> PyResult<bool>,
+    ) -> PyResult<bool> {
+        loop {
+            if finished(py)? {
+                return Ok(true);
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                return Ok(false);
+            }
+            self._run_once(py)?;
+            py.check_signals()?;
+        }
+    }
+
+    pub fn get_slow_callback_duration(&self) -> f64 {
+        self.state.borrow().slow_callback_duration
+    }
+
+    pub fn set_slow_callback_duration(&self, seconds: f64) -> PyResult<()> {
+        self.state_mut()?.slow_callback_duration = seconds;
+        Ok(())
+    }
+
+    /// Copy of the hot state that never panics: if the cell is mutably
+    /// borrowed, `stopped` is taken from `atomic_state` instead
+    pub(crate) fn state_snapshot(&self) -> HotState {
+        match self.state.try_borrow() {
+            Ok(state) => state.clone(),
+            Err(_) => HotState {
+                stopped: self.atomic_state.is_stopped(),
+                ..HotState::default()
+            },
+        }
     }
 
     // Exception handler methods
@@ -91,7 +380,50 @@ impl VeloxLoop {
             .map(|h| h.clone_ref(py))
     }
 
+    // Transport observer methods
+    pub fn set_transport_observer(&self, callback: Option<Py<PyAny>>) {
+        *self.transport_observer.borrow_mut() = callback;
+    }
+
+    pub fn get_transport_observer(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.transport_observer
+            .borrow()
+            .as_ref()
+            .map(|o| o.clone_ref(py))
+    }
+
+    /// Deliver a transport lifecycle event to the observer, if one is set.
+    /// The event tuple is only built when someone is listening, and the
+    /// observer runs via call_soon so it can't re-enter the transport; its
+    /// exceptions end up in the exception handler like any other callback.
+    pub(crate) fn emit_transport_event<'py, F>(&self, py: Python<'py>, build: F)
+    where
+        F: FnOnce(Python<'py>) -> PyResult<Bound<'py, PyTuple>>,
+    {
+        let observer = match self.transport_observer.borrow().as_ref() {
+            Some(observer) => observer.clone_ref(py),
+            None => return,
+        };
+        match build(py) {
+            Ok(event) => self.call_soon(observer, vec![event.into_any().unbind()], None),
+            Err(e) => {
+                let _ = ExceptionContext::new("Exception while building transport event")
+                    .exception(e.value(py))
+                    .report(py, self);
+            }
+        }
+    }
+
     pub fn call_exception_handler(&self, py: Python<'_>, context: Py<PyDict>) -> PyResult<()> {
+        let sink = self
+            .exception_sink
+            .borrow()
+            .as_ref()
+            .map(|s| s.clone_ref(py));
+        if let Some(sink) = sink {
+            return sink.call1(py, (context,)).map(drop);
+        }
+
         let handler = self
             .exception_handler
             .borrow()
@@ -153,46 +485,20 @@ impl VeloxLoop {
         self.task_factory.borrow().as_ref().map(|f| f.clone_ref(py))
     }
 
-    // Async generator tracking methods
-    pub fn _track_async_generator(&self, agen: Py<PyAny>) {
-        self.async_generators.borrow_mut().push(agen);
+    /// None restores the built-in transports, a `TransportFactoryConfig` tunes
+    /// them, and any other callable is called as
+    /// `factory(kind, loop, sock_fd, protocol, extra)` for "tcp" and "udp"
+    pub fn set_transport_factory(&self, factory: Option<Bound<'_, PyAny>>) -> PyResult<()> {
+        *self.transport_factory.borrow_mut() = LoopTransportFactory::from_py(factory)?;
+        Ok(())
     }
 
-    pub fn _untrack_async_generator(&self, py: Python<'_>, agen: Py<PyAny>) {
-        self.async_generators
-            .borrow_mut()
-            .retain(|g| !g.bind(py).is(agen.bind(py)));
+    pub fn get_transport_factory(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.transport_factory.borrow().to_py(py)
     }
 
-    pub fn shutdown_asyncgens(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        let generators = {
-            let mut gen_guard = self.async_generators.borrow_mut();
-            let gens: Vec<Py<PyAny>> = gen_guard.iter().map(|g| g.clone_ref(py)).collect();
-            gen_guard.clear();
-            gens
-        };
-
-        if generators.is_empty() {
-            let future = self.create_future(py)?;
-            future.bind(py).borrow().set_result(py, py.None())?;
-            return Ok(future.into_any());
-        }
-
-        let mut close_coros = Vec::new();
-        for generator in generators {
-            if let Ok(aclose) = generator.getattr(py, "aclose") {
-                if let Ok(coro) = aclose.call0(py) {
-                    close_coros.push(coro);
-                }
-            }
-        }
-
-        let asyncio = get_asyncio(py).bind(py);
-        let gather = asyncio.getattr("gather")?;
-
-        let coros_tuple = PyTuple::new(py, &close_coros)?;
-        let close_task = gather.call1(coros_tuple)?;
-
-        Ok(close_task.unbind())
+    /// The factory to create the next transport with
+    pub(crate) fn transport_factory(&self, py: Python<'_>) -> LoopTransportFactory {
+        self.transport_factory.borrow().clone_ref(py)
     }
 }
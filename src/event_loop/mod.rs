@@ -1,29 +1,65 @@
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyTuple};
-use rustc_hash::FxHashSet;
-use std::cell::RefCell;
+use pyo3::types::{PyDict, PyTuple, PyWeakrefReference};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cell::{Cell, OnceCell, RefCell, RefMut};
 use std::os::fd::RawFd;
-use std::time::Instant;
-
-use crate::callbacks::{Callback, CallbackQueue};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::buffer_pool::{BufferPool, BufferTrim, check_read_chunk_size};
+use crate::callbacks::{Callback, CallbackQueue, ThreadsafeHandle};
+use crate::constants::{
+    DEFAULT_READ_CHUNK_SIZE, DEFAULT_SLOW_CALLBACK_DURATION, IDLE_TIMEOUT_TICK_NS,
+};
 use crate::executor::ThreadPoolExecutor;
+use crate::fork::ForkGuard;
 use crate::handles::{Handle, IoHandles};
-use crate::poller::{LoopPoller, PollerWaker};
+use crate::poller::{LoopPoller, PlatformEvent, PollerWaker};
+use crate::stats_export::{Counters, StatsExport};
+use crate::timeout_wheel::TimeoutWheel;
 use crate::timers::Timers;
-use crate::transports::future::PendingFuture;
+use crate::transports::factory::LoopTransportFactory;
+use crate::transports::future::{FuturePool, PendingFuture};
+use crate::transports::stats::LoopTraffic;
+use crate::transports::timeouts::TimedTransport;
 use crate::utils::VeloxResult;
 
+/// Starting (and smallest kept) capacities of the per-iteration buffers
+const CALLBACK_BUFFER_CAPACITY: usize = 1024;
+const PENDING_IOS_CAPACITY: usize = 128;
+const EVENT_BUFFER_CAPACITY: usize = 128;
+/// SQPOLL thread idle time when `uring_sqpoll_idle_ms` isn't given
+const DEFAULT_SQPOLL_IDLE_MS: u32 = 1000;
+
+pub(crate) use exception_context::ExceptionContext;
+use fd_limit::open_spare_fd;
+pub(crate) use fd_limit::{Shed, is_fd_exhaustion};
+
+/// An I/O event waiting for dispatch: fd, and the reader and writer handles
+/// it is for
+pub(crate) type PendingIo = (RawFd, Option<Handle>, Option<Handle>);
+
+mod asyncgens;
 mod callbacks;
+mod endpoint_kwargs;
+mod exception_context;
 mod executor;
+mod fd_limit;
+#[cfg(target_os = "linux")]
+mod files;
 mod io;
 mod lifecycle;
 mod network;
 mod poll;
+#[cfg(target_os = "linux")]
+mod subprocess;
 
 /// Atomic state flags for lock-free state checking in hot paths.
-/// These replace the RefCell<HotState> booleans for frequently checked state.
+/// `running` and `closed` live only here: other threads read them (e.g.
+/// call_soon_threadsafe's closed check), and HotState's RefCell must never be
+/// touched off the loop thread.
 /// Using atomics eliminates RefCell borrow overhead in the critical event loop.
-/// Now uses AtomicFlag from the concurrent module for lock-free operations.
 pub struct AtomicState {
     pub running: crate::concurrent::AtomicFlag,
     pub stopped: crate::concurrent::AtomicFlag,
@@ -81,35 +117,116 @@ impl AtomicState {
 #[repr(C)]
 #[derive(Clone)]
 pub struct HotState {
-    pub running: bool,
     pub stopped: bool,
-    pub closed: bool,
     pub debug: bool,
     pub is_polling: bool,
+    /// Seconds after which debug mode reports a callback as slow
+    pub slow_callback_duration: f64,
+}
+
+impl Default for HotState {
+    fn default() -> Self {
+        Self {
+            stopped: false,
+            debug: false,
+            is_polling: false,
+            slow_callback_duration: DEFAULT_SLOW_CALLBACK_DURATION,
+        }
+    }
 }
 
 #[pyclass(subclass, module = "veloxloop._veloxloop")]
 pub struct VeloxLoop {
     pub(crate) poller: RefCell<LoopPoller>,
-    pub(crate) waker: PollerWaker,
+    pub(crate) waker: Arc<PollerWaker>,
     pub(crate) handles: RefCell<IoHandles>,
-    pub(crate) callbacks: CallbackQueue,
+    pub(crate) callbacks: Arc<CallbackQueue>,
     pub(crate) timers: RefCell<Timers>,
+    /// Transports with `set_timeouts()` armed, and the tick they stamp
+    /// their reads and sends with, see `idle_tick`
+    pub(crate) idle_timeouts: RefCell<TimeoutWheel<TimedTransport>>,
+    pub(crate) idle_clock: Arc<AtomicU64>,
     pub(crate) state: RefCell<HotState>,
     /// Atomic state for lock-free hot path checks (duplicates key state vars)
     pub(crate) atomic_state: AtomicState,
     pub(crate) start_time: Instant,
+    /// Loop time in ns for a `test_mode=True` loop; only `advance_time()`
+    /// moves it, and `time()` and timer deadlines follow it
+    pub(crate) virtual_now: Option<Cell<u64>>,
     pub(crate) executor: RefCell<Option<ThreadPoolExecutor>>,
+    /// Executor given to `set_default_executor`; the internal pool is used when None
+    pub(crate) default_executor: RefCell<Option<Py<PyAny>>>,
+    /// Async resolver given to `set_resolver`, and whether its failures fall
+    /// back to libc getaddrinfo
+    pub(crate) resolver: RefCell<Option<Py<PyAny>>>,
+    pub(crate) resolver_fallback: Cell<bool>,
+    /// Name lookups waiting for or running on the internal pool
+    pub(crate) lookups: RefCell<executor::Lookups>,
     pub(crate) exception_handler: RefCell<Option<Py<PyAny>>>,
+    /// Held open on /dev/null so an accept that hits EMFILE can still drain
+    /// one connection, see `shed_connection`
+    pub(crate) spare_fd: RefCell<Option<std::fs::File>>,
+    /// Test hook taking every context instead of the handler, see `_set_test_exception_sink`
+    pub(crate) exception_sink: RefCell<Option<Py<PyAny>>>,
+    /// Receives transport lifecycle events (see `set_transport_observer`)
+    pub(crate) transport_observer: RefCell<Option<Py<PyAny>>>,
     pub(crate) task_factory: RefCell<Option<Py<PyAny>>>,
-    pub(crate) async_generators: RefCell<Vec<Py<PyAny>>>,
+    /// Creates the transports for create_server/create_connection/create_datagram_endpoint
+    pub(crate) transport_factory: RefCell<LoopTransportFactory>,
+    pub(crate) async_generators: RefCell<Vec<Py<PyWeakrefReference>>>,
+    pub(crate) asyncgens_shutdown_called: Cell<bool>,
     pub(crate) callback_buffer: RefCell<Vec<Callback>>,
-    pub(crate) pending_ios: RefCell<Vec<(RawFd, Option<Handle>, Option<Handle>, bool, bool)>>,
+    /// Readiness filled in by each poll; kept with the loop, so neither a new
+    /// iteration nor a new `run_forever` allocates one
+    pub(crate) event_buffer: RefCell<Vec<PlatformEvent>>,
+    pub(crate) pending_ios: RefCell<Vec<PendingIo>>,
+    /// Shrink the two buffers above back after a spike
+    pub(crate) callback_buffer_trim: RefCell<BufferTrim>,
+    pub(crate) pending_ios_trim: RefCell<BufferTrim>,
+    /// TCP transports holding coalesced writes, flushed at the end of each iteration
+    pub(crate) coalesced_writers: RefCell<Vec<Py<crate::transports::tcp::TcpTransport>>>,
+    /// Recycled internal futures (disabled unless `future_pool_size` is given)
+    pub(crate) future_pool: RefCell<FuturePool>,
+    /// Bytes per socket read for new transports (see `set_read_buffer_size`)
+    pub(crate) read_chunk_size: Cell<usize>,
+    /// Whether new TCP transports watch for errors while paused (see
+    /// `set_monitor_idle_connections`)
+    pub(crate) monitor_idle_connections: Cell<bool>,
+    /// Most call_soon callbacks run per iteration; the rest wait at the front
+    /// of `callback_buffer` for the next one (unbounded when None)
+    pub(crate) max_callbacks_per_tick: Option<usize>,
+    /// `expected_connections` hint the internal maps were sized for
+    pub(crate) expected_connections: usize,
+    /// Iterations that left callbacks over because of `max_callbacks_per_tick`
+    pub(crate) deferred_callback_ticks: Cell<u64>,
+    /// Iterations that read several TCP sockets before delivering any data,
+    /// and the reads done that way
+    pub(crate) batched_read_ticks: Cell<u64>,
+    pub(crate) batched_reads: Cell<u64>,
     /// Track FDs registered with EPOLLONESHOT that are currently disabled (fired once)
     #[cfg(target_os = "linux")]
     pub(crate) oneshot_disabled: RefCell<FxHashSet<RawFd>>,
+    /// `AsyncFile` operations in flight, by io_uring token
+    #[cfg(target_os = "linux")]
+    pub(crate) file_ops: RefCell<FxHashMap<u64, files::FileOp>>,
+    /// Polls of cancelled sock_recv()/sock_sendall() calls kept armed for a
+    /// retry on the same socket, by fd
+    #[cfg(target_os = "linux")]
+    pub(crate) parked_sock_ops: RefCell<FxHashMap<RawFd, io::ParkedSockOp>>,
+    /// sock_* operations that took over a parked poll instead of adding one
+    pub(crate) sock_op_reuses: Cell<u64>,
     /// Atomic counter for tracking I/O operations (lock-free)
     pub(crate) io_op_counter: crate::concurrent::AtomicCounter,
+    /// Process the loop was created in; a fork child gets RuntimeError
+    pub(crate) owner: ForkGuard,
+    /// Coroutine origin tracking depth from before debug mode raised it
+    pub(crate) saved_origin_depth: Cell<Option<i32>>,
+    /// Record published at the end of each iteration once
+    /// `enable_stats_export` is called, and the counts it carries
+    pub(crate) stats_export: RefCell<Option<StatsExport>>,
+    pub(crate) export_counters: Cell<Counters>,
+    /// Totals of the transports made since the stats export was enabled
+    pub(crate) traffic: OnceCell<Arc<LoopTraffic>>,
 }
 
 unsafe impl Send for VeloxLoop {}
@@ -117,7 +234,52 @@ unsafe impl Sync for VeloxLoop {}
 
 impl VeloxLoop {
     pub fn time(&self) -> f64 {
-        self.start_time.elapsed().as_secs_f64()
+        match &self.virtual_now {
+            Some(now) => Duration::from_nanos(now.get()).as_secs_f64(),
+            None => self.start_time.elapsed().as_secs_f64(),
+        }
+    }
+
+    /// The running totals new transports join while the stats export is on
+    pub(crate) fn exported_traffic(&self) -> Option<&Arc<LoopTraffic>> {
+        self.traffic.get()
+    }
+
+    /// End of an iteration that polled `events`: rewrite the exported record
+    #[inline]
+    pub(crate) fn publish_stats(&self, events: usize) {
+        let mut export = self.stats_export.borrow_mut();
+        let Some(export) = export.as_mut() else {
+            return;
+        };
+        let mut counters = self.export_counters.get();
+        counters.iterations += 1;
+        counters.events += events as u64;
+        if let Some(traffic) = self.traffic.get() {
+            (counters.bytes_in, counters.bytes_out, counters.active_connections) =
+                traffic.totals();
+        }
+        counters.callbacks_queued =
+            (self.callbacks.len() + self.callback_buffer.borrow().len()) as u64;
+        counters.timers = self.timers.borrow().len() as u64;
+        self.export_counters.set(counters);
+        export.publish(&counters);
+    }
+
+    /// Loop time in whole nanoseconds; timer deadlines are kept in this unit
+    pub(crate) fn now_ns(&self) -> u64 {
+        match &self.virtual_now {
+            Some(now) => now.get(),
+            None => self.start_time.elapsed().as_nanos() as u64,
+        }
+    }
+
+    /// The idle timeout tick for the current loop time, published to the
+    /// transports stamping their activity
+    pub(crate) fn idle_tick(&self) -> u64 {
+        let tick = self.now_ns() / IDLE_TIMEOUT_TICK_NS;
+        self.idle_clock.store(tick, Ordering::Relaxed);
+        tick
     }
 
     /// Get the current I/O operation count (lock-free)
@@ -130,44 +292,215 @@ impl VeloxLoop {
     pub(crate) fn track_io_operation(&self) -> u64 {
         self.io_op_counter.increment()
     }
+
+    pub(crate) fn handles_mut(&self) -> PyResult<RefMut<'_, IoHandles>> {
+        borrow_state(&self.handles, "I/O handles")
+    }
+
+    pub(crate) fn poller_mut(&self) -> PyResult<RefMut<'_, LoopPoller>> {
+        borrow_state(&self.poller, "poller")
+    }
+
+    pub(crate) fn timers_mut(&self) -> PyResult<RefMut<'_, Timers>> {
+        borrow_state(&self.timers, "timers")
+    }
+
+    pub(crate) fn state_mut(&self) -> PyResult<RefMut<'_, HotState>> {
+        borrow_state(&self.state, "loop state")
+    }
+
+    /// `call_soon_threadsafe` for worker threads that must not keep the loop alive
+    pub(crate) fn threadsafe_handle(&self) -> ThreadsafeHandle {
+        ThreadsafeHandle::new(&self.callbacks, &self.waker)
+    }
+}
+
+impl Drop for VeloxLoop {
+    /// Dropping the loop must not wait for executor jobs: they may need the
+    /// GIL, which the dropping thread usually holds. Their results are
+    /// discarded since the queue they would be delivered to is gone.
+    fn drop(&mut self) {
+        if let Some(pool) = self.executor.get_mut().take() {
+            if self.owner.is_forked() {
+                // Its threads stayed in the parent, maybe holding the queues' locks
+                std::mem::forget(pool);
+            } else {
+                pool.detach();
+            }
+        }
+    }
+}
+
+/// `try_borrow_mut` for loop state touched from Python-facing methods.
+/// A conflict means a loop method was re-entered while another still held the
+/// state, e.g. from a finalizer run mid-update or from another thread; report
+/// it instead of panicking.
+fn borrow_state<'a, T>(cell: &'a RefCell<T>, what: &str) -> PyResult<RefMut<'a, T>> {
+    cell.try_borrow_mut().map_err(|_| {
+        PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
+            "event loop re-entered while updating its {}; loop methods \
+             must not be called from finalizers or other threads \
+             (use call_soon_threadsafe from other threads)",
+            what
+        ))
+    })
+}
+
+/// Python spelling of a bool, for reprs
+fn py_bool(value: bool) -> &'static str {
+    if value { "True" } else { "False" }
+}
+
+/// Debug mode for a loop created without `debug=`, picked up the way asyncio
+/// does: `-X dev`, or PYTHONASYNCIODEBUG set to anything unless `-E` was given
+fn debug_from_env(py: Python<'_>) -> PyResult<bool> {
+    let flags = py.import("sys")?.getattr("flags")?;
+    if flags.getattr("dev_mode")?.is_truthy()? {
+        return Ok(true);
+    }
+    Ok(!flags.getattr("ignore_environment")?.is_truthy()?
+        && std::env::var_os("PYTHONASYNCIODEBUG").is_some_and(|value| !value.is_empty()))
 }
 #[pymethods]
 impl VeloxLoop {
     #[new]
-    #[pyo3(signature = (debug=None))]
-    pub fn new(debug: Option<bool>) -> VeloxResult<Self> {
-        let poller = LoopPoller::new()?;
-        let waker = poller.waker();
-        let debug_val = debug.unwrap_or(false);
-
-        Ok(Self {
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        debug=None,
+        future_pool_size=None,
+        read_chunk_size=None,
+        *,
+        uring_sqpoll=false,
+        uring_sqpoll_idle_ms=None,
+        max_callbacks_per_tick=None,
+        reserve_fd=true,
+        test_mode=false,
+        expected_connections=None,
+    ))]
+    pub fn new(
+        py: Python<'_>,
+        debug: Option<bool>,
+        future_pool_size: Option<usize>,
+        read_chunk_size: Option<usize>,
+        uring_sqpoll: bool,
+        uring_sqpoll_idle_ms: Option<u32>,
+        max_callbacks_per_tick: Option<usize>,
+        reserve_fd: bool,
+        test_mode: bool,
+        expected_connections: Option<usize>,
+    ) -> VeloxResult<Self> {
+        let read_chunk_size = match read_chunk_size {
+            Some(size) => check_read_chunk_size(size)?,
+            None => DEFAULT_READ_CHUNK_SIZE,
+        };
+        if uring_sqpoll_idle_ms.is_some() && !uring_sqpoll {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "uring_sqpoll_idle_ms requires uring_sqpoll=True",
+            )
+            .into());
+        }
+        if max_callbacks_per_tick == Some(0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "max_callbacks_per_tick must be positive",
+            )
+            .into());
+        }
+        #[cfg(target_os = "linux")]
+        crate::self_check::ensure_io_uring()?;
+        let mut poller = if uring_sqpoll {
+            let idle = uring_sqpoll_idle_ms.unwrap_or(DEFAULT_SQPOLL_IDLE_MS);
+            let (poller, refused) = LoopPoller::with_sqpoll(idle)?;
+            let message = match refused {
+                Some(e) => format!(
+                    "io_uring SQPOLL is unavailable ({}); using a regular ring",
+                    e
+                ),
+                None => "io_uring SQPOLL is running without registered files; the kernel \
+                         thread still resolves each fd per operation, which reduces the benefit"
+                    .to_string(),
+            };
+            PyErr::warn(
+                py,
+                py.get_type::<pyo3::exceptions::PyRuntimeWarning>().as_any(),
+                &std::ffi::CString::new(message).map_err(PyErr::from)?,
+                1,
+            )?;
+            poller
+        } else {
+            LoopPoller::new()?
+        };
+        let expected_connections = expected_connections.unwrap_or(0);
+        poller.reserve(expected_connections);
+        let waker = Arc::new(poller.waker()?);
+        let debug = match debug {
+            Some(debug) => debug,
+            None => debug_from_env(py)?,
+        };
+
+        let loop_ = Self {
             poller: RefCell::new(poller),
             waker,
-            handles: RefCell::new(IoHandles::new()),
-            callbacks: CallbackQueue::new(),
+            handles: RefCell::new(IoHandles::with_capacity(expected_connections.max(256))),
+            callbacks: Arc::new(CallbackQueue::new()),
             timers: RefCell::new(Timers::new()),
-            state: RefCell::new(HotState {
-                running: false,
-                stopped: false,
-                closed: false,
-                debug: debug_val,
-                is_polling: false,
-            }),
+            idle_timeouts: RefCell::new(TimeoutWheel::new()),
+            idle_clock: Arc::new(AtomicU64::new(0)),
+            state: RefCell::new(HotState::default()),
             atomic_state: AtomicState::new(),
             start_time: Instant::now(),
+            virtual_now: test_mode.then(|| Cell::new(0)),
             executor: RefCell::new(None),
+            default_executor: RefCell::new(None),
+            resolver: RefCell::new(None),
+            resolver_fallback: Cell::new(false),
+            lookups: RefCell::new(executor::Lookups::default()),
             exception_handler: RefCell::new(None),
+            exception_sink: RefCell::new(None),
+            spare_fd: RefCell::new(if reserve_fd { open_spare_fd() } else { None }),
+            transport_observer: RefCell::new(None),
             task_factory: RefCell::new(None),
+            transport_factory: RefCell::new(LoopTransportFactory::Default),
             async_generators: RefCell::new(Vec::new()),
-            callback_buffer: RefCell::new(Vec::with_capacity(1024)),
-            pending_ios: RefCell::new(Vec::with_capacity(128)),
+            asyncgens_shutdown_called: Cell::new(false),
+            callback_buffer: RefCell::new(Vec::with_capacity(CALLBACK_BUFFER_CAPACITY)),
+            event_buffer: RefCell::new(Vec::with_capacity(
+                EVENT_BUFFER_CAPACITY.max(expected_connections),
+            )),
+            pending_ios: RefCell::new(Vec::with_capacity(
+                PENDING_IOS_CAPACITY.max(expected_connections),
+            )),
+            callback_buffer_trim: RefCell::new(BufferTrim::new(CALLBACK_BUFFER_CAPACITY)),
+            pending_ios_trim: RefCell::new(BufferTrim::new(
+                PENDING_IOS_CAPACITY.max(expected_connections),
+            )),
+            coalesced_writers: RefCell::new(Vec::new()),
+            future_pool: RefCell::new(FuturePool::new(future_pool_size.unwrap_or(0))),
+            read_chunk_size: Cell::new(read_chunk_size),
+            monitor_idle_connections: Cell::new(false),
+            max_callbacks_per_tick,
+            expected_connections,
+            deferred_callback_ticks: Cell::new(0),
+            batched_read_ticks: Cell::new(0),
+            batched_reads: Cell::new(0),
             #[cfg(target_os = "linux")]
             oneshot_disabled: RefCell::new(FxHashSet::with_capacity_and_hasher(
-                64,
+                expected_connections.max(64),
                 Default::default(),
             )),
+            #[cfg(target_os = "linux")]
+            file_ops: RefCell::new(FxHashMap::default()),
+            #[cfg(target_os = "linux")]
+            parked_sock_ops: RefCell::new(FxHashMap::default()),
+            sock_op_reuses: Cell::new(0),
             io_op_counter: crate::concurrent::AtomicCounter::new(0),
-        })
+            owner: ForkGuard::new(),
+            saved_origin_depth: Cell::new(None),
+            stats_export: RefCell::new(None),
+            export_counters: Cell::new(Counters::default()),
+            traffic: OnceCell::new(),
+        };
+        loop_.state.borrow_mut().debug = debug;
+        Ok(loop_)
     }
 
     #[pyo3(name = "time")]
@@ -175,28 +508,65 @@ impl VeloxLoop {
         self.time()
     }
 
+    /// Move the virtual clock of a `test_mode=True` loop `seconds` forward and
+    /// run the timers that became due, in deadline order. Returns how many ran.
+    #[pyo3(name = "advance_time")]
+    pub fn py_advance_time(&self, seconds: f64) -> PyResult<usize> {
+        self.advance_time(seconds)
+    }
+
     // Lifecycle methods
     #[pyo3(name = "run_forever")]
-    pub fn py_run_forever(&self, py: Python<'_>) -> PyResult<()> {
-        self.run_forever(py).map_err(|e| e.into())
+    pub fn py_run_forever(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let old_hooks = Self::install_asyncgen_hooks(slf)?;
+        let signal_wakeup = match slf.borrow().install_signal_wakeup(py) {
+            Ok(fd) => fd,
+            Err(e) => {
+                // The install error is the one worth reporting
+                let _ = Self::restore_asyncgen_hooks(py, old_hooks);
+                return Err(e);
+            }
+        };
+        let this = slf.borrow();
+        let result = this
+            .set_coroutine_origin_tracking(py, this.debug_enabled())
+            .and_then(|()| this.run_forever(py).map_err(PyErr::from));
+        // Undo everything before reporting; a teardown error only surfaces
+        // when the run itself succeeded
+        let tracking = this.set_coroutine_origin_tracking(py, false);
+        let wakeup = signal_wakeup.map_or(Ok(()), |fd| Self::restore_signal_wakeup(py, fd));
+        let hooks = Self::restore_asyncgen_hooks(py, old_hooks);
+        result.and(tracking).and(wakeup).and(hooks)
+    }
+
+    /// Pre-allocate buffers and run the ring through one no-op cycle, so the
+    /// first connection doesn't pay for the setup. Only before the loop runs.
+    #[pyo3(name = "warm_up")]
+    pub fn py_warm_up(&self, py: Python<'_>) -> VeloxResult<()> {
+        self.warm_up(py)
     }
 
     #[pyo3(name = "_run_once")]
     pub fn py_run_once(&self, py: Python<'_>) -> PyResult<()> {
-        let mut events = poll::PlatformEvents::new();
-        self._run_once(py, &mut events).map_err(|e| e.into())
+        self._run_once(py).map_err(|e| e.into())
     }
 
     #[pyo3(name = "stop")]
-    pub fn py_stop(&self) {
+    pub fn py_stop(&self) -> PyResult<()> {
         self.stop()
     }
 
     #[pyo3(name = "close")]
-    pub fn py_close(&self) {
+    pub fn py_close(&self) -> VeloxResult<()> {
         self.close()
     }
 
+    #[pyo3(name = "shutdown", signature = (timeout=None))]
+    pub fn py_shutdown(slf: &Bound<'_, Self>, timeout: Option<f64>) -> PyResult<()> {
+        Self::shutdown(slf, timeout)
+    }
+
     #[pyo3(name = "is_running")]
     pub fn py_is_running(&self) -> bool {
         self.is_running()
@@ -207,14 +577,50 @@ impl VeloxLoop {
         self.is_closed()
     }
 
+    #[pyo3(name = "_check_closed")]
+    pub fn py_check_closed(&self) -> VeloxResult<()> {
+        self.check_closed()
+    }
+
     #[pyo3(name = "get_debug")]
     pub fn py_get_debug(&self) -> bool {
         self.get_debug()
     }
 
     #[pyo3(name = "set_debug")]
-    pub fn py_set_debug(&self, enabled: bool) {
-        self.set_debug(enabled)
+    pub fn py_set_debug(&self, py: Python<'_>, enabled: bool) -> PyResult<()> {
+        self.set_debug(py, enabled)
+    }
+
+    #[getter(slow_callback_duration)]
+    pub fn py_get_slow_callback_duration(&self) -> f64 {
+        self.get_slow_callback_duration()
+    }
+
+    #[setter(slow_callback_duration)]
+    pub fn py_set_slow_callback_duration(&self, seconds: f64) -> PyResult<()> {
+        self.set_slow_callback_duration(seconds)
+    }
+
+    /// Built from atomics and a copy of the hot state, so it is safe to call
+    /// from any callback, including while the loop is dispatching
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let self_ = slf.borrow();
+        let debug = self_.state_snapshot().debug;
+        Ok(format!(
+            "<{} running={} closed={} debug={}>",
+            slf.get_type().name()?,
+            py_bool(self_.is_running()),
+            py_bool(self_.is_closed()),
+            py_bool(debug),
+        ))
+    }
+
+    /// Write end of the loop's self-pipe. Writing a byte to it from any
+    /// thread (or passing it to `signal.set_wakeup_fd`) interrupts the poll.
+    #[pyo3(name = "get_wakeup_fd")]
+    pub fn py_get_wakeup_fd(&self) -> PyResult<RawFd> {
+        Ok(self.poller_mut()?.wakeup_fd()?)
     }
 
     /// Get the number of I/O operations tracked by this event loop
@@ -225,23 +631,56 @@ impl VeloxLoop {
 
     // I/O methods
     #[pyo3(name = "add_reader", signature = (fd, callback))]
-    pub fn py_add_reader(&self, py: Python<'_>, fd: RawFd, callback: Py<PyAny>) -> PyResult<()> {
-        self.add_reader(py, fd, callback)
+    pub fn py_add_reader(
+        &self,
+        py: Python<'_>,
+        fd: &Bound<'_, PyAny>,
+        callback: Py<PyAny>,
+    ) -> PyResult<()> {
+        self.check_closed()?;
+        let fd = io::checked_pollable_fd(io::fileobj_to_fd(fd)?)?;
+        self.add_user_handler(py, fd, true, callback)
     }
 
     #[pyo3(name = "remove_reader")]
-    pub fn py_remove_reader(&self, py: Python<'_>, fd: RawFd) -> PyResult<bool> {
-        self.remove_reader(py, fd)
+    pub fn py_remove_reader(&self, py: Python<'_>, fd: &Bound<'_, PyAny>) -> PyResult<bool> {
+        // Like asyncio, an fd that can't be registered just isn't found
+        match RawFd::try_from(io::fileobj_to_fd(fd)?) {
+            Ok(fd) => self.remove_user_handler(py, fd, true),
+            Err(_) => Ok(false),
+        }
     }
 
     #[pyo3(name = "add_writer", signature = (fd, callback))]
-    pub fn py_add_writer(&self, py: Python<'_>, fd: RawFd, callback: Py<PyAny>) -> PyResult<()> {
-        self.add_writer(py, fd, callback)
+    pub fn py_add_writer(
+        &self,
+        py: Python<'_>,
+        fd: &Bound<'_, PyAny>,
+        callback: Py<PyAny>,
+    ) -> PyResult<()> {
+        self.check_closed()?;
+        let fd = io::checked_pollable_fd(io::fileobj_to_fd(fd)?)?;
+        self.add_user_handler(py, fd, false, callback)
     }
 
     #[pyo3(name = "remove_writer")]
-    pub fn py_remove_writer(&self, py: Python<'_>, fd: RawFd) -> PyResult<bool> {
-        self.remove_writer(py, fd)
+    pub fn py_remove_writer(&self, py: Python<'_>, fd: &Bound<'_, PyAny>) -> PyResult<bool> {
+        // Like asyncio, an fd that can't be registered just isn't found
+        match RawFd::try_from(io::fileobj_to_fd(fd)?) {
+            Ok(fd) => self.remove_user_handler(py, fd, false),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Dispatch the fd's reader and writer before other ready fds within a
+    /// tick. Server listeners get this automatically. Returns False if
+    /// nothing is registered for the fd.
+    #[pyo3(name = "set_fd_priority")]
+    pub fn py_set_fd_priority(&self, fd: &Bound<'_, PyAny>, high: bool) -> PyResult<bool> {
+        match RawFd::try_from(io::fileobj_to_fd(fd)?) {
+            Ok(fd) => self.set_fd_priority(fd, high),
+            Err(_) => Ok(false),
+        }
     }
 
     // Callback/Timer methods
@@ -251,8 +690,10 @@ impl VeloxLoop {
         callback: Py<PyAny>,
         args: Vec<Py<PyAny>>,
         context: Option<Py<PyAny>>,
-    ) {
-        self.call_soon(callback, args, context)
+    ) -> VeloxResult<()> {
+        self.check_closed()?;
+        self.call_soon(callback, args, context);
+        Ok(())
     }
 
     #[pyo3(name = "call_soon_threadsafe", signature = (callback, *args, context=None))]
@@ -261,7 +702,7 @@ impl VeloxLoop {
         callback: Py<PyAny>,
         args: Vec<Py<PyAny>>,
         context: Option<Py<PyAny>>,
-    ) {
+    ) -> VeloxResult<()> {
         self.call_soon_threadsafe(callback, args, context)
     }
 
@@ -272,7 +713,8 @@ impl VeloxLoop {
         callback: Py<PyAny>,
         args: Vec<Py<PyAny>>,
         context: Option<Py<PyAny>>,
-    ) -> u64 {
+    ) -> PyResult<u64> {
+        self.check_closed()?;
         self.call_later(delay, callback, args, context)
     }
 
@@ -283,12 +725,18 @@ impl VeloxLoop {
         callback: Py<PyAny>,
         args: Vec<Py<PyAny>>,
         context: Option<Py<PyAny>>,
-    ) -> u64 {
+    ) -> PyResult<u64> {
+        self.check_closed()?;
         self.call_at(when, callback, args, context)
     }
 
+    #[pyo3(name = "_timer_when")]
+    pub fn py_timer_when(&self, timer_id: u64) -> PyResult<Option<f64>> {
+        self.timer_when(timer_id)
+    }
+
     #[pyo3(name = "_cancel_timer")]
-    pub fn py_cancel_timer(&self, timer_id: u64) {
+    pub fn py_cancel_timer(&self, timer_id: u64) -> PyResult<()> {
         self._cancel_timer(timer_id)
     }
 
@@ -297,6 +745,123 @@ impl VeloxLoop {
         self.create_future(py)
     }
 
+    /// `{"capacity", "in_use", "free", "reused"}` for the internal future pool
+    pub fn future_pool_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        self.future_pool.borrow().to_dict(py)
+    }
+
+    /// Capacities of the loop's reused buffers and an estimate of the memory
+    /// they and this thread's read buffer pool keep allocated
+    pub fn get_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let callbacks = self.callback_buffer_trim.borrow().capacity();
+        let pending = self.pending_ios_trim.borrow().capacity();
+        let pool = BufferPool::retained_bytes();
+        let dict = PyDict::new(py);
+        dict.set_item("callback_buffer_capacity", callbacks)?;
+        dict.set_item("pending_ios_capacity", pending)?;
+        dict.set_item("buffer_pool_bytes", pool)?;
+        dict.set_item(
+            "retained_bytes",
+            callbacks * std::mem::size_of::<Callback>()
+                + pending * std::mem::size_of::<PendingIo>()
+                + pool,
+        )?;
+        let poller = self.poller.borrow();
+        dict.set_item("uring_sqpoll", poller.sqpoll_idle_ms().is_some())?;
+        dict.set_item("uring_sqpoll_idle_ms", poller.sqpoll_idle_ms())?;
+        dict.set_item("uring_sqpoll_wakeups", poller.sqpoll_wakeups())?;
+        let (registers, rearms, deletes) = poller.fd_op_counts();
+        dict.set_item("poller_registers", registers)?;
+        dict.set_item("poller_rearms", rearms)?;
+        dict.set_item("poller_deletes", deletes)?;
+        dict.set_item("expected_connections", self.expected_connections)?;
+        dict.set_item("event_buffer_capacity", self.event_buffer.borrow().capacity())?;
+        #[cfg(target_os = "linux")]
+        dict.set_item("backend_builds", crate::poller::backend_builds())?;
+        #[cfg(target_os = "linux")]
+        dict.set_item("notify_failures", crate::poller::notify_failures())?;
+        dict.set_item("sock_op_reuses", self.sock_op_reuses.get())?;
+        let (created, reused) = self.handles.borrow().allocation_counts();
+        dict.set_item("handles_created", created)?;
+        dict.set_item("handles_reused", reused)?;
+        dict.set_item("max_callbacks_per_tick", self.max_callbacks_per_tick)?;
+        dict.set_item(
+            "deferred_callback_ticks",
+            self.deferred_callback_ticks.get(),
+        )?;
+        dict.set_item("batched_read_ticks", self.batched_read_ticks.get())?;
+        dict.set_item("batched_reads", self.batched_reads.get())?;
+        let (in_flight, queued, timed_out) = self.lookup_counts();
+        dict.set_item("lookups_in_flight", in_flight)?;
+        dict.set_item("lookups_queued", queued)?;
+        dict.set_item("lookups_timed_out", timed_out)?;
+        Ok(dict)
+    }
+
+    /// Publish this loop's counters to the file at `path` (created or
+    /// truncated) at the end of every iteration, for scrapers in other
+    /// processes; see `read_stats_file`. Bytes and connections are counted
+    /// for the transports made from now on. Calling it again moves the
+    /// export to another file, keeping the counts.
+    pub fn enable_stats_export(&self, path: std::path::PathBuf) -> PyResult<()> {
+        let mut export = StatsExport::create(&path).map_err(crate::utils::os_error_to_pyerr)?;
+        export.publish(&self.export_counters.get());
+        self.traffic.get_or_init(Default::default);
+        *self.stats_export.borrow_mut() = Some(export);
+        Ok(())
+    }
+
+    /// The counters a loop last published to `path` with
+    /// `enable_stats_export`, as a dict. Needs no loop, so a sidecar process
+    /// can read every worker's file; a record caught mid-update is retried,
+    /// never returned torn.
+    #[staticmethod]
+    pub fn read_stats_file<'py>(
+        py: Python<'py>,
+        path: std::path::PathBuf,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let record = py
+            .detach(|| crate::stats_export::read(&path))
+            .map_err(crate::utils::os_error_to_pyerr)?;
+        let dict = PyDict::new(py);
+        for (name, value) in record {
+            dict.set_item(name, value)?;
+        }
+        Ok(dict)
+    }
+
+    /// `(registered, soft_limit)`: how many fds have a reader or writer on this
+    /// loop, and the process's RLIMIT_NOFILE soft limit (None when unlimited)
+    pub fn get_fd_usage(&self) -> (usize, Option<u64>) {
+        let (registered, soft_limit) = self.fd_usage();
+        (
+            registered,
+            (soft_limit != libc::RLIM_INFINITY).then_some(soft_limit),
+        )
+    }
+
+    /// Set how many bytes transports created from now on read per syscall.
+    /// Must be a power of two between 1 KB and 4 MB; existing transports keep
+    /// their size (use `transport.set_read_chunk_size` to change those).
+    pub fn set_read_buffer_size(&self, size: usize) -> PyResult<()> {
+        self.read_chunk_size.set(check_read_chunk_size(size)?);
+        Ok(())
+    }
+
+    pub fn get_read_buffer_size(&self) -> usize {
+        self.read_chunk_size.get()
+    }
+
+    /// Have TCP transports created from now on keep watching for socket errors
+    /// while reading is paused, as `transport.enable_error_monitoring()` does
+    pub fn set_monitor_idle_connections(&self, enabled: bool) {
+        self.monitor_idle_connections.set(enabled);
+    }
+
+    pub fn get_monitor_idle_connections(&self) -> bool {
+        self.monitor_idle_connections.get()
+    }
+
     // Network methods
     #[pyo3(name = "sock_connect")]
     pub fn py_sock_connect(
@@ -357,21 +922,41 @@ impl VeloxLoop {
     pub fn py_sock_sendall(
         slf: &Bound<'_, Self>,
         sock: Py<PyAny>,
-        data: &[u8],
+        data: &Bound<'_, PyAny>,
     ) -> PyResult<Py<PyAny>> {
         Self::sock_sendall(slf, sock, data)
     }
 
+    /// Native sendfile attempt — returns the bytes sent, or PendingFuture if async needed.
+    #[pyo3(name = "_sock_sendfile_try", signature = (sock, file, offset=0, count=None))]
+    pub fn py_sock_sendfile_try(
+        slf: &Bound<'_, Self>,
+        sock: Py<PyAny>,
+        file: &Bound<'_, PyAny>,
+        offset: i64,
+        count: Option<usize>,
+    ) -> PyResult<Py<PyAny>> {
+        Self::sock_sendfile_try(slf, sock, file, offset, count)
+    }
+
     /// Synchronous sendall attempt — returns True if all sent, PendingFuture if async needed.
     #[pyo3(name = "_sock_sendall_try")]
     pub fn py_sock_sendall_try(
         slf: &Bound<'_, Self>,
         sock: Py<PyAny>,
-        data: &[u8],
+        data: &Bound<'_, PyAny>,
     ) -> PyResult<Py<PyAny>> {
         Self::sock_sendall_try(slf, sock, data)
     }
 
+    /// Raise the TypeError `method` would for an unexpected keyword; lets the
+    /// Python wrappers fail before they resolve any host names
+    #[staticmethod]
+    #[pyo3(name = "_check_kwargs")]
+    pub fn py_check_kwargs(method: &str, kwargs: &Bound<'_, PyDict>) -> PyResult<()> {
+        endpoint_kwargs::check_method_kwargs(method, kwargs)
+    }
+
     #[pyo3(name = "create_connection", signature = (protocol_factory, host=None, port=None, **_kwargs))]
     pub fn py_create_connection(
         slf: &Bound<'_, Self>,
@@ -387,7 +972,7 @@ impl VeloxLoop {
     pub fn py_create_server(
         slf: &Bound<'_, Self>,
         protocol_factory: Py<PyAny>,
-        host: Option<&str>,
+        host: Option<&Bound<'_, PyAny>>,
         port: Option<u16>,
         _kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<PyAny>> {
@@ -398,7 +983,7 @@ impl VeloxLoop {
     pub fn py_start_server(
         slf: &Bound<'_, Self>,
         client_connected_cb: Py<PyAny>,
-        host: Option<&str>,
+        host: Option<&Bound<'_, PyAny>>,
         port: Option<u16>,
         limit: Option<usize>,
         _kwargs: Option<&Bound<'_, PyDict>>,
@@ -406,11 +991,11 @@ impl VeloxLoop {
         Self::start_server(slf, client_connected_cb, host, port, limit, _kwargs)
     }
 
-    #[pyo3(name = "open_connection", signature = (host, port, limit=None, **_kwargs))]
+    #[pyo3(name = "open_connection", signature = (host=None, port=None, limit=None, **_kwargs))]
     pub fn py_open_connection(
         slf: &Bound<'_, Self>,
-        host: &str,
-        port: u16,
+        host: Option<&str>,
+        port: Option<u16>,
         limit: Option<usize>,
         _kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<PyAny>> {
@@ -428,45 +1013,147 @@ impl VeloxLoop {
         Self::create_datagram_endpoint(slf, protocol_factory, local_addr, remote_addr, kwargs)
     }
 
+    // Subprocess methods
+    /// Open a file for io_uring backed reads and writes; `flags` are
+    /// `os.open` flags. Resolves to an `AsyncFile`.
+    #[cfg(target_os = "linux")]
+    #[pyo3(name = "open_file", signature = (path, flags=libc::O_RDONLY, mode=0o644))]
+    pub fn py_open_file(
+        slf: &Bound<'_, Self>,
+        path: &Bound<'_, PyAny>,
+        flags: i32,
+        mode: u32,
+    ) -> PyResult<Py<PyAny>> {
+        Self::open_file(slf, path, flags, mode)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[pyo3(name = "subprocess_exec", signature = (protocol_factory, *args, **kwargs))]
+    pub fn py_subprocess_exec(
+        slf: &Bound<'_, Self>,
+        protocol_factory: Py<PyAny>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        Self::subprocess_exec(slf, protocol_factory, args, kwargs)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[pyo3(name = "subprocess_shell", signature = (protocol_factory, cmd, **kwargs))]
+    pub fn py_subprocess_shell(
+        slf: &Bound<'_, Self>,
+        protocol_factory: Py<PyAny>,
+        cmd: &Bound<'_, PyAny>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        Self::subprocess_shell(slf, protocol_factory, cmd, kwargs)
+    }
+
     // Executor methods
-    #[pyo3(name = "run_in_executor", signature = (_executor, func, *args))]
+    #[pyo3(name = "run_in_executor", signature = (executor, func, *args))]
     pub fn py_run_in_executor(
-        &self,
-        py: Python<'_>,
-        _executor: Option<Py<PyAny>>,
+        slf: &Bound<'_, Self>,
+        executor: Option<Py<PyAny>>,
         func: Py<PyAny>,
         args: &Bound<'_, PyTuple>,
     ) -> PyResult<Py<PyAny>> {
-        self.run_in_executor(py, _executor, func, args)
+        Self::run_in_executor(slf, executor, func, args)
     }
 
     #[pyo3(name = "set_default_executor")]
-    pub fn py_set_default_executor(&self, _executor: Option<Py<PyAny>>) -> PyResult<()> {
-        self.set_default_executor(_executor)
+    pub fn py_set_default_executor(
+        &self,
+        py: Python<'_>,
+        executor: Option<Py<PyAny>>,
+    ) -> PyResult<()> {
+        self.set_default_executor(py, executor)
     }
 
-    #[pyo3(name = "getaddrinfo", signature = (host, port, *, family=0, r#type=0, proto=0, flags=0))]
+    #[pyo3(name = "_shutdown_default_executor")]
+    pub fn py_shutdown_default_executor(&self, py: Python<'_>) -> PyResult<Py<PendingFuture>> {
+        self.shutdown_default_executor(py)
+    }
+
+    #[pyo3(name = "getaddrinfo", signature = (host, port, *, family=None, r#type=None, proto=None, flags=None))]
     pub fn py_getaddrinfo(
-        &self,
-        py: Python<'_>,
+        slf: &Bound<'_, Self>,
         host: Option<Bound<'_, PyAny>>,
         port: Option<Bound<'_, PyAny>>,
-        family: i32,
-        r#type: i32,
-        proto: i32,
-        flags: i32,
+        family: Option<Bound<'_, PyAny>>,
+        r#type: Option<Bound<'_, PyAny>>,
+        proto: Option<Bound<'_, PyAny>>,
+        flags: Option<Bound<'_, PyAny>>,
     ) -> PyResult<Py<PyAny>> {
-        self.getaddrinfo(py, host, port, family, r#type, proto, flags)
+        let family = executor::socket_constant(family.as_ref(), "family")?;
+        let r#type = executor::socket_constant(r#type.as_ref(), "type")?;
+        let proto = executor::socket_constant(proto.as_ref(), "proto")?;
+        let flags = executor::socket_constant(flags.as_ref(), "flags")?;
+        Self::getaddrinfo(slf, host, port, family, r#type, proto, flags)
     }
 
-    #[pyo3(name = "getnameinfo", signature = (sockaddr, flags=0))]
-    pub fn py_getnameinfo(
+    #[pyo3(name = "set_resolver", signature = (resolver, *, fallback=false))]
+    pub fn py_set_resolver(
         &self,
         py: Python<'_>,
+        resolver: Option<Py<PyAny>>,
+        fallback: bool,
+    ) -> PyResult<()> {
+        self.set_resolver(py, resolver, fallback)
+    }
+
+    #[pyo3(name = "get_resolver")]
+    pub fn py_get_resolver(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.get_resolver(py)
+    }
+
+    #[pyo3(name = "_resolver_fallback")]
+    pub fn py_resolver_fallback(&self) -> bool {
+        self.resolver_fallback.get()
+    }
+
+    /// Whether this process was forked after the loop was created, which
+    /// leaves the loop unusable here
+    #[pyo3(name = "_forked")]
+    pub fn py_forked(&self) -> bool {
+        self.owner.is_forked()
+    }
+
+    #[pyo3(name = "getnameinfo", signature = (sockaddr, flags=0))]
+    pub fn py_getnameinfo(
+        slf: &Bound<'_, Self>,
         sockaddr: Bound<'_, PyTuple>,
         flags: i32,
     ) -> PyResult<Py<PyAny>> {
-        self.getnameinfo(py, sockaddr, flags)
+        Self::getnameinfo(slf, sockaddr, flags)
+    }
+
+    #[pyo3(name = "set_lookup_limit")]
+    pub fn py_set_lookup_limit(slf: &Bound<'_, Self>, limit: usize) -> PyResult<()> {
+        Self::set_lookup_limit(slf, limit)
+    }
+
+    #[pyo3(name = "set_resolver_timeout")]
+    pub fn py_set_resolver_timeout(&self, timeout: Option<f64>) -> PyResult<()> {
+        self.set_resolver_timeout(timeout)
+    }
+
+    #[pyo3(name = "get_resolver_timeout")]
+    pub fn py_get_resolver_timeout(&self) -> Option<f64> {
+        self.get_resolver_timeout()
+    }
+
+    /// For tests: while set, getaddrinfo() calls `hook(host, port)` on the
+    /// pool thread instead of libc, e.g. to stand in for a stuck resolver
+    #[pyo3(name = "_set_lookup_hook")]
+    pub fn py_set_lookup_hook(&self, hook: Option<Py<PyAny>>) {
+        self.set_lookup_hook(hook)
+    }
+
+    /// For tests: hold each libc lookup on its pool thread for `seconds`
+    /// first, like a resolver that is slow to answer
+    #[pyo3(name = "_set_lookup_stall")]
+    pub fn py_set_lookup_stall(&self, seconds: Option<f64>) {
+        self.set_lookup_stall(seconds.map(std::time::Duration::from_secs_f64))
     }
 
     // Exception handler methods
@@ -494,6 +1181,24 @@ impl VeloxLoop {
         self.call_exception_handler(py, context)
     }
 
+    /// For tests: while set, every context passed to call_exception_handler
+    /// goes synchronously to `sink(context)` instead of the exception handler
+    #[pyo3(name = "_set_test_exception_sink")]
+    pub fn py_set_test_exception_sink(&self, sink: Option<Py<PyAny>>) {
+        *self.exception_sink.borrow_mut() = sink;
+    }
+
+    // Transport observer methods
+    #[pyo3(name = "set_transport_observer")]
+    pub fn py_set_transport_observer(&self, callback: Option<Py<PyAny>>) {
+        self.set_transport_observer(callback)
+    }
+
+    #[pyo3(name = "get_transport_observer")]
+    pub fn py_get_transport_observer(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.get_transport_observer(py)
+    }
+
     // Task factory methods
     #[pyo3(name = "set_task_factory")]
     pub fn py_set_task_factory(&self, factory: Option<Py<PyAny>>) {
@@ -505,10 +1210,21 @@ impl VeloxLoop {
         self.get_task_factory(py)
     }
 
+    // Transport factory methods
+    #[pyo3(name = "set_transport_factory")]
+    pub fn py_set_transport_factory(&self, factory: Option<Bound<'_, PyAny>>) -> PyResult<()> {
+        self.set_transport_factory(factory)
+    }
+
+    #[pyo3(name = "get_transport_factory")]
+    pub fn py_get_transport_factory(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.get_transport_factory(py)
+    }
+
     // Async generator methods
     #[pyo3(name = "_track_async_generator")]
-    pub fn py_track_async_generator(&self, agen: Py<PyAny>) {
-        self._track_async_generator(agen)
+    pub fn py_track_async_generator(&self, py: Python<'_>, agen: Py<PyAny>) -> PyResult<()> {
+        self._track_async_generator(py, agen)
     }
 
     #[pyo3(name = "_untrack_async_generator")]
@@ -516,9 +1232,19 @@ impl VeloxLoop {
         self._untrack_async_generator(py, agen)
     }
 
+    #[pyo3(name = "_asyncgen_firstiter_hook")]
+    pub fn py_asyncgen_firstiter_hook(&self, py: Python<'_>, agen: Py<PyAny>) -> PyResult<()> {
+        self._asyncgen_firstiter_hook(py, agen)
+    }
+
+    #[pyo3(name = "_asyncgen_finalizer_hook")]
+    pub fn py_asyncgen_finalizer_hook(slf: &Bound<'_, Self>, agen: Py<PyAny>) -> PyResult<()> {
+        Self::_asyncgen_finalizer_hook(slf, agen)
+    }
+
     #[pyo3(name = "shutdown_asyncgens")]
-    pub fn py_shutdown_asyncgens(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        self.shutdown_asyncgens(py)
+    pub fn py_shutdown_asyncgens(slf: &Bound<'_, Self>) -> PyResult<Py<PendingFuture>> {
+        Self::shutdown_asyncgens(slf)
     }
 
     /// Get the number of active tasks in the executor
@@ -540,4 +1266,22 @@ impl VeloxLoop {
             0
         }
     }
+
+    /// io_uring opcodes the kernel supports, as probed when the loop was
+    /// created; missing ones are emulated with readiness polls and syscalls
+    #[pyo3(name = "get_backend_capabilities")]
+    pub fn py_get_backend_capabilities<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let poller = self.poller.borrow();
+        let dict = PyDict::new(py);
+        dict.set_item("backend", "io_uring")?;
+        for (name, supported) in poller.capabilities().flags() {
+            dict.set_item(name, supported)?;
+        }
+        dict.set_item("uring_sqpoll", poller.sqpoll_idle_ms().is_some())?;
+        dict.set_item("max_callbacks_per_tick", self.max_callbacks_per_tick)?;
+        Ok(dict)
+    }
 }
@@ -1,11 +1,22 @@
+#[cfg(target_os = "linux")]
+use crate::callbacks::SockOpParker;
 use crate::callbacks::{
     AsyncConnectCallback, RemoveWriterCallback, SendfileCallback, SockAcceptCallback,
-    SockConnectCallback,
+    SockConnectCallback, SockSendallCallback, SockSendfileCallback, buffer_bytes, connect_error,
+    send_some, sendfile_error, sendfile_not_available, sendfile_some,
 };
-use crate::constants::{RECV_BUF_SIZE, get_socket};
-use crate::event_loop::VeloxLoop;
+use crate::constants::{
+    LISTEN_BACKLOG, RECV_BUF_SIZE, SENDALL_BUDGET, SSL_HANDSHAKE_TIMEOUT, get_socket,
+};
+use crate::event_loop::endpoint_kwargs::{
+    CONNECT_MODIFIERS, CREATE_CONNECTION, CREATE_DATAGRAM_ENDPOINT, CREATE_SERVER,
+    DATAGRAM_MODIFIERS, OPEN_CONNECTION, SERVER_MODIFIERS, check_kwargs, check_sock_modifiers,
+    client_ssl_kwarg, family_kwarg, flag_kwarg, kwarg, reuse_port_kwarg, seconds_kwarg,
+    server_hostname_kwarg, sock_kwarg, ssl_timeouts,
+};
+use crate::event_loop::{VeloxLoop, is_fd_exhaustion};
 use crate::ffi_utils;
-use crate::transports::future::{CompletedFuture, PendingFuture};
+use crate::socket::{KeepaliveParams, ProxyOptions, TcpTuning, accept_nonblocking};
 use crate::transports::tcp::TcpServer;
 use crate::transports::udp::UdpTransport;
 use std::cell::RefCell;
@@ -15,12 +26,13 @@ thread_local! {
     /// 256KB matches the transport read buffer size.
     static SOCK_RECV_BUF: RefCell<Vec<u8>> = RefCell::new(vec![0u8; RECV_BUF_SIZE]);
 }
-use crate::transports::{DefaultTransportFactory, TransportFactory};
+use crate::transports::TransportFactory;
+use pyo3::buffer::PyBuffer;
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyTuple};
 use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 use std::net::SocketAddr;
-use std::os::fd::{AsRawFd, RawFd};
+use std::os::fd::{AsRawFd, IntoRawFd, RawFd};
 use std::sync::Arc;
 
 use pyo3::IntoPyObjectExt;
@@ -36,20 +48,21 @@ impl VeloxLoop {
 
         let fd: RawFd = sock.getattr(py, "fileno")?.call0(py)?.extract(py)?;
 
-        let tuple: Bound<'_, PyTuple> = address.extract().map_err(|_| {
-            PyErr::new::<pyo3::exceptions::PyTypeError, _>("address must be a tuple (host, port)")
-        })?;
-
-        let host: String = tuple.get_item(0)?.extract()?;
-        let port: u16 = tuple.get_item(1)?.extract()?;
-
-        let ip_addr: std::net::IpAddr = host.parse().map_err(|_| {
-            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid IP address: {}", host))
-        })?;
-
-        let addr = SocketAddr::new(ip_addr, port);
+        let family: i32 = sock.getattr(py, "family")?.extract(py)?;
 
-        let sock_addr: SockAddr = addr.into();
+        let sock_addr: SockAddr = if family == libc::AF_UNIX {
+            let path: std::path::PathBuf = address.extract().map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyTypeError, _>("AF_UNIX address must be a path")
+            })?;
+            SockAddr::unix(path)?
+        } else {
+            let tuple = address.cast::<PyTuple>().map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                    "address must be a tuple (host, port) or (host, port, flowinfo, scope_id)",
+                )
+            })?;
+            crate::utils::ipv6::parse_address_tuple(tuple, family)?.into()
+        };
 
         unsafe {
             let ret = libc::connect(
@@ -59,38 +72,36 @@ impl VeloxLoop {
             );
 
             if ret == 0 {
-                let fut = PendingFuture::new();
-                fut.set_result(py, py.None())?;
-                return Ok(Py::new(py, fut)?.into_any());
+                return self_.resolved_future(py, py.None());
             }
 
             let err = std::io::Error::last_os_error();
             match err.kind() {
                 std::io::ErrorKind::WouldBlock => {}
                 _ if err.raw_os_error() == Some(libc::EINPROGRESS) => {}
-                _ => {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
-                        err.to_string(),
-                    ));
-                }
+                _ => return Err(connect_error(py, &err, sock_addr.as_socket())),
             }
         }
+        drop(self_);
 
-        let future = self_.create_future(py)?;
-        let future_clone = future.clone_ref(py);
-
-        let callback = SockConnectCallback::new(future_clone).into_py_any(py)?;
+        // An asyncio future: when a timeout cancels the caller, the done
+        // callback below still takes the writer off the socket
+        let future = slf.call_method0("create_future")?.unbind();
+        let callback = SockConnectCallback::new(
+            future.clone_ref(py),
+            fd,
+            slf.clone().unbind(),
+            sock_addr.as_socket(),
+        )
+        .into_py_any(py)?;
 
-        self_.add_writer(py, fd, callback)?;
+        slf.borrow().add_writer(py, fd, callback)?;
 
         let loop_ref = slf.clone().unbind();
         let done_callback_obj = RemoveWriterCallback::new(fd, loop_ref).into_py_any(py)?;
-        future
-            .bind(py)
-            .borrow()
-            .add_done_callback(done_callback_obj)?;
+        future.call_method1(py, "add_done_callback", (done_callback_obj,))?;
 
-        Ok(future.into_any())
+        Ok(future)
     }
 
     pub fn sock_accept(slf: &Bound<'_, Self>, sock: Py<PyAny>) -> PyResult<Py<PyAny>> {
@@ -99,81 +110,43 @@ impl VeloxLoop {
 
         let fd: RawFd = sock.getattr(py, "fileno")?.call0(py)?.extract(py)?;
 
-        unsafe {
-            let mut addr: libc::sockaddr_storage = std::mem::zeroed();
-            let mut addr_len: libc::socklen_t =
-                std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
-
-            let client_fd = libc::accept(
-                fd,
-                &mut addr as *mut _ as *mut libc::sockaddr,
-                &mut addr_len,
-            );
-
-            if client_fd >= 0 {
+        match accept_nonblocking(fd) {
+            Ok((client_fd, addr, addr_len)) => {
+                // socket.socket(family, SOCK_STREAM, 0, fileno) adopts the fd as-is
                 let socket_module = get_socket(py).bind(py);
-                let client_sock = socket_module.call_method1("fromfd", (client_fd, 2, 1))?;
-
-                let flags = libc::fcntl(client_fd, libc::F_GETFL, 0);
-                if flags >= 0 {
-                    libc::fcntl(client_fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
-                }
-
-                // Use C API for address tuple creation
-                let addr_tuple_ptr = if addr_len as usize >= std::mem::size_of::<libc::sockaddr_in>() {
-                    let addr_in = &*((&addr) as *const _ as *const libc::sockaddr_in);
-                    let is_ipv4 = addr_in.sin_family == libc::AF_INET as u16;
-
-                    if is_ipv4 {
-                        let ip = u32::from_be(addr_in.sin_addr.s_addr);
-                        let ip_str = format!(
-                            "{}.{}.{}.{}",
-                            (ip >> 24) & 0xff,
-                            (ip >> 16) & 0xff,
-                            (ip >> 8) & 0xff,
-                            ip & 0xff
-                        );
-                        ffi_utils::tuple2(
-                            ffi_utils::string_from_str(&ip_str),
-                            ffi_utils::long_from_u16(u16::from_be(addr_in.sin_port)),
-                        )
-                    } else {
-                        ffi_utils::tuple2(
-                            ffi_utils::string_from_str(""),
-                            ffi_utils::long_from_i32(0),
-                        )
-                    }
-                } else {
-                    ffi_utils::tuple2(
-                        ffi_utils::string_from_str(""),
-                        ffi_utils::long_from_i32(0),
-                    )
+                let client_sock = socket_module.call_method1(
+                    "socket",
+                    (
+                        addr.ss_family as i32,
+                        libc::SOCK_STREAM,
+                        0,
+                        client_fd.as_raw_fd(),
+                    ),
+                )?;
+                // Owned by the Python socket now
+                let _ = client_fd.into_raw_fd();
+
+                let addr_tuple_ptr =
+                    crate::utils::ipv6::parse_sockaddr_storage(py, &addr, addr_len)?.into_ptr();
+
+                let result: Py<PyAny> = unsafe {
+                    let result_ptr = ffi_utils::tuple2(
+                        {
+                            pyo3::ffi::Py_INCREF(client_sock.as_ptr());
+                            client_sock.as_ptr()
+                        },
+                        addr_tuple_ptr,
+                    );
+                    pyo3::Bound::from_owned_ptr(py, result_ptr).unbind()
                 };
 
-                let result_ptr = ffi_utils::tuple2(
-                    {
-                        pyo3::ffi::Py_INCREF(client_sock.as_ptr());
-                        client_sock.as_ptr()
-                    },
-                    addr_tuple_ptr,
-                );
-                let result: Py<PyAny> = pyo3::Bound::from_owned_ptr(py, result_ptr).unbind();
-
-                let fut = PendingFuture::new();
-                fut.set_result(py, result)?;
-                return Ok(Py::new(py, fut)?.into_any());
+                return self_.resolved_future(py, result);
             }
-
-            let err = std::io::Error::last_os_error();
-            match err.kind() {
-                std::io::ErrorKind::WouldBlock => {}
-                _ if err.raw_os_error() == Some(libc::EAGAIN) => {}
-                _ => {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
-                        err.to_string(),
-                    ));
-                }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(err) if is_fd_exhaustion(&err) => {
+                return Err(self_.fd_error("sock_accept()", err));
             }
+            Err(err) => return Err(crate::utils::os_error_to_pyerr(err)),
         }
 
         let future = self_.create_future(py)?;
@@ -189,14 +162,15 @@ impl VeloxLoop {
     #[inline(always)]
     /// Fast-path synchronous recv attempt.
     /// Returns Python bytes if data is available, None if WouldBlock.
-    /// Called from Python `async def sock_recv()` wrapper to avoid CompletedFuture overhead.
+    /// Called from Python `async def sock_recv()` wrapper to skip creating a future.
     pub fn sock_recv_try(slf: &Bound<'_, Self>, sock: Py<PyAny>, nbytes: usize) -> PyResult<Py<PyAny>> {
         let py = slf.py();
 
         let fd: RawFd = sock.getattr(py, "fileno")?.call0(py)?.extract(py)?;
 
         if fd < 0 {
-            return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
+            return Err(crate::utils::os_error_with_message(
+                &std::io::Error::from_raw_os_error(libc::EBADF),
                 "Invalid file descriptor",
             ));
         }
@@ -216,7 +190,7 @@ impl VeloxLoop {
                         if err.kind() != std::io::ErrorKind::WouldBlock
                             && err.raw_os_error() != Some(libc::EAGAIN)
                         {
-                            Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string()))
+                            Err(crate::utils::os_error_to_pyerr(err))
                         } else {
                             Ok(None) // WouldBlock — caller will use sock_recv_wait
                         }
@@ -229,25 +203,12 @@ impl VeloxLoop {
                 None => Ok(py.None()),
             }
         } else {
-            // Very large request — heap allocate (rare path)
-            let mut buf = vec![0u8; nbytes];
-            unsafe {
-                let n = libc::recv(fd, buf.as_mut_ptr() as *mut libc::c_void, nbytes, 0);
-                if n > 0 {
-                    let bytes = ffi_utils::bytes_from_slice(py, &buf[..n as usize]);
-                    Ok(bytes)
-                } else if n == 0 {
-                    Ok(ffi_utils::bytes_from_slice(py, &[]))
-                } else {
-                    let err = std::io::Error::last_os_error();
-                    if err.kind() != std::io::ErrorKind::WouldBlock
-                        && err.raw_os_error() != Some(libc::EAGAIN)
-                    {
-                        Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string()))
-                    } else {
-                        Ok(py.None())
-                    }
-                }
+            // Very large request: recv straight into the bytes object, which
+            // is trimmed to what arrived
+            match ffi_utils::bytes_filled(py, nbytes, |dst| crate::socket::recv_into(fd, dst))? {
+                Ok((bytes, _)) => Ok(bytes),
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => Ok(py.None()),
+                Err(err) => Err(crate::utils::os_error_to_pyerr(err)),
             }
         }
     }
@@ -266,42 +227,44 @@ impl VeloxLoop {
 
         #[cfg(target_os = "linux")]
         {
-            let recv_buf = Arc::new(std::sync::Mutex::new(vec![0u8; nbytes]));
             let native_callback: Arc<dyn Fn(Python<'_>) -> PyResult<()> + Send + Sync> =
                 Arc::new(move |py: Python<'_>| {
+                    // Cancelled: the data is left for whoever reads next
+                    if future_clone.bind(py).borrow().done() {
+                        return Ok(());
+                    }
+                    let received = crate::ffi_utils::bytes_filled(py, nbytes, |dst| {
+                        crate::socket::recv_into(fd, dst)
+                    })?;
+                    if matches!(&received, Err(err) if err.kind() == std::io::ErrorKind::WouldBlock)
+                    {
+                        // Woken for nothing: stay registered, the poll is re-armed
+                        return Ok(());
+                    }
                     loop_ref.bind(py).borrow().mark_oneshot_disabled(fd);
-
-                    let mut buf = recv_buf.lock().unwrap();
-
-                    let n = unsafe {
-                        libc::recv(fd, buf.as_mut_ptr() as *mut libc::c_void, nbytes, 0)
-                    };
-
-                    if n > 0 {
-                        let bytes = unsafe { crate::ffi_utils::bytes_from_slice(py, &buf[..n as usize]) };
-                        let _ = future_clone.bind(py).borrow().set_result(py, bytes);
-                    } else if n == 0 {
-                        let bytes = unsafe { crate::ffi_utils::bytes_from_slice(py, &[]) };
-                        let _ = future_clone.bind(py).borrow().set_result(py, bytes);
-                    } else {
-                        let err = std::io::Error::last_os_error();
-                        if err.kind() != std::io::ErrorKind::WouldBlock
-                            && err.raw_os_error() != Some(libc::EAGAIN)
-                            && err.raw_os_error() != Some(libc::EBADF)
-                        {
-                            let py_err =
-                                PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string());
-                            let exc_val = py_err.value(py).as_any().clone().unbind();
-                            let _ = future_clone.bind(py).borrow().set_exception(py, exc_val);
-                        } else if err.raw_os_error() == Some(libc::EBADF) {
+                    match received {
+                        Ok((bytes, _)) => {
+                            let _ = future_clone.bind(py).borrow().set_result(py, bytes);
+                        }
+                        Err(err) if err.raw_os_error() == Some(libc::EBADF) => {
                             let bytes = unsafe { crate::ffi_utils::bytes_from_slice(py, &[]) };
                             let _ = future_clone.bind(py).borrow().set_result(py, bytes);
                         }
+                        Err(err) => {
+                            let py_err = crate::utils::os_error_to_pyerr(err);
+                            let exc_val = py_err.value(py).as_any().clone().unbind();
+                            let _ = future_clone.bind(py).borrow().set_exception(py, exc_val);
+                        }
                     }
                     Ok(())
                 });
 
-            self_.add_reader_oneshot(fd, native_callback)?;
+            let generation = self_.add_sock_op(fd, &sock, true, native_callback)?;
+            let parker = SockOpParker::new(slf.clone().unbind(), sock, fd, true, generation);
+            future
+                .bind(py)
+                .borrow()
+                .add_done_callback(py, Py::new(py, parker)?.into_any())?;
         }
 
         #[cfg(not(target_os = "linux"))]
@@ -309,37 +272,28 @@ impl VeloxLoop {
             let handled = Arc::new(std::sync::atomic::AtomicBool::new(false));
             let handled_clone = handled.clone();
 
-            let recv_buf = Arc::new(std::sync::Mutex::new(vec![0u8; nbytes]));
             let native_callback: Arc<dyn Fn(Python<'_>) -> PyResult<()> + Send + Sync> =
                 Arc::new(move |py: Python<'_>| {
                     if handled_clone.swap(true, std::sync::atomic::Ordering::Relaxed) {
                         return Ok(());
                     }
 
-                    let mut buf = recv_buf.lock().unwrap();
-
-                    let n = unsafe {
-                        libc::recv(fd, buf.as_mut_ptr() as *mut libc::c_void, nbytes, 0)
-                    };
+                    let received = crate::ffi_utils::bytes_filled(py, nbytes, |dst| {
+                        crate::socket::recv_into(fd, dst)
+                    })?;
 
                     let _ = loop_ref.bind(py).borrow().remove_reader(py, fd);
 
-                    if n > 0 {
-                        let bytes = unsafe { crate::ffi_utils::bytes_from_slice(py, &buf[..n as usize]) };
-                        let _ = future_clone.bind(py).borrow().set_result(py, bytes);
-                    } else if n == 0 {
-                        let bytes = unsafe { crate::ffi_utils::bytes_from_slice(py, &[]) };
-                        let _ = future_clone.bind(py).borrow().set_result(py, bytes);
-                    } else {
-                        let err = std::io::Error::last_os_error();
-                        if err.kind() != std::io::ErrorKind::WouldBlock
-                            && err.raw_os_error() != Some(libc::EAGAIN)
-                        {
-                            let py_err =
-                                PyErr::new::<pyo3::exceptions::PyOSError, _>(err.to_string());
+                    match received {
+                        Ok((bytes, _)) => {
+                            let _ = future_clone.bind(py).borrow().set_result(py, bytes);
+                        }
+                        Err(err) if err.kind() != std::io::ErrorKind::WouldBlock => {
+                            let py_err = crate::utils::os_error_to_pyerr(err);
                             let exc_val = py_err.value(py).as_any().clone().unbind();
                             let _ = future_clone.bind(py).borrow().set_exception(py, exc_val);
                         }
+                        Err(_) => {}
                     }
                     Ok(())
                 });
@@ -350,7 +304,7 @@ impl VeloxLoop {
         Ok(future.into_any())
     }
 
-    /// Legacy sock_recv that returns CompletedFuture/PendingFuture.
+    /// Legacy sock_recv that returns a PendingFuture, resolved if data was ready.
     /// Kept for backward compatibility. The Python wrapper uses sock_recv_try/sock_recv_wait instead.
     pub fn sock_recv(slf: &Bound<'_, Self>, sock: Py<PyAny>, nbytes: usize) -> PyResult<Py<PyAny>> {
         let py = slf.py();
@@ -358,9 +312,8 @@ impl VeloxLoop {
         // Try synchronous fast path
         let result = Self::sock_recv_try(slf, sock.clone_ref(py), nbytes)?;
         if !result.is_none(py) {
-            // Data ready — wrap in CompletedFuture for legacy callers
-            let fut = CompletedFuture::new(result);
-            return Ok(Py::new(py, fut)?.into_any());
+            // Data ready — hand it over already resolved
+            return slf.borrow().resolved_future(py, result);
         }
 
         // Async path
@@ -406,17 +359,13 @@ impl VeloxLoop {
                 if libc::fstat(in_fd, &mut stat) == 0 {
                     (stat.st_size as i64 - offset).max(0) as usize
                 } else {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
-                        "failed to get file size",
-                    ));
+                    return Err(crate::utils::last_os_error("failed to get file size"));
                 }
             },
         };
 
         if total_count == 0 {
-            let fut = PendingFuture::new();
-            fut.set_result(py, py.None())?;
-            return Ok(Py::new(py, fut)?.into_any());
+            return self_.resolved_future(py, py.None());
         }
 
         let mut current_sent = 0;
@@ -426,22 +375,16 @@ impl VeloxLoop {
             if n > 0 {
                 current_sent = n as usize;
                 if current_sent >= total_count {
-                    let fut = PendingFuture::new();
-                    fut.set_result(py, py.None())?;
-                    return Ok(Py::new(py, fut)?.into_any());
+                    return self_.resolved_future(py, py.None());
                 }
             } else if n == 0 {
-                let fut = PendingFuture::new();
-                fut.set_result(py, py.None())?;
-                return Ok(Py::new(py, fut)?.into_any());
+                return self_.resolved_future(py, py.None());
             } else {
                 let err = std::io::Error::last_os_error();
                 if err.kind() != std::io::ErrorKind::WouldBlock
                     && err.raw_os_error() != Some(libc::EAGAIN)
                 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
-                        err.to_string(),
-                    ));
+                    return Err(crate::utils::os_error_to_pyerr(err));
                 }
             }
         }
@@ -466,97 +409,62 @@ impl VeloxLoop {
     }
 
     /// Fast-path synchronous sendall attempt.
-    /// Returns true if all data was sent, false if WouldBlock (caller should use sock_sendall_wait).
-    /// Key optimization: uses the borrowed &[u8] directly — no data.to_vec() on fast path.
+    /// Returns None if all data was sent, otherwise a PendingFuture completed by a writer callback.
+    /// `data` is any contiguous buffer; it is held, not copied, until the send finishes.
+    /// At most `SENDALL_BUDGET` bytes go out before returning to the loop.
     pub fn sock_sendall_try(
         slf: &Bound<'_, Self>,
         sock: Py<PyAny>,
-        data: &[u8],
+        data: &Bound<'_, PyAny>,
     ) -> PyResult<Py<PyAny>> {
         let py = slf.py();
 
         let fd: RawFd = sock.getattr(py, "fileno")?.call0(py)?.extract(py)?;
-
-        let mut total_sent = 0;
-        while total_sent < data.len() {
-            unsafe {
-                let n = libc::send(
-                    fd,
-                    data[total_sent..].as_ptr() as *const libc::c_void,
-                    data.len() - total_sent,
-                    0,
-                );
-
-                if n > 0 {
-                    total_sent += n as usize;
-                } else {
-                    let err = std::io::Error::last_os_error();
-                    match err.kind() {
-                        std::io::ErrorKind::WouldBlock => break,
-                        _ if err.raw_os_error() == Some(libc::EAGAIN) => break,
-                        _ => {
-                            return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
-                                err.to_string(),
-                            ));
-                        }
-                    }
-                }
-            }
+        let buf = PyBuffer::<u8>::get(data)?;
+        if !buf.is_c_contiguous() {
+            return Err(PyErr::new::<pyo3::exceptions::PyBufferError, _>(
+                "sock_sendall requires a contiguous buffer",
+            ));
         }
 
-        if total_sent == data.len() {
-            // All sent synchronously — no copy needed!
+        let bytes = buffer_bytes(&buf);
+        let total_sent =
+            send_some(fd, bytes, SENDALL_BUDGET).map_err(crate::utils::os_error_to_pyerr)?;
+        if total_sent == bytes.len() {
+            // All sent synchronously — no future, no copy
             return Ok(py.None());
         }
 
-        // Partial send — need async completion. Copy only the REMAINING data.
+        // Partial send — the writer callback continues from `total_sent`
         let self_ = slf.borrow();
         let future = self_.create_future(py)?;
-        let loop_ref = slf.clone().unbind();
-        let remaining_data = Arc::new(std::sync::Mutex::new(data[total_sent..].to_vec()));
-        let sent_counter = Arc::new(std::sync::Mutex::new(0usize));
-        let future_clone = future.clone_ref(py);
+        let callback = Py::new(
+            py,
+            SockSendallCallback::new(
+                slf.clone().unbind(),
+                future.clone_ref(py),
+                fd,
+                buf,
+                total_sent,
+            ),
+        )?;
+        future
+            .bind(py)
+            .borrow()
+            .add_done_callback(py, callback.clone_ref(py).into_any())?;
 
         let native_callback: Arc<dyn Fn(Python<'_>) -> PyResult<()> + Send + Sync> =
-            Arc::new(move |py: Python<'_>| {
-                let mut sent = sent_counter.lock().unwrap();
-                let data = remaining_data.lock().unwrap();
-
-                while *sent < data.len() {
-                    unsafe {
-                        let n = libc::send(
-                            fd,
-                            data[*sent..].as_ptr() as *const libc::c_void,
-                            data.len() - *sent,
-                            0,
-                        );
-
-                        if n > 0 {
-                            *sent += n as usize;
-                        } else {
-                            let err = std::io::Error::last_os_error();
-                            match err.kind() {
-                                std::io::ErrorKind::WouldBlock => return Ok(()),
-                                _ if err.raw_os_error() == Some(libc::EAGAIN) => return Ok(()),
-                                _ => {
-                                    let py_err = PyErr::new::<pyo3::exceptions::PyOSError, _>(
-                                        err.to_string(),
-                                    );
-                                    let exc_val = py_err.value(py).as_any().clone().unbind();
-                                    future_clone.bind(py).borrow().set_exception(py, exc_val)?;
-                                    loop_ref.bind(py).borrow().remove_writer(py, fd)?;
-                                    return Ok(());
-                                }
-                            }
-                        }
-                    }
-                }
-
-                future_clone.bind(py).borrow().set_result(py, py.None())?;
-                loop_ref.bind(py).borrow().remove_writer(py, fd)?;
-                Ok(())
-            });
-
+            Arc::new(move |py: Python<'_>| callback.get().on_writable(py));
+        #[cfg(target_os = "linux")]
+        {
+            let generation = self_.add_sock_op(fd, &sock, false, native_callback)?;
+            let parker = SockOpParker::new(slf.clone().unbind(), sock, fd, false, generation);
+            future
+                .bind(py)
+                .borrow()
+                .add_done_callback(py, Py::new(py, parker)?.into_any())?;
+        }
+        #[cfg(not(target_os = "linux"))]
         self_.add_writer_native(fd, native_callback)?;
 
         // Return the PendingFuture — Python wrapper will `await` it
@@ -567,7 +475,7 @@ impl VeloxLoop {
     pub fn sock_sendall(
         slf: &Bound<'_, Self>,
         sock: Py<PyAny>,
-        data: &[u8],
+        data: &Bound<'_, PyAny>,
     ) -> PyResult<Py<PyAny>> {
         let py = slf.py();
 
@@ -575,14 +483,83 @@ impl VeloxLoop {
 
         // Check if None (all sent) or a PendingFuture
         if result.is_none(py) {
-            let fut = CompletedFuture::new(py.None());
-            return Ok(Py::new(py, fut)?.into_any());
+            return slf.borrow().resolved_future(py, py.None());
         }
 
         // It's a PendingFuture — return as-is
         Ok(result)
     }
 
+    /// Native half of `sock_sendfile`: `count` bytes of `file` from `offset`
+    /// (to the end when None) with `sendfile()`. Returns the total sent if it
+    /// all went out synchronously, otherwise a PendingFuture resolving to it.
+    /// Raises SendfileNotAvailableError when `file` isn't a regular file.
+    pub fn sock_sendfile_try(
+        slf: &Bound<'_, Self>,
+        sock: Py<PyAny>,
+        file: &Bound<'_, PyAny>,
+        offset: i64,
+        count: Option<usize>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let out_fd: RawFd = sock.getattr(py, "fileno")?.call0(py)?.extract(py)?;
+
+        // BytesIO and friends have no fileno (io.UnsupportedOperation is an OSError)
+        let in_fd: RawFd = match file.call_method0("fileno") {
+            Ok(fd) => fd.extract()?,
+            Err(e)
+                if e.is_instance_of::<pyo3::exceptions::PyAttributeError>(py)
+                    || e.is_instance_of::<pyo3::exceptions::PyOSError>(py) =>
+            {
+                return Err(sendfile_not_available(py, "not a regular file"));
+            }
+            Err(e) => return Err(e),
+        };
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        if unsafe { libc::fstat(in_fd, &mut stat) } != 0
+            || stat.st_mode & libc::S_IFMT != libc::S_IFREG
+        {
+            return Err(sendfile_not_available(py, "not a regular file"));
+        }
+
+        let available = (stat.st_size - offset).max(0) as usize;
+        let total = count.map_or(available, |count| count.min(available));
+        if total == 0 {
+            return 0usize.into_py_any(py);
+        }
+
+        let (sent, eof) = sendfile_some(out_fd, in_fd, offset, total.min(SENDALL_BUDGET))
+            .map_err(|e| sendfile_error(py, e, 0))?;
+        if eof || sent == total {
+            return sent.into_py_any(py);
+        }
+
+        // Partial send — the writer callback continues from `offset + sent`
+        let self_ = slf.borrow();
+        let future = self_.create_future(py)?;
+        let callback = Py::new(
+            py,
+            SockSendfileCallback::new(
+                slf.clone().unbind(),
+                future.clone_ref(py),
+                out_fd,
+                in_fd,
+                offset + sent as i64,
+                total - sent,
+                sent,
+            ),
+        )?;
+        future
+            .bind(py)
+            .borrow()
+            .add_done_callback(py, callback.clone_ref(py).into_any())?;
+
+        let native_callback: Arc<dyn Fn(Python<'_>) -> PyResult<()> + Send + Sync> =
+            Arc::new(move |py: Python<'_>| callback.get().on_writable(py));
+        self_.add_writer_native(out_fd, native_callback)?;
+        Ok(future.into_any())
+    }
+
     pub fn create_connection(
         slf: &Bound<'_, Self>,
         protocol_factory: Py<PyAny>,
@@ -592,27 +569,30 @@ impl VeloxLoop {
     ) -> PyResult<Py<PyAny>> {
         let py = slf.py();
         let self_ = slf.borrow();
+        check_kwargs("create_connection", _kwargs, CREATE_CONNECTION)?;
 
-        let ssl_context = _kwargs
-            .as_ref()
-            .and_then(|kw| kw.get_item("ssl").ok().flatten())
-            .and_then(|v| v.extract::<Py<crate::transports::ssl::SSLContext>>().ok());
+        let ssl_context = client_ssl_kwarg(py, _kwargs)?;
+        let timeout = seconds_kwarg(_kwargs, "timeout")?;
+        let ssl_handshake_timeout = ssl_timeouts(_kwargs, ssl_context.is_some())?;
+        let server_hostname = server_hostname_kwarg(_kwargs, ssl_context.is_some(), host)?;
 
         // Check if a pre-existing socket is provided
-        let sock_obj = _kwargs
-            .as_ref()
-            .and_then(|kw| kw.get_item("sock").ok().flatten());
+        let sock = sock_kwarg(_kwargs, libc::SOCK_STREAM, host.is_some() || port.is_some())?;
 
-        let (stream, fd) = if let Some(sock) = sock_obj {
-            // Use the provided socket
-            let fd = sock.call_method0("fileno")?.extract::<RawFd>()?;
+        let (stream, fd, addr) = if let Some(fd) = sock {
+            check_sock_modifiers(_kwargs, CONNECT_MODIFIERS, Vec::new())?;
 
             // Duplicate the file descriptor so we don't steal it from Python
             use std::os::unix::io::FromRawFd;
             let dup_fd = unsafe { libc::dup(fd) };
             if dup_fd < 0 {
-                return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
-                    "Failed to duplicate file descriptor",
+                let err = std::io::Error::last_os_error();
+                if is_fd_exhaustion(&err) {
+                    return Err(self_.fd_error("create_connection(sock=...)", err));
+                }
+                return Err(crate::utils::os_error_with_message(
+                    &err,
+                    format!("Failed to duplicate file descriptor: {err}"),
                 ));
             }
             let stream = unsafe { std::net::TcpStream::from_raw_fd(dup_fd) };
@@ -620,61 +600,35 @@ impl VeloxLoop {
             // Set nonblocking mode
             stream
                 .set_nonblocking(true)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
+                .map_err(crate::utils::os_error_to_pyerr)?;
 
-            (stream, dup_fd)
+            (stream, dup_fd, None)
         } else {
-            // Create a new socket as before
-            let host = host.unwrap_or("127.0.0.1");
-            let port = port.unwrap_or(0);
-            let addr_str = format!("{}:{}", host, port);
-
-            let mut addrs = std::net::ToSocketAddrs::to_socket_addrs(&addr_str)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
-
-            let addr = addrs
-                .next()
-                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyOSError, _>("No address found"))?;
-
-            let is_ipv6 = addr.is_ipv6();
-            let domain = if is_ipv6 { Domain::IPV6 } else { Domain::IPV4 };
-            let socket = Socket::new(domain, Type::STREAM, None)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
-
-            socket
-                .set_nonblocking(true)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
+            if host.is_none() && port.is_none() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "host and port was not specified and no sock specified",
+                ));
+            }
+            let addr = connect_addr(py, host, port)?;
+            let socket = connecting_socket(py, &self_, "create_connection()", addr, _kwargs)?;
 
             match socket.connect(&addr.into()) {
                 Ok(_) => {}
                 Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
                 #[cfg(unix)]
                 Err(e) if e.raw_os_error() == Some(36) || e.raw_os_error() == Some(115) => {}
-                Err(e) => {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Connection failed: {}",
-                        e
-                    )));
-                }
+                Err(e) => return Err(connect_error(py, &e, Some(addr))),
             }
 
             let stream: std::net::TcpStream = socket.into();
             let fd = stream.as_raw_fd();
 
-            (stream, fd)
+            (stream, fd, Some(addr))
         };
 
-        let server_hostname = _kwargs
-            .as_ref()
-            .and_then(|kw| kw.get_item("server_hostname").ok().flatten())
-            .and_then(|v| v.extract::<String>().ok())
-            .or_else(|| {
-                if ssl_context.is_some() {
-                    host.map(|h| h.to_string())
-                } else {
-                    None
-                }
-            });
+        if let Some(keepalive) = keepalive_kwarg(_kwargs)? {
+            keepalive.apply(fd)?;
+        }
 
         let fut = self_.create_future(py)?;
 
@@ -686,10 +640,15 @@ impl VeloxLoop {
             stream,
             ssl_context,
             server_hostname,
-        );
-        let callback_py = Py::new(py, callback)?.into_any();
+            addr,
+        )
+        .with_ssl_handshake_timeout(ssl_handshake_timeout.or(Some(SSL_HANDSHAKE_TIMEOUT)));
+        let callback_py = Py::new(py, callback)?;
+        if let Some(timeout) = timeout {
+            AsyncConnectCallback::arm_timeout(callback_py.bind(py), timeout)?;
+        }
 
-        self_.add_writer(py, fd, callback_py)?;
+        self_.add_writer(py, fd, callback_py.into_any())?;
 
         Ok(fut.into_any())
     }
@@ -697,122 +656,148 @@ impl VeloxLoop {
     pub fn create_server(
         slf: &Bound<'_, Self>,
         protocol_factory: Py<PyAny>,
-        host: Option<&str>,
+        host: Option<&Bound<'_, PyAny>>,
         port: Option<u16>,
         _kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<PyAny>> {
         let py = slf.py();
-        let self_ = slf.borrow();
         let loop_obj = slf.clone().unbind();
 
-        let host = host.unwrap_or("127.0.0.1");
-        let port = port.unwrap_or(0);
-        let addr = format!("{}:{}", host, port);
-
-        let listener = std::net::TcpListener::bind(&addr)?;
-        listener.set_nonblocking(true)?;
+        let (listeners, keepalive, start_serving) =
+            server_listeners(py, &slf.borrow(), "create_server", host, port, _kwargs)?;
 
         let server = TcpServer::new(
-            listener,
+            listeners,
             loop_obj.clone_ref(py),
             protocol_factory.clone_ref(py),
+            keepalive,
         );
         let server_py = Py::new(py, server)?;
+        if start_serving {
+            TcpServer::begin_serving(server_py.bind(py))?;
+        }
 
-        let on_accept = server_py.getattr(py, "_on_accept")?;
-
-        let fd = server_py.borrow(py).fd().ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Server has no listener")
-        })?;
-
-        self_.add_reader(py, fd, on_accept)?;
-
-        let fut = crate::transports::future::CompletedFuture::new(server_py.into_any());
-
-        Ok(Py::new(py, fut)?.into_any())
+        slf.borrow().resolved_future(py, server_py.into_any())
     }
 
     pub fn start_server(
         slf: &Bound<'_, Self>,
         client_connected_cb: Py<PyAny>,
-        host: Option<&str>,
+        host: Option<&Bound<'_, PyAny>>,
         port: Option<u16>,
         limit: Option<usize>,
         _kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<PyAny>> {
         let py = slf.py();
-        let self_ = slf.borrow();
         let loop_obj = slf.clone().unbind();
 
-        let host = host.unwrap_or("127.0.0.1");
-        let port = port.unwrap_or(0);
-        let addr = format!("{}:{}", host, port);
         let limit = limit.unwrap_or(65536);
-
-        let listener = std::net::TcpListener::bind(&addr)?;
-        listener.set_nonblocking(true)?;
+        let (listeners, keepalive, start_serving) =
+            server_listeners(py, &slf.borrow(), "start_server", host, port, _kwargs)?;
 
         let server = crate::transports::stream_server::StreamServer::new(
-            listener,
+            listeners,
             loop_obj.clone_ref(py),
             client_connected_cb,
             limit,
+            keepalive,
         );
         let server_py = Py::new(py, server)?;
+        if start_serving {
+            crate::transports::stream_server::StreamServer::begin_serving(server_py.bind(py))?;
+        }
 
-        let on_accept = server_py.getattr(py, "_on_accept")?;
-
-        let fd = server_py.borrow(py).get_fd().ok_or_else(|| {
-            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Server has no listener")
-        })?;
-
-        self_.add_reader(py, fd, on_accept)?;
-
-        let fut = crate::transports::future::CompletedFuture::new(server_py.into_any());
-
-        Ok(Py::new(py, fut)?.into_any())
+        slf.borrow().resolved_future(py, server_py.into_any())
     }
 
     pub fn open_connection(
         slf: &Bound<'_, Self>,
-        host: &str,
-        port: u16,
+        host: Option<&str>,
+        port: Option<u16>,
         limit: Option<usize>,
         _kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<PyAny>> {
         let py = slf.py();
         let loop_obj = slf.clone().unbind();
         let limit = limit.unwrap_or(65536);
+        check_kwargs("open_connection", _kwargs, OPEN_CONNECTION)?;
 
-        let addr = format!("{}:{}", host, port);
-        let stream = std::net::TcpStream::connect(&addr)?;
-        stream.set_nonblocking(true)?;
-
-        let reader = Py::new(py, crate::streams::StreamReader::new(Some(limit)))?;
-        let writer = Py::new(
-            py,
-            crate::streams::StreamWriter::new(Some(65536), Some(16384)),
-        )?;
-
-        let transport_py = crate::transports::stream_server::StreamTransport::new(
-            py,
-            loop_obj.clone_ref(py),
-            stream,
-            reader.clone_ref(py),
-            writer.clone_ref(py),
-        )?;
+        // The native streams have no TLS layer; asyncio.open_connection() does
+        if client_ssl_kwarg(py, _kwargs)?.is_some() {
+            return Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+                "open_connection() does not support ssl; use asyncio.open_connection()",
+            ));
+        }
+        ssl_timeouts(_kwargs, false)?;
+        server_hostname_kwarg(_kwargs, false, host)?;
+        let timeout = seconds_kwarg(_kwargs, "timeout")?;
 
-        let transport_clone = transport_py.clone_ref(py);
-        let read_callback =
-            Arc::new(move |py: Python<'_>| transport_clone.bind(py).borrow_mut()._read_ready(py));
-        let fd = transport_py.borrow(py).get_fd();
-        slf.borrow().add_reader_native(fd, read_callback)?;
+        let stream = match sock_kwarg(_kwargs, libc::SOCK_STREAM, host.is_some() || port.is_some())?
+        {
+            Some(fd) => {
+                check_sock_modifiers(_kwargs, CONNECT_MODIFIERS, Vec::new())?;
+                // Duplicate the file descriptor so we don't steal it from Python
+                let dup_fd = unsafe { libc::dup(fd) };
+                if dup_fd < 0 {
+                    let err = std::io::Error::last_os_error();
+                    return Err(slf.borrow().fd_error("open_connection(sock=...)", err));
+                }
+                let stream =
+                    unsafe { <std::net::TcpStream as std::os::fd::FromRawFd>::from_raw_fd(dup_fd) };
+                stream.set_nonblocking(true)?;
+                stream
+            }
+            None => {
+                if host.is_none() && port.is_none() {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                        "host and port was not specified and no sock specified",
+                    ));
+                }
+                // Connect without blocking, as create_connection() does; the
+                // Python side has tried each looked-up address through here
+                let self_ = slf.borrow();
+                let addr = connect_addr(py, host, port)?;
+                let socket = connecting_socket(py, &self_, "open_connection()", addr, _kwargs)?;
+                match socket.connect(&addr.into()) {
+                    Ok(_) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                    #[cfg(unix)]
+                    Err(e) if e.raw_os_error() == Some(36) || e.raw_os_error() == Some(115) => {}
+                    Err(e) => return Err(connect_error(py, &e, Some(addr))),
+                }
+                let stream: std::net::TcpStream = socket.into();
+                if let Some(keepalive) = keepalive_kwarg(_kwargs)? {
+                    keepalive.apply(stream.as_raw_fd())?;
+                }
 
-        let result = (reader.into_any(), writer.into_any());
-        let result_tuple = pyo3::types::PyTuple::new(py, &[result.0, result.1])?;
-        let fut = crate::transports::future::CompletedFuture::new(result_tuple.into());
+                let fd = stream.as_raw_fd();
+                let fut = self_.create_future(py)?;
+                let callback = AsyncConnectCallback::new_with_ssl(
+                    loop_obj,
+                    fut.clone_ref(py),
+                    py.None(),
+                    stream,
+                    None,
+                    None,
+                    Some(addr),
+                )
+                .for_streams(limit);
+                let callback_py = Py::new(py, callback)?;
+                if let Some(timeout) = timeout {
+                    AsyncConnectCallback::arm_timeout(callback_py.bind(py), timeout)?;
+                }
+                self_.add_writer(py, fd, callback_py.into_any())?;
+                return Ok(fut.into_any());
+            }
+        };
+        if let Some(keepalive) = keepalive_kwarg(_kwargs)? {
+            keepalive.apply(stream.as_raw_fd())?;
+        }
 
-        Ok(Py::new(py, fut)?.into_any())
+        let streams = crate::transports::stream_server::StreamTransport::open_client(
+            py, &loop_obj, stream, limit,
+        )?;
+        slf.borrow().resolved_future(py, streams.into_any())
     }
 
     pub fn create_datagram_endpoint(
@@ -825,95 +810,131 @@ impl VeloxLoop {
         let py = slf.py();
         let loop_obj = slf.clone().unbind();
 
-        let allow_broadcast = kwargs
-            .and_then(|k| k.get_item("allow_broadcast").ok().flatten())
-            .and_then(|v| v.extract::<bool>().ok())
-            .unwrap_or(false);
+        check_kwargs("create_datagram_endpoint", kwargs, CREATE_DATAGRAM_ENDPOINT)?;
+        let allow_broadcast = flag_kwarg(kwargs, "allow_broadcast", false)?;
 
-        let reuse_port = kwargs
-            .and_then(|k| k.get_item("reuse_port").ok().flatten())
-            .and_then(|v| v.extract::<bool>().ok())
-            .unwrap_or(false);
+        let max_datagram_size = match kwarg(kwargs, "max_datagram_size")? {
+            Some(value) => match value.extract::<usize>() {
+                Ok(size) if size > 0 => Some(size),
+                _ => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "max_datagram_size should be a positive integer, got {}",
+                        value.repr()?
+                    )));
+                }
+            },
+            None => None,
+        };
 
-        let is_ipv6 = if let Some((ref host, _)) = local_addr {
-            crate::utils::ipv6::is_ipv6_string(host)
-        } else if let Some((ref host, _)) = remote_addr {
-            crate::utils::ipv6::is_ipv6_string(host)
+        let addressed = local_addr.is_some() || remote_addr.is_some();
+        let (udp_socket, remote_sockaddr) = if let Some(fd) =
+            sock_kwarg(kwargs, libc::SOCK_DGRAM, false)?
+        {
+            let problems = [("local_addr", &local_addr), ("remote_addr", &remote_addr)]
+                .into_iter()
+                .filter_map(|(name, addr)| {
+                    addr.as_ref()
+                        .map(|(host, port)| format!("{name}=('{host}', {port})"))
+                })
+                .collect();
+            check_sock_modifiers(kwargs, DATAGRAM_MODIFIERS, problems)?;
+
+            // Duplicate the file descriptor so we don't steal it from Python
+            let dup_fd = unsafe { libc::dup(fd) };
+            if dup_fd < 0 {
+                let err = std::io::Error::last_os_error();
+                return Err(slf
+                    .borrow()
+                    .fd_error("create_datagram_endpoint(sock=...)", err));
+            }
+            let udp_socket =
+                unsafe { <std::net::UdpSocket as std::os::fd::FromRawFd>::from_raw_fd(dup_fd) };
+            udp_socket.set_nonblocking(true)?;
+            let remote_sockaddr = udp_socket.peer_addr().ok();
+            (udp_socket, remote_sockaddr)
         } else {
-            false
-        };
+            if flag_kwarg(kwargs, "reuse_address", false)? {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "Passing `reuse_address=True` is no longer supported, as the usage of \
+                     SO_REUSEPORT in UDP poses a significant security concern.",
+                ));
+            }
+            let reuse_port = reuse_port_kwarg(kwargs)?;
+            if let Some(proto) = kwarg(kwargs, "proto")?
+                && !matches!(proto.extract::<libc::c_int>()?, 0 | libc::IPPROTO_UDP)
+            {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "create_datagram_endpoint() only supports UDP, got proto={}",
+                    proto.repr()?
+                )));
+            }
+            // `flags` only steer the Python side's lookup of a remote host name
+            let family = family_kwarg(kwargs)?;
 
-        let domain = if is_ipv6 { Domain::IPV6 } else { Domain::IPV4 };
-        let socket = Socket::new(domain, Type::DGRAM, Some(Protocol::UDP))
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
+            let is_ipv6 = if let Some((ref host, _)) = local_addr {
+                crate::utils::ipv6::is_ipv6_string(host)
+            } else if let Some((ref host, _)) = remote_addr {
+                crate::utils::ipv6::is_ipv6_string(host)
+            } else {
+                !addressed && family == libc::AF_INET6
+            };
 
-        socket
-            .set_nonblocking(true)
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
+            let domain = if is_ipv6 { Domain::IPV6 } else { Domain::IPV4 };
+            let socket = Socket::new(domain, Type::DGRAM, Some(Protocol::UDP))
+                .map_err(crate::utils::os_error_to_pyerr)?;
 
-        if allow_broadcast {
             socket
-                .set_broadcast(true)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
-        }
-
-        #[cfg(all(unix, not(target_os = "solaris")))]
-        if reuse_port {
-            let fd = socket.as_raw_fd();
-            unsafe {
-                let optval: libc::c_int = 1;
-                let ret = libc::setsockopt(
-                    fd,
-                    libc::SOL_SOCKET,
-                    libc::SO_REUSEPORT,
-                    &optval as *const _ as *const libc::c_void,
-                    std::mem::size_of_val(&optval) as libc::socklen_t,
-                );
-                if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set SO_REUSEPORT: {}",
-                        std::io::Error::last_os_error()
-                    )));
-                }
+                .set_nonblocking(true)
+                .map_err(crate::utils::os_error_to_pyerr)?;
+
+            if allow_broadcast {
+                socket
+                    .set_broadcast(true)
+                    .map_err(crate::utils::os_error_to_pyerr)?;
             }
-        }
 
-        if let Some((host, port)) = local_addr {
-            let addr_str = format!("{}:{}", host, port);
-            let bind_addr: SocketAddr = addr_str.parse().map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                    "Invalid local address: {}",
-                    e
-                ))
-            })?;
-            socket.bind(&bind_addr.into()).map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyOSError, _>(format!("Failed to bind: {}", e))
-            })?;
-        }
+            if reuse_port {
+                crate::socket::set_reuse_port(socket.as_raw_fd())?;
+            }
 
-        let remote_sockaddr = if let Some((host, port)) = remote_addr {
-            let addr_str = format!("{}:{}", host, port);
-            let addr: SocketAddr = addr_str.parse().map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                    "Invalid remote address: {}",
-                    e
-                ))
-            })?;
+            if let Some((host, port)) = local_addr {
+                let addr_str = format!("{}:{}", host, port);
+                let bind_addr: SocketAddr = addr_str.parse().map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid local address: {}",
+                        e
+                    ))
+                })?;
+                socket.bind(&bind_addr.into()).map_err(|e| {
+                    crate::utils::os_error_with_message(&e, format!("Failed to bind: {e}"))
+                })?;
+            }
 
-            socket.connect(&addr.into()).map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyOSError, _>(format!("Failed to connect: {}", e))
-            })?;
-            Some(addr)
-        } else {
-            None
-        };
+            let remote_sockaddr = if let Some((host, port)) = remote_addr {
+                let addr_str = format!("{}:{}", host, port);
+                let addr: SocketAddr = addr_str.parse().map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid remote address: {}",
+                        e
+                    ))
+                })?;
 
-        let udp_socket: std::net::UdpSocket = socket.into();
+                socket.connect(&addr.into()).map_err(|e| {
+                    crate::utils::os_error_with_message(&e, format!("Failed to connect: {e}"))
+                })?;
+                Some(addr)
+            } else {
+                None
+            };
+
+            (socket.into(), remote_sockaddr)
+        };
 
         let protocol = protocol_factory.call0(py)?;
 
-        let factory = DefaultTransportFactory;
+        let factory = slf.borrow().transport_factory(py);
         let loop_py = loop_obj.clone_ref(py).into_any();
+        let fd = udp_socket.as_raw_fd();
 
         let transport_py = factory.create_udp(
             py,
@@ -924,26 +945,319 @@ impl VeloxLoop {
             allow_broadcast,
         )?;
 
-        let fd = transport_py
-            .getattr(py, "fileno")?
-            .call0(py)?
-            .extract::<i32>(py)?;
+        if let Some(size) = max_datagram_size
+            && let Ok(udp) = transport_py.bind(py).cast::<UdpTransport>()
+        {
+            udp.borrow_mut().set_max_datagram_size(size);
+        }
 
         protocol.call_method1(py, "connection_made", (transport_py.clone_ref(py),))?;
 
-        let transport_clone = transport_py.clone_ref(py);
-        let read_callback = Arc::new(move |py: Python<'_>| {
-            let b = transport_clone.bind(py);
-            let udp = b.cast::<UdpTransport>().map_err(|_| {
-                PyErr::new::<pyo3::exceptions::PyTypeError, _>("Expected UdpTransport")
-            })?;
-            udp.borrow()._read_ready(py)
-        });
-        slf.borrow().add_reader_native(fd, read_callback)?;
+        if let Ok(udp) = transport_py.bind(py).cast::<UdpTransport>() {
+            slf.borrow().add_udp_reader(fd, udp.clone().unbind())?;
+        } else if let Ok(read_ready) = transport_py.getattr(py, "_read_ready") {
+            // A transport from a Python factory reads through its own callback
+            slf.borrow().add_reader(py, fd, read_ready)?;
+        }
 
         let result_tuple = PyTuple::new(py, vec![transport_py.into_any(), protocol.into_any()])?;
 
-        let fut = CompletedFuture::new(result_tuple.into());
-        Ok(Py::new(py, fut)?.into_any())
+        slf.borrow()
+            .resolved_future(py, result_tuple.into_any().unbind())
+    }
+}
+
+/// Listener options from the `tcp_defer_accept`/`tcp_fastopen` kwargs
+fn listener_tuning(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Vec<(TcpTuning, u32)>> {
+    let mut tuning = Vec::new();
+    if let Some(kw) = kwargs {
+        for (key, option) in [
+            ("tcp_defer_accept", TcpTuning::DeferAccept),
+            ("tcp_fastopen", TcpTuning::FastOpen),
+        ] {
+            if let Some(value) = kw.get_item(key)?.filter(|v| !v.is_none()) {
+                tuning.push((option, value.extract::<u32>()?));
+            }
+        }
+    }
+    Ok(tuning)
+}
+
+/// The `keepalive=` kwarg of create_server/start_server/create_connection
+fn keepalive_kwarg(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Option<KeepaliveParams>> {
+    match kwargs {
+        Some(kw) => KeepaliveParams::from_py(kw.get_item("keepalive")?.as_ref()),
+        None => Ok(None),
+    }
+}
+
+/// `host` as given to create_server/start_server: None, a str, or a sequence of str
+fn server_hosts(host: Option<&Bound<'_, PyAny>>) -> PyResult<Vec<String>> {
+    let Some(host) = host.filter(|h| !h.is_none()) else {
+        return Ok(vec!["127.0.0.1".to_string()]);
+    };
+    if let Ok(host) = host.extract::<String>() {
+        return Ok(vec![host]);
+    }
+    let hosts = host
+        .try_iter()
+        .map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "host must be a str or a sequence of str",
+            )
+        })?
+        .map(|h| h?.extract::<String>())
+        .collect::<PyResult<Vec<_>>>()?;
+    if hosts.is_empty() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "host sequence is empty",
+        ));
+    }
+    Ok(hosts)
+}
+
+/// How create_server/start_server set up each listening socket
+struct ListenOptions {
+    /// AF_UNSPEC, or the one family to bind addresses of
+    family: libc::c_int,
+    backlog: i32,
+    reuse_address: bool,
+    reuse_port: bool,
+    /// Set before `listen()`
+    tuning: Vec<(TcpTuning, u32)>,
+    /// Set before `bind()`
+    proxy: ProxyOptions,
+}
+
+/// Listening sockets for create_server/start_server from host/port or
+/// `sock=`, the keep-alive for accepted connections, and whether to start
+/// accepting at once (`start_serving=`)
+fn server_listeners(
+    py: Python<'_>,
+    loop_: &VeloxLoop,
+    method: &str,
+    host: Option<&Bound<'_, PyAny>>,
+    port: Option<u16>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<(Vec<std::net::TcpListener>, Option<KeepaliveParams>, bool)> {
+    check_kwargs(method, kwargs, CREATE_SERVER)?;
+    // Refused rather than serving plaintext to clients that expect TLS
+    if flag_kwarg(kwargs, "ssl", false)? {
+        return Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            format!("{method}() does not support ssl; terminate TLS in front of the server"),
+        ));
+    }
+    ssl_timeouts(kwargs, false)?;
+    if let Some(flags) = kwarg(kwargs, "flags")?
+        && flags.extract::<libc::c_int>()? & !libc::AI_PASSIVE != 0
+    {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "{method}() supports no getaddrinfo() flags besides AI_PASSIVE, got {}",
+            flags.repr()?
+        )));
+    }
+
+    // asyncio's keep_alive=True is SO_KEEPALIVE with the OS defaults
+    let keepalive = match (kwarg(kwargs, "keep_alive")?, keepalive_kwarg(kwargs)?) {
+        (Some(_), Some(_)) => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "keep_alive and keepalive can not be used together",
+            ));
+        }
+        (Some(keep_alive), None) => keep_alive.is_truthy()?.then(KeepaliveParams::default),
+        (None, keepalive) => keepalive,
+    };
+
+    let options = ListenOptions {
+        family: family_kwarg(kwargs)?,
+        backlog: kwarg(kwargs, "backlog")?
+            .map(|v| v.extract::<i32>())
+            .transpose()?
+            .unwrap_or(LISTEN_BACKLOG),
+        reuse_address: flag_kwarg(kwargs, "reuse_address", true)?,
+        reuse_port: reuse_port_kwarg(kwargs)?,
+        tuning: listener_tuning(kwargs)?,
+        proxy: ProxyOptions::from_kwargs(kwargs)?,
+    };
+    let addressed = host.is_some_and(|h| !h.is_none()) || port.is_some();
+    let listeners = match sock_kwarg(kwargs, libc::SOCK_STREAM, addressed)? {
+        Some(fd) => {
+            check_sock_modifiers(kwargs, SERVER_MODIFIERS, Vec::new())?;
+            vec![adopt_listener(py, loop_, method, fd, &options)?]
+        }
+        None => bind_listeners(
+            py,
+            &server_hosts(host)?,
+            port.unwrap_or(0),
+            &options,
+            loop_.debug_enabled(),
+        )?,
+    };
+    Ok((
+        listeners,
+        keepalive,
+        flag_kwarg(kwargs, "start_serving", true)?,
+    ))
+}
+
+/// One non-blocking listener per resolved address of every host; addresses
+/// several hosts resolve to are bound once. If any bind fails, the listeners
+/// bound so far are closed and a single OSError names the failed address.
+fn bind_listeners(
+    py: Python<'_>,
+    hosts: &[String],
+    port: u16,
+    options: &ListenOptions,
+    debug: bool,
+) -> PyResult<Vec<std::net::TcpListener>> {
+    let mut addrs: Vec<SocketAddr> = Vec::new();
+    for host in hosts {
+        for addr in std::net::ToSocketAddrs::to_socket_addrs(&(host.as_str(), port))? {
+            let wanted = match options.family {
+                libc::AF_INET => addr.is_ipv4(),
+                libc::AF_INET6 => addr.is_ipv6(),
+                _ => true,
+            };
+            if wanted && !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+    }
+    if addrs.is_empty() {
+        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>("No address found"));
+    }
+
+    let mut listeners = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)
+            .and_then(|socket| {
+                socket.set_reuse_address(options.reuse_address)?;
+                socket.set_nonblocking(true)?;
+                // As in asyncio: a v6 listener leaves IPv4 to a listener of
+                // its own, so both can share a port
+                if addr.is_ipv6() {
+                    socket.set_only_v6(true)?;
+                }
+                Ok(socket)
+            })
+            .map_err(crate::utils::os_error_to_pyerr)?;
+        if options.reuse_port {
+            crate::socket::set_reuse_port(socket.as_raw_fd())?;
+        }
+        options.proxy.apply(socket.as_raw_fd(), addr.is_ipv6())?;
+        for (option, value) in &options.tuning {
+            option.set_or_warn(py, socket.as_raw_fd(), *value, debug)?;
+        }
+        match socket
+            .bind(&addr.into())
+            .and_then(|_| socket.listen(options.backlog))
+        {
+            Ok(()) => listeners.push(socket.into()),
+            // Dropping `listeners` on return closes what was already bound
+            Err(e) => return Err(bind_error(py, addr, &e)),
+        }
+    }
+    Ok(listeners)
+}
+
+/// The caller's socket given as `sock=`, duplicated so Python keeps its own,
+/// made non-blocking and listening with the requested backlog
+fn adopt_listener(
+    py: Python<'_>,
+    loop_: &VeloxLoop,
+    method: &str,
+    fd: RawFd,
+    options: &ListenOptions,
+) -> PyResult<std::net::TcpListener> {
+    use std::os::unix::io::FromRawFd;
+    let dup_fd = unsafe { libc::dup(fd) };
+    if dup_fd < 0 {
+        let err = std::io::Error::last_os_error();
+        return Err(loop_.fd_error(&format!("{method}(sock=...)"), err));
+    }
+    let socket = unsafe { Socket::from_raw_fd(dup_fd) };
+    socket.set_nonblocking(true)?;
+    for (option, value) in &options.tuning {
+        option.set_or_warn(py, dup_fd, *value, loop_.debug_enabled())?;
+    }
+    socket.listen(options.backlog)?;
+    Ok(socket.into())
+}
+
+/// The OSError for a failed bind, naming the address like asyncio does
+fn bind_error(py: Python<'_>, addr: SocketAddr, err: &std::io::Error) -> PyErr {
+    let shown = crate::utils::ipv6::socket_addr_to_tuple(py, addr)
+        .and_then(|shown| Ok(shown.bind(py).repr()?.to_string()))
+        .unwrap_or_else(|_| addr.to_string());
+    crate::utils::os_error_with_message(
+        err,
+        format!("error while attempting to bind on address {shown}: {err}"),
+    )
+}
+
+/// The `local_addr=(host, port)` kwarg of create_connection as an address of
+/// the connecting socket's family. The host must be an address literal: the
+/// Python side resolves names through `getaddrinfo()` first
+fn local_addr_kwarg(
+    kwargs: Option<&Bound<'_, PyDict>>,
+    ipv6: bool,
+) -> PyResult<Option<SocketAddr>> {
+    let Some(value) = kwargs
+        .map(|kw| kw.get_item("local_addr"))
+        .transpose()?
+        .flatten()
+    else {
+        return Ok(None);
+    };
+    if value.is_none() {
+        return Ok(None);
+    }
+    let family = if ipv6 { libc::AF_INET6 } else { libc::AF_INET };
+    crate::utils::ipv6::parse_address_tuple(value.cast::<PyTuple>()?, family).map(Some)
+}
+
+/// The address a connect to `(host, port)` goes to. The host must be an
+/// address literal (a bare IPv6 one included): the Python side looks names up
+/// through `getaddrinfo()` first
+fn connect_addr(py: Python<'_>, host: Option<&str>, port: Option<u16>) -> PyResult<SocketAddr> {
+    let address = (host.unwrap_or("127.0.0.1"), port.unwrap_or(0)).into_pyobject(py)?;
+    crate::utils::ipv6::parse_address_tuple(&address, libc::AF_UNSPEC)
+}
+
+/// A non-blocking socket for connecting to `addr`, with the `transparent=`,
+/// `freebind=`, `local_addr=` and `fastopen=` kwargs applied
+fn connecting_socket(
+    py: Python<'_>,
+    loop_: &VeloxLoop,
+    op: &str,
+    addr: SocketAddr,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Socket> {
+    let is_ipv6 = addr.is_ipv6();
+    let domain = if is_ipv6 { Domain::IPV6 } else { Domain::IPV4 };
+    let socket = Socket::new(domain, Type::STREAM, None).map_err(|e| {
+        if is_fd_exhaustion(&e) {
+            loop_.fd_error(op, e)
+        } else {
+            crate::utils::os_error_to_pyerr(e)
+        }
+    })?;
+
+    socket
+        .set_nonblocking(true)
+        .map_err(crate::utils::os_error_to_pyerr)?;
+
+    // transparent=True lets local_addr be a non-local source address
+    ProxyOptions::from_kwargs(kwargs)?.apply(socket.as_raw_fd(), is_ipv6)?;
+    if let Some(local_addr) = local_addr_kwarg(kwargs, is_ipv6)? {
+        socket
+            .bind(&local_addr.into())
+            .map_err(|e| bind_error(py, local_addr, &e))?;
+    }
+
+    // With a cached cookie the first write rides in the SYN
+    if flag_kwarg(kwargs, "fastopen", false)? {
+        TcpTuning::FastOpenConnect.set_or_warn(py, socket.as_raw_fd(), 1, loop_.debug_enabled())?;
     }
+    Ok(socket)
 }
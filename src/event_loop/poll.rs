@@ -1,37 +1,31 @@
-use crate::event_loop::VeloxLoop;
+use crate::constants::IDLE_TIMEOUT_TICK_NS;
+use crate::event_loop::{ExceptionContext, VeloxLoop};
 use crate::handles::{Handle, IoCallback};
 use crate::poller::{PlatformEvent, PollerEvent};
+use crate::transports::tcp::TcpTransport;
 use crate::utils::VeloxResult;
 use pyo3::prelude::*;
-use pyo3::types::PyDict;
-use std::time::Duration;
-
-/// Platform events - on all platforms we use native events
-pub(crate) struct PlatformEvents;
-
-impl PlatformEvents {
-    pub fn new() -> Self {
-        Self
-    }
-}
+use std::os::fd::RawFd;
+use std::time::{Duration, Instant};
 
 impl VeloxLoop {
     /// single iteration of the event loop
     #[inline(always)]
-    pub(crate) fn _run_once(
-        &self,
-        py: Python<'_>,
-        _events: &mut PlatformEvents,
-    ) -> VeloxResult<()> {
-        let has_callbacks = !self.callbacks.is_empty();
+    pub(crate) fn _run_once(&self, py: Python<'_>) -> VeloxResult<()> {
+        // Callbacks held back by `max_callbacks_per_tick` count as ready too
+        let has_callbacks = !self.callbacks.is_empty() || !self.callback_buffer.borrow().is_empty();
 
         // Calculate timeout
-        let timeout = if has_callbacks {
+        // Ready callbacks only shorten the poll to zero, never skip it, so a
+        // chain of callbacks scheduling each other can't hold back I/O.
+        // A virtual clock only moves with advance_time(), so a test-mode loop
+        // never sleeps waiting for a timer; its I/O is polled without blocking
+        let timeout = if has_callbacks || self.virtual_now.is_some() {
             Some(Duration::ZERO)
         } else {
             let mut timers = self.timers.borrow_mut();
             if let Some(next) = timers.next_expiry() {
-                let now_ns = (self.time() * 1_000_000_000.0) as u64;
+                let now_ns = self.now_ns();
                 if next > now_ns {
                     Some(Duration::from_nanos(next - now_ns))
                 } else {
@@ -42,61 +36,198 @@ impl VeloxLoop {
                 Some(Duration::from_millis(10))
             }
         };
+        // Wake up for the next idle timeout deadline too
+        let timeout = match self.idle_timeouts.borrow().next_due() {
+            Some(tick) if timeout != Some(Duration::ZERO) => {
+                let due = (tick * IDLE_TIMEOUT_TICK_NS).saturating_sub(self.now_ns());
+                let due = Duration::from_nanos(due);
+                Some(timeout.map_or(due, |t| t.min(due)))
+            }
+            _ => timeout,
+        };
+        // Parked sock_* polls expire by iterations: don't sleep long on them
+        #[cfg(target_os = "linux")]
+        let timeout = if self.parked_sock_ops.borrow().is_empty() {
+            timeout
+        } else {
+            timeout.map(|t| t.min(Duration::from_millis(10)))
+        };
 
         // Poll - use atomic state for lock-free polling flag
         self.atomic_state.set_polling(true);
 
         // Use io-uring based polling on Linux
-        // Release GIL during blocking poll to allow other threads to run
-        let events = py.detach(|| self.poller.borrow_mut().poll_native(timeout));
+        // Release GIL during blocking poll to allow other threads to run.
+        // The event buffer is taken out of its cell for the same reason as
+        // the callback batch, and put back once dispatched
+        let mut events = std::mem::take(&mut *self.event_buffer.borrow_mut());
+        let result = py.detach(|| self.poller.borrow_mut().poll_into(timeout, &mut events));
         self.atomic_state.set_polling(false);
 
-        match events {
-            Ok(evs) => {
-                self._process_native_events(py, evs)?;
+        // Activity dispatched below is stamped with the tick it was polled in
+        let idle_now = if self.idle_timeouts.borrow().is_empty() {
+            None
+        } else {
+            Some(self.idle_tick())
+        };
+
+        let result = result.and_then(|()| self._process_native_events(py, &events));
+        let polled = events.len();
+        events.clear();
+        *self.event_buffer.borrow_mut() = events;
+        result?;
+
+        // Settle file operations whose completions arrived with this poll
+        #[cfg(target_os = "linux")]
+        if self.poller.borrow().has_completions() {
+            self.settle_file_ops(py)?;
+        }
+
+        // Process Timers - use C API for callback invocation (no PyTuple allocation).
+        // Without any there is nothing to expire, so an idle tick skips the clock;
+        // the wheel catches up with the time that passed on the next pop
+        if !self.timers.borrow().is_empty() {
+            let now_ns = self.now_ns();
+            let expired = self.timers.borrow_mut().pop_expired(now_ns, 0);
+            let debug = self.debug_enabled();
+            for entry in expired {
+                let started = debug.then(Instant::now);
+                // Use C API: avoids PyTuple::new() overhead and trait dispatch
+                unsafe {
+                    crate::ffi_utils::call_callback_ignore_err(
+                        entry.callback.as_ptr(),
+                        &entry.args,
+                    );
+                }
+                if let Some(started) = started {
+                    self._check_slow_callback(py, &entry.callback, started)?;
+                }
             }
-            Err(e) => return Err(e),
         }
 
-        // Process Timers - use C API for callback invocation (no PyTuple allocation)
-        let now_ns = (self.time() * 1_000_000_000.0) as u64;
-        let expired = self.timers.borrow_mut().pop_expired(now_ns, 0);
-        for entry in expired {
-            // Use C API: avoids PyTuple::new() overhead and trait dispatch
-            unsafe {
-                crate::ffi_utils::call_callback_ignore_err(
-                    entry.callback.as_ptr(),
-                    &entry.args,
-                );
+        if let Some(now) = idle_now {
+            self._expire_idle_timeouts(py, now)?;
+        }
+
+        // I/O callbacks and timers may have scheduled some since the poll
+        if !self.callbacks.is_empty() || !self.callback_buffer.borrow().is_empty() {
+            self._run_callbacks(py)?;
+        }
+
+        // Give back what a spike of callbacks or events left in the buffers
+        self.callback_buffer_trim
+            .borrow_mut()
+            .tick(&mut self.callback_buffer.borrow_mut());
+        self.pending_ios_trim
+            .borrow_mut()
+            .tick(&mut self.pending_ios.borrow_mut());
+
+        // Send whatever write coalescing held back during this iteration
+        self.flush_coalesced_writers(py)?;
+
+        // Polls parked by cancelled sock_* calls expire after a few iterations
+        #[cfg(target_os = "linux")]
+        self.purge_parked_sock_ops();
+
+        // Recycle internal futures that finished and were let go this iteration
+        self.reclaim_futures(py);
+
+        self.publish_stats(polled);
+
+        Ok(())
+    }
+
+    /// Let the transports whose idle timeout deadline came due check it; those
+    /// still within their timeouts go back into the wheel
+    fn _expire_idle_timeouts(&self, py: Python<'_>, now: u64) -> VeloxResult<()> {
+        let due = self.idle_timeouts.borrow_mut().advance(now);
+        for key in due {
+            let transport = self.idle_timeouts.borrow().get(key).map(|t| t.clone_ref(py));
+            let Some(transport) = transport else {
+                continue;
+            };
+            match transport.expire(py, now) {
+                Ok(Some(deadline)) => {
+                    self.idle_timeouts.borrow_mut().schedule(key, deadline);
+                }
+                // Closed, and out of the wheel with it
+                Ok(None) => {}
+                // Left out of the slots until the transport disarms
+                Err(e) => {
+                    ExceptionContext::new("Exception in transport idle timeout")
+                        .exception(e.value(py))
+                        .report(py, self)?;
+                }
             }
         }
+        Ok(())
+    }
 
-        // Process Callbacks (call_soon) - lock-free drain via crossbeam
-        let mut cb_batch = self.callback_buffer.borrow_mut();
-        cb_batch.clear();
-        self.callbacks.swap_into(&mut *cb_batch);
+    /// Process Callbacks (call_soon) - single drain point per iteration, FIFO by seq.
+    fn _run_callbacks(&self, py: Python<'_>) -> VeloxResult<()> {
+        // The batch is taken out of its cell so callbacks can re-enter the loop
+        // (including a nested `_run_once`) without hitting a held borrow.
+        // Whatever `max_callbacks_per_tick` left last time is still at the front.
+        let mut cb_batch = std::mem::take(&mut *self.callback_buffer.borrow_mut());
+        self.callbacks.swap_into(&mut cb_batch);
+        self.callback_buffer_trim.borrow_mut().note(&cb_batch);
+        let run = match self.max_callbacks_per_tick {
+            Some(limit) if limit < cb_batch.len() => {
+                self.deferred_callback_ticks
+                    .set(self.deferred_callback_ticks.get() + 1);
+                limit
+            }
+            _ => cb_batch.len(),
+        };
 
-        for cb in cb_batch.drain(..) {
+        let debug = self.debug_enabled();
+        for cb in cb_batch.drain(..run) {
+            let started = debug.then(Instant::now);
             // Use C API: for 0-arg case uses PyObject_CallNoArgs (no tuple at all)
             unsafe {
                 if let Err(e) = crate::ffi_utils::call_callback(py, cb.callback.as_ptr(), &cb.args) {
-                    let context = PyDict::new(py);
-                    context.set_item("message", "Exception in callback")?;
-                    context.set_item("exception", e.value(py))?;
-                    self.call_exception_handler(py, context.unbind())?;
+                    ExceptionContext::new("Exception in callback")
+                        .exception(e.value(py))
+                        .report(py, self)?;
                 }
             }
+            if let Some(started) = started {
+                self._check_slow_callback(py, &cb.callback, started)?;
+            }
         }
+        // A nested `_run_once` may have left callbacks of its own; they were
+        // scheduled after ours
+        let nested = std::mem::replace(&mut *self.callback_buffer.borrow_mut(), cb_batch);
+        self.callback_buffer.borrow_mut().extend(nested);
+        Ok(())
+    }
 
+    /// Debug mode: report a callback that held the loop for
+    /// `slow_callback_duration` or longer, like asyncio's "Executing ... took"
+    fn _check_slow_callback(
+        &self,
+        py: Python<'_>,
+        callback: &Py<PyAny>,
+        started: Instant,
+    ) -> VeloxResult<()> {
+        let took = started.elapsed().as_secs_f64();
+        if took < self.get_slow_callback_duration() {
+            return Ok(());
+        }
+        let callback = callback.bind(py);
+        let repr = callback.repr()?;
+        ExceptionContext::new(format!("Executing {repr} took {took:.3} seconds"))
+            .with("callback", callback)
+            .report(py, self)?;
         Ok(())
     }
 
     /// Process io-uring completion events
     #[inline(always)]
-    fn _process_native_events(
+    pub(crate) fn _process_native_events(
         &self,
         py: Python<'_>,
-        events: Vec<PlatformEvent>,
+        events: &[PlatformEvent],
     ) -> VeloxResult<()> {
         if events.is_empty() {
             return Ok(());
@@ -106,15 +237,9 @@ impl VeloxLoop {
             let event = &events[0];
             let fd = event.fd;
 
-            // Handle error events - unregister the FD if there's an error
             #[cfg(target_os = "linux")]
             if event.error {
-                // On error, remove both reader and writer
-                let mut handles = self.handles.borrow_mut();
-                handles.remove_reader(fd);
-                handles.remove_writer(fd);
-                let _ = self.poller.borrow_mut().delete(fd);
-                return Ok(());
+                return self._poll_failed(py, fd);
             }
 
             // Clone callbacks to avoid borrow issues - direct extraction, no Vec needed
@@ -122,27 +247,32 @@ impl VeloxLoop {
                 let handles = self.handles.borrow();
                 (handles.get_reader(fd), handles.get_writer(fd))
             };
-            if let Some(cb) = r_cb {
-                cb.execute(py)?;
+            if let Some(cb) = r_cb
+                && let Err(e) = cb.execute(py)
+            {
+                self._report_io_error(py, fd, &cb, e);
             }
-            if let Some(cb) = w_cb {
-                cb.execute(py)?;
+            // The reader may have replaced or removed the writer
+            if let Some(cb) = w_cb
+                && self.handles.borrow().is_current(fd, false, &cb)
+                && let Err(e) = cb.execute(py)
+            {
+                self._report_io_error(py, fd, &cb, e);
             }
             // Re-arm the FD for io-uring (poll_add is oneshot)
             // may have removed themselves (e.g., oneshot sock_recv callbacks)
-            let (still_has_reader, still_has_writer) = {
-                let handles = self.handles.borrow();
-                handles.get_states(fd)
-            };
+            let interest = self.handles.borrow().interest(fd);
 
-            if still_has_reader || still_has_writer {
-                let ev = PollerEvent::new(fd as usize, still_has_reader, still_has_writer);
+            if let Some((readable, writable)) = interest {
+                let ev = PollerEvent::new(readable, writable);
                 let mut poller = self.poller.borrow_mut();
 
                 // Check FD state: is it already registered or not
                 #[cfg(target_os = "linux")]
                 {
-                    if self.oneshot_disabled.borrow().contains(&fd) {
+                    if poller.is_armed_for(fd, ev) {
+                        // A callback registered exactly this poll already
+                    } else if self.oneshot_disabled.borrow().contains(&fd) {
                         poller.rearm_oneshot(fd, ev)?;
                     } else {
                         // FD is new or has been removed → needs to be registered again
@@ -154,7 +284,8 @@ impl VeloxLoop {
             return Ok(());
         }
 
-        let mut pending = self.pending_ios.borrow_mut();
+        // Taken out of the cell for the same reason as the callback batch
+        let mut pending = std::mem::take(&mut *self.pending_ios.borrow_mut());
         pending.clear();
 
         let event_count = events.len();
@@ -163,14 +294,23 @@ impl VeloxLoop {
             pending.reserve(event_count - capacity);
         }
 
+        // Events on high-priority fds (server listeners), dispatched before the
+        // rest so a tick full of data events doesn't delay accepts
+        let mut urgent = Vec::new();
+        let mut failed = Vec::new();
         {
             let handles = self.handles.borrow();
             for event in events.iter() {
                 let fd = event.fd;
+                if event.error {
+                    failed.push(fd);
+                    continue;
+                }
                 if let Some((r_handle, w_handle)) = handles.get_state_owned(fd) {
-                    // Save is_some state before filter() consumes the Option
-                    let has_reader = r_handle.is_some();
-                    let has_writer = w_handle.is_some();
+                    let high_priority = r_handle
+                        .as_ref()
+                        .or(w_handle.as_ref())
+                        .is_some_and(|h| h.high_priority);
 
                     // Use .filter() on owned Option<Handle> - avoids second clone
                     // that was previously done by .as_ref().filter().cloned()
@@ -185,58 +325,173 @@ impl VeloxLoop {
                         None
                     };
 
-                    pending.push((
-                        fd,
-                        reader_cb,
-                        writer_cb,
-                        has_reader,
-                        has_writer,
-                    ));
+                    let entry = (fd, reader_cb, writer_cb);
+                    if high_priority {
+                        urgent.push(entry);
+                    } else {
+                        pending.push(entry);
+                    }
                 }
             }
         }
 
-        let mut python_callbacks: Vec<Handle> = Vec::new();
+        for fd in failed {
+            self._poll_failed(py, fd)?;
+        }
+
+        // Urgent Python callbacks run right away rather than joining the batch
+        for (fd, r_h, w_h) in urgent {
+            self._dispatch_io_event(py, fd, [r_h, w_h], None);
+        }
+
+        // (fd, is_reader, handle) so each one can be checked against the
+        // registration it came from right before it runs
+        let mut python_callbacks: Vec<(RawFd, bool, Handle)> = Vec::new();
+
+        self.pending_ios_trim.borrow_mut().note(&pending);
 
         // Use drain() to consume pending_ios, moving handles instead of cloning
-        for (fd, r_h, w_h, _has_r, _has_w) in pending.drain(..) {
-            if let Some(h) = r_h {
-                match &h.callback {
-                    IoCallback::Native(cb) => {
-                        let _ = cb(py);
-                    } // Native first, no GIL hold
-                    _ => python_callbacks.push(h), // Move instead of clone
+        for (fd, r_h, w_h) in pending.drain(..) {
+            self._dispatch_io_event(py, fd, [r_h, w_h], Some(&mut python_callbacks));
+        }
+        *self.pending_ios.borrow_mut() = pending;
+
+        // With several TCP transports ready, every socket is read first with
+        // no Python involved, then the chunks go out back to back along with
+        // the other callbacks, in the order the events came in
+        let tcp_reads = python_callbacks
+            .iter()
+            .filter(|(_, _, cb)| matches!(cb.callback, IoCallback::TcpRead(_)))
+            .count();
+        let mut batch = Vec::with_capacity(python_callbacks.len());
+        let mut prefetched = 0;
+        for (fd, is_reader, cb) in python_callbacks {
+            let read = match &cb.callback {
+                IoCallback::TcpRead(tcp)
+                    if tcp_reads > 1 && self.handles.borrow().is_current(fd, is_reader, &cb) =>
+                {
+                    TcpTransport::prefetch_read(tcp.bind(py))
                 }
-            }
-            if let Some(h) = w_h {
-                match &h.callback {
-                    IoCallback::Native(cb) => {
-                        let _ = cb(py);
-                    }
-                    _ => python_callbacks.push(h), // Move instead of clone
+                _ => None,
+            };
+            prefetched += read.is_some() as u64;
+            batch.push((fd, is_reader, cb, read));
+        }
+        if prefetched > 0 {
+            self.batched_read_ticks
+                .set(self.batched_read_ticks.get() + 1);
+            self.batched_reads
+                .set(self.batched_reads.get() + prefetched);
+        }
+
+        // Execute batched Python callbacks at end (one GIL hold)
+        for (fd, is_reader, cb, read) in batch {
+            let result = match (read, &cb.callback) {
+                // Already taken off the socket, so delivered even if the
+                // reader went away since
+                (Some(read), IoCallback::TcpRead(tcp)) => {
+                    TcpTransport::deliver_prefetched(tcp.bind(py), read)
                 }
+                // Replacement via add_reader/add_writer takes effect immediately,
+                // even for events collected before it happened
+                _ if !self.handles.borrow().is_current(fd, is_reader, &cb) => continue,
+                _ => cb.execute(py),
+            };
+            if let Err(e) = result {
+                self._report_io_error(py, fd, &cb, e);
             }
+        }
 
-            // Re-arm the FD for io-uring (poll_add is oneshot)
-            // CRITICAL: Re-check handles state AFTER callback execution since callbacks
-            // may have removed themselves (e.g., oneshot sock_recv callbacks)
-            let (still_has_reader, still_has_writer) = {
-                let handles = self.handles.borrow();
-                handles.get_states(fd)
-            };
+        Ok(())
+    }
+
+    /// The poll on `fd` itself failed: unregister it, and let a TCP transport
+    /// reading from or monitoring it report the error through connection_lost
+    fn _poll_failed(&self, py: Python<'_>, fd: RawFd) -> VeloxResult<()> {
+        let reader = {
+            let mut handles = self.handles.borrow_mut();
+            let reader = handles.get_reader(fd);
+            handles.remove_reader(fd);
+            handles.remove_writer(fd);
+            reader
+        };
+        let _ = self.poller.borrow_mut().delete(fd);
+        if let Some(
+            handle @ Handle {
+                callback: IoCallback::TcpRead(tcp) | IoCallback::TcpError(tcp),
+                ..
+            },
+        ) = &reader
+            && let Err(e) = crate::transports::tcp::TcpTransport::_error_ready(tcp.bind(py))
+        {
+            self._report_io_error(py, fd, handle, e);
+        }
+        Ok(())
+    }
 
-            if still_has_reader || still_has_writer {
-                let ev = PollerEvent::new(fd as usize, still_has_reader, still_has_writer);
-                let _ = self.poller.borrow_mut().rearm_oneshot(fd, ev);
+    /// Hand an exception raised by the reader or writer of `fd` to the
+    /// exception handler, along with the transport or callback behind it
+    fn _report_io_error(&self, py: Python<'_>, fd: RawFd, handle: &Handle, err: PyErr) {
+        let context = ExceptionContext::new(format!("Exception in callback for fd {fd}"))
+            .exception(err.value(py))
+            .with("fd", &pyo3::types::PyInt::new(py, fd));
+        let context = match &handle.callback {
+            IoCallback::Python(cb) => context.with("callback", cb.bind(py)),
+            IoCallback::Native(_) => context,
+            IoCallback::TcpRead(tcp) | IoCallback::TcpWrite(tcp) | IoCallback::TcpError(tcp) => {
+                context.transport(tcp.bind(py))
             }
+            IoCallback::UdpRead(udp) => context.transport(udp.bind(py)),
+        };
+        if let Err(e) = context.report(py, self) {
+            e.print(py);
         }
-        // Execute batched Python callbacks at end (one GIL hold)
-        for cb in python_callbacks {
-            if let Err(e) = cb.execute(py) {
-                e.print(py);
+    }
+
+    /// Run the native callbacks of one ready fd and re-arm it. Python callbacks
+    /// are moved to `deferred` when given, otherwise they run here too.
+    #[inline(always)]
+    fn _dispatch_io_event(
+        &self,
+        py: Python<'_>,
+        fd: RawFd,
+        handles: [Option<Handle>; 2],
+        mut deferred: Option<&mut Vec<(RawFd, bool, Handle)>>,
+    ) {
+        for (is_reader, handle) in [true, false].into_iter().zip(handles) {
+            let Some(h) = handle else { continue };
+            match (&h.callback, deferred.as_deref_mut()) {
+                // Native first, no GIL hold; skipped if an earlier callback
+                // this tick removed or replaced it
+                (IoCallback::Native(cb), _) => {
+                    if self.handles.borrow().is_current(fd, is_reader, &h)
+                        && let Err(e) = cb(py)
+                    {
+                        self._report_io_error(py, fd, &h, e);
+                    }
+                }
+                (_, Some(deferred)) => deferred.push((fd, is_reader, h)), // Move instead of clone
+                (_, None) => {
+                    if self.handles.borrow().is_current(fd, is_reader, &h)
+                        && let Err(e) = h.execute(py)
+                    {
+                        self._report_io_error(py, fd, &h, e);
+                    }
+                }
             }
         }
 
-        Ok(())
+        // Re-arm the FD for io-uring (poll_add is oneshot)
+        // CRITICAL: Re-check handles state AFTER callback execution since callbacks
+        // may have removed themselves (e.g., oneshot sock_recv callbacks)
+        let interest = self.handles.borrow().interest(fd);
+
+        if let Some((readable, writable)) = interest {
+            let ev = PollerEvent::new(readable, writable);
+            let mut poller = self.poller.borrow_mut();
+            if !poller.is_armed_for(fd, ev) {
+                let _ = poller.rearm_oneshot(fd, ev);
+            }
+        }
     }
 }
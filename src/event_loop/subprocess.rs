@@ -0,0 +1,253 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyString, PyTuple};
+use std::ffi::OsString;
+use std::os::fd::OwnedFd;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+
+use crate::event_loop::VeloxLoop;
+use crate::transports::subprocess::{
+    StdioSpec, SubprocessTransport, cloexec_pipe, dup_fd, pidfd_open,
+};
+
+/// `os.fsencode()` for program names, arguments, cwd and environment entries
+fn fsencode(value: &Bound<'_, PyAny>) -> PyResult<OsString> {
+    let encoded = value.py().import("os")?.call_method1("fsencode", (value,))?;
+    Ok(OsString::from_vec(encoded.extract::<Vec<u8>>()?))
+}
+
+fn value_error(msg: &str) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(msg.to_string())
+}
+
+/// Remove and return `kwargs[name]`
+fn pop_kwarg<'py>(
+    kwargs: Option<&Bound<'py, PyDict>>,
+    name: &str,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let Some(kwargs) = kwargs else {
+        return Ok(None);
+    };
+    let value = kwargs.get_item(name)?;
+    if value.is_some() {
+        kwargs.del_item(name)?;
+    }
+    Ok(value)
+}
+
+/// Child-side fd for stdin plus the parent's write end when piped
+fn input_fds(spec: StdioSpec) -> PyResult<(Stdio, Option<OwnedFd>)> {
+    Ok(match spec {
+        StdioSpec::Inherit => (Stdio::inherit(), None),
+        StdioSpec::DevNull => (Stdio::null(), None),
+        StdioSpec::Pipe => {
+            let (read, write) = cloexec_pipe().map_err(crate::utils::os_error_to_pyerr)?;
+            (Stdio::from(read), Some(write))
+        }
+        StdioSpec::Fd(fd) => (
+            Stdio::from(dup_fd(fd).map_err(crate::utils::os_error_to_pyerr)?),
+            None,
+        ),
+        StdioSpec::Stdout => return Err(value_error("STDOUT can only be used for stderr")),
+    })
+}
+
+/// Child-side fd for an output stream (None = inherit) plus the parent's read end when piped
+fn output_fds(spec: StdioSpec) -> PyResult<(Option<OwnedFd>, Option<OwnedFd>)> {
+    Ok(match spec {
+        StdioSpec::Inherit => (None, None),
+        StdioSpec::DevNull => (
+            Some(
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .open("/dev/null")
+                    .map_err(crate::utils::os_error_to_pyerr)?
+                    .into(),
+            ),
+            None,
+        ),
+        StdioSpec::Pipe => {
+            let (read, write) = cloexec_pipe().map_err(crate::utils::os_error_to_pyerr)?;
+            (Some(write), Some(read))
+        }
+        StdioSpec::Fd(fd) => (
+            Some(dup_fd(fd).map_err(crate::utils::os_error_to_pyerr)?),
+            None,
+        ),
+        StdioSpec::Stdout => return Err(value_error("STDOUT can only be used for stderr")),
+    })
+}
+
+fn output_stdio(fd: Option<OwnedFd>) -> Stdio {
+    fd.map_or_else(Stdio::inherit, Stdio::from)
+}
+
+impl VeloxLoop {
+    /// Spawn `argv` and wrap it in a SubprocessTransport.
+    /// `kwargs` holds stdin/stdout/stderr and the supported Popen options.
+    pub(crate) fn spawn_subprocess(
+        slf: &Bound<'_, Self>,
+        protocol_factory: Py<PyAny>,
+        argv: Vec<OsString>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let stdin = StdioSpec::from_py(pop_kwarg(kwargs, "stdin")?.as_ref())?;
+        let stdout = StdioSpec::from_py(pop_kwarg(kwargs, "stdout")?.as_ref())?;
+        let stderr = StdioSpec::from_py(pop_kwarg(kwargs, "stderr")?.as_ref())?;
+
+        for (name, message) in [
+            ("universal_newlines", "universal_newlines must be False"),
+            ("text", "text must be False"),
+        ] {
+            if let Some(value) = pop_kwarg(kwargs, name)?
+                && value.is_truthy()?
+            {
+                return Err(value_error(message));
+            }
+        }
+        for name in ["encoding", "errors"] {
+            if let Some(value) = pop_kwarg(kwargs, name)?
+                && !value.is_none()
+            {
+                return Err(value_error(&format!("{} must be None", name)));
+            }
+        }
+        if let Some(bufsize) = pop_kwarg(kwargs, "bufsize")?
+            && bufsize.extract::<i64>()? != 0
+        {
+            return Err(value_error("bufsize must be 0"));
+        }
+
+        let Some((program, args)) = argv.split_first() else {
+            return Err(value_error("program must not be empty"));
+        };
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+
+        if let Some(cwd) = pop_kwarg(kwargs, "cwd")?
+            && !cwd.is_none()
+        {
+            cmd.current_dir(fsencode(&cwd)?);
+        }
+        if let Some(env) = pop_kwarg(kwargs, "env")?
+            && !env.is_none()
+        {
+            cmd.env_clear();
+            for item in env.call_method0("items")?.try_iter()? {
+                let (key, value): (Bound<'_, PyAny>, Bound<'_, PyAny>) = item?.extract()?;
+                cmd.env(fsencode(&key)?, fsencode(&value)?);
+            }
+        }
+        if let Some(value) = pop_kwarg(kwargs, "start_new_session")?
+            && value.is_truthy()?
+        {
+            unsafe {
+                cmd.pre_exec(|| {
+                    if libc::setsid() == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+        if let Some(pgid) = pop_kwarg(kwargs, "process_group")?
+            && !pgid.is_none()
+        {
+            cmd.process_group(pgid.extract()?);
+        }
+        // Python creates fds non-inheritable, so closing them is already the default
+        pop_kwarg(kwargs, "close_fds")?;
+        if let Some(kwargs) = kwargs
+            && let Some((name, _)) = kwargs.iter().next()
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                "unsupported subprocess argument: {}",
+                name
+            )));
+        }
+
+        let (stdin_stdio, stdin_pipe) = input_fds(stdin)?;
+        let (stdout_fd, stdout_pipe) = output_fds(stdout)?;
+        let (stderr_fd, stderr_pipe) = match stderr {
+            StdioSpec::Stdout => match &stdout_fd {
+                Some(fd) => (Some(fd.try_clone()?), None),
+                None => (
+                    Some(dup_fd(1).map_err(crate::utils::os_error_to_pyerr)?),
+                    None,
+                ),
+            },
+            spec => output_fds(spec)?,
+        };
+        cmd.stdin(stdin_stdio)
+            .stdout(output_stdio(stdout_fd))
+            .stderr(output_stdio(stderr_fd));
+
+        let child = cmd.spawn()?;
+        // Closes our copies of the child's ends, so EOF shows up once it exits
+        drop(cmd);
+        let pid = child.id() as libc::pid_t;
+        drop(child);
+
+        let pidfd = match pidfd_open(pid) {
+            Ok(fd) => fd,
+            Err(e) => {
+                // Can't watch it, so don't leave it running or unreaped
+                unsafe {
+                    libc::kill(pid, libc::SIGKILL);
+                    libc::waitpid(pid, std::ptr::null_mut(), 0);
+                }
+                return Err(crate::utils::os_error_to_pyerr(e));
+            }
+        };
+
+        let protocol = protocol_factory.call0(py)?;
+        let transport = SubprocessTransport::start(
+            py,
+            slf.clone().unbind(),
+            protocol.clone_ref(py),
+            pid,
+            pidfd,
+            [stdin_pipe, stdout_pipe, stderr_pipe],
+        )?;
+        let result = PyTuple::new(py, [transport.into_any(), protocol])?;
+        slf.borrow().resolved_future(py, result.into_any().unbind())
+    }
+
+    pub fn subprocess_exec(
+        slf: &Bound<'_, Self>,
+        protocol_factory: Py<PyAny>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        if let Some(shell) = pop_kwarg(kwargs, "shell")?
+            && shell.is_truthy()?
+        {
+            return Err(value_error("shell must be False"));
+        }
+        let argv = args
+            .iter()
+            .map(|arg| fsencode(&arg))
+            .collect::<PyResult<Vec<_>>>()?;
+        Self::spawn_subprocess(slf, protocol_factory, argv, kwargs)
+    }
+
+    pub fn subprocess_shell(
+        slf: &Bound<'_, Self>,
+        protocol_factory: Py<PyAny>,
+        cmd: &Bound<'_, PyAny>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        if !cmd.is_instance_of::<PyString>() && !cmd.is_instance_of::<PyBytes>() {
+            return Err(value_error("cmd must be a string"));
+        }
+        if let Some(shell) = pop_kwarg(kwargs, "shell")?
+            && !shell.is_truthy()?
+        {
+            return Err(value_error("shell must be True"));
+        }
+        let argv = vec![OsString::from("/bin/sh"), OsString::from("-c"), fsencode(cmd)?];
+        Self::spawn_subprocess(slf, protocol_factory, argv, kwargs)
+    }
+}
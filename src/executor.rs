@@ -48,11 +48,6 @@ impl WorkerState {
     /// Find and execute tasks using work-stealing
     fn run(&self) {
         loop {
-            // Check shutdown
-            if self.shutdown.load(Ordering::Relaxed) {
-                return;
-            }
-
             // Try to get a task from local queue first (cache-friendly)
             if let Some(task) = self.worker.pop() {
                 task();
@@ -84,6 +79,11 @@ impl WorkerState {
                 continue;
             }
 
+            // Only exit once the queues are drained, so shutdown doesn't drop queued jobs
+            if self.shutdown.load(Ordering::Relaxed) {
+                return;
+            }
+
             // No work available, park the thread briefly
             self.parker.park_timeout(std::time::Duration::from_micros(100));
         }
@@ -208,6 +208,13 @@ impl WorkStealingExecutor {
     pub fn shutdown(&self) {
         self.shutdown.store(true, Ordering::Release);
     }
+
+    /// Stop the workers without waiting for them: each exits once the queues
+    /// are drained
+    pub fn detach(mut self) {
+        self.shutdown();
+        self.workers.clear();
+    }
 }
 
 impl Drop for WorkStealingExecutor {
@@ -267,6 +274,14 @@ impl ThreadPoolExecutor {
     pub fn num_workers(&self) -> usize {
         self.executor.num_workers()
     }
+
+    /// Let running jobs finish in the background instead of joining them.
+    /// Used when the loop is dropped, possibly with the GIL held, which the
+    /// jobs may be waiting for.
+    pub fn detach(self) {
+        self.executor.detach();
+        self.rt.shutdown_background();
+    }
 }
 
 impl Default for ThreadPoolExecutor {
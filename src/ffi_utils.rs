@@ -9,6 +9,7 @@
 //! - For callbacks: vectorcall avoids tuple allocation entirely (Python 3.12+)
 //! - For 0-arg callbacks: PyObject_CallNoArgs avoids tuple allocation
 
+use crate::constants::DETACH_COPY_BYTES;
 use pyo3::ffi;
 use pyo3::prelude::*;
 use std::ffi::c_char;
@@ -29,6 +30,61 @@ pub unsafe fn bytes_from_slice(py: Python<'_>, data: &[u8]) -> Py<PyAny> {
     }
 }
 
+/// Create a `PyBytes` of up to `capacity` bytes by letting `fill` write
+/// straight into its storage, so a socket read or buffer copy needs no
+/// staging buffer. `fill` returns how many bytes it wrote and the object is
+/// shrunk to that with `_PyBytes_Resize`. At `DETACH_COPY_BYTES` and above
+/// `fill` runs with the GIL released: copying megabytes takes long enough to
+/// stall every other Python thread. When `fill` fails nothing is created and
+/// its error is returned as the inner `Err`.
+pub fn bytes_filled<E: Send>(
+    py: Python<'_>,
+    capacity: usize,
+    fill: impl FnOnce(&mut [u8]) -> Result<usize, E> + Send,
+) -> PyResult<Result<(Py<PyAny>, usize), E>> {
+    unsafe {
+        let mut obj = ffi::PyBytes_FromStringAndSize(std::ptr::null(), capacity as ffi::Py_ssize_t);
+        if obj.is_null() {
+            return Err(PyErr::fetch(py));
+        }
+        // Nothing else can see the object yet, so its storage is ours to fill
+        // with or without the GIL. Passed as an address to keep the closure Send
+        let data = ffi::PyBytes_AS_STRING(obj) as usize;
+        let run = move || fill(std::slice::from_raw_parts_mut(data as *mut u8, capacity));
+        let filled = if capacity >= DETACH_COPY_BYTES {
+            py.detach(run)
+        } else {
+            run()
+        };
+        let n = match filled {
+            Ok(n) => n.min(capacity),
+            Err(e) => {
+                ffi::Py_DECREF(obj);
+                return Ok(Err(e));
+            }
+        };
+        // On failure the object is already freed and obj set to NULL
+        if n < capacity && ffi::_PyBytes_Resize(&mut obj, n as ffi::Py_ssize_t) != 0 {
+            return Err(PyErr::fetch(py));
+        }
+        Ok(Ok((Bound::from_owned_ptr(py, obj).unbind(), n)))
+    }
+}
+
+/// Like `bytes_from_slice`, but a payload of `DETACH_COPY_BYTES` or more is
+/// copied with the GIL released.
+pub fn bytes_from_large_slice(py: Python<'_>, data: &[u8]) -> PyResult<Py<PyAny>> {
+    if data.len() < DETACH_COPY_BYTES {
+        return Ok(unsafe { bytes_from_slice(py, data) });
+    }
+    let filled = bytes_filled(py, data.len(), |dst| {
+        dst.copy_from_slice(data);
+        Ok::<_, std::convert::Infallible>(data.len())
+    })?;
+    let Ok((bytes, _)) = filled;
+    Ok(bytes)
+}
+
 /// Create a `PyUnicode` string from a Rust `&str` using C API.
 /// Returns a new reference (raw pointer).
 #[inline(always)]
@@ -255,3 +311,27 @@ pub unsafe fn call_callback_ignore_err(
         }
     }
 }
+
+// ─── Interpreter State ──────────────────────────────────────────────────────
+
+/// Whether the interpreter is shutting down. Threads outside Python must not
+/// attach then: taking the GIL during finalization hangs or kills the thread.
+///
+/// `Py_IsFinalizing` is public from 3.13; older versions export it as
+/// `_Py_IsFinalizing`. It is looked up at runtime so one build covers both.
+pub fn is_finalizing() -> bool {
+    type IsFinalizing = unsafe extern "C" fn() -> std::os::raw::c_int;
+    static LOOKUP: std::sync::OnceLock<Option<IsFinalizing>> = std::sync::OnceLock::new();
+
+    let lookup = LOOKUP.get_or_init(|| {
+        let names = [c"Py_IsFinalizing", c"_Py_IsFinalizing"];
+        names.iter().find_map(|name| {
+            let sym = unsafe { libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr()) };
+            if sym.is_null() {
+                return None;
+            }
+            Some(unsafe { std::mem::transmute::<*mut libc::c_void, IsFinalizing>(sym) })
+        })
+    });
+    lookup.is_some_and(|f| unsafe { f() } != 0)
+}
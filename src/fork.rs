@@ -0,0 +1,98 @@
+//! Refusing to reuse a loop across `fork()`
+//!
+//! A forked child inherits the parent's io_uring fd together with the ring's
+//! shared memory, the wakeup eventfd and pipe, but none of the executor
+//! threads. Driving any of that from the child submits into the parent's ring
+//! and hangs on workers that don't exist. A `pthread_atfork` child handler
+//! bumps the fork generation and closes every fd loops registered here, so
+//! loops and pollers from before the fork see a generation that isn't theirs
+//! and refuse to run, and nothing of theirs leaks into the child.
+
+use parking_lot::Mutex;
+use std::os::fd::RawFd;
+use std::sync::Once;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::utils::{VeloxError, VeloxResult};
+
+/// Forks this process descends through since the module was loaded
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Ring, eventfd and pipe fds of the live loops, closed in a fork child
+static LOOP_FDS: Mutex<Vec<RawFd>> = Mutex::new(Vec::new());
+
+static INSTALL: Once = Once::new();
+
+extern "C" fn before_fork() {
+    // Held across fork() so the child's copy can't be mid-update
+    std::mem::forget(LOOP_FDS.lock());
+}
+
+extern "C" fn after_fork_in_parent() {
+    unsafe { LOOP_FDS.force_unlock() };
+}
+
+extern "C" fn after_fork_in_child() {
+    GENERATION.fetch_add(1, Ordering::Release);
+    unsafe { LOOP_FDS.force_unlock() };
+    // Only close(2) and no allocation: other threads' locks are gone with them
+    let mut fds = LOOP_FDS.lock();
+    for &fd in fds.iter() {
+        unsafe { libc::close(fd) };
+    }
+    fds.clear();
+}
+
+/// The process a loop or poller was created in, as a fork generation
+#[derive(Clone, Copy)]
+pub(crate) struct ForkGuard {
+    generation: u64,
+    pid: u32,
+}
+
+impl ForkGuard {
+    pub(crate) fn new() -> Self {
+        INSTALL.call_once(|| unsafe {
+            libc::pthread_atfork(
+                Some(before_fork),
+                Some(after_fork_in_parent),
+                Some(after_fork_in_child),
+            );
+        });
+        Self {
+            generation: GENERATION.load(Ordering::Acquire),
+            pid: std::process::id(),
+        }
+    }
+
+    /// True in a child forked since; its fds were closed by the fork handler
+    #[inline]
+    pub(crate) fn is_forked(&self) -> bool {
+        self.generation != GENERATION.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub(crate) fn check(&self) -> VeloxResult<()> {
+        if self.is_forked() {
+            return Err(VeloxError::RuntimeError(format!(
+                "VeloxLoop was created in a different process (pid {}); \
+                 create a new loop after fork",
+                self.pid
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Have `fd` closed in a child forked while it is open
+pub(crate) fn track_fd(fd: RawFd) {
+    LOOP_FDS.lock().push(fd);
+}
+
+/// `fd` is about to be closed by its owner
+pub(crate) fn untrack_fd(fd: RawFd) {
+    let mut fds = LOOP_FDS.lock();
+    if let Some(at) = fds.iter().position(|&tracked| tracked == fd) {
+        fds.swap_remove(at);
+    }
+}
@@ -10,6 +10,9 @@ pub enum IoCallback {
     // Specialized handlers for common transports - direct dispatch without dynamic dispatch
     TcpRead(Py<crate::transports::tcp::TcpTransport>),
     TcpWrite(Py<crate::transports::tcp::TcpTransport>),
+    UdpRead(Py<crate::transports::udp::UdpTransport>),
+    /// Paused transport watching only for POLLERR/POLLHUP, see `add_error_monitor`
+    TcpError(Py<crate::transports::tcp::TcpTransport>),
 }
 
 impl Clone for IoCallback {
@@ -35,6 +38,14 @@ impl Clone for IoCallback {
                     pyo3::ffi::Py_INCREF(cb.as_ptr());
                     IoCallback::TcpWrite(std::ptr::read(cb))
                 }
+                IoCallback::UdpRead(cb) => {
+                    pyo3::ffi::Py_INCREF(cb.as_ptr());
+                    IoCallback::UdpRead(std::ptr::read(cb))
+                }
+                IoCallback::TcpError(cb) => {
+                    pyo3::ffi::Py_INCREF(cb.as_ptr());
+                    IoCallback::TcpError(std::ptr::read(cb))
+                }
             }
         }
     }
@@ -44,9 +55,23 @@ impl Clone for IoCallback {
 pub struct Handle {
     pub callback: IoCallback,
     pub cancelled: bool,
+    /// Registration number, unique per `IoHandles`; tells a snapshot taken for
+    /// dispatch apart from a callback registered for the same fd afterwards
+    pub generation: u64,
+    /// Dispatched ahead of ordinary events in the same tick (listeners)
+    pub high_priority: bool,
+    /// Added by `loop.add_reader()`/`add_writer()`. Only these can be removed by
+    /// `loop.remove_reader()`/`remove_writer()`; the loop's own servers and
+    /// transports keep theirs
+    pub user: bool,
 }
 
 impl Handle {
+    #[inline]
+    pub fn is_error_monitor(&self) -> bool {
+        matches!(self.callback, IoCallback::TcpError(_))
+    }
+
     /// Execute the callback - inlined for performance
     #[inline(always)]
     pub fn execute(&self, py: Python<'_>) -> PyResult<()> {
@@ -63,118 +88,422 @@ impl Handle {
                 crate::transports::tcp::TcpTransport::_read_ready(tcp.bind(py))
             }
             IoCallback::TcpWrite(tcp) => {
-                let tcp_bound = tcp.bind(py);
-                crate::transports::tcp::TcpTransport::_write_ready(&mut *tcp_bound.borrow_mut(), py)
+                crate::transports::tcp::TcpTransport::_on_writable(tcp.bind(py))
+            }
+            IoCallback::UdpRead(udp) => {
+                crate::transports::udp::UdpTransport::_read_ready(udp.bind(py))
+            }
+            IoCallback::TcpError(tcp) => {
+                crate::transports::tcp::TcpTransport::_error_ready(tcp.bind(py))
             }
         }
     }
 }
 
 /// Lock-free I/O handle storage using DashMap for true concurrent access
-/// Uses ConcurrentIntMap (wrapping DashMap) for zero-lock contention in multi-threaded scenarios
+/// Uses ConcurrentIntMap (wrapping DashMap) for zero-lock contention in multi-threaded scenarios.
+/// The handles themselves live in a slab: the map holds slot numbers, a
+/// removed handle's slot goes on a free list for the next registration, and
+/// replacing an fd's reader or writer swaps the callback in place, so a
+/// register/unregister cycle allocates nothing once the slab has grown
 pub struct IoHandles {
-    // Maps FD to (Reader, Writer) - lock-free concurrent map
-    pub(crate) map: ConcurrentIntMap<(Option<Handle>, Option<Handle>)>,
+    // Maps FD to the (Reader, Writer) slots - lock-free concurrent map
+    pub(crate) map: ConcurrentIntMap<(Option<u32>, Option<u32>)>,
+    slots: Vec<Option<Handle>>,
+    /// Empty slots, taken before the slab grows
+    free: Vec<u32>,
+    next_generation: u64,
+    /// Registrations that needed a new slot, and those that reused one
+    created: u64,
+    reused: u64,
 }
 
 impl IoHandles {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_capacity(256)
+    }
+
+    /// Handles for about `fds` fds without growing the map
+    pub fn with_capacity(fds: usize) -> Self {
         Self {
-            map: ConcurrentIntMap::with_capacity(256),
+            map: ConcurrentIntMap::with_capacity(fds),
+            slots: Vec::with_capacity(fds),
+            free: Vec::with_capacity(fds),
+            next_generation: 0,
+            created: 0,
+            reused: 0,
+        }
+    }
+
+    #[inline]
+    fn new_handle(&mut self, callback: IoCallback, high_priority: bool) -> Handle {
+        self.next_generation += 1;
+        Handle {
+            callback,
+            cancelled: false,
+            generation: self.next_generation,
+            high_priority,
+            user: false,
         }
     }
 
+    /// `fd`'s reader and writer slots
+    #[inline]
+    fn slots_of(&self, fd: RawFd) -> Option<(Option<u32>, Option<u32>)> {
+        self.map.get(&fd).map(|pair| *pair)
+    }
+
+    #[inline]
+    fn slot(&self, slot: Option<u32>) -> Option<&Handle> {
+        slot.and_then(|slot| self.slots[slot as usize].as_ref())
+    }
+
+    #[inline]
+    fn slot_mut(&mut self, slot: u32) -> Option<&mut Handle> {
+        self.slots[slot as usize].as_mut()
+    }
+
+    /// `fd`'s reader (`reader`) or writer
+    #[inline]
+    fn handle(&self, fd: RawFd, reader: bool) -> Option<&Handle> {
+        let (r, w) = self.slots_of(fd)?;
+        self.slot(if reader { r } else { w })
+    }
+
+    /// Put `handle` in a free slot, or a new one when there is none
+    #[inline]
+    fn store(&mut self, handle: Handle) -> u32 {
+        if let Some(slot) = self.free.pop() {
+            self.reused += 1;
+            self.slots[slot as usize] = Some(handle);
+            return slot;
+        }
+        self.created += 1;
+        self.slots.push(Some(handle));
+        (self.slots.len() - 1) as u32
+    }
+
+    /// Drop the handle in `slot` and free the slot
+    #[inline]
+    fn release(&mut self, slot: u32) {
+        self.slots[slot as usize] = None;
+        self.free.push(slot);
+    }
+
+    /// Registrations that took a new slot, and those that reused a freed
+    /// one or replaced a handle in place
+    pub fn allocation_counts(&self) -> (u64, u64) {
+        (self.created, self.reused)
+    }
+
+    /// Priority already set for `fd`; a new reader/writer keeps it
+    #[inline]
+    fn priority_of(&self, fd: RawFd) -> bool {
+        self.handle(fd, true)
+            .or(self.handle(fd, false))
+            .is_some_and(|h| h.high_priority)
+    }
+
+    /// Flag `fd`'s reader and writer as high priority (or not). The flag stays
+    /// with the fd until both are removed; returns false if nothing is registered.
+    pub fn set_priority(&mut self, fd: RawFd, high: bool) -> bool {
+        let Some((reader, writer)) = self.slots_of(fd) else {
+            return false;
+        };
+        for slot in [reader, writer].into_iter().flatten() {
+            if let Some(handle) = self.slot_mut(slot) {
+                handle.high_priority = high;
+            }
+        }
+        true
+    }
+
+    /// Whether `handle` is still the registered reader (`reader`) or writer for `fd`,
+    /// i.e. it was neither removed nor replaced since it was looked up
+    #[inline]
+    pub fn is_current(&self, fd: RawFd, reader: bool, handle: &Handle) -> bool {
+        self.handle(fd, reader)
+            .is_some_and(|h| h.generation == handle.generation)
+    }
+
+    /// Generation of `fd`'s current reader (`reader`) or writer
+    #[inline]
+    pub fn generation(&self, fd: RawFd, reader: bool) -> Option<u64> {
+        self.handle(fd, reader).map(|h| h.generation)
+    }
+
+    /// Mark `fd`'s current reader (`reader`) or writer as a user registration
+    pub fn mark_user(&mut self, fd: RawFd, reader: bool) {
+        if let Some((r, w)) = self.slots_of(fd)
+            && let Some(slot) = if reader { r } else { w }
+            && let Some(handle) = self.slot_mut(slot)
+        {
+            handle.user = true;
+        }
+    }
+
+    /// Who owns `fd`'s reader (`reader`) or writer: None when nothing is
+    /// registered, else whether it is a user registration
+    #[inline]
+    pub fn owner(&self, fd: RawFd, reader: bool) -> Option<bool> {
+        self.handle(fd, reader).map(|h| h.user)
+    }
+
     #[inline]
     pub fn get_states(&self, fd: RawFd) -> (bool, bool) {
-        if let Some(pair) = self.map.get(&fd) {
-            (pair.0.is_some(), pair.1.is_some())
-        } else {
-            (false, false)
+        match self.slots_of(fd) {
+            Some((reader, writer)) => (reader.is_some(), writer.is_some()),
+            None => (false, false),
+        }
+    }
+
+    /// Poll interest for `fd` as (readable, writable), or None when nothing is
+    /// registered. An error monitor in the reader slot adds no interest: the
+    /// poll still reports POLLERR/POLLHUP
+    #[inline]
+    pub fn interest(&self, fd: RawFd) -> Option<(bool, bool)> {
+        match self.slots_of(fd)? {
+            (None, None) => None,
+            (reader, writer) => Some((
+                self.slot(reader).is_some_and(|h| !h.is_error_monitor()),
+                writer.is_some(),
+            )),
         }
     }
 
     #[inline]
     pub fn get_state_owned(&self, fd: RawFd) -> Option<(Option<Handle>, Option<Handle>)> {
-        self.map.get(&fd).map(|pair| (pair.0.clone(), pair.1.clone()))
+        let (reader, writer) = self.slots_of(fd)?;
+        Some((self.slot(reader).cloned(), self.slot(writer).cloned()))
     }
 
+    /// Register `callback` as `fd`'s reader (`reader`) or writer: swapped into
+    /// the slot of the one it replaces, else stored in a free slot
     #[inline]
-    pub fn add_reader(&mut self, fd: RawFd, callback: IoCallback) {
+    fn add(&mut self, fd: RawFd, reader: bool, callback: IoCallback) {
         use dashmap::mapref::entry::Entry;
+        let high_priority = self.priority_of(fd);
+        let handle = self.new_handle(callback, high_priority);
+        let current = self
+            .slots_of(fd)
+            .and_then(|(r, w)| if reader { r } else { w });
+        if let Some(slot) = current {
+            self.reused += 1;
+            self.slots[slot as usize] = Some(handle);
+            return;
+        }
+        let slot = Some(self.store(handle));
         match self.map.entry(fd) {
             Entry::Occupied(mut entry) => {
-                entry.get_mut().0 = Some(Handle {
-                    callback,
-                    cancelled: false,
-                });
+                let pair = entry.get_mut();
+                if reader {
+                    pair.0 = slot;
+                } else {
+                    pair.1 = slot;
+                }
             }
             Entry::Vacant(entry) => {
-                entry.insert((Some(Handle {
-                    callback,
-                    cancelled: false,
-                }), None));
+                entry.insert(if reader { (slot, None) } else { (None, slot) });
             }
         }
     }
 
+    /// Unregister `fd`'s reader (`reader`) or writer; false if there was none
     #[inline]
-    pub fn remove_reader(&mut self, fd: RawFd) -> bool {
-        if let Some(mut pair) = self.map.get_mut(&fd) {
-            if pair.0.is_some() {
-                pair.0 = None;
-                let should_remove = pair.1.is_none();
-                drop(pair); // Release lock before remove
-                if should_remove {
-                    self.map.remove(&fd);
-                }
-                return true;
-            }
+    fn remove(&mut self, fd: RawFd, reader: bool) -> bool {
+        let Some(mut pair) = self.map.get_mut(&fd) else {
+            return false;
+        };
+        let (taken, other) = if reader {
+            (pair.0.take(), pair.1)
+        } else {
+            (pair.1.take(), pair.0)
+        };
+        drop(pair); // Release lock before remove
+        let Some(slot) = taken else {
+            return false;
+        };
+        if other.is_none() {
+            self.map.remove(&fd);
         }
-        false
+        self.release(slot);
+        true
+    }
+
+    #[inline]
+    pub fn add_reader(&mut self, fd: RawFd, callback: IoCallback) {
+        self.add(fd, true, callback);
+    }
+
+    #[inline]
+    pub fn remove_reader(&mut self, fd: RawFd) -> bool {
+        self.remove(fd, true)
     }
 
     #[inline]
     pub fn add_writer(&mut self, fd: RawFd, callback: IoCallback) {
-        use dashmap::mapref::entry::Entry;
-        match self.map.entry(fd) {
-            Entry::Occupied(mut entry) => {
-                entry.get_mut().1 = Some(Handle {
-                    callback,
-                    cancelled: false,
-                });
-            }
-            Entry::Vacant(entry) => {
-                entry.insert((None, Some(Handle {
-                    callback,
-                    cancelled: false,
-                })));
-            }
-        }
+        self.add(fd, false, callback);
     }
 
     #[inline]
     pub fn remove_writer(&mut self, fd: RawFd) -> bool {
-        if let Some(mut pair) = self.map.get_mut(&fd) {
-            if pair.1.is_some() {
-                pair.1 = None;
-                let should_remove = pair.0.is_none();
-                drop(pair); // Release lock before remove
-                if should_remove {
-                    self.map.remove(&fd);
-                }
-                return true;
-            }
-        }
-        false
+        self.remove(fd, false)
     }
 
     #[inline]
     pub fn get_reader(&self, fd: RawFd) -> Option<Handle> {
-        self.map.get(&fd).and_then(|v| v.0.clone())
+        self.handle(fd, true).cloned()
     }
 
     #[inline]
     pub fn get_writer(&self, fd: RawFd) -> Option<Handle> {
-        self.map.get(&fd).and_then(|v| v.1.clone())
+        self.handle(fd, false).cloned()
+    }
+
+    /// Fds with a reader, a writer or both
+    pub fn fd_count(&self) -> usize {
+        self.map.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop() -> IoCallback {
+        IoCallback::Native(Arc::new(|_| Ok(())))
+    }
+
+    #[test]
+    fn reader_and_writer_share_an_fd() {
+        let mut handles = IoHandles::new();
+        handles.add_reader(3, noop());
+        handles.add_writer(3, noop());
+        assert_eq!(handles.get_states(3), (true, true));
+
+        assert!(handles.remove_reader(3));
+        assert!(!handles.remove_reader(3));
+        assert_eq!(handles.get_states(3), (false, true));
+        assert!(handles.remove_writer(3));
+        assert!(handles.map.get(&3).is_none());
+    }
+
+    #[test]
+    fn replaced_handle_is_not_current() {
+        let mut handles = IoHandles::new();
+        handles.add_reader(4, noop());
+        let old = handles.get_reader(4).unwrap();
+        assert!(handles.is_current(4, true, &old));
+        assert!(!handles.is_current(4, false, &old));
+
+        handles.add_reader(4, noop());
+        assert!(!handles.is_current(4, true, &old));
+        let new = handles.get_reader(4).unwrap();
+        assert!(new.generation > old.generation);
+        handles.remove_reader(4);
+        assert!(!handles.is_current(4, true, &new));
+    }
+
+    #[test]
+    fn priority_sticks_until_fd_is_empty() {
+        let mut handles = IoHandles::new();
+        assert!(!handles.set_priority(5, true));
+        handles.add_reader(5, noop());
+        assert!(handles.set_priority(5, true));
+        handles.add_writer(5, noop());
+        handles.add_reader(5, noop());
+        assert!(handles.get_writer(5).unwrap().high_priority);
+        assert!(handles.get_reader(5).unwrap().high_priority);
+
+        handles.remove_reader(5);
+        handles.remove_writer(5);
+        handles.add_reader(5, noop());
+        assert!(!handles.get_reader(5).unwrap().high_priority);
+    }
+
+    #[test]
+    fn interest_follows_registrations() {
+        let mut handles = IoHandles::new();
+        assert_eq!(handles.interest(6), None);
+        handles.add_writer(6, noop());
+        assert_eq!(handles.interest(6), Some((false, true)));
+        handles.add_reader(6, noop());
+        assert_eq!(handles.interest(6), Some((true, true)));
+        handles.remove_writer(6);
+        assert_eq!(handles.interest(6), Some((true, false)));
+        handles.remove_reader(6);
+        assert_eq!(handles.interest(6), None);
+    }
+
+    #[test]
+    fn user_mark_stays_with_the_handle() {
+        let mut handles = IoHandles::new();
+        assert_eq!(handles.owner(7, true), None);
+        handles.add_reader(7, noop());
+        handles.mark_user(7, true);
+        handles.add_writer(7, noop());
+        assert_eq!(handles.owner(7, true), Some(true));
+        assert_eq!(handles.owner(7, false), Some(false));
+
+        // A replacement starts out owned by the loop again
+        handles.add_reader(7, noop());
+        assert_eq!(handles.owner(7, true), Some(false));
+    }
+
+    #[test]
+    fn slots_are_reused() {
+        let mut handles = IoHandles::new();
+        handles.add_reader(8, noop());
+        handles.add_writer(8, noop());
+        assert_eq!(handles.allocation_counts(), (2, 0));
+
+        // Replaced in place: same slot, new generation and flags
+        let old = handles.get_reader(8).unwrap();
+        handles.mark_user(8, true);
+        handles.add_reader(8, noop());
+        assert_eq!(handles.allocation_counts(), (2, 1));
+        assert!(!handles.is_current(8, true, &old));
+        assert_eq!(handles.owner(8, true), Some(false));
+
+        // Freed by a remove, taken by the next fd
+        assert!(handles.remove_writer(8));
+        handles.add_writer(9, noop());
+        assert_eq!(handles.allocation_counts(), (2, 2));
+        assert_eq!(handles.get_states(8), (true, false));
+        assert_eq!(handles.get_states(9), (false, true));
+        assert_eq!(handles.slots.len(), 2);
+    }
+
+    #[test]
+    fn register_unregister_allocates_nothing() {
+        const ROUNDS: usize = 1_000_000;
+        const FDS: RawFd = 64;
+        let mut handles = IoHandles::new();
+        let callback = noop();
+        let cycle = |handles: &mut IoHandles, round: usize| {
+            let fd = round as RawFd % FDS;
+            handles.add_reader(fd, callback.clone());
+            handles.add_writer(fd, callback.clone());
+            handles.add_reader(fd, callback.clone());
+            assert!(handles.remove_reader(fd));
+            assert!(handles.remove_writer(fd));
+        };
+        // Grows the slab and the map once
+        for round in 0..FDS as usize {
+            cycle(&mut handles, round);
+        }
+
+        let before = crate::test_alloc::allocations();
+        for round in 0..ROUNDS {
+            cycle(&mut handles, round);
+        }
+        let allocations = crate::test_alloc::allocations() - before;
+        assert_eq!(allocations, 0);
+        let (created, reused) = handles.allocation_counts();
+        assert!(created <= 2);
+        let registrations = 3 * (ROUNDS + FDS as usize) as u64;
+        assert_eq!(reused, registrations - created);
+        assert_eq!(handles.fd_count(), 0);
     }
 }
@@ -1,12 +1,19 @@
 use pyo3::prelude::*;
 
-#[cfg(not(any(target_env = "musl", target_os = "freebsd", target_os = "openbsd", target_os = "windows")))]
+#[cfg(not(any(test, target_env = "musl", target_os = "freebsd", target_os = "openbsd", target_os = "windows")))]
 use tikv_jemallocator::Jemalloc;
 
-#[cfg(not(any(target_env = "musl", target_os = "freebsd", target_os = "openbsd", target_os = "windows")))]
+#[cfg(not(any(test, target_env = "musl", target_os = "freebsd", target_os = "openbsd", target_os = "windows")))]
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 
+// Unit tests count allocations on top of the same allocator
+#[cfg(test)]
+#[global_allocator]
+static GLOBAL: test_alloc::Counting = test_alloc::Counting;
+
+#[cfg(feature = "bench")]
+pub mod bench;
 mod buffer_pool;
 mod callbacks;
 mod concurrent;
@@ -14,11 +21,19 @@ mod constants;
 mod event_loop;
 mod executor;
 mod ffi_utils;
+mod fork;
 mod handles;
+mod loop_bench;
 mod policy;
 mod poller;
+#[cfg(target_os = "linux")]
+mod self_check;
 mod socket;
+mod stats_export;
 mod streams;
+#[cfg(test)]
+mod test_alloc;
+mod timeout_wheel;
 mod timers;
 mod transports;
 mod utils;
@@ -28,9 +43,12 @@ use event_loop::VeloxLoop;
 use policy::VeloxLoopPolicy;
 use socket::SocketOptions;
 use streams::{StreamReader, StreamWriter, VeloxBuffer};
-use transports::future::CompletedFuture;
+use transports::factory::TransportFactoryConfig;
+use transports::future::{CompletedFuture, PendingFuture};
 use transports::ssl::{SSLContext, SSLTransport};
 use transports::stream_server::{StreamServer, StreamTransport};
+#[cfg(target_os = "linux")]
+use transports::subprocess::{PipeTransport, SubprocessTransport};
 use transports::tcp::{SocketWrapper, TcpServer, TcpTransport};
 use transports::udp::{UdpSocketWrapper, UdpTransport};
 
@@ -45,6 +63,7 @@ fn _veloxloop(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<SSLContext>()?;
     m.add_class::<SSLTransport>()?;
     m.add_class::<CompletedFuture>()?;
+    m.add_class::<PendingFuture>()?;
     m.add_class::<AsyncConnectCallback>()?;
     m.add_class::<VeloxLoopPolicy>()?;
     m.add_class::<StreamReader>()?;
@@ -53,5 +72,20 @@ fn _veloxloop(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<StreamServer>()?;
     m.add_class::<StreamTransport>()?;
     m.add_class::<SocketOptions>()?;
+    m.add_class::<TransportFactoryConfig>()?;
+    #[cfg(target_os = "linux")]
+    {
+        m.add_class::<SubprocessTransport>()?;
+        m.add_class::<PipeTransport>()?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        m.add_function(wrap_pyfunction!(self_check::self_check, m)?)?;
+        m.add_function(wrap_pyfunction!(self_check::is_io_uring_available, m)?)?;
+    }
+    m.add_function(wrap_pyfunction!(utils::ipv6::_parse_sockaddr, m)?)?;
+    m.add_function(wrap_pyfunction!(utils::ipv6::configured_address_families, m)?)?;
+    constants::add_module(m)?;
+    loop_bench::add_module(m)?;
     Ok(())
 }
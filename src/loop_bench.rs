@@ -0,0 +1,656 @@
+//! `veloxloop._veloxloop.bench`: self-contained scenarios that run on any
+//! asyncio loop object, so VeloxLoop, uvloop and asyncio can be compared on
+//! equal terms. The timing happens here, around the points where a scenario
+//! waits on the loop, which keeps Python-level measuring overhead out of the
+//! numbers. A VeloxLoop runs the echo scenario over its native
+//! StreamServer/StreamReader; any other loop gets asyncio streams driven by
+//! the same code.
+//!
+//! Scenarios are driven by callbacks rather than coroutines, run the loop
+//! with `run_until_complete()` and close every server and connection they
+//! opened before returning. Not to be confused with the `bench` feature
+//! (`src/bench.rs`), which drives the poller without an interpreter.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyTuple};
+
+use crate::constants::get_asyncio;
+use crate::event_loop::VeloxLoop;
+use crate::transports::future::PendingFuture;
+
+const HOST: &str = "127.0.0.1";
+/// Seconds a scenario may overrun its own work before it is called hung
+const GRACE: f64 = 30.0;
+
+/// Resolves `future` once `tick()` has been called `remaining` times
+#[pyclass(frozen)]
+struct Countdown {
+    remaining: Mutex<usize>,
+    finished: Mutex<Option<Instant>>,
+    future: Py<PyAny>,
+}
+
+impl Countdown {
+    fn new(py: Python<'_>, loop_: &Bound<'_, PyAny>, count: usize) -> PyResult<Py<Self>> {
+        let future = loop_.call_method0("create_future")?.unbind();
+        let countdown = Py::new(
+            py,
+            Self {
+                remaining: Mutex::new(count),
+                finished: Mutex::new(None),
+                future,
+            },
+        )?;
+        if count == 0 {
+            countdown.get().tick(py)?;
+        }
+        Ok(countdown)
+    }
+
+    fn tick(&self, py: Python<'_>) -> PyResult<()> {
+        let mut remaining = self.remaining.lock();
+        *remaining = remaining.saturating_sub(1);
+        if *remaining > 0 {
+            return Ok(());
+        }
+        drop(remaining);
+        self.finished.lock().get_or_insert_with(Instant::now);
+        let future = self.future.bind(py);
+        if !future.call_method0("done")?.is_truthy()? {
+            future.call_method1("set_result", (py.None(),))?;
+        }
+        Ok(())
+    }
+
+    fn finished(&self) -> Option<Instant> {
+        *self.finished.lock()
+    }
+
+    /// Run `loop_` until every tick arrived, or fail after `timeout` seconds
+    fn wait(&self, py: Python<'_>, loop_: &Bound<'_, PyAny>, timeout: f64) -> PyResult<()> {
+        let asyncio = get_asyncio(py).bind(py);
+        let shielded = asyncio.call_method1("shield", (self.future.bind(py),))?;
+        let bounded = asyncio.call_method1("wait_for", (shielded, timeout))?;
+        loop_.call_method1("run_until_complete", (bounded,))?;
+        Ok(())
+    }
+}
+
+/// The side of an echo connection waiting on a read
+enum Reader {
+    Server(Py<EchoServerConn>),
+    Client(Py<EchoClient>),
+}
+
+impl Reader {
+    fn resume<'py>(&self, py: Python<'py>, outcome: PyResult<Bound<'py, PyAny>>) -> PyResult<()> {
+        match self {
+            Reader::Server(conn) => EchoServerConn::pump(conn.bind(py), outcome),
+            Reader::Client(conn) => EchoClient::received(conn.bind(py), outcome),
+        }
+    }
+}
+
+/// Done callback carrying a pending read's outcome back to its connection.
+/// The native futures call it with None, so it keeps the future itself.
+#[pyclass(frozen)]
+struct Resume {
+    future: Py<PyAny>,
+    reader: Reader,
+    /// Set for native futures, which run done callbacks right away, inside
+    /// the read dispatch: the first call only schedules the second, the way
+    /// an asyncio future runs its callbacks
+    call_soon: Option<Py<PyAny>>,
+    scheduled: AtomicBool,
+}
+
+#[pymethods]
+impl Resume {
+    #[pyo3(signature = (*_args))]
+    fn __call__(slf: &Bound<'_, Self>, _args: &Bound<'_, PyTuple>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.get();
+        if let Some(call_soon) = &this.call_soon
+            && !this.scheduled.swap(true, Ordering::Relaxed)
+        {
+            call_soon.bind(py).call1((slf,))?;
+            return Ok(());
+        }
+        let outcome = this.future.bind(py).call_method0("result");
+        this.reader.resume(py, outcome)
+    }
+}
+
+/// `readexactly(size)` on a native or an asyncio StreamReader: the data when
+/// it was already buffered, otherwise None after arranging for `reader` to
+/// get the outcome later
+fn read_exactly<'py>(
+    loop_: &Bound<'py, PyAny>,
+    stream: &Bound<'py, PyAny>,
+    size: usize,
+    reader: impl FnOnce() -> Reader,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let py = loop_.py();
+    let result = stream.call_method1("readexactly", (size,))?;
+    let (future, call_soon) = if result.cast::<PendingFuture>().is_ok() {
+        (result, Some(loop_.getattr("call_soon")?.unbind()))
+    } else if result.hasattr("__await__")? {
+        // asyncio's coroutine method: the task is what gets a done callback
+        (loop_.call_method1("create_task", (result,))?, None)
+    } else {
+        return Ok(Some(result));
+    };
+    let resume = Resume {
+        future: future.clone().unbind(),
+        reader: reader(),
+        call_soon,
+        scheduled: AtomicBool::new(false),
+    };
+    future.call_method1("add_done_callback", (Py::new(py, resume)?,))?;
+    Ok(None)
+}
+
+/// Server side of one echo connection: every message goes straight back
+#[pyclass(frozen)]
+struct EchoServerConn {
+    loop_: Py<PyAny>,
+    reader: Py<PyAny>,
+    writer: Py<PyAny>,
+    size: usize,
+    done: Py<Countdown>,
+}
+
+impl EchoServerConn {
+    fn pump<'py>(slf: &Bound<'py, Self>, mut outcome: PyResult<Bound<'py, PyAny>>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.get();
+        let loop_ = this.loop_.bind(py);
+        loop {
+            let next = outcome.and_then(|data| {
+                this.writer.bind(py).call_method1("write", (data,))?;
+                read_exactly(loop_, this.reader.bind(py), this.size, || {
+                    Reader::Server(slf.clone().unbind())
+                })
+            });
+            match next {
+                Ok(Some(data)) => outcome = Ok(data),
+                Ok(None) => return Ok(()),
+                // The client hung up (IncompleteReadError) or the connection broke
+                Err(_) => {
+                    this.writer.bind(py).call_method0("close")?;
+                    return this.done.get().tick(py);
+                }
+            }
+        }
+    }
+}
+
+/// `client_connected_cb` of the echo server; a plain callable, which native
+/// and asyncio servers both accept
+#[pyclass(frozen)]
+struct EchoHandler {
+    loop_: Py<PyAny>,
+    size: usize,
+    done: Py<Countdown>,
+}
+
+#[pymethods]
+impl EchoHandler {
+    fn __call__(&self, py: Python<'_>, reader: Py<PyAny>, writer: Py<PyAny>) -> PyResult<()> {
+        let conn = Bound::new(
+            py,
+            EchoServerConn {
+                loop_: self.loop_.clone_ref(py),
+                reader,
+                writer,
+                size: self.size,
+                done: self.done.clone_ref(py),
+            },
+        )?;
+        let this = conn.get();
+        let first = read_exactly(this.loop_.bind(py), this.reader.bind(py), this.size, || {
+            Reader::Server(conn.clone().unbind())
+        });
+        match first {
+            Ok(Some(data)) => EchoServerConn::pump(&conn, Ok(data)),
+            Ok(None) => Ok(()),
+            Err(e) => EchoServerConn::pump(&conn, Err(e)),
+        }
+    }
+}
+
+/// One client of the echo scenario: sends a message, waits for all of it to
+/// come back, records the round trip and goes again until the deadline
+#[pyclass(frozen)]
+struct EchoClient {
+    loop_: Py<PyAny>,
+    reader: Py<PyAny>,
+    writer: Py<PyAny>,
+    message: Py<PyBytes>,
+    deadline: Mutex<Instant>,
+    sent_at: Mutex<Instant>,
+    round_trips: Mutex<Vec<i64>>,
+    error: Mutex<Option<PyErr>>,
+    done: Py<Countdown>,
+}
+
+impl EchoClient {
+    fn size(&self, py: Python<'_>) -> usize {
+        self.message.bind(py).as_bytes().len()
+    }
+
+    fn send(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.get();
+        loop {
+            *this.sent_at.lock() = Instant::now();
+            let next = this
+                .writer
+                .bind(py)
+                .call_method1("write", (this.message.bind(py),))
+                .and_then(|_| {
+                    read_exactly(
+                        this.loop_.bind(py),
+                        this.reader.bind(py),
+                        this.size(py),
+                        || Reader::Client(slf.clone().unbind()),
+                    )
+                });
+            match next {
+                Ok(Some(_)) => {
+                    if !this.record() {
+                        return Self::finish(slf, None);
+                    }
+                }
+                Ok(None) => return Ok(()),
+                Err(e) => return Self::finish(slf, Some(e)),
+            }
+        }
+    }
+
+    fn received<'py>(slf: &Bound<'py, Self>, outcome: PyResult<Bound<'py, PyAny>>) -> PyResult<()> {
+        match outcome {
+            Ok(_) if slf.get().record() => Self::send(slf),
+            Ok(_) => Self::finish(slf, None),
+            Err(e) => Self::finish(slf, Some(e)),
+        }
+    }
+
+    /// Note the round trip that just ended; whether to send another
+    fn record(&self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(*self.sent_at.lock());
+        self.round_trips.lock().push(elapsed.as_nanos() as i64);
+        now < *self.deadline.lock()
+    }
+
+    fn finish(slf: &Bound<'_, Self>, error: Option<PyErr>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.get();
+        if error.is_some() {
+            *this.error.lock() = error;
+        }
+        this.writer.bind(py).call_method0("close")?;
+        this.done.get().tick(py)
+    }
+}
+
+#[pymethods]
+impl EchoClient {
+    /// Scheduled with call_soon() to start the first round trip
+    fn __call__(slf: &Bound<'_, Self>) -> PyResult<()> {
+        Self::send(slf)
+    }
+}
+
+/// Median, 99th percentile and maximum of nanosecond samples, in microseconds
+fn percentiles_us(samples: &mut [i64]) -> (f64, f64, f64) {
+    if samples.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+    samples.sort_unstable();
+    let at = |q: f64| {
+        let index = ((samples.len() - 1) as f64 * q).round() as usize;
+        samples[index] as f64 / 1e3
+    };
+    (at(0.5), at(0.99), at(1.0))
+}
+
+fn per_sec(count: usize, elapsed: Duration) -> f64 {
+    count as f64 / elapsed.as_secs_f64().max(1e-9)
+}
+
+fn positive(name: &str, value: usize) -> PyResult<()> {
+    if value == 0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "{name} must be at least 1"
+        )));
+    }
+    Ok(())
+}
+
+/// What every result starts with: which loop ran, and whether it used the
+/// native streams
+fn result_dict<'py>(loop_: &Bound<'py, PyAny>, native: bool) -> PyResult<Bound<'py, PyDict>> {
+    let result = PyDict::new(loop_.py());
+    let kind = loop_.get_type();
+    result.set_item("loop", format!("{}.{}", kind.module()?, kind.qualname()?))?;
+    result.set_item("native", native)?;
+    Ok(result)
+}
+
+/// Let the loop run the close callbacks scheduled so far
+fn settle(loop_: &Bound<'_, PyAny>) -> PyResult<()> {
+    let py = loop_.py();
+    let asyncio = get_asyncio(py).bind(py);
+    for _ in 0..2 {
+        loop_.call_method1(
+            "run_until_complete",
+            (asyncio.call_method1("sleep", (0,))?,),
+        )?;
+    }
+    Ok(())
+}
+
+/// Echo server and `connections` clients on loopback; each client keeps one
+/// `message_size` message in flight for `duration` seconds. Returns
+/// messages/sec and round-trip percentiles in microseconds.
+#[pyfunction]
+#[pyo3(signature = (r#loop, connections=1, message_size=1024, duration=1.0))]
+fn echo_throughput<'py>(
+    r#loop: &Bound<'py, PyAny>,
+    connections: usize,
+    message_size: usize,
+    duration: f64,
+) -> PyResult<Bound<'py, PyDict>> {
+    positive("connections", connections)?;
+    positive("message_size", message_size)?;
+    if !(duration > 0.0 && duration.is_finite()) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "duration must be a positive number of seconds",
+        ));
+    }
+    let py = r#loop.py();
+    let loop_ = r#loop;
+    let asyncio = get_asyncio(py).bind(py);
+    let native = loop_.cast::<VeloxLoop>().is_ok();
+    // The native streams live on the loop; asyncio's are module functions
+    // that find the running loop
+    let streams = if native {
+        loop_.clone()
+    } else {
+        asyncio.clone().into_any()
+    };
+
+    let served = Countdown::new(py, loop_, connections)?;
+    let handler = EchoHandler {
+        loop_: loop_.clone().unbind(),
+        size: message_size,
+        done: served.clone_ref(py),
+    };
+    let server = loop_.call_method1(
+        "run_until_complete",
+        (streams.call_method1("start_server", (Py::new(py, handler)?, HOST, 0))?,),
+    )?;
+    let port: u16 = server
+        .getattr("sockets")?
+        .get_item(0)?
+        .call_method0("getsockname")?
+        .get_item(1)?
+        .extract()?;
+
+    let finished = Countdown::new(py, loop_, connections)?;
+    let message = PyBytes::new(py, &vec![b'x'; message_size]).unbind();
+    let mut clients = Vec::with_capacity(connections);
+    let run = (|| -> PyResult<Instant> {
+        for _ in 0..connections {
+            let pair = loop_.call_method1(
+                "run_until_complete",
+                (streams.call_method1("open_connection", (HOST, port))?,),
+            )?;
+            let now = Instant::now();
+            clients.push(Py::new(
+                py,
+                EchoClient {
+                    loop_: loop_.clone().unbind(),
+                    reader: pair.get_item(0)?.unbind(),
+                    writer: pair.get_item(1)?.unbind(),
+                    message: message.clone_ref(py),
+                    deadline: Mutex::new(now),
+                    sent_at: Mutex::new(now),
+                    round_trips: Mutex::new(Vec::new()),
+                    error: Mutex::new(None),
+                    done: finished.clone_ref(py),
+                },
+            )?);
+        }
+
+        let start = Instant::now();
+        let deadline = start + Duration::from_secs_f64(duration);
+        for client in &clients {
+            *client.get().deadline.lock() = deadline;
+            loop_.call_method1("call_soon", (client,))?;
+        }
+        finished.get().wait(py, loop_, duration + GRACE)?;
+        Ok(start)
+    })();
+
+    // Teardown whatever happened: no connection, server or task outlives the call
+    for client in &clients {
+        let writer = client.get().writer.bind(py);
+        if !writer.call_method0("is_closing")?.is_truthy()? {
+            writer.call_method0("close")?;
+        }
+    }
+    let drained = run
+        .is_ok()
+        .then(|| served.get().wait(py, loop_, GRACE))
+        .transpose();
+    server.call_method0("close")?;
+    loop_.call_method1("run_until_complete", (server.call_method0("wait_closed")?,))?;
+    settle(loop_)?;
+    let start = run?;
+    drained?;
+
+    let mut round_trips = Vec::new();
+    for client in &clients {
+        if let Some(e) = client.get().error.lock().take() {
+            return Err(e);
+        }
+        round_trips.append(&mut client.get().round_trips.lock());
+    }
+    let elapsed = finished
+        .get()
+        .finished()
+        .unwrap_or_else(Instant::now)
+        .duration_since(start);
+    let (p50, p99, max) = percentiles_us(&mut round_trips);
+
+    let result = result_dict(loop_, native)?;
+    result.set_item("connections", connections)?;
+    result.set_item("message_size", message_size)?;
+    result.set_item("duration", elapsed.as_secs_f64())?;
+    result.set_item("messages", round_trips.len())?;
+    result.set_item("messages_per_sec", per_sec(round_trips.len(), elapsed))?;
+    result.set_item("p50_us", p50)?;
+    result.set_item("p99_us", p99)?;
+    result.set_item("max_us", max)?;
+    Ok(result)
+}
+
+/// Callback of every timer in the storm, called with the timer's index
+#[pyclass(frozen)]
+struct Storm {
+    delays: Vec<Duration>,
+    scheduled: Mutex<Vec<Instant>>,
+    /// When the loop started running; timers due while it was still being
+    /// filled are late from here, not from their deadline
+    running: Mutex<Option<Instant>>,
+    lateness: Mutex<Vec<i64>>,
+    done: Py<Countdown>,
+}
+
+#[pymethods]
+impl Storm {
+    fn __call__(&self, py: Python<'_>, index: usize) -> PyResult<()> {
+        let now = Instant::now();
+        let due = self.scheduled.lock()[index] + self.delays[index];
+        let due = self.running.lock().map_or(due, |running| due.max(running));
+        let late = match now.checked_duration_since(due) {
+            Some(late) => late.as_nanos() as i64,
+            // Loops may run a timer up to their clock resolution early
+            None => -(due.duration_since(now).as_nanos() as i64),
+        };
+        self.lateness.lock().push(late);
+        self.done.get().tick(py)
+    }
+}
+
+/// Schedule `count` call_later() timers due evenly across `spread` seconds
+/// and run them all. Returns the scheduling rate, the overall
+/// schedule-and-fire rate, and how late the timers ran in microseconds.
+#[pyfunction]
+#[pyo3(signature = (r#loop, count=10_000, spread=0.05))]
+fn timer_storm<'py>(
+    r#loop: &Bound<'py, PyAny>,
+    count: usize,
+    spread: f64,
+) -> PyResult<Bound<'py, PyDict>> {
+    positive("count", count)?;
+    if !(spread >= 0.0 && spread.is_finite()) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "spread must be a non-negative number of seconds",
+        ));
+    }
+    let py = r#loop.py();
+    let loop_ = r#loop;
+    let done = Countdown::new(py, loop_, count)?;
+    let delays: Vec<Duration> = (0..count)
+        .map(|i| Duration::from_secs_f64(spread * i as f64 / count as f64))
+        .collect();
+    let storm = Bound::new(
+        py,
+        Storm {
+            delays,
+            scheduled: Mutex::new(Vec::with_capacity(count)),
+            running: Mutex::new(None),
+            lateness: Mutex::new(Vec::with_capacity(count)),
+            done: done.clone_ref(py),
+        },
+    )?;
+
+    let call_later = loop_.getattr("call_later")?;
+    let start = Instant::now();
+    for (i, delay) in storm.get().delays.iter().enumerate() {
+        storm.get().scheduled.lock().push(Instant::now());
+        call_later.call1((delay.as_secs_f64(), &storm, i))?;
+    }
+    let scheduling = start.elapsed();
+    *storm.get().running.lock() = Some(Instant::now());
+    done.get().wait(py, loop_, spread + GRACE)?;
+    let elapsed = done
+        .get()
+        .finished()
+        .unwrap_or_else(Instant::now)
+        .duration_since(start);
+    settle(loop_)?;
+
+    let (p50, p99, max) = percentiles_us(&mut storm.get().lateness.lock());
+    let result = result_dict(loop_, loop_.cast::<VeloxLoop>().is_ok())?;
+    result.set_item("count", count)?;
+    result.set_item("spread", spread)?;
+    result.set_item("duration", elapsed.as_secs_f64())?;
+    result.set_item("schedule_per_sec", per_sec(count, scheduling))?;
+    result.set_item("timers_per_sec", per_sec(count, elapsed))?;
+    result.set_item("lateness_p50_us", p50)?;
+    result.set_item("lateness_p99_us", p99)?;
+    result.set_item("lateness_max_us", max)?;
+    Ok(result)
+}
+
+/// One chain of the callback scenario: each call schedules the next
+#[pyclass(frozen)]
+struct Link {
+    call_soon: Py<PyAny>,
+    left: Mutex<usize>,
+    done: Py<Countdown>,
+}
+
+#[pymethods]
+impl Link {
+    fn __call__(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.get();
+        let mut left = this.left.lock();
+        *left -= 1;
+        if *left == 0 {
+            drop(left);
+            return this.done.get().tick(py);
+        }
+        drop(left);
+        this.call_soon.bind(py).call1((slf,))?;
+        Ok(())
+    }
+}
+
+/// `width` chains of `depth` callbacks, each link scheduling the next with
+/// call_soon(), all interleaved on one loop. Returns the dispatch rate.
+#[pyfunction]
+#[pyo3(signature = (r#loop, depth=10_000, width=10))]
+fn callback_chain<'py>(
+    r#loop: &Bound<'py, PyAny>,
+    depth: usize,
+    width: usize,
+) -> PyResult<Bound<'py, PyDict>> {
+    positive("depth", depth)?;
+    positive("width", width)?;
+    let py = r#loop.py();
+    let loop_ = r#loop;
+    let done = Countdown::new(py, loop_, width)?;
+    let call_soon = loop_.getattr("call_soon")?;
+
+    let start = Instant::now();
+    for _ in 0..width {
+        let link = Link {
+            call_soon: call_soon.clone().unbind(),
+            left: Mutex::new(depth),
+            done: done.clone_ref(py),
+        };
+        call_soon.call1((Py::new(py, link)?,))?;
+    }
+    let budget = GRACE + (depth * width) as f64 * 1e-5;
+    done.get().wait(py, loop_, budget)?;
+    let elapsed = done
+        .get()
+        .finished()
+        .unwrap_or_else(Instant::now)
+        .duration_since(start);
+    settle(loop_)?;
+
+    let callbacks = depth * width;
+    let result = result_dict(loop_, loop_.cast::<VeloxLoop>().is_ok())?;
+    result.set_item("depth", depth)?;
+    result.set_item("width", width)?;
+    result.set_item("callbacks", callbacks)?;
+    result.set_item("duration", elapsed.as_secs_f64())?;
+    result.set_item("callbacks_per_sec", per_sec(callbacks, elapsed))?;
+    Ok(result)
+}
+
+pub(crate) fn add_module(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = parent.py();
+    let m = PyModule::new(py, "bench")?;
+    m.add_function(wrap_pyfunction!(echo_throughput, &m)?)?;
+    m.add_function(wrap_pyfunction!(timer_storm, &m)?)?;
+    m.add_function(wrap_pyfunction!(callback_chain, &m)?)?;
+
+    parent.add_submodule(&m)?;
+    // Importable by name too, not only reachable as an attribute
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("veloxloop._veloxloop.bench", m)
+}
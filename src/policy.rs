@@ -36,7 +36,8 @@ impl VeloxLoopPolicy {
     }
 
     fn new_event_loop(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        let loop_instance = VeloxLoop::new(None)?;
+        let loop_instance =
+            VeloxLoop::new(py, None, None, None, false, None, None, true, false, None)?;
         Ok(Py::new(py, loop_instance)?.into())
     }
 }
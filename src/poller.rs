@@ -7,8 +7,9 @@
 //! Performance features:
 //! - io-uring for zero-copy, batched I/O operations
 //! - Completion-based model with submit_read/submit_write for true async I/O
-//! - Integrated with IoUringBackend from io_backend module
 //! - Lock-free data structures via dashmap/crossbeam
+//! - Opcodes older kernels lack are emulated with PollAdd plus the plain
+//!   syscall; `BackendCapabilities` records which path is active
 
 #[cfg(target_os = "linux")]
 use std::io;
@@ -18,7 +19,10 @@ use std::os::fd::RawFd;
 use std::net::SocketAddr;
 
 #[cfg(target_os = "linux")]
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::os::fd::AsRawFd;
+
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 
 #[cfg(target_os = "linux")]
 use io_uring::{opcode, types, IoUring, Probe};
@@ -26,9 +30,15 @@ use io_uring::{opcode, types, IoUring, Probe};
 #[cfg(target_os = "linux")]
 use rustc_hash::FxHashMap;
 
+#[cfg(target_os = "linux")]
+use std::mem::ManuallyDrop;
+
+#[cfg(target_os = "linux")]
+use crate::fork::{self, ForkGuard};
+
 use std::time::Duration;
 
-/// Event type that works across platforms
+/// Readiness interest for a registered fd
 #[derive(Clone, Copy)]
 pub struct PollerEvent {
     pub readable: bool,
@@ -38,13 +48,13 @@ pub struct PollerEvent {
 impl PollerEvent {
     /// Create a new poller event with specified interest
     #[inline]
-    pub fn new(_key: usize, readable: bool, writable: bool) -> Self {
+    pub fn new(readable: bool, writable: bool) -> Self {
         Self { readable, writable }
     }
 
     /// Create an event for readable interest only
     #[inline]
-    pub fn readable(_key: usize) -> Self {
+    pub fn readable() -> Self {
         Self {
             readable: true,
             writable: false,
@@ -53,7 +63,7 @@ impl PollerEvent {
 
     /// Create an event for writable interest only
     #[inline]
-    pub fn writable(_key: usize) -> Self {
+    pub fn writable() -> Self {
         Self {
             readable: false,
             writable: true,
@@ -61,8 +71,8 @@ impl PollerEvent {
     }
 }
 
-/// Platform-specific event type representing a completed IO operation
-#[cfg(target_os = "linux")]
+/// Readiness reported by `LoopPoller::poll_native`, the one event type
+/// `_run_once` dispatches
 #[derive(Clone, Copy, Debug)]
 pub struct PlatformEvent {
     pub fd: RawFd,
@@ -80,44 +90,413 @@ pub struct IoToken(pub u64);
 #[cfg(target_os = "linux")]
 struct PendingPoll {
     fd: RawFd,
-    #[allow(dead_code)]
     readable: bool,
-    #[allow(dead_code)]
     writable: bool,
+    /// Set when this poll stands in for an opcode the kernel lacks
+    op: Option<EmulatedOp>,
+    /// Set for a completion opcode (Read, Write, ...), whose CQE carries the
+    /// operation's own result rather than poll flags
+    completion: bool,
+}
+
+/// Operation run as a plain syscall once its PollAdd reports the fd ready,
+/// for opcodes the kernel lacks. Buffers follow the io_uring contract: the
+/// caller keeps them alive until the operation completes.
+#[cfg(target_os = "linux")]
+enum EmulatedOp {
+    Read {
+        buf: *mut u8,
+        len: usize,
+        offset: Option<u64>,
+    },
+    Write {
+        buf: *const u8,
+        len: usize,
+        offset: Option<u64>,
+    },
+    Recv {
+        buf: *mut u8,
+        len: usize,
+        flags: i32,
+    },
+    Send {
+        buf: *const u8,
+        len: usize,
+        flags: i32,
+    },
+    Accept,
+    Connect,
+    Splice {
+        in_fd: RawFd,
+        offset: u64,
+        count: usize,
+    },
+}
+
+#[cfg(target_os = "linux")]
+impl EmulatedOp {
+    /// Run the syscall on `fd`; the result is shaped like a CQE's (value or -errno)
+    fn run(&self, fd: RawFd) -> i32 {
+        let ret = unsafe {
+            match *self {
+                Self::Read {
+                    buf,
+                    len,
+                    offset: Some(off),
+                } => libc::pread(fd, buf as *mut _, len, off as libc::off_t),
+                Self::Read {
+                    buf,
+                    len,
+                    offset: None,
+                } => libc::read(fd, buf as *mut _, len),
+                Self::Write {
+                    buf,
+                    len,
+                    offset: Some(off),
+                } => libc::pwrite(fd, buf as *const _, len, off as libc::off_t),
+                Self::Write {
+                    buf,
+                    len,
+                    offset: None,
+                } => libc::write(fd, buf as *const _, len),
+                Self::Recv { buf, len, flags } => libc::recv(fd, buf as *mut _, len, flags),
+                Self::Send { buf, len, flags } => libc::send(fd, buf as *const _, len, flags),
+                Self::Accept => {
+                    libc::accept4(fd, std::ptr::null_mut(), std::ptr::null_mut(), ACCEPT_FLAGS)
+                        as isize
+                }
+                Self::Connect => {
+                    let mut err: libc::c_int = 0;
+                    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+                    let ret = libc::getsockopt(
+                        fd,
+                        libc::SOL_SOCKET,
+                        libc::SO_ERROR,
+                        &mut err as *mut _ as *mut libc::c_void,
+                        &mut len,
+                    );
+                    if ret == 0 {
+                        return -err;
+                    }
+                    ret as isize
+                }
+                Self::Splice {
+                    in_fd,
+                    offset,
+                    count,
+                } => {
+                    let mut off = offset as libc::loff_t;
+                    libc::splice(in_fd, &mut off, fd, std::ptr::null_mut(), count, 0)
+                }
+            }
+        };
+        if ret < 0 { -last_errno() } else { ret as i32 }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn last_errno() -> i32 {
+    io::Error::last_os_error()
+        .raw_os_error()
+        .unwrap_or(libc::EIO)
+}
+
+/// io_uring opcodes the running kernel supports, probed when the poller is
+/// created. Operations whose opcode is missing are emulated: a PollAdd waits
+/// for readiness and the plain syscall runs when it completes. All false is
+/// the 5.1 baseline, which only has polls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BackendCapabilities {
+    /// Timeout (5.4); without it timed waits sleep on the ring fd
+    pub has_timeout: bool,
+    /// Timeout with IORING_TIMEOUT_ABS, which came with the opcode
+    pub has_timeout_abs: bool,
+    /// Read/Write (5.6)
+    pub has_read_write: bool,
+    /// Send/Recv (5.6)
+    pub has_send_recv: bool,
+    /// Accept (5.5)
+    pub has_accept: bool,
+    /// Connect (5.5)
+    pub has_connect: bool,
+    /// Close (5.6)
+    pub has_close: bool,
+    /// Splice (5.7)
+    pub has_splice: bool,
+    /// AsyncCancel (5.5); without it only polls can be withdrawn
+    pub has_async_cancel: bool,
+    /// Multishot PollAdd (5.13)
+    pub has_multishot_poll: bool,
+    /// Multishot Accept (5.19)
+    pub has_multishot_accept: bool,
+    /// OpenAt (5.6)
+    pub has_openat: bool,
+    /// Fsync (5.1, but only probeable from 5.6)
+    pub has_fsync: bool,
+}
+
+impl BackendCapabilities {
+    /// Every capability with its name, in declaration order
+    pub fn flags(&self) -> [(&'static str, bool); 13] {
+        [
+            ("has_timeout", self.has_timeout),
+            ("has_timeout_abs", self.has_timeout_abs),
+            ("has_read_write", self.has_read_write),
+            ("has_send_recv", self.has_send_recv),
+            ("has_accept", self.has_accept),
+            ("has_connect", self.has_connect),
+            ("has_close", self.has_close),
+            ("has_splice", self.has_splice),
+            ("has_async_cancel", self.has_async_cancel),
+            ("has_multishot_poll", self.has_multishot_poll),
+            ("has_multishot_accept", self.has_multishot_accept),
+            ("has_openat", self.has_openat),
+            ("has_fsync", self.has_fsync),
+        ]
+    }
+
+    /// Capabilities from a probe; None (no IORING_REGISTER_PROBE before 5.6)
+    /// means the baseline
+    #[cfg(target_os = "linux")]
+    fn from_probe(probe: Option<&Probe>) -> Self {
+        let Some(probe) = probe else {
+            return Self::default();
+        };
+        let has = |code| probe.is_supported(code);
+        Self {
+            has_timeout: has(opcode::Timeout::CODE),
+            has_timeout_abs: has(opcode::Timeout::CODE),
+            has_read_write: has(opcode::Read::CODE) && has(opcode::Write::CODE),
+            has_send_recv: has(opcode::Send::CODE) && has(opcode::Recv::CODE),
+            has_accept: has(opcode::Accept::CODE),
+            has_connect: has(opcode::Connect::CODE),
+            has_close: has(opcode::Close::CODE),
+            has_splice: has(opcode::Splice::CODE),
+            has_async_cancel: has(opcode::AsyncCancel::CODE),
+            // Flags rather than opcodes, so inferred from opcodes that
+            // arrived in the same or a later release
+            has_multishot_poll: has(opcode::MkDirAt::CODE),
+            has_multishot_accept: has(opcode::Socket::CODE),
+            has_openat: has(opcode::OpenAt::CODE),
+            has_fsync: has(opcode::Fsync::CODE),
+        }
+    }
+}
+
+/// Why io_uring can't be set up, told apart by the errno of io_uring_setup
+/// and the kernel.io_uring_disabled sysctl (6.6+)
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UringUnavailable {
+    /// ENOSYS: a kernel before 5.1 or built without io_uring, or a seccomp
+    /// filter answering ENOSYS
+    NotInKernel,
+    /// kernel.io_uring_disabled=2: refused to every process
+    DisabledBySysctl,
+    /// kernel.io_uring_disabled=1: only CAP_SYS_ADMIN and the members of
+    /// kernel.io_uring_group may create rings
+    RestrictedBySysctl,
+    /// EPERM with the sysctl allowing it, usually a container's seccomp profile
+    Blocked,
+    /// ENOMEM: before 5.12 rings are charged to RLIMIT_MEMLOCK
+    Memlock,
+    /// Anything else, by errno
+    Other(i32),
+}
+
+#[cfg(target_os = "linux")]
+impl UringUnavailable {
+    pub fn classify(errno: i32, disabled_sysctl: Option<u8>) -> Self {
+        match (errno, disabled_sysctl) {
+            (libc::ENOSYS, _) => Self::NotInKernel,
+            (libc::EPERM, Some(2)) => Self::DisabledBySysctl,
+            (libc::EPERM, Some(1)) => Self::RestrictedBySysctl,
+            (libc::EPERM | libc::EACCES, _) => Self::Blocked,
+            (libc::ENOMEM, _) => Self::Memlock,
+            (errno, _) => Self::Other(errno),
+        }
+    }
+
+    pub fn reason(&self) -> String {
+        match self {
+            Self::NotInKernel => "the kernel has no io_uring_setup syscall".to_string(),
+            Self::DisabledBySysctl => {
+                "io_uring disabled by sysctl kernel.io_uring_disabled=2".to_string()
+            }
+            Self::RestrictedBySysctl => {
+                "io_uring restricted by sysctl kernel.io_uring_disabled=1".to_string()
+            }
+            Self::Blocked => "io_uring_setup was denied, likely by a seccomp profile".to_string(),
+            Self::Memlock => "io_uring_setup ran out of locked memory".to_string(),
+            Self::Other(errno) => format!(
+                "io_uring_setup failed: {}",
+                io::Error::from_raw_os_error(*errno)
+            ),
+        }
+    }
+
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            Self::NotInKernel => {
+                "run on Linux 5.1 or later, or allow io_uring in the seccomp profile; \
+                 asyncio's default event loop works meanwhile"
+            }
+            Self::DisabledBySysctl => {
+                "set kernel.io_uring_disabled=0, or use asyncio's default event loop"
+            }
+            Self::RestrictedBySysctl => {
+                "add the user to the group in kernel.io_uring_group or set \
+                 kernel.io_uring_disabled=0"
+            }
+            Self::Blocked => {
+                "allow io_uring_setup, io_uring_enter and io_uring_register in the \
+                 container's seccomp profile"
+            }
+            Self::Memlock => "raise RLIMIT_MEMLOCK (ulimit -l)",
+            Self::Other(_) => "use asyncio's default event loop",
+        }
+    }
 }
 
+/// kernel.io_uring_disabled, None on kernels without the sysctl
 #[cfg(target_os = "linux")]
-const SQ_SIZE: u32 = 256;
+pub fn io_uring_disabled_sysctl() -> Option<u8> {
+    std::fs::read_to_string("/proc/sys/kernel/io_uring_disabled")
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Set up a small ring to see whether io_uring works here and which opcodes
+/// it has; the errno of a failure goes to `UringUnavailable::classify`
 #[cfg(target_os = "linux")]
-const CQ_SIZE: u32 = 512;
+pub fn probe_io_uring() -> io::Result<BackendCapabilities> {
+    let ring: IoUring = IoUring::new(2)?;
+    Ok(probe_capabilities(&ring))
+}
 
-/// Thread-safe waker for the event loop
-#[derive(Clone)]
+/// Whether a ring polled by a kernel SQPOLL thread can be set up for plain
+/// fds, as `LoopPoller::with_sqpoll` needs
+#[cfg(target_os = "linux")]
+pub fn probe_sqpoll() -> io::Result<()> {
+    let ring: IoUring = IoUring::builder().setup_sqpoll(10).build(2)?;
+    if !ring.params().is_feature_sqpoll_nonfixed() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "kernel only supports SQPOLL with registered files",
+        ));
+    }
+    Ok(())
+}
+
+/// Opcodes `ring`'s kernel supports. Kernels before 5.6 can't be probed and
+/// get the baseline.
+#[cfg(target_os = "linux")]
+fn probe_capabilities(ring: &IoUring) -> BackendCapabilities {
+    let mut probe = Probe::new();
+    let probed = ring.submitter().register_probe(&mut probe).is_ok();
+    BackendCapabilities::from_probe(probed.then_some(&probe))
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) const SQ_SIZE: u32 = 256;
+#[cfg(target_os = "linux")]
+pub(crate) const CQ_SIZE: u32 = 512;
+/// Accepted connections are non-blocking and never inherited by a subprocess
+#[cfg(target_os = "linux")]
+const ACCEPT_FLAGS: libc::c_int = libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC;
+
+/// Longest `LoopPoller::shutdown` waits for the kernel to confirm the
+/// cancellation of what was still in flight
+#[cfg(target_os = "linux")]
+const SHUTDOWN_WAIT: Duration = Duration::from_millis(250);
+
+/// `PollerWaker::notify` writes that failed, across the process
+#[cfg(target_os = "linux")]
+static NOTIFY_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// How many wakeups could not be written to their eventfd
+#[cfg(target_os = "linux")]
+pub fn notify_failures() -> u64 {
+    NOTIFY_FAILURES.load(Ordering::Relaxed)
+}
+
+/// Thread-safe waker for the event loop. Owns its own duplicate of the
+/// poller's eventfd, so it stays valid after the poller is closed.
 pub struct PollerWaker {
     eventfd: RawFd,
+    /// Set once the loop is closed; nothing polls the eventfd any more
+    shut_down: AtomicBool,
+    owner: ForkGuard,
 }
 
 impl PollerWaker {
-    pub fn new(eventfd: RawFd) -> Self {
-        Self { eventfd }
+    pub fn new(eventfd: RawFd) -> crate::utils::VeloxResult<Self> {
+        let eventfd = unsafe { libc::fcntl(eventfd, libc::F_DUPFD_CLOEXEC, 0) };
+        if eventfd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        fork::track_fd(eventfd);
+        Ok(Self {
+            eventfd,
+            shut_down: AtomicBool::new(false),
+            owner: ForkGuard::new(),
+        })
     }
 
-    /// Wake up the poller from any thread
+    /// Wake up the poller from any thread. A no-op once the loop is closed;
+    /// the fd itself stays open until the last handle on the waker is gone,
+    /// so a racing call never writes to a reused fd number
     #[inline]
     pub fn notify(&self) -> crate::utils::VeloxResult<()> {
+        self.owner.check()?;
+        if self.shut_down.load(Ordering::Acquire) {
+            return Ok(());
+        }
         let val: u64 = 1;
         unsafe {
             if libc::write(self.eventfd, &val as *const _ as *const _, 8) < 0 {
+                NOTIFY_FAILURES.fetch_add(1, Ordering::Relaxed);
                 return Err(std::io::Error::last_os_error().into());
             }
         }
         Ok(())
     }
+
+    /// Stop waking the poller, at loop close
+    pub fn shutdown(&self) {
+        self.shut_down.store(true, Ordering::Release);
+    }
+}
+
+impl Drop for PollerWaker {
+    fn drop(&mut self) {
+        if self.owner.is_forked() {
+            return;
+        }
+        fork::untrack_fd(self.eventfd);
+        unsafe {
+            libc::close(self.eventfd);
+        }
+    }
+}
+
+/// Rings built by `LoopPoller::build` in this process; a loop builds its
+/// backend once, however many times it is run
+#[cfg(target_os = "linux")]
+static BACKEND_BUILDS: AtomicU64 = AtomicU64::new(0);
+
+/// How many io_uring backends this process has built
+#[cfg(target_os = "linux")]
+pub fn backend_builds() -> u64 {
+    BACKEND_BUILDS.load(Ordering::Relaxed)
 }
 
 pub struct LoopPoller {
-    /// The io-uring instance
-    ring: IoUring,
+    /// The io-uring instance; leaked in a fork child, whose copy of the
+    /// mapping is still shared with the parent's ring
+    ring: ManuallyDrop<IoUring>,
     /// Token counter for operations
     token_counter: AtomicU64,
     /// Track registered FDs and their poll tokens
@@ -128,26 +507,88 @@ pub struct LoopPoller {
     eventfd: RawFd,
     /// Token for eventfd poll
     eventfd_token: u64,
-    /// Probe for checking supported operations (kept for future op support checks)
-    #[allow(dead_code)]
-    probe: Probe,
+    /// Self-pipe handed out by `wakeup_fd()` as (read end, write end).
+    /// Unlike the eventfd it accepts single-byte writes, which is what
+    /// `signal.set_wakeup_fd` produces.
+    wakeup_pipe: Option<(RawFd, RawFd)>,
+    /// Token for the self-pipe poll
+    wakeup_pipe_token: u64,
+    /// Opcodes the kernel supports; the rest are emulated
+    capabilities: BackendCapabilities,
+    /// Results of emulated operations that completed without a CQE of
+    /// their own, by token
+    ready_ops: FxHashMap<u64, i32>,
     pending_submissions: AtomicUsize,
     last_submit_time: parking_lot::Mutex<std::time::Instant>,
+    /// Idle time of the kernel's submission polling thread, when the ring
+    /// was built with SQPOLL
+    sqpoll_idle_ms: Option<u32>,
+    /// Submits that had to wake the idle SQPOLL thread
+    sqpoll_wakeups: u64,
+    /// Fd polls added through `register`/`modify`, re-armed through
+    /// `rearm_oneshot`, and cancelled with a PollRemove
+    fd_registers: u64,
+    fd_rearms: u64,
+    fd_deletes: u64,
+    /// CQEs copied out of the ring by `poll_native`, kept between polls so
+    /// an idle wakeup doesn't allocate
+    completions: Vec<(u64, i32)>,
+    /// Set by `shutdown`: the in-flight operations are cancelled, the
+    /// eventfd and self-pipe closed, and nothing more is submitted
+    shut_down: bool,
+    /// Process the ring belongs to; nothing is submitted from a fork child
+    owner: ForkGuard,
 }
 
 #[cfg(target_os = "linux")]
 impl LoopPoller {
     pub fn new() -> crate::utils::VeloxResult<Self> {
-        let ring = IoUring::builder()
-            .setup_cqsize(CQ_SIZE)
+        Self::build(None, None)
+    }
+
+    /// A poller whose ring is drained by a kernel SQPOLL thread that sleeps
+    /// after `idle_ms` without submissions. Falls back to a regular ring,
+    /// returning why, when the kernel or its permissions refuse SQPOLL or
+    /// only allow it for registered files.
+    pub fn with_sqpoll(idle_ms: u32) -> crate::utils::VeloxResult<(Self, Option<io::Error>)> {
+        let refused = match Self::build(None, Some(idle_ms)) {
+            Ok(poller) if poller.ring.params().is_feature_sqpoll_nonfixed() => {
+                return Ok((poller, None));
+            }
+            Ok(_) => io::Error::new(
+                io::ErrorKind::Unsupported,
+                "kernel only supports SQPOLL with registered files",
+            ),
+            Err(crate::utils::VeloxError::Io(e)) => e,
+            Err(e) => return Err(e),
+        };
+        Ok((Self::build(None, None)?, Some(refused)))
+    }
+
+    /// A poller that trusts `capabilities` over the kernel's probe, so the
+    /// emulated paths can be exercised on any kernel
+    #[cfg(test)]
+    pub(crate) fn with_capabilities(
+        capabilities: BackendCapabilities,
+    ) -> crate::utils::VeloxResult<Self> {
+        Self::build(Some(capabilities), None)
+    }
+
+    fn build(
+        forced: Option<BackendCapabilities>,
+        sqpoll_idle_ms: Option<u32>,
+    ) -> crate::utils::VeloxResult<Self> {
+        let mut builder = IoUring::builder();
+        builder.setup_cqsize(CQ_SIZE);
+        if let Some(idle) = sqpoll_idle_ms {
+            builder.setup_sqpoll(idle);
+        }
+        let ring = builder
             .build(SQ_SIZE)
             .map_err(crate::utils::VeloxError::Io)?;
 
-        // Probe for supported operations
-        let mut probe = Probe::new();
-        ring.submitter()
-            .register_probe(&mut probe)
-            .map_err(crate::utils::VeloxError::Io)?;
+        let capabilities = forced.unwrap_or_else(|| probe_capabilities(&ring));
+        BACKEND_BUILDS.fetch_add(1, Ordering::Relaxed);
 
         // Create eventfd for waking
         let eventfd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
@@ -155,16 +596,29 @@ impl LoopPoller {
             return Err(std::io::Error::last_os_error().into());
         }
 
+        fork::track_fd(ring.as_raw_fd());
+        fork::track_fd(eventfd);
         let mut poller = Self {
-            ring,
+            ring: ManuallyDrop::new(ring),
             token_counter: AtomicU64::new(1),
             fd_tokens: FxHashMap::with_capacity_and_hasher(256, Default::default()),
             pending_polls: FxHashMap::with_capacity_and_hasher(256, Default::default()),
             eventfd,
             eventfd_token: 0,
-            probe,
+            wakeup_pipe: None,
+            wakeup_pipe_token: 0,
+            capabilities,
+            ready_ops: FxHashMap::default(),
             pending_submissions: AtomicUsize::new(0),
             last_submit_time: parking_lot::Mutex::new(std::time::Instant::now()),
+            sqpoll_idle_ms,
+            sqpoll_wakeups: 0,
+            fd_registers: 0,
+            fd_rearms: 0,
+            fd_deletes: 0,
+            completions: Vec::new(),
+            shut_down: false,
+            owner: ForkGuard::new(),
         };
 
         // Register eventfd for notifications
@@ -175,11 +629,170 @@ impl LoopPoller {
         Ok(poller)
     }
 
+    /// Make room for `fds` registered fds up front, so the first connections
+    /// don't grow the token maps
+    pub fn reserve(&mut self, fds: usize) {
+        self.fd_tokens
+            .reserve(fds.saturating_sub(self.fd_tokens.len()));
+        // A read and a write poll per fd at most
+        let polls = fds * 2;
+        self.pending_polls
+            .reserve(polls.saturating_sub(self.pending_polls.len()));
+        self.completions
+            .reserve(polls.saturating_sub(self.completions.len()));
+    }
+
+    /// Push one no-op through the ring and wait for the kernel to complete
+    /// it, so the submission and completion paths are set up before the
+    /// first real operation. Its CQE (token 0) is skipped by the next poll.
+    pub fn warm_up(&mut self) -> crate::utils::VeloxResult<()> {
+        let nop = opcode::Nop::new().build().user_data(0);
+        unsafe {
+            self.submission()?
+                .push(&nop)
+                .map_err(|_| std::io::Error::other("SQ full"))?;
+        }
+        // Anything batched so far goes in with it
+        self.submit_queued_and_wait(1)?;
+        self.pending_submissions.store(0, Ordering::Relaxed);
+        *self.last_submit_time.lock() = std::time::Instant::now();
+        Ok(())
+    }
+
+    /// Opcodes the kernel supports, as probed at construction
+    pub fn capabilities(&self) -> BackendCapabilities {
+        self.capabilities
+    }
+
+    /// SQPOLL thread idle time, if the ring is being polled by the kernel
+    pub fn sqpoll_idle_ms(&self) -> Option<u32> {
+        self.sqpoll_idle_ms
+    }
+
+    /// How many submits found the SQPOLL thread asleep and had to enter the
+    /// kernel to wake it
+    pub fn sqpoll_wakeups(&self) -> u64 {
+        self.sqpoll_wakeups
+    }
+
+    /// `(registers, rearms, deletes)`: fd polls submitted fresh, re-armed
+    /// after firing, and cancelled
+    pub fn fd_op_counts(&self) -> (u64, u64, u64) {
+        (self.fd_registers, self.fd_rearms, self.fd_deletes)
+    }
+
     /// Get a thread-safe waker for this poller
-    pub fn waker(&self) -> PollerWaker {
+    pub fn waker(&self) -> crate::utils::VeloxResult<PollerWaker> {
+        self.owner.check()?;
         PollerWaker::new(self.eventfd)
     }
 
+    /// Write end of the self-pipe; writing any byte to it interrupts the
+    /// current poll. The read end stays registered and is drained here.
+    pub fn wakeup_fd(&mut self) -> crate::utils::VeloxResult<RawFd> {
+        self.owner.check()?;
+        if let Some((_, write_fd)) = self.wakeup_pipe {
+            return Ok(write_fd);
+        }
+        let mut fds = [0 as RawFd; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        fork::track_fd(fds[0]);
+        fork::track_fd(fds[1]);
+        self.wakeup_pipe = Some((fds[0], fds[1]));
+        self.wakeup_pipe_token = self.next_token();
+        self.submit_poll_add(fds[0], true, false, self.wakeup_pipe_token)?;
+        Ok(fds[1])
+    }
+
+    /// The submission queue, refused in a fork child (its memory is the
+    /// parent's ring) and after `shutdown`
+    #[inline]
+    fn submission(&mut self) -> crate::utils::VeloxResult<io_uring::SubmissionQueue<'_>> {
+        self.owner.check()?;
+        if self.shut_down {
+            return Err(crate::utils::VeloxError::RuntimeError(
+                "Event loop is closed".to_string(),
+            ));
+        }
+        Ok(self.ring.submission())
+    }
+
+    /// Tear the backend down in order: cancel every operation still in
+    /// flight (the eventfd and self-pipe polls included), wait up to
+    /// `SHUTDOWN_WAIT` for the kernel to complete them, drain the CQ, and
+    /// only then close the eventfd and the self-pipe, so no poll can outlive
+    /// the fd it watches and land on the number once it is reused.
+    /// Idempotent; the ring itself goes when the poller is dropped.
+    pub fn shutdown(&mut self) {
+        if self.shut_down {
+            return;
+        }
+        self.shut_down = true;
+        if self.owner.is_forked() {
+            // The fork handler closed the fds, and the ring is the parent's
+            return;
+        }
+
+        let mut outstanding: rustc_hash::FxHashSet<u64> =
+            self.pending_polls.keys().copied().collect();
+        for (&token, pending) in &self.pending_polls {
+            // Emulated operations are polls underneath
+            let cancel = if pending.completion && self.capabilities.has_async_cancel {
+                opcode::AsyncCancel::new(token).build()
+            } else {
+                opcode::PollRemove::new(token).build()
+            }
+            .user_data(0);
+            unsafe {
+                while self.ring.submission().push(&cancel).is_err() {
+                    // SQ full: hand what's queued to the kernel and retry
+                    if self.ring.submit().is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = self.ring.submit();
+
+        let deadline = std::time::Instant::now() + SHUTDOWN_WAIT;
+        loop {
+            for cqe in self.ring.completion() {
+                outstanding.remove(&cqe.user_data());
+            }
+            let left = deadline.saturating_duration_since(std::time::Instant::now());
+            if outstanding.is_empty() || left.is_zero() {
+                break;
+            }
+            let mut pfd = libc::pollfd {
+                fd: self.ring.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ms = left.as_nanos().div_ceil(1_000_000) as i32;
+            unsafe { libc::poll(&mut pfd, 1, ms) };
+        }
+
+        self.pending_polls.clear();
+        self.fd_tokens.clear();
+        self.ready_ops.clear();
+        self.completions.clear();
+        self.pending_submissions.store(0, Ordering::Relaxed);
+
+        fork::untrack_fd(self.eventfd);
+        unsafe {
+            libc::close(self.eventfd);
+            if let Some((read_fd, write_fd)) = self.wakeup_pipe.take() {
+                fork::untrack_fd(read_fd);
+                fork::untrack_fd(write_fd);
+                libc::close(read_fd);
+                libc::close(write_fd);
+            }
+        }
+        self.eventfd = -1;
+    }
+
     #[inline]
     fn next_token(&self) -> u64 {
         self.token_counter.fetch_add(1, Ordering::Relaxed)
@@ -209,8 +822,7 @@ impl LoopPoller {
             .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&poll_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
@@ -221,6 +833,8 @@ impl LoopPoller {
                 fd,
                 readable,
                 writable,
+                op: None,
+                completion: false,
             },
         );
 
@@ -238,13 +852,65 @@ impl LoopPoller {
             .user_data(0); // We don't track cancellation completions
 
         unsafe {
-            let _ = self.ring.submission().push(&cancel_e);
+            if let Ok(mut sq) = self.submission() {
+                let _ = sq.push(&cancel_e);
+            }
         }
 
         self.pending_polls.remove(&token);
+        self.fd_deletes += 1;
         Ok(())
     }
 
+    /// Stand-in for an opcode the kernel lacks: PollAdd waits for the fd,
+    /// then `op` runs as a syscall when the poll completes
+    fn submit_emulated(
+        &mut self,
+        fd: RawFd,
+        readable: bool,
+        writable: bool,
+        op: EmulatedOp,
+    ) -> crate::utils::VeloxResult<IoToken> {
+        let token = self.next_token();
+        self.submit_poll_add(fd, readable, writable, token)?;
+        if let Some(pending) = self.pending_polls.get_mut(&token) {
+            pending.op = Some(op);
+        }
+        let _ = self.submit_queued();
+        Ok(IoToken(token))
+    }
+
+    /// Token for an emulated operation that finished on submission
+    fn complete_now(&mut self, result: i32) -> IoToken {
+        let token = self.next_token();
+        self.ready_ops.insert(token, result);
+        IoToken(token)
+    }
+
+    /// The poll behind an emulated operation completed: run the syscall, or
+    /// wait again if it would still block
+    fn finish_emulated(&mut self, token: u64, mut pending: PendingPoll, poll_result: i32) {
+        let Some(op) = pending.op.take() else {
+            return;
+        };
+        let result = if poll_result < 0 {
+            poll_result
+        } else {
+            op.run(pending.fd)
+        };
+        if result == -libc::EAGAIN
+            && self
+                .submit_poll_add(pending.fd, pending.readable, pending.writable, token)
+                .is_ok()
+        {
+            if let Some(pending) = self.pending_polls.get_mut(&token) {
+                pending.op = Some(op);
+            }
+            return;
+        }
+        self.ready_ops.insert(token, result);
+    }
+
     /// Register FD with specific interest
     #[inline]
     pub fn register(
@@ -260,6 +926,7 @@ impl LoopPoller {
         let token = self.next_token();
         self.fd_tokens.insert(fd, IoToken(token));
         self.submit_poll_add(fd, interest.readable, interest.writable, token)?;
+        self.fd_registers += 1;
 
         Ok(())
     }
@@ -283,9 +950,15 @@ impl LoopPoller {
         fd: RawFd,
         interest: PollerEvent,
     ) -> crate::utils::VeloxResult<()> {
+        // Re-armed while a poll is still out (registered again in between):
+        // that one would otherwise fire as well
+        if let Some(&IoToken(old_token)) = self.fd_tokens.get(&fd) {
+            self.submit_poll_remove(old_token)?;
+        }
         let token = self.next_token();
         self.fd_tokens.insert(fd, IoToken(token));
         self.submit_poll_add(fd, interest.readable, interest.writable, token)?;
+        self.fd_rearms += 1;
         Ok(())
     }
 
@@ -300,10 +973,38 @@ impl LoopPoller {
         let token = self.next_token();
         self.fd_tokens.insert(fd, IoToken(token));
         self.submit_poll_add(fd, interest.readable, interest.writable, token)?;
+        self.fd_registers += 1;
 
         Ok(())
     }
 
+    /// Whether a poll for `fd` is submitted and hasn't fired yet
+    #[inline]
+    pub fn is_armed(&self, fd: RawFd) -> bool {
+        self.fd_tokens.contains_key(&fd)
+    }
+
+    /// Whether the poll out on `fd` asks for exactly `interest`. The re-arm
+    /// after a dispatch checks this first: a callback that removed its
+    /// handler and added another registered the right poll already, and
+    /// replacing it would only cancel a poll that was just submitted
+    #[inline]
+    pub fn is_armed_for(&self, fd: RawFd, interest: PollerEvent) -> bool {
+        self.fd_tokens
+            .get(&fd)
+            .and_then(|IoToken(token)| self.pending_polls.get(token))
+            .is_some_and(|p| p.readable == interest.readable && p.writable == interest.writable)
+    }
+
+    /// A poll on `fd` completed: forget it unless `fd` has been registered
+    /// again since, in which case the mapping names the replacement
+    #[inline]
+    fn forget_token(&mut self, fd: RawFd, token: u64) {
+        if self.fd_tokens.get(&fd) == Some(&IoToken(token)) {
+            self.fd_tokens.remove(&fd);
+        }
+    }
+
     /// Delete FD from monitoring
     #[inline]
     pub fn delete(&mut self, fd: RawFd) -> crate::utils::VeloxResult<()> {
@@ -314,46 +1015,93 @@ impl LoopPoller {
     }
 
     /// Poll for events using io-uring
+    #[cfg(any(test, feature = "bench"))]
     #[inline]
     pub fn poll_native(
         &mut self,
         timeout: Option<std::time::Duration>,
     ) -> crate::utils::VeloxResult<Vec<PlatformEvent>> {
-        let should_flush = {
+        let mut events = Vec::new();
+        self.poll_into(timeout, &mut events)?;
+        Ok(events)
+    }
+
+    /// `poll_native` appending to a buffer the caller keeps between polls
+    #[inline]
+    pub fn poll_into(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+        events: &mut Vec<PlatformEvent>,
+    ) -> crate::utils::VeloxResult<()> {
+        // Completions in a fork child's mapping are the parent's to reap
+        self.owner.check()?;
+        if self.shut_down {
+            return Err(crate::utils::VeloxError::RuntimeError(
+                "Event loop is closed".to_string(),
+            ));
+        }
+        // Nothing queued means nothing to flush, without reading the clock
+        let should_flush = self.pending_submissions.load(Ordering::Relaxed) > 0 && {
             let last_submit = *self.last_submit_time.lock();
             last_submit.elapsed() > Duration::from_micros(100) // 100µs batching window
         };
-        
+
         if should_flush {
             self.flush_submissions()?;
         }
 
         // Use submit_and_wait with timeout
-        if let Some(dur) = timeout {
-            if dur > Duration::ZERO {
-                let ts = types::Timespec::new()
-                    .sec(dur.as_secs() as u64)
-                    .nsec(dur.subsec_nanos() as u32);
-                
-                let timeout_e = opcode::Timeout::new(&ts).build().user_data(0);
-                unsafe { let _ = self.ring.submission().push(&timeout_e); }
+        let timed = timeout.filter(|dur| *dur > Duration::ZERO);
+        if let Some(dur) = timed
+            && self.capabilities.has_timeout
+        {
+            let ts = types::Timespec::new()
+                .sec(dur.as_secs())
+                .nsec(dur.subsec_nanos());
+
+            let timeout_e = opcode::Timeout::new(&ts).build().user_data(0);
+            unsafe {
+                let _ = self.ring.submission().push(&timeout_e);
             }
         }
-        
-        let want = if timeout == Some(Duration::ZERO) { 0 } else { 1 };
-        let _ = self.ring.submit_and_wait(want);
+
+        if let Some(dur) = timed
+            && !self.capabilities.has_timeout
+        {
+            // No Timeout opcode: the ring fd polls readable once a
+            // completion is posted, so sleep on that instead
+            let _ = self.submit_queued();
+            let mut pfd = libc::pollfd {
+                fd: self.ring.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ms = dur.as_nanos().div_ceil(1_000_000).min(i32::MAX as u128) as i32;
+            unsafe { libc::poll(&mut pfd, 1, ms) };
+        } else {
+            let want = if timeout == Some(Duration::ZERO) {
+                0
+            } else {
+                1
+            };
+            let _ = self.submit_queued_and_wait(want);
+        }
 
         // Collect completions first to avoid borrow issues
-        let completions: Vec<(u64, i32)> = {
-            let cq = self.ring.completion();
-            cq.map(|cqe| (cqe.user_data(), cqe.result())).collect()
-        };
+        let mut completions = std::mem::take(&mut self.completions);
+        completions.extend(
+            self.ring
+                .completion()
+                .map(|cqe| (cqe.user_data(), cqe.result())),
+        );
 
-        let mut events = Vec::with_capacity(completions.len());
+        // Timeout CQEs (token 0) produce no event; an idle wakeup is only those
+        events.reserve(completions.iter().filter(|(t, _)| *t != 0).count());
         let mut need_rearm_eventfd = false;
+        let mut need_rearm_pipe = false;
         
         // Process collected completions
-        for (token, result) in completions {
+        for (token, result) in completions.drain(..) {
             // Skip timeout completions and cancellation completions
             if token == 0 {
                 continue;
@@ -366,24 +1114,45 @@ impl LoopPoller {
                 unsafe {
                     let _ = libc::read(self.eventfd, &mut buf as *mut _ as *mut _, 8);
                 }
+                self.pending_polls.remove(&token);
                 need_rearm_eventfd = true;
                 continue;
             }
 
+            // Handle self-pipe wakeup: read until empty so it doesn't fire again
+            if let Some((read_fd, _)) = self.wakeup_pipe
+                && token == self.wakeup_pipe_token
+            {
+                let mut buf = [0u8; 256];
+                while unsafe { libc::read(read_fd, buf.as_mut_ptr() as *mut _, buf.len()) } > 0 {}
+                self.pending_polls.remove(&token);
+                need_rearm_pipe = true;
+                continue;
+            }
+
             // Get the pending poll info
             if let Some(pending) = self.pending_polls.remove(&token) {
-                if result >= 0 {
+                if pending.op.is_some() {
+                    self.finish_emulated(token, pending, result);
+                } else if pending.completion {
+                    self.ready_ops.insert(token, result);
+                } else if result >= 0 {
                     let poll_events = result as u32;
+                    // A pending socket error (e.g. ICMP unreachable on a connected UDP
+                    // socket) wakes both sides like selectors' epoll mapping does, so
+                    // the recv/send in the callback picks it up
+                    let sock_error = (poll_events & libc::POLLERR as u32) != 0;
                     events.push(PlatformEvent {
                         fd: pending.fd,
                         readable: (poll_events & libc::POLLIN as u32) != 0
-                            || (poll_events & libc::POLLHUP as u32) != 0,
-                        writable: (poll_events & libc::POLLOUT as u32) != 0,
-                        error: (poll_events & libc::POLLERR as u32) != 0,
+                            || (poll_events & libc::POLLHUP as u32) != 0
+                            || sock_error,
+                        writable: (poll_events & libc::POLLOUT as u32) != 0 || sock_error,
+                        error: false,
                     });
 
                     // Remove the fd -> token mapping since poll completed
-                    self.fd_tokens.remove(&pending.fd);
+                    self.forget_token(pending.fd, token);
                 } else if result == -libc::ECANCELED {
                     // Poll was cancelled, ignore
                 } else {
@@ -394,7 +1163,7 @@ impl LoopPoller {
                         writable: false,
                         error: true,
                     });
-                    self.fd_tokens.remove(&pending.fd);
+                    self.forget_token(pending.fd, token);
                 }
             }
         }
@@ -404,8 +1173,13 @@ impl LoopPoller {
             self.eventfd_token = self.next_token();
             let _ = self.submit_poll_add(self.eventfd, true, false, self.eventfd_token);
         }
+        if need_rearm_pipe && let Some((read_fd, _)) = self.wakeup_pipe {
+            self.wakeup_pipe_token = self.next_token();
+            let _ = self.submit_poll_add(read_fd, true, false, self.wakeup_pipe_token);
+        }
+        self.completions = completions;
 
-        Ok(events)
+        Ok(())
     }
     /// Submit an async read operation via io-uring
     /// Returns a token to track completion
@@ -418,6 +1192,14 @@ impl LoopPoller {
     ) -> crate::utils::VeloxResult<IoToken> {
         use crate::constants::POLLER_BATCH_THRESHOLD;
 
+        if !self.capabilities.has_read_write {
+            let op = EmulatedOp::Read {
+                buf: buf.as_mut_ptr(),
+                len: buf.len(),
+                offset,
+            };
+            return self.submit_emulated(fd, true, false, op);
+        }
 
         let token = self.next_token();
         let off = offset.unwrap_or(u64::MAX); // -1 for current position
@@ -428,8 +1210,7 @@ impl LoopPoller {
             .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&read_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
@@ -440,6 +1221,8 @@ impl LoopPoller {
                 fd,
                 readable: true,
                 writable: false,
+                op: None,
+                completion: true,
             },
         );
 
@@ -453,13 +1236,32 @@ impl LoopPoller {
     #[inline]
     fn flush_submissions(&mut self) -> io::Result<()> {
         if self.pending_submissions.load(Ordering::Relaxed) > 0 {
-            self.ring.submit()?;
+            self.submit_queued()?;
             self.pending_submissions.store(0, Ordering::Relaxed);
             *self.last_submit_time.lock() = std::time::Instant::now();
         }
         Ok(())
     }
 
+    /// Hand queued SQEs to the kernel. Under SQPOLL the kernel thread picks
+    /// them up by itself, so this only enters the kernel once the thread has
+    /// gone idle and set IORING_SQ_NEED_WAKEUP.
+    #[inline]
+    fn submit_queued(&mut self) -> io::Result<usize> {
+        self.submit_queued_and_wait(0)
+    }
+
+    #[inline]
+    fn submit_queued_and_wait(&mut self, want: usize) -> io::Result<usize> {
+        if self.owner.is_forked() {
+            return Err(io::Error::from_raw_os_error(libc::EBADF));
+        }
+        if self.sqpoll_idle_ms.is_some() && self.ring.submission().need_wakeup() {
+            self.sqpoll_wakeups += 1;
+        }
+        self.ring.submit_and_wait(want)
+    }
+
     /// Submit an async write operation via io-uring
     #[inline]
     pub fn submit_write(
@@ -468,6 +1270,15 @@ impl LoopPoller {
         buf: &[u8],
         offset: Option<u64>,
     ) -> crate::utils::VeloxResult<IoToken> {
+        if !self.capabilities.has_read_write {
+            let op = EmulatedOp::Write {
+                buf: buf.as_ptr(),
+                len: buf.len(),
+                offset,
+            };
+            return self.submit_emulated(fd, false, true, op);
+        }
+
         let token = self.next_token();
         let off = offset.unwrap_or(u64::MAX);
 
@@ -477,8 +1288,7 @@ impl LoopPoller {
             .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&write_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
@@ -489,10 +1299,12 @@ impl LoopPoller {
                 fd,
                 readable: false,
                 writable: true,
+                op: None,
+                completion: true,
             },
         );
 
-        let _ = self.ring.submit();
+        let _ = self.submit_queued();
         Ok(IoToken(token))
     }
 
@@ -504,6 +1316,15 @@ impl LoopPoller {
         buf: &mut [u8],
         flags: i32,
     ) -> crate::utils::VeloxResult<IoToken> {
+        if !self.capabilities.has_send_recv {
+            let op = EmulatedOp::Recv {
+                buf: buf.as_mut_ptr(),
+                len: buf.len(),
+                flags,
+            };
+            return self.submit_emulated(fd, true, false, op);
+        }
+
         let token = self.next_token();
 
         let recv_e = opcode::Recv::new(types::Fd(fd), buf.as_mut_ptr(), buf.len() as u32)
@@ -512,8 +1333,7 @@ impl LoopPoller {
             .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&recv_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
@@ -524,10 +1344,12 @@ impl LoopPoller {
                 fd,
                 readable: true,
                 writable: false,
+                op: None,
+                completion: true,
             },
         );
 
-        let _ = self.ring.submit();
+        let _ = self.submit_queued();
         Ok(IoToken(token))
     }
 
@@ -539,6 +1361,15 @@ impl LoopPoller {
         buf: &[u8],
         flags: i32,
     ) -> crate::utils::VeloxResult<IoToken> {
+        if !self.capabilities.has_send_recv {
+            let op = EmulatedOp::Send {
+                buf: buf.as_ptr(),
+                len: buf.len(),
+                flags,
+            };
+            return self.submit_emulated(fd, false, true, op);
+        }
+
         let token = self.next_token();
 
         let send_e = opcode::Send::new(types::Fd(fd), buf.as_ptr(), buf.len() as u32)
@@ -547,8 +1378,7 @@ impl LoopPoller {
             .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&send_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
@@ -559,25 +1389,31 @@ impl LoopPoller {
                 fd,
                 readable: false,
                 writable: true,
+                op: None,
+                completion: true,
             },
         );
 
-        let _ = self.ring.submit();
+        let _ = self.submit_queued();
         Ok(IoToken(token))
     }
 
     /// Submit an async accept operation via io-uring
     #[inline]
     pub fn submit_accept(&mut self, fd: RawFd) -> crate::utils::VeloxResult<IoToken> {
+        if !self.capabilities.has_accept {
+            return self.submit_emulated(fd, true, false, EmulatedOp::Accept);
+        }
+
         let token = self.next_token();
 
         let accept_e = opcode::Accept::new(types::Fd(fd), std::ptr::null_mut(), std::ptr::null_mut())
+            .flags(ACCEPT_FLAGS)
             .build()
             .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&accept_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
@@ -588,10 +1424,12 @@ impl LoopPoller {
                 fd,
                 readable: true,
                 writable: false,
+                op: None,
+                completion: true,
             },
         );
 
-        let _ = self.ring.submit();
+        let _ = self.submit_queued();
         Ok(IoToken(token))
     }
 
@@ -602,8 +1440,21 @@ impl LoopPoller {
         fd: RawFd,
         addr: SocketAddr,
     ) -> crate::utils::VeloxResult<IoToken> {
-        let token = self.next_token();
         let sock_addr: socket2::SockAddr = addr.into();
+        if !self.capabilities.has_connect {
+            // Nonblocking connect, then wait for writability and read SO_ERROR
+            let ret = unsafe { libc::connect(fd, sock_addr.as_ptr() as *const _, sock_addr.len()) };
+            if ret == 0 {
+                return Ok(self.complete_now(0));
+            }
+            let errno = last_errno();
+            if errno != libc::EINPROGRESS {
+                return Ok(self.complete_now(-errno));
+            }
+            return self.submit_emulated(fd, false, true, EmulatedOp::Connect);
+        }
+
+        let token = self.next_token();
 
         let connect_e = opcode::Connect::new(
             types::Fd(fd),
@@ -614,8 +1465,7 @@ impl LoopPoller {
         .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&connect_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
@@ -626,16 +1476,23 @@ impl LoopPoller {
                 fd,
                 readable: false,
                 writable: true,
+                op: None,
+                completion: true,
             },
         );
 
-        let _ = self.ring.submit();
+        let _ = self.submit_queued();
         Ok(IoToken(token))
     }
 
     /// Submit an async close operation via io-uring
     #[inline]
     pub fn submit_close(&mut self, fd: RawFd) -> crate::utils::VeloxResult<IoToken> {
+        if !self.capabilities.has_close {
+            let ret = unsafe { libc::close(fd) };
+            return Ok(self.complete_now(if ret < 0 { -last_errno() } else { 0 }));
+        }
+
         let token = self.next_token();
 
         let close_e = opcode::Close::new(types::Fd(fd))
@@ -643,13 +1500,100 @@ impl LoopPoller {
             .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&close_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
 
-        let _ = self.ring.submit();
+        self.pending_polls.insert(
+            token,
+            PendingPoll {
+                fd,
+                readable: false,
+                writable: false,
+                op: None,
+                completion: true,
+            },
+        );
+
+        let _ = self.submit_queued();
+        Ok(IoToken(token))
+    }
+
+    /// Submit an async openat relative to the working directory. `path` must
+    /// stay alive until the operation completes; the result is the new fd.
+    /// Without the opcode the open runs right away.
+    #[inline]
+    pub fn submit_openat(
+        &mut self,
+        path: &std::ffi::CStr,
+        flags: i32,
+        mode: u32,
+    ) -> crate::utils::VeloxResult<IoToken> {
+        if !self.capabilities.has_openat {
+            let ret = unsafe { libc::open(path.as_ptr(), flags | libc::O_CLOEXEC, mode) };
+            return Ok(self.complete_now(if ret < 0 { -last_errno() } else { ret }));
+        }
+
+        let token = self.next_token();
+
+        let open_e = opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), path.as_ptr())
+            .flags(flags | libc::O_CLOEXEC)
+            .mode(mode)
+            .build()
+            .user_data(token);
+
+        unsafe {
+            self.submission()?
+                .push(&open_e)
+                .map_err(|_| std::io::Error::other("SQ full"))?;
+        }
+
+        self.pending_polls.insert(
+            token,
+            PendingPoll {
+                fd: -1,
+                readable: false,
+                writable: false,
+                op: None,
+                completion: true,
+            },
+        );
+
+        let _ = self.submit_queued();
+        Ok(IoToken(token))
+    }
+
+    /// Submit an async fsync; without the opcode it runs right away
+    #[inline]
+    pub fn submit_fsync(&mut self, fd: RawFd) -> crate::utils::VeloxResult<IoToken> {
+        if !self.capabilities.has_fsync {
+            let ret = unsafe { libc::fsync(fd) };
+            return Ok(self.complete_now(if ret < 0 { -last_errno() } else { 0 }));
+        }
+
+        let token = self.next_token();
+
+        let fsync_e = opcode::Fsync::new(types::Fd(fd)).build().user_data(token);
+
+        unsafe {
+            self.submission()?
+                .push(&fsync_e)
+                .map_err(|_| std::io::Error::other("SQ full"))?;
+        }
+
+        self.pending_polls.insert(
+            token,
+            PendingPoll {
+                fd,
+                readable: false,
+                writable: false,
+                op: None,
+                completion: true,
+            },
+        );
+
+        let _ = self.submit_queued();
         Ok(IoToken(token))
     }
 
@@ -663,6 +1607,15 @@ impl LoopPoller {
         offset: u64,
         count: usize,
     ) -> crate::utils::VeloxResult<IoToken> {
+        if !self.capabilities.has_splice {
+            let op = EmulatedOp::Splice {
+                in_fd,
+                offset,
+                count,
+            };
+            return self.submit_emulated(out_fd, false, true, op);
+        }
+
         let token = self.next_token();
 
         let splice_e = opcode::Splice::new(
@@ -676,8 +1629,7 @@ impl LoopPoller {
         .user_data(token);
 
         unsafe {
-            self.ring
-                .submission()
+            self.submission()?
                 .push(&splice_e)
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "SQ full"))?;
         }
@@ -688,35 +1640,490 @@ impl LoopPoller {
                 fd: out_fd,
                 readable: false,
                 writable: true,
+                op: None,
+                completion: true,
             },
         );
 
-        let _ = self.ring.submit();
+        let _ = self.submit_queued();
         Ok(IoToken(token))
     }
 
     /// Cancel an in-flight io-uring operation
     #[inline]
     pub fn cancel_operation(&mut self, target_token: IoToken) -> crate::utils::VeloxResult<()> {
-        let cancel_e = opcode::AsyncCancel::new(target_token.0)
-            .build()
-            .user_data(0); // Don't track cancellation completion
+        self.ready_ops.remove(&target_token.0);
+        // Without AsyncCancel only polls, which includes every emulated
+        // operation, can be withdrawn
+        let cancel_e = if self.capabilities.has_async_cancel {
+            opcode::AsyncCancel::new(target_token.0).build()
+        } else {
+            opcode::PollRemove::new(target_token.0).build()
+        }
+        .user_data(0); // Don't track cancellation completion
 
         unsafe {
-            let _ = self.ring.submission().push(&cancel_e);
+            if let Ok(mut sq) = self.submission() {
+                let _ = sq.push(&cancel_e);
+            }
         }
 
         self.pending_polls.remove(&target_token.0);
-        let _ = self.ring.submit();
+        let _ = self.submit_queued();
         Ok(())
     }
+
+    /// Ask the kernel to abort an in-flight operation. Unlike
+    /// `cancel_operation` the operation stays tracked: its CQE (-ECANCELED,
+    /// or the result if it finished first) is still reported, so buffers it
+    /// uses must stay alive until then.
+    pub fn request_cancel(&mut self, target_token: IoToken) -> crate::utils::VeloxResult<()> {
+        if !self.capabilities.has_async_cancel {
+            return Ok(());
+        }
+        let cancel_e = opcode::AsyncCancel::new(target_token.0)
+            .build()
+            .user_data(0);
+        unsafe {
+            self.submission()?
+                .push(&cancel_e)
+                .map_err(|_| std::io::Error::other("SQ full"))?;
+        }
+        let _ = self.submit_queued();
+        Ok(())
+    }
+
+    /// Whether operations have finished since the last `take_completions`
+    #[inline]
+    pub fn has_completions(&self) -> bool {
+        !self.ready_ops.is_empty()
+    }
+
+    /// Results of finished completion operations, by token (bytes
+    /// transferred, a new fd, or -errno)
+    pub fn take_completions(&mut self) -> Vec<(IoToken, i32)> {
+        self.ready_ops
+            .drain()
+            .map(|(token, result)| (IoToken(token), result))
+            .collect()
+    }
+
+    /// Block until the operation behind `token` completes and return its raw
+    /// result (bytes transferred, or -errno). Completions for other tokens are
+    /// discarded, so this is only for drivers with nothing else in flight;
+    /// other emulated operations still run and keep their results.
+    #[cfg(any(test, feature = "bench"))]
+    pub fn wait_completion(&mut self, token: IoToken) -> crate::utils::VeloxResult<i32> {
+        self.flush_submissions()?;
+        loop {
+            if let Some(result) = self.ready_ops.remove(&token.0) {
+                return Ok(result);
+            }
+            self.ring.submit_and_wait(1)?;
+            let completions: Vec<(u64, i32)> = self
+                .ring
+                .completion()
+                .map(|cqe| (cqe.user_data(), cqe.result()))
+                .collect();
+            for (user_data, result) in completions {
+                if self
+                    .pending_polls
+                    .get(&user_data)
+                    .is_some_and(|p| p.op.is_some())
+                {
+                    if let Some(pending) = self.pending_polls.remove(&user_data) {
+                        self.finish_emulated(user_data, pending, result);
+                    }
+                } else if user_data == token.0 {
+                    self.pending_polls.remove(&user_data);
+                    self.ready_ops.insert(user_data, result);
+                }
+            }
+        }
+    }
 }
 
 #[cfg(target_os = "linux")]
 impl Drop for LoopPoller {
     fn drop(&mut self) {
+        if self.owner.is_forked() {
+            // The fork handler closed the fds; numbers may have been reused since
+            return;
+        }
+        // A loop that was never closed still tears down in order
+        self.shutdown();
+        fork::untrack_fd(self.ring.as_raw_fd());
         unsafe {
-            libc::close(self.eventfd);
+            ManuallyDrop::drop(&mut self.ring);
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::os::unix::net::UnixStream;
+
+    fn pair() -> (UnixStream, UnixStream) {
+        let (a, b) = UnixStream::pair().unwrap();
+        a.set_nonblocking(true).unwrap();
+        b.set_nonblocking(true).unwrap();
+        (a, b)
+    }
+
+    fn readable_fds(poller: &mut LoopPoller, timeout: Duration) -> Vec<RawFd> {
+        poller
+            .poll_native(Some(timeout))
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.readable)
+            .map(|e| e.fd)
+            .collect()
+    }
+
+    #[test]
+    fn registered_fd_reports_readable_once() {
+        let mut poller = LoopPoller::new().unwrap();
+        let (mut a, mut b) = pair();
+        poller.register(b.as_raw_fd(), PollerEvent::readable()).unwrap();
+        a.write_all(b"x").unwrap();
+        assert_eq!(readable_fds(&mut poller, Duration::from_secs(1)), vec![b.as_raw_fd()]);
+
+        // Oneshot: nothing more until re-armed
+        a.write_all(b"y").unwrap();
+        assert!(readable_fds(&mut poller, Duration::from_millis(10)).is_empty());
+        poller.rearm_oneshot(b.as_raw_fd(), PollerEvent::readable()).unwrap();
+        assert_eq!(readable_fds(&mut poller, Duration::from_secs(1)), vec![b.as_raw_fd()]);
+        let mut buf = [0u8; 2];
+        assert_eq!(b.read(&mut buf).unwrap(), 2);
+    }
+
+    #[test]
+    fn deleted_and_modified_fds_stop_reporting() {
+        let mut poller = LoopPoller::new().unwrap();
+        let (mut a, b) = pair();
+        poller.register(b.as_raw_fd(), PollerEvent::readable()).unwrap();
+        poller.delete(b.as_raw_fd()).unwrap();
+        a.write_all(b"x").unwrap();
+        assert!(readable_fds(&mut poller, Duration::from_millis(10)).is_empty());
+
+        // Writable-only interest on a readable socket
+        poller.register(b.as_raw_fd(), PollerEvent::readable()).unwrap();
+        poller.modify(b.as_raw_fd(), PollerEvent::writable()).unwrap();
+        let events = poller.poll_native(Some(Duration::from_secs(1))).unwrap();
+        assert!(events.iter().any(|e| e.fd == b.as_raw_fd() && e.writable && !e.readable));
+    }
+
+    /// Fd polls out on `fd`, not counting operations running on it
+    fn live_polls(poller: &LoopPoller, fd: RawFd) -> usize {
+        poller
+            .pending_polls
+            .values()
+            .filter(|p| p.fd == fd && p.op.is_none() && !p.completion)
+            .count()
+    }
+
+    #[test]
+    fn remove_then_add_in_one_dispatch_keeps_one_poll() {
+        let mut poller = LoopPoller::new().unwrap();
+        let (_a, b) = pair();
+        let fd = b.as_raw_fd();
+        // A connect's writer poll fires
+        poller.register(fd, PollerEvent::writable()).unwrap();
+        let events = poller.poll_native(Some(Duration::from_secs(1))).unwrap();
+        assert!(events.iter().any(|e| e.fd == fd && e.writable));
+        assert!(!poller.is_armed(fd));
+
+        // Its callback removes itself, then adds a reader and a writer
+        poller.delete(fd).unwrap();
+        poller.register(fd, PollerEvent::readable()).unwrap();
+        poller.modify(fd, PollerEvent::new(true, true)).unwrap();
+        let counts = poller.fd_op_counts();
+        assert_eq!(live_polls(&poller, fd), 1);
+
+        // The re-arm after the dispatch finds that poll and keeps it
+        assert!(poller.is_armed_for(fd, PollerEvent::new(true, true)));
+        assert!(!poller.is_armed_for(fd, PollerEvent::writable()));
+        assert_eq!(poller.fd_op_counts(), counts);
+        let events = poller.poll_native(Some(Duration::from_secs(1))).unwrap();
+        let ours: Vec<_> = events.iter().filter(|e| e.fd == fd).collect();
+        assert_eq!(ours.len(), 1);
+        assert!(ours[0].writable);
+        assert_eq!(live_polls(&poller, fd), 0);
+    }
+
+    #[test]
+    fn replaced_poll_completing_keeps_its_replacement() {
+        let mut poller = LoopPoller::new().unwrap();
+        let (_a, b) = pair();
+        let fd = b.as_raw_fd();
+        // Ready at once, so its completion is posted as soon as it's submitted
+        poller.register(fd, PollerEvent::writable()).unwrap();
+        poller.flush_submissions().unwrap();
+        let replaced = poller.fd_tokens[&fd];
+        poller.modify(fd, PollerEvent::readable()).unwrap();
+        assert_ne!(poller.fd_tokens[&fd], replaced);
+
+        // The replaced poll's completion is dropped; the new one stays out
+        let events = poller.poll_native(Some(Duration::from_millis(10))).unwrap();
+        assert!(events.is_empty());
+        assert!(poller.is_armed_for(fd, PollerEvent::readable()));
+        assert_eq!(live_polls(&poller, fd), 1);
+    }
+
+    #[test]
+    fn waker_interrupts_poll() {
+        let mut poller = LoopPoller::new().unwrap();
+        poller.waker().unwrap().notify().unwrap();
+        let start = std::time::Instant::now();
+        assert!(poller.poll_native(Some(Duration::from_secs(5))).unwrap().is_empty());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn shutdown_withdraws_everything_in_flight() {
+        let mut poller = LoopPoller::new().unwrap();
+        let (_a, b) = pair();
+        poller
+            .register(b.as_raw_fd(), PollerEvent::readable())
+            .unwrap();
+        poller.wakeup_fd().unwrap();
+        let waker = poller.waker().unwrap();
+        poller.shutdown();
+        assert!(poller.pending_polls.is_empty());
+        assert!(poller.wakeup_pipe.is_none());
+        // The cancelled polls' completions were reaped, not left for later
+        assert!(poller.ring.completion().is_empty());
+        assert!(
+            poller
+                .register(b.as_raw_fd(), PollerEvent::readable())
+                .is_err()
+        );
+        assert!(poller.poll_native(Some(Duration::ZERO)).is_err());
+        poller.shutdown();
+
+        // The waker keeps its own fd; after shutdown it doesn't write at all
+        waker.shutdown();
+        let failures = notify_failures();
+        waker.notify().unwrap();
+        assert_eq!(notify_failures(), failures);
+    }
+
+    #[test]
+    fn send_recv_round_trip() {
+        let mut poller = LoopPoller::new().unwrap();
+        let (a, b) = pair();
+        let token = poller.submit_send(a.as_raw_fd(), b"ping", 0).unwrap();
+        assert_eq!(poller.wait_completion(token).unwrap(), 4);
+
+        let mut buf = [0u8; 16];
+        let token = poller.submit_recv(b.as_raw_fd(), &mut buf, 0).unwrap();
+        assert_eq!(poller.wait_completion(token).unwrap(), 4);
+        assert_eq!(&buf[..4], b"ping");
+    }
+
+    #[test]
+    fn file_completions_come_back_from_poll() {
+        let mut poller = LoopPoller::new().unwrap();
+        let path = std::env::temp_dir().join(format!("veloxloop-file-{}", std::process::id()));
+        std::fs::write(&path, b"0123456789").unwrap();
+        let cpath = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+
+        let open = poller.submit_openat(&cpath, libc::O_RDWR, 0).unwrap();
+        let fd = poller.wait_completion(open).unwrap();
+        assert!(fd >= 0);
+
+        // Reported as results, not as readiness events
+        let mut buf = [0u8; 4];
+        let read = poller.submit_read(fd, &mut buf, Some(6)).unwrap();
+        let fsync = poller.submit_fsync(fd).unwrap();
+        let mut done = Vec::new();
+        while done.len() < 2 {
+            assert!(
+                poller
+                    .poll_native(Some(Duration::from_secs(1)))
+                    .unwrap()
+                    .is_empty()
+            );
+            done.extend(poller.take_completions());
+        }
+        done.sort_by_key(|(token, _)| token.0);
+        assert_eq!(done, vec![(read, 4), (fsync, 0)]);
+        assert_eq!(&buf, b"6789");
+        assert!(!poller.has_completions());
+
+        let close = poller.submit_close(fd).unwrap();
+        assert_eq!(poller.wait_completion(close).unwrap(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// A poller told the kernel has none of the optional opcodes (5.1)
+    fn baseline_poller() -> LoopPoller {
+        LoopPoller::with_capabilities(BackendCapabilities::default()).unwrap()
+    }
+
+    #[test]
+    fn probe_reports_capabilities() {
+        let caps = LoopPoller::new().unwrap().capabilities();
+        // Anything new enough to probe has Timeout
+        assert!(caps.has_timeout);
+        assert_eq!(caps.flags().len(), 13);
+        assert!(
+            caps.flags()
+                .iter()
+                .all(|(name, _)| name.starts_with("has_"))
+        );
+    }
+
+    #[test]
+    fn standalone_probe_matches_poller() {
+        let caps = probe_io_uring().unwrap();
+        assert_eq!(caps, LoopPoller::new().unwrap().capabilities());
+    }
+
+    #[test]
+    fn setup_failures_are_classified() {
+        use UringUnavailable::*;
+        assert_eq!(UringUnavailable::classify(libc::ENOSYS, None), NotInKernel);
+        // A seccomp ENOSYS looks the same whatever the sysctl says
+        assert_eq!(
+            UringUnavailable::classify(libc::ENOSYS, Some(2)),
+            NotInKernel
+        );
+        assert_eq!(
+            UringUnavailable::classify(libc::EPERM, Some(2)),
+            DisabledBySysctl
+        );
+        assert_eq!(
+            UringUnavailable::classify(libc::EPERM, Some(1)),
+            RestrictedBySysctl
+        );
+        assert_eq!(UringUnavailable::classify(libc::EPERM, Some(0)), Blocked);
+        assert_eq!(UringUnavailable::classify(libc::EPERM, None), Blocked);
+        assert_eq!(UringUnavailable::classify(libc::ENOMEM, Some(0)), Memlock);
+        assert_eq!(
+            UringUnavailable::classify(libc::EINVAL, None),
+            Other(libc::EINVAL)
+        );
+
+        assert!(
+            DisabledBySysctl
+                .reason()
+                .contains("kernel.io_uring_disabled=2")
+        );
+        assert!(
+            DisabledBySysctl
+                .remediation()
+                .contains("kernel.io_uring_disabled=0")
+        );
+        assert!(RestrictedBySysctl.remediation().contains("io_uring_group"));
+        assert!(Blocked.remediation().contains("seccomp"));
+        assert!(Memlock.remediation().contains("RLIMIT_MEMLOCK"));
+        assert!(Other(libc::EINVAL).reason().contains("Invalid argument"));
+    }
+
+    #[test]
+    fn emulated_timeout_and_wakeup() {
+        let mut poller = baseline_poller();
+        let start = std::time::Instant::now();
+        assert!(
+            poller
+                .poll_native(Some(Duration::from_millis(50)))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(start.elapsed() >= Duration::from_millis(40));
+
+        poller.waker().unwrap().notify().unwrap();
+        let start = std::time::Instant::now();
+        assert!(
+            poller
+                .poll_native(Some(Duration::from_secs(5)))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn emulated_send_recv_waits_for_data() {
+        let mut poller = baseline_poller();
+        let (a, b) = pair();
+        // Queued before any data exists, so the recv has to wait for readiness
+        let mut buf = [0u8; 16];
+        let recv = poller.submit_recv(b.as_raw_fd(), &mut buf, 0).unwrap();
+        let send = poller.submit_send(a.as_raw_fd(), b"ping", 0).unwrap();
+        assert_eq!(poller.wait_completion(send).unwrap(), 4);
+        assert_eq!(poller.wait_completion(recv).unwrap(), 4);
+        assert_eq!(&buf[..4], b"ping");
+    }
+
+    #[test]
+    fn emulated_connect_accept_close() {
+        let mut poller = baseline_poller();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accept = poller.submit_accept(listener.as_raw_fd()).unwrap();
+
+        let client =
+            socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+        client.set_nonblocking(true).unwrap();
+        let connect = poller.submit_connect(client.as_raw_fd(), addr).unwrap();
+        assert_eq!(poller.wait_completion(connect).unwrap(), 0);
+        let accepted = poller.wait_completion(accept).unwrap();
+        assert!(accepted >= 0);
+        let fd_flags = unsafe { libc::fcntl(accepted, libc::F_GETFD) };
+        assert_ne!(fd_flags & libc::FD_CLOEXEC, 0);
+        let status_flags = unsafe { libc::fcntl(accepted, libc::F_GETFL) };
+        assert_ne!(status_flags & libc::O_NONBLOCK, 0);
+
+        let close = poller.submit_close(accepted).unwrap();
+        assert_eq!(poller.wait_completion(close).unwrap(), 0);
+        let close = poller.submit_close(accepted).unwrap();
+        assert_eq!(poller.wait_completion(close).unwrap(), -libc::EBADF);
+    }
+
+    #[test]
+    fn emulated_connect_refused() {
+        let mut poller = baseline_poller();
+        let addr = {
+            let probe = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap()
+        };
+        let client =
+            socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+        client.set_nonblocking(true).unwrap();
+        let connect = poller.submit_connect(client.as_raw_fd(), addr).unwrap();
+        assert_eq!(
+            poller.wait_completion(connect).unwrap(),
+            -libc::ECONNREFUSED
+        );
+    }
+
+    #[test]
+    fn emulated_splice_from_file() {
+        let mut poller = baseline_poller();
+        let path = std::env::temp_dir().join(format!("veloxloop-splice-{}", std::process::id()));
+        std::fs::write(&path, b"0123456789").unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let mut fds = [0 as RawFd; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let splice = poller
+            .submit_sendfile(fds[1], file.as_raw_fd(), 4, 3)
+            .unwrap();
+        assert_eq!(poller.wait_completion(splice).unwrap(), 3);
+        let mut buf = [0u8; 8];
+        let n = unsafe { libc::read(fds[0], buf.as_mut_ptr() as *mut _, buf.len()) };
+        assert_eq!(&buf[..n as usize], b"456");
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
         }
+        std::fs::remove_file(path).unwrap();
     }
 }
@@ -0,0 +1,147 @@
+//! What the host offers the event loop, probed without creating one
+//!
+//! `self_check()` is the full report for checking a machine before deploying
+//! to it; `ensure_io_uring()` is the part the first `VeloxLoop` runs so an
+//! unusable io_uring fails with the reason and what to do about it.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ffi::CStr;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::poller::{self, UringUnavailable};
+
+/// Set once a ring could be set up; failures are probed again next time
+static IO_URING_CHECKED: AtomicBool = AtomicBool::new(false);
+
+/// Probe the kernel and return a report: `kernel` and `kernel_version`,
+/// `io_uring` with `io_uring_reason`/`io_uring_remediation` when unavailable,
+/// `io_uring_disabled` (the sysctl, None before 6.6), `opcodes`, `sqpoll`
+/// with `sqpoll_reason`, `eventfd`, `epoll` and `nofile` as (soft, hard)
+/// with -1 for unlimited like `resource.getrlimit()`
+#[pyfunction]
+pub fn self_check(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let report = PyDict::new(py);
+    let release = kernel_release();
+    report.set_item("kernel", &release)?;
+    report.set_item(
+        "kernel_version",
+        release.as_deref().and_then(kernel_version),
+    )?;
+
+    let disabled = poller::io_uring_disabled_sysctl();
+    report.set_item("io_uring_disabled", disabled)?;
+    let opcodes = PyDict::new(py);
+    match poller::probe_io_uring() {
+        Ok(capabilities) => {
+            report.set_item("io_uring", true)?;
+            report.set_item("io_uring_reason", py.None())?;
+            report.set_item("io_uring_remediation", py.None())?;
+            for (name, supported) in capabilities.flags() {
+                opcodes.set_item(name, supported)?;
+            }
+        }
+        Err(e) => {
+            let unavailable = classify(&e, disabled);
+            report.set_item("io_uring", false)?;
+            report.set_item("io_uring_reason", unavailable.reason())?;
+            report.set_item("io_uring_remediation", unavailable.remediation())?;
+        }
+    }
+    report.set_item("opcodes", opcodes)?;
+
+    let sqpoll = poller::probe_sqpoll();
+    report.set_item("sqpoll", sqpoll.is_ok())?;
+    report.set_item("sqpoll_reason", sqpoll.err().map(|e| e.to_string()))?;
+
+    report.set_item(
+        "eventfd",
+        closes(unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) }),
+    )?;
+    report.set_item(
+        "epoll",
+        closes(unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) }),
+    )?;
+    report.set_item("nofile", nofile_limits())?;
+    Ok(report)
+}
+
+/// Whether io_uring can be set up here; `self_check()` says why not
+#[pyfunction]
+pub fn is_io_uring_available() -> bool {
+    poller::probe_io_uring().is_ok()
+}
+
+/// OSError with the reason and remediation when io_uring can't be set up.
+/// Probes until the first success, after which loops skip it.
+pub(crate) fn ensure_io_uring() -> PyResult<()> {
+    if IO_URING_CHECKED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    if let Err(e) = poller::probe_io_uring() {
+        let unavailable = classify(&e, poller::io_uring_disabled_sysctl());
+        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>((
+            e.raw_os_error().unwrap_or(0),
+            format!(
+                "VeloxLoop needs io_uring: {}; {}",
+                unavailable.reason(),
+                unavailable.remediation()
+            ),
+        )));
+    }
+    IO_URING_CHECKED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+fn classify(err: &io::Error, disabled: Option<u8>) -> UringUnavailable {
+    UringUnavailable::classify(err.raw_os_error().unwrap_or(0), disabled)
+}
+
+/// Whether `fd` was created, closing it
+fn closes(fd: libc::c_int) -> bool {
+    if fd < 0 {
+        return false;
+    }
+    unsafe { libc::close(fd) };
+    true
+}
+
+/// `uname -r`
+fn kernel_release() -> Option<String> {
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return None;
+    }
+    let release = unsafe { CStr::from_ptr(uts.release.as_ptr()) };
+    Some(release.to_string_lossy().into_owned())
+}
+
+/// (major, minor, patch) from a release such as "5.15.0-91-generic"
+fn kernel_version(release: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = release
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|part| part.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    Some((major, minor, parts.next().flatten().unwrap_or(0)))
+}
+
+/// RLIMIT_NOFILE as (soft, hard), -1 standing for unlimited
+fn nofile_limits() -> Option<(i64, i64)> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+    let value = |v: libc::rlim_t| {
+        if v == libc::RLIM_INFINITY {
+            -1
+        } else {
+            v as i64
+        }
+    };
+    Some((value(limit.rlim_cur), value(limit.rlim_max)))
+}
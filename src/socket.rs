@@ -15,6 +15,8 @@ pub struct InnerSocketOptions {
     pub so_reuseport: Option<bool>,
     pub so_rcvbuf: Option<usize>,
     pub so_sndbuf: Option<usize>,
+    pub ip_transparent: Option<bool>,
+    pub ip_freebind: Option<bool>,
 }
 
 impl InnerSocketOptions {
@@ -28,30 +30,44 @@ impl InnerSocketOptions {
         if let Some(nodelay) = self.tcp_nodelay {
             socket
                 .set_tcp_nodelay(nodelay)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
+                .map_err(crate::utils::os_error_to_pyerr)?;
         }
 
         if let Some(reuse_addr) = self.so_reuseaddr {
             socket
                 .set_reuse_address(reuse_addr)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
+                .map_err(crate::utils::os_error_to_pyerr)?;
         }
 
         if let Some(rcvbuf) = self.so_rcvbuf {
             socket
                 .set_recv_buffer_size(rcvbuf)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
+                .map_err(crate::utils::os_error_to_pyerr)?;
         }
 
         if let Some(sndbuf) = self.so_sndbuf {
             socket
                 .set_send_buffer_size(sndbuf)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyOSError, _>(e.to_string()))?;
+                .map_err(crate::utils::os_error_to_pyerr)?;
         }
 
         self.apply_keepalive(socket)?;
         self.apply_reuseport(socket)?;
 
+        let ipv6 = socket.local_addr()?.is_ipv6();
+        self.apply_proxy(std::os::unix::io::AsRawFd::as_raw_fd(socket), ipv6)?;
+
+        Ok(())
+    }
+
+    /// Apply IP_TRANSPARENT and IP_FREEBIND (or their IPv6 variants)
+    fn apply_proxy(&self, fd: std::os::fd::RawFd, ipv6: bool) -> PyResult<()> {
+        if let Some(transparent) = self.ip_transparent {
+            ProxyOption::Transparent.set(fd, ipv6, transparent)?;
+        }
+        if let Some(freebind) = self.ip_freebind {
+            ProxyOption::FreeBind.set(fd, ipv6, freebind)?;
+        }
         Ok(())
     }
 
@@ -74,10 +90,7 @@ impl InnerSocketOptions {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set SO_KEEPALIVE: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set SO_KEEPALIVE"));
                 }
             }
         }
@@ -95,10 +108,7 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set TCP_KEEPIDLE: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set TCP_KEEPIDLE"));
                     }
                 }
             }
@@ -114,10 +124,7 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set TCP_KEEPINTVL: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set TCP_KEEPINTVL"));
                     }
                 }
             }
@@ -133,10 +140,7 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set TCP_KEEPCNT: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set TCP_KEEPCNT"));
                     }
                 }
             }
@@ -161,10 +165,7 @@ impl InnerSocketOptions {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set SO_REUSEPORT: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set SO_REUSEPORT"));
                 }
             }
         }
@@ -191,10 +192,7 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set TCP_NODELAY: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set TCP_NODELAY"));
                     }
                 }
             }
@@ -210,10 +208,7 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set SO_KEEPALIVE: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set SO_KEEPALIVE"));
                     }
                 }
             }
@@ -231,10 +226,7 @@ impl InnerSocketOptions {
                             std::mem::size_of_val(&optval) as libc::socklen_t,
                         );
                         if ret != 0 {
-                            return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                                "Failed to set TCP_KEEPIDLE: {}",
-                                std::io::Error::last_os_error()
-                            )));
+                            return Err(crate::utils::last_os_error("Failed to set TCP_KEEPIDLE"));
                         }
                     }
                 }
@@ -250,10 +242,7 @@ impl InnerSocketOptions {
                             std::mem::size_of_val(&optval) as libc::socklen_t,
                         );
                         if ret != 0 {
-                            return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                                "Failed to set TCP_KEEPINTVL: {}",
-                                std::io::Error::last_os_error()
-                            )));
+                            return Err(crate::utils::last_os_error("Failed to set TCP_KEEPINTVL"));
                         }
                     }
                 }
@@ -269,10 +258,7 @@ impl InnerSocketOptions {
                             std::mem::size_of_val(&optval) as libc::socklen_t,
                         );
                         if ret != 0 {
-                            return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                                "Failed to set TCP_KEEPCNT: {}",
-                                std::io::Error::last_os_error()
-                            )));
+                            return Err(crate::utils::last_os_error("Failed to set TCP_KEEPCNT"));
                         }
                     }
                 }
@@ -289,10 +275,7 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 && cfg!(not(target_os = "solaris")) {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set SO_REUSEPORT: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set SO_REUSEPORT"));
                     }
                 }
             }
@@ -308,10 +291,7 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set SO_REUSEADDR: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set SO_REUSEADDR"));
                     }
                 }
             }
@@ -327,10 +307,7 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set SO_RCVBUF: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set SO_RCVBUF"));
                     }
                 }
             }
@@ -346,13 +323,12 @@ impl InnerSocketOptions {
                         std::mem::size_of_val(&optval) as libc::socklen_t,
                     );
                     if ret != 0 {
-                        return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                            "Failed to set SO_SNDBUF: {}",
-                            std::io::Error::last_os_error()
-                        )));
+                        return Err(crate::utils::last_os_error("Failed to set SO_SNDBUF"));
                     }
                 }
             }
+
+            self.apply_proxy(fd, stream.local_addr()?.is_ipv6())?;
         }
 
         #[cfg(not(unix))]
@@ -488,6 +464,30 @@ impl SocketOptions {
         self.inner.so_sndbuf
     }
 
+    /// Set IP_TRANSPARENT (IPV6_TRANSPARENT on IPv6 sockets)
+    /// Lets a transparent proxy bind to and accept for non-local addresses; needs CAP_NET_ADMIN
+    fn set_ip_transparent(&mut self, enabled: bool) -> PyResult<()> {
+        self.inner.ip_transparent = Some(enabled);
+        Ok(())
+    }
+
+    /// Get IP_TRANSPARENT option
+    fn get_ip_transparent(&self) -> Option<bool> {
+        self.inner.ip_transparent
+    }
+
+    /// Set IP_FREEBIND (IPV6_FREEBIND on IPv6 sockets)
+    /// Allows binding to an address that is not configured on any interface
+    fn set_ip_freebind(&mut self, enabled: bool) -> PyResult<()> {
+        self.inner.ip_freebind = Some(enabled);
+        Ok(())
+    }
+
+    /// Get IP_FREEBIND option
+    fn get_ip_freebind(&self) -> Option<bool> {
+        self.inner.ip_freebind
+    }
+
     /// Reset all options to None
     fn reset(&mut self) -> PyResult<()> {
         self.inner = InnerSocketOptions::new();
@@ -496,7 +496,7 @@ impl SocketOptions {
 
     fn __repr__(&self) -> String {
         format!(
-            "SocketOptions(tcp_nodelay={:?}, keepalive={:?}, keepalive_time={:?}, keepalive_interval={:?}, keepalive_count={:?}, reuse_address={:?}, reuse_port={:?}, rcvbuf={:?}, sndbuf={:?})",
+            "SocketOptions(tcp_nodelay={:?}, keepalive={:?}, keepalive_time={:?}, keepalive_interval={:?}, keepalive_count={:?}, reuse_address={:?}, reuse_port={:?}, rcvbuf={:?}, sndbuf={:?}, ip_transparent={:?}, ip_freebind={:?})",
             self.inner.tcp_nodelay,
             self.inner.keepalive,
             self.inner.keepalive_time,
@@ -506,6 +506,552 @@ impl SocketOptions {
             self.inner.so_reuseport,
             self.inner.so_rcvbuf,
             self.inner.so_sndbuf,
+            self.inner.ip_transparent,
+            self.inner.ip_freebind,
+        )
+    }
+}
+
+/// Connection metrics read with `getsockopt(IPPROTO_TCP, TCP_INFO)`.
+/// Times are in microseconds, as the kernel reports them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TcpInfo {
+    pub state: u8,
+    pub rtt: u32,
+    pub rttvar: u32,
+    pub snd_cwnd: u32,
+    pub retrans: u32,
+    pub total_retrans: u32,
+}
+
+impl TcpInfo {
+    /// Read TCP_INFO for `fd`; None if the call fails or the platform lacks it.
+    /// `struct tcp_info` grows with kernel versions, so the kernel fills a
+    /// generous buffer and only fields inside the returned length are decoded.
+    #[cfg(target_os = "linux")]
+    pub fn read(fd: std::os::fd::RawFd) -> Option<Self> {
+        use std::mem::offset_of;
+
+        let mut buf = [0u64; 64];
+        let mut len = std::mem::size_of_val(&buf) as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                fd,
+                libc::IPPROTO_TCP,
+                libc::TCP_INFO,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret != 0 {
+            return None;
+        }
+        let bytes = unsafe {
+            std::slice::from_raw_parts(buf.as_ptr() as *const u8, len as usize)
+        };
+        let u32_at = |offset: usize| {
+            bytes
+                .get(offset..offset + 4)
+                .map_or(0, |b| u32::from_ne_bytes(b.try_into().unwrap()))
+        };
+
+        Some(Self {
+            state: *bytes.get(offset_of!(libc::tcp_info, tcpi_state))?,
+            rtt: u32_at(offset_of!(libc::tcp_info, tcpi_rtt)),
+            rttvar: u32_at(offset_of!(libc::tcp_info, tcpi_rttvar)),
+            snd_cwnd: u32_at(offset_of!(libc::tcp_info, tcpi_snd_cwnd)),
+            retrans: u32_at(offset_of!(libc::tcp_info, tcpi_retrans)),
+            total_retrans: u32_at(offset_of!(libc::tcp_info, tcpi_total_retrans)),
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn read(_fd: std::os::fd::RawFd) -> Option<Self> {
+        None
+    }
+
+    /// The `get_extra_info("tcp_info")` dict
+    pub fn to_dict(self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let dict = pyo3::types::PyDict::new(py);
+        dict.set_item("state", self.state)?;
+        dict.set_item("rtt", self.rtt)?;
+        dict.set_item("rttvar", self.rttvar)?;
+        dict.set_item("snd_cwnd", self.snd_cwnd)?;
+        dict.set_item("retrans", self.retrans)?;
+        dict.set_item("total_retrans", self.total_retrans)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Smoothed RTT in seconds
+    pub fn rtt_secs(self) -> f64 {
+        self.rtt as f64 / 1_000_000.0
+    }
+}
+
+/// Bytes still in `fd`'s kernel send queue, i.e. not yet acknowledged by the
+/// peer (`ioctl(SIOCOUTQ)`, named TIOCOUTQ in libc); None if unavailable
+#[cfg(target_os = "linux")]
+pub fn kernel_write_queue(fd: std::os::fd::RawFd) -> Option<usize> {
+    let mut queued: libc::c_int = 0;
+    let ret = unsafe { libc::ioctl(fd, libc::TIOCOUTQ, &mut queued) };
+    (ret == 0).then_some(queued.max(0) as usize)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn kernel_write_queue(_fd: std::os::fd::RawFd) -> Option<usize> {
+    None
+}
+
+/// TCP options that only some kernels and platforms have. Setting one that is
+/// missing is a no-op rather than an error, so servers can ask for them
+/// unconditionally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpTuning {
+    /// TCP_DEFER_ACCEPT: wake the listener only once data arrives (seconds)
+    DeferAccept,
+    /// TCP_FASTOPEN: queue length for SYNs carrying data on a listener
+    FastOpen,
+    /// TCP_FASTOPEN_CONNECT: send the first write in the SYN on a client
+    FastOpenConnect,
+}
+
+impl TcpTuning {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DeferAccept => "TCP_DEFER_ACCEPT",
+            Self::FastOpen => "TCP_FASTOPEN",
+            Self::FastOpenConnect => "TCP_FASTOPEN_CONNECT",
+        }
+    }
+
+    /// Set the option on `fd`; Ok(false) when the kernel or platform lacks it
+    #[cfg(target_os = "linux")]
+    pub fn set(self, fd: std::os::fd::RawFd, value: u32) -> PyResult<bool> {
+        let optname = match self {
+            Self::DeferAccept => libc::TCP_DEFER_ACCEPT,
+            Self::FastOpen => libc::TCP_FASTOPEN,
+            Self::FastOpenConnect => libc::TCP_FASTOPEN_CONNECT,
+        };
+        let optval = value.min(libc::c_int::MAX as u32) as libc::c_int;
+        let ret = unsafe {
+            libc::setsockopt(
+                fd,
+                libc::IPPROTO_TCP,
+                optname,
+                &optval as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&optval) as libc::socklen_t,
+            )
+        };
+        if ret == 0 {
+            return Ok(true);
+        }
+        let err = std::io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::ENOPROTOOPT) | Some(libc::EOPNOTSUPP) => Ok(false),
+            _ => Err(crate::utils::os_error_with_message(
+                &err,
+                format!("Failed to set {}: {}", self.name(), err),
+            )),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn set(self, _fd: std::os::fd::RawFd, _value: u32) -> PyResult<bool> {
+        Ok(false)
+    }
+
+    /// `set`, warning about a missing option when the loop is in debug mode
+    pub fn set_or_warn(
+        self,
+        py: Python<'_>,
+        fd: std::os::fd::RawFd,
+        value: u32,
+        debug: bool,
+    ) -> PyResult<()> {
+        if !self.set(fd, value)? && debug {
+            let message = format!("{} is not supported here; ignoring it", self.name());
+            PyErr::warn(
+                py,
+                py.get_type::<pyo3::exceptions::PyRuntimeWarning>().as_any(),
+                &std::ffi::CString::new(message)?,
+                1,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// IP-level options for transparent proxies. Unlike `TcpTuning` these are
+/// never skipped silently: a proxy that cannot bind non-local addresses is
+/// broken, so failures are raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyOption {
+    /// IP_TRANSPARENT: bind to and accept for non-local addresses (TPROXY);
+    /// needs CAP_NET_ADMIN (or CAP_NET_RAW)
+    Transparent,
+    /// IP_FREEBIND: bind to an address that is not (yet) configured
+    FreeBind,
+}
+
+impl ProxyOption {
+    pub fn name(self, ipv6: bool) -> &'static str {
+        match (self, ipv6) {
+            (Self::Transparent, false) => "IP_TRANSPARENT",
+            (Self::Transparent, true) => "IPV6_TRANSPARENT",
+            (Self::FreeBind, false) => "IP_FREEBIND",
+            (Self::FreeBind, true) => "IPV6_FREEBIND",
+        }
+    }
+
+    /// `(level, optname)` for a socket of the given family
+    #[cfg(target_os = "linux")]
+    pub fn sockopt(self, ipv6: bool) -> (libc::c_int, libc::c_int) {
+        match (self, ipv6) {
+            (Self::Transparent, false) => (libc::SOL_IP, libc::IP_TRANSPARENT),
+            (Self::Transparent, true) => (libc::SOL_IPV6, libc::IPV6_TRANSPARENT),
+            (Self::FreeBind, false) => (libc::SOL_IP, libc::IP_FREEBIND),
+            (Self::FreeBind, true) => (libc::SOL_IPV6, libc::IPV6_FREEBIND),
+        }
+    }
+
+    /// Turn the option on or off for `fd`. EPERM for IP_TRANSPARENT is raised
+    /// as a PermissionError that names the missing capability.
+    #[cfg(target_os = "linux")]
+    pub fn set(self, fd: std::os::fd::RawFd, ipv6: bool, enabled: bool) -> PyResult<()> {
+        let (level, optname) = self.sockopt(ipv6);
+        let optval = enabled as libc::c_int;
+        let ret = unsafe {
+            libc::setsockopt(
+                fd,
+                level,
+                optname,
+                &optval as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&optval) as libc::socklen_t,
+            )
+        };
+        if ret == 0 {
+            return Ok(());
+        }
+        let err = std::io::Error::last_os_error();
+        let errno = err.raw_os_error().unwrap_or(0);
+        let hint = if errno == libc::EPERM && self == Self::Transparent {
+            " (transparent proxying needs CAP_NET_ADMIN or CAP_NET_RAW)"
+        } else {
+            ""
+        };
+        Err(crate::utils::os_error_with_message(
+            &err,
+            format!("Failed to set {}: {}{}", self.name(ipv6), err, hint),
+        ))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn set(self, _fd: std::os::fd::RawFd, ipv6: bool, _enabled: bool) -> PyResult<()> {
+        Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
+            "{} is not supported on this platform",
+            self.name(ipv6)
+        )))
+    }
+}
+
+/// The `transparent=` and `freebind=` kwargs of create_server/start_server/create_connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProxyOptions {
+    pub transparent: bool,
+    pub freebind: bool,
+}
+
+impl ProxyOptions {
+    pub fn from_kwargs(kwargs: Option<&Bound<'_, pyo3::types::PyDict>>) -> PyResult<Self> {
+        let mut options = Self::default();
+        if let Some(kw) = kwargs {
+            for (key, slot) in [
+                ("transparent", &mut options.transparent),
+                ("freebind", &mut options.freebind),
+            ] {
+                if let Some(value) = kw.get_item(key)? {
+                    *slot = value.is_truthy()?;
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    /// Set the requested options on `fd`, before it is bound
+    pub fn apply(self, fd: std::os::fd::RawFd, ipv6: bool) -> PyResult<()> {
+        if self.transparent {
+            ProxyOption::Transparent.set(fd, ipv6, true)?;
+        }
+        if self.freebind {
+            ProxyOption::FreeBind.set(fd, ipv6, true)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a connection redirected by iptables REDIRECT/DNAT was originally
+/// headed (`getsockopt(SO_ORIGINAL_DST)`). None for traffic that was not
+/// redirected, or without conntrack. TPROXY connections need no lookup: their
+/// original destination is the socket's own address.
+#[cfg(target_os = "linux")]
+pub fn original_dst(stream: &TcpStream) -> Option<std::net::SocketAddr> {
+    use std::os::fd::AsRawFd;
+
+    let fd = stream.as_raw_fd();
+    let (level, optname) = if stream.local_addr().ok()?.is_ipv6() {
+        (libc::SOL_IPV6, libc::IP6T_SO_ORIGINAL_DST)
+    } else {
+        (libc::SOL_IP, libc::SO_ORIGINAL_DST)
+    };
+    let (_, addr) = unsafe {
+        socket2::SockAddr::try_init(|storage, len| {
+            if libc::getsockopt(fd, level, optname, storage.cast(), len) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        })
+    }
+    .ok()?;
+    addr.as_socket()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn original_dst(_stream: &TcpStream) -> Option<std::net::SocketAddr> {
+    None
+}
+
+/// Whether this platform has SO_REUSEPORT
+pub const HAS_REUSE_PORT: bool = cfg!(all(unix, not(target_os = "solaris")));
+
+/// Set SO_REUSEPORT on `fd`, before it is bound
+#[cfg(all(unix, not(target_os = "solaris")))]
+pub fn set_reuse_port(fd: std::os::fd::RawFd) -> PyResult<()> {
+    set_int_option(fd, libc::SOL_SOCKET, libc::SO_REUSEPORT, 1, "SO_REUSEPORT")
+}
+
+#[cfg(not(all(unix, not(target_os = "solaris"))))]
+pub fn set_reuse_port(_fd: std::os::fd::RawFd) -> PyResult<()> {
+    Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
+        "SO_REUSEPORT is not available on this platform",
+    ))
+}
+
+/// Accept a connection on listening socket `fd`, already non-blocking and
+/// close-on-exec so it never leaks into a subprocess. Returns the connection
+/// and the peer's address.
+pub fn accept_nonblocking(
+    fd: std::os::fd::RawFd,
+) -> std::io::Result<(
+    std::os::fd::OwnedFd,
+    libc::sockaddr_storage,
+    libc::socklen_t,
+)> {
+    use std::os::fd::FromRawFd;
+
+    let mut addr: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut addr_len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    let addr_ptr = &mut addr as *mut _ as *mut libc::sockaddr;
+    loop {
+        // accept4 sets both flags in the accept itself; elsewhere it takes
+        // two fcntl calls, racing a fork on another thread in between
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+        let conn = unsafe {
+            libc::accept4(
+                fd,
+                addr_ptr,
+                &mut addr_len,
+                libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            )
+        };
+        #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+        let conn = unsafe {
+            let conn = libc::accept(fd, addr_ptr, &mut addr_len);
+            if conn >= 0 {
+                let flags = libc::fcntl(conn, libc::F_GETFL);
+                libc::fcntl(conn, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                libc::fcntl(conn, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+            conn
+        };
+        if conn >= 0 {
+            let conn = unsafe { std::os::fd::OwnedFd::from_raw_fd(conn) };
+            return Ok((conn, addr, addr_len));
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// `recv()` from `fd` into `buf`, retrying EINTR. Returns how many bytes
+/// arrived, 0 at EOF.
+pub fn recv_into(fd: std::os::fd::RawFd, buf: &mut [u8]) -> std::io::Result<usize> {
+    loop {
+        let n = unsafe { libc::recv(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+        if n >= 0 {
+            return Ok(n as usize);
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// SO_TYPE of `fd`: SOCK_STREAM, SOCK_DGRAM, ...
+pub fn socket_type(fd: std::os::fd::RawFd) -> PyResult<libc::c_int> {
+    get_int_option(fd, libc::SOL_SOCKET, libc::SO_TYPE)
+}
+
+/// TCP keep-alive probing: SO_KEEPALIVE plus the idle time, probe interval and
+/// probe count, each left at the OS default when None
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeepaliveParams {
+    pub idle: Option<u32>,
+    pub interval: Option<u32>,
+    pub count: Option<u32>,
+}
+
+/// Option names for (idle, interval, count); macOS calls the idle time
+/// TCP_KEEPALIVE. None where the platform has no such option.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+const KEEPALIVE_OPTS: [Option<libc::c_int>; 3] = [
+    Some(libc::TCP_KEEPIDLE),
+    Some(libc::TCP_KEEPINTVL),
+    Some(libc::TCP_KEEPCNT),
+];
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const KEEPALIVE_OPTS: [Option<libc::c_int>; 3] = [
+    Some(libc::TCP_KEEPALIVE),
+    Some(libc::TCP_KEEPINTVL),
+    Some(libc::TCP_KEEPCNT),
+];
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "macos",
+    target_os = "ios"
+)))]
+const KEEPALIVE_OPTS: [Option<libc::c_int>; 3] = [None, None, None];
+
+const KEEPALIVE_NAMES: [&str; 3] = ["idle", "interval", "count"];
+
+impl KeepaliveParams {
+    /// The `keepalive=` kwarg: None/False for off, True for OS defaults, or a
+    /// dict with any of "idle", "interval" and "count" in seconds/probes
+    pub fn from_py(value: Option<&Bound<'_, PyAny>>) -> PyResult<Option<Self>> {
+        let Some(value) = value.filter(|v| !v.is_none()) else {
+            return Ok(None);
+        };
+        if let Ok(enabled) = value.cast::<pyo3::types::PyBool>() {
+            return Ok(enabled.is_true().then(Self::default));
+        }
+        let dict = value.cast::<pyo3::types::PyDict>().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "keepalive must be a bool or a dict of idle/interval/count",
+            )
+        })?;
+        let mut params = [None; 3];
+        for (key, item) in dict.iter() {
+            let key = key.extract::<String>()?;
+            let slot = KEEPALIVE_NAMES
+                .iter()
+                .position(|name| *name == key)
+                .ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "unknown keepalive parameter: {key:?}"
+                    ))
+                })?;
+            params[slot] = item.extract::<Option<u32>>()?;
+        }
+        let [idle, interval, count] = params;
+        Ok(Some(Self {
+            idle,
+            interval,
+            count,
+        }))
+    }
+
+    /// Enable SO_KEEPALIVE on `fd` and set whichever parameters are given;
+    /// parameters the platform lacks are skipped
+    pub fn apply(&self, fd: std::os::fd::RawFd) -> PyResult<()> {
+        set_int_option(fd, libc::SOL_SOCKET, libc::SO_KEEPALIVE, 1, "SO_KEEPALIVE")?;
+        let values = [self.idle, self.interval, self.count];
+        for (i, value) in values.into_iter().enumerate() {
+            if let (Some(value), Some(optname)) = (value, KEEPALIVE_OPTS[i]) {
+                let optval = value.min(libc::c_int::MAX as u32) as libc::c_int;
+                let name = format!("keepalive {}", KEEPALIVE_NAMES[i]);
+                set_int_option(fd, libc::IPPROTO_TCP, optname, optval, &name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the settings back: (SO_KEEPALIVE on, current parameters)
+    pub fn read(fd: std::os::fd::RawFd) -> PyResult<(bool, Self)> {
+        let enabled = get_int_option(fd, libc::SOL_SOCKET, libc::SO_KEEPALIVE)? != 0;
+        let mut values = [None; 3];
+        for (value, optname) in values.iter_mut().zip(KEEPALIVE_OPTS) {
+            if let Some(optname) = optname {
+                *value = Some(get_int_option(fd, libc::IPPROTO_TCP, optname)? as u32);
+            }
+        }
+        let [idle, interval, count] = values;
+        Ok((
+            enabled,
+            Self {
+                idle,
+                interval,
+                count,
+            },
+        ))
+    }
+}
+
+fn set_int_option(
+    fd: std::os::fd::RawFd,
+    level: libc::c_int,
+    optname: libc::c_int,
+    optval: libc::c_int,
+    name: &str,
+) -> PyResult<()> {
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            optname,
+            &optval as *const _ as *const libc::c_void,
+            std::mem::size_of_val(&optval) as libc::socklen_t,
+        )
+    };
+    if ret != 0 {
+        return Err(crate::utils::last_os_error(&format!(
+            "Failed to set {name}"
+        )));
+    }
+    Ok(())
+}
+
+fn get_int_option(
+    fd: std::os::fd::RawFd,
+    level: libc::c_int,
+    optname: libc::c_int,
+) -> PyResult<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            level,
+            optname,
+            &mut value as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
         )
+    };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error().into());
     }
+    Ok(value)
 }
@@ -0,0 +1,291 @@
+//! Loop counters published through a shared-memory file (`loop.enable_stats_export`)
+//!
+//! The file holds one fixed record of native-endian u64 words: a header
+//! (magic, layout version, sequence) followed by `FIELDS`. The loop rewrites
+//! the record at most once per iteration with plain stores under a seqlock:
+//! the sequence is odd while the fields are being stored, and a reader in any
+//! process copies the fields only counting them when it saw the same even
+//! sequence before and after.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Names of the words after the header, in file order
+pub const FIELDS: [&str; 9] = [
+    "pid",
+    "updated_ns",
+    "iterations",
+    "events",
+    "bytes_in",
+    "bytes_out",
+    "active_connections",
+    "callbacks_queued",
+    "timers",
+];
+
+const MAGIC: u64 = u64::from_le_bytes(*b"VLXSTATS");
+const LAYOUT_VERSION: u64 = 1;
+const SEQ: usize = 2;
+const HEADER_WORDS: usize = 3;
+const WORDS: usize = HEADER_WORDS + FIELDS.len();
+const RECORD_BYTES: usize = WORDS * std::mem::size_of::<u64>();
+/// Attempts a reader makes before deciding the writer died mid-update
+const READ_ATTEMPTS: usize = 10_000;
+
+/// What the loop reports each iteration; `pid` and `updated_ns` are filled in
+/// by `StatsExport::publish`
+#[derive(Clone, Copy, Default)]
+pub struct Counters {
+    pub iterations: u64,
+    pub events: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub active_connections: u64,
+    pub callbacks_queued: u64,
+    pub timers: u64,
+}
+
+/// The record mapped into this process, shared with every other mapping
+struct Mapping {
+    words: NonNull<AtomicU64>,
+}
+
+// Safety: the mapping is only ever accessed through atomics
+unsafe impl Send for Mapping {}
+
+impl Mapping {
+    fn new(file: &std::fs::File, writable: bool) -> io::Result<Self> {
+        let prot = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                RECORD_BYTES,
+                prot,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            words: NonNull::new(ptr.cast()).expect("mmap returned null"),
+        })
+    }
+
+    #[inline(always)]
+    fn word(&self, index: usize) -> &AtomicU64 {
+        debug_assert!(index < WORDS);
+        // Page aligned and RECORD_BYTES long: every index below WORDS is in bounds
+        unsafe { &*self.words.as_ptr().add(index) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.words.as_ptr().cast(), RECORD_BYTES) };
+    }
+}
+
+/// The writing side, owned by one loop
+pub struct StatsExport {
+    map: Mapping,
+    seq: u64,
+    pid: u64,
+}
+
+impl StatsExport {
+    /// Create or truncate the file at `path` and map a zeroed record in it
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o644)
+            .open(path)?;
+        file.set_len(RECORD_BYTES as u64)?;
+        let map = Mapping::new(&file, true)?;
+        map.word(0).store(MAGIC, Ordering::Relaxed);
+        map.word(1).store(LAYOUT_VERSION, Ordering::Relaxed);
+        let mut export = Self {
+            map,
+            seq: 0,
+            pid: std::process::id() as u64,
+        };
+        export.publish(&Counters::default());
+        Ok(export)
+    }
+
+    /// Overwrite the record with `counters`
+    #[inline]
+    pub fn publish(&mut self, counters: &Counters) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let values = [
+            self.pid,
+            now,
+            counters.iterations,
+            counters.events,
+            counters.bytes_in,
+            counters.bytes_out,
+            counters.active_connections,
+            counters.callbacks_queued,
+            counters.timers,
+        ];
+        self.seq += 1;
+        self.map.word(SEQ).store(self.seq, Ordering::Relaxed);
+        fence(Ordering::Release);
+        for (i, value) in values.into_iter().enumerate() {
+            self.map
+                .word(HEADER_WORDS + i)
+                .store(value, Ordering::Relaxed);
+        }
+        self.seq += 1;
+        self.map.word(SEQ).store(self.seq, Ordering::Release);
+    }
+}
+
+/// A consistent copy of the record at `path`, as `(name, value)` pairs in
+/// `FIELDS` order. The writer needn't be in this process, or still alive.
+pub fn read(path: &Path) -> io::Result<Vec<(&'static str, u64)>> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    if file.metadata()?.len() < RECORD_BYTES as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a veloxloop stats file",
+        ));
+    }
+    let map = Mapping::new(&file, false)?;
+    if map.word(0).load(Ordering::Relaxed) != MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a veloxloop stats file",
+        ));
+    }
+    let version = map.word(1).load(Ordering::Relaxed);
+    if version != LAYOUT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported stats file layout {version}"),
+        ));
+    }
+
+    let mut values = [0u64; FIELDS.len()];
+    for attempt in 0..READ_ATTEMPTS {
+        let before = map.word(SEQ).load(Ordering::Acquire);
+        if before % 2 == 0 {
+            for (i, value) in values.iter_mut().enumerate() {
+                *value = map.word(HEADER_WORDS + i).load(Ordering::Relaxed);
+            }
+            fence(Ordering::Acquire);
+            if map.word(SEQ).load(Ordering::Relaxed) == before {
+                return Ok(FIELDS.into_iter().zip(values).collect());
+            }
+        }
+        if attempt % 64 == 63 {
+            std::thread::yield_now();
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::WouldBlock,
+        "stats file stayed mid-update",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("veloxloop-{}-{name}", std::process::id()))
+    }
+
+    fn counters(n: u64) -> Counters {
+        Counters {
+            iterations: n,
+            events: n,
+            bytes_in: n,
+            bytes_out: n,
+            active_connections: n,
+            callbacks_queued: n,
+            timers: n,
+        }
+    }
+
+    #[test]
+    fn reads_what_was_published() {
+        let path = temp_path("published");
+        let mut export = StatsExport::create(&path).unwrap();
+        export.publish(&Counters {
+            events: 7,
+            timers: 3,
+            ..counters(1)
+        });
+        let record = read(&path).unwrap();
+        let get = |name| record.iter().find(|(n, _)| *n == name).unwrap().1;
+        assert_eq!(get("pid"), std::process::id() as u64);
+        assert_eq!(get("iterations"), 1);
+        assert_eq!(get("events"), 7);
+        assert_eq!(get("timers"), 3);
+        assert!(get("updated_ns") > 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn never_sees_a_torn_record() {
+        // Every update sets all counters to the same value; a reader mixing
+        // two updates would see them differ
+        let path = temp_path("torn");
+        let mut export = StatsExport::create(&path).unwrap();
+        let done = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let done = done.clone();
+            std::thread::spawn(move || {
+                for n in 1..=200_000 {
+                    export.publish(&counters(n));
+                }
+                done.store(true, Ordering::Release);
+            })
+        };
+        let mut reads = 0;
+        let mut last = 0;
+        while !done.load(Ordering::Acquire) {
+            let record = read(&path).unwrap();
+            let counts: Vec<u64> = record[2..].iter().map(|(_, v)| *v).collect();
+            assert!(counts.iter().all(|&v| v == counts[0]), "torn: {counts:?}");
+            assert!(counts[0] >= last);
+            last = counts[0];
+            reads += 1;
+        }
+        writer.join().unwrap();
+        assert!(reads > 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let path = temp_path("other");
+        std::fs::write(&path, vec![0u8; RECORD_BYTES]).unwrap();
+        assert_eq!(read(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        std::fs::write(&path, b"short").unwrap();
+        assert_eq!(read(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,21 +1,25 @@
 use crate::buffer_pool::BufferPool;
 use crate::ffi_utils;
 use crate::{
-    constants::{DEFAULT_HIGH, DEFAULT_LIMIT, DEFAULT_LOW},
+    constants::{
+        DEFAULT_HIGH, DEFAULT_LIMIT, DEFAULT_LOW, DEFAULT_READ_CHUNK_SIZE, SHRINK_FACTOR,
+        WAKEUP_BATCH,
+    },
+    event_loop::{ExceptionContext, VeloxLoop},
     transports::future::PendingFuture,
 };
-use bytes::BytesMut;
-use memchr::memchr;
+use bytes::{Buf, BytesMut};
+use memchr::{memchr, memchr2, memchr3};
 use parking_lot::Mutex;
 use pyo3::IntoPyObjectExt;
 use pyo3::buffer::PyBuffer;
 use pyo3::ffi;
 #[allow(unused)]
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
-use std::cell::RefCell;
+use pyo3::types::{PyBytes, PyMemoryView, PySlice, PyTuple};
+use std::cell::{OnceCell, RefCell};
 use std::io::{self, Read};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 thread_local! {
     static TEMP_READ_BUF: RefCell<Vec<u8>> = RefCell::new(vec![0; 131072]);
@@ -26,6 +30,10 @@ pub struct StreamReader {
     pub(crate) inner: RefCell<StreamReaderInner>,
     /// Maximum buffer size before pausing
     pub(crate) limit: usize,
+    /// Loop of the transport feeding this reader; waiters with done callbacks
+    /// beyond the first `WAKEUP_BATCH` of a wakeup are settled through its
+    /// call_soon
+    loop_: OnceCell<Py<VeloxLoop>>,
 }
 
 // Safety: StreamReader is only used in single-threaded Python context
@@ -38,11 +46,81 @@ pub(crate) struct StreamReaderInner {
     pub(crate) eof: bool,
     pub(crate) exception: Option<String>,
     pub(crate) waiters: Vec<(WaiterType, Py<PendingFuture>)>,
+    /// Capacity the buffer was acquired with
+    base_capacity: usize,
+    /// Most bytes buffered since the buffer was last replaced
+    peak: usize,
+    /// Bytes ever buffered. The buffer starts at stream position
+    /// `fed - buffer.len()`, however much reads have taken off its front.
+    fed: u64,
+    /// Separators of the last immediate readuntil() that found none, and the
+    /// stream position its search got to
+    scan: Option<(Vec<Vec<u8>>, u64)>,
 }
 
 impl StreamReaderInner {
-    fn feed_data(&mut self, data: &[u8]) {
+    fn new(base_capacity: usize) -> Self {
+        Self {
+            buffer: BufferPool::acquire_sized(base_capacity),
+            eof: false,
+            exception: None,
+            waiters: Vec::new(),
+            base_capacity,
+            peak: 0,
+            fed: 0,
+            scan: None,
+        }
+    }
+
+    pub(crate) fn feed_data(&mut self, data: &[u8]) {
         self.buffer.extend_from_slice(data);
+        self.fed += data.len() as u64;
+        self.peak = self.peak.max(self.buffer.len());
+    }
+
+    /// Where an unfinished search for `separators` got to, if the last
+    /// immediate readuntil() was for the same ones
+    fn scanned_for<S: AsRef<[u8]>>(&self, separators: &[S]) -> u64 {
+        match &self.scan {
+            Some((cached, scanned)) if same_separators(cached, separators) => *scanned,
+            _ => 0,
+        }
+    }
+
+    /// Data up to and including the earliest of `separators`, resuming a
+    /// search for the same separators that an earlier call left unfinished
+    pub(crate) fn readuntil<S: AsRef<[u8]>>(
+        &mut self,
+        separators: &[S],
+    ) -> PyResult<Option<(Vec<u8>, Option<usize>)>> {
+        let mut scanned = self.scanned_for(separators);
+        let found = StreamReader::_try_readuntil_any(
+            &mut self.buffer,
+            self.eof,
+            separators,
+            self.fed,
+            &mut scanned,
+        )?;
+        if found.is_some() {
+            self.scan = None;
+        } else if let Some((cached, position)) = &mut self.scan
+            && same_separators(cached, separators)
+        {
+            *position = scanned;
+        } else {
+            let key = separators.iter().map(|sep| sep.as_ref().to_vec()).collect();
+            self.scan = Some((key, scanned));
+        }
+        Ok(found)
+    }
+
+    /// Once a large message has been consumed, swap its allocation for a
+    /// pooled buffer of the original size instead of keeping it for good
+    fn release_spare(&mut self) {
+        if self.buffer.is_empty() && self.peak > SHRINK_FACTOR * self.base_capacity {
+            self.buffer = BufferPool::acquire_sized(self.base_capacity);
+            self.peak = 0;
+        }
     }
 
     fn feed_eof(&mut self) {
@@ -50,6 +128,10 @@ impl StreamReaderInner {
     }
 }
 
+fn same_separators<S: AsRef<[u8]>>(cached: &[Vec<u8>], separators: &[S]) -> bool {
+    cached.len() == separators.len() && cached.iter().zip(separators).all(|(a, b)| a == b.as_ref())
+}
+
 impl Drop for StreamReaderInner {
     fn drop(&mut self) {
         let buf = std::mem::replace(&mut self.buffer, BytesMut::new());
@@ -57,11 +139,104 @@ impl Drop for StreamReaderInner {
     }
 }
 
-#[derive(Clone)]
 pub(crate) enum WaiterType {
-    ReadLine,
-    ReadUntil(Vec<u8>),
+    ReadLine {
+        /// Stream position the search for `\n` resumes from
+        scanned: u64,
+    },
+    /// `async for`: a line like `ReadLine`, but the empty one at EOF ends the
+    /// iteration with StopAsyncIteration
+    ReadLineIter {
+        scanned: u64,
+    },
+    ReadUntil {
+        separators: Vec<Vec<u8>>,
+        /// Resolve with `(data, separator)` instead of just the data
+        with_separator: bool,
+        /// Stream position the search resumes from, so each wakeup scans
+        /// only what arrived since the last one
+        scanned: u64,
+    },
     ReadExactly(usize),
+    /// `readexactly_into()`: the caller's buffer is filled in place
+    ReadInto(ReadInto),
+}
+
+/// The caller's buffer of a `readexactly_into()` and how much of it is filled
+pub(crate) struct ReadInto {
+    /// The object passed in, for the `partial` memoryview of a short read
+    target: Py<PyAny>,
+    /// The buffer export; holding it keeps the memory alive and in place
+    view: PyBuffer<u8>,
+    filled: usize,
+}
+
+impl ReadInto {
+    fn new(target: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let view = PyBuffer::<u8>::get(target)?;
+        if view.readonly() || !view.is_c_contiguous() {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "readexactly_into() needs a writable, contiguous buffer",
+            ));
+        }
+        Ok(Self {
+            target: target.clone().unbind(),
+            view,
+            filled: 0,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.view.len_bytes()
+    }
+
+    fn is_full(&self) -> bool {
+        self.filled == self.len()
+    }
+
+    /// The part still to be filled
+    fn remaining(&mut self) -> &mut [u8] {
+        // The export is writable and contiguous (checked in `new`), and only
+        // touched with the GIL held
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                (self.view.buf_ptr() as *mut u8).add(self.filled),
+                self.len() - self.filled,
+            )
+        }
+    }
+
+    /// Move as much of `buffer` as fits
+    fn fill_from(&mut self, buffer: &mut BytesMut) {
+        let n = buffer.len().min(self.len() - self.filled);
+        self.remaining()[..n].copy_from_slice(&buffer[..n]);
+        buffer.advance(n);
+        self.filled += n;
+    }
+
+    /// `memoryview(target)[:filled]`
+    fn partial<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let end = PySlice::new(py, 0, self.filled as isize, 1);
+        PyMemoryView::from(self.target.bind(py))?.get_item(end)
+    }
+
+    /// EOF came first: `IncompleteReadError` whose `partial` is a memoryview
+    /// of what was filled
+    fn incomplete_error(&self, py: Python<'_>) -> PyResult<PyErr> {
+        let error = py
+            .import("asyncio")?
+            .getattr("IncompleteReadError")?
+            .call1((self.partial(py)?, self.len()))?;
+        Ok(PyErr::from_value(error))
+    }
+
+    /// The reader failed: the same RuntimeError as other reads, carrying
+    /// the filled part as `partial`
+    fn failed_error(&self, py: Python<'_>, message: String) -> PyResult<PyErr> {
+        let error = pyo3::exceptions::PyRuntimeError::new_err(message);
+        error.value(py).setattr("partial", self.partial(py)?)?;
+        Ok(error)
+    }
 }
 
 #[pymethods]
@@ -69,24 +244,13 @@ impl StreamReader {
     #[new]
     #[pyo3(signature = (limit=None))]
     pub fn new(limit: Option<usize>) -> Self {
-        Self {
-            inner: RefCell::new(StreamReaderInner {
-                buffer: BufferPool::acquire(),
-                eof: false,
-                exception: None,
-                waiters: Vec::new(),
-            }),
-            limit: limit.unwrap_or(DEFAULT_LIMIT),
-        }
+        Self::with_chunk_size(limit, DEFAULT_READ_CHUNK_SIZE)
     }
 
-    /// Feed data into the buffer and wake up waiters
-    pub fn feed_data(&self, py: Python<'_>, data: &[u8]) -> PyResult<()> {
-        if data.is_empty() {
-            return Ok(());
-        }
-
-        self.feed_data_native(py, data)
+    /// Feed any bytes-like object into the buffer and wake up waiters
+    pub fn feed_data(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<()> {
+        let view = contiguous_buffer(data)?;
+        self.feed_data_native(py, crate::callbacks::buffer_bytes(&view))
     }
 
     /// Feed data into the buffer from Rust and wake up waiters
@@ -125,61 +289,149 @@ impl StreamReader {
         // Collect satisfied futures to avoid holding the borrow while calling Python code
         let mut ready_waiters = Vec::new();
         let mut error_waiters = Vec::new();
+        // readexactly_into() waiters that are full, or cut short by EOF
+        let mut done_fills = Vec::new();
+        let mut failed_waiters = Vec::new();
 
         {
             let mut inner_guard = self.inner.borrow_mut();
             let inner = &mut *inner_guard;
+            // Waiters cancelled while they waited want nothing, and mustn't
+            // take data from the buffer on the way out
+            inner
+                .waiters
+                .retain(|(_, future)| !future.bind(py).borrow().done());
 
             // Check for exception first
             if let Some(exc_msg) = &inner.exception {
                 // All waiters get error
-                for (_, future) in inner.waiters.drain(..) {
-                    error_waiters.push((future, exc_msg.clone()));
+                for (waiter, future) in inner.waiters.drain(..) {
+                    let fill = match waiter {
+                        WaiterType::ReadInto(fill) => Some(fill),
+                        _ => None,
+                    };
+                    error_waiters.push((future, exc_msg.clone(), fill));
                 }
             } else {
                 // Split borrows to allow independent access to buffer and waiters
                 let eof = inner.eof;
+                let fed = inner.fed;
                 let buffer = &mut inner.buffer;
                 let waiters = &mut inner.waiters;
 
-                let mut i = 0;
-                while i < waiters.len() {
-                    let should_remove = {
-                        let waiter_type = &waiters[i].0;
-                        match waiter_type {
-                            WaiterType::ReadLine => Self::_try_readuntil_inner(buffer, eof, b"\n"),
-                            WaiterType::ReadUntil(sep) => {
-                                Self::_try_readuntil_inner(buffer, eof, sep)
+                // Rebuilt in order from the waiters left waiting, rather than
+                // removing each satisfied one from the middle
+                for (mut waiter, future) in std::mem::take(waiters) {
+                    if let WaiterType::ReadInto(fill) = &mut waiter {
+                        fill.fill_from(buffer);
+                        if fill.is_full() || eof {
+                            if let WaiterType::ReadInto(fill) = waiter {
+                                done_fills.push((future, fill));
                             }
-                            WaiterType::ReadExactly(n) => {
-                                Self::_try_readexactly_inner(buffer, eof, *n)
-                            }
-                        }?
+                        } else {
+                            waiters.push((waiter, future));
+                        }
+                        continue;
+                    }
+                    let iterating = matches!(waiter, WaiterType::ReadLineIter { .. });
+                    let satisfied = match &mut waiter {
+                        WaiterType::ReadLine { scanned } | WaiterType::ReadLineIter { scanned } => {
+                            Self::_try_readuntil_any(buffer, eof, &[b"\n"], fed, scanned)
+                                .map(|found| found.map(|(data, _)| (data, None)))
+                        }
+                        WaiterType::ReadUntil {
+                            separators,
+                            with_separator,
+                            scanned,
+                        } => Self::_try_readuntil_any(buffer, eof, separators, fed, scanned).map(
+                            |found| {
+                                found.map(|(data, matched)| {
+                                    let sep = with_separator
+                                        .then(|| matched.map(|i| separators[i].clone()));
+                                    (data, sep)
+                                })
+                            },
+                        ),
+                        WaiterType::ReadExactly(n) => Self::_try_readexactly_inner(buffer, eof, *n)
+                            .map(|data| data.map(|data| (data, None))),
+                        WaiterType::ReadInto(_) => unreachable!("handled above"),
                     };
 
-                    if let Some(data) = should_remove {
-                        let (_, future) = waiters.remove(i);
-                        ready_waiters.push((future, data));
-                    } else {
-                        i += 1;
+                    match satisfied {
+                        Ok(Some((data, _))) if iterating && data.is_empty() => failed_waiters
+                            .push((future, pyo3::exceptions::PyStopAsyncIteration::new_err(()))),
+                        Ok(Some((data, sep))) => ready_waiters.push((future, data, sep)),
+                        Ok(None) => waiters.push((waiter, future)),
+                        // A readexactly() that EOF cut short fails as it
+                        // would have had it been called after the EOF
+                        Err(e) => failed_waiters.push((future, e)),
                     }
                 }
+                inner.release_spare();
             }
         }
 
-        // Dispatch results outside lock - use C API for PyBytes to reduce overhead
-        for (future, data) in ready_waiters {
-            let bytes = unsafe { ffi_utils::bytes_from_slice(py, &data) };
-            future.bind(py).borrow().set_result(py, bytes)?;
+        // Build results outside lock - use C API for PyBytes to reduce overhead
+        let mut settled = Vec::with_capacity(
+            ready_waiters.len() + done_fills.len() + failed_waiters.len() + error_waiters.len(),
+        );
+        for (future, data, sep) in ready_waiters {
+            let bytes = ffi_utils::bytes_from_large_slice(py, &data)?;
+            let result = match sep {
+                Some(sep) => (bytes, sep.map(|sep| PyBytes::new(py, &sep))).into_py_any(py)?,
+                None => bytes,
+            };
+            settled.push((future, Settle::Result(result)));
+        }
+
+        for (future, fill) in done_fills {
+            let outcome = if fill.is_full() {
+                Settle::Result(fill.len().into_py_any(py)?)
+            } else {
+                Settle::Exception(fill.incomplete_error(py)?.into_value(py).into_any())
+            };
+            settled.push((future, outcome));
         }
 
-        for (future, msg) in error_waiters {
+        for (future, err) in failed_waiters {
+            settled.push((future, Settle::Exception(err.into_value(py).into_any())));
+        }
+
+        for (future, msg, fill) in error_waiters {
             // Correctly create exception object
-            let exc = pyo3::exceptions::PyRuntimeError::new_err(msg).into_py_any(py)?;
-            future.bind(py).borrow().set_exception(py, exc)?;
+            let exc = match fill {
+                Some(fill) => fill.failed_error(py, msg)?,
+                None => pyo3::exceptions::PyRuntimeError::new_err(msg),
+            };
+            settled.push((future, Settle::Exception(exc.into_value(py).into_any())));
         }
 
-        Ok(())
+        // Settling a future runs its done callbacks right here. Past one batch
+        // of futures that have some, the rest go through call_soon so a single
+        // feed can't keep the loop from polling other connections. Futures
+        // without callbacks cost nothing to settle and are never held back.
+        if let Some(loop_) = self.loop_.get()
+            && settled.len() > WAKEUP_BATCH
+        {
+            let mut with_callbacks = 0;
+            let (now, later): (Vec<_>, Vec<_>) = settled.into_iter().partition(|(future, _)| {
+                if !future.bind(py).borrow().has_callbacks() {
+                    return true;
+                }
+                with_callbacks += 1;
+                with_callbacks <= WAKEUP_BATCH
+            });
+            if !later.is_empty() {
+                DeferredWakeups::schedule(py, loop_, later)?;
+            }
+            settled = now;
+        }
+        settle_all(
+            py,
+            self.loop_.get(),
+            settled,
+            "Exception while waking StreamReader waiters",
+        )
     }
 
     fn _try_readline(&self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
@@ -187,17 +439,7 @@ impl StreamReader {
     }
 
     fn _try_readuntil(&self, py: Python<'_>, separator: &[u8]) -> PyResult<Option<Py<PyAny>>> {
-        let mut inner = self.inner.borrow_mut();
-        if let Some(msg) = &inner.exception {
-            return Err(pyo3::exceptions::PyRuntimeError::new_err(msg.clone()));
-        }
-        let eof = inner.eof;
-        if let Some(data) = Self::_try_readuntil_inner(&mut inner.buffer, eof, separator)? {
-            let bytes = PyBytes::new(py, &data);
-            Ok(Some(bytes.into()))
-        } else {
-            Ok(None)
-        }
+        self.try_readuntil_separators(py, &[separator], false)
     }
 
     fn _try_readexactly(&self, py: Python<'_>, n: usize) -> PyResult<Option<Py<PyAny>>> {
@@ -206,12 +448,15 @@ impl StreamReader {
             return Err(pyo3::exceptions::PyRuntimeError::new_err(msg.clone()));
         }
         let eof = inner.eof;
-        if let Some(data) = Self::_try_readexactly_inner(&mut inner.buffer, eof, n)? {
-            let bytes = PyBytes::new(py, &data);
-            Ok(Some(bytes.into()))
-        } else {
-            Ok(None)
+        if inner.buffer.len() < n {
+            // Not there yet, or an error when EOF means it never will be
+            return Self::_try_readexactly_inner(&mut inner.buffer, eof, n).map(|_| None);
         }
+        // Straight from the buffer, without an intermediate Vec
+        let bytes = ffi_utils::bytes_from_large_slice(py, &inner.buffer[..n])?;
+        inner.buffer.advance(n);
+        inner.release_spare();
+        Ok(Some(bytes))
     }
 
     /// Set an exception message to be raised on next read
@@ -243,19 +488,15 @@ impl StreamReader {
             return Err(pyo3::exceptions::PyRuntimeError::new_err(exc_msg));
         }
 
-        if n < 0 {
-            // Read all available data
-            let data = inner.buffer.split().to_vec();
-            let bytes = PyBytes::new(py, &data);
-            return Ok(bytes.into());
-        }
-
-        let n = n as usize;
-        let available = inner.buffer.len().min(n);
-        let data = inner.buffer.split_to(available).to_vec();
-        let bytes = PyBytes::new(py, &data);
-
-        Ok(bytes.into())
+        // All available data for a negative n
+        let available = match usize::try_from(n) {
+            Ok(n) => inner.buffer.len().min(n),
+            Err(_) => inner.buffer.len(),
+        };
+        let bytes = ffi_utils::bytes_from_large_slice(py, &inner.buffer[..available])?;
+        inner.buffer.advance(available);
+        inner.release_spare();
+        Ok(bytes)
     }
 
     /// Read exactly n bytes (async - returns a future)
@@ -275,28 +516,71 @@ impl StreamReader {
         }
     }
 
-    /// Read until delimiter is found (async - returns a future)
-    #[pyo3(signature = (separator=b"\n".as_slice()))]
-    pub fn readuntil(&self, py: Python<'_>, separator: &[u8]) -> PyResult<Py<PyAny>> {
-        if separator.is_empty() {
-            return Err(pyo3::exceptions::PyValueError::new_err(
-                "Separator cannot be empty",
-            ));
-        }
-
-        // Try to get data immediately
-        match self._try_readuntil(py, separator)? {
-            Some(data) => Ok(data),
-            None => {
-                // Create a pending future
-                let future = Py::new(py, PendingFuture::new())?;
-                self.inner.borrow_mut().waiters.push((
-                    WaiterType::ReadUntil(separator.to_vec()),
-                    future.clone_ref(py),
-                ));
-                Ok(future.into_any())
+    /// Up to `n` buffered bytes (all of them for -1) as a memoryview, without
+    /// waiting or copying: the view holds the bytes taken out of the buffer.
+    /// Empty when nothing is buffered; the reader's exception is raised as is.
+    #[pyo3(signature = (n=-1))]
+    pub fn read_nowait<'py>(&self, py: Python<'py>, n: isize) -> PyResult<Bound<'py, PyAny>> {
+        let data = {
+            let mut inner = self.inner.borrow_mut();
+            if let Some(msg) = &inner.exception {
+                return Err(pyo3::exceptions::PyRuntimeError::new_err(msg.clone()));
             }
+            let available = inner.buffer.len();
+            let take = usize::try_from(n).map_or(available, |n| n.min(available));
+            let data = inner.buffer.split_to(take);
+            inner.release_spare();
+            data
+        };
+        let buffer = Bound::new(py, VeloxBuffer::from_bytes_mut(data))?;
+        Ok(PyMemoryView::from(buffer.as_any())?.into_any())
+    }
+
+    /// Fill the writable buffer `buf` with exactly `len(buf)` bytes, read
+    /// straight into it where possible. Returns the count, or a future of it
+    /// when more data has to arrive. EOF first fails with IncompleteReadError
+    /// whose `partial` is a memoryview of the bytes filled in.
+    pub fn readexactly_into(&self, py: Python<'_>, buf: &Bound<'_, PyAny>) -> PyResult<Py<PyAny>> {
+        let mut fill = ReadInto::new(buf)?;
+        let mut inner = self.inner.borrow_mut();
+        if let Some(msg) = &inner.exception {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(msg.clone()));
+        }
+        fill.fill_from(&mut inner.buffer);
+        inner.release_spare();
+        if fill.is_full() {
+            return fill.len().into_py_any(py);
+        }
+        if inner.eof {
+            return Err(fill.incomplete_error(py)?);
         }
+        let future = Py::new(py, PendingFuture::new())?;
+        inner
+            .waiters
+            .push((WaiterType::ReadInto(fill), future.clone_ref(py)));
+        Ok(future.into_any())
+    }
+
+    /// Read until a delimiter is found (async - returns a future). `separator`
+    /// is bytes or a tuple of bytes; with several, the earliest match wins
+    #[pyo3(signature = (separator=None))]
+    pub fn readuntil(
+        &self,
+        py: Python<'_>,
+        separator: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        self.readuntil_impl(py, separator, false)
+    }
+
+    /// Like `readuntil()`, but resolves with `(data, separator)` so callers
+    /// know which separator ended the data (None if EOF came first)
+    #[pyo3(signature = (separator=None))]
+    pub fn readuntil_with_separator(
+        &self,
+        py: Python<'_>,
+        separator: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        self.readuntil_impl(py, separator, true)
     }
 
     /// Read one line (until \n) - async, returns a future
@@ -304,15 +588,23 @@ impl StreamReader {
         // Try to get data immediately
         match self._try_readline(py)? {
             Some(data) => Ok(data),
-            None => {
-                // Create a pending future
-                let future = Py::new(py, PendingFuture::new())?;
-                self.inner
-                    .borrow_mut()
-                    .waiters
-                    .push((WaiterType::ReadLine, future.clone_ref(py)));
-                Ok(future.into_any())
+            None => self.wait_for_line(py, false),
+        }
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// `async for line in reader`: a future of the next line, whether or not
+    /// it's buffered yet. Iteration stops once EOF leaves nothing to read.
+    fn __anext__(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        match self._try_readline(py)? {
+            Some(line) if line.bind(py).len()? == 0 => {
+                Err(pyo3::exceptions::PyStopAsyncIteration::new_err(()))
             }
+            Some(line) => PendingFuture::resolved(py, line),
+            None => self.wait_for_line(py, true),
         }
     }
 
@@ -336,38 +628,326 @@ impl StreamReader {
     }
 }
 
+/// What a satisfied waiter's future is settled with
+enum Settle {
+    Result(Py<PyAny>),
+    Exception(Py<PyAny>),
+}
+
+impl Settle {
+    fn apply(self, py: Python<'_>, future: &Py<PendingFuture>) -> PyResult<()> {
+        let future = future.bind(py).borrow();
+        match self {
+            Settle::Result(value) => future.set_result(py, value),
+            Settle::Exception(exc) => future.set_exception(py, exc),
+        }
+    }
+}
+
+/// Settle every waiter in `settled`, skipping futures already done (cancelled
+/// while they waited). One that fails doesn't keep the rest waiting: once all
+/// are settled the failures go to `loop_`'s exception handler with `message`,
+/// or without a loop the first is returned.
+fn settle_all(
+    py: Python<'_>,
+    loop_: Option<&Py<VeloxLoop>>,
+    settled: Vec<(Py<PendingFuture>, Settle)>,
+    message: &str,
+) -> PyResult<()> {
+    let mut failed = Vec::new();
+    for (future, outcome) in settled {
+        if future.bind(py).borrow().done() {
+            continue;
+        }
+        if let Err(e) = outcome.apply(py, &future) {
+            failed.push((future, e));
+        }
+    }
+    let Some(loop_) = loop_ else {
+        return failed.into_iter().next().map_or(Ok(()), |(_, e)| Err(e));
+    };
+    let loop_ = loop_.bind(py).borrow();
+    for (future, e) in failed {
+        ExceptionContext::new(message)
+            .exception(e.value(py))
+            .with("future", future.bind(py))
+            .report(py, &loop_)?;
+    }
+    Ok(())
+}
+
+/// Waiters with done callbacks that one wakeup satisfied beyond its first
+/// `WAKEUP_BATCH`. Each call settles the next batch and queues itself again
+/// for the rest, so the loop polls in between.
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+struct DeferredWakeups {
+    loop_: Py<VeloxLoop>,
+    pending: Mutex<std::vec::IntoIter<(Py<PendingFuture>, Settle)>>,
+}
+
+impl DeferredWakeups {
+    fn schedule(
+        py: Python<'_>,
+        loop_: &Py<VeloxLoop>,
+        settled: Vec<(Py<PendingFuture>, Settle)>,
+    ) -> PyResult<()> {
+        let wakeups = Py::new(
+            py,
+            Self {
+                loop_: loop_.clone_ref(py),
+                pending: Mutex::new(settled.into_iter()),
+            },
+        )?;
+        loop_
+            .bind(py)
+            .borrow()
+            .call_soon(wakeups.into_any(), Vec::new(), None);
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl DeferredWakeups {
+    fn __call__(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.get();
+        let batch: Vec<_> = {
+            let mut pending = this.pending.lock();
+            let batch = pending.by_ref().take(WAKEUP_BATCH).collect();
+            if pending.len() > 0 {
+                this.loop_.bind(py).borrow().call_soon(
+                    slf.clone().into_any().unbind(),
+                    Vec::new(),
+                    None,
+                );
+            }
+            batch
+        };
+        for (future, outcome) in batch {
+            // Fails only for a waiter cancelled since, which wants nothing
+            let _ = outcome.apply(py, &future);
+        }
+        Ok(())
+    }
+}
+
 impl StreamReader {
-    // Helper method for readuntil logic operating on raw buffer
-    fn _try_readuntil_inner(
-        buffer: &mut BytesMut,
-        eof: bool,
-        separator: &[u8],
-    ) -> PyResult<Option<Vec<u8>>> {
-        let pos = if separator.len() == 1 {
-            memchr(separator[0], &buffer)
+    /// Reader for a transport reading `chunk_size` bytes at a time; the initial
+    /// buffer comes from the matching pool bucket instead of the 128 KB default
+    pub(crate) fn with_chunk_size(limit: Option<usize>, chunk_size: usize) -> Self {
+        Self {
+            inner: RefCell::new(StreamReaderInner::new(chunk_size)),
+            limit: limit.unwrap_or(DEFAULT_LIMIT),
+            loop_: OnceCell::new(),
+        }
+    }
+
+    /// Settle large wakeups through `loop_`, see `_wakeup_waiters`
+    pub(crate) fn link_loop(&self, loop_: Py<VeloxLoop>) {
+        let _ = self.loop_.set(loop_);
+    }
+
+    /// EOF was fed, whether or not the buffer has been read out yet
+    pub(crate) fn eof_received(&self) -> bool {
+        self.inner.borrow().eof
+    }
+
+    /// Queue a readline() waiter, resuming the immediate attempt's search
+    fn wait_for_line(&self, py: Python<'_>, iterating: bool) -> PyResult<Py<PyAny>> {
+        let future = Py::new(py, PendingFuture::new())?;
+        let mut inner = self.inner.borrow_mut();
+        let scanned = inner.scanned_for(&[b"\n"]);
+        let waiter = if iterating {
+            WaiterType::ReadLineIter { scanned }
         } else {
-            buffer
-                .windows(separator.len())
-                .position(|window| window == separator)
+            WaiterType::ReadLine { scanned }
+        };
+        inner.waiters.push((waiter, future.clone_ref(py)));
+        Ok(future.into_any())
+    }
+
+    fn readuntil_impl(
+        &self,
+        py: Python<'_>,
+        separator: Option<&Bound<'_, PyAny>>,
+        with_separator: bool,
+    ) -> PyResult<Py<PyAny>> {
+        let separators = match separator {
+            None => vec![b"\n".to_vec()],
+            Some(sep) => match sep.cast::<PyTuple>() {
+                Ok(tuple) => tuple
+                    .iter()
+                    .map(|sep| sep.extract::<Vec<u8>>())
+                    .collect::<PyResult<Vec<_>>>()?,
+                Err(_) => vec![sep.extract::<Vec<u8>>()?],
+            },
         };
+        if separators.is_empty() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Separator should contain at least one element",
+            ));
+        }
+        if separators.iter().any(|sep| sep.is_empty()) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Separator cannot be empty",
+            ));
+        }
+
+        // Try to get data immediately
+        match self.try_readuntil_separators(py, &separators, with_separator)? {
+            Some(data) => Ok(data),
+            None => {
+                // Create a pending future
+                let future = Py::new(py, PendingFuture::new())?;
+                let mut inner = self.inner.borrow_mut();
+                // Carry on from where the immediate attempt left off
+                let scanned = inner.scanned_for(&separators);
+                inner.waiters.push((
+                    WaiterType::ReadUntil {
+                        separators,
+                        with_separator,
+                        scanned,
+                    },
+                    future.clone_ref(py),
+                ));
+                Ok(future.into_any())
+            }
+        }
+    }
+
+    /// Data up to and including the earliest separator, or `(data, separator)`
+    /// when `with_separator` is set
+    fn try_readuntil_separators<S: AsRef<[u8]>>(
+        &self,
+        py: Python<'_>,
+        separators: &[S],
+        with_separator: bool,
+    ) -> PyResult<Option<Py<PyAny>>> {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(msg) = &inner.exception {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(msg.clone()));
+        }
+        if let Some((data, matched)) = inner.readuntil(separators)? {
+            inner.release_spare();
+            let bytes = PyBytes::new(py, &data);
+            let result = if with_separator {
+                let sep = matched.map(|i| PyBytes::new(py, separators[i].as_ref()));
+                (bytes, sep).into_py_any(py)?
+            } else {
+                bytes.into()
+            };
+            Ok(Some(result))
+        } else {
+            Ok(None)
+        }
+    }
 
-        if let Some(pos) = pos {
-            let end = pos + separator.len();
+    /// Data up to and including the earliest of `separators`, also returning
+    /// the index of the one that matched (None when EOF returned the rest).
+    /// The search starts at stream position `scanned` (the buffer holds
+    /// everything up to `fed`) and, when it finds nothing, moves `scanned`
+    /// as far on as a match could still start once more data arrives.
+    fn _try_readuntil_any<S: AsRef<[u8]>>(
+        buffer: &mut BytesMut,
+        eof: bool,
+        separators: &[S],
+        fed: u64,
+        scanned: &mut u64,
+    ) -> PyResult<Option<(Vec<u8>, Option<usize>)>> {
+        // Positions before the buffer were taken off its front since
+        let start = fed - buffer.len() as u64;
+        let from = usize::try_from(scanned.saturating_sub(start))
+            .map_or(buffer.len(), |from| from.min(buffer.len()));
+        if let Some((end, matched)) = Self::find_separator(buffer, separators, from) {
             let data = buffer.split_to(end).to_vec();
-            return Ok(Some(data));
+            return Ok(Some((data, Some(matched))));
         }
 
         if eof {
             if buffer.is_empty() {
-                return Ok(Some(Vec::new()));
+                return Ok(Some((Vec::new(), None)));
             }
             let data = buffer.split().to_vec();
-            return Ok(Some(data));
+            return Ok(Some((data, None)));
         }
 
+        // The last `longest - 1` bytes may begin a separator still arriving
+        let longest = separators
+            .iter()
+            .map(|sep| sep.as_ref().len())
+            .max()
+            .unwrap_or(1);
+        let resume = from.max(buffer.len().saturating_sub(longest - 1));
+        *scanned = start + resume as u64;
         Ok(None)
     }
 
+    /// End offset and index of the earliest separator match starting at
+    /// `from` or later. As in asyncio the match that ends first wins; on a
+    /// tie, the longer separator
+    fn find_separator<S: AsRef<[u8]>>(
+        buffer: &[u8],
+        separators: &[S],
+        from: usize,
+    ) -> Option<(usize, usize)> {
+        if let [separator] = separators {
+            let separator = separator.as_ref();
+            let haystack = &buffer[from..];
+            let pos = if separator.len() == 1 {
+                memchr(separator[0], haystack)
+            } else {
+                haystack
+                    .windows(separator.len())
+                    .position(|window| window == separator)
+            };
+            return pos.map(|pos| (from + pos + separator.len(), 0));
+        }
+
+        // Prefilter on the separators' first bytes, then compare in full
+        let min_len = separators.iter().map(|sep| sep.as_ref().len()).min()?;
+        let mut is_first = [false; 256];
+        let mut firsts = Vec::new();
+        for sep in separators {
+            let byte = sep.as_ref()[0];
+            if !std::mem::replace(&mut is_first[byte as usize], true) {
+                firsts.push(byte);
+            }
+        }
+        let next_candidate = |from: usize| {
+            let haystack = &buffer[from..];
+            match *firsts.as_slice() {
+                [a] => memchr(a, haystack),
+                [a, b] => memchr2(a, b, haystack),
+                [a, b, c] => memchr3(a, b, c, haystack),
+                _ => haystack.iter().position(|&byte| is_first[byte as usize]),
+            }
+            .map(|pos| from + pos)
+        };
+
+        let mut best: Option<(usize, usize)> = None;
+        let mut from = from;
+        while let Some(pos) = next_candidate(from) {
+            // Nothing starting here or later can end before the best match
+            if let Some((end, _)) = best
+                && pos + min_len >= end
+            {
+                break;
+            }
+            for (i, sep) in separators.iter().enumerate() {
+                let sep = sep.as_ref();
+                let end = pos + sep.len();
+                // Candidates come in start order, so an equal end found later
+                // belongs to a shorter separator and loses the tie
+                if buffer[pos..].starts_with(sep) && best.is_none_or(|(best_end, _)| end < best_end)
+                {
+                    best = Some((end, i));
+                }
+            }
+            from = pos + 1;
+        }
+        best
+    }
+
     // Helper for readexactly logic
     fn _try_readexactly_inner(
         buffer: &mut BytesMut,
@@ -395,23 +975,41 @@ impl StreamReader {
     pub(crate) fn read_from_socket(
         &self,
         stream: &mut std::net::TcpStream,
+        chunk_size: usize,
     ) -> std::io::Result<usize> {
-        let mut inner = self.inner.borrow_mut();
+        let mut inner_guard = self.inner.borrow_mut();
+        let inner = &mut *inner_guard;
         let mut total = 0;
 
-        // Chunk size for reading
-        const CHUNK_SIZE: usize = 131072;
+        // A readexactly_into() first in line, with nothing buffered ahead of
+        // it, gets the bytes straight in its own buffer instead of via ours
+        if inner.buffer.is_empty()
+            && let Some((WaiterType::ReadInto(fill), _)) = inner.waiters.first_mut()
+        {
+            while !fill.is_full() {
+                match stream.read(fill.remaining()) {
+                    Ok(0) => return Ok(total),
+                    Ok(n) => {
+                        fill.filled += n;
+                        total += n;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock && total > 0 => {
+                        return Ok(total);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
 
         loop {
-            // Reserve space in the buffer
-            inner.buffer.reserve(CHUNK_SIZE);
+            // Reserve one transport-configured chunk in the buffer
+            inner.buffer.reserve(chunk_size);
             let len = inner.buffer.len();
-            let cap = inner.buffer.capacity();
 
-            // BytesMut guarantees that the capacity up to `cap` is allocated and valid.
+            // BytesMut guarantees that `reserve` made `chunk_size` bytes past `len` allocated.
             // We only set the length to the actual bytes read.
             let slice = unsafe {
-                std::slice::from_raw_parts_mut(inner.buffer.as_mut_ptr().add(len), cap - len)
+                std::slice::from_raw_parts_mut(inner.buffer.as_mut_ptr().add(len), chunk_size)
             };
 
             match stream.read(slice) {
@@ -419,6 +1017,7 @@ impl StreamReader {
                 Ok(n) => {
                     total += n;
                     unsafe { inner.buffer.set_len(len + n) };
+                    inner.fed += n as u64;
                     // If we read less than we requested, it might be that the socket
                     // is drained for now or we filled the chunk.
                     if n < slice.len() {
@@ -429,6 +1028,7 @@ impl StreamReader {
                 Err(e) => return Err(e),
             }
         }
+        inner.peak = inner.peak.max(inner.buffer.len());
         Ok(total)
     }
 }
@@ -436,6 +1036,11 @@ impl StreamReader {
 /// Trait for transport to trigger write flush from StreamWriter without Python
 pub trait StreamWriterProxy: Send + Sync {
     fn trigger_write(&self, py: Python<'_>) -> PyResult<()>;
+    /// Shut down the write half once the buffered data is sent
+    fn write_eof(&self, py: Python<'_>) -> PyResult<()>;
+    fn can_write_eof(&self, py: Python<'_>) -> bool;
+    /// Close once the buffered data is sent
+    fn close(&self, py: Python<'_>) -> PyResult<()>;
 }
 
 #[pyclass(module = "veloxloop._veloxloop")]
@@ -454,12 +1059,18 @@ pub struct StreamWriter {
     pub(crate) transport: Arc<Mutex<Option<Py<PyAny>>>>,
     /// Native transport proxy for triggering writes (optimized path)
     pub(crate) proxy: Arc<Mutex<Option<Arc<dyn StreamWriterProxy>>>>,
+    /// wait_closed() futures, resolved when the transport is gone
+    close_waiters: Arc<Mutex<Vec<Py<PendingFuture>>>>,
+    /// Loop of the transport, whose exception handler hears about waiters
+    /// that failed to wake
+    loop_: OnceLock<Py<VeloxLoop>>,
 }
 
 /// Combined writer state flags to reduce lock count
 pub(crate) struct WriterFlags {
     pub closed: bool,
     pub closing: bool,
+    pub eof_written: bool,
 }
 
 #[pymethods]
@@ -468,16 +1079,26 @@ impl StreamWriter {
     #[pyo3(signature = (high_water=None, low_water=None))]
     pub fn new(high_water: Option<usize>, low_water: Option<usize>) -> Self {
         let high = high_water.unwrap_or(DEFAULT_HIGH);
-        let low = low_water.unwrap_or(DEFAULT_LOW);
+        let low = low_water.unwrap_or(if high_water.is_some() {
+            high / 4
+        } else {
+            DEFAULT_LOW
+        });
 
         Self {
             buffer: Arc::new(Mutex::new(BytesMut::with_capacity(high))),
-            flags: Arc::new(Mutex::new(WriterFlags { closed: false, closing: false })),
+            flags: Arc::new(Mutex::new(WriterFlags {
+                closed: false,
+                closing: false,
+                eof_written: false,
+            })),
             high_water: high,
             low_water: low,
             drain_waiters: Arc::new(Mutex::new(Vec::new())),
             transport: Arc::new(Mutex::new(None)),
             proxy: Arc::new(Mutex::new(None)),
+            close_waiters: Arc::new(Mutex::new(Vec::new())),
+            loop_: OnceLock::new(),
         }
     }
 
@@ -486,74 +1107,128 @@ impl StreamWriter {
         *self.transport.lock() = Some(transport);
     }
 
-    /// Write data to the buffer and trigger transport write
-    pub fn write(&self, py: Python<'_>, data: &[u8]) -> PyResult<()> {
-        {
-            let flags = self.flags.lock();
-            if flags.closed {
-                return Err(pyo3::exceptions::PyRuntimeError::new_err(
-                    "Writer is closed",
-                ));
-            }
-            if flags.closing {
-                return Err(pyo3::exceptions::PyRuntimeError::new_err(
-                    "Writer is closing",
-                ));
-            }
-        }
-
-        // Add data to buffer
-        let mut buffer = self.buffer.lock();
-        buffer.extend_from_slice(data);
-        drop(buffer);
+    /// The transport this writer feeds, None until one is attached
+    #[getter]
+    pub fn transport(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.transport.lock().as_ref().map(|t| t.clone_ref(py))
+    }
 
-        // Trigger transport to write
-        if let Some(proxy) = self.proxy.lock().as_ref() {
-            proxy.trigger_write(py)?;
-        } else if let Some(transport) = self.transport.lock().as_ref() {
-            transport.call_method1(py, "_trigger_write", ())?;
+    /// `get_extra_info()` of the transport, `default` until one is attached
+    #[pyo3(signature = (name, default=None))]
+    pub fn get_extra_info(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        match self.transport(py) {
+            Some(transport) => transport.call_method1(py, "get_extra_info", (name, default)),
+            None => Ok(default.unwrap_or_else(|| py.None())),
         }
+    }
 
-        Ok(())
+    /// Write any bytes-like object to the buffer and trigger transport write
+    pub fn write(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.check_writable()?;
+        let view = contiguous_buffer(data)?;
+        self.buffer
+            .lock()
+            .extend_from_slice(crate::callbacks::buffer_bytes(&view));
+        self.trigger_transport(py)
     }
 
     /// Wait for the write buffer to drain below the low water mark
     pub fn drain(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        // If already below low water mark, return completed future
+        // If already below low water mark, return a resolved future
         if self.is_drained() {
-            let fut = crate::transports::future::CompletedFuture::new(py.None());
-            return Ok(Py::new(py, fut)?.into_any());
+            return PendingFuture::resolved(py, py.None());
         }
 
-        // Create a pending future
+        // Create a pending future, dropped from the waiters if cancelled
         let future = Py::new(py, PendingFuture::new())?;
+        let forget = DrainWaiterDone {
+            waiters: self.drain_waiters.clone(),
+            future: future.as_ptr() as usize,
+        };
+        future
+            .bind(py)
+            .borrow()
+            .add_done_callback(py, Py::new(py, forget)?.into_any())?;
         self.drain_waiters.lock().push(future.clone_ref(py));
         Ok(future.into_any())
     }
 
     /// Internal method to wake up drain waiters when buffer is drained
     pub fn _wakeup_drain_waiters(&self, py: Python<'_>) -> PyResult<()> {
-        if self.is_drained() {
-            let mut waiters = self.drain_waiters.lock();
-            for future in waiters.drain(..) {
-                future.bind(py).borrow().set_result(py, py.None())?;
+        if !self.is_drained() {
+            return Ok(());
+        }
+        // Taken out first: settling runs DrainWaiterDone, which locks the list
+        let waiters = std::mem::take(&mut *self.drain_waiters.lock());
+        let settled = waiters
+            .into_iter()
+            .map(|future| (future, Settle::Result(py.None())))
+            .collect();
+        settle_all(
+            py,
+            self.loop_.get(),
+            settled,
+            "Exception while waking drain() waiters",
+        )
+    }
+
+    /// Write every buffer from an iterable. The whole batch lands in the buffer
+    /// under one lock and the transport is triggered once, so `needs_drain()`
+    /// and `drain()` right after already see its full size
+    pub fn writelines(&self, py: Python<'_>, lines: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.check_writable()?;
+        let mut views = Vec::new();
+        let mut total = 0;
+        for line in lines.try_iter()? {
+            let view = PyBuffer::<u8>::get(&line?)?;
+            if !view.is_c_contiguous() {
+                return Err(PyErr::new::<pyo3::exceptions::PyBufferError, _>(
+                    "Only contiguous buffers are supported",
+                ));
             }
+            total += view.len_bytes();
+            views.push(view);
         }
-        Ok(())
+
+        {
+            let mut buffer = self.buffer.lock();
+            buffer.reserve(total);
+            for view in &views {
+                let ptr = view.buf_ptr() as *const u8;
+                buffer.extend_from_slice(unsafe {
+                    std::slice::from_raw_parts(ptr, view.len_bytes())
+                });
+            }
+        }
+        self.trigger_transport(py)
     }
 
-    /// Write multiple lines
-    pub fn writelines(&self, py: Python<'_>, lines: Vec<Vec<u8>>) -> PyResult<()> {
-        for line in lines {
-            self.write(py, &line)?;
+    /// Stop accepting writes and close the transport once the buffered data
+    /// is sent; `wait_closed()` resolves when it is gone
+    pub fn close(&self, py: Python<'_>) -> PyResult<()> {
+        self.mark_closing();
+        let proxy = self.proxy.lock().clone();
+        if let Some(proxy) = proxy {
+            proxy.close(py)?;
+        } else if let Some(transport) = self.transport(py) {
+            transport.call_method0(py, "close")?;
         }
         Ok(())
     }
 
-    /// Mark the writer as closing
-    pub fn close(&self) -> PyResult<()> {
-        self.flags.lock().closing = true;
-        Ok(())
+    /// Wait until the transport has closed
+    pub fn wait_closed(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        if self.flags.lock().closed {
+            return PendingFuture::resolved(py, py.None());
+        }
+        let future = Py::new(py, PendingFuture::new())?;
+        self.close_waiters.lock().push(future.clone_ref(py));
+        Ok(future.into_any())
     }
 
     /// Check if transport is closing
@@ -583,20 +1258,40 @@ impl StreamWriter {
         self.buffer.lock().len() <= self.low_water
     }
 
-    /// Check if can write EOF
-    pub fn can_write_eof(&self) -> bool {
-        !self.flags.lock().closed
+    /// Check if can write EOF (asks the transport once attached)
+    pub fn can_write_eof(&self, py: Python<'_>) -> PyResult<bool> {
+        {
+            let f = self.flags.lock();
+            if f.closed || f.eof_written {
+                return Ok(false);
+            }
+        }
+        if let Some(proxy) = self.proxy.lock().as_ref() {
+            Ok(proxy.can_write_eof(py))
+        } else if let Some(transport) = self.transport.lock().as_ref() {
+            transport.call_method0(py, "can_write_eof")?.extract(py)
+        } else {
+            Ok(true)
+        }
     }
 
-    /// Write EOF (mark as closed)
-    pub fn write_eof(&self) -> PyResult<()> {
-        let mut f = self.flags.lock();
-        if f.closed {
-            return Err(pyo3::exceptions::PyRuntimeError::new_err("Already closed"));
+    /// Write EOF: no more writes are accepted, and the transport shuts down its
+    /// write half after flushing. Reading keeps working.
+    pub fn write_eof(&self, py: Python<'_>) -> PyResult<()> {
+        {
+            let mut f = self.flags.lock();
+            if f.closed || f.eof_written {
+                return Err(pyo3::exceptions::PyRuntimeError::new_err("Already closed"));
+            }
+            f.eof_written = true;
+            f.closing = true;
         }
 
-        f.closed = true;
-        f.closing = true;
+        if let Some(proxy) = self.proxy.lock().as_ref() {
+            proxy.write_eof(py)?;
+        } else if let Some(transport) = self.transport.lock().as_ref() {
+            transport.call_method0(py, "write_eof")?;
+        }
         Ok(())
     }
 
@@ -620,6 +1315,49 @@ impl StreamWriter {
     }
 }
 
+/// The export of a bytes-like object, refused when not contiguous
+fn contiguous_buffer(data: &Bound<'_, PyAny>) -> PyResult<PyBuffer<u8>> {
+    let view = PyBuffer::<u8>::get(data)?;
+    if !view.is_c_contiguous() {
+        return Err(PyErr::new::<pyo3::exceptions::PyBufferError, _>(
+            "Only contiguous buffers are supported",
+        ));
+    }
+    Ok(view)
+}
+
+impl StreamWriter {
+    fn check_writable(&self) -> PyResult<()> {
+        let flags = self.flags.lock();
+        if flags.eof_written {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(
+                "Cannot write after write_eof",
+            ));
+        }
+        if flags.closed {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(
+                "Writer is closed",
+            ));
+        }
+        if flags.closing {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err(
+                "Writer is closing",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Have the transport send what was just buffered
+    fn trigger_transport(&self, py: Python<'_>) -> PyResult<()> {
+        if let Some(proxy) = self.proxy.lock().as_ref() {
+            proxy.trigger_write(py)?;
+        } else if let Some(transport) = self.transport.lock().as_ref() {
+            transport.call_method1(py, "_trigger_write", ())?;
+        }
+        Ok(())
+    }
+}
+
 #[pyclass(module = "veloxloop._veloxloop")]
 pub struct VeloxBuffer {
     data: Option<BytesMut>,
@@ -795,8 +1533,53 @@ impl StreamWriter {
         *self.proxy.lock() = Some(proxy);
     }
 
+    /// Refuse further writes; the transport's own close() marks its writer
+    /// this way
+    pub(crate) fn mark_closing(&self) {
+        self.flags.lock().closing = true;
+    }
+
+    /// Report waiters that fail to wake to `loop_`'s exception handler
+    pub(crate) fn link_loop(&self, loop_: Py<VeloxLoop>) {
+        let _ = self.loop_.set(loop_);
+    }
+
+    /// The transport is gone: resolve wait_closed() futures
+    pub(crate) fn mark_closed(&self, py: Python<'_>) -> PyResult<()> {
+        self.flags.lock().closed = true;
+        let waiters = std::mem::take(&mut *self.close_waiters.lock());
+        let settled = waiters
+            .into_iter()
+            .map(|future| (future, Settle::Result(py.None())))
+            .collect();
+        settle_all(
+            py,
+            self.loop_.get(),
+            settled,
+            "Exception while waking wait_closed() waiters",
+        )
+    }
+
     /// Get the buffer Arc for sharing with transport (Rust-only method)
     pub(crate) fn get_buffer_arc(&self) -> Arc<Mutex<BytesMut>> {
         self.buffer.clone()
     }
 }
+
+/// Done callback of a drain() future: drops it from the writer's waiters, so
+/// one cancelled while it waited doesn't stay until the buffer drains
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+struct DrainWaiterDone {
+    waiters: Arc<Mutex<Vec<Py<PendingFuture>>>>,
+    /// Identity of the future; holding a reference would make a cycle
+    future: usize,
+}
+
+#[pymethods]
+impl DrainWaiterDone {
+    fn __call__(&self, _result: &Bound<'_, PyAny>) {
+        self.waiters
+            .lock()
+            .retain(|waiter| waiter.as_ptr() as usize != self.future);
+    }
+}
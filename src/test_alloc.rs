@@ -0,0 +1,59 @@
+//! Allocator for the unit tests: the one the extension uses, plus a count of
+//! the allocations each thread makes, so a test can check a hot path
+//! allocates nothing whatever the tests running beside it do.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+
+#[cfg(not(any(
+    target_env = "musl",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "windows"
+)))]
+const INNER: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+#[cfg(any(
+    target_env = "musl",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "windows"
+))]
+const INNER: std::alloc::System = std::alloc::System;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Allocations and reallocations made by this thread so far
+pub fn allocations() -> u64 {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[inline]
+fn count() {
+    // Gone while the thread is being torn down
+    let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+}
+
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { INNER.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { INNER.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        unsafe { INNER.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { INNER.dealloc(ptr, layout) }
+    }
+}
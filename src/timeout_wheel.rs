@@ -0,0 +1,213 @@
+//! Coarse timing wheel behind transport idle timeouts (`set_timeouts`)
+//!
+//! Deadlines are whole ticks kept in a fixed ring of slots, no heap and no
+//! cascade between levels: a deadline more than a lap away simply stays in
+//! its slot until the lap comes round. Activity on a transport never touches
+//! the wheel; when an entry comes due its owner checks when it was last
+//! active and either goes or is scheduled again for the new deadline.
+
+use slab::Slab;
+
+use crate::constants::IDLE_TIMEOUT_SLOTS;
+
+pub type WheelKey = usize;
+
+/// `WheelEntry::tick` of an entry handed out by `advance` and not yet rescheduled
+const DUE: u64 = u64::MAX;
+
+struct WheelEntry<T> {
+    owner: T,
+    /// Tick whose slot holds the entry
+    tick: u64,
+}
+
+pub struct TimeoutWheel<T> {
+    slots: Vec<Vec<WheelKey>>,
+    entries: Slab<WheelEntry<T>>,
+    /// Last tick `advance` reached
+    now: u64,
+}
+
+impl<T> Default for TimeoutWheel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline]
+fn slot_of(tick: u64) -> usize {
+    (tick % IDLE_TIMEOUT_SLOTS as u64) as usize
+}
+
+impl<T> TimeoutWheel<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: (0..IDLE_TIMEOUT_SLOTS).map(|_| Vec::new()).collect(),
+            entries: Slab::new(),
+            now: 0,
+        }
+    }
+
+    /// Nothing armed; the loop skips the wheel altogether then
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: WheelKey) -> Option<&T> {
+        self.entries.get(key).map(|entry| &entry.owner)
+    }
+
+    /// Arm `owner` for tick `deadline`. `now` catches up an empty wheel,
+    /// which the loop stops advancing.
+    pub fn insert(&mut self, owner: T, deadline: u64, now: u64) -> WheelKey {
+        if self.entries.is_empty() {
+            self.now = self.now.max(now);
+        }
+        let key = self.entries.insert(WheelEntry { owner, tick: DUE });
+        self.schedule(key, deadline);
+        key
+    }
+
+    /// Move `key` to tick `deadline`, the next tick at the earliest.
+    /// Its old slot keeps a stale reference that `advance` drops.
+    pub fn schedule(&mut self, key: WheelKey, deadline: u64) -> bool {
+        let tick = deadline.max(self.now + 1);
+        let Some(entry) = self.entries.get_mut(key) else {
+            return false;
+        };
+        entry.tick = tick;
+        self.slots[slot_of(tick)].push(key);
+        true
+    }
+
+    pub fn remove(&mut self, key: WheelKey) -> Option<T> {
+        self.entries.try_remove(key).map(|entry| entry.owner)
+    }
+
+    /// Keys due by tick `now`. They stay armed, out of every slot, until the
+    /// caller schedules or removes them.
+    pub fn advance(&mut self, now: u64) -> Vec<WheelKey> {
+        let mut due = Vec::new();
+        if now <= self.now {
+            return due;
+        }
+        // A lap or more behind, every slot is visited once
+        let last = now.min(self.now + IDLE_TIMEOUT_SLOTS as u64);
+        let entries = &mut self.entries;
+        for tick in self.now + 1..=last {
+            let slot = slot_of(tick);
+            self.slots[slot].retain(|&key| match entries.get_mut(key) {
+                // Anything else is stale: rescheduled elsewhere, removed, or
+                // already handed out through an earlier reference
+                Some(entry) if entry.tick != DUE && slot_of(entry.tick) == slot => {
+                    if entry.tick > now {
+                        return true;
+                    }
+                    entry.tick = DUE;
+                    due.push(key);
+                    false
+                }
+                _ => false,
+            });
+        }
+        self.now = now;
+        due
+    }
+
+    /// The first tick after the current one whose slot holds anything, so
+    /// the loop knows how long it may sleep. Entries a lap ahead or stale
+    /// references only cause an early wakeup.
+    pub fn next_due(&self) -> Option<u64> {
+        (self.now + 1..=self.now + IDLE_TIMEOUT_SLOTS as u64)
+            .find(|&tick| !self.slots[slot_of(tick)].is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAP: u64 = IDLE_TIMEOUT_SLOTS as u64;
+
+    fn owners(wheel: &mut TimeoutWheel<u64>, now: u64) -> Vec<u64> {
+        let mut due: Vec<u64> = wheel
+            .advance(now)
+            .into_iter()
+            .map(|key| wheel.remove(key).unwrap())
+            .collect();
+        due.sort_unstable();
+        due
+    }
+
+    #[test]
+    fn due_at_deadline_not_before() {
+        let mut wheel = TimeoutWheel::new();
+        wheel.insert(3, 3, 0);
+        wheel.insert(5, 5, 0);
+        assert_eq!(wheel.next_due(), Some(3));
+        assert!(owners(&mut wheel, 2).is_empty());
+        assert_eq!(owners(&mut wheel, 4), vec![3]);
+        assert_eq!(owners(&mut wheel, 5), vec![5]);
+        assert!(wheel.is_empty());
+        assert_eq!(wheel.next_due(), None);
+    }
+
+    #[test]
+    fn rescheduled_entry_comes_due_again() {
+        let mut wheel = TimeoutWheel::new();
+        let key = wheel.insert(1, 2, 0);
+        assert_eq!(wheel.advance(2), vec![key]);
+        // Handed out entries wait for the caller's decision
+        assert!(wheel.advance(10).is_empty());
+        assert!(wheel.schedule(key, 15));
+        assert!(wheel.advance(14).is_empty());
+        assert_eq!(wheel.advance(15), vec![key]);
+        // A deadline already passed goes to the next tick
+        assert!(wheel.schedule(key, 3));
+        assert_eq!(wheel.advance(16), vec![key]);
+    }
+
+    #[test]
+    fn moved_entry_leaves_stale_reference_behind() {
+        let mut wheel = TimeoutWheel::new();
+        let key = wheel.insert(1, 4, 0);
+        assert!(wheel.schedule(key, 9));
+        assert!(wheel.advance(8).is_empty());
+        assert_eq!(wheel.advance(9), vec![key]);
+    }
+
+    #[test]
+    fn removed_key_reused_keeps_its_own_deadline() {
+        let mut wheel = TimeoutWheel::new();
+        let key = wheel.insert(1, 4, 0);
+        wheel.remove(key);
+        assert_eq!(wheel.insert(2, 4 + LAP, 0), key);
+        assert!(owners(&mut wheel, 4 + LAP - 1).is_empty());
+        assert_eq!(owners(&mut wheel, 4 + LAP), vec![2]);
+    }
+
+    #[test]
+    fn deadline_beyond_a_lap_waits_for_its_lap() {
+        let mut wheel = TimeoutWheel::new();
+        wheel.insert(1, 3 * LAP + 7, 0);
+        for now in [7, LAP + 7, 2 * LAP + 7, 3 * LAP + 6] {
+            assert!(owners(&mut wheel, now).is_empty());
+        }
+        assert_eq!(owners(&mut wheel, 3 * LAP + 7), vec![1]);
+    }
+
+    #[test]
+    fn catches_up_after_long_stretches() {
+        let mut wheel = TimeoutWheel::new();
+        for deadline in [1, LAP / 2, 10 * LAP] {
+            wheel.insert(deadline, deadline, 0);
+        }
+        assert_eq!(owners(&mut wheel, 5 * LAP), vec![1, LAP / 2]);
+        assert_eq!(owners(&mut wheel, 100 * LAP), vec![10 * LAP]);
+
+        // An empty wheel isn't advanced; the next insert brings it up to date
+        wheel.insert(7, 1000 * LAP + 2, 1000 * LAP);
+        assert!(owners(&mut wheel, 1000 * LAP + 1).is_empty());
+        assert_eq!(owners(&mut wheel, 1000 * LAP + 2), vec![7]);
+    }
+}
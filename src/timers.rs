@@ -7,10 +7,12 @@ use crate::constants::{PRECISION_NS, WHEEL_BITS, WHEEL_MASK, WHEEL_SIZE, WHEELS}
 /// Timer entry key for slab storage
 pub type TimerKey = usize;
 
-pub struct TimerEntry {
+/// `C` is the callback type; the loop stores Python objects, the Rust-only
+/// bench driver and unit tests use plain integers
+pub struct TimerEntry<C = pyo3::Py<pyo3::PyAny>> {
     pub expires_at: u64, // absolute ns
-    pub callback: pyo3::Py<pyo3::PyAny>,
-    pub args: Vec<pyo3::Py<pyo3::PyAny>>,
+    pub callback: C,
+    pub args: Vec<C>,
 }
 
 /// Slot entry storing timer ID and its slab key for efficient lookup
@@ -20,11 +22,11 @@ struct SlotEntry {
     slab_key: TimerKey,
 }
 
-pub struct Timers {
+pub struct Timers<C = pyo3::Py<pyo3::PyAny>> {
     /// Wheels storing list of timer slot entries
     wheels: [Vec<Vec<SlotEntry>>; WHEELS],
     /// Pre-allocated storage for timer entries using slab
-    entries: Slab<TimerEntry>,
+    entries: Slab<TimerEntry<C>>,
     /// Fast ID to slab key lookup (for cancel operations)
     id_to_key: rustc_hash::FxHashMap<u64, TimerKey>,
     /// Current time in milliseconds (relative to start_time)
@@ -37,7 +39,13 @@ pub struct Timers {
     heap: BinaryHeap<Reverse<(u64, TimerKey)>>
 }
 
-impl Timers {
+impl<C> Default for Timers<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Timers<C> {
     pub fn new() -> Self {
         let mut wheels = [(); WHEELS].map(|_| Vec::with_capacity(WHEEL_SIZE));
         for w in &mut wheels {
@@ -60,8 +68,8 @@ impl Timers {
     pub fn insert(
         &mut self,
         expires_at_ns: u64,
-        callback: pyo3::Py<pyo3::PyAny>,
-        args: Vec<pyo3::Py<pyo3::PyAny>>,
+        callback: C,
+        args: Vec<C>,
         _context: Option<pyo3::Py<pyo3::PyAny>>,
         start_ns: u64,
     ) -> u64 {
@@ -84,10 +92,11 @@ impl Timers {
         let expiry_ms = (expires_at_ns.saturating_sub(start_ns)) / PRECISION_NS;
         self.cascade_timer(id, slab_key, expiry_ms);
         
-        // Update cache if this is earlier
+        // Update cache if this is earlier. An empty cache may only mean it
+        // was invalidated, so it's filled in here just for the sole timer
         match self.min_expiry_cache {
             Some(min) if expires_at_ns < min => self.min_expiry_cache = Some(expires_at_ns),
-            None => self.min_expiry_cache = Some(expires_at_ns),
+            None if self.entries.len() == 1 => self.min_expiry_cache = Some(expires_at_ns),
             _ => {}
         }
         self.heap.push(Reverse((expires_at_ns, slab_key)));
@@ -126,6 +135,22 @@ impl Timers {
         false
     }
 
+    /// No timer pending; cancelled ones are gone from `entries` right away
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Timers pending
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Deadline of a pending timer
+    pub fn deadline(&self, id: u64) -> Option<u64> {
+        let key = self.id_to_key.get(&id)?;
+        self.entries.get(*key).map(|entry| entry.expires_at)
+    }
+
     pub fn next_expiry(&mut self) -> Option<u64> {
         if self.min_expiry_cache.is_none() {
             self.recompute_min_expiry();
@@ -144,25 +169,36 @@ impl Timers {
         self.min_expiry_cache = None;
     }
 
-    /// Pop all expired timers up to current_ns
+    /// Pop all timers due at `current_ns`. Slots of whole milliseconds that
+    /// have passed are emptied; the current millisecond's slot only gives up
+    /// the timers whose exact deadline has been reached, so none fires early.
     pub fn pop_expired(
         &mut self,
         current_ns: u64,
         start_ns: u64,
-    ) -> Vec<TimerEntry> {
+    ) -> Vec<TimerEntry<C>> {
         let target_ms = (current_ns.saturating_sub(start_ns)) / PRECISION_NS;
         let mut expired = Vec::new();
 
-        while self.current_ms <= target_ms {
+        while self.current_ms < target_ms {
+            if target_ms - self.current_ms > WHEEL_SIZE as u64 {
+                self.skip_idle(target_ms, start_ns);
+                if self.current_ms >= target_ms {
+                    break;
+                }
+            }
             let slot = (self.current_ms & WHEEL_MASK as u64) as usize;
 
             // Collect expired timers from wheel 0
+            let before = expired.len();
             for slot_entry in std::mem::take(&mut self.wheels[0][slot]) {
-                if let Some(entry) = self.entries.try_remove(slot_entry.slab_key) {
-                    self.id_to_key.remove(&slot_entry.id);
+                if let Some(entry) = self.take_entry(slot_entry) {
                     expired.push(entry);
                 }
             }
+            if expired.len() > before {
+                self.min_expiry_cache = None;
+            }
 
             self.current_ms += 1;
 
@@ -178,6 +214,22 @@ impl Timers {
             }
         }
 
+        let slot = (self.current_ms & WHEEL_MASK as u64) as usize;
+        let mut current = std::mem::take(&mut self.wheels[0][slot]);
+        current.retain(|slot_entry| {
+            if !self.is_live(*slot_entry) {
+                return false;
+            }
+            if self.entries[slot_entry.slab_key].expires_at > current_ns {
+                return true;
+            }
+            if let Some(entry) = self.take_entry(*slot_entry) {
+                expired.push(entry);
+            }
+            false
+        });
+        self.wheels[0][slot] = current;
+
         // Invalidate cache if any timers expired
         if !expired.is_empty() {
             self.min_expiry_cache = None;
@@ -186,6 +238,44 @@ impl Timers {
         expired
     }
 
+    /// Jump `current_ms` towards `target_ms` when no timer is due for more
+    /// than a wheel's worth of milliseconds, re-slotting the live timers from
+    /// there. A long idle stretch (or a virtual clock moved by days) would
+    /// otherwise be walked one millisecond at a time.
+    fn skip_idle(&mut self, target_ms: u64, start_ns: u64) {
+        let next_ms = self
+            .next_expiry()
+            .map_or(target_ms, |ns| ns.saturating_sub(start_ns) / PRECISION_NS);
+        let skip_to = next_ms.min(target_ms);
+        if skip_to <= self.current_ms + WHEEL_SIZE as u64 {
+            return;
+        }
+        let mut live = Vec::new();
+        for wheel in &mut self.wheels {
+            for slot in wheel.iter_mut() {
+                live.append(slot);
+            }
+        }
+        self.current_ms = skip_to;
+        for slot_entry in live {
+            self.re_cascade(slot_entry.id, slot_entry.slab_key, start_ns);
+        }
+    }
+
+    /// Whether a slot entry still refers to its timer; a cancelled timer's
+    /// slab key can be reused by a later one before the slot is reached
+    fn is_live(&self, slot_entry: SlotEntry) -> bool {
+        self.id_to_key.get(&slot_entry.id) == Some(&slot_entry.slab_key)
+    }
+
+    fn take_entry(&mut self, slot_entry: SlotEntry) -> Option<TimerEntry<C>> {
+        if !self.is_live(slot_entry) {
+            return None;
+        }
+        self.id_to_key.remove(&slot_entry.id);
+        self.entries.try_remove(slot_entry.slab_key)
+    }
+
     fn cascade_down(&mut self, wheel: u32, start_ns: u64) {
         let slot = ((self.current_ms >> (wheel * WHEEL_BITS)) & WHEEL_MASK as u64) as usize;
         
@@ -195,9 +285,143 @@ impl Timers {
     }
 
     fn re_cascade(&mut self, id: u64, slab_key: TimerKey, start_ns: u64) {
+        if !self.is_live(SlotEntry { id, slab_key }) {
+            return;
+        }
         if let Some(entry) = self.entries.get(slab_key) {
             let expiry_ms = (entry.expires_at.saturating_sub(start_ns)) / PRECISION_NS;
             self.cascade_timer(id, slab_key, expiry_ms);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = PRECISION_NS;
+
+    fn insert(timers: &mut Timers<u64>, ms: u64) -> u64 {
+        timers.insert(ms * MS, ms, Vec::new(), None, 0)
+    }
+
+    fn popped(timers: &mut Timers<u64>, ms: u64) -> Vec<u64> {
+        let mut expired: Vec<u64> = timers
+            .pop_expired(ms * MS, 0)
+            .into_iter()
+            .map(|e| e.callback)
+            .collect();
+        expired.sort_unstable();
+        expired
+    }
+
+    #[test]
+    fn pops_across_wheel_levels() {
+        let mut timers = Timers::new();
+        for ms in [70_000, 5, 1, 300] {
+            insert(&mut timers, ms);
+        }
+        assert_eq!(timers.next_expiry(), Some(MS));
+        assert_eq!(popped(&mut timers, 2), vec![1]);
+        assert_eq!(timers.next_expiry(), Some(5 * MS));
+        assert_eq!(popped(&mut timers, 400), vec![5, 300]);
+        assert_eq!(popped(&mut timers, 69_999), Vec::<u64>::new());
+        assert_eq!(popped(&mut timers, 70_000), vec![70_000]);
+        assert_eq!(timers.next_expiry(), None);
+    }
+
+    #[test]
+    fn cancelled_timers_never_fire() {
+        let mut timers = Timers::new();
+        let first = insert(&mut timers, 1);
+        insert(&mut timers, 2);
+        assert!(timers.cancel(first));
+        assert!(!timers.cancel(first));
+        assert_eq!(timers.next_expiry(), Some(2 * MS));
+        assert_eq!(popped(&mut timers, 10), vec![2]);
+    }
+
+    #[test]
+    fn never_fires_before_deadline() {
+        let mut timers = Timers::new();
+        timers.insert(5 * MS + 700, 1, Vec::new(), None, 0);
+        assert!(timers.pop_expired(5 * MS + 699, 0).is_empty());
+        assert_eq!(timers.deadline(1), Some(5 * MS + 700));
+        assert_eq!(timers.pop_expired(5 * MS + 700, 0).len(), 1);
+        assert_eq!(timers.deadline(1), None);
+
+        // Past-due timers in the same millisecond still go right away
+        timers.insert(5 * MS, 2, Vec::new(), None, 0);
+        assert_eq!(timers.pop_expired(5 * MS + 800, 0).len(), 1);
+    }
+
+    #[test]
+    fn insert_after_pop_keeps_earlier_deadline() {
+        // Popping invalidates the cache; a later insert mustn't take the
+        // invalidated cache for "no timers" and hide the 25ms one
+        let mut timers = Timers::new();
+        insert(&mut timers, 0);
+        insert(&mut timers, 25);
+        assert_eq!(popped(&mut timers, 0), vec![0]);
+        insert(&mut timers, 2000);
+        assert_eq!(timers.next_expiry(), Some(25 * MS));
+        assert_eq!(popped(&mut timers, 25), vec![25]);
+    }
+
+    #[test]
+    fn reused_slab_key_keeps_its_own_slot() {
+        let mut timers = Timers::new();
+        let early = insert(&mut timers, 3);
+        assert!(timers.cancel(early));
+        // Takes over the cancelled timer's slab key
+        insert(&mut timers, 500);
+        assert!(popped(&mut timers, 10).is_empty());
+        assert_eq!(popped(&mut timers, 500), vec![500]);
+    }
+
+    #[test]
+    fn skips_long_idle_stretches() {
+        const DAY: u64 = 86_400_000;
+        let mut timers = Timers::new();
+        for ms in [3, DAY, DAY + 1, 7 * DAY] {
+            insert(&mut timers, ms);
+        }
+        assert_eq!(popped(&mut timers, 10), vec![3]);
+        assert_eq!(popped(&mut timers, DAY - 1), Vec::<u64>::new());
+        // Inserted after the jump, relative to the new position
+        insert(&mut timers, DAY + 300);
+        assert_eq!(popped(&mut timers, 2 * DAY), vec![DAY, DAY + 1, DAY + 300]);
+        assert_eq!(timers.next_expiry(), Some(7 * DAY * MS));
+        assert_eq!(popped(&mut timers, 30 * DAY), vec![7 * DAY]);
+        assert_eq!(timers.next_expiry(), None);
+    }
+
+    #[test]
+    fn inserts_after_unpolled_idle_stretch() {
+        // The loop doesn't pop while no timer is pending, so the wheel can be
+        // behind the clock by any amount when the next one is inserted
+        let mut timers = Timers::new();
+        assert!(timers.is_empty());
+        insert(&mut timers, 2);
+        assert_eq!(popped(&mut timers, 10), vec![2]);
+        assert!(timers.is_empty());
+        for now in [200, 70_000] {
+            insert(&mut timers, now + 3);
+            assert!(!timers.is_empty());
+            assert!(popped(&mut timers, now + 2).is_empty());
+            assert_eq!(popped(&mut timers, now + 3), vec![now + 3]);
+            assert!(timers.is_empty());
+        }
+    }
+
+    #[test]
+    fn many_timers_pop_once() {
+        let mut timers = Timers::new();
+        let ids: Vec<u64> = (0..10_000).map(|i| insert(&mut timers, i % 1000)).collect();
+        for id in ids.iter().step_by(2) {
+            assert!(timers.cancel(*id));
+        }
+        assert_eq!(timers.pop_expired(1000 * MS, 0).len(), 5_000);
+        assert!(timers.pop_expired(2000 * MS, 0).is_empty());
+    }
+}
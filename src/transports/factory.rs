@@ -0,0 +1,328 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+
+use super::tcp::TcpTransport;
+use super::{DefaultTransportFactory, TransportFactory, stats, write_buffer_limits};
+use crate::buffer_pool::check_read_chunk_size;
+use crate::event_loop::VeloxLoop;
+use crate::socket::KeepaliveParams;
+use crate::utils::ipv6::socket_addr_to_tuple;
+
+/// Tweaks to the native transports the loop creates, installed with
+/// `loop.set_transport_factory(TransportFactoryConfig(...))`
+#[pyclass(module = "veloxloop._veloxloop", frozen)]
+pub struct TransportFactoryConfig {
+    #[pyo3(get)]
+    pub(crate) nodelay: bool,
+    pub(crate) keepalive: Option<KeepaliveParams>,
+    #[pyo3(get)]
+    pub(crate) read_chunk_size: Option<usize>,
+    #[pyo3(get)]
+    pub(crate) write_buffer_size: usize,
+    /// Resolved (high, low) water marks, or None for the transport defaults
+    pub(crate) write_buffer_limits: Option<(usize, usize)>,
+}
+
+#[pymethods]
+impl TransportFactoryConfig {
+    #[new]
+    #[pyo3(signature = (*, nodelay=true, keepalive=None, read_chunk_size=None, write_buffer_size=65536, write_buffer_high=None, write_buffer_low=None))]
+    fn new(
+        nodelay: bool,
+        keepalive: Option<&Bound<'_, PyAny>>,
+        read_chunk_size: Option<usize>,
+        write_buffer_size: usize,
+        write_buffer_high: Option<isize>,
+        write_buffer_low: Option<isize>,
+    ) -> PyResult<Self> {
+        let write_buffer_limits = if write_buffer_high.is_some() || write_buffer_low.is_some() {
+            Some(write_buffer_limits(write_buffer_high, write_buffer_low)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            nodelay,
+            keepalive: KeepaliveParams::from_py(keepalive)?,
+            read_chunk_size: read_chunk_size.map(check_read_chunk_size).transpose()?,
+            write_buffer_size,
+            write_buffer_limits,
+        })
+    }
+
+    /// The native transport for `sock_fd`, which it takes ownership of. Lets a
+    /// Python factory wrap the transport the loop would have created
+    #[pyo3(signature = (kind, r#loop, sock_fd, protocol, extra=None))]
+    fn __call__(
+        &self,
+        py: Python<'_>,
+        kind: &str,
+        r#loop: Py<PyAny>,
+        sock_fd: RawFd,
+        protocol: Py<PyAny>,
+        extra: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        // The socket itself tells the native transports everything in `extra`
+        let _ = extra;
+        if sock_fd < 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "invalid file descriptor: {}",
+                sock_fd
+            )));
+        }
+        match kind {
+            "tcp" => {
+                let stream = unsafe { TcpStream::from_raw_fd(sock_fd) };
+                self.create_tcp(py, r#loop, stream, protocol)
+            }
+            "udp" => {
+                let socket = unsafe { UdpSocket::from_raw_fd(sock_fd) };
+                let remote = socket.peer_addr().ok();
+                self.create_udp(py, r#loop, socket, protocol, remote, false)
+            }
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "unknown transport kind: {:?}",
+                kind
+            ))),
+        }
+    }
+
+    #[getter]
+    fn write_buffer_limits(&self) -> Option<(usize, usize)> {
+        self.write_buffer_limits.map(|(high, low)| (low, high))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<TransportFactoryConfig nodelay={} keepalive={} read_chunk_size={:?} write_buffer_size={}>",
+            self.nodelay,
+            self.keepalive.is_some(),
+            self.read_chunk_size,
+            self.write_buffer_size
+        )
+    }
+}
+
+impl TransportFactoryConfig {
+    pub(crate) fn apply_socket_options(&self, stream: &TcpStream) -> PyResult<()> {
+        stream.set_nodelay(self.nodelay)?;
+        if let Some(keepalive) = self.keepalive.as_ref() {
+            keepalive.apply(stream.as_raw_fd())?;
+        }
+        Ok(())
+    }
+}
+
+impl TransportFactory for TransportFactoryConfig {
+    fn create_tcp(
+        &self,
+        py: Python<'_>,
+        loop_: Py<PyAny>,
+        stream: TcpStream,
+        protocol: Py<PyAny>,
+    ) -> PyResult<Py<PyAny>> {
+        let velox_loop: Py<VeloxLoop> = loop_.extract(py)?;
+        let mut transport = TcpTransport::new(velox_loop.clone_ref(py), stream, protocol)?;
+        transport.configure(self)?;
+        stats::emit_connection_made(py, &velox_loop, &transport);
+        Ok(Py::new(py, transport)?.into_any())
+    }
+
+    fn create_ssl(
+        &self,
+        py: Python<'_>,
+        loop_: Py<PyAny>,
+        stream: TcpStream,
+        protocol: Py<PyAny>,
+        ssl_context: Py<PyAny>,
+        server_hostname: Option<String>,
+        is_client: bool,
+    ) -> PyResult<Py<PyAny>> {
+        self.apply_socket_options(&stream)?;
+        DefaultTransportFactory.create_ssl(
+            py,
+            loop_,
+            stream,
+            protocol,
+            ssl_context,
+            server_hostname,
+            is_client,
+        )
+    }
+
+    fn create_udp(
+        &self,
+        py: Python<'_>,
+        loop_: Py<PyAny>,
+        socket: UdpSocket,
+        protocol: Py<PyAny>,
+        remote_addr: Option<SocketAddr>,
+        allow_broadcast: bool,
+    ) -> PyResult<Py<PyAny>> {
+        DefaultTransportFactory.create_udp(py, loop_, socket, protocol, remote_addr, allow_broadcast)
+    }
+}
+
+/// The factory a loop creates its transports with, see `set_transport_factory`
+pub(crate) enum LoopTransportFactory {
+    Default,
+    Native(Py<TransportFactoryConfig>),
+    /// Called as `factory(kind, loop, sock_fd, protocol, extra)`; it owns the
+    /// fd from then on and may return any transport-like object
+    Python(Py<PyAny>),
+}
+
+impl LoopTransportFactory {
+    pub(crate) fn from_py(factory: Option<Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(factory) = factory.filter(|f| !f.is_none()) else {
+            return Ok(Self::Default);
+        };
+        if let Ok(config) = factory.cast::<TransportFactoryConfig>() {
+            return Ok(Self::Native(config.clone().unbind()));
+        }
+        if !factory.is_callable() {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "transport factory must be None, a TransportFactoryConfig or a callable",
+            ));
+        }
+        Ok(Self::Python(factory.unbind()))
+    }
+
+    pub(crate) fn to_py(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        match self {
+            Self::Default => None,
+            Self::Native(config) => Some(config.clone_ref(py).into_any()),
+            Self::Python(factory) => Some(factory.clone_ref(py)),
+        }
+    }
+
+    pub(crate) fn clone_ref(&self, py: Python<'_>) -> Self {
+        match self {
+            Self::Default => Self::Default,
+            Self::Native(config) => Self::Native(config.clone_ref(py)),
+            Self::Python(factory) => Self::Python(factory.clone_ref(py)),
+        }
+    }
+
+    fn call_python(
+        py: Python<'_>,
+        factory: &Py<PyAny>,
+        kind: &str,
+        loop_: Py<PyAny>,
+        fd: RawFd,
+        protocol: Py<PyAny>,
+        addrs: (Option<SocketAddr>, Option<SocketAddr>),
+    ) -> PyResult<Py<PyAny>> {
+        let extra = PyDict::new(py);
+        let (sockname, peername) = addrs;
+        for (key, addr) in [("sockname", sockname), ("peername", peername)] {
+            let value = addr.map(|addr| socket_addr_to_tuple(py, addr)).transpose()?;
+            extra.set_item(key, value)?;
+        }
+        let transport = factory.call1(py, (kind, loop_, fd, protocol, extra))?;
+        if transport.is_none(py) {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "transport factory returned None",
+            ));
+        }
+        Ok(transport)
+    }
+}
+
+impl TransportFactory for LoopTransportFactory {
+    fn create_tcp(
+        &self,
+        py: Python<'_>,
+        loop_: Py<PyAny>,
+        stream: TcpStream,
+        protocol: Py<PyAny>,
+    ) -> PyResult<Py<PyAny>> {
+        match self {
+            Self::Default => DefaultTransportFactory.create_tcp(py, loop_, stream, protocol),
+            Self::Native(config) => config.get().create_tcp(py, loop_, stream, protocol),
+            Self::Python(factory) => {
+                let addrs = (stream.local_addr().ok(), stream.peer_addr().ok());
+                let fd = stream.into_raw_fd();
+                Self::call_python(py, factory, "tcp", loop_, fd, protocol, addrs)
+            }
+        }
+    }
+
+    /// TLS always runs on the native SSLTransport; a Python factory isn't asked
+    fn create_ssl(
+        &self,
+        py: Python<'_>,
+        loop_: Py<PyAny>,
+        stream: TcpStream,
+        protocol: Py<PyAny>,
+        ssl_context: Py<PyAny>,
+        server_hostname: Option<String>,
+        is_client: bool,
+    ) -> PyResult<Py<PyAny>> {
+        match self {
+            Self::Native(config) => config.get().create_ssl(
+                py,
+                loop_,
+                stream,
+                protocol,
+                ssl_context,
+                server_hostname,
+                is_client,
+            ),
+            _ => DefaultTransportFactory.create_ssl(
+                py,
+                loop_,
+                stream,
+                protocol,
+                ssl_context,
+                server_hostname,
+                is_client,
+            ),
+        }
+    }
+
+    fn create_udp(
+        &self,
+        py: Python<'_>,
+        loop_: Py<PyAny>,
+        socket: UdpSocket,
+        protocol: Py<PyAny>,
+        remote_addr: Option<SocketAddr>,
+        allow_broadcast: bool,
+    ) -> PyResult<Py<PyAny>> {
+        match self {
+            Self::Python(factory) => {
+                let addrs = (socket.local_addr().ok(), remote_addr);
+                let fd = socket.into_raw_fd();
+                Self::call_python(py, factory, "udp", loop_, fd, protocol, addrs)
+            }
+            _ => DefaultTransportFactory.create_udp(
+                py,
+                loop_,
+                socket,
+                protocol,
+                remote_addr,
+                allow_broadcast,
+            ),
+        }
+    }
+}
+
+/// Start reading for a transport the factory returned: the native path for a
+/// TcpTransport, otherwise its `_read_ready` as a plain reader callback. An
+/// object without `_read_ready` is left to do its own reading
+pub(crate) fn start_reading(
+    py: Python<'_>,
+    loop_: &Py<VeloxLoop>,
+    transport: &Bound<'_, PyAny>,
+    fd: RawFd,
+) -> PyResult<()> {
+    if let Ok(tcp) = transport.cast::<TcpTransport>() {
+        return TcpTransport::start_reading(tcp);
+    }
+    if let Ok(read_ready) = transport.getattr("_read_ready") {
+        loop_.bind(py).borrow().add_reader(py, fd, read_ready.unbind())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use pyo3::prelude::*;
+
+use crate::constants::FLUSH_POLL_INTERVAL;
+use crate::event_loop::VeloxLoop;
+
+/// Drives `transport.flush()`: checks on a short timer until the transport's
+/// own buffer and the kernel send queue are both empty, or the deadline passes
+#[pyclass(module = "veloxloop._veloxloop")]
+pub(crate) struct FlushWaiter {
+    loop_: Py<VeloxLoop>,
+    transport: Py<PyAny>,
+    future: Py<PyAny>,
+    deadline: Option<f64>,
+    // Kernel send queue at the last check, kept for once the socket is gone
+    last_queued: Option<usize>,
+}
+
+impl FlushWaiter {
+    /// Future resolving once everything written to `transport` has left the
+    /// host, or failing with TimeoutError after `timeout` seconds
+    pub(crate) fn start(
+        loop_: &Py<VeloxLoop>,
+        transport: Bound<'_, PyAny>,
+        timeout: Option<f64>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = transport.py();
+        if let Some(timeout) = timeout
+            && (timeout.is_nan() || timeout < 0.0)
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "timeout should be a non-negative number, got {timeout}"
+            )));
+        }
+        let future = loop_.bind(py).call_method0("create_future")?.unbind();
+        let deadline = timeout.map(|t| loop_.bind(py).borrow().time() + t);
+        let waiter = Bound::new(
+            py,
+            Self {
+                loop_: loop_.clone_ref(py),
+                transport: transport.unbind(),
+                future: future.clone_ref(py),
+                deadline,
+                last_queued: None,
+            },
+        )?;
+        Self::check(&waiter)?;
+        Ok(future)
+    }
+
+    fn check(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let mut this = slf.borrow_mut();
+        let future = this.future.bind(py).clone();
+        if future.call_method0("done")?.is_truthy()? {
+            return Ok(());
+        }
+
+        let transport = this.transport.bind(py).clone();
+        let buffered: usize = transport.call_method0("get_write_buffer_size")?.extract()?;
+        let queued: Option<usize> = transport.call_method0("get_kernel_write_queue")?.extract()?;
+        // Only Linux has SIOCOUTQ; elsewhere None says nothing about the socket
+        let unsupported = !cfg!(target_os = "linux");
+        match queued {
+            Some(0) | None if buffered == 0 && (queued.is_some() || unsupported) => {
+                future.call_method1("set_result", (py.None(),))?;
+                return Ok(());
+            }
+            // The socket is gone: everything left only if the kernel queue
+            // was empty when last seen
+            None if transport.call_method0("is_closing")?.is_truthy()? => {
+                if buffered == 0 && this.last_queued == Some(0) {
+                    future.call_method1("set_result", (py.None(),))?;
+                } else {
+                    let exc = pyo3::exceptions::PyConnectionResetError::new_err(
+                        "Connection lost before the write buffer was flushed",
+                    );
+                    future.call_method1("set_exception", (exc.into_value(py),))?;
+                }
+                return Ok(());
+            }
+            Some(_) => this.last_queued = queued,
+            None => {}
+        }
+
+        let loop_ = this.loop_.bind(py).borrow();
+        let mut delay = FLUSH_POLL_INTERVAL;
+        if let Some(deadline) = this.deadline {
+            let left = deadline - loop_.time();
+            if left <= 0.0 {
+                let exc = pyo3::exceptions::PyTimeoutError::new_err(());
+                future.call_method1("set_exception", (exc.into_value(py),))?;
+                return Ok(());
+            }
+            delay = delay.min(left);
+        }
+        loop_.call_later(delay, slf.clone().into_any().unbind(), Vec::new(), None)?;
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl FlushWaiter {
+    fn __call__(slf: &Bound<'_, Self>) -> PyResult<()> {
+        Self::check(slf)
+    }
+}
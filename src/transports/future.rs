@@ -1,4 +1,5 @@
 use parking_lot::Mutex;
+use pyo3::exceptions::PyBaseException;
 use pyo3::prelude::*;
 
 enum FutureState {
@@ -49,7 +50,29 @@ impl PendingFuture {
         }
     }
 
-    fn result(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+    /// An exception thrown into the coroutine awaiting the future, such as
+    /// a cancelled task's CancelledError, reaches it here through `yield
+    /// from`. The awaiter has given up, so as with an asyncio task the
+    /// future is cancelled, and the exception is raised at the await.
+    #[pyo3(signature = (typ, val=None, _tb=None))]
+    fn throw(
+        &self,
+        py: Python<'_>,
+        typ: &Bound<'_, PyAny>,
+        val: Option<&Bound<'_, PyAny>>,
+        _tb: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        self.cancel(py)?;
+        let exc = match val {
+            Some(val) if val.is_instance_of::<PyBaseException>() => val.clone(),
+            Some(val) if !val.is_none() => typ.call1((val,))?,
+            _ if typ.is_instance_of::<PyBaseException>() => typ.clone(),
+            _ => typ.call0()?,
+        };
+        Err(PyErr::from_value(exc))
+    }
+
+    pub fn result(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
         let lock = self.state.lock();
         match &lock.0 {
             FutureState::Finished(res) => Ok(res.clone_ref(py)),
@@ -61,10 +84,14 @@ impl PendingFuture {
         }
     }
 
-    fn done(&self) -> bool {
+    pub fn done(&self) -> bool {
         !matches!(self.state.lock().0, FutureState::Pending)
     }
 
+    pub fn cancelled(&self) -> bool {
+        matches!(self.state.lock().0, FutureState::Cancelled)
+    }
+
     pub fn set_result(&self, py: Python<'_>, result: Py<PyAny>) -> PyResult<()> {
         let mut lock = self.state.lock();
         if !matches!(lock.0, FutureState::Pending) {
@@ -116,17 +143,33 @@ impl PendingFuture {
         Ok(())
     }
 
-    pub fn add_done_callback(&self, callback: Py<PyAny>) -> PyResult<()> {
+    /// Run `callback` once the future is done; right away if it already is
+    pub fn add_done_callback(&self, py: Python<'_>, callback: Py<PyAny>) -> PyResult<()> {
         let mut lock = self.state.lock();
-        if !matches!(lock.0, FutureState::Pending) {
-            return Err(pyo3::exceptions::PyRuntimeError::new_err(
-                "Cannot add callback to completed future",
-            ));
+        if matches!(lock.0, FutureState::Pending) {
+            lock.1.push(callback);
+            return Ok(());
         }
-        lock.1.push(callback);
+        drop(lock);
+        let _ = unsafe {
+            crate::ffi_utils::vectorcall_one_arg(py, callback.as_ptr(), pyo3::ffi::Py_None())
+        };
         Ok(())
     }
 
+    /// Whether settling the future would run done callbacks
+    pub(crate) fn has_callbacks(&self) -> bool {
+        !self.state.lock().1.is_empty()
+    }
+
+    /// Put the future back into the pending state, dropping any result and callbacks.
+    /// Only called by `FuturePool` once nothing outside the pool references it.
+    pub(crate) fn reset(&self) {
+        let mut lock = self.state.lock();
+        lock.0 = FutureState::Pending;
+        lock.1.clear();
+    }
+
     pub fn cancel(&self, py: Python<'_>) -> PyResult<bool> {
         let mut lock = self.state.lock();
         if !matches!(lock.0, FutureState::Pending) {
@@ -148,6 +191,77 @@ impl PendingFuture {
     }
 }
 
+/// Free-list of loop-created PendingFutures, enabled with `VeloxLoop(future_pool_size=N)`.
+/// The pool keeps one reference to every future it hands out; a future whose
+/// refcount has dropped back to that single reference can't be observed by
+/// anyone else, so it's safe to reset and hand out again.
+pub struct FuturePool {
+    capacity: usize,
+    in_use: Vec<Py<PendingFuture>>,
+    free: Vec<Py<PendingFuture>>,
+    reused: u64,
+}
+
+impl FuturePool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            in_use: Vec::with_capacity(capacity),
+            free: Vec::with_capacity(capacity),
+            reused: 0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Take a recycled future, or allocate one (tracked while the pool has room)
+    pub fn acquire(&mut self, py: Python<'_>) -> PyResult<Py<PendingFuture>> {
+        let future = match self.free.pop() {
+            Some(future) => {
+                self.reused += 1;
+                future
+            }
+            None => Py::new(py, PendingFuture::new())?,
+        };
+        if self.in_use.len() + self.free.len() < self.capacity {
+            self.in_use.push(future.clone_ref(py));
+        }
+        Ok(future)
+    }
+
+    /// Remove and return the futures only the pool still references.
+    /// The caller resets them outside any pool borrow and hands them to `release`.
+    pub fn take_unreferenced(&mut self) -> Vec<Py<PendingFuture>> {
+        let mut taken = Vec::new();
+        let mut i = 0;
+        while i < self.in_use.len() {
+            // Checked with the GIL held: no other thread can take a new reference meanwhile
+            if unsafe { pyo3::ffi::Py_REFCNT(self.in_use[i].as_ptr()) } == 1 {
+                taken.push(self.in_use.swap_remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        taken
+    }
+
+    pub fn release(&mut self, futures: Vec<Py<PendingFuture>>) {
+        self.free.extend(futures);
+    }
+
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        let dict = pyo3::types::PyDict::new(py);
+        dict.set_item("capacity", self.capacity)?;
+        dict.set_item("in_use", self.in_use.len())?;
+        dict.set_item("free", self.free.len())?;
+        dict.set_item("reused", self.reused)?;
+        Ok(dict)
+    }
+}
+
 #[pymethods]
 impl CompletedFuture {
     fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
@@ -175,6 +289,16 @@ impl CompletedFuture {
     }
 }
 
+impl PendingFuture {
+    /// A future already finished with `result`, for callers without a loop
+    /// at hand; see `VeloxLoop::resolved_future`
+    pub fn resolved(py: Python<'_>, result: Py<PyAny>) -> PyResult<Py<PyAny>> {
+        let future = Self::new();
+        future.state.lock().0 = FutureState::Finished(result);
+        Ok(Py::new(py, future)?.into_any())
+    }
+}
+
 impl CompletedFuture {
     pub fn new(result: Py<PyAny>) -> Self {
         Self { result }
@@ -1,14 +1,27 @@
+pub mod factory;
+pub mod flush;
 pub mod future;
+pub mod pacing;
+pub mod splice;
 pub mod ssl;
+pub mod stats;
 pub mod stream_server;
+#[cfg(target_os = "linux")]
+pub mod subprocess;
 pub mod tcp;
+pub mod timeouts;
 pub mod udp;
 
 use bitflags::bitflags;
+use pyo3::buffer::PyBuffer;
 use pyo3::prelude::*;
-use std::os::fd::RawFd;
+use pyo3::types::{PyBytes, PyFrozenSet, PyInt, PyList};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::fd::{AsRawFd, RawFd};
 
-use crate::event_loop::VeloxLoop;
+use crate::constants::{ACCEPT_RETRY_DELAY, DEFAULT_HIGH};
+use crate::event_loop::{ExceptionContext, Shed, VeloxLoop};
+use crate::socket::KeepaliveParams;
 
 bitflags! {
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -19,6 +32,294 @@ bitflags! {
         const READING_PAUSED = 1 << 3;
         const WRITING_PAUSED = 1 << 4;
         const EOF_RECEIVED   = 1 << 5;
+        const EOF_PENDING    = 1 << 6;
+        const EOF_WRITTEN    = 1 << 7;
+        const CONNECTION_LOST = 1 << 8;
+        const DETACHING      = 1 << 9;
+    }
+}
+
+impl TransportState {
+    /// Claim the single `connection_lost()` notification; false once it is taken
+    pub(crate) fn claim_connection_lost(&mut self) -> bool {
+        let first = !self.contains(Self::CONNECTION_LOST);
+        self.insert(Self::CONNECTION_LOST);
+        first
+    }
+
+    /// Names of the set flags in bit order. The names are the constants
+    /// above and part of the Python API, see `get_state()`.
+    pub(crate) fn names(self) -> impl Iterator<Item = &'static str> {
+        self.iter_names().map(|(name, _)| name)
+    }
+
+    /// `get_state()` of a transport
+    pub(crate) fn to_frozenset(self, py: Python<'_>) -> PyResult<Bound<'_, PyFrozenSet>> {
+        PyFrozenSet::new(py, self.names())
+    }
+}
+
+/// `<TcpTransport fd=7 state=ACTIVE|READING_PAUSED buf=1234>`
+pub(crate) fn transport_repr(
+    kind: &str,
+    fd: RawFd,
+    state: TransportState,
+    buffered: usize,
+) -> String {
+    let names: Vec<_> = state.names().collect();
+    let state = if names.is_empty() {
+        "0".to_string()
+    } else {
+        names.join("|")
+    };
+    format!("<{kind} fd={fd} state={state} buf={buffered}>")
+}
+
+/// Call `protocol.connection_lost(exc)` inline. Whatever it raises goes to the
+/// loop's exception handler, as it would for a callback run by the loop.
+pub(crate) fn call_connection_lost(
+    py: Python<'_>,
+    loop_: &Py<VeloxLoop>,
+    callback: &Py<PyAny>,
+    exc: Option<Py<PyAny>>,
+) -> PyResult<()> {
+    let exc = exc.unwrap_or_else(|| py.None());
+    if let Err(e) = callback.call1(py, (exc,)) {
+        ExceptionContext::new("Exception in connection_lost")
+            .exception(e.value(py))
+            .report(py, &loop_.bind(py).borrow())?;
+    }
+    Ok(())
+}
+
+/// Hand an exception raised by a protocol callback to the loop's exception
+/// handler, with the context asyncio builds in `_fatal_error`
+pub(crate) fn report_protocol_error(
+    py: Python<'_>,
+    loop_: &Py<VeloxLoop>,
+    message: &str,
+    err: &PyErr,
+    transport: Option<&Bound<'_, PyAny>>,
+    protocol: &Py<PyAny>,
+) -> PyResult<()> {
+    let mut context = ExceptionContext::new(message).exception(err.value(py));
+    if let Some(transport) = transport {
+        context = context.transport(transport);
+    }
+    context
+        .protocol(protocol.bind(py))
+        .report(py, &loop_.bind(py).borrow())
+}
+
+/// Resolve `set_write_buffer_limits(high, low)` the way asyncio does: high
+/// defaults to 4*low (or 64 KiB), low to high/4, and 0 <= low <= high must hold.
+/// Returns (high, low).
+pub(crate) fn write_buffer_limits(
+    high: Option<isize>,
+    low: Option<isize>,
+) -> PyResult<(usize, usize)> {
+    let high =
+        high.unwrap_or_else(|| low.map_or(DEFAULT_HIGH as isize, |low| low.saturating_mul(4)));
+    let low = low.unwrap_or(high / 4);
+    if !(high >= low && low >= 0) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "high ({high}) must be >= low ({low}) must be >= 0"
+        )));
+    }
+    Ok((high as usize, low as usize))
+}
+
+/// `writelines()` payload: the buffers from `lines` joined into one bytes
+/// object, as asyncio's `b''.join(list_of_data)`, so they go out as one write
+pub(crate) fn join_lines<'py>(lines: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+    let py = lines.py();
+    let views = lines
+        .try_iter()?
+        .map(|line| PyBuffer::<u8>::get(&line?))
+        .collect::<PyResult<Vec<_>>>()?;
+    let total = views.iter().map(|view| view.len_bytes()).sum();
+    PyBytes::new_with(py, total, |out| {
+        let mut start = 0;
+        for view in &views {
+            let end = start + view.len_bytes();
+            view.copy_to_slice(py, &mut out[start..end])?;
+            start = end;
+        }
+        Ok(())
+    })
+}
+
+/// `Server.sockets` for a server's listeners
+pub(crate) fn listener_sockets(py: Python<'_>, listeners: &[TcpListener]) -> PyResult<Py<PyAny>> {
+    let sockets = listeners
+        .iter()
+        .map(|l| Py::new(py, tcp::SocketWrapper::new(l.as_raw_fd(), l.local_addr()?)))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(PyList::new(py, sockets)?.into_any().unbind())
+}
+
+/// Bound address of each listener: (host, port) or (host, port, flowinfo, scope_id)
+pub(crate) fn listener_addresses(
+    py: Python<'_>,
+    listeners: &[TcpListener],
+) -> PyResult<Py<PyAny>> {
+    let addresses = listeners
+        .iter()
+        .map(|l| crate::utils::ipv6::socket_addr_to_tuple(py, l.local_addr()?))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(PyList::new(py, addresses)?.into_any().unbind())
+}
+
+/// `__aenter__` of a server: a fresh awaitable resolving to the server, or
+/// RuntimeError when it is already the target of an `async with`
+pub(crate) fn enter_server_context(
+    server: &Bound<'_, PyAny>,
+    loop_: &VeloxLoop,
+    entered: &mut bool,
+) -> PyResult<Py<PyAny>> {
+    if *entered {
+        return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+            "Server is already in use by an 'async with' block",
+        ));
+    }
+    *entered = true;
+    loop_.resolved_future(server.py(), server.clone().unbind())
+}
+
+/// Future for a server's `serve_forever()`, kept in `slot` for close() to
+/// resolve. Refused on a closed server and while an earlier call is still
+/// waiting, as asyncio does
+pub(crate) fn serve_forever_future(
+    py: Python<'_>,
+    slot: &parking_lot::Mutex<Option<Py<future::PendingFuture>>>,
+    closed: bool,
+) -> PyResult<Py<future::PendingFuture>> {
+    if closed {
+        return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+            "Server is closed",
+        ));
+    }
+    let mut slot = slot.lock();
+    if slot.as_ref().is_some_and(|f| !f.bind(py).borrow().done()) {
+        return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+            "Server is already being awaited on serve_forever()",
+        ));
+    }
+    let future = Py::new(py, future::PendingFuture::new())?;
+    *slot = Some(future.clone_ref(py));
+    Ok(future)
+}
+
+/// Let a closing server's `serve_forever()` return; a no-op without one
+pub(crate) fn finish_serve_forever(
+    py: Python<'_>,
+    slot: &parking_lot::Mutex<Option<Py<future::PendingFuture>>>,
+) -> PyResult<()> {
+    // Taken out first: the future's callbacks may run arbitrary code
+    let future = slot.lock().take();
+    match future {
+        Some(future) if !future.bind(py).borrow().done() => {
+            future.bind(py).borrow().set_result(py, py.None())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A connection accepted by `accept_connection`, with the peer address
+/// accept4 reported for it
+pub(crate) struct Accepted {
+    pub(crate) stream: TcpStream,
+    pub(crate) peer: Option<SocketAddr>,
+}
+
+/// Accept a connection on a server's `listener` and give it the server's
+/// socket options: TCP_NODELAY, plus `keepalive` if the server has one. It
+/// comes out of `accept_nonblocking`, so a subprocess never inherits it and
+/// the transport finds it non-blocking already. The outer error is the
+/// listener's; the inner one only fails this connection.
+pub(crate) fn accept_connection(
+    listener: &TcpListener,
+    keepalive: Option<&KeepaliveParams>,
+) -> std::io::Result<PyResult<Accepted>> {
+    let (conn, addr, addr_len) = crate::socket::accept_nonblocking(listener.as_raw_fd())?;
+    let stream = TcpStream::from(conn);
+    let peer = crate::utils::ipv6::sockaddr_storage_to_socket_addr(&addr, addr_len);
+    let configured = stream
+        .set_nodelay(true)
+        .map_err(PyErr::from)
+        .and_then(|()| keepalive.map_or(Ok(()), |k| k.apply(stream.as_raw_fd())));
+    Ok(configured.map(|()| Accepted { stream, peer }))
+}
+
+/// A server's `accept()` on `listener` failed with `err`. Out of fds, a
+/// pending connection is dropped through the loop's spare fd so the backlog
+/// keeps draining (true: go on accepting, false: it is empty). Without a
+/// spare the listener is paused for `ACCEPT_RETRY_DELAY` instead of waking
+/// the loop for nothing. Any other error is returned as is.
+pub(crate) fn accept_failed(
+    py: Python<'_>,
+    loop_: &Py<VeloxLoop>,
+    server: &Bound<'_, PyAny>,
+    listener: RawFd,
+    err: std::io::Error,
+) -> PyResult<bool> {
+    if !crate::event_loop::is_fd_exhaustion(&err) {
+        return Err(crate::utils::os_error_to_pyerr(err));
+    }
+    let loop_py = loop_;
+    let loop_ = loop_py.bind(py).borrow();
+    let exc = loop_.fd_error("accept()", err);
+    match loop_.shed_connection(listener) {
+        Shed::Dropped => {
+            ExceptionContext::new("Out of file descriptors: dropped an incoming connection")
+                .exception(exc.value(py))
+                .with("socket", &PyInt::new(py, listener))
+                .report(py, &loop_)?;
+            return Ok(true);
+        }
+        Shed::Drained => return Ok(false),
+        Shed::NoSpare => {}
+    }
+
+    loop_.remove_reader(py, listener)?;
+    let retry = Py::new(
+        py,
+        AcceptRetry {
+            loop_: loop_py.clone_ref(py),
+            server: server.clone().unbind(),
+            listener,
+        },
+    )?;
+    loop_.call_later(ACCEPT_RETRY_DELAY, retry.into_any(), Vec::new(), None)?;
+    ExceptionContext::new(format!(
+        "Out of file descriptors: pausing accept() for {ACCEPT_RETRY_DELAY} seconds"
+    ))
+    .exception(exc.value(py))
+    .with("socket", &PyInt::new(py, listener))
+    .report(py, &loop_)?;
+    Ok(false)
+}
+
+/// Timer callback putting a listener paused by `accept_failed` back on the loop
+#[pyclass(module = "veloxloop._veloxloop")]
+pub(crate) struct AcceptRetry {
+    loop_: Py<VeloxLoop>,
+    server: Py<PyAny>,
+    listener: RawFd,
+}
+
+#[pymethods]
+impl AcceptRetry {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let server = self.server.bind(py);
+        // A closed server has given up its listeners, and the fd may be reused
+        if !server.call_method0("is_serving")?.is_truthy()? {
+            return Ok(());
+        }
+        let loop_ = self.loop_.bind(py).borrow();
+        loop_.add_reader(py, self.listener, server.getattr("_on_accept")?.unbind())?;
+        loop_.set_fd_priority(self.listener, true)?;
+        Ok(())
     }
 }
 
@@ -38,6 +339,9 @@ pub trait Transport {
 
     /// Get the file descriptor associated with this transport
     fn get_fd(&self) -> RawFd;
+
+    /// Per-connection byte counters and age
+    fn stats(&self) -> &stats::TransportStats;
 }
 
 /// Trait for stream-based transports (TCP, SSL)
@@ -61,16 +365,22 @@ pub trait StreamTransport: Transport {
     /// Get the size of the write buffer
     fn get_write_buffer_size(&self) -> usize;
 
+    /// Get write buffer limits as (low, high)
+    fn get_write_buffer_limits(&self) -> (usize, usize);
+
     /// Set write buffer limits (high and low water marks)
     fn set_write_buffer_limits(
         &mut self,
         py: Python<'_>,
-        high: Option<usize>,
-        low: Option<usize>,
+        high: Option<isize>,
+        low: Option<isize>,
     ) -> PyResult<()>;
 
-    /// Internal callback called when the socket is readable
-    fn read_ready(&mut self, py: Python<'_>) -> PyResult<()>;
+    /// Internal callback called when the socket is readable. Takes the
+    /// Python object, so protocol callbacks run with the transport unborrowed.
+    fn read_ready(slf: &Bound<'_, Self>) -> PyResult<()>
+    where
+        Self: Sized;
 
     /// Internal callback called when the socket is writable
     fn write_ready(&mut self, py: Python<'_>) -> PyResult<()>;
@@ -124,7 +434,8 @@ impl TransportFactory for DefaultTransportFactory {
     ) -> PyResult<Py<PyAny>> {
         // Downcast loop_ from PyAny to VeloxLoop
         let velox_loop: Py<VeloxLoop> = loop_.extract(py)?;
-        let transport = tcp::TcpTransport::new(velox_loop, stream, protocol)?;
+        let transport = tcp::TcpTransport::new(velox_loop.clone_ref(py), stream, protocol)?;
+        stats::emit_connection_made(py, &velox_loop, &transport);
         Ok(Py::new(py, transport)?.into_any())
     }
 
@@ -169,7 +480,48 @@ impl TransportFactory for DefaultTransportFactory {
     ) -> PyResult<Py<PyAny>> {
         // Downcast loop_ from PyAny to VeloxLoop
         let velox_loop: Py<VeloxLoop> = loop_.extract(py)?;
-        let transport = udp::UdpTransport::new(velox_loop, socket, protocol, remote_addr)?;
+        let transport =
+            udp::UdpTransport::new(velox_loop.clone_ref(py), socket, protocol, remote_addr)?;
+        stats::emit_connection_made(py, &velox_loop, &transport);
         Ok(Py::new(py, transport)?.into_any())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitflags::Flags;
+
+    #[test]
+    fn every_state_bit_has_a_name() {
+        for bit in 0..u32::BITS {
+            let flag = TransportState::from_bits_retain(1 << bit);
+            if TransportState::all().contains(flag) {
+                let names: Vec<_> = flag.names().collect();
+                assert_eq!(names.len(), 1, "bit {bit}");
+                assert_eq!(TransportState::from_name(names[0]), Some(flag));
+            }
+        }
+        // No aliases or multi-bit flags, so names map 1:1 to bits
+        for flag in TransportState::FLAGS {
+            assert_eq!(flag.value().bits().count_ones(), 1, "{}", flag.name());
+        }
+        assert_eq!(
+            TransportState::all().bits().count_ones() as usize,
+            TransportState::FLAGS.len()
+        );
+    }
+
+    #[test]
+    fn repr_lists_state_names() {
+        let state = TransportState::ACTIVE | TransportState::READING_PAUSED;
+        assert_eq!(
+            transport_repr("TcpTransport", 7, state, 1234),
+            "<TcpTransport fd=7 state=ACTIVE|READING_PAUSED buf=1234>"
+        );
+        assert_eq!(
+            transport_repr("UdpTransport", 3, TransportState::empty(), 0),
+            "<UdpTransport fd=3 state=0 buf=0>"
+        );
+    }
+}
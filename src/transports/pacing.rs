@@ -0,0 +1,135 @@
+use pyo3::prelude::*;
+
+use super::stream_server::StreamTransport;
+use super::tcp::TcpTransport;
+use crate::constants::{DEFAULT_RATE_LIMIT_BURST_NS, PACING_INTERVAL_NS};
+use crate::event_loop::VeloxLoop;
+
+const NS_PER_SEC: u128 = 1_000_000_000;
+
+/// Token bucket behind `transport.set_rate_limit()`. Credit is kept in
+/// byte-nanoseconds, so refills driven by loop time never lose a fraction
+/// of a byte to rounding.
+pub(crate) struct RateLimit {
+    bytes_per_sec: u64,
+    burst: u64,
+    credit: u128,
+    refilled_at: u64,
+    /// The loop timer that re-arms the writer, while the bucket is empty
+    timer: Option<u64>,
+    wakeup: Py<PacingWakeup>,
+}
+
+impl RateLimit {
+    /// A full bucket; `burst` defaults to 100ms worth of `bytes_per_sec`
+    pub(crate) fn new(
+        py: Python<'_>,
+        loop_: &VeloxLoop,
+        transport: PacedTransport,
+        bytes_per_sec: u64,
+        burst: Option<u64>,
+    ) -> PyResult<Self> {
+        if bytes_per_sec == 0 || burst == Some(0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "bytes_per_sec and burst must be positive",
+            ));
+        }
+        let burst = burst.unwrap_or_else(|| {
+            (bytes_per_sec as u128 * DEFAULT_RATE_LIMIT_BURST_NS as u128 / NS_PER_SEC).max(1) as u64
+        });
+        Ok(Self {
+            bytes_per_sec,
+            burst,
+            credit: burst as u128 * NS_PER_SEC,
+            refilled_at: loop_.now_ns(),
+            timer: None,
+            wakeup: Py::new(py, PacingWakeup { transport })?,
+        })
+    }
+
+    /// `(bytes_per_sec, burst)`
+    pub(crate) fn settings(&self) -> (u64, u64) {
+        (self.bytes_per_sec, self.burst)
+    }
+
+    /// Waiting for the bucket to refill; the writer stays off meanwhile
+    pub(crate) fn is_waiting(&self) -> bool {
+        self.timer.is_some()
+    }
+
+    /// How many of `pending` bytes may be sent now. With nothing to spare the
+    /// refill timer is armed and 0 returned; the caller drops its writer.
+    pub(crate) fn budget(
+        &mut self,
+        py: Python<'_>,
+        loop_: &VeloxLoop,
+        pending: usize,
+    ) -> PyResult<usize> {
+        let now = loop_.now_ns();
+        let elapsed = now.saturating_sub(self.refilled_at) as u128;
+        self.refilled_at = now;
+        let cap = self.burst as u128 * NS_PER_SEC;
+        self.credit = (self.credit + elapsed * self.bytes_per_sec as u128).min(cap);
+
+        let available = (self.credit / NS_PER_SEC) as usize;
+        if available > 0 || pending == 0 {
+            return Ok(available.min(pending));
+        }
+        if self.timer.is_none() {
+            // Sleep until the next send can fill the bucket (or the buffer),
+            // but no longer than one pacing interval
+            let want = (pending as u128).min(self.burst as u128) * NS_PER_SEC;
+            let per_ns = self.bytes_per_sec as u128;
+            let delay = (want - self.credit)
+                .div_ceil(per_ns)
+                .min(PACING_INTERVAL_NS as u128);
+            let delay = delay.max((NS_PER_SEC - self.credit).div_ceil(per_ns));
+            let wakeup = self.wakeup.clone_ref(py).into_any();
+            self.timer = Some(loop_.call_later(
+                delay as f64 / NS_PER_SEC as f64,
+                wakeup,
+                Vec::new(),
+                None,
+            )?);
+        }
+        Ok(0)
+    }
+
+    /// The refill timer has run
+    pub(crate) fn timer_fired(&mut self) {
+        self.timer = None;
+    }
+
+    pub(crate) fn consume(&mut self, sent: usize) {
+        self.credit = self.credit.saturating_sub(sent as u128 * NS_PER_SEC);
+    }
+
+    /// Drop the refill timer, e.g. when the limit is removed or the transport closes
+    pub(crate) fn cancel(&mut self, loop_: &VeloxLoop) {
+        if let Some(timer) = self.timer.take() {
+            let _ = loop_._cancel_timer(timer);
+        }
+    }
+}
+
+pub(crate) enum PacedTransport {
+    Tcp(Py<TcpTransport>),
+    Stream(Py<StreamTransport>),
+}
+
+/// Timer callback of a rate-limited transport: the bucket has refilled, so
+/// the writer goes back on the socket
+#[pyclass(module = "veloxloop._veloxloop")]
+pub(crate) struct PacingWakeup {
+    transport: PacedTransport,
+}
+
+#[pymethods]
+impl PacingWakeup {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        match &self.transport {
+            PacedTransport::Tcp(transport) => TcpTransport::resume_paced(transport.bind(py)),
+            PacedTransport::Stream(transport) => StreamTransport::resume_paced(transport.bind(py)),
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use pyo3::IntoPyObjectExt;
+use pyo3::prelude::*;
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::sync::Arc;
+
+use super::future::PendingFuture;
+use super::stream_server::StreamTransport;
+#[cfg(target_os = "linux")]
+use super::subprocess::cloexec_pipe;
+use super::tcp::TcpTransport;
+use super::Transport;
+use crate::constants::{SPLICE_BUDGET, SPLICE_PIPE_SIZE};
+use crate::event_loop::VeloxLoop;
+
+type ReadyCallback = Arc<dyn Fn(Python<'_>) -> PyResult<()> + Send + Sync>;
+
+/// A transport taking part in a splice
+pub(crate) enum SpliceEnd {
+    Tcp(Py<TcpTransport>),
+    Stream(Py<StreamTransport>),
+}
+
+impl SpliceEnd {
+    pub(crate) fn extract(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(t) = obj.cast::<TcpTransport>() {
+            Ok(Self::Tcp(t.clone().unbind()))
+        } else if let Ok(t) = obj.cast::<StreamTransport>() {
+            Ok(Self::Stream(t.clone().unbind()))
+        } else {
+            Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "splice_to() needs a TcpTransport or StreamTransport",
+            ))
+        }
+    }
+
+    fn fd(&self, py: Python<'_>) -> RawFd {
+        match self {
+            Self::Tcp(t) => t.borrow(py).get_fd(),
+            Self::Stream(t) => t.borrow(py).get_fd(),
+        }
+    }
+
+    fn clone_ref(&self, py: Python<'_>) -> Self {
+        match self {
+            Self::Tcp(t) => Self::Tcp(t.clone_ref(py)),
+            Self::Stream(t) => Self::Stream(t.clone_ref(py)),
+        }
+    }
+
+    fn is(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Tcp(a), Self::Tcp(b)) => a.is(b),
+            (Self::Stream(a), Self::Stream(b)) => a.is(b),
+            _ => false,
+        }
+    }
+
+    /// Take the read side (source) or write side (sink) away from the transport
+    fn attach(&self, py: Python<'_>, splice: &Py<Splice>, source: bool) -> PyResult<()> {
+        match self {
+            Self::Tcp(t) => TcpTransport::begin_splice(t.bind(py), splice, source),
+            Self::Stream(t) => StreamTransport::begin_splice(t.bind(py), splice, source),
+        }
+    }
+
+    /// Hand that side back to the transport's own dispatch
+    fn release(&self, py: Python<'_>, source: bool) -> PyResult<()> {
+        match self {
+            Self::Tcp(t) => TcpTransport::end_splice(t.bind(py), source),
+            Self::Stream(t) => StreamTransport::end_splice(t.bind(py), source),
+        }
+    }
+}
+
+/// Kernel-side copy from one transport's socket to another's through a pipe.
+/// Readiness-driven like `sock_sendfile`: the source is read into the pipe while
+/// it is empty, and the pipe is drained into the sink before reading again.
+#[pyclass(module = "veloxloop._veloxloop")]
+pub struct Splice {
+    loop_: Py<VeloxLoop>,
+    future: Py<PendingFuture>,
+    source: SpliceEnd,
+    sink: SpliceEnd,
+    src_fd: RawFd,
+    dst_fd: RawFd,
+    pipe_r: OwnedFd,
+    pipe_w: OwnedFd,
+    pipe_size: usize,
+    count: Option<usize>,
+    // Bytes delivered to the sink
+    moved: usize,
+    // Bytes read from the source but still in the pipe
+    in_pipe: usize,
+    eof: bool,
+    reading: bool,
+    writing: bool,
+    finished: bool,
+    callback: Option<ReadyCallback>,
+}
+
+impl Splice {
+    /// Start moving bytes from `source` to `sink`; the future resolves to the total
+    pub(crate) fn start(
+        py: Python<'_>,
+        loop_: Py<VeloxLoop>,
+        source: SpliceEnd,
+        sink: SpliceEnd,
+        count: Option<usize>,
+    ) -> PyResult<Py<PendingFuture>> {
+        if source.is(&sink) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Cannot splice a transport to itself",
+            ));
+        }
+        let future = loop_.bind(py).borrow().create_future(py)?;
+        if count == Some(0) {
+            future.bind(py).borrow().set_result(py, 0usize.into_py_any(py)?)?;
+            return Ok(future);
+        }
+
+        let (pipe_r, pipe_w) = splice_pipe().map_err(crate::utils::os_error_to_pyerr)?;
+        let pipe_size = pipe_capacity(&pipe_w);
+        let splice = Py::new(
+            py,
+            Self {
+                loop_,
+                future: future.clone_ref(py),
+                src_fd: source.fd(py),
+                dst_fd: sink.fd(py),
+                source,
+                sink,
+                pipe_r,
+                pipe_w,
+                pipe_size,
+                count,
+                moved: 0,
+                in_pipe: 0,
+                eof: false,
+                reading: false,
+                writing: false,
+                finished: false,
+                callback: None,
+            },
+        )?;
+
+        let splice_clone = splice.clone_ref(py);
+        let callback: ReadyCallback =
+            Arc::new(move |py: Python<'_>| Self::on_ready(splice_clone.bind(py)));
+        splice.borrow_mut(py).callback = Some(callback);
+
+        let this = splice.borrow(py);
+        this.sink.attach(py, &splice, false)?;
+        if let Err(e) = this.source.attach(py, &splice, true) {
+            this.sink.release(py, false)?;
+            drop(this);
+            splice.borrow_mut(py).callback = None;
+            return Err(e);
+        }
+        drop(this);
+        Self::rearm(splice.bind(py))?;
+        Ok(future)
+    }
+
+    /// One of the transports is closing: stop, give the other one back and
+    /// cancel the future. `closing_fd` is left alone, its transport is going away.
+    pub(crate) fn cancel(py: Python<'_>, splice: &Py<Self>, closing_fd: RawFd) -> PyResult<()> {
+        Self::finish(splice.bind(py), None, closing_fd)
+    }
+
+    fn on_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let step = {
+            let mut this = slf.borrow_mut();
+            if this.finished {
+                return Ok(());
+            }
+            this.pump()
+        };
+        match step {
+            Ok(false) => Self::rearm(slf),
+            Ok(true) => {
+                let moved = slf.borrow().moved;
+                Self::finish(slf, Some(Ok(moved)), -1)
+            }
+            Err(e) => Self::finish(slf, Some(Err(crate::utils::os_error_to_pyerr(e))), -1),
+        }
+    }
+
+    /// Move bytes until a side would block or the budget runs out; true once done
+    fn pump(&mut self) -> io::Result<bool> {
+        let mut budget = SPLICE_BUDGET;
+        while budget > 0 {
+            if self.in_pipe > 0 {
+                match splice_fd(self.pipe_r.as_raw_fd(), self.dst_fd, self.in_pipe) {
+                    Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                    Ok(n) => {
+                        self.in_pipe -= n;
+                        self.moved += n;
+                        budget = budget.saturating_sub(n);
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                    Err(e) => return Err(e),
+                }
+                continue;
+            }
+
+            let remaining = self.count.map_or(usize::MAX, |c| c - self.moved);
+            if self.eof || remaining == 0 {
+                return Ok(true);
+            }
+            match splice_fd(self.src_fd, self.pipe_w.as_raw_fd(), remaining.min(self.pipe_size)) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.in_pipe += n,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Watch the source while the pipe is empty, the sink while it holds data
+    fn rearm(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let mut this = slf.borrow_mut();
+        let Some(callback) = this.callback.clone() else {
+            return Ok(());
+        };
+        let want_write = this.in_pipe > 0;
+        let loop_ = this.loop_.bind(py).borrow();
+        if this.reading == want_write {
+            if want_write {
+                loop_.remove_reader(py, this.src_fd)?;
+            } else {
+                loop_.add_reader_native(this.src_fd, callback.clone())?;
+            }
+        }
+        if this.writing != want_write {
+            if want_write {
+                loop_.add_writer_native(this.dst_fd, callback)?;
+            } else {
+                loop_.remove_writer(py, this.dst_fd)?;
+            }
+        }
+        drop(loop_);
+        this.reading = !want_write;
+        this.writing = want_write;
+        Ok(())
+    }
+
+    /// Stop watching both sockets, return them to their transports and settle
+    /// the future: a byte total, an error, or cancelled when `outcome` is None
+    fn finish(
+        slf: &Bound<'_, Self>,
+        outcome: Option<PyResult<usize>>,
+        closing_fd: RawFd,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        let (source, sink, future, src_fd, dst_fd) = {
+            let mut this = slf.borrow_mut();
+            if this.finished {
+                return Ok(());
+            }
+            this.finished = true;
+            this.callback = None;
+            let loop_ = this.loop_.bind(py).borrow();
+            if this.reading {
+                let _ = loop_.remove_reader(py, this.src_fd);
+            }
+            if this.writing {
+                let _ = loop_.remove_writer(py, this.dst_fd);
+            }
+            drop(loop_);
+            this.reading = false;
+            this.writing = false;
+            (
+                this.source.clone_ref(py),
+                this.sink.clone_ref(py),
+                this.future.clone_ref(py),
+                this.src_fd,
+                this.dst_fd,
+            )
+        };
+
+        // Both transports are usable again before the awaiting task resumes
+        if src_fd != closing_fd {
+            source.release(py, true)?;
+        }
+        if dst_fd != closing_fd {
+            sink.release(py, false)?;
+        }
+
+        let future = future.bind(py).borrow();
+        match outcome {
+            Some(Ok(moved)) => future.set_result(py, moved.into_py_any(py)?),
+            Some(Err(e)) => future.set_exception(py, e.into_value(py).into_any()),
+            None => future.cancel(py).map(|_| ()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn splice_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    cloexec_pipe()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn splice_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "splice_to() requires Linux",
+    ))
+}
+
+/// Grow the pipe so each round trip moves more; keeps the default if refused
+#[cfg(target_os = "linux")]
+fn pipe_capacity(pipe: &OwnedFd) -> usize {
+    let fd = pipe.as_raw_fd();
+    unsafe {
+        libc::fcntl(fd, libc::F_SETPIPE_SZ, SPLICE_PIPE_SIZE as libc::c_int);
+        match libc::fcntl(fd, libc::F_GETPIPE_SZ) {
+            n if n > 0 => n as usize,
+            _ => 65536,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pipe_capacity(_pipe: &OwnedFd) -> usize {
+    SPLICE_PIPE_SIZE
+}
+
+#[cfg(target_os = "linux")]
+fn splice_fd(from: RawFd, to: RawFd, len: usize) -> io::Result<usize> {
+    let n = unsafe {
+        libc::splice(
+            from,
+            std::ptr::null_mut(),
+            to,
+            std::ptr::null_mut(),
+            len,
+            libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK,
+        )
+    };
+    if n < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn splice_fd(_from: RawFd, _to: RawFd, _len: usize) -> io::Result<usize> {
+    Err(io::ErrorKind::Unsupported.into())
+}
@@ -1,22 +1,36 @@
 use parking_lot::Mutex;
 use pyo3::buffer::PyBuffer;
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyFrozenSet};
+use rustls::client::Resumption;
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
-use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use rustls::server::{NoServerSessionStorage, ServerSessionMemoryCache};
+use rustls::{ClientConfig, HandshakeKind, RootCertStore, ServerConfig, SupportedCipherSuite};
 use rustls_pemfile::{certs, pkcs8_private_keys, rsa_private_keys};
+use rustc_hash::FxHashMap;
 use std::fs::File;
 use std::io::{BufReader, Read, Write};
 use std::net::TcpStream;
 use std::os::fd::{AsRawFd, RawFd};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
-use crate::buffer_pool::BufferPool;
+use super::flush::FlushWaiter;
 use crate::constants::{DEFAULT_HIGH, DEFAULT_LOW};
 use crate::event_loop::VeloxLoop;
-use crate::transports::{StreamTransport, Transport, TransportState};
+use crate::socket::TcpInfo;
+use crate::transports::future::PendingFuture;
+use crate::transports::stats::{self, TransportStats};
+use crate::transports::tcp::SocketWrapper;
+use crate::transports::timeouts::{IdleTimeouts, TimedTransport};
+use crate::transports::{StreamTransport, Transport, TransportState, call_connection_lost};
 use crate::utils::VeloxResult;
-use bytes::BytesMut;
+use bytes::{Buf, BufMut, BytesMut};
+
+/// Default number of TLS sessions remembered per server port (matches rustls)
+const DEFAULT_SESSION_CACHE_SIZE: usize = 256;
+
+/// Largest TLS record payload; encryption may always run this far ahead
+const MAX_RECORD_PAYLOAD: usize = 16384;
 
 /// SSL/TLS Context for configuring secure connections
 #[pyclass(module = "veloxloop._veloxloop", skip_from_py_object)]
@@ -26,6 +40,13 @@ pub struct SSLContext {
     server_config: Option<Arc<ServerConfig>>,
     purpose: SSLPurpose,
     check_hostname: bool,
+    session_cache_enabled: bool,
+    session_cache_size: usize,
+    /// Client configs with their own resumption store, one per server port.
+    /// rustls keys stored sessions by server name, so together this gives
+    /// session reuse keyed by `server_hostname:port`.
+    resumption_configs: Arc<Mutex<FxHashMap<u16, Arc<ClientConfig>>>>,
+    session_tickets: bool,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -38,7 +59,7 @@ enum SSLPurpose {
 impl SSLContext {
     /// Create a new SSL context for client connections
     #[staticmethod]
-    fn create_client_context(py: Python<'_>) -> PyResult<Py<SSLContext>> {
+    pub(crate) fn create_client_context(py: Python<'_>) -> PyResult<Py<SSLContext>> {
         let mut root_store = RootCertStore::empty();
 
         // Load system root certificates
@@ -61,6 +82,10 @@ impl SSLContext {
             server_config: None,
             purpose: SSLPurpose::ServerAuth,
             check_hostname: true,
+            session_cache_enabled: true,
+            session_cache_size: DEFAULT_SESSION_CACHE_SIZE,
+            resumption_configs: Arc::new(Mutex::new(FxHashMap::default())),
+            session_tickets: true,
         };
 
         Py::new(py, ctx)
@@ -74,6 +99,10 @@ impl SSLContext {
             server_config: None, // Will be configured with load_cert_chain
             purpose: SSLPurpose::ClientAuth,
             check_hostname: false,
+            session_cache_enabled: true,
+            session_cache_size: DEFAULT_SESSION_CACHE_SIZE,
+            resumption_configs: Arc::new(Mutex::new(FxHashMap::default())),
+            session_tickets: true,
         };
 
         Py::new(py, ctx)
@@ -137,7 +166,7 @@ impl SSLContext {
         };
 
         // Build server config
-        let config = ServerConfig::builder()
+        let mut config = ServerConfig::builder()
             .with_no_client_auth()
             .with_single_cert(cert_chain, private_key_der)
             .map_err(|e| {
@@ -147,10 +176,47 @@ impl SSLContext {
                 ))
             })?;
 
+        self.apply_session_tickets(&mut config)?;
         self.server_config = Some(Arc::new(config));
         Ok(())
     }
 
+    /// Enable or disable client-side session resumption.
+    /// `size` is the number of sessions remembered per server port.
+    #[pyo3(signature = (enabled, size=None))]
+    fn set_session_cache(&mut self, enabled: bool, size: Option<usize>) -> PyResult<()> {
+        if size == Some(0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "size must be greater than 0",
+            ));
+        }
+        self.session_cache_enabled = enabled;
+        if let Some(size) = size {
+            self.session_cache_size = size;
+        }
+        // Drop cached sessions; they were created with the old settings
+        self.resumption_configs.lock().clear();
+        Ok(())
+    }
+
+    /// Enable or disable TLS session tickets (server contexts only).
+    /// Tickets are enabled by default.
+    fn set_session_tickets(&mut self, enabled: bool) -> PyResult<()> {
+        self.session_tickets = enabled;
+        self.rebuild_server_config()
+    }
+
+    /// Replace the session ticket key; tickets issued with the old key can no
+    /// longer be used for resumption.
+    fn rotate_session_ticket_key(&mut self) -> PyResult<()> {
+        if self.server_config.is_none() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "SSL context not configured for server connections",
+            ));
+        }
+        self.rebuild_server_config()
+    }
+
     /// Set whether to check hostname (client contexts only)
     fn set_check_hostname(&mut self, check: bool) {
         self.check_hostname = check;
@@ -191,6 +257,7 @@ impl SSLContext {
                 .with_no_client_auth();
 
             self.client_config = Some(Arc::new(config));
+            self.resumption_configs.lock().clear();
         }
 
         if capath.is_some() {
@@ -212,6 +279,75 @@ impl SSLContext {
     }
 }
 
+impl SSLContext {
+    /// Client config to use for a connection to `port`, with its own session
+    /// cache when resumption is enabled
+    fn client_config_for(&self, port: u16) -> Option<Arc<ClientConfig>> {
+        let base = self.client_config.as_ref()?;
+        if !self.session_cache_enabled {
+            let mut config = (**base).clone();
+            config.resumption = Resumption::disabled();
+            return Some(Arc::new(config));
+        }
+        let mut configs = self.resumption_configs.lock();
+        let config = configs.entry(port).or_insert_with(|| {
+            let mut config = (**base).clone();
+            config.resumption = Resumption::in_memory_sessions(self.session_cache_size);
+            Arc::new(config)
+        });
+        Some(config.clone())
+    }
+
+    /// Install (or remove) the ticketer and session storage on a server config.
+    /// A fresh ticketer means a fresh ticket key.
+    fn apply_session_tickets(&self, config: &mut ServerConfig) -> PyResult<()> {
+        if self.session_tickets {
+            config.ticketer = rustls::crypto::ring::Ticketer::new().map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Failed to create session ticketer: {}",
+                    e
+                ))
+            })?;
+            config.session_storage = ServerSessionMemoryCache::new(DEFAULT_SESSION_CACHE_SIZE);
+            config.send_tls13_tickets = 2;
+        } else {
+            config.session_storage = Arc::new(NoServerSessionStorage {});
+            config.send_tls13_tickets = 0;
+        }
+        Ok(())
+    }
+
+    fn rebuild_server_config(&mut self) -> PyResult<()> {
+        if let Some(current) = self.server_config.as_ref() {
+            let mut config = (**current).clone();
+            self.apply_session_tickets(&mut config)?;
+            self.server_config = Some(Arc::new(config));
+        }
+        Ok(())
+    }
+}
+
+/// Build the stdlib-style `(name, protocol, secret_bits)` cipher tuple
+fn cipher_info(
+    suite: SupportedCipherSuite,
+    version: Option<rustls::ProtocolVersion>,
+) -> (String, Option<String>, u32) {
+    // IANA names; rustls spells the TLS 1.3 ones "TLS13_..." instead of "TLS_..."
+    let name = suite
+        .suite()
+        .as_str()
+        .map(|name| name.replacen("TLS13_", "TLS_", 1))
+        .unwrap_or_else(|| format!("{:?}", suite.suite()));
+    // ssl.SSLSocket.cipher() reports versions as "TLSv1.3", rustls as "TLSv1_3"
+    let protocol = version.and_then(|v| v.as_str()).map(|v| v.replace('_', "."));
+    // The bulk cipher's key, as OpenSSL's SSL_CIPHER_get_bits() reports it
+    let key_len = match suite {
+        SupportedCipherSuite::Tls13(suite) => suite.aead_alg.key_len(),
+        SupportedCipherSuite::Tls12(suite) => suite.aead_alg.key_block_shape().enc_key_len,
+    };
+    (name, protocol, key_len as u32 * 8)
+}
+
 /// TLS-wrapped transport
 #[pyclass(module = "veloxloop._veloxloop")]
 pub struct SSLTransport {
@@ -220,13 +356,28 @@ pub struct SSLTransport {
     protocol: Py<PyAny>,
     loop_: Py<VeloxLoop>,
     state: TransportState,
+    /// Plaintext not yet handed to rustls: written mid-handshake, or beyond
+    /// what may be encrypted ahead of the socket
     write_buffer: BytesMut,
+    /// TLS records waiting for the socket
+    tls_out: BytesMut,
+    /// Whether `_write_ready` is registered as the socket's writer
+    writer_registered: bool,
     write_buffer_high: usize,
     write_buffer_low: usize,
     #[allow(dead_code)]
     server_hostname: Option<String>,
     ssl_context: Py<SSLContext>,
     handshake_complete: bool,
+    /// `create_connection` future, settled once the client handshake ends
+    handshake_waiter: Option<Py<PendingFuture>>,
+    /// Timer enforcing `ssl_handshake_timeout`
+    handshake_timer: Option<u64>,
+    stats: TransportStats,
+    /// Read/write idle timeouts, off unless `set_timeouts` was called
+    idle: Option<IdleTimeouts>,
+    /// `get_extra_info('socket')`, made on first request
+    socket: OnceLock<Py<SocketWrapper>>,
 }
 
 struct TlsState {
@@ -257,13 +408,43 @@ impl TlsConnection {
         }
     }
 
-    fn write_tls(&mut self, stream: &mut TcpStream) -> std::io::Result<()> {
+    fn write_tls(&mut self, out: &mut dyn Write) -> std::io::Result<usize> {
         match self {
-            TlsConnection::Client(conn) => conn.write_tls(stream).map(|_| ()),
-            TlsConnection::Server(conn) => conn.write_tls(stream).map(|_| ()),
+            TlsConnection::Client(conn) => conn.write_tls(out),
+            TlsConnection::Server(conn) => conn.write_tls(out),
         }
     }
 
+    /// Move every record rustls has ready onto the end of `out`
+    fn drain_into(&mut self, out: &mut BytesMut) -> std::io::Result<()> {
+        let mut out = out.writer();
+        while self.wants_write() {
+            self.write_tls(&mut out)?;
+        }
+        Ok(())
+    }
+
+    /// Encrypt the front of `data` into `out` until `out` holds `limit` bytes,
+    /// returning how much of `data` was taken
+    fn encrypt_into(
+        &mut self,
+        data: &[u8],
+        out: &mut BytesMut,
+        limit: usize,
+    ) -> std::io::Result<usize> {
+        let mut taken = 0;
+        while taken < data.len() && out.len() < limit {
+            let end = data.len().min(taken + limit - out.len());
+            let n = self.writer().write(&data[taken..end])?;
+            self.drain_into(out)?;
+            if n == 0 {
+                break;
+            }
+            taken += n;
+        }
+        Ok(taken)
+    }
+
     fn wants_write(&self) -> bool {
         match self {
             TlsConnection::Client(conn) => conn.wants_write(),
@@ -298,6 +479,27 @@ impl TlsConnection {
             TlsConnection::Server(conn) => conn.peer_certificates().map(|c| c.to_vec()),
         }
     }
+
+    fn handshake_kind(&self) -> Option<HandshakeKind> {
+        match self {
+            TlsConnection::Client(conn) => conn.handshake_kind(),
+            TlsConnection::Server(conn) => conn.handshake_kind(),
+        }
+    }
+
+    fn negotiated_cipher_suite(&self) -> Option<SupportedCipherSuite> {
+        match self {
+            TlsConnection::Client(conn) => conn.negotiated_cipher_suite(),
+            TlsConnection::Server(conn) => conn.negotiated_cipher_suite(),
+        }
+    }
+
+    fn protocol_version(&self) -> Option<rustls::ProtocolVersion> {
+        match self {
+            TlsConnection::Client(conn) => conn.protocol_version(),
+            TlsConnection::Server(conn) => conn.protocol_version(),
+        }
+    }
 }
 
 // Implement Transport trait for SSLTransport
@@ -323,8 +525,22 @@ impl crate::transports::Transport for SSLTransport {
                 }
                 Ok(default.unwrap_or_else(|| py.None()))
             }
+            "tcp_info" => {
+                if let Some(info) = self.tcp_info() {
+                    return info.to_dict(py);
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            "socket" => {
+                if !self.state.contains(TransportState::CLOSED) {
+                    let state = self.tls_state.lock();
+                    if let Some(socket) = SocketWrapper::cached(py, &self.socket, &state.stream)? {
+                        return Ok(socket);
+                    }
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
             "sslcontext" => Ok(self.ssl_context.clone_ref(py).into_any()),
-            "ssl_object" => Ok(py.None()),
             "peercert" => {
                 let state = self.tls_state.lock();
                 let conn = &state.connection;
@@ -336,8 +552,23 @@ impl crate::transports::Transport for SSLTransport {
                 }
                 Ok(default.unwrap_or_else(|| py.None()))
             }
-            "cipher" => Ok(default.unwrap_or_else(|| py.None())),
+            "cipher" => {
+                let state = self.tls_state.lock();
+                let conn = &state.connection;
+                if let Some(suite) = conn.negotiated_cipher_suite() {
+                    let info = cipher_info(suite, conn.protocol_version());
+                    return Ok(info.into_pyobject(py)?.into_any().unbind());
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            // TLS compression is never negotiated (rustls doesn't support it)
             "compression" => Ok(default.unwrap_or_else(|| py.None())),
+            "session_reused" => {
+                let state = self.tls_state.lock();
+                let reused = state.connection.handshake_kind() == Some(HandshakeKind::Resumed);
+                Ok(pyo3::types::PyBool::new(py, reused).to_owned().into_any().unbind())
+            }
+            "veloxloop_stats" => self.stats.to_dict(py),
             _ => Ok(default.unwrap_or_else(|| py.None())),
         }
     }
@@ -349,6 +580,10 @@ impl crate::transports::Transport for SSLTransport {
     fn get_fd(&self) -> RawFd {
         self.fd
     }
+
+    fn stats(&self) -> &TransportStats {
+        &self.stats
+    }
 }
 
 // Implement StreamTransport trait for SSLTransport
@@ -362,7 +597,7 @@ impl crate::transports::StreamTransport for SSLTransport {
 
         self.state.insert(TransportState::CLOSING);
 
-        if self.write_buffer.is_empty() {
+        if self.pending_bytes() == 0 {
             self.force_close(py)?;
         }
         Ok(())
@@ -393,24 +628,20 @@ impl crate::transports::StreamTransport for SSLTransport {
         let len = buf_view.len_bytes();
         let data_slice = unsafe { std::slice::from_raw_parts(ptr, len) };
 
-        self.write_buffer.extend_from_slice(data_slice);
-
-        let mut state = self.tls_state.lock();
-        let mut writer = state.connection.writer();
-
-        match writer.write_all(data_slice) {
-            Ok(_) => {
-                drop(writer);
-                // Split the mutable borrows by destructuring
-                let TlsState { connection, stream } = &mut *state;
-                match connection.write_tls(stream) {
-                    Ok(_) => Ok(()),
-                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(()),
-                    Err(e) => Err(e.into()),
-                }
-            }
-            Err(e) => Err(e.into()),
-        }
+        // Encrypt straight from the caller's buffer when nothing is queued ahead
+        // of it; only what doesn't fit under the limit is copied into
+        // `write_buffer`, and sending is left to `flush_tls`
+        let limit = self.encrypt_ahead();
+        let connection = &mut self.tls_state.get_mut().connection;
+        let taken = if self.write_buffer.is_empty() && !connection.is_handshaking() {
+            connection.encrypt_into(data_slice, &mut self.tls_out, limit)?
+        } else {
+            0
+        };
+        self.write_buffer.extend_from_slice(&data_slice[taken..]);
+        // Plaintext accepted; `flush_tls` stamps progress as records go out
+        self.stats.count_bytes_out(len);
+        Ok(())
     }
 
     fn recv_into(&mut self, _py: Python<'_>, buffer: Bound<'_, PyAny>) -> PyResult<usize> {
@@ -430,149 +661,72 @@ impl crate::transports::StreamTransport for SSLTransport {
         // Rustls reader will write directly into it.
         let slice_mut = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
         match reader.read(slice_mut) {
-            Ok(n) => Ok(n),
+            Ok(n) => {
+                self.stats.add_bytes_in(n);
+                Ok(n)
+            }
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(0),
-            Err(e) => Err(e.into()),
+            Err(e) => Err(crate::utils::os_error_to_pyerr(e)),
         }
     }
 
+    /// Shutting down the raw stream would cut the TLS session off mid-record
     fn write_eof(&mut self) -> PyResult<()> {
-        let state = self.tls_state.lock();
-        state.stream.shutdown(std::net::Shutdown::Write)?;
-        Ok(())
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "SSL doesn't support half-closes",
+        ))
     }
 
     fn get_write_buffer_size(&self) -> usize {
-        self.write_buffer.len()
+        self.pending_bytes()
+    }
+
+    fn get_write_buffer_limits(&self) -> (usize, usize) {
+        (self.write_buffer_low, self.write_buffer_high)
     }
 
     fn set_write_buffer_limits(
         &mut self,
         py: Python<'_>,
-        high: Option<usize>,
-        low: Option<usize>,
+        high: Option<isize>,
+        low: Option<isize>,
     ) -> PyResult<()> {
-        let high_limit = high.unwrap_or(DEFAULT_HIGH);
-        let low_limit = low.unwrap_or_else(|| if high_limit == 0 { 0 } else { high_limit / 4 });
-
-        // Special case: high=0 means disable flow control (both should be 0)
-        // Otherwise, validate that low < high
-        if high_limit > 0 && low_limit >= high_limit {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                "low must be less than high",
-            ));
-        }
-
+        let (high_limit, low_limit) = super::write_buffer_limits(high, low)?;
         self.write_buffer_high = high_limit;
         self.write_buffer_low = low_limit;
 
-        if high_limit > 0 && self.write_buffer.len() > self.write_buffer_high {
-            let _ = self.protocol.call_method0(py, "pause_writing");
-        }
-
-        Ok(())
-    }
-
-    fn read_ready(&mut self, py: Python<'_>) -> PyResult<()> {
-        let mut state = self.tls_state.lock();
-
-        // Read TLS records - split the mutable borrows
-        let result = {
-            let TlsState { connection, stream } = &mut *state;
-            connection.process_tls_records(stream)
-        };
-
-        match result {
-            Ok(_) => {}
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
-            Err(e) => return Err(e.into()),
-        }
-
-        // Handle handshake
-        if state.connection.is_handshaking() {
-            if state.connection.wants_write() {
-                let TlsState { connection, stream } = &mut *state;
-                match connection.write_tls(stream) {
-                    Ok(_) => {}
-                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
-                    Err(e) => return Err(e.into()),
-                }
-            }
-
-            if !state.connection.is_handshaking() && !self.handshake_complete {
-                drop(state);
-                self.handshake_complete = true;
-                self.protocol
-                    .call_method1(py, "connection_made", (py.None(),))?;
-            }
+        if high_limit > 0
+            && self.pending_bytes() > self.write_buffer_high
+            && !self.state.contains(TransportState::WRITING_PAUSED)
+        {
+            self.state.insert(TransportState::WRITING_PAUSED);
+        } else {
             return Ok(());
         }
-
-        // Read application data
-        let mut reader = state.connection.reader();
-        let mut pbuf = BufferPool::acquire();
-        pbuf.reserve(16384); // Standard TLS record size
-        let len = pbuf.len();
-        let cap = pbuf.capacity();
-        let slice =
-            unsafe { std::slice::from_raw_parts_mut(pbuf.as_mut_ptr().add(len), cap - len) };
-
-        match reader.read(slice) {
-            Ok(0) => {
-                drop(reader);
-                drop(state);
-                BufferPool::release(pbuf);
-                if let Ok(res) = self.protocol.call_method0(py, "eof_received") {
-                    if let Ok(keep_open) = res.extract::<bool>(py) {
-                        if !keep_open {
-                            self.close(py)?;
-                        }
-                    } else {
-                        self.close(py)?;
-                    }
-                } else {
-                    self.close(py)?;
-                }
-            }
-            Ok(n) => {
-                unsafe { pbuf.set_len(len + n) };
-                drop(reader);
-                drop(state);
-
-                // Create VeloxBuffer for zero-copy data passing
-                let velox_buf = crate::streams::VeloxBuffer::from_bytes_mut(pbuf);
-                let py_buf = Py::new(py, velox_buf)?;
-                self.protocol.call_method1(py, "data_received", (py_buf,))?;
-            }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                BufferPool::release(pbuf);
-            }
-            Err(e) => {
-                BufferPool::release(pbuf);
-                return Err(e.into());
-            }
+        if let Err(e) = self.protocol.call_method0(py, "pause_writing") {
+            // Flow-control failures are reported but leave the connection up
+            super::report_protocol_error(
+                py,
+                &self.loop_,
+                "protocol.pause_writing() failed",
+                &e,
+                None,
+                &self.protocol,
+            )?;
         }
 
         Ok(())
     }
 
-    fn write_ready(&mut self, py: Python<'_>) -> PyResult<()> {
-        let mut state = self.tls_state.lock();
+    fn read_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        Self::_read_ready(slf)
+    }
 
-        if state.connection.wants_write() {
-            let TlsState { connection, stream } = &mut *state;
-            match connection.write_tls(stream) {
-                Ok(_) => {
-                    if !connection.wants_write() && self.write_buffer.is_empty() {
-                        drop(state);
-                        self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
-                    }
-                }
-                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
-                Err(e) => return Err(e.into()),
-            }
+    fn write_ready(&mut self, py: Python<'_>) -> PyResult<()> {
+        if !self.flush_tls().map_err(crate::utils::os_error_to_pyerr)? {
+            self.writer_registered = false;
+            self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
         }
-
         Ok(())
     }
 }
@@ -595,12 +749,77 @@ impl SSLTransport {
         StreamTransport::get_write_buffer_size(self)
     }
 
+    /// Names of the set state flags, e.g. `frozenset({'ACTIVE', 'READING_PAUSED'})`;
+    /// the bits are in `veloxloop._veloxloop.constants.TransportState`
+    fn get_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyFrozenSet>> {
+        self.state.to_frozenset(py)
+    }
+
+    fn __repr__(&self) -> String {
+        let buffered = StreamTransport::get_write_buffer_size(self);
+        super::transport_repr("SSLTransport", self.fd, self.state, buffered)
+    }
+
+    /// Smoothed round-trip time in seconds from TCP_INFO, or None if unavailable
+    fn get_rtt(&self) -> Option<f64> {
+        self.tcp_info().map(|info| info.rtt_secs())
+    }
+
+    /// Bytes of TLS records the peer hasn't acknowledged yet, or None when
+    /// closed or unsupported
+    fn get_kernel_write_queue(&self) -> Option<usize> {
+        if self.state.contains(TransportState::CLOSED) {
+            return None;
+        }
+        crate::socket::kernel_write_queue(self.tls_state.lock().stream.as_raw_fd())
+    }
+
+    /// Resolve once the write buffer and the kernel send queue are both empty
+    #[pyo3(signature = (timeout=None))]
+    fn flush(slf: &Bound<'_, Self>, timeout: Option<f64>) -> PyResult<Py<PyAny>> {
+        let loop_ = slf.borrow().loop_.clone_ref(slf.py());
+        FlushWaiter::start(&loop_, slf.clone().into_any(), timeout)
+    }
+
+    /// Close the connection with TimeoutError when no application data
+    /// arrives for `read` seconds while reading, or records waiting for the
+    /// socket make no progress for `write` seconds. None for both turns them off.
+    #[pyo3(signature = (read=None, write=None))]
+    fn set_timeouts(slf: &Bound<'_, Self>, read: Option<f64>, write: Option<f64>) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_.state.contains(TransportState::CLOSED) {
+            return Ok(());
+        }
+        let this = &mut *self_;
+        let loop_ = this.loop_.bind(py).borrow();
+        IdleTimeouts::set(
+            &mut this.idle,
+            &loop_,
+            &this.stats,
+            || TimedTransport::Ssl(slf.clone().unbind()),
+            read,
+            write,
+        )
+    }
+
+    /// `(read, write)` as given to `set_timeouts`
+    fn get_timeouts(&self) -> (Option<f64>, Option<f64>) {
+        self.idle
+            .as_ref()
+            .map_or((None, None), IdleTimeouts::settings)
+    }
+
+    fn get_write_buffer_limits(&self) -> (usize, usize) {
+        StreamTransport::get_write_buffer_limits(self)
+    }
+
     #[pyo3(signature = (high=None, low=None))]
     fn set_write_buffer_limits(
         &mut self,
         py: Python<'_>,
-        high: Option<usize>,
-        low: Option<usize>,
+        high: Option<isize>,
+        low: Option<isize>,
     ) -> PyResult<()> {
         // Delegate to trait implementation
         StreamTransport::set_write_buffer_limits(self, py, high, low)
@@ -611,6 +830,19 @@ impl SSLTransport {
         StreamTransport::write_eof(self)
     }
 
+    /// TLS has no half-close, as in asyncio
+    fn can_write_eof(&self) -> bool {
+        false
+    }
+
+    fn get_protocol(&self, py: Python<'_>) -> Py<PyAny> {
+        self.protocol.clone_ref(py)
+    }
+
+    fn set_protocol(&mut self, protocol: Py<PyAny>) {
+        self.protocol = protocol;
+    }
+
     fn is_closing(&self) -> bool {
         // Delegate to trait implementation
         Transport::is_closing(self)
@@ -621,6 +853,13 @@ impl SSLTransport {
         Transport::get_fd(self)
     }
 
+    /// False once paused or closing, like asyncio's SSL transport
+    fn is_reading(&self) -> bool {
+        !self.state.intersects(
+            TransportState::READING_PAUSED | TransportState::CLOSING | TransportState::CLOSED,
+        )
+    }
+
     fn pause_reading(slf: &Bound<'_, Self>) -> PyResult<()> {
         let py = slf.py();
         let mut self_ = slf.borrow_mut();
@@ -630,6 +869,8 @@ impl SSLTransport {
             let fd = self_.fd;
             let loop_ = self_.loop_.bind(py).borrow();
             loop_.remove_reader(py, fd)?;
+            drop(loop_);
+            stats::emit_fd_event(py, &self_.loop_, "pause_reading", fd);
         }
         Ok(())
     }
@@ -640,6 +881,9 @@ impl SSLTransport {
 
         if self_.state.contains(TransportState::READING_PAUSED) {
             self_.state.remove(TransportState::READING_PAUSED);
+            if let Some(idle) = self_.idle.as_mut() {
+                idle.restart_read();
+            }
             let fd = self_.fd;
             drop(self_); // Drop borrow before calling into loop
 
@@ -649,14 +893,16 @@ impl SSLTransport {
             let self_ = slf.borrow();
             let loop_ = self_.loop_.bind(py).borrow();
             loop_.add_reader_native(fd, read_callback)?;
+            drop(loop_);
+            stats::emit_fd_event(py, &self_.loop_, "resume_reading", fd);
         }
         Ok(())
     }
 
     fn close(slf: &Bound<'_, Self>) -> PyResult<()> {
         let py = slf.py();
-        let mut protocol = None;
-        let mut needs_writer = false;
+        let mut connection_lost = None;
+        let mut needs_flush = false;
 
         {
             let mut self_ = slf.borrow_mut();
@@ -668,62 +914,50 @@ impl SSLTransport {
 
             self_.state.insert(TransportState::CLOSING);
 
-            if self_.write_buffer.is_empty() {
+            if self_.pending_bytes() == 0 {
                 self_._force_close_internal(py)?;
-                protocol = Some(self_.protocol.clone_ref(py));
+                connection_lost = self_.claim_connection_lost(py);
             } else {
-                needs_writer = true;
+                needs_flush = true;
             }
         }
 
         // Notify protocol after dropping borrow
-        if let Some(proto) = protocol {
-            let _ = proto.call_method1(py, "connection_lost", (py.None(),));
+        if let Some(callback) = connection_lost {
+            let loop_ = slf.borrow().loop_.clone_ref(py);
+            call_connection_lost(py, &loop_, &callback, None)?;
         }
 
-        if needs_writer {
-            let fd = slf.borrow().fd;
-            let slf_clone = slf.clone().unbind();
-            let write_callback =
-                Arc::new(move |py: Python<'_>| SSLTransport::_write_ready(&slf_clone.bind(py)));
-            slf.borrow()
-                .loop_
-                .bind(py)
-                .borrow()
-                .add_writer_native(fd, write_callback)?;
+        // Closes once the buffers drain
+        if needs_flush {
+            Self::_write_ready(slf)?;
         }
         Ok(())
     }
 
     fn abort(slf: &Bound<'_, Self>) -> PyResult<()> {
         let py = slf.py();
-        let protocol = {
+        let (connection_lost, loop_) = {
             let mut self_ = slf.borrow_mut();
             self_._force_close_internal(py)?;
-            self_.protocol.clone_ref(py)
+            (self_.claim_connection_lost(py), self_.loop_.clone_ref(py))
         };
-        let _ = protocol.call_method1(py, "connection_lost", (py.None(),));
+        if let Some(callback) = connection_lost {
+            call_connection_lost(py, &loop_, &callback, None)?;
+        }
         Ok(())
     }
 
     fn _force_close(&mut self, py: Python<'_>) -> PyResult<()> {
         self._force_close_internal(py)?;
-        let _ = self
-            .protocol
-            .call_method1(py, "connection_lost", (py.None(),));
+        if let Some(callback) = self.claim_connection_lost(py) {
+            call_connection_lost(py, &self.loop_, &callback, None)?;
+        }
         Ok(())
     }
 
     fn _force_close_internal(&mut self, py: Python<'_>) -> PyResult<()> {
-        let fd = self.fd;
-
-        let loop_ = self.loop_.bind(py).borrow();
-        loop_.remove_reader(py, fd)?;
-        loop_.remove_writer(py, fd)?;
-        drop(loop_);
-
-        // Stream will be dropped when tls_state is dropped
-        Ok(())
+        self.teardown(py, None)
     }
 
     fn write(slf: &Bound<'_, Self>, data: &Bound<'_, PyBytes>) -> PyResult<()> {
@@ -734,159 +968,62 @@ impl SSLTransport {
         StreamTransport::write(&mut *self_mut, py, data.clone().into_any())?;
         drop(self_mut);
 
-        // Try to flush immediately
-        Self::_write_ready(slf)?;
-
-        // Register writer if needed
-        let self_ = slf.borrow();
-        let state = self_.tls_state.lock();
-        let conn = &state.connection;
-        if conn.wants_write() || !self_.write_buffer.is_empty() {
-            let fd = self_.fd;
-            let slf_clone = slf.clone().unbind();
-            let write_callback =
-                Arc::new(move |py: Python<'_>| SSLTransport::_write_ready(&slf_clone.bind(py)));
-            drop(state);
-            drop(self_);
-            let loop_ = slf.borrow().loop_.clone_ref(py);
-            loop_
-                .bind(py)
-                .borrow()
-                .add_writer_native(fd, write_callback)?;
-        }
+        // Send what the socket takes now; the rest waits for writable
+        Self::_write_ready(slf)
+    }
 
-        Ok(())
+    /// Write an iterable of bytes-like objects as one joined buffer
+    fn writelines(slf: &Bound<'_, Self>, lines: &Bound<'_, PyAny>) -> PyResult<()> {
+        Self::write(slf, &super::join_lines(lines)?)
     }
 
     pub(crate) fn _write_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
         let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_.state.contains(TransportState::CLOSED) {
+            return Ok(());
+        }
 
-        // Get necessary data without holding borrows
-        let (fd, loop_ref) = {
-            let self_ = slf.borrow();
-            (self_.fd, self_.loop_.clone_ref(py))
-        };
-
-        // Process writes
-        loop {
-            let (should_write_app_data, should_write_tls, _, _) = {
-                let self_ = slf.borrow();
-                let state = self_.tls_state.lock();
-                let conn = &state.connection;
-
-                let should_write_app = !self_.write_buffer.is_empty();
-                let should_write_tls = conn.wants_write();
-                let buffer_empty = self_.write_buffer.is_empty();
-                let wants_write = conn.wants_write();
-
-                drop(state);
-                drop(self_);
-                (
-                    should_write_app,
-                    should_write_tls,
-                    buffer_empty,
-                    wants_write,
-                )
-            };
-
-            // Write application data to TLS
-            if should_write_app_data {
-                let mut self_ = slf.borrow_mut();
-                let mut state = self_.tls_state.lock();
-                let conn = &mut state.connection;
-
-                if !self_.write_buffer.is_empty() {
-                    let mut writer = conn.writer();
-                    match writer.write(&self_.write_buffer) {
-                        Ok(n) => {
-                            drop(writer);
-                            drop(state);
-                            let _ = self_.write_buffer.split_to(n);
-                        }
-                        Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                            drop(writer);
-                            drop(state);
-                            drop(self_);
-                            break;
-                        }
-                        Err(e) => {
-                            drop(writer);
-                            drop(state);
-                            drop(self_);
-                            return Err(e.into());
-                        }
-                    }
-                } else {
-                    drop(state);
-                    drop(self_);
-                }
-            }
-
-            // Write TLS data to socket
-            if should_write_tls || should_write_app_data {
-                let self_ = slf.borrow_mut();
-                let mut state = self_.tls_state.lock();
-
-                // Use &mut TcpStream from state - split borrow
-                let TlsState {
-                    connection, stream, ..
-                } = &mut *state;
-                match connection.write_tls(stream) {
-                    Ok(_) => {}
-                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                        drop(state);
-                        drop(self_);
-                        break;
-                    }
-                    Err(e) => {
-                        drop(state);
-                        drop(self_);
-                        return Err(e.into());
-                    }
-                }
-                drop(state);
-                drop(self_);
-            } else {
-                break;
-            }
-
-            // Check if we're done
-            let done = {
-                let self_ = slf.borrow();
-                let state = self_.tls_state.lock();
-                let conn = &state.connection;
-                let result = !conn.wants_write() && self_.write_buffer.is_empty();
-                drop(state);
+        let pending = match self_.flush_tls() {
+            Ok(pending) => pending,
+            Err(e) => {
                 drop(self_);
-                result
-            };
-
-            if done {
-                break;
+                return Self::fatal_error(slf, crate::utils::os_error_to_pyerr(e));
             }
-        }
-
-        // Remove writer if nothing left to write
-        let should_remove_writer = {
-            let self_ = slf.borrow();
-            let state = self_.tls_state.lock();
-            let conn = &state.connection;
-            let result = !conn.wants_write() && self_.write_buffer.is_empty();
-            drop(state);
-            drop(self_);
-            result
         };
+        let (fd, loop_ref) = (self_.fd, self_.loop_.clone_ref(py));
+        let flow_control = self_.flow_control_due();
 
-        if should_remove_writer {
+        let registered = std::mem::replace(&mut self_.writer_registered, pending);
+        drop(self_);
+        if pending && !registered {
+            // Records are left over: wait for the socket to become writable
+            let slf_clone = slf.clone().unbind();
+            let write_callback =
+                Arc::new(move |py: Python<'_>| SSLTransport::_write_ready(slf_clone.bind(py)));
+            loop_ref
+                .bind(py)
+                .borrow()
+                .add_writer_native(fd, write_callback)?;
+        } else if !pending {
             loop_ref.bind(py).borrow().remove_writer(py, fd).ok();
+        }
 
-            // Handle final close if in CLOSING state
-            let mut self_ = slf.borrow_mut();
-            if self_.state.contains(TransportState::CLOSING) {
-                self_._force_close_internal(py)?;
-                let protocol = self_.protocol.clone_ref(py);
-                drop(self_); // Drop borrow before calling out
-                let _ = protocol.call_method1(py, "connection_lost", (py.None(),));
+        if let Some(method) = flow_control {
+            Self::call_flow_control(slf, method)?;
+        }
+
+        // Handle final close if in CLOSING state
+        let mut self_ = slf.borrow_mut();
+        if self_.state.contains(TransportState::CLOSING)
+            && !self_.state.contains(TransportState::CLOSED)
+            && self_.pending_bytes() == 0
+        {
+            self_._force_close_internal(py)?;
+            let connection_lost = self_.claim_connection_lost(py);
+            drop(self_); // Drop borrow before calling out
+            if let Some(callback) = connection_lost {
+                call_connection_lost(py, &loop_ref, &callback, None)?;
             }
         }
 
@@ -925,7 +1062,7 @@ impl SSLTransport {
                 Err(e) => {
                     drop(state);
                     drop(self_);
-                    return Err(e.into());
+                    return Self::fatal_error(slf, crate::utils::os_error_to_pyerr(e));
                 }
             }
             drop(state);
@@ -945,10 +1082,29 @@ impl SSLTransport {
 
         if handshake_just_completed {
             slf.borrow_mut().handshake_complete = true;
+            {
+                let self_ = slf.borrow();
+                stats::emit_connection_made(py, &self_.loop_, &*self_);
+            }
 
             // Notify protocol of connection
             let transport_py: Py<PyAny> = slf.clone().unbind().into();
-            protocol.call_method1(py, "connection_made", (transport_py,))?;
+            protocol.call_method1(py, "connection_made", (transport_py.clone_ref(py),))?;
+
+            let (waiter, timer) = {
+                let mut self_ = slf.borrow_mut();
+                (self_.handshake_waiter.take(), self_.handshake_timer.take())
+            };
+            if let Some(timer) = timer {
+                slf.borrow().loop_.bind(py).borrow()._cancel_timer(timer)?;
+            }
+            if let Some(waiter) = waiter {
+                let result = pyo3::types::PyTuple::new(py, [transport_py, protocol])?;
+                let waiter = waiter.bind(py).borrow();
+                if !waiter.done() {
+                    waiter.set_result(py, result.into_any().unbind())?;
+                }
+            }
 
             // Trigger write if needed for handshake completion
             Self::_write_ready(slf)?;
@@ -1002,15 +1158,27 @@ impl SSLTransport {
                     drop(reader);
                     drop(state);
                     drop(self_);
-                    return Err(e.into());
+                    return Self::fatal_error(slf, crate::utils::os_error_to_pyerr(e));
                 }
             }
         };
 
         // Deliver data to protocol
         if let Some(data) = data_read {
+            slf.borrow().stats.add_bytes_in(data.len());
             let py_data = PyBytes::new(py, &data);
-            protocol.call_method1(py, "data_received", (py_data,))?;
+            if let Err(err) = protocol.call_method1(py, "data_received", (py_data,)) {
+                let loop_ = slf.borrow().loop_.clone_ref(py);
+                super::report_protocol_error(
+                    py,
+                    &loop_,
+                    "Fatal error: protocol.data_received() call failed.",
+                    &err,
+                    Some(slf.as_any()),
+                    &protocol,
+                )?;
+                return Self::fatal_error(slf, err);
+            }
         }
 
         // Handle TLS write needs (e.g., post-handshake messages)
@@ -1033,6 +1201,208 @@ impl SSLTransport {
 }
 
 impl SSLTransport {
+    /// Bytes written but not yet taken by the socket: plaintext rustls hasn't
+    /// encrypted plus records waiting to be sent
+    fn pending_bytes(&self) -> usize {
+        self.write_buffer.len() + self.tls_out.len()
+    }
+
+    /// How many bytes of records may wait for the socket before further
+    /// plaintext stays queued unencrypted: the high-water mark, at least a record
+    fn encrypt_ahead(&self) -> usize {
+        self.write_buffer_high.max(MAX_RECORD_PAYLOAD)
+    }
+
+    /// Encrypt queued plaintext and send records until the socket would block.
+    /// Returns whether records are left over, i.e. the socket's writer is needed
+    fn flush_tls(&mut self) -> std::io::Result<bool> {
+        let limit = self.encrypt_ahead();
+        let TlsState { connection, stream } = self.tls_state.get_mut();
+        loop {
+            // Plaintext written during the handshake waits for it to finish
+            if !self.write_buffer.is_empty() && !connection.is_handshaking() {
+                let taken =
+                    connection.encrypt_into(&self.write_buffer, &mut self.tls_out, limit)?;
+                self.write_buffer.advance(taken);
+            }
+            // Handshake messages, alerts and key updates queue behind the data
+            connection.drain_into(&mut self.tls_out)?;
+            if self.tls_out.is_empty() {
+                return Ok(false);
+            }
+
+            self.stats.add_write_call();
+            match stream.write(&self.tls_out) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.tls_out.advance(n);
+                    self.stats.mark_sent();
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(true),
+                Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The flow-control method due now the buffered amount changed:
+    /// `pause_writing` past the high mark, `resume_writing` back at the low one
+    fn flow_control_due(&mut self) -> Option<&'static str> {
+        let pending = self.pending_bytes();
+        let paused = self.state.contains(TransportState::WRITING_PAUSED);
+        if !paused && self.write_buffer_high > 0 && pending > self.write_buffer_high {
+            self.state.insert(TransportState::WRITING_PAUSED);
+            Some("pause_writing")
+        } else if paused && pending <= self.write_buffer_low {
+            self.state.remove(TransportState::WRITING_PAUSED);
+            Some("resume_writing")
+        } else {
+            None
+        }
+    }
+
+    /// Flow-control failures are reported but leave the connection up
+    fn call_flow_control(slf: &Bound<'_, Self>, method: &str) -> PyResult<()> {
+        let py = slf.py();
+        let (protocol, loop_, fd, pending) = {
+            let self_ = slf.borrow();
+            let protocol = self_.protocol.clone_ref(py);
+            (
+                protocol,
+                self_.loop_.clone_ref(py),
+                self_.fd,
+                self_.pending_bytes(),
+            )
+        };
+        if method == "pause_writing" {
+            stats::emit_write_buffer_high(py, &loop_, fd, pending);
+        }
+        match protocol.call_method0(py, method) {
+            Ok(_) => Ok(()),
+            Err(e) => super::report_protocol_error(
+                py,
+                &loop_,
+                &format!("protocol.{method}() failed"),
+                &e,
+                None,
+                &protocol,
+            ),
+        }
+    }
+
+    /// Stop watching the socket and report `connection_lost` to the observer
+    fn teardown(&mut self, py: Python<'_>, exc: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+        let fd = self.fd;
+
+        let loop_ = self.loop_.bind(py).borrow();
+        loop_.remove_reader(py, fd)?;
+        loop_.remove_writer(py, fd)?;
+        drop(loop_);
+
+        if let Some(timer) = self.handshake_timer.take() {
+            self.loop_.bind(py).borrow()._cancel_timer(timer)?;
+        }
+        if let Some(idle) = self.idle.take() {
+            idle.disarm(&self.loop_.bind(py).borrow());
+        }
+
+        if !self.state.contains(TransportState::CLOSED) {
+            self.state.insert(TransportState::CLOSED);
+            stats::emit_connection_lost(py, &self.loop_, self, exc);
+        }
+        SocketWrapper::invalidate(&mut self.socket);
+
+        // Stream will be dropped when tls_state is dropped
+        Ok(())
+    }
+
+    /// The protocol's `connection_lost`, or None when it has already been called.
+    /// Before the handshake the protocol hasn't seen `connection_made`, so like
+    /// asyncio the loss goes to the `create_connection` future instead, if any.
+    fn claim_connection_lost(&mut self, py: Python<'_>) -> Option<Py<PyAny>> {
+        if !self.state.claim_connection_lost() {
+            return None;
+        }
+        if !self.handshake_complete {
+            let waiter = self.handshake_waiter.take()?;
+            return Py::new(py, HandshakeFailed { waiter })
+                .ok()
+                .map(Py::into_any);
+        }
+        self.protocol.getattr(py, "connection_lost").ok()
+    }
+
+    /// Settle `waiter` with `(transport, protocol)` once the handshake
+    /// completes, failing it if the handshake takes more than `timeout` seconds
+    pub(crate) fn wait_for_handshake(
+        slf: &Bound<'_, Self>,
+        waiter: Py<PendingFuture>,
+        timeout: Option<f64>,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        slf.borrow_mut().handshake_waiter = Some(waiter);
+        if let Some(timeout) = timeout {
+            let expiry = Py::new(
+                py,
+                HandshakeTimeout {
+                    transport: slf.clone().unbind(),
+                    timeout,
+                },
+            )?;
+            let timer = slf.borrow().loop_.bind(py).borrow().call_later(
+                timeout,
+                expiry.into_any(),
+                Vec::new(),
+                None,
+            )?;
+            slf.borrow_mut().handshake_timer = Some(timer);
+        }
+        Ok(())
+    }
+
+    /// The idle timeout wheel reached this transport at tick `now`: close it
+    /// if a timeout ran out, else the tick to check again
+    pub(crate) fn idle_expired(slf: &Bound<'_, Self>, now: u64) -> PyResult<Option<u64>> {
+        let checked = {
+            let mut self_ = slf.borrow_mut();
+            let this = &mut *self_;
+            let reading = this.is_reading();
+            let writing = this.pending_bytes() > 0;
+            let Some(idle) = this.idle.as_mut() else {
+                return Ok(None);
+            };
+            idle.check(&this.stats, now, reading, writing)
+        };
+        match checked {
+            Ok(deadline) => Ok(Some(deadline)),
+            Err(err) => {
+                Self::fatal_error(slf, err)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// A TLS or socket operation failed: close and pass the error to
+    /// `connection_lost`, scheduled like asyncio's `_fatal_error`
+    fn fatal_error(slf: &Bound<'_, Self>, err: PyErr) -> PyResult<()> {
+        let py = slf.py();
+        let exc = err.into_value(py).into_any();
+        let mut self_ = slf.borrow_mut();
+        self_.teardown(py, Some(exc.bind(py)))?;
+        if let Some(connection_lost) = self_.claim_connection_lost(py) {
+            self_
+                .loop_
+                .bind(py)
+                .borrow()
+                .call_soon(connection_lost, vec![exc], None);
+        }
+        Ok(())
+    }
+
+    fn tcp_info(&self) -> Option<TcpInfo> {
+        TcpInfo::read(self.tls_state.lock().stream.as_raw_fd())
+    }
+
     pub fn new_client(
         loop_: Py<VeloxLoop>,
         stream: TcpStream,
@@ -1044,17 +1414,15 @@ impl SSLTransport {
         stream.set_nonblocking(true)?;
         let fd = stream.as_raw_fd();
 
-        let client_config = {
-            let ctx = ssl_context.borrow(py);
-            ctx.client_config
-                .as_ref()
-                .ok_or_else(|| {
-                    PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                        "SSL context not configured for client connections",
-                    )
-                })?
-                .clone()
-        };
+        let port = stream.peer_addr().map(|addr| addr.port()).unwrap_or(0);
+        let client_config = ssl_context
+            .borrow(py)
+            .client_config_for(port)
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "SSL context not configured for client connections",
+                )
+            })?;
 
         let server_name = server_hostname.as_ref().ok_or_else(|| {
             PyErr::new::<pyo3::exceptions::PyValueError, _>(
@@ -1085,12 +1453,19 @@ impl SSLTransport {
             protocol,
             loop_,
             state: TransportState::ACTIVE,
-            write_buffer: BytesMut::with_capacity(65536),
+            write_buffer: BytesMut::new(),
+            tls_out: BytesMut::with_capacity(65536),
+            writer_registered: false,
             write_buffer_high: DEFAULT_HIGH,
             write_buffer_low: DEFAULT_LOW,
             server_hostname,
             ssl_context,
             handshake_complete: false,
+            handshake_waiter: None,
+            handshake_timer: None,
+            stats: TransportStats::new(),
+            idle: None,
+            socket: OnceLock::new(),
         })
     }
 
@@ -1132,12 +1507,73 @@ impl SSLTransport {
             protocol,
             loop_,
             state: TransportState::ACTIVE,
-            write_buffer: BytesMut::with_capacity(65536),
+            write_buffer: BytesMut::new(),
+            tls_out: BytesMut::with_capacity(65536),
+            writer_registered: false,
             write_buffer_high: DEFAULT_HIGH,
             write_buffer_low: DEFAULT_LOW,
             server_hostname: None,
             ssl_context,
             handshake_complete: false,
+            handshake_waiter: None,
+            handshake_timer: None,
+            stats: TransportStats::new(),
+            idle: None,
+            socket: OnceLock::new(),
         })
     }
 }
+
+/// Timer callback for `ssl_handshake_timeout`
+#[pyclass(module = "veloxloop._veloxloop")]
+struct HandshakeTimeout {
+    transport: Py<SSLTransport>,
+    timeout: f64,
+}
+
+#[pymethods]
+impl HandshakeTimeout {
+    fn __call__(&self, py: Python<'_>) -> PyResult<()> {
+        let transport = self.transport.bind(py);
+        {
+            let mut this = transport.borrow_mut();
+            this.handshake_timer = None;
+            if this.handshake_complete || this.state.contains(TransportState::CLOSED) {
+                return Ok(());
+            }
+        }
+        // Same wording as asyncio's sslproto
+        let err = PyErr::new::<pyo3::exceptions::PyConnectionAbortedError, _>(format!(
+            "SSL handshake is taking longer than {:?} seconds: aborting the connection",
+            self.timeout
+        ));
+        SSLTransport::fatal_error(transport, err)
+    }
+}
+
+/// Receives the `connection_lost` of a client transport whose handshake never
+/// finished, failing the `create_connection` future with the cause
+#[pyclass(module = "veloxloop._veloxloop")]
+struct HandshakeFailed {
+    waiter: Py<PendingFuture>,
+}
+
+#[pymethods]
+impl HandshakeFailed {
+    fn __call__(&self, py: Python<'_>, exc: Py<PyAny>) -> PyResult<()> {
+        let waiter = self.waiter.bind(py).borrow();
+        if waiter.done() {
+            return Ok(());
+        }
+        let exc = if exc.is_none(py) {
+            PyErr::new::<pyo3::exceptions::PyConnectionResetError, _>(
+                "Connection lost during SSL handshake",
+            )
+            .into_value(py)
+            .into_any()
+        } else {
+            exc
+        };
+        waiter.set_exception(py, exc)
+    }
+}
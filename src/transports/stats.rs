@@ -0,0 +1,217 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use super::Transport;
+use crate::event_loop::VeloxLoop;
+
+/// Lightweight per-connection counters.
+/// Exposed through `get_extra_info("veloxloop_stats")` and the transport observer.
+pub struct TransportStats {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    write_calls: AtomicU64,
+    created_at: Instant,
+    /// The loop's idle-timeout tick, shared once `set_timeouts` is called;
+    /// from then on reads and sends note the tick they happened in
+    idle_clock: OnceLock<Arc<AtomicU64>>,
+    last_read: AtomicU64,
+    last_sent: AtomicU64,
+    /// The loop's running totals, joined while its stats export is enabled
+    traffic: OnceLock<Arc<LoopTraffic>>,
+}
+
+/// Bytes and live connections summed over the transports of a loop, for the
+/// stats export. Transports join when connection_made is reported
+#[derive(Default)]
+pub struct LoopTraffic {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    active: AtomicU64,
+}
+
+impl LoopTraffic {
+    /// `(bytes_in, bytes_out, active_connections)`
+    pub fn totals(&self) -> (u64, u64, u64) {
+        (
+            self.bytes_in.load(Ordering::Relaxed),
+            self.bytes_out.load(Ordering::Relaxed),
+            self.active.load(Ordering::Relaxed),
+        )
+    }
+}
+
+impl TransportStats {
+    pub fn new() -> Self {
+        Self {
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            write_calls: AtomicU64::new(0),
+            created_at: Instant::now(),
+            idle_clock: OnceLock::new(),
+            last_read: AtomicU64::new(0),
+            last_sent: AtomicU64::new(0),
+            traffic: OnceLock::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn add_bytes_in(&self, n: usize) {
+        self.bytes_in.fetch_add(n as u64, Ordering::Relaxed);
+        if let Some(traffic) = self.traffic.get() {
+            traffic.bytes_in.fetch_add(n as u64, Ordering::Relaxed);
+        }
+        self.stamp(&self.last_read);
+    }
+
+    /// Bytes the socket took, which also counts as write progress
+    #[inline(always)]
+    pub fn add_bytes_out(&self, n: usize) {
+        self.count_bytes_out(n);
+        self.stamp(&self.last_sent);
+    }
+
+    /// `add_bytes_out` for bytes accepted but not sent yet (TLS plaintext)
+    #[inline(always)]
+    pub fn count_bytes_out(&self, n: usize) {
+        self.bytes_out.fetch_add(n as u64, Ordering::Relaxed);
+        if let Some(traffic) = self.traffic.get() {
+            traffic.bytes_out.fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Write progress without counting bytes (TLS records reaching the socket)
+    #[inline(always)]
+    pub fn mark_sent(&self) {
+        self.stamp(&self.last_sent);
+    }
+
+    #[inline(always)]
+    fn stamp(&self, last: &AtomicU64) {
+        if let Some(clock) = self.idle_clock.get() {
+            last.store(clock.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+    }
+
+    /// Start noting activity against the loop's idle clock
+    pub(crate) fn watch_idle(&self, clock: &Arc<AtomicU64>) {
+        let _ = self.idle_clock.set(clock.clone());
+    }
+
+    /// Count this transport's traffic in the loop's totals from now on
+    fn join_traffic(&self, traffic: &Arc<LoopTraffic>) {
+        if self.traffic.set(traffic.clone()).is_ok() {
+            traffic.active.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The transport closed: no longer one of the loop's live connections
+    fn leave_traffic(&self) {
+        if let Some(traffic) = self.traffic.get() {
+            traffic.active.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Ticks of the last read and the last send, 0 before `watch_idle`
+    pub(crate) fn last_activity(&self) -> (u64, u64) {
+        (
+            self.last_read.load(Ordering::Relaxed),
+            self.last_sent.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Count one send syscall on the underlying socket
+    #[inline(always)]
+    pub fn add_write_call(&self) {
+        self.write_calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn bytes_in(&self) -> u64 {
+        self.bytes_in.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_out(&self) -> u64 {
+        self.bytes_out.load(Ordering::Relaxed)
+    }
+
+    pub fn write_calls(&self) -> u64 {
+        self.write_calls.load(Ordering::Relaxed)
+    }
+
+    /// Seconds since the transport was created
+    pub fn duration(&self) -> f64 {
+        self.created_at.elapsed().as_secs_f64()
+    }
+
+    /// `{"bytes_in": int, "bytes_out": int, "write_calls": int, "duration": float}`
+    pub fn to_dict(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let dict = PyDict::new(py);
+        dict.set_item("bytes_in", self.bytes_in())?;
+        dict.set_item("bytes_out", self.bytes_out())?;
+        dict.set_item("write_calls", self.write_calls())?;
+        dict.set_item("duration", self.duration())?;
+        Ok(dict.into_any().unbind())
+    }
+}
+
+impl Default for TransportStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Report `("connection_made", fd, peername)` to the loop's transport observer
+pub(crate) fn emit_connection_made<T: Transport + ?Sized>(
+    py: Python<'_>,
+    loop_: &Py<VeloxLoop>,
+    transport: &T,
+) {
+    let loop_ = loop_.bind(py).borrow();
+    if let Some(traffic) = loop_.exported_traffic() {
+        transport.stats().join_traffic(traffic);
+    }
+    loop_.emit_transport_event(py, |py| {
+        let peername = transport.get_extra_info(py, "peername", None)?;
+        ("connection_made", transport.get_fd(), peername).into_pyobject(py)
+    });
+}
+
+/// Report `("connection_lost", fd, exc, bytes_in, bytes_out, duration)`
+pub(crate) fn emit_connection_lost<T: Transport + ?Sized>(
+    py: Python<'_>,
+    loop_: &Py<VeloxLoop>,
+    transport: &T,
+    exc: Option<&Bound<'_, PyAny>>,
+) {
+    transport.stats().leave_traffic();
+    loop_.bind(py).borrow().emit_transport_event(py, |py| {
+        let stats = transport.stats();
+        (
+            "connection_lost",
+            transport.get_fd(),
+            exc,
+            stats.bytes_in(),
+            stats.bytes_out(),
+            stats.duration(),
+        )
+            .into_pyobject(py)
+    });
+}
+
+/// Report a `(name, fd)` event such as `pause_reading` / `resume_reading`
+pub(crate) fn emit_fd_event(py: Python<'_>, loop_: &Py<VeloxLoop>, name: &str, fd: i32) {
+    loop_
+        .bind(py)
+        .borrow()
+        .emit_transport_event(py, |py| (name, fd).into_pyobject(py));
+}
+
+/// Report `("write_buffer_high", fd, size)`
+pub(crate) fn emit_write_buffer_high(py: Python<'_>, loop_: &Py<VeloxLoop>, fd: i32, size: usize) {
+    loop_
+        .bind(py)
+        .borrow()
+        .emit_transport_event(py, |py| ("write_buffer_high", fd, size).into_pyobject(py));
+}
@@ -1,13 +1,24 @@
 use bytes::BytesMut;
 use parking_lot::Mutex;
 use pyo3::prelude::*;
+use pyo3::types::{PyFrozenSet, PyTuple};
 use std::io::{self, Write};
 use std::net::{TcpListener, TcpStream};
 use std::os::fd::{AsRawFd, RawFd};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 
 use super::TransportState;
-use crate::event_loop::VeloxLoop;
+use super::flush::FlushWaiter;
+use super::future::PendingFuture;
+use super::pacing::{PacedTransport, RateLimit};
+use super::splice::{Splice, SpliceEnd};
+use super::stats::{self, TransportStats};
+use super::tcp::SocketWrapper;
+use super::timeouts::{IdleTimeouts, TimedTransport};
+use crate::constants::get_socket;
+use crate::event_loop::{ExceptionContext, VeloxLoop};
+use crate::socket::KeepaliveParams;
 use crate::streams::{StreamReader, StreamWriter};
 use crate::utils::VeloxResult;
 
@@ -25,6 +36,24 @@ pub struct StreamTransport {
     write_buffer: Arc<Mutex<BytesMut>>,
     // Cached write callback for registering writer (native path)
     write_callback: Arc<Mutex<Option<Arc<dyn Fn(Python<'_>) -> PyResult<()> + Send + Sync>>>>,
+    // Bytes requested per recv, see `set_read_chunk_size`
+    read_chunk_size: usize,
+    stats: TransportStats,
+    // Splice reading from this socket / writing into it, see `splice_to`
+    splice_out: Option<Py<Splice>>,
+    splice_in: Option<Py<Splice>>,
+    // Write pacing, off unless `set_rate_limit` was called
+    rate_limit: Mutex<Option<RateLimit>>,
+    // Read/write idle timeouts, off unless `set_timeouts` was called
+    idle: Option<IdleTimeouts>,
+    // Resolved with the socket once `detach` has flushed the write buffer
+    detach_waiter: Option<Py<PendingFuture>>,
+    // `get_extra_info('socket')`, made on first request
+    socket: OnceLock<Py<SocketWrapper>>,
+    // Past the writer's high mark since it was last reported, until the
+    // buffer drains to the low mark; keeps `write_buffer_high` to one event
+    // per crossing
+    above_high: AtomicBool,
 }
 
 /// Native proxy for StreamWriter to trigger writes on StreamTransport
@@ -37,10 +66,74 @@ impl crate::streams::StreamWriterProxy for StreamTransportProxy {
         let t = self.transport.bind(py).borrow();
         t._trigger_write(py)
     }
+
+    fn write_eof(&self, py: Python<'_>) -> PyResult<()> {
+        self.transport.bind(py).borrow_mut().write_eof(py)
+    }
+
+    fn can_write_eof(&self, py: Python<'_>) -> bool {
+        self.transport.bind(py).borrow().can_write_eof()
+    }
+
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        self.transport.bind(py).borrow_mut().close(py)
+    }
 }
 unsafe impl Send for StreamTransportProxy {}
 unsafe impl Sync for StreamTransportProxy {}
 
+impl crate::transports::Transport for StreamTransport {
+    fn get_extra_info(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        match name {
+            "peername" => {
+                if let Some(Ok(addr)) = self.stream.as_ref().map(|s| s.peer_addr()) {
+                    return crate::utils::ipv6::socket_addr_to_tuple(py, addr);
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            "sockname" => {
+                if let Some(Ok(addr)) = self.stream.as_ref().map(|s| s.local_addr()) {
+                    return crate::utils::ipv6::socket_addr_to_tuple(py, addr);
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            "original_dst" => {
+                if let Some(addr) = self.stream.as_ref().and_then(crate::socket::original_dst) {
+                    return crate::utils::ipv6::socket_addr_to_tuple(py, addr);
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            "socket" => {
+                if let Some(stream) = self.stream.as_ref()
+                    && let Some(socket) = SocketWrapper::cached(py, &self.socket, stream)?
+                {
+                    return Ok(socket);
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            "veloxloop_stats" => self.stats.to_dict(py),
+            _ => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    fn is_closing(&self) -> bool {
+        self.state.contains(TransportState::CLOSING) || self.state.contains(TransportState::CLOSED)
+    }
+
+    fn get_fd(&self) -> RawFd {
+        self.fd
+    }
+
+    fn stats(&self) -> &TransportStats {
+        &self.stats
+    }
+}
+
 #[pymethods]
 impl StreamTransport {
     pub fn get_reader(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
@@ -51,6 +144,140 @@ impl StreamTransport {
         Ok(self.writer.clone_ref(py).into_any())
     }
 
+    /// Bytes requested per recv on this transport; takes effect on the next read
+    fn set_read_chunk_size(&mut self, size: usize) -> PyResult<()> {
+        self.read_chunk_size = crate::buffer_pool::check_read_chunk_size(size)?;
+        Ok(())
+    }
+
+    fn get_read_chunk_size(&self) -> usize {
+        self.read_chunk_size
+    }
+
+    /// Cap the send rate at `bytes_per_sec`, with bursts of up to `burst` bytes
+    /// (100ms worth by default). drain() still follows the write buffer
+    /// limits; None removes the limit.
+    #[pyo3(signature = (bytes_per_sec, burst=None))]
+    fn set_rate_limit(
+        slf: &Bound<'_, Self>,
+        bytes_per_sec: Option<u64>,
+        burst: Option<u64>,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        {
+            let self_ = slf.borrow();
+            let loop_ = self_.loop_.bind(py).borrow();
+            let limit = match bytes_per_sec {
+                Some(rate) => Some(RateLimit::new(
+                    py,
+                    &loop_,
+                    PacedTransport::Stream(slf.clone().unbind()),
+                    rate,
+                    burst,
+                )?),
+                None => None,
+            };
+            if let Some(mut old) = std::mem::replace(&mut *self_.rate_limit.lock(), limit) {
+                old.cancel(&loop_);
+            }
+        }
+        Self::resume_paced(slf)
+    }
+
+    /// `(bytes_per_sec, burst)` while a rate limit is set, else None
+    fn get_rate_limit(&self) -> Option<(u64, u64)> {
+        self.rate_limit.lock().as_ref().map(RateLimit::settings)
+    }
+
+    /// Close with TimeoutError when nothing arrives for `read` seconds while
+    /// reading, or buffered writes make no progress for `write` seconds;
+    /// pending reads and drain() fail with it. None for both turns them off.
+    #[pyo3(signature = (read=None, write=None))]
+    fn set_timeouts(slf: &Bound<'_, Self>, read: Option<f64>, write: Option<f64>) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_.state.contains(TransportState::CLOSED) {
+            return Ok(());
+        }
+        let this = &mut *self_;
+        let loop_ = this.loop_.bind(py).borrow();
+        IdleTimeouts::set(
+            &mut this.idle,
+            &loop_,
+            &this.stats,
+            || TimedTransport::Stream(slf.clone().unbind()),
+            read,
+            write,
+        )
+    }
+
+    /// `(read, write)` as given to `set_timeouts`
+    fn get_timeouts(&self) -> (Option<f64>, Option<f64>) {
+        self.idle
+            .as_ref()
+            .map_or((None, None), IdleTimeouts::settings)
+    }
+
+    fn get_write_buffer_size(&self) -> usize {
+        self.write_buffer.lock().len()
+    }
+
+    /// Names of the set state flags, e.g. `frozenset({'ACTIVE', 'READING_PAUSED'})`;
+    /// the bits are in `veloxloop._veloxloop.constants.TransportState`
+    fn get_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyFrozenSet>> {
+        self.state.to_frozenset(py)
+    }
+
+    fn __repr__(&self) -> String {
+        let buffered = self.write_buffer.lock().len();
+        super::transport_repr("StreamTransport", self.fd, self.state, buffered)
+    }
+
+    /// Bytes written to the socket that the peer hasn't acknowledged yet, or
+    /// None when closed or unsupported
+    fn get_kernel_write_queue(&self) -> Option<usize> {
+        self.stream
+            .as_ref()
+            .and_then(|s| crate::socket::kernel_write_queue(s.as_raw_fd()))
+    }
+
+    /// Resolve once the write buffer and the kernel send queue are both empty
+    #[pyo3(signature = (timeout=None))]
+    fn flush(slf: &Bound<'_, Self>, timeout: Option<f64>) -> PyResult<Py<PyAny>> {
+        let loop_ = slf.borrow().loop_.clone_ref(slf.py());
+        FlushWaiter::start(&loop_, slf.clone().into_any(), timeout)
+    }
+
+    /// The StreamWriter's (low, high) water marks
+    fn get_write_buffer_limits(&self, py: Python<'_>) -> (usize, usize) {
+        let writer = self.writer.bind(py).borrow();
+        (writer.low_water, writer.high_water)
+    }
+
+    #[pyo3(signature = (high=None, low=None))]
+    fn set_write_buffer_limits(
+        &self,
+        py: Python<'_>,
+        high: Option<isize>,
+        low: Option<isize>,
+    ) -> PyResult<()> {
+        let (high, low) = crate::transports::write_buffer_limits(high, low)?;
+        let mut writer = self.writer.bind(py).borrow_mut();
+        writer.high_water = high;
+        writer.low_water = low;
+        Ok(())
+    }
+
+    #[pyo3(signature = (name, default=None))]
+    fn get_extra_info(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        crate::transports::Transport::get_extra_info(self, py, name, default)
+    }
+
     fn close(&mut self, py: Python<'_>) -> PyResult<()> {
         if self.state.contains(TransportState::CLOSING)
             || self.state.contains(TransportState::CLOSED)
@@ -59,9 +286,10 @@ impl StreamTransport {
         }
 
         self.state.insert(TransportState::CLOSING);
+        self.cancel_splices(py)?;
 
         // Mark writer as closing
-        self.writer.bind(py).borrow().close()?;
+        self.writer.bind(py).borrow().mark_closing();
 
         // If buffer is empty, close now
         if self.write_buffer.lock().is_empty() {
@@ -75,22 +303,119 @@ impl StreamTransport {
         self._force_close_internal(py)
     }
 
+    /// Close at once, dropping buffered writes. As with asyncio's
+    /// `connection_lost(None)`, the reader sees EOF and drain() waiters return.
+    fn abort(&mut self, py: Python<'_>) -> PyResult<()> {
+        if self.state.contains(TransportState::CLOSED) {
+            return Ok(());
+        }
+        self.write_buffer.lock().clear();
+        let writer = self.writer.bind(py).borrow();
+        writer.flags.lock().closed = true;
+        writer._wakeup_drain_waiters(py)?;
+        drop(writer);
+        self.reader.bind(py).borrow().feed_eof_native(py)?;
+        self._force_close_internal(py)
+    }
+
     fn _force_close_internal(&mut self, py: Python<'_>) -> PyResult<()> {
-        self.state.insert(TransportState::CLOSED);
-        self.state.remove(TransportState::ACTIVE);
-        self.state.remove(TransportState::CLOSING);
+        self.teardown(py, None)
+    }
 
-        if let Some(stream) = self.stream.take() {
-            let loop_ = self.loop_.bind(py).borrow();
-            let _ = loop_.remove_reader(py, self.fd);
-            let _ = loop_.remove_writer(py, self.fd);
-            drop(stream);
+    fn is_closing(&self) -> bool {
+        self.state.contains(TransportState::CLOSING) || self.state.contains(TransportState::CLOSED)
+    }
+
+    fn is_reading(&self) -> bool {
+        !self.state.intersects(
+            TransportState::READING_PAUSED | TransportState::CLOSING | TransportState::CLOSED,
+        )
+    }
+
+    fn pause_reading(&mut self, py: Python<'_>) -> PyResult<()> {
+        if !self.is_reading() {
+            return Ok(());
+        }
+        self.state.insert(TransportState::READING_PAUSED);
+        if self.splice_out.is_some() {
+            // The splice owns the reader; it is left off when the splice ends
+            return Ok(());
         }
+        self.loop_.bind(py).borrow().remove_reader(py, self.fd)?;
+        stats::emit_fd_event(py, &self.loop_, "pause_reading", self.fd);
         Ok(())
     }
 
-    fn is_closing(&self) -> bool {
-        self.state.contains(TransportState::CLOSING) || self.state.contains(TransportState::CLOSED)
+    fn resume_reading(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        {
+            let mut self_ = slf.borrow_mut();
+            if !self_.state.contains(TransportState::READING_PAUSED) {
+                return Ok(());
+            }
+            self_.state.remove(TransportState::READING_PAUSED);
+            if let Some(idle) = self_.idle.as_mut() {
+                idle.restart_read();
+            }
+            if self_.splice_out.is_some() || self_.is_closing() {
+                // Reading comes back when the splice ends; a closing transport stays off
+                return Ok(());
+            }
+        }
+        Self::watch_reads(slf)?;
+        let self_ = slf.borrow();
+        stats::emit_fd_event(py, &self_.loop_, "resume_reading", self_.fd);
+        Ok(())
+    }
+
+    /// There is no protocol: received data goes straight to the StreamReader
+    fn get_protocol(&self, py: Python<'_>) -> Py<PyAny> {
+        py.None()
+    }
+
+    fn set_protocol(&self, _protocol: Py<PyAny>) -> PyResult<()> {
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "StreamTransport feeds its StreamReader directly and has no protocol to replace",
+        ))
+    }
+
+    fn can_write_eof(&self) -> bool {
+        !self.state.intersects(
+            TransportState::CLOSING | TransportState::CLOSED | TransportState::EOF_WRITTEN,
+        )
+    }
+
+    /// Half-close: shut down the write side once the shared buffer is
+    /// flushed. Reading continues until the peer closes.
+    fn write_eof(&mut self, py: Python<'_>) -> PyResult<()> {
+        if self
+            .state
+            .intersects(TransportState::CLOSING | TransportState::CLOSED)
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Transport is closed",
+            ));
+        }
+        if self.state.contains(TransportState::EOF_WRITTEN) {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "write_eof() was already called",
+            ));
+        }
+        if self.splice_in.is_some() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Cannot write_eof while splicing into the transport",
+            ));
+        }
+        self.state.insert(TransportState::EOF_WRITTEN);
+
+        // Sends what it can now and registers the writer for the rest
+        self._trigger_write(py)?;
+        if self.write_buffer.lock().is_empty() {
+            self.shutdown_write()
+        } else {
+            self.state.insert(TransportState::EOF_PENDING);
+            Ok(())
+        }
     }
 
     pub(crate) fn _read_ready(&mut self, py: Python<'_>) -> PyResult<()> {
@@ -103,47 +428,75 @@ impl StreamTransport {
 
         if let Some(stream) = self.stream.as_mut() {
             let reader = self.reader.bind(py).borrow();
-            match reader.read_from_socket(stream) {
+            match reader.read_from_socket(stream, self.read_chunk_size) {
                 Ok(0) => {
                     // Signal EOF to reader and let protocol decide when to close
                     drop(reader);
                     self.reader.bind(py).borrow().feed_eof_native(py)?;
                 }
-                Ok(_) => {}
+                Ok(n) => {
+                    self.stats.add_bytes_in(n);
+                    reader._wakeup_waiters(py)?;
+                }
                 Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
-                Err(e) => return Err(e.into()),
+                Err(e) => {
+                    drop(reader);
+                    return self.fatal_error(py, crate::utils::os_error_to_pyerr(e));
+                }
             }
         }
         Ok(())
     }
 
     pub(crate) fn _write_ready(&mut self, py: Python<'_>) -> PyResult<()> {
+        let mut failure = None;
         if let Some(mut stream) = self.stream.as_ref() {
             loop {
                 let mut buffer = self.write_buffer.lock();
                 if !buffer.is_empty() {
+                    let budget = self.write_budget(py, buffer.len())?;
+                    if budget == 0 {
+                        // Out of tokens: the refill timer puts the writer back
+                        self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
+                        break;
+                    }
                     // Try to write as much as possible
-                    match stream.write(&buffer) {
+                    self.stats.add_write_call();
+                    match stream.write(&buffer[..budget]) {
                         Ok(0) => {
-                            return Err(PyErr::new::<pyo3::exceptions::PyConnectionError, _>(
+                            failure = Some(PyErr::new::<pyo3::exceptions::PyConnectionError, _>(
                                 "Connection closed during write",
                             ));
+                            break;
                         }
                         Ok(n) => {
+                            self.stats.add_bytes_out(n);
+                            self.consume_budget(n);
                             let _ = buffer.split_to(n);
+                            if self.above_high.load(Ordering::Relaxed)
+                                && buffer.len() <= self.writer.bind(py).borrow().get_low_water()
+                            {
+                                self.above_high.store(false, Ordering::Relaxed);
+                            }
                             if buffer.is_empty() {
                                 self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
                                 drop(buffer);
 
+                                // write_eof() was waiting for the queued data
+                                if self.state.contains(TransportState::EOF_PENDING) {
+                                    self.state.remove(TransportState::EOF_PENDING);
+                                    self.shutdown_write()?;
+                                }
+
                                 // Wake up drain waiters
                                 self.writer.bind(py).borrow()._wakeup_drain_waiters(py)?;
 
-                                // If closing and buffer is empty, close now
-                                if self.state.contains(TransportState::CLOSING) {
+                                // If closing and buffer is empty, close now;
+                                // a detach hands the socket over instead
+                                if self.state.contains(TransportState::DETACHING) {
+                                    self.finish_detach(py)?;
+                                } else if self.state.contains(TransportState::CLOSING) {
                                     self._force_close_internal(py)?;
-                                    // Notify StreamWriter it is closed
-                                    let writer = self.writer.bind(py).borrow();
-                                    writer.flags.lock().closed = true;
                                 }
                                 break;
                             }
@@ -152,7 +505,8 @@ impl StreamTransport {
                             break;
                         }
                         Err(e) => {
-                            return Err(e.into());
+                            failure = Some(crate::utils::os_error_to_pyerr(e));
+                            break;
                         }
                     }
                 } else {
@@ -160,12 +514,16 @@ impl StreamTransport {
                 }
             }
         }
-        Ok(())
+        match failure {
+            Some(err) => self.fatal_error(py, err),
+            None => Ok(()),
+        }
     }
 
     /// Trigger write when data is added to buffer (called by StreamWriter)
     fn _trigger_write(&self, py: Python<'_>) -> PyResult<()> {
-        if self.state.contains(TransportState::CLOSED) {
+        if self.state.contains(TransportState::CLOSED) || self.splice_in.is_some() {
+            // While spliced into, writes wait in the buffer until the splice ends
             return Ok(());
         }
 
@@ -174,23 +532,29 @@ impl StreamTransport {
             // Try immediate write first
             if let Some(mut stream) = self.stream.as_ref() {
                 let mut buffer = self.write_buffer.lock();
-                if !buffer.is_empty() {
-                    match stream.write(&buffer) {
+                let budget = self.write_budget(py, buffer.len())?;
+                if budget > 0 {
+                    self.stats.add_write_call();
+                    match stream.write(&buffer[..budget]) {
                         Ok(n) if n > 0 => {
+                            self.stats.add_bytes_out(n);
+                            self.consume_budget(n);
                             let _ = buffer.split_to(n);
                         }
                         _ => {}
                     }
+                }
 
-                    // If still have data, register writer callback
-                    if !buffer.is_empty() {
-                        drop(buffer);
-                        if let Some(callback) = self.write_callback.lock().as_ref() {
-                            self.loop_
-                                .bind(py)
-                                .borrow()
-                                .add_writer_native(self.fd, callback.clone())?;
-                        }
+                // If still have data, register writer callback
+                if !buffer.is_empty() {
+                    let size = buffer.len();
+                    drop(buffer);
+                    let high = self.writer.bind(py).borrow().get_high_water();
+                    if high > 0 && size > high && !self.above_high.swap(true, Ordering::Relaxed) {
+                        stats::emit_write_buffer_high(py, &self.loop_, self.fd, size);
+                    }
+                    if !self.is_pacing_wait() {
+                        self.register_writer(py)?;
                     }
                 }
             }
@@ -220,16 +584,88 @@ impl StreamTransport {
         Ok(())
     }
 
-    fn write(&mut self, _py: Python<'_>, data: &[u8]) -> PyResult<()> {
+    fn write(&mut self, py: Python<'_>, data: &[u8]) -> PyResult<()> {
         if self.state.contains(TransportState::CLOSED) {
             return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
                 "Transport is closed",
             ));
         }
+        if self.state.contains(TransportState::EOF_WRITTEN) {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Cannot write after write_eof",
+            ));
+        }
+        if self.state.contains(TransportState::DETACHING) {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Transport is being detached",
+            ));
+        }
 
-        let mut buffer = self.write_buffer.lock();
-        buffer.extend_from_slice(data);
-        Ok(())
+        self.write_buffer.lock().extend_from_slice(data);
+        self._trigger_write(py)
+    }
+
+    /// Write an iterable of bytes-like objects as one joined buffer
+    fn writelines(&mut self, py: Python<'_>, lines: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.write(py, super::join_lines(lines)?.as_bytes())
+    }
+
+    /// Move bytes from this socket to `other` in the kernel, through a pipe,
+    /// until `count` bytes are moved or the peer sends EOF. Meanwhile nothing
+    /// reaches this transport's StreamReader and writes to `other` are held
+    /// back. Resolves to the number of bytes moved; closing either transport
+    /// cancels it.
+    #[pyo3(signature = (other, count=None))]
+    fn splice_to(
+        slf: &Bound<'_, Self>,
+        other: &Bound<'_, PyAny>,
+        count: Option<usize>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let loop_ = slf.borrow().loop_.clone_ref(py);
+        let source = SpliceEnd::Stream(slf.clone().unbind());
+        let future = Splice::start(py, loop_, source, SpliceEnd::extract(other)?, count)?;
+        Ok(future.into_any())
+    }
+
+    /// Stop using the socket and hand it over, e.g. to run TLS over it
+    /// (STARTTLS) and rebuild streams around that with
+    /// `loop.wrap_socket_streams()`. Reading stops at once: what the
+    /// StreamReader already holds stays readable from it, followed by EOF.
+    /// The write buffer is sent first; the future resolves to the
+    /// connected, non-blocking `socket.socket` after that, or fails if the
+    /// connection is lost meanwhile. The transport and its StreamWriter are
+    /// closed from then on.
+    fn detach(slf: &Bound<'_, Self>) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_.is_closing() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Transport is closing or closed",
+            ));
+        }
+        if self_.splice_out.is_some() || self_.splice_in.is_some() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Cannot detach while splicing",
+            ));
+        }
+        if !self_.state.contains(TransportState::READING_PAUSED) {
+            self_.loop_.bind(py).borrow().remove_reader(py, self_.fd)?;
+        }
+        self_
+            .state
+            .insert(TransportState::CLOSING | TransportState::DETACHING);
+        self_.writer.bind(py).borrow().mark_closing();
+
+        let future = Py::new(py, PendingFuture::new())?;
+        self_.detach_waiter = Some(future.clone_ref(py));
+        if self_.write_buffer.lock().is_empty() {
+            self_.finish_detach(py)?;
+        } else if !self_.is_pacing_wait() {
+            // Normally registered already; the write callback finishes the detach
+            self_.register_writer(py)?;
+        }
+        Ok(future.into_any())
     }
 
     fn fileno(&self) -> RawFd {
@@ -242,6 +678,274 @@ impl StreamTransport {
 }
 
 impl StreamTransport {
+    /// How many of `pending` bytes the rate limit lets out now, all of them
+    /// without one; 0 arms the refill timer
+    fn write_budget(&self, py: Python<'_>, pending: usize) -> PyResult<usize> {
+        match self.rate_limit.lock().as_mut() {
+            Some(limit) => limit.budget(py, &self.loop_.bind(py).borrow(), pending),
+            None => Ok(pending),
+        }
+    }
+
+    fn consume_budget(&self, sent: usize) {
+        if let Some(limit) = self.rate_limit.lock().as_mut() {
+            limit.consume(sent);
+        }
+    }
+
+    /// Waiting for the rate limit's refill timer rather than for writability
+    fn is_pacing_wait(&self) -> bool {
+        self.rate_limit
+            .lock()
+            .as_ref()
+            .is_some_and(RateLimit::is_waiting)
+    }
+
+    fn register_writer(&self, py: Python<'_>) -> PyResult<()> {
+        if let Some(callback) = self.write_callback.lock().as_ref() {
+            self.loop_
+                .bind(py)
+                .borrow()
+                .add_writer_native(self.fd, callback.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Put the writer back for buffered data, e.g. once a paced transport's
+    /// bucket has refilled; a closing transport keeps flushing this way
+    pub(crate) fn resume_paced(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let self_ = slf.borrow();
+        if let Some(limit) = self_.rate_limit.lock().as_mut() {
+            limit.timer_fired();
+        }
+        if self_.state.contains(TransportState::CLOSED)
+            || self_.stream.is_none()
+            || self_.splice_in.is_some()
+            || self_.write_buffer.lock().is_empty()
+            || self_.is_pacing_wait()
+        {
+            return Ok(());
+        }
+        self_.register_writer(slf.py())
+    }
+
+    /// Give reading (`source`) or writing over to a splice
+    pub(crate) fn begin_splice(
+        slf: &Bound<'_, Self>,
+        splice: &Py<Splice>,
+        source: bool,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_
+            .state
+            .intersects(TransportState::CLOSING | TransportState::CLOSED)
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Transport is closing or closed",
+            ));
+        }
+        let slot = if source {
+            &self_.splice_out
+        } else {
+            &self_.splice_in
+        };
+        if slot.is_some() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Transport is already splicing",
+            ));
+        }
+        if source {
+            self_.splice_out = Some(splice.clone_ref(py));
+            self_.loop_.bind(py).borrow().remove_reader(py, self_.fd)?;
+        } else {
+            if !self_.write_buffer.lock().is_empty() {
+                return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                    "Cannot splice into a transport with buffered writes",
+                ));
+            }
+            self_.splice_in = Some(splice.clone_ref(py));
+        }
+        Ok(())
+    }
+
+    /// The splice is over: resume reading, or send what was written meanwhile
+    pub(crate) fn end_splice(slf: &Bound<'_, Self>, source: bool) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if !source {
+            self_.splice_in = None;
+            return self_._trigger_write(py);
+        }
+        self_.splice_out = None;
+        if self_.state.intersects(
+            TransportState::CLOSING | TransportState::CLOSED | TransportState::READING_PAUSED,
+        ) {
+            return Ok(());
+        }
+        drop(self_);
+        Self::watch_reads(slf)
+    }
+
+    /// Register `_read_ready` as the socket's reader
+    fn watch_reads(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let transport = slf.clone().unbind();
+        let read_callback =
+            Arc::new(move |py: Python<'_>| transport.bind(py).borrow_mut()._read_ready(py));
+        let self_ = slf.borrow();
+        self_
+            .loop_
+            .bind(py)
+            .borrow()
+            .add_reader_native(self_.fd, read_callback)
+    }
+
+    /// Closing: a splice through this socket is cancelled before the fd goes away
+    fn cancel_splices(&mut self, py: Python<'_>) -> PyResult<()> {
+        for splice in [self.splice_out.take(), self.splice_in.take()]
+            .into_iter()
+            .flatten()
+        {
+            Splice::cancel(py, &splice, self.fd)?;
+        }
+        Ok(())
+    }
+
+    /// Drop the socket and report `connection_lost` to the transport observer;
+    /// a detach still waiting for its flush fails
+    fn teardown(&mut self, py: Python<'_>, exc: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+        drop(self.release(py, exc)?);
+        if let Some(waiter) = self.detach_waiter.take() {
+            let exc = match exc {
+                Some(exc) => exc.clone().unbind(),
+                None => PyErr::new::<pyo3::exceptions::PyConnectionAbortedError, _>(
+                    "Transport closed before it was detached",
+                )
+                .into_value(py)
+                .into_any(),
+            };
+            waiter.bind(py).borrow().set_exception(py, exc)?;
+        }
+        Ok(())
+    }
+
+    /// The write buffer is flushed: give the socket to the `detach` caller.
+    /// The reader gets EOF after whatever it has buffered.
+    fn finish_detach(&mut self, py: Python<'_>) -> PyResult<()> {
+        let Some(stream) = self.release(py, None)? else {
+            return Ok(());
+        };
+        let fd = std::os::fd::IntoRawFd::into_raw_fd(stream);
+        let kwargs = pyo3::types::PyDict::new(py);
+        kwargs.set_item("fileno", fd)?;
+        let socket = get_socket(py)
+            .bind(py)
+            .getattr("socket")
+            .and_then(|socket| socket.call((), Some(&kwargs)));
+        let socket = match socket {
+            Ok(socket) => Ok(socket.unbind()),
+            Err(err) => {
+                unsafe { libc::close(fd) };
+                Err(err)
+            }
+        };
+        self.reader.bind(py).borrow().feed_eof_native(py)?;
+        if let Some(waiter) = self.detach_waiter.take() {
+            let waiter = waiter.bind(py).borrow();
+            match socket {
+                Ok(socket) => waiter.set_result(py, socket)?,
+                Err(err) => waiter.set_exception(py, err.into_value(py).into_any())?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Everything `teardown` does but dropping the socket, which is returned
+    /// unless it was already gone
+    fn release(
+        &mut self,
+        py: Python<'_>,
+        exc: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Option<TcpStream>> {
+        self.cancel_splices(py)?;
+        if self.state.claim_connection_lost() {
+            stats::emit_connection_lost(py, &self.loop_, self, exc);
+        }
+        self.state.insert(TransportState::CLOSED);
+        self.state
+            .remove(TransportState::ACTIVE | TransportState::CLOSING | TransportState::DETACHING);
+
+        SocketWrapper::invalidate(&mut self.socket);
+        let stream = self.stream.take();
+        if stream.is_some() {
+            let loop_ = self.loop_.bind(py).borrow();
+            let _ = loop_.remove_reader(py, self.fd);
+            let _ = loop_.remove_writer(py, self.fd);
+        }
+        // Also breaks the transport -> wakeup -> transport cycle
+        if let Some(mut limit) = self.rate_limit.get_mut().take() {
+            limit.cancel(&self.loop_.bind(py).borrow());
+        }
+        if let Some(idle) = self.idle.take() {
+            idle.disarm(&self.loop_.bind(py).borrow());
+        }
+        self.writer.bind(py).borrow().mark_closed(py)?;
+        Ok(stream)
+    }
+
+    /// The idle timeout wheel reached this transport at tick `now`: fail it
+    /// if a timeout ran out, else the tick to check again
+    pub(crate) fn idle_expired(slf: &Bound<'_, Self>, now: u64) -> PyResult<Option<u64>> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        let this = &mut *self_;
+        let reading = this.is_reading() && !this.reader.bind(py).borrow().eof_received();
+        let writing = !this.write_buffer.lock().is_empty();
+        let Some(idle) = this.idle.as_mut() else {
+            return Ok(None);
+        };
+        match idle.check(&this.stats, now, reading, writing) {
+            Ok(deadline) => Ok(Some(deadline)),
+            Err(err) => {
+                self_.fatal_error(py, err)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// A read or write failed: fail pending reads with the error, release
+    /// drain() waiters and close. There is no protocol to notify here.
+    fn fatal_error(&mut self, py: Python<'_>, err: PyErr) -> PyResult<()> {
+        let reader = self.reader.bind(py).borrow();
+        reader.set_exception(err.to_string())?;
+        reader._wakeup_waiters(py)?;
+        drop(reader);
+
+        // Unsendable now; clearing it lets drain() waiters return
+        self.write_buffer.lock().clear();
+        let writer = self.writer.bind(py).borrow();
+        writer.flags.lock().closed = true;
+        writer._wakeup_drain_waiters(py)?;
+        drop(writer);
+
+        let exc = err.into_value(py).into_any();
+        self.teardown(py, Some(exc.bind(py)))
+    }
+
+    fn shutdown_write(&self) -> PyResult<()> {
+        if let Some(stream) = self.stream.as_ref() {
+            match stream.shutdown(std::net::Shutdown::Write) {
+                // The peer may already be gone; close() reports that, not write_eof
+                Err(e) if e.kind() != io::ErrorKind::NotConnected => {
+                    return Err(crate::utils::os_error_to_pyerr(e));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     pub fn new(
         py: Python<'_>,
         loop_: Py<VeloxLoop>,
@@ -250,11 +954,13 @@ impl StreamTransport {
         writer: Py<StreamWriter>,
     ) -> VeloxResult<Py<StreamTransport>> {
         stream.set_nonblocking(true)?;
-        stream.set_nodelay(true).expect("set_nodelay call failed"); // lower latency (disable Nagle algorithm)
         let fd = stream.as_raw_fd();
 
+        reader.bind(py).borrow().link_loop(loop_.clone_ref(py));
+
         // Use the writer's buffer directly (shared)
         let writer_obj = writer.bind(py).borrow();
+        writer_obj.link_loop(loop_.clone_ref(py));
         let write_buffer = writer_obj.get_buffer_arc();
 
         let transport = Self {
@@ -266,7 +972,17 @@ impl StreamTransport {
             state: TransportState::ACTIVE,
             write_buffer,
             write_callback: Arc::new(Mutex::new(None)),
+            read_chunk_size: loop_.bind(py).borrow().read_chunk_size.get(),
+            stats: TransportStats::new(),
+            splice_out: None,
+            splice_in: None,
+            rate_limit: Mutex::new(None),
+            idle: None,
+            detach_waiter: None,
+            socket: OnceLock::new(),
+            above_high: AtomicBool::new(false),
         };
+        stats::emit_connection_made(py, &loop_, &transport);
 
         let transport_py = Py::new(py, transport)?;
 
@@ -284,6 +1000,13 @@ impl StreamTransport {
             .replace(write_callback);
 
         // Set the transport proxy in the writer for native trigger_write
+        transport_py
+            .bind(py)
+            .borrow()
+            .writer
+            .bind(py)
+            .borrow()
+            ._set_transport(transport_py.clone_ref(py).into_any());
         let proxy = Arc::new(StreamTransportProxy {
             transport: transport_py.clone_ref(py),
         });
@@ -297,42 +1020,119 @@ impl StreamTransport {
 
         Ok(transport_py)
     }
+
+    /// The `(reader, writer)` pair `open_connection()` returns for a connected
+    /// client socket, with its transport already reading
+    pub(crate) fn open_client(
+        py: Python<'_>,
+        loop_: &Py<VeloxLoop>,
+        stream: TcpStream,
+        limit: usize,
+    ) -> PyResult<Py<PyTuple>> {
+        // Lower latency (disable Nagle algorithm); a Unix socket passed as
+        // sock= has no Nagle to disable. Accepted connections got it from
+        // accept_connection
+        let _ = stream.set_nodelay(true);
+        let chunk_size = loop_.bind(py).borrow().read_chunk_size.get();
+        let reader = Py::new(py, StreamReader::with_chunk_size(Some(limit), chunk_size))?;
+        let writer = Py::new(py, StreamWriter::new(Some(65536), Some(16384)))?;
+        let transport = Self::new(
+            py,
+            loop_.clone_ref(py),
+            stream,
+            reader.clone_ref(py),
+            writer.clone_ref(py),
+        )?;
+
+        let transport_clone = transport.clone_ref(py);
+        let read_callback =
+            Arc::new(move |py: Python<'_>| transport_clone.bind(py).borrow_mut()._read_ready(py));
+        let fd = transport.borrow(py).get_fd();
+        loop_
+            .bind(py)
+            .borrow()
+            .add_reader_native(fd, read_callback)?;
+
+        Ok(PyTuple::new(py, [reader.into_any(), writer.into_any()])?.unbind())
+    }
 }
 
 /// Server that accepts connections and creates StreamReader/StreamWriter pairs
 #[pyclass(module = "veloxloop._veloxloop")]
 pub struct StreamServer {
-    listener: Option<TcpListener>,
+    /// One listener per bound address; empty once closed
+    listeners: Vec<TcpListener>,
     loop_: Py<VeloxLoop>,
     client_connected_cb: Py<PyAny>,
     active: bool,
+    serve_forever_future: Mutex<Option<Py<PendingFuture>>>,
     limit: usize,
+    // Strong refs to running client_connected_cb tasks so they can't be collected mid-flight
+    tasks: Mutex<Vec<Py<PyAny>>>,
+    /// Applied to every accepted connection
+    keepalive: Option<KeepaliveParams>,
+    /// Inside an `async with` block
+    entered: bool,
+}
+
+/// Done callback for a client_connected_cb task: drops the server's reference and
+/// reports a failed handler the way asyncio does (exception handler + close)
+#[pyclass(module = "veloxloop._veloxloop")]
+struct ServerTaskDone {
+    server: Py<StreamServer>,
+    transport: Py<StreamTransport>,
+}
+
+#[pymethods]
+impl ServerTaskDone {
+    fn __call__(&self, py: Python<'_>, task: Bound<'_, PyAny>) -> PyResult<()> {
+        let server = self.server.bind(py).borrow();
+        server.tasks.lock().retain(|t| t.as_ptr() != task.as_ptr());
+
+        if task.call_method0("cancelled")?.is_truthy()? {
+            return Ok(());
+        }
+        let exc = task.call_method0("exception")?;
+        if !exc.is_none() {
+            let _ = self.transport.bind(py).borrow_mut().force_close(py);
+            report_client_error(py, &server.loop_, &exc, &self.transport)?;
+        }
+        Ok(())
+    }
+}
+
+/// Report a client_connected_cb failure without disturbing the accept loop
+fn report_client_error(
+    py: Python<'_>,
+    loop_: &Py<VeloxLoop>,
+    exc: &Bound<'_, PyAny>,
+    transport: &Py<StreamTransport>,
+) -> PyResult<()> {
+    ExceptionContext::new("Unhandled exception in client_connected_cb")
+        .exception(exc)
+        .transport(transport.bind(py))
+        .report(py, &loop_.bind(py).borrow())
 }
 
 #[pymethods]
 impl StreamServer {
+    /// Listening sockets, like `asyncio.Server.sockets`
+    #[getter]
     pub fn sockets(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        if let Some(listener) = self.listener.as_ref() {
-            let addr = listener.local_addr()?;
-            let addr_tuple = crate::utils::ipv6::socket_addr_to_tuple(py, addr)?;
-            let list = pyo3::types::PyList::new(py, vec![addr_tuple])?;
-            Ok(list.into_any().unbind())
-        } else {
-            Ok(pyo3::types::PyList::empty(py).into_any().unbind())
-        }
+        super::listener_sockets(py, &self.listeners)
+    }
+
+    /// Bound address of every listener, in `getsockname()` shape
+    pub fn addresses(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        super::listener_addresses(py, &self.listeners)
     }
 
     pub fn close(&mut self, py: Python<'_>) -> PyResult<()> {
-        if !self.active {
-            return Ok(());
-        }
         self.active = false;
-        if let Some(listener) = self.listener.take() {
-            let fd = listener.as_raw_fd();
-            self.loop_.bind(py).borrow().remove_reader(py, fd)?;
-            drop(listener);
+        for listener in std::mem::take(&mut self.listeners) {
+            self.loop_.bind(py).borrow().remove_reader(py, listener.as_raw_fd())?;
         }
-        Ok(())
+        super::finish_serve_forever(py, &self.serve_forever_future)
     }
 
     pub fn get_loop(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
@@ -343,73 +1143,243 @@ impl StreamServer {
         self.active
     }
 
-    pub fn wait_closed(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        // Return a completed future as we don't have a specific wait mechanism yet
-        let fut = crate::transports::future::CompletedFuture::new(py.None());
-        Ok(Py::new(py, fut)?.into_any())
+    /// Begin accepting connections; a no-op if already serving or closed.
+    /// Registration happens before this returns, so awaiting the result (an
+    /// already resolved future) is optional
+    pub fn start_serving(slf: &Bound<'_, Self>) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        Self::begin_serving(slf)?;
+        let loop_ = slf.borrow().loop_.clone_ref(py);
+        loop_.bind(py).borrow().resolved_future(py, py.None())
     }
 
-    pub fn _on_accept(&self, py: Python<'_>) -> PyResult<()> {
-        if !self.active {
-            return Ok(());
-        }
-
-        if let Some(listener) = self.listener.as_ref() {
-            match listener.accept() {
-                Ok((stream, _addr)) => {
-                    let loop_py = self.loop_.clone_ref(py);
-                    let limit = self.limit;
-
-                    // Create StreamReader and StreamWriter
-                    let reader = Py::new(py, StreamReader::new(Some(limit)))?;
-                    let writer = Py::new(py, StreamWriter::new(None, None))?;
+    /// Start serving if need be; resolves once close() is called
+    pub fn serve_forever(slf: &Bound<'_, Self>) -> PyResult<Py<PyAny>> {
+        let future = {
+            let self_ = slf.borrow();
+            super::serve_forever_future(
+                slf.py(),
+                &self_.serve_forever_future,
+                self_.listeners.is_empty(),
+            )?
+        };
+        Self::begin_serving(slf)?;
+        Ok(future.into_any())
+    }
 
-                    // Create StreamTransport
-                    let _transport = StreamTransport::new(
-                        py,
-                        loop_py.clone_ref(py),
-                        stream,
-                        reader.clone_ref(py),
-                        writer.clone_ref(py),
-                    )?;
+    pub fn wait_closed(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        // Return a resolved future as we don't have a specific wait mechanism yet
+        self.loop_.bind(py).borrow().resolved_future(py, py.None())
+    }
 
-                    let reader_py = reader.into_any();
-                    let writer_py = writer.into_any();
+    pub fn __aenter__(slf: &Bound<'_, Self>) -> PyResult<Py<PyAny>> {
+        let loop_ = slf.borrow().loop_.clone_ref(slf.py());
+        super::enter_server_context(
+            slf.as_any(),
+            &loop_.bind(slf.py()).borrow(),
+            &mut slf.borrow_mut().entered,
+        )
+    }
 
-                    // Call the callback
-                    let result = self.client_connected_cb.call1(py, (reader_py, writer_py))?;
+    /// close() and wait_closed(); resolves to None so an exception raised in
+    /// the block propagates
+    pub fn __aexit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: Py<PyAny>,
+        _exc_val: Py<PyAny>,
+        _exc_tb: Py<PyAny>,
+    ) -> PyResult<Py<PyAny>> {
+        self.entered = false;
+        self.close(py)?;
+        self.wait_closed(py)
+    }
 
-                    // Check if the result is a coroutine and schedule it
-                    if result.bind(py).hasattr("__await__")? {
-                        // It's a coroutine - create a task using the Python loop wrapper
-                        loop_py.call_method1(py, "create_task", (result,))?;
-                    }
-                }
-                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
-                Err(e) => return Err(e.into()),
-            }
+    pub fn _on_accept(slf: &Bound<'_, Self>) -> PyResult<()> {
+        // Registered for every listener, so check them all; an idle one
+        // just reports WouldBlock
+        let count = slf.borrow().listeners.len();
+        for index in 0..count {
+            Self::accept_from(slf, index)?;
         }
         Ok(())
     }
+
+    /// Number of client_connected_cb tasks still running
+    pub fn active_tasks(&self) -> usize {
+        self.tasks.lock().len()
+    }
 }
 
 impl StreamServer {
     pub fn new(
-        listener: TcpListener,
+        listeners: Vec<TcpListener>,
         loop_: Py<VeloxLoop>,
         client_connected_cb: Py<PyAny>,
         limit: usize,
+        keepalive: Option<KeepaliveParams>,
     ) -> Self {
         Self {
-            listener: Some(listener),
+            listeners,
             loop_,
             client_connected_cb,
-            active: true,
+            active: false,
+            serve_forever_future: Mutex::new(None),
             limit,
+            tasks: Mutex::new(Vec::new()),
+            keepalive,
+            entered: false,
+        }
+    }
+
+    /// Put the listeners on the loop; a no-op if already serving or closed
+    pub(crate) fn begin_serving(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let (fds, loop_) = {
+            let mut self_ = slf.borrow_mut();
+            if self_.active || self_.listeners.is_empty() {
+                return Ok(());
+            }
+            self_.active = true;
+            (self_.listener_fds(), self_.loop_.clone_ref(py))
+        };
+        let on_accept = slf.getattr("_on_accept")?.unbind();
+        let loop_ = loop_.bind(py).borrow();
+        for fd in fds {
+            loop_.add_reader(py, fd, on_accept.clone_ref(py))?;
+            loop_.set_fd_priority(fd, true)?;
+        }
+        Ok(())
+    }
+
+    /// Drain listener `index`'s backlog (up to one batch per tick); a failing
+    /// client must not stall the ones behind it
+    fn accept_from(slf: &Bound<'_, Self>, index: usize) -> PyResult<()> {
+        for _ in 0..crate::constants::ACCEPT_BATCH {
+            let (accepted, fd) = {
+                let self_ = slf.borrow();
+                match self_.listeners.get(index) {
+                    Some(listener) if self_.active => (
+                        super::accept_connection(listener, self_.keepalive.as_ref()),
+                        listener.as_raw_fd(),
+                    ),
+                    _ => return Ok(()),
+                }
+            };
+
+            match accepted {
+                // One bad connection must not stop the server: report and drop it
+                Ok(accepted) => {
+                    if let Err(e) = accepted.and_then(|a| Self::serve_client(slf, a.stream)) {
+                        let py = slf.py();
+                        let loop_ = slf.borrow().loop_.clone_ref(py);
+                        ExceptionContext::new(
+                            "Error on transport creation for incoming connection",
+                        )
+                        .exception(e.value(py))
+                        .report(py, &loop_.bind(py).borrow())?;
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => {
+                    let loop_ = slf.borrow().loop_.clone_ref(slf.py());
+                    if !super::accept_failed(slf.py(), &loop_, slf.as_any(), fd, e)? {
+                        return Ok(());
+                    }
+                }
+            }
         }
+        Ok(())
+    }
+
+    /// Wire up one accepted connection and hand it to client_connected_cb.
+    /// Errors from the callback are reported and close only this connection.
+    fn serve_client(slf: &Bound<'_, Self>, stream: TcpStream) -> PyResult<()> {
+        let py = slf.py();
+        let (loop_py, limit, callback) = {
+            let self_ = slf.borrow();
+            (
+                self_.loop_.clone_ref(py),
+                self_.limit,
+                self_.client_connected_cb.clone_ref(py),
+            )
+        };
+
+        let chunk_size = loop_py.bind(py).borrow().read_chunk_size.get();
+        let reader = Py::new(py, StreamReader::with_chunk_size(Some(limit), chunk_size))?;
+        let writer = Py::new(py, StreamWriter::new(None, None))?;
+        let transport = StreamTransport::new(
+            py,
+            loop_py.clone_ref(py),
+            stream,
+            reader.clone_ref(py),
+            writer.clone_ref(py),
+        )?;
+
+        let transport_clone = transport.clone_ref(py);
+        let read_callback =
+            Arc::new(move |py: Python<'_>| transport_clone.bind(py).borrow_mut()._read_ready(py));
+        let fd = transport.borrow(py).get_fd();
+        loop_py.bind(py).borrow().add_reader_native(fd, read_callback)?;
+
+        let scheduled = callback
+            .call1(py, (reader.into_any(), writer.into_any()))
+            .and_then(|result| {
+                if result.bind(py).hasattr("__await__")? {
+                    Self::schedule_handler(slf, &loop_py, result, &transport)
+                } else {
+                    Ok(())
+                }
+            });
+
+        if let Err(e) = scheduled {
+            let _ = transport.bind(py).borrow_mut().force_close(py);
+            report_client_error(py, &loop_py, e.value(py).as_any(), &transport)?;
+        }
+        Ok(())
+    }
+
+    /// Turn the handler coroutine into a task: the loop's task factory, then its
+    /// `create_task` (the Python wrapper), then a plain `asyncio.Task`
+    fn schedule_handler(
+        slf: &Bound<'_, Self>,
+        loop_py: &Py<VeloxLoop>,
+        coro: Py<PyAny>,
+        transport: &Py<StreamTransport>,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        let loop_ = loop_py.bind(py);
+        let factory = loop_
+            .borrow()
+            .task_factory
+            .borrow()
+            .as_ref()
+            .map(|f| f.clone_ref(py));
+
+        let task = if let Some(factory) = factory {
+            factory.bind(py).call1((loop_, coro))?
+        } else if loop_.hasattr("create_task")? {
+            loop_.call_method1("create_task", (coro,))?
+        } else {
+            // ensure_future() would call loop.create_task(), so build the Task directly
+            let kwargs = pyo3::types::PyDict::new(py);
+            kwargs.set_item("loop", loop_)?;
+            crate::constants::get_asyncio(py)
+                .bind(py)
+                .getattr("Task")?
+                .call((coro,), Some(&kwargs))?
+        };
+
+        slf.borrow().tasks.lock().push(task.clone().unbind());
+        let done = ServerTaskDone {
+            server: slf.clone().unbind(),
+            transport: transport.clone_ref(py),
+        };
+        task.call_method1("add_done_callback", (Py::new(py, done)?,))?;
+        Ok(())
     }
 
-    pub(crate) fn get_fd(&self) -> Option<RawFd> {
-        self.listener.as_ref().map(|l| l.as_raw_fd())
+    pub(crate) fn listener_fds(&self) -> Vec<RawFd> {
+        self.listeners.iter().map(|l| l.as_raw_fd()).collect()
     }
 }
@@ -0,0 +1,946 @@
+use bytes::BytesMut;
+use pyo3::buffer::PyBuffer;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyInt};
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::Arc;
+
+use super::stats::TransportStats;
+use super::{Transport, TransportState};
+use crate::buffer_pool::BufferPool;
+use crate::constants::{DEFAULT_HIGH, DEFAULT_LOW};
+use crate::event_loop::{ExceptionContext, VeloxLoop};
+
+// `subprocess` module constants
+const PIPE: i64 = -1;
+const STDOUT: i64 = -2;
+const DEVNULL: i64 = -3;
+
+/// What a child's stdin / stdout / stderr is connected to
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StdioSpec {
+    Inherit,
+    Pipe,
+    DevNull,
+    /// stderr only: share whatever stdout goes to
+    Stdout,
+    Fd(RawFd),
+}
+
+impl StdioSpec {
+    /// Parse a `stdin=` / `stdout=` / `stderr=` argument; a missing argument means PIPE
+    pub(crate) fn from_py(value: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(value) = value else {
+            return Ok(StdioSpec::Pipe);
+        };
+        if value.is_none() {
+            return Ok(StdioSpec::Inherit);
+        }
+        let fd: i64 = match value.extract() {
+            Ok(fd) => fd,
+            Err(_) => value.call_method0("fileno")?.extract()?,
+        };
+        match fd {
+            PIPE => Ok(StdioSpec::Pipe),
+            STDOUT => Ok(StdioSpec::Stdout),
+            DEVNULL => Ok(StdioSpec::DevNull),
+            fd if fd >= 0 => Ok(StdioSpec::Fd(fd as RawFd)),
+            fd => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "invalid stdio value: {}",
+                fd
+            ))),
+        }
+    }
+}
+
+/// `pipe2(O_CLOEXEC)` as (read end, write end)
+pub(crate) fn cloexec_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0 as libc::c_int; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+/// Close-on-exec duplicate of a caller-owned fd
+pub(crate) fn dup_fd(fd: RawFd) -> io::Result<OwnedFd> {
+    unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()
+}
+
+pub(crate) fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags == -1 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn pidfd_open(pid: libc::pid_t) -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
+
+/// Reap the child behind `pidfd` without blocking.
+/// `Ok(None)` while it's still running; otherwise the asyncio-style returncode
+/// (exit status, or minus the signal number that killed it).
+fn pidfd_try_wait(pidfd: RawFd) -> io::Result<Option<i32>> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let rc = unsafe {
+        libc::waitid(
+            libc::P_PIDFD,
+            pidfd as libc::id_t,
+            &mut info,
+            libc::WEXITED | libc::WNOHANG,
+        )
+    };
+    if rc == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    if unsafe { info.si_pid() } == 0 {
+        return Ok(None);
+    }
+    let status = unsafe { info.si_status() };
+    Ok(Some(if info.si_code == libc::CLD_EXITED {
+        status
+    } else {
+        -status
+    }))
+}
+
+/// One end of a child's stdin / stdout / stderr pipe.
+/// Reads go to `protocol.pipe_data_received(fd, data)` on the owning
+/// SubprocessTransport's protocol; stdin supports write / write_eof.
+#[pyclass(module = "veloxloop._veloxloop")]
+pub struct PipeTransport {
+    /// Parent's end of the pipe, -1 once closed
+    fd: Cell<RawFd>,
+    /// 0, 1 or 2: which of the child's stdio this pipe is
+    child_fd: i32,
+    writable: bool,
+    /// Dropped on close, which breaks the transport <-> pipe reference cycle
+    owner: RefCell<Option<Py<SubprocessTransport>>>,
+    loop_: Py<VeloxLoop>,
+    state: Cell<TransportState>,
+    write_buffer: RefCell<BytesMut>,
+    write_buffer_high: Cell<usize>,
+    write_buffer_low: Cell<usize>,
+    read_chunk_size: usize,
+    stats: TransportStats,
+    /// `get_extra_info('pipe')`: an unbuffered file over `fd` that doesn't
+    /// own it, made on first request and closed with the pipe
+    pipe: RefCell<Option<Py<PyAny>>>,
+}
+
+// Safety: only touched from the event loop thread with the GIL held
+unsafe impl Send for PipeTransport {}
+unsafe impl Sync for PipeTransport {}
+
+impl Drop for PipeTransport {
+    fn drop(&mut self) {
+        let fd = self.fd.replace(-1);
+        if fd >= 0 {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+impl Transport for PipeTransport {
+    fn get_extra_info(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        match name {
+            "pipe" => {
+                if let Some(pipe) = self.pipe(py)? {
+                    return Ok(pipe);
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            "veloxloop_stats" => self.stats.to_dict(py),
+            _ => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    fn is_closing(&self) -> bool {
+        self.state
+            .get()
+            .intersects(TransportState::CLOSING | TransportState::CLOSED)
+    }
+
+    fn get_fd(&self) -> RawFd {
+        self.fd.get()
+    }
+
+    fn stats(&self) -> &TransportStats {
+        &self.stats
+    }
+}
+
+impl PipeTransport {
+    fn new(
+        py: Python<'_>,
+        fd: OwnedFd,
+        child_fd: i32,
+        owner: Py<SubprocessTransport>,
+        loop_: Py<VeloxLoop>,
+    ) -> PyResult<Self> {
+        set_nonblocking(fd.as_raw_fd())?;
+        let read_chunk_size = loop_.bind(py).borrow().read_chunk_size.get();
+        Ok(Self {
+            fd: Cell::new(std::os::fd::IntoRawFd::into_raw_fd(fd)),
+            child_fd,
+            writable: child_fd == 0,
+            owner: RefCell::new(Some(owner)),
+            loop_,
+            state: Cell::new(TransportState::ACTIVE),
+            write_buffer: RefCell::new(BytesMut::new()),
+            write_buffer_high: Cell::new(DEFAULT_HIGH),
+            write_buffer_low: Cell::new(DEFAULT_LOW),
+            read_chunk_size,
+            stats: TransportStats::new(),
+            pipe: RefCell::new(None),
+        })
+    }
+
+    fn insert_state(&self, flags: TransportState) {
+        self.state.set(self.state.get() | flags);
+    }
+
+    fn remove_state(&self, flags: TransportState) {
+        self.state.set(self.state.get() - flags);
+    }
+
+    fn owner(&self, py: Python<'_>) -> Option<Py<SubprocessTransport>> {
+        self.owner.borrow().as_ref().map(|o| o.clone_ref(py))
+    }
+
+    /// Call a method on the subprocess protocol, if the pipe still has an owner
+    fn call_protocol<'py, A>(&self, py: Python<'py>, method: &str, args: A) -> PyResult<()>
+    where
+        A: pyo3::call::PyCallArgs<'py>,
+    {
+        let protocol = self
+            .owner(py)
+            .and_then(|owner| owner.bind(py).borrow().protocol(py));
+        if let Some(protocol) = protocol {
+            protocol.call_method1(py, method, args)?;
+        }
+        Ok(())
+    }
+
+    fn report_error(&self, py: Python<'_>, message: &str, err: &PyErr) {
+        let mut context = ExceptionContext::new(message).exception(err.value(py));
+        if let Some(owner) = self.owner(py) {
+            context = context.transport(owner.bind(py));
+        }
+        let _ = context.report(py, &self.loop_.bind(py).borrow());
+    }
+
+    /// The file for `get_extra_info('pipe')`; None once the pipe is closed
+    fn pipe(&self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        let fd = self.fd.get();
+        if fd < 0 {
+            return Ok(None);
+        }
+        let mut pipe = self.pipe.borrow_mut();
+        if pipe.is_none() {
+            let kwargs = pyo3::types::PyDict::new(py);
+            kwargs.set_item("buffering", 0)?;
+            kwargs.set_item("closefd", false)?;
+            let mode = if self.writable { "wb" } else { "rb" };
+            let file = py
+                .import("io")?
+                .call_method("open", (fd, mode), Some(&kwargs))?;
+            *pipe = Some(file.unbind());
+        }
+        Ok(pipe.as_ref().map(|pipe| pipe.clone_ref(py)))
+    }
+
+    /// Stop watching and close the fd, then tell the owner this pipe is gone
+    fn close_pipe(&self, py: Python<'_>, exc: Option<PyErr>) -> PyResult<()> {
+        let fd = self.fd.replace(-1);
+        if fd < 0 {
+            return Ok(());
+        }
+        self.insert_state(TransportState::CLOSING | TransportState::CLOSED);
+        {
+            let loop_ = self.loop_.bind(py).borrow();
+            loop_.remove_reader(py, fd)?;
+            loop_.remove_writer(py, fd)?;
+        }
+        unsafe { libc::close(fd) };
+        self.write_buffer.borrow_mut().clear();
+        if let Some(pipe) = self.pipe.borrow_mut().take() {
+            pipe.call_method0(py, "close")?;
+        }
+
+        let owner = self.owner.borrow_mut().take();
+        if let Some(owner) = owner {
+            SubprocessTransport::pipe_connection_lost(owner.bind(py), self.child_fd, exc)?;
+        }
+        Ok(())
+    }
+
+    fn watch_reads(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let pipe = slf.clone().unbind();
+        let callback = Arc::new(move |py: Python<'_>| Self::_read_ready(pipe.bind(py)));
+        let this = slf.borrow();
+        this.loop_
+            .bind(slf.py())
+            .borrow()
+            .add_reader_native(this.fd.get(), callback)
+    }
+
+    fn _read_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let fd = this.fd.get();
+        if fd < 0 || this.state.get().contains(TransportState::READING_PAUSED) {
+            return Ok(());
+        }
+
+        let mut buf = BufferPool::acquire_sized(this.read_chunk_size);
+        let n = unsafe {
+            libc::read(
+                fd,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                this.read_chunk_size,
+            )
+        };
+        if n > 0 {
+            let n = n as usize;
+            unsafe { buf.set_len(n) };
+            this.stats.add_bytes_in(n);
+            let data = PyBytes::new(py, &buf);
+            BufferPool::release(buf);
+            if let Err(e) = this.call_protocol(py, "pipe_data_received", (this.child_fd, data)) {
+                this.report_error(py, "Fatal error: protocol.pipe_data_received() call failed.", &e);
+                return this.close_pipe(py, Some(e));
+            }
+            return Ok(());
+        }
+        BufferPool::release(buf);
+
+        if n == 0 {
+            return this.close_pipe(py, None);
+        }
+        let err = io::Error::last_os_error();
+        match err.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => Ok(()),
+            _ => this.close_pipe(py, Some(crate::utils::os_error_to_pyerr(err))),
+        }
+    }
+
+    fn _write_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let fd = this.fd.get();
+        if fd < 0 {
+            return Ok(());
+        }
+
+        let result = {
+            let mut buffer = this.write_buffer.borrow_mut();
+            let mut result = Ok(());
+            while !buffer.is_empty() {
+                this.stats.add_write_call();
+                let n = unsafe {
+                    libc::write(fd, buffer.as_ptr() as *const libc::c_void, buffer.len())
+                };
+                if n < 0 {
+                    let err = io::Error::last_os_error();
+                    match err.kind() {
+                        io::ErrorKind::Interrupted => continue,
+                        io::ErrorKind::WouldBlock => {}
+                        _ => result = Err(err),
+                    }
+                    break;
+                }
+                this.stats.add_bytes_out(n as usize);
+                let _ = buffer.split_to(n as usize);
+            }
+            result
+        };
+        if let Err(err) = result {
+            return this.close_pipe(py, Some(crate::utils::os_error_to_pyerr(err)));
+        }
+
+        if this.write_buffer.borrow().is_empty() {
+            this.loop_.bind(py).borrow().remove_writer(py, fd)?;
+            this.maybe_resume_protocol(py)?;
+            if this.state.get().contains(TransportState::CLOSING) {
+                return this.close_pipe(py, None);
+            }
+        } else {
+            this.maybe_resume_protocol(py)?;
+        }
+        Ok(())
+    }
+
+    fn maybe_pause_protocol(&self, py: Python<'_>) -> PyResult<()> {
+        let size = self.write_buffer.borrow().len();
+        if size > self.write_buffer_high.get()
+            && !self.state.get().contains(TransportState::WRITING_PAUSED)
+        {
+            self.insert_state(TransportState::WRITING_PAUSED);
+            if let Err(e) = self.call_protocol(py, "pause_writing", ()) {
+                self.report_error(py, "protocol.pause_writing() failed", &e);
+            }
+        }
+        Ok(())
+    }
+
+    fn maybe_resume_protocol(&self, py: Python<'_>) -> PyResult<()> {
+        let size = self.write_buffer.borrow().len();
+        if size <= self.write_buffer_low.get()
+            && self.state.get().contains(TransportState::WRITING_PAUSED)
+        {
+            self.remove_state(TransportState::WRITING_PAUSED);
+            if let Err(e) = self.call_protocol(py, "resume_writing", ()) {
+                self.report_error(py, "protocol.resume_writing() failed", &e);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_writable(&self) -> PyResult<()> {
+        if !self.writable {
+            return Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+                "read pipe transports don't support writing",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Close the stdin pipe if the child has closed its end (poll reports POLLERR)
+    fn close_if_peer_gone(&self, py: Python<'_>) -> PyResult<()> {
+        let fd = self.fd.get();
+        if fd < 0 || !self.writable {
+            return Ok(());
+        }
+        let mut pfd = libc::pollfd {
+            fd,
+            events: 0,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut pfd, 1, 0) };
+        if ready > 0 && pfd.revents & libc::POLLERR != 0 {
+            let exc = if self.write_buffer.borrow().is_empty() {
+                None
+            } else {
+                Some(PyErr::new::<pyo3::exceptions::PyBrokenPipeError, _>((
+                    libc::EPIPE,
+                    "child closed its stdin",
+                )))
+            };
+            return self.close_pipe(py, exc);
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PipeTransport {
+    #[pyo3(signature = (name, default=None))]
+    fn get_extra_info(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        Transport::get_extra_info(self, py, name, default)
+    }
+
+    fn is_closing(&self) -> bool {
+        Transport::is_closing(self)
+    }
+
+    fn fileno(&self) -> RawFd {
+        self.fd.get()
+    }
+
+    fn is_reading(&self) -> bool {
+        !self.writable
+            && !self
+                .state
+                .get()
+                .intersects(TransportState::CLOSED | TransportState::READING_PAUSED)
+    }
+
+    fn pause_reading(&self, py: Python<'_>) -> PyResult<()> {
+        if !self.is_reading() {
+            return Ok(());
+        }
+        self.insert_state(TransportState::READING_PAUSED);
+        self.loop_.bind(py).borrow().remove_reader(py, self.fd.get())?;
+        Ok(())
+    }
+
+    fn resume_reading(slf: &Bound<'_, Self>) -> PyResult<()> {
+        {
+            let this = slf.borrow();
+            if this.writable
+                || this.fd.get() < 0
+                || !this.state.get().contains(TransportState::READING_PAUSED)
+            {
+                return Ok(());
+            }
+            this.remove_state(TransportState::READING_PAUSED);
+        }
+        Self::watch_reads(slf)
+    }
+
+    fn write(slf: &Bound<'_, Self>, data: Bound<'_, PyAny>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.borrow();
+        this.check_writable()?;
+        let buf_view = PyBuffer::<u8>::get(&data)?;
+        if !buf_view.is_c_contiguous() {
+            return Err(PyErr::new::<pyo3::exceptions::PyBufferError, _>(
+                "Only contiguous buffers are supported",
+            ));
+        }
+        let data = unsafe {
+            std::slice::from_raw_parts(buf_view.buf_ptr() as *const u8, buf_view.len_bytes())
+        };
+        // Like asyncio, writes after close or a lost pipe are dropped silently
+        let fd = this.fd.get();
+        if data.is_empty() || fd < 0 || this.state.get().contains(TransportState::CLOSING) {
+            return Ok(());
+        }
+
+        let mut written = 0;
+        if this.write_buffer.borrow().is_empty() {
+            this.stats.add_write_call();
+            let n = unsafe { libc::write(fd, data.as_ptr() as *const libc::c_void, data.len()) };
+            if n < 0 {
+                let err = io::Error::last_os_error();
+                if !matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                ) {
+                    return this.close_pipe(py, Some(crate::utils::os_error_to_pyerr(err)));
+                }
+            } else {
+                written = n as usize;
+                this.stats.add_bytes_out(written);
+            }
+            if written == data.len() {
+                return Ok(());
+            }
+            let pipe = slf.clone().unbind();
+            let callback = Arc::new(move |py: Python<'_>| Self::_write_ready(pipe.bind(py)));
+            this.loop_.bind(py).borrow().add_writer_native(fd, callback)?;
+        }
+        this.write_buffer
+            .borrow_mut()
+            .extend_from_slice(&data[written..]);
+        this.maybe_pause_protocol(py)
+    }
+
+    fn writelines(slf: &Bound<'_, Self>, lines: Bound<'_, PyAny>) -> PyResult<()> {
+        for line in lines.try_iter()? {
+            Self::write(slf, line?)?;
+        }
+        Ok(())
+    }
+
+    fn can_write_eof(&self) -> bool {
+        self.writable
+    }
+
+    /// Close the child's stdin once buffered data has been written
+    fn write_eof(&self, py: Python<'_>) -> PyResult<()> {
+        self.check_writable()?;
+        if Transport::is_closing(self) {
+            return Ok(());
+        }
+        self.insert_state(TransportState::CLOSING);
+        if self.write_buffer.borrow().is_empty() {
+            self.close_pipe(py, None)?;
+        }
+        Ok(())
+    }
+
+    fn get_write_buffer_size(&self) -> usize {
+        self.write_buffer.borrow().len()
+    }
+
+    fn get_write_buffer_limits(&self) -> (usize, usize) {
+        (self.write_buffer_low.get(), self.write_buffer_high.get())
+    }
+
+    #[pyo3(signature = (high=None, low=None))]
+    fn set_write_buffer_limits(
+        &self,
+        py: Python<'_>,
+        high: Option<isize>,
+        low: Option<isize>,
+    ) -> PyResult<()> {
+        let (high, low) = super::write_buffer_limits(high, low)?;
+        self.write_buffer_high.set(high);
+        self.write_buffer_low.set(low);
+        self.maybe_pause_protocol(py)
+    }
+
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        if self.writable {
+            return self.write_eof(py);
+        }
+        self.close_pipe(py, None)
+    }
+
+    fn abort(&self, py: Python<'_>) -> PyResult<()> {
+        self.close_pipe(py, None)
+    }
+
+    /// The subprocess protocol, which gets this pipe's `pipe_data_received`
+    /// and `pipe_connection_lost`; None once the pipe is closed
+    fn get_protocol(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.owner(py).and_then(|owner| owner.bind(py).borrow().protocol(py))
+    }
+
+    fn set_protocol(&self, _protocol: Py<PyAny>) -> PyResult<()> {
+        Err(PyErr::new::<pyo3::exceptions::PyNotImplementedError, _>(
+            "pipe events go to the subprocess protocol; use SubprocessTransport.set_protocol",
+        ))
+    }
+}
+
+/// A child process started by `loop.subprocess_exec` / `subprocess_shell`.
+/// Exit is noticed through a pidfd registered with the loop's poller, so no
+/// SIGCHLD handler or child watcher thread is involved.
+#[pyclass(module = "veloxloop._veloxloop")]
+pub struct SubprocessTransport {
+    pid: libc::pid_t,
+    /// -1 once the child has been reaped
+    pidfd: Cell<RawFd>,
+    returncode: Cell<Option<i32>>,
+    protocol: RefCell<Option<Py<PyAny>>>,
+    loop_: Py<VeloxLoop>,
+    /// stdin, stdout, stderr; None unless that stream is a PIPE
+    pipes: RefCell<[Option<Py<PipeTransport>>; 3]>,
+    exit_waiters: RefCell<Vec<Py<PyAny>>>,
+    closed: Cell<bool>,
+    finished: Cell<bool>,
+}
+
+// Safety: only touched from the event loop thread with the GIL held
+unsafe impl Send for SubprocessTransport {}
+unsafe impl Sync for SubprocessTransport {}
+
+impl Drop for SubprocessTransport {
+    fn drop(&mut self) {
+        let fd = self.pidfd.replace(-1);
+        if fd >= 0 {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+impl SubprocessTransport {
+    /// Wrap a freshly spawned child: build its pipe transports, run
+    /// `protocol.connection_made`, then start watching the pipes and the pidfd.
+    pub(crate) fn start(
+        py: Python<'_>,
+        loop_: Py<VeloxLoop>,
+        protocol: Py<PyAny>,
+        pid: libc::pid_t,
+        pidfd: OwnedFd,
+        stdio: [Option<OwnedFd>; 3],
+    ) -> PyResult<Py<SubprocessTransport>> {
+        let transport = Py::new(
+            py,
+            Self {
+                pid,
+                pidfd: Cell::new(std::os::fd::IntoRawFd::into_raw_fd(pidfd)),
+                returncode: Cell::new(None),
+                protocol: RefCell::new(Some(protocol.clone_ref(py))),
+                loop_: loop_.clone_ref(py),
+                pipes: RefCell::new([None, None, None]),
+                exit_waiters: RefCell::new(Vec::new()),
+                closed: Cell::new(false),
+                finished: Cell::new(false),
+            },
+        )?;
+
+        let mut pipes: [Option<Py<PipeTransport>>; 3] = [None, None, None];
+        for (child_fd, fd) in stdio.into_iter().enumerate() {
+            if let Some(fd) = fd {
+                let pipe = PipeTransport::new(
+                    py,
+                    fd,
+                    child_fd as i32,
+                    transport.clone_ref(py),
+                    loop_.clone_ref(py),
+                )?;
+                pipes[child_fd] = Some(Py::new(py, pipe)?);
+            }
+        }
+        *transport.borrow(py).pipes.borrow_mut() = pipes;
+
+        // Watch for exit first, so the child still gets reaped if connection_made fails
+        let bound = transport.bind(py);
+        let callback = {
+            let transport = transport.clone_ref(py);
+            Arc::new(move |py: Python<'_>| Self::_pidfd_ready(transport.bind(py)))
+        };
+        loop_
+            .bind(py)
+            .borrow()
+            .add_reader_native(bound.borrow().pidfd.get(), callback)?;
+
+        if let Err(e) = protocol.call_method1(py, "connection_made", (transport.clone_ref(py),)) {
+            let _ = Self::close(bound);
+            return Err(e);
+        }
+
+        for pipe in bound.borrow().pipe_list(py) {
+            if !pipe.borrow(py).writable {
+                PipeTransport::watch_reads(pipe.bind(py))?;
+            }
+        }
+        Ok(transport)
+    }
+
+    fn protocol(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.protocol.borrow().as_ref().map(|p| p.clone_ref(py))
+    }
+
+    fn pipe_list(&self, py: Python<'_>) -> Vec<Py<PipeTransport>> {
+        self.pipes.borrow().iter().flatten().map(|p| p.clone_ref(py)).collect()
+    }
+
+    /// Schedule `protocol.<method>(*args)` on the loop, the way asyncio does
+    fn schedule_protocol_call(&self, py: Python<'_>, method: &str, args: Vec<Py<PyAny>>) {
+        if let Some(protocol) = self.protocol(py)
+            && let Ok(callback) = protocol.getattr(py, method)
+        {
+            self.loop_.bind(py).borrow().call_soon(callback, args, None);
+        }
+    }
+
+    fn pipe_connection_lost(slf: &Bound<'_, Self>, fd: i32, exc: Option<PyErr>) -> PyResult<()> {
+        let py = slf.py();
+        let exc = exc.map_or_else(|| py.None(), |e| e.into_value(py).into_any());
+        slf.borrow().schedule_protocol_call(
+            py,
+            "pipe_connection_lost",
+            vec![fd.into_pyobject(py)?.into_any().unbind(), exc],
+        );
+        Self::try_finish(slf)
+    }
+
+    fn _pidfd_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let pidfd = slf.borrow().pidfd.get();
+        if pidfd < 0 {
+            return Ok(());
+        }
+        let returncode = match pidfd_try_wait(pidfd) {
+            Ok(Some(code)) => code,
+            Ok(None) => return Ok(()),
+            // Someone else reaped it (e.g. a SIGCHLD handler); asyncio reports 255 here
+            Err(e) if e.raw_os_error() == Some(libc::ECHILD) => 255,
+            Err(e) => return Err(crate::utils::os_error_to_pyerr(e)),
+        };
+        Self::process_exited(slf, returncode)
+    }
+
+    fn process_exited(slf: &Bound<'_, Self>, returncode: i32) -> PyResult<()> {
+        let py = slf.py();
+        {
+            let this = slf.borrow();
+            this.returncode.set(Some(returncode));
+            let pidfd = this.pidfd.replace(-1);
+            this.loop_.bind(py).borrow().remove_reader(py, pidfd)?;
+            unsafe { libc::close(pidfd) };
+            this.schedule_protocol_call(py, "process_exited", Vec::new());
+        }
+
+        // The poller doesn't report POLLERR on an idle stdin pipe, so check
+        // now whether the child's exit left it without a reader
+        let stdin = slf.borrow().pipes.borrow()[0]
+            .as_ref()
+            .map(|p| p.clone_ref(py));
+        if let Some(stdin) = stdin {
+            stdin.bind(py).borrow().close_if_peer_gone(py)?;
+        }
+        Self::try_finish(slf)
+    }
+
+    /// Once the child has exited and every pipe is closed, schedule connection_lost
+    fn try_finish(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let this = slf.borrow();
+        if this.returncode.get().is_none() || this.finished.get() {
+            return Ok(());
+        }
+        let all_closed = this
+            .pipe_list(py)
+            .iter()
+            .all(|p| p.bind(py).borrow().fd.get() < 0);
+        if !all_closed {
+            return Ok(());
+        }
+        this.finished.set(true);
+        let callback = slf.getattr("_call_connection_lost")?.unbind();
+        this.loop_.bind(py).borrow().call_soon(callback, Vec::new(), None);
+        Ok(())
+    }
+
+    fn check_proc(&self) -> PyResult<RawFd> {
+        let pidfd = self.pidfd.get();
+        if pidfd < 0 || self.returncode.get().is_some() {
+            return Err(PyErr::new::<pyo3::exceptions::PyProcessLookupError, _>(
+                "process has already exited",
+            ));
+        }
+        Ok(pidfd)
+    }
+}
+
+#[pymethods]
+impl SubprocessTransport {
+    fn get_pid(&self) -> libc::pid_t {
+        self.pid
+    }
+
+    fn get_returncode(&self) -> Option<i32> {
+        self.returncode.get()
+    }
+
+    fn get_pipe_transport(&self, py: Python<'_>, fd: usize) -> Option<Py<PipeTransport>> {
+        self.pipes
+            .borrow()
+            .get(fd)
+            .and_then(|p| p.as_ref().map(|p| p.clone_ref(py)))
+    }
+
+    fn get_protocol(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.protocol(py)
+    }
+
+    fn set_protocol(&self, protocol: Py<PyAny>) {
+        *self.protocol.borrow_mut() = Some(protocol);
+    }
+
+    /// "pid", and "pidfd" until the exit is collected. asyncio's
+    /// "subprocess" holds a Popen, which a child spawned here doesn't have,
+    /// so like any other key it gets `default`
+    #[pyo3(signature = (name, default=None))]
+    fn get_extra_info(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        default: Option<Py<PyAny>>,
+    ) -> PyResult<Py<PyAny>> {
+        match name {
+            "pid" => Ok(PyInt::new(py, self.pid).into_any().unbind()),
+            "pidfd" if self.pidfd.get() >= 0 => {
+                Ok(PyInt::new(py, self.pidfd.get()).into_any().unbind())
+            }
+            _ => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    fn is_closing(&self) -> bool {
+        self.closed.get()
+    }
+
+    /// Signal the child through its pidfd, so a recycled pid is never hit
+    fn send_signal(&self, signal: i32) -> PyResult<()> {
+        let pidfd = self.check_proc()?;
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                pidfd,
+                signal,
+                std::ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        };
+        if rc == -1 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ESRCH) {
+                return Err(PyErr::new::<pyo3::exceptions::PyProcessLookupError, _>(
+                    "process has already exited",
+                ));
+            }
+            return Err(crate::utils::os_error_to_pyerr(err));
+        }
+        Ok(())
+    }
+
+    fn terminate(&self) -> PyResult<()> {
+        self.send_signal(libc::SIGTERM)
+    }
+
+    fn kill(&self) -> PyResult<()> {
+        self.send_signal(libc::SIGKILL)
+    }
+
+    /// Close all pipes and kill the child if it is still running
+    fn close(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let pipes = {
+            let this = slf.borrow();
+            if this.closed.replace(true) {
+                return Ok(());
+            }
+            this.pipe_list(py)
+        };
+        for pipe in pipes {
+            let pipe = pipe.bind(py).borrow();
+            if !Transport::is_closing(&*pipe) {
+                pipe.close(py)?;
+            }
+        }
+        let this = slf.borrow();
+        if this.returncode.get().is_none() {
+            match this.kill() {
+                Ok(()) => {}
+                Err(e) if e.is_instance_of::<pyo3::exceptions::PyProcessLookupError>(py) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Awaitable returning the exit status once the child exited and its pipes closed
+    fn _wait(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let waiter = self.loop_.bind(py).call_method0("create_future")?;
+        if self.finished.get() && self.protocol.borrow().is_none() {
+            waiter.call_method1("set_result", (self.returncode.get(),))?;
+        } else {
+            self.exit_waiters.borrow_mut().push(waiter.clone().unbind());
+        }
+        Ok(waiter.unbind())
+    }
+
+    fn _call_connection_lost(&self, py: Python<'_>) -> PyResult<()> {
+        let protocol = self.protocol.borrow_mut().take();
+        let result = match protocol {
+            Some(protocol) => protocol
+                .call_method1(py, "connection_lost", (py.None(),))
+                .map(|_| ()),
+            None => Ok(()),
+        };
+        let returncode = self.returncode.get();
+        for waiter in self.exit_waiters.borrow_mut().drain(..) {
+            let waiter = waiter.bind(py);
+            if !waiter.call_method0("done")?.is_truthy()? {
+                waiter.call_method1("set_result", (returncode,))?;
+            }
+        }
+        result
+    }
+}
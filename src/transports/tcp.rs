@@ -2,66 +2,120 @@ use bytes::BytesMut;
 use parking_lot::Mutex;
 use pyo3::buffer::PyBuffer;
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyFrozenSet};
 use std::cell::RefCell;
 use std::io::{self, Read, Write};
 use std::net::{SocketAddr, TcpStream};
-use std::os::fd::{AsRawFd, RawFd};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-
-use crate::buffer_pool::BufferPool;
-use crate::constants::{DEFAULT_HIGH, DEFAULT_LOW, RECV_BUF_SIZE};
-use crate::event_loop::VeloxLoop;
-use crate::transports::DefaultTransportFactory;
-
-use super::future::{CompletedFuture, PendingFuture};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::factory::{self, TransportFactoryConfig};
+use super::flush::FlushWaiter;
+use crate::buffer_pool::{BufferPool, check_read_chunk_size};
+use crate::constants::{
+    DEFAULT_COALESCE_BYTES, DEFAULT_COALESCE_DELAY_US, DEFAULT_HIGH, DEFAULT_LOW, RECV_BUF_SIZE,
+};
+use crate::event_loop::{ExceptionContext, VeloxLoop};
+use crate::socket::{KeepaliveParams, TcpInfo, TcpTuning};
+use crate::transports::call_connection_lost;
+
+use super::future::PendingFuture;
+use super::pacing::{PacedTransport, RateLimit};
+use super::splice::{Splice, SpliceEnd};
+use super::stats::{self, TransportStats};
+use super::timeouts::{IdleTimeouts, TimedTransport};
 use super::{StreamTransport, Transport, TransportFactory, TransportState};
 
 // Thread-local 256KB read buffer — eliminates per-read allocation,
 // reads 100KB+ messages in a single syscall instead of 7× 16KB chunks.
+// Grows if a transport is configured with a larger read chunk.
 thread_local! {
     static RECV_BUF: RefCell<Vec<u8>> = RefCell::new(vec![0u8; RECV_BUF_SIZE]);
 }
 
-#[pyclass(module = "veloxloop._veloxloop")]
-pub struct SocketWrapper {
+/// Borrow the shared read buffer, growing it when a transport reads larger chunks
+#[inline]
+fn recv_buf(cell: &RefCell<Vec<u8>>, chunk: usize) -> std::cell::RefMut<'_, Vec<u8>> {
+    let mut buf = cell.borrow_mut();
+    if buf.len() < chunk {
+        buf.resize(chunk, 0);
+    }
+    buf
+}
+
+/// One read of up to `chunk` bytes from `fd` for a protocol's data_received.
+/// Once a read has filled a whole chunk more is likely waiting, and with
+/// `direct` the next goes straight into the bytes object rather than through
+/// `buf` and a copy; a short read is trimmed off the end of it. Small reads
+/// keep using `buf`, so they don't pay for a chunk-sized allocation.
+fn read_chunk(
+    py: Python<'_>,
     fd: RawFd,
-    addr: SocketAddr,
-    peer_addr: Option<SocketAddr>,
+    buf: &mut [u8],
+    chunk: usize,
+    direct: bool,
+) -> PyResult<io::Result<(Py<PyAny>, usize)>> {
+    if direct {
+        return crate::ffi_utils::bytes_filled(py, chunk, |dst| crate::socket::recv_into(fd, dst));
+    }
+    Ok(crate::socket::recv_into(fd, &mut buf[..chunk]).map(|n| {
+        let data = unsafe { crate::ffi_utils::bytes_from_slice(py, &buf[..n]) };
+        (data, n)
+    }))
+}
+
+/// `get_extra_info('socket')` of a stream transport (and the entries of
+/// `Server.sockets`): a live view of the socket, so addresses and options are
+/// read when asked for. A transport keeps one and closes it with itself;
+/// afterwards calls fail with EBADF and `fileno()` is -1, as on a closed
+/// `socket.socket`.
+#[pyclass(frozen, module = "veloxloop._veloxloop")]
+pub struct SocketWrapper {
+    /// -1 once the owner has closed the socket
+    fd: AtomicI32,
+    family: i32,
 }
 
 #[pymethods]
 impl SocketWrapper {
-    fn getsockname(&self) -> PyResult<(String, u16)> {
-        Ok((self.addr.ip().to_string(), self.addr.port()))
+    /// Address tuple in the `socket` module's shape: (host, port) for IPv4,
+    /// (host, port, flowinfo, scope_id) for IPv6
+    fn getsockname(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let addr = self.with_socket(|s| s.local_addr())?;
+        crate::utils::ipv6::socket_addr_to_tuple(py, addr)
     }
 
-    fn getpeername(&self) -> PyResult<(String, u16)> {
-        if let Some(peer) = self.peer_addr {
-            Ok((peer.ip().to_string(), peer.port()))
-        } else {
-            Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(
-                "Transport endpoint is not connected",
-            ))
-        }
+    fn getpeername(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let addr = self.with_socket(|s| s.peer_addr())?;
+        crate::utils::ipv6::socket_addr_to_tuple(py, addr)
     }
 
     #[getter]
     fn family(&self) -> i32 {
-        match self.addr {
-            SocketAddr::V4(_) => libc::AF_INET,
-            SocketAddr::V6(_) => libc::AF_INET6,
-        }
+        self.family
+    }
+
+    #[getter(r#type)]
+    fn sock_type(&self) -> i32 {
+        self.sockopt_int(libc::SOL_SOCKET, libc::SO_TYPE)
+            .unwrap_or(libc::SOCK_STREAM)
+    }
+
+    #[getter]
+    fn proto(&self) -> i32 {
+        self.sockopt_int(libc::SOL_SOCKET, libc::SO_PROTOCOL)
+            .unwrap_or(0)
     }
 
     fn fileno(&self) -> RawFd {
-        self.fd
+        self.fd.load(Ordering::Acquire)
     }
 
     /// Get IPv6-specific information (flowinfo and scope_id for IPv6 addresses)
     fn get_ipv6_info(&self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
-        match self.addr {
+        match self.with_socket(|s| s.local_addr())? {
             SocketAddr::V6(addr) => {
                 let flowinfo = addr.flowinfo();
                 let scope_id = addr.scope_id();
@@ -85,22 +139,25 @@ impl SocketWrapper {
         unsafe {
             let optval = value as libc::c_int;
             let ret = setsockopt(
-                self.fd,
+                self.fileno(),
                 level,
                 optname,
                 &optval as *const _ as *const libc::c_void,
                 std::mem::size_of_val(&optval) as libc::socklen_t,
             );
             if ret != 0 {
-                return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                    "Failed to set socket option: {}",
-                    std::io::Error::last_os_error()
-                )));
+                return Err(crate::utils::last_os_error("Failed to set socket option"));
             }
         }
         Ok(())
     }
 
+    /// Read an integer socket option
+    fn getsockopt(&self, level: i32, optname: i32) -> PyResult<i32> {
+        self.sockopt_int(level, optname)
+            .ok_or_else(|| crate::utils::last_os_error("Failed to get socket option"))
+    }
+
     /// Set socket options (Windows version)
     #[cfg(windows)]
     fn setsockopt(&self, level: i32, optname: i32, value: i32) -> PyResult<()> {
@@ -109,17 +166,14 @@ impl SocketWrapper {
         unsafe {
             let optval = value as i32;
             let ret = setsockopt(
-                self.fd as usize,
+                self.fileno() as usize,
                 level,
                 optname,
                 &optval as *const _ as *const i8,
                 std::mem::size_of_val(&optval) as i32,
             );
             if ret != 0 {
-                return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                    "Failed to set socket option: {}",
-                    std::io::Error::last_os_error()
-                )));
+                return Err(crate::utils::last_os_error("Failed to set socket option"));
             }
         }
         Ok(())
@@ -127,63 +181,104 @@ impl SocketWrapper {
 }
 
 impl SocketWrapper {
+    /// Integer socket option of the wrapped fd; None if the fd is already closed
+    fn sockopt_int(&self, level: libc::c_int, name: libc::c_int) -> Option<i32> {
+        let mut value: libc::c_int = 0;
+        let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let rc = unsafe {
+            libc::getsockopt(
+                self.fileno(),
+                level,
+                name,
+                &mut value as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        (rc == 0).then_some(value)
+    }
+
+    /// Run `op` on the socket, borrowed without taking ownership of the fd
+    fn with_socket<T>(&self, op: impl FnOnce(&TcpStream) -> io::Result<T>) -> PyResult<T> {
+        let fd = self.fileno();
+        if fd < 0 {
+            return Err(crate::utils::os_error_to_pyerr(
+                io::Error::from_raw_os_error(libc::EBADF),
+            ));
+        }
+        let socket = std::mem::ManuallyDrop::new(unsafe { TcpStream::from_raw_fd(fd) });
+        op(&socket).map_err(crate::utils::os_error_to_pyerr)
+    }
+
     pub(crate) fn new(fd: RawFd, addr: SocketAddr) -> Self {
+        let family = match addr {
+            SocketAddr::V4(_) => libc::AF_INET,
+            SocketAddr::V6(_) => libc::AF_INET6,
+        };
         Self {
-            fd,
-            addr,
-            peer_addr: None,
+            fd: AtomicI32::new(fd),
+            family,
         }
     }
 
-    pub(crate) fn new_with_peer(fd: RawFd, addr: SocketAddr, peer_addr: SocketAddr) -> Self {
-        Self {
-            fd,
-            addr,
-            peer_addr: Some(peer_addr),
+    /// The wrapper cached in `slot` for `stream`, made on first request
+    pub(crate) fn cached(
+        py: Python<'_>,
+        slot: &OnceLock<Py<SocketWrapper>>,
+        stream: &TcpStream,
+    ) -> PyResult<Option<Py<PyAny>>> {
+        if let Some(socket) = slot.get() {
+            return Ok(Some(socket.clone_ref(py).into_any()));
+        }
+        let Ok(addr) = stream.local_addr() else {
+            return Ok(None);
+        };
+        let socket = Py::new(py, Self::new(stream.as_raw_fd(), addr))?;
+        let _ = slot.set(socket.clone_ref(py));
+        Ok(Some(socket.into_any()))
+    }
+
+    /// The owner is closing the socket: calls on a wrapper kept from
+    /// `slot` fail from now on instead of reaching whatever reuses the fd
+    pub(crate) fn invalidate(slot: &mut OnceLock<Py<SocketWrapper>>) {
+        if let Some(socket) = slot.take() {
+            socket.get().fd.store(-1, Ordering::Release);
         }
     }
 }
 
 #[pyclass(module = "veloxloop._veloxloop")]
 pub struct TcpServer {
-    listener: Option<std::net::TcpListener>,
+    /// One listener per bound address; empty once closed
+    listeners: Vec<std::net::TcpListener>,
     loop_: Py<VeloxLoop>,
     protocol_factory: Py<PyAny>,
     active: bool,
     serve_forever_future: Mutex<Option<Py<PendingFuture>>>,
+    /// Applied to every accepted connection
+    keepalive: Option<KeepaliveParams>,
+    /// Inside an `async with` block
+    entered: bool,
 }
 
 #[pymethods]
 impl TcpServer {
     #[getter]
     fn sockets(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        // Return a list containing a socket wrapper
-        if let Some(listener) = self.listener.as_ref() {
-            let fd = listener.as_raw_fd();
-            let addr = listener.local_addr()?;
-            let socket_wrapper = SocketWrapper::new(fd, addr);
-            let sock_py = Py::new(py, socket_wrapper)?;
-            let list = pyo3::types::PyList::new(py, &[sock_py])?;
-            Ok(list.into())
-        } else {
-            Ok(pyo3::types::PyList::empty(py).into())
-        }
+        super::listener_sockets(py, &self.listeners)
+    }
+
+    /// Bound address of every listener, in `getsockname()` shape
+    fn addresses(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        super::listener_addresses(py, &self.listeners)
     }
 
     fn close(&mut self, py: Python<'_>) -> PyResult<()> {
-        if let Some(listener) = self.listener.as_ref() {
-            let fd = listener.as_raw_fd();
-            self.loop_.bind(py).borrow().remove_reader(py, fd)?;
+        for listener in &self.listeners {
+            self.loop_.bind(py).borrow().remove_reader(py, listener.as_raw_fd())?;
         }
         self.active = false;
-        self.listener = None;
-
-        // Resolve serve_forever future if it exists
-        if let Some(future) = self.serve_forever_future.lock().as_ref() {
-            future.bind(py).borrow().set_result(py, py.None())?;
-        }
-
-        Ok(())
+        self.listeners.clear();
+        super::finish_serve_forever(py, &self.serve_forever_future)
     }
 
     fn get_loop(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
@@ -194,25 +289,27 @@ impl TcpServer {
         self.active
     }
 
+    /// Fd of the first listener
     pub fn fd(&self) -> Option<RawFd> {
-        self.listener.as_ref().map(|l| l.as_raw_fd())
+        self.listeners.first().map(|l| l.as_raw_fd())
     }
 
-    // wait_closed is async. We return a completed future-like object
+    // wait_closed is async. We return an already resolved future
     fn wait_closed(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        // Create a simple completed future wrapper
-        let fut = CompletedFuture::new(py.None());
-        Ok(Py::new(py, fut)?.into())
+        self.loop_.bind(py).borrow().resolved_future(py, py.None())
     }
 
-    fn __aenter__<'py>(slf: Bound<'py, Self>) -> PyResult<Py<PyAny>> {
-        // Async context manager protocol - return a completed future with self
-        let py = slf.py();
-        let server_obj = slf.clone().unbind();
-        let fut = CompletedFuture::new(server_obj.into());
-        Ok(Py::new(py, fut)?.into())
+    fn __aenter__(slf: &Bound<'_, Self>) -> PyResult<Py<PyAny>> {
+        let loop_ = slf.borrow().loop_.clone_ref(slf.py());
+        super::enter_server_context(
+            slf.as_any(),
+            &loop_.bind(slf.py()).borrow(),
+            &mut slf.borrow_mut().entered,
+        )
     }
 
+    /// close() and wait_closed(); resolves to None so an exception raised in
+    /// the block propagates
     fn __aexit__(
         &mut self,
         py: Python<'_>,
@@ -220,60 +317,24 @@ impl TcpServer {
         _exc_val: Py<PyAny>,
         _exc_tb: Py<PyAny>,
     ) -> PyResult<Py<PyAny>> {
-        // Close the server when exiting context
+        self.entered = false;
         self.close(py)?;
-        // Return a completed future with None
-        let fut = CompletedFuture::new(py.None());
-        Ok(Py::new(py, fut)?.into())
-    }
-
-    fn _on_accept(&self, py: Python<'_>) -> PyResult<()> {
-        // Accept
-        // We need mutable access or interior mutability? TcpListener accept takes &self.
-        if let Some(listener) = self.listener.as_ref() {
-            match listener.accept() {
-                Ok((stream, _addr)) => {
-                    // Create protocol
-                    let protocol = self.protocol_factory.call0(py)?;
-                    // Create Transport using factory
-                    let factory = DefaultTransportFactory;
-                    let loop_py = self.loop_.clone_ref(py).into_any();
-
-                    let transport_py =
-                        factory.create_tcp(py, loop_py, stream, protocol.clone_ref(py))?;
-
-                    // Connection made
-                    protocol.call_method1(py, "connection_made", (transport_py.clone_ref(py),))?;
-
-                    // Attempt to link StreamReader for direct path if it's a StreamReaderProtocol
-                    if let Ok(reader_attr) = protocol.getattr(py, "_reader") {
-                        if let Ok(reader) =
-                            reader_attr.extract::<Py<crate::streams::StreamReader>>(py)
-                        {
-                            if let Ok(tcp_transport) = transport_py.extract::<Py<TcpTransport>>(py)
-                            {
-                                tcp_transport.bind(py).borrow_mut()._link_reader(reader);
-                            }
-                        }
-                    }
-                    // Start reading (native path)
-                    let transport_clone = transport_py.extract::<Py<TcpTransport>>(py)?;
-                    let fd = transport_clone.bind(py).borrow().fd;
-                    self.loop_
-                        .bind(py)
-                        .borrow()
-                        .add_tcp_reader(fd, transport_clone)?;
-                }
-                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
-                Err(e) => return Err(e.into()),
-            }
+        self.wait_closed(py)
+    }
+
+    fn _on_accept(slf: &Bound<'_, Self>) -> PyResult<()> {
+        // Registered for every listener, so check them all; an idle one
+        // just reports WouldBlock
+        let count = slf.borrow().listeners.len();
+        for index in 0..count {
+            Self::accept_from(slf, index)?;
         }
         Ok(())
     }
 
-    /// Set SO_REUSEADDR option on the server socket
+    /// Set SO_REUSEADDR option on the server sockets
     fn set_reuse_address(&self, enabled: bool) -> PyResult<()> {
-        if let Some(listener) = self.listener.as_ref() {
+        for listener in &self.listeners {
             use libc::{SO_REUSEADDR, SOL_SOCKET, setsockopt};
             use std::os::unix::io::AsRawFd;
 
@@ -288,20 +349,17 @@ impl TcpServer {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set SO_REUSEADDR: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set SO_REUSEADDR"));
                 }
             }
         }
         Ok(())
     }
 
-    /// Set SO_REUSEPORT option on the server socket (Unix only, not Solaris)
+    /// Set SO_REUSEPORT option on the server sockets (Unix only, not Solaris)
     #[cfg(all(unix, not(target_os = "solaris")))]
     fn set_reuse_port(&self, enabled: bool) -> PyResult<()> {
-        if let Some(listener) = self.listener.as_ref() {
+        for listener in &self.listeners {
             use std::os::unix::io::AsRawFd;
 
             let fd = listener.as_raw_fd();
@@ -315,42 +373,46 @@ impl TcpServer {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set SO_REUSEPORT: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set SO_REUSEPORT"));
                 }
             }
         }
         Ok(())
     }
-    /// Serve forever - runs the server until explicitly closed
-    fn serve_forever(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
-        // Create a PendingFuture that will be resolved when close() is called
-        let future = Py::new(py, PendingFuture::new())?;
-        *self.serve_forever_future.lock() = Some(future.clone_ref(py));
 
+    /// Set TCP_DEFER_ACCEPT on the server sockets; a no-op where unsupported
+    fn set_defer_accept(&self, py: Python<'_>, secs: u32) -> PyResult<()> {
+        self.set_tuning(py, TcpTuning::DeferAccept, secs)
+    }
+
+    /// Set the TCP_FASTOPEN queue length on the server sockets; a no-op where unsupported
+    fn set_fastopen(&self, py: Python<'_>, qlen: u32) -> PyResult<()> {
+        self.set_tuning(py, TcpTuning::FastOpen, qlen)
+    }
+
+    /// Serve forever - starts serving if need be, and resolves once close()
+    /// is called
+    fn serve_forever(slf: &Bound<'_, Self>) -> PyResult<Py<PyAny>> {
+        let future = {
+            let self_ = slf.borrow();
+            super::serve_forever_future(
+                slf.py(),
+                &self_.serve_forever_future,
+                self_.listeners.is_empty(),
+            )?
+        };
+        Self::begin_serving(slf)?;
         Ok(future.into_any())
     }
 
-    /// Start serving - begin accepting connections
-    fn start_serving(slf: &Bound<'_, Self>) -> PyResult<()> {
+    /// Start serving - begin accepting connections. The listeners are on the
+    /// loop by the time this returns; the result is an already resolved
+    /// future, so awaiting it is optional
+    fn start_serving(slf: &Bound<'_, Self>) -> PyResult<Py<PyAny>> {
         let py = slf.py();
-        let mut self_ = slf.borrow_mut();
-
-        if !self_.active {
-            self_.active = true;
-            if let Some(listener) = self_.listener.as_ref() {
-                let fd = listener.as_raw_fd();
-                // Register the accept callback (native path)
-                let slf_clone = slf.clone().unbind();
-                let on_accept =
-                    Arc::new(move |py: Python<'_>| slf_clone.bind(py).borrow()._on_accept(py));
-                let loop_ = slf.borrow().loop_.clone_ref(py);
-                loop_.bind(py).borrow().add_reader_native(fd, on_accept)?;
-            }
-        }
-        Ok(())
+        Self::begin_serving(slf)?;
+        let loop_ = slf.borrow().loop_.clone_ref(py);
+        loop_.bind(py).borrow().resolved_future(py, py.None())
     }
 }
 
@@ -368,15 +430,92 @@ pub struct TcpTransport {
     write_buffer_low: usize,
     // Direct path to reader
     reader: Option<Py<crate::streams::StreamReader>>,
-    // Cached protocol.data_received method for vectorcall dispatch
-    // Eliminates Python attribute lookup + method call overhead per read
-    cached_data_received: Option<Py<PyAny>>,
-    // Cached protocol.eof_received method
-    cached_eof_received: Option<Py<PyAny>>,
-    // Cached protocol.connection_lost method
-    cached_connection_lost: Option<Py<PyAny>>,
+    // Bound protocol callbacks, re-resolved by `set_protocol`
+    methods: ProtocolMethods,
 
     reading: AtomicBool,
+    // `get_extra_info('socket')`, made on first request
+    socket: OnceLock<Py<SocketWrapper>>,
+    // Bytes requested per recv, see `set_read_chunk_size`
+    read_chunk_size: usize,
+    stats: TransportStats,
+    // Userspace write batching, off unless `set_write_coalescing` was called
+    coalescing: Option<WriteCoalescing>,
+    // Splice reading from this socket / writing into it, see `splice_to`
+    splice_out: Option<Py<Splice>>,
+    splice_in: Option<Py<Splice>>,
+    // Watch for POLLERR/POLLHUP while reading is paused, see `enable_error_monitoring`
+    error_monitoring: bool,
+    // Write pacing, off unless `set_rate_limit` was called
+    rate_limit: Option<RateLimit>,
+    // Read/write idle timeouts, off unless `set_timeouts` was called
+    idle: Option<IdleTimeouts>,
+    // Chunk the loop read ahead before reading got paused, see `deliver_prefetched`
+    stashed: Option<BytesMut>,
+    // The last read filled a whole chunk, see `read_chunk`
+    read_direct: bool,
+    // `pause_writing()` / `resume_writing()` is owed once the transport is
+    // released / one is running, see `_flow_control`
+    pause_due: bool,
+    resume_due: bool,
+    in_flow_control: bool,
+}
+
+/// Protocol callbacks looked up once per protocol instead of once per event.
+/// Eliminates the attribute lookup and bound-method allocation on every read;
+/// a method the protocol lacks is cached as None and its event is skipped.
+struct ProtocolMethods {
+    data_received: Option<Py<PyAny>>,
+    eof_received: Option<Py<PyAny>>,
+    connection_lost: Option<Py<PyAny>>,
+    pause_writing: Option<Py<PyAny>>,
+    resume_writing: Option<Py<PyAny>>,
+}
+
+impl ProtocolMethods {
+    fn resolve(py: Python<'_>, protocol: &Py<PyAny>) -> Self {
+        let method = |name: &str| protocol.getattr(py, name).ok();
+        Self {
+            data_received: method("data_received"),
+            eof_received: method("eof_received"),
+            connection_lost: method("connection_lost"),
+            pause_writing: method("pause_writing"),
+            resume_writing: method("resume_writing"),
+        }
+    }
+}
+
+/// Settings and pending state for `TcpTransport.set_write_coalescing`
+struct WriteCoalescing {
+    max_delay: Duration,
+    max_bytes: usize,
+    /// When the oldest unsent coalesced byte was buffered
+    pending_since: Option<Instant>,
+    /// Already registered for the loop's end-of-iteration flush
+    queued: bool,
+}
+
+impl WriteCoalescing {
+    /// Whether buffered data has to go out now instead of at the end of the iteration
+    #[inline]
+    fn is_due(&self, buffered: usize) -> bool {
+        buffered >= self.max_bytes
+            || self
+                .pending_since
+                .is_some_and(|since| since.elapsed() >= self.max_delay)
+    }
+}
+
+/// One read the loop did ahead of delivery when several sockets were ready
+/// at once, handed back through `TcpTransport::deliver_prefetched`
+pub(crate) enum PrefetchedRead {
+    /// `full` when the read filled a whole chunk, so more may be waiting
+    Data {
+        buf: BytesMut,
+        full: bool,
+    },
+    Eof,
+    Failed(io::Error),
 }
 
 unsafe impl Send for TcpTransport {}
@@ -416,18 +555,26 @@ impl crate::transports::Transport for TcpTransport {
                 Ok(default.unwrap_or_else(|| py.None()))
             }
             "socket" => {
-                if let Some(stream) = self.stream.as_ref() {
-                    let fd = stream.as_raw_fd();
-                    if let (Ok(addr), Ok(peer_addr)) = (stream.local_addr(), stream.peer_addr()) {
-                        let socket_wrapper = SocketWrapper::new_with_peer(fd, addr, peer_addr);
-                        return Ok(Py::new(py, socket_wrapper)?.into_any());
-                    } else if let Ok(addr) = stream.local_addr() {
-                        let socket_wrapper = SocketWrapper::new(fd, addr);
-                        return Ok(Py::new(py, socket_wrapper)?.into_any());
-                    }
+                if let Some(stream) = self.stream.as_ref()
+                    && let Some(socket) = SocketWrapper::cached(py, &self.socket, stream)?
+                {
+                    return Ok(socket);
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            "tcp_info" => {
+                if let Some(info) = self.tcp_info() {
+                    return info.to_dict(py);
                 }
                 Ok(default.unwrap_or_else(|| py.None()))
             }
+            "original_dst" => {
+                if let Some(addr) = self.stream.as_ref().and_then(crate::socket::original_dst) {
+                    return crate::utils::ipv6::socket_addr_to_tuple(py, addr);
+                }
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            "veloxloop_stats" => self.stats.to_dict(py),
             _ => Ok(default.unwrap_or_else(|| py.None())),
         }
     }
@@ -439,6 +586,10 @@ impl crate::transports::Transport for TcpTransport {
     fn get_fd(&self) -> RawFd {
         self.fd
     }
+
+    fn stats(&self) -> &TransportStats {
+        &self.stats
+    }
 }
 
 // Implement StreamTransport trait for TcpTransport
@@ -463,7 +614,7 @@ impl crate::transports::StreamTransport for TcpTransport {
         self._force_close_internal(py)
     }
 
-    fn write(&mut self, _py: Python<'_>, data: Bound<'_, PyAny>) -> PyResult<()> {
+    fn write(&mut self, py: Python<'_>, data: Bound<'_, PyAny>) -> PyResult<()> {
         let buf_view = PyBuffer::<u8>::get(&data)?;
 
         if !buf_view.is_c_contiguous() {
@@ -476,11 +627,27 @@ impl crate::transports::StreamTransport for TcpTransport {
         let len = buf_view.len_bytes();
         let slice = unsafe { std::slice::from_raw_parts(ptr, len) };
 
+        // Coalesce small writes until the end of the iteration; once closing, every
+        // write goes straight out. Anything already queued must leave first.
+        let coalesce = self.coalescing.is_some() && !self.state.contains(TransportState::CLOSING);
+        if coalesce
+            || self.splice_in.is_some()
+            || self.rate_limit.is_some()
+            || !self.write_buffer.borrow().is_empty()
+        {
+            self.buffer_write(py, slice);
+            if coalesce && let Some(c) = self.coalescing.as_mut() {
+                c.pending_since.get_or_insert_with(Instant::now);
+            }
+            return Ok(());
+        }
+
         if let Some(mut stream) = self.stream.as_ref() {
             // Loop to push through as much data as possible in one call.
             // For 100KB writes, this avoids buffering → event loop → write_ready overhead.
             let mut offset = 0;
             while offset < len {
+                self.stats.add_write_call();
                 match stream.write(&slice[offset..]) {
                     Ok(0) => {
                         return Err(PyErr::new::<pyo3::exceptions::PyConnectionError, _>(
@@ -489,16 +656,15 @@ impl crate::transports::StreamTransport for TcpTransport {
                     }
                     Ok(n) => {
                         offset += n;
+                        self.stats.add_bytes_out(n);
                     }
                     Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
                         // Buffer remaining data for write_ready to handle
-                        self.write_buffer
-                            .borrow_mut()
-                            .extend_from_slice(&slice[offset..]);
+                        self.buffer_write(py, &slice[offset..]);
                         break;
                     }
                     Err(e) => {
-                        return Err(e.into());
+                        return Err(crate::utils::os_error_to_pyerr(e));
                     }
                 }
             }
@@ -517,128 +683,25 @@ impl crate::transports::StreamTransport for TcpTransport {
         self.write_buffer.borrow().len()
     }
 
+    fn get_write_buffer_limits(&self) -> (usize, usize) {
+        (self.write_buffer_low, self.write_buffer_high)
+    }
+
     fn set_write_buffer_limits(
         &mut self,
         py: Python<'_>,
-        high: Option<usize>,
-        low: Option<usize>,
+        high: Option<isize>,
+        low: Option<isize>,
     ) -> PyResult<()> {
-        let high_limit = high.unwrap_or(DEFAULT_HIGH);
-        let low_limit = low.unwrap_or_else(|| if high_limit == 0 { 0 } else { high_limit / 4 });
-
-        // Special case: high=0 means disable flow control (both should be 0)
-        // Otherwise, validate that low < high
-        if high_limit > 0 && low_limit >= high_limit {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                "low must be less than high",
-            ));
-        }
-
+        let (high_limit, low_limit) = super::write_buffer_limits(high, low)?;
         self.write_buffer_high = high_limit;
         self.write_buffer_low = low_limit;
-
-        if high_limit > 0 && self.write_buffer.borrow().len() > self.write_buffer_high {
-            let _ = self.protocol.call_method0(py, "pause_writing");
-        }
-
+        self.maybe_pause_protocol(py);
         Ok(())
     }
 
-    /// Optimized read_ready handler - key performance path
-    /// Uses larger buffer and reduces Python callback overhead
-    fn read_ready(&mut self, py: Python<'_>) -> PyResult<()> {
-        // Fast path: Direct to StreamReader if available (streams API)
-        if let Some(reader_py) = &self.reader {
-            if let Some(stream) = self.stream.as_mut() {
-                let reader = reader_py.bind(py).borrow();
-                // Read directly using StreamReader's optimized method
-                match reader.read_from_socket(stream) {
-                    Ok(0) => {
-                        // EOF
-                        let _ = reader.feed_eof_native(py);
-                        let _ = reader._wakeup_waiters(py);
-                        self.close(py)?;
-                    }
-                    Ok(_) => {
-                        // Data already in buffer via read_from_socket
-                        let _ = reader._wakeup_waiters(py);
-                    }
-                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
-                    Err(e) => return Err(e.into()),
-                }
-            }
-            return Ok(());
-        }
-
-        // Protocol path: read and call data_received with 256KB buffer + loop
-        // Extract raw pointer to avoid borrow conflict with self.close() in the closure
-        let stream_ptr = self.stream.as_ref().map(|s| s as *const std::net::TcpStream);
-        let cached_data_ptr = self.cached_data_received.as_ref().map(|m| m.as_ptr());
-        let cached_eof_ptr = self.cached_eof_received.as_ref().map(|m| m.as_ptr());
-
-        if let Some(sptr) = stream_ptr {
-            let mut needs_close = false;
-
-            RECV_BUF.with(|buf_cell| -> PyResult<()> {
-                let mut buf = buf_cell.borrow_mut();
-                loop {
-                    let n = unsafe {
-                        let stream = &*sptr;
-                        let mut s = stream;
-                        std::io::Read::read(&mut s, &mut buf[..])
-                    };
-
-                    match n {
-                        Ok(0) => {
-                            // EOF — handle after closure
-                            if let Some(eof_ptr) = cached_eof_ptr {
-                                let result = unsafe { pyo3::ffi::PyObject_CallNoArgs(eof_ptr) };
-                                if !result.is_null() {
-                                    let keep_open = unsafe {
-                                        let val = pyo3::ffi::PyObject_IsTrue(result);
-                                        pyo3::ffi::Py_DECREF(result);
-                                        val == 1
-                                    };
-                                    if !keep_open {
-                                        needs_close = true;
-                                    }
-                                } else {
-                                    unsafe { pyo3::ffi::PyErr_Clear() };
-                                    needs_close = true;
-                                }
-                            } else {
-                                needs_close = true;
-                            }
-                            break;
-                        }
-                        Ok(n) => {
-                            let py_data =
-                                unsafe { crate::ffi_utils::bytes_from_slice(py, &buf[..n]) };
-                            if let Some(data_ptr) = cached_data_ptr {
-                                unsafe {
-                                    crate::ffi_utils::vectorcall_one_arg(
-                                        py,
-                                        data_ptr,
-                                        py_data.as_ptr(),
-                                    )?;
-                                }
-                            }
-                            if n < RECV_BUF_SIZE {
-                                break;
-                            }
-                        }
-                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
-                        Err(e) => return Err(e.into()),
-                    }
-                }
-                Ok(())
-            })?;
-
-            if needs_close {
-                self.close(py)?;
-            }
-        }
-        Ok(())
+    fn read_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        Self::_read_ready(slf)
     }
 
     /// Zero-copy read into a Python buffer (bytearray, memoryview, etc.)
@@ -655,9 +718,12 @@ impl crate::transports::StreamTransport for TcpTransport {
             let slice_mut =
                 unsafe { std::slice::from_raw_parts_mut(slice.as_ptr() as *mut u8, slice.len()) };
             match stream.read(slice_mut) {
-                Ok(n) => Ok(n),
+                Ok(n) => {
+                    self.stats.add_bytes_in(n);
+                    Ok(n)
+                }
                 Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Ok(0),
-                Err(e) => Err(e.into()),
+                Err(e) => Err(crate::utils::os_error_to_pyerr(e)),
             }
         } else {
             Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
@@ -669,6 +735,7 @@ impl crate::transports::StreamTransport for TcpTransport {
     /// Optimized write_ready handler
     fn write_ready(&mut self, py: Python<'_>) -> PyResult<()> {
         let mut should_finalize = false;
+        let mut failure = None;
         if let Some(stream) = self.stream.as_mut() {
             // Try to write as much as possible in one iteration
             // Minimize RefCell borrows by doing them outside the loop when possible
@@ -677,25 +744,46 @@ impl crate::transports::StreamTransport for TcpTransport {
                 if data_len == 0 {
                     break;
                 }
+                let budget = match self.rate_limit.as_mut() {
+                    Some(limit) => limit.budget(py, &self.loop_.bind(py).borrow(), data_len)?,
+                    None => data_len,
+                };
+                if budget == 0 {
+                    // Out of tokens: the refill timer puts the writer back
+                    self.loop_.bind(py).borrow().remove_writer(py, self.fd)?;
+                    break;
+                }
 
                 // Borrow the data for writing
+                self.stats.add_write_call();
                 let write_result = {
                     let data = self.write_buffer.borrow();
-                    stream.write(&data[..])
+                    stream.write(&data[..budget])
                 };
 
                 match write_result {
                     Ok(0) => {
-                        return Err(PyErr::new::<pyo3::exceptions::PyConnectionError, _>(
+                        failure = Some(PyErr::new::<pyo3::exceptions::PyConnectionError, _>(
                             "Connection closed during write",
                         ));
+                        break;
                     }
                     Ok(n) => {
+                        self.stats.add_bytes_out(n);
+                        if let Some(limit) = self.rate_limit.as_mut() {
+                            limit.consume(n);
+                        }
                         let _ = self.write_buffer.borrow_mut().split_to(n);
                         if self.write_buffer.borrow().is_empty() {
                             let fd = self.fd;
                             self.loop_.bind(py).borrow().remove_writer(py, fd)?;
 
+                            // write_eof() was waiting for the queued data
+                            if self.state.contains(TransportState::EOF_PENDING) {
+                                self.state.remove(TransportState::EOF_PENDING);
+                                stream.shutdown(std::net::Shutdown::Write)?;
+                            }
+
                             // If we are in CLOSING state and buffer is empty, finalize closure
                             if self.state.contains(TransportState::CLOSING) {
                                 should_finalize = true;
@@ -707,26 +795,27 @@ impl crate::transports::StreamTransport for TcpTransport {
                         break;
                     }
                     Err(e) => {
-                        return Err(e.into());
+                        failure = Some(crate::utils::os_error_to_pyerr(e));
+                        break;
                     }
                 }
             }
         }
 
+        if let Some(err) = failure {
+            return self.fatal_error(py, err);
+        }
+        self.maybe_resume_protocol();
+
         if should_finalize {
             self._force_close_internal(py)?;
-            // Use cached connection_lost method
-            if let Some(ref cached) = self.cached_connection_lost {
-                let _ = unsafe {
-                    crate::ffi_utils::vectorcall_one_arg(
-                        py,
-                        cached.as_ptr(),
-                        pyo3::ffi::Py_None(),
-                    )
-                };
-            } else {
-                let protocol = self.protocol.clone_ref(py);
-                let _ = protocol.call_method1(py, "connection_lost", (py.None(),));
+            // Scheduled like asyncio does: the loop dispatches this with the
+            // transport mutably borrowed, so connection_lost must not run inline
+            if let Some(connection_lost) = self.claim_connection_lost(py) {
+                self.loop_
+                    .bind(py)
+                    .borrow()
+                    .call_soon(connection_lost, vec![py.None()], None);
             }
         }
 
@@ -752,20 +841,250 @@ impl TcpTransport {
         StreamTransport::get_write_buffer_size(self)
     }
 
+    /// Names of the set state flags, e.g. `frozenset({'ACTIVE', 'READING_PAUSED'})`;
+    /// the bits are in `veloxloop._veloxloop.constants.TransportState`
+    fn get_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyFrozenSet>> {
+        self.state.to_frozenset(py)
+    }
+
+    fn __repr__(&self) -> String {
+        super::transport_repr(
+            "TcpTransport",
+            self.fd,
+            self.state,
+            self.write_buffer.borrow().len(),
+        )
+    }
+
+    /// Smoothed round-trip time in seconds from TCP_INFO, or None if unavailable
+    fn get_rtt(&self) -> Option<f64> {
+        self.tcp_info().map(|info| info.rtt_secs())
+    }
+
+    /// Bytes written to the socket that the peer hasn't acknowledged yet, or
+    /// None when closed or unsupported
+    fn get_kernel_write_queue(&self) -> Option<usize> {
+        self.stream
+            .as_ref()
+            .and_then(|s| crate::socket::kernel_write_queue(s.as_raw_fd()))
+    }
+
+    /// Resolve once the write buffer and the kernel send queue are both empty
+    #[pyo3(signature = (timeout=None))]
+    fn flush(slf: &Bound<'_, Self>, timeout: Option<f64>) -> PyResult<Py<PyAny>> {
+        let loop_ = slf.borrow().loop_.clone_ref(slf.py());
+        FlushWaiter::start(&loop_, slf.clone().into_any(), timeout)
+    }
+
+    fn get_write_buffer_limits(&self) -> (usize, usize) {
+        StreamTransport::get_write_buffer_limits(self)
+    }
+
     #[pyo3(signature = (high=None, low=None))]
     fn set_write_buffer_limits(
-        &mut self,
-        py: Python<'_>,
-        high: Option<usize>,
-        low: Option<usize>,
+        slf: &Bound<'_, Self>,
+        high: Option<isize>,
+        low: Option<isize>,
     ) -> PyResult<()> {
         // Delegate to trait implementation
-        StreamTransport::set_write_buffer_limits(self, py, high, low)
+        StreamTransport::set_write_buffer_limits(&mut *slf.borrow_mut(), slf.py(), high, low)?;
+        Self::_flow_control(slf)
     }
 
-    fn write_eof(&mut self) -> PyResult<()> {
-        // Delegate to trait implementation
-        StreamTransport::write_eof(self)
+    fn get_protocol(&self, py: Python<'_>) -> Py<PyAny> {
+        self.protocol.clone_ref(py)
+    }
+
+    /// Switch to another protocol; its callbacks are looked up here once
+    fn set_protocol(&mut self, py: Python<'_>, protocol: Py<PyAny>) {
+        self.methods = ProtocolMethods::resolve(py, &protocol);
+        self.protocol = protocol;
+    }
+
+    /// Move bytes from this socket to `other` in the kernel, through a pipe,
+    /// until `count` bytes are moved or the peer sends EOF. Meanwhile this
+    /// transport does not call `data_received()` and writes to `other` are
+    /// held back. Resolves to the number of bytes moved; closing either
+    /// transport cancels it.
+    #[pyo3(signature = (other, count=None))]
+    fn splice_to(
+        slf: &Bound<'_, Self>,
+        other: &Bound<'_, PyAny>,
+        count: Option<usize>,
+    ) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let loop_ = slf.borrow().loop_.clone_ref(py);
+        let source = SpliceEnd::Tcp(slf.clone().unbind());
+        let future = Splice::start(py, loop_, source, SpliceEnd::extract(other)?, count)?;
+        Ok(future.into_any())
+    }
+
+    /// False once closing or after write_eof()
+    fn can_write_eof(&self) -> bool {
+        !self.state.intersects(
+            TransportState::CLOSING | TransportState::CLOSED | TransportState::EOF_WRITTEN,
+        )
+    }
+
+    fn write_eof(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_
+            .state
+            .intersects(TransportState::CLOSING | TransportState::CLOSED)
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Transport is closed",
+            ));
+        }
+        if self_.state.contains(TransportState::EOF_WRITTEN) {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "write_eof() was already called",
+            ));
+        }
+        if self_.splice_in.is_some() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Cannot write_eof while splicing into the transport",
+            ));
+        }
+        self_.state.insert(TransportState::EOF_WRITTEN);
+        if self_.write_buffer.borrow().is_empty() {
+            // Delegate to trait implementation
+            return StreamTransport::write_eof(&mut *self_);
+        }
+
+        // Queued (or coalesced) data has to reach the peer before the FIN
+        if let Some(c) = self_.coalescing.as_mut() {
+            c.pending_since = None;
+        }
+        self_.state.insert(TransportState::EOF_PENDING);
+        self_._write_ready(py)?;
+
+        let wants_writer = self_.wants_writer();
+        let (fd, loop_) = (self_.fd, self_.loop_.clone_ref(py));
+        drop(self_);
+        if wants_writer {
+            loop_
+                .bind(py)
+                .borrow()
+                .add_tcp_writer(fd, slf.clone().unbind())?;
+        }
+        Self::_flow_control(slf)
+    }
+
+    /// Batch small writes in userspace. Buffered data is sent at the end of the
+    /// current loop iteration, or earlier once `max_bytes` are queued or the oldest
+    /// queued byte has waited `max_delay_us`.
+    #[pyo3(signature = (max_delay_us=DEFAULT_COALESCE_DELAY_US, max_bytes=DEFAULT_COALESCE_BYTES))]
+    fn set_write_coalescing(&mut self, max_delay_us: u64, max_bytes: usize) -> PyResult<()> {
+        if max_bytes == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "max_bytes must be greater than 0",
+            ));
+        }
+
+        let (pending_since, queued) = self
+            .coalescing
+            .as_ref()
+            .map_or((None, false), |c| (c.pending_since, c.queued));
+        self.coalescing = Some(WriteCoalescing {
+            max_delay: Duration::from_micros(max_delay_us),
+            max_bytes,
+            pending_since,
+            queued,
+        });
+        Ok(())
+    }
+
+    /// Go back to sending on every `write()`, flushing anything coalesced so far
+    fn disable_write_coalescing(slf: &Bound<'_, Self>) -> PyResult<()> {
+        if slf.borrow_mut().coalescing.take().is_some() {
+            Self::_trigger_write(slf)?;
+        }
+        Ok(())
+    }
+
+    /// `(max_delay_us, max_bytes)` when write coalescing is enabled, else None
+    fn get_write_coalescing(&self) -> Option<(u64, usize)> {
+        self.coalescing
+            .as_ref()
+            .map(|c| (c.max_delay.as_micros() as u64, c.max_bytes))
+    }
+
+    /// Cap the send rate at `bytes_per_sec`, with bursts of up to `burst` bytes
+    /// (100ms worth by default). Writes are still buffered as usual and the
+    /// write buffer limits apply; None removes the limit.
+    #[pyo3(signature = (bytes_per_sec, burst=None))]
+    fn set_rate_limit(
+        slf: &Bound<'_, Self>,
+        bytes_per_sec: Option<u64>,
+        burst: Option<u64>,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        let loop_ = self_.loop_.clone_ref(py);
+        let loop_ = loop_.bind(py).borrow();
+        let limit = match bytes_per_sec {
+            Some(rate) => Some(RateLimit::new(
+                py,
+                &loop_,
+                PacedTransport::Tcp(slf.clone().unbind()),
+                rate,
+                burst,
+            )?),
+            None => None,
+        };
+        if let Some(mut old) = std::mem::replace(&mut self_.rate_limit, limit) {
+            old.cancel(&loop_);
+        }
+        drop(loop_);
+        drop(self_);
+        Self::resume_paced(slf)
+    }
+
+    /// `(bytes_per_sec, burst)` while a rate limit is set, else None
+    fn get_rate_limit(&self) -> Option<(u64, u64)> {
+        self.rate_limit.as_ref().map(RateLimit::settings)
+    }
+
+    /// Close the connection with TimeoutError when nothing arrives for `read`
+    /// seconds while reading, or buffered writes make no progress for `write`
+    /// seconds. Setting either restarts both; None for both turns them off.
+    #[pyo3(signature = (read=None, write=None))]
+    fn set_timeouts(slf: &Bound<'_, Self>, read: Option<f64>, write: Option<f64>) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_.state.contains(TransportState::CLOSED) {
+            return Ok(());
+        }
+        let this = &mut *self_;
+        let loop_ = this.loop_.bind(py).borrow();
+        IdleTimeouts::set(
+            &mut this.idle,
+            &loop_,
+            &this.stats,
+            || TimedTransport::Tcp(slf.clone().unbind()),
+            read,
+            write,
+        )
+    }
+
+    /// `(read, write)` as given to `set_timeouts`
+    fn get_timeouts(&self) -> (Option<f64>, Option<f64>) {
+        self.idle
+            .as_ref()
+            .map_or((None, None), IdleTimeouts::settings)
+    }
+
+    /// Bytes requested per recv on this transport; takes effect on the next read.
+    /// Must be a power of two between 1 KB and 4 MB.
+    fn set_read_chunk_size(&mut self, size: usize) -> PyResult<()> {
+        self.read_chunk_size = check_read_chunk_size(size)?;
+        Ok(())
+    }
+
+    fn get_read_chunk_size(&self) -> usize {
+        self.read_chunk_size
     }
 
     fn is_closing(&self) -> bool {
@@ -778,6 +1097,13 @@ impl TcpTransport {
         Transport::get_fd(self)
     }
 
+    /// False once paused or closing, like asyncio's socket transports
+    fn is_reading(&self) -> bool {
+        !self.state.intersects(
+            TransportState::READING_PAUSED | TransportState::CLOSING | TransportState::CLOSED,
+        )
+    }
+
     fn pause_reading(slf: &Bound<'_, Self>) -> PyResult<()> {
         let py = slf.py();
         let (should_remove, fd, loop_obj) = {
@@ -785,6 +1111,10 @@ impl TcpTransport {
 
             if !self_.state.contains(TransportState::READING_PAUSED) {
                 self_.state.insert(TransportState::READING_PAUSED);
+                if self_.splice_out.is_some() {
+                    // The splice owns the reader; it is left off when the splice ends
+                    return Ok(());
+                }
                 let fd = self_.fd;
                 let loop_obj = self_.loop_.clone_ref(py);
                 (true, fd, loop_obj)
@@ -795,7 +1125,13 @@ impl TcpTransport {
 
         if should_remove {
             let loop_ = loop_obj.bind(py).borrow();
-            loop_.remove_reader(py, fd)?;
+            if slf.borrow().error_monitoring {
+                loop_.add_error_monitor(fd, slf.clone().unbind())?;
+            } else {
+                loop_.remove_reader(py, fd)?;
+            }
+            drop(loop_);
+            stats::emit_fd_event(py, &loop_obj, "pause_reading", fd);
         }
         Ok(())
     }
@@ -806,6 +1142,17 @@ impl TcpTransport {
 
         if self_.state.contains(TransportState::READING_PAUSED) {
             self_.state.remove(TransportState::READING_PAUSED);
+            if self_.state.contains(TransportState::EOF_RECEIVED) {
+                // Nothing left to read once the peer has shut down its side
+                return Ok(());
+            }
+            if let Some(idle) = self_.idle.as_mut() {
+                idle.restart_read();
+            }
+            if self_.splice_out.is_some() {
+                // Reading comes back when the splice ends
+                return Ok(());
+            }
             let fd = self_.fd;
             let loop_obj = self_.loop_.clone_ref(py);
             drop(self_); // Drop borrow before calling into loop
@@ -814,15 +1161,67 @@ impl TcpTransport {
                 .bind(py)
                 .borrow()
                 .add_tcp_reader(fd, slf.clone().unbind())?;
+            stats::emit_fd_event(py, &loop_obj, "resume_reading", fd);
+            // A quiet socket wouldn't bring the reader back for it
+            if slf.borrow().stashed.is_some() {
+                let read_ready = slf.getattr(pyo3::intern!(py, "_read_ready"))?;
+                loop_obj
+                    .bind(py)
+                    .borrow()
+                    .call_soon(read_ready.unbind(), Vec::new(), None);
+            }
         }
         Ok(())
     }
 
-    fn close(slf: &Bound<'_, Self>) -> PyResult<()> {
+    /// Keep watching the socket while reading is paused, so a peer that resets
+    /// an idle connection is noticed without a read or write: the transport
+    /// is closed and `connection_lost` gets the socket error
+    fn enable_error_monitoring(slf: &Bound<'_, Self>) -> PyResult<()> {
         let py = slf.py();
-        let mut protocol = None;
-        let mut needs_writer = false;
-
+        let mut self_ = slf.borrow_mut();
+        if self_.error_monitoring {
+            return Ok(());
+        }
+        self_.error_monitoring = true;
+        if !self_.is_monitor_due() {
+            return Ok(());
+        }
+        let fd = self_.fd;
+        let loop_ = self_.loop_.clone_ref(py);
+        drop(self_);
+        loop_
+            .bind(py)
+            .borrow()
+            .add_error_monitor(fd, slf.clone().unbind())
+    }
+
+    fn disable_error_monitoring(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if !self_.error_monitoring {
+            return Ok(());
+        }
+        self_.error_monitoring = false;
+        if !self_.is_monitor_due() {
+            return Ok(());
+        }
+        let fd = self_.fd;
+        let loop_ = self_.loop_.clone_ref(py);
+        drop(self_);
+        loop_.bind(py).borrow().remove_reader(py, fd)?;
+        Ok(())
+    }
+
+    fn is_error_monitoring(&self) -> bool {
+        self.error_monitoring
+    }
+
+    fn close(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let mut connection_lost = None;
+        let mut needs_writer = false;
+
         {
             let mut self_ = slf.borrow_mut();
             if self_.state.contains(TransportState::CLOSING)
@@ -832,28 +1231,20 @@ impl TcpTransport {
             }
 
             self_.state.insert(TransportState::CLOSING);
+            self_.cancel_splices(py)?;
 
             if self_.write_buffer.borrow().is_empty() {
                 self_._force_close_internal(py)?;
-                protocol = Some(self_.protocol.clone_ref(py));
+                connection_lost = self_.claim_connection_lost(py);
             } else {
-                needs_writer = true;
+                needs_writer = self_.wants_writer();
             }
         }
 
-        // Notify protocol after dropping borrow via cached method
-        if let Some(proto) = protocol {
-            if let Some(ref cached) = slf.borrow().cached_connection_lost {
-                let _ = unsafe {
-                    crate::ffi_utils::vectorcall_one_arg(
-                        py,
-                        cached.as_ptr(),
-                        pyo3::ffi::Py_None(),
-                    )
-                };
-            } else {
-                let _ = proto.call_method1(py, "connection_lost", (py.None(),));
-            }
+        // Notify protocol after dropping borrow
+        if let Some(callback) = connection_lost {
+            let loop_ = slf.borrow().loop_.clone_ref(py);
+            call_connection_lost(py, &loop_, &callback, None)?;
         }
 
         if needs_writer {
@@ -871,66 +1262,32 @@ impl TcpTransport {
 
     fn abort(slf: &Bound<'_, Self>) -> PyResult<()> {
         let py = slf.py();
-        {
+        let (connection_lost, loop_) = {
             let mut self_ = slf.borrow_mut();
             self_._force_close_internal(py)?;
-        }
-        // Use cached connection_lost method
-        if let Some(ref cached) = slf.borrow().cached_connection_lost {
-            let _ = unsafe {
-                crate::ffi_utils::vectorcall_one_arg(
-                    py,
-                    cached.as_ptr(),
-                    pyo3::ffi::Py_None(),
-                )
-            };
-        } else {
-            let protocol = slf.borrow().protocol.clone_ref(py);
-            let _ = protocol.call_method1(py, "connection_lost", (py.None(),));
+            (self_.claim_connection_lost(py), self_.loop_.clone_ref(py))
+        };
+        if let Some(callback) = connection_lost {
+            call_connection_lost(py, &loop_, &callback, None)?;
         }
         Ok(())
     }
 
     fn _force_close(&mut self, py: Python<'_>) -> PyResult<()> {
         self._force_close_internal(py)?;
-        // Use cached connection_lost method
-        if let Some(ref cached) = self.cached_connection_lost {
-            let _ = unsafe {
-                crate::ffi_utils::vectorcall_one_arg(
-                    py,
-                    cached.as_ptr(),
-                    pyo3::ffi::Py_None(),
-                )
-            };
-        } else {
-            let _ = self
-                .protocol
-                .call_method1(py, "connection_lost", (py.None(),));
+        if let Some(callback) = self.claim_connection_lost(py) {
+            call_connection_lost(py, &self.loop_, &callback, None)?;
         }
         Ok(())
     }
 
     fn _force_close_internal(&mut self, py: Python<'_>) -> PyResult<()> {
-        if self.state.contains(TransportState::CLOSED) {
-            return Ok(());
-        }
-
-        let fd = self.fd;
-        self.state.insert(TransportState::CLOSED);
-        self.state.remove(TransportState::ACTIVE);
-        self.state.remove(TransportState::CLOSING);
-
-        let loop_ = self.loop_.bind(py).borrow();
-        let _ = loop_.remove_reader(py, fd);
-        let _ = loop_.remove_writer(py, fd);
-        drop(loop_);
-
-        self.stream = None;
-        self.reader = None;
-        Ok(())
+        self.teardown(py, None)
     }
 
-    /// Trigger write when data is added to buffer (called by StreamWriter)
+    /// Trigger write when data is added to buffer (called by StreamWriter).
+    /// From inside a read dispatch the writer callback sends it instead, so
+    /// `resume_writing()` never runs nested in `data_received()`.
     fn _trigger_write(slf: &Bound<'_, Self>) -> PyResult<()> {
         let py = slf.py();
         let mut self_ = slf.borrow_mut();
@@ -938,25 +1295,31 @@ impl TcpTransport {
         if self_.state.contains(TransportState::CLOSING)
             || self_.state.contains(TransportState::CLOSED)
             || self_.stream.is_none()
+            || self_.splice_in.is_some()
         {
             return Ok(());
         }
 
         if !self_.write_buffer.borrow().is_empty() {
             // Try immediate write first
-            let res = self_._write_ready(py);
+            let res = if self_.in_read_dispatch() {
+                Ok(())
+            } else {
+                self_._write_ready(py)
+            };
 
             // If still have data, ensure writer callback is registered
-            if !self_.write_buffer.borrow().is_empty() {
-                let fd = self_.fd;
-                let loop_ = self_.loop_.clone_ref(py);
-                drop(self_); // Drop borrow before calling into loop
+            let wants_writer = self_.wants_writer();
+            let (fd, loop_) = (self_.fd, self_.loop_.clone_ref(py));
+            drop(self_); // Drop borrow before calling into loop
+            if wants_writer {
                 loop_
                     .bind(py)
                     .borrow()
                     .add_tcp_writer(fd, slf.clone().unbind())?;
             }
-            res
+            res?;
+            Self::_flow_control(slf)
         } else {
             Ok(())
         }
@@ -969,21 +1332,70 @@ impl TcpTransport {
 
     fn write(slf: &Bound<'_, Self>, data: &Bound<'_, PyBytes>) -> PyResult<()> {
         let mut self_ = slf.borrow_mut();
+        if self_.state.contains(TransportState::EOF_WRITTEN) {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Cannot write after write_eof",
+            ));
+        }
 
-        // Delegate to trait implementation
-        StreamTransport::write(&mut *self_, slf.py(), data.clone().into_any())?;
+        // Delegate to trait implementation; a failed send closes the
+        // transport instead of raising, like asyncio's write()
+        if let Err(e) = StreamTransport::write(&mut *self_, slf.py(), data.clone().into_any()) {
+            if !e.is_instance_of::<pyo3::exceptions::PyOSError>(slf.py()) {
+                return Err(e);
+            }
+            return self_.fatal_error(slf.py(), e);
+        }
+        if self_.splice_in.is_some() {
+            // Held until the splice into this transport ends
+            drop(self_);
+            return Self::_flow_control(slf);
+        }
+
+        let buffered = self_.write_buffer.borrow().len();
+        if let Some(c) = self_.coalescing.as_mut()
+            && c.pending_since.is_some()
+        {
+            if !c.is_due(buffered) {
+                // Hold the data until the end of the loop iteration
+                let queue = !std::mem::replace(&mut c.queued, true);
+                let loop_ = self_.loop_.clone_ref(slf.py());
+                drop(self_);
+                if queue {
+                    loop_
+                        .bind(slf.py())
+                        .borrow()
+                        .add_coalesced_writer(slf.clone().unbind());
+                }
+                return Self::_flow_control(slf);
+            }
+            c.pending_since = None;
+            if !self_.in_read_dispatch() {
+                self_._write_ready(slf.py())?;
+            }
+        }
+
+        if self_.rate_limit.is_some() && !self_.in_read_dispatch() {
+            // Paced data goes out as far as the bucket allows right away
+            self_._write_ready(slf.py())?;
+        }
 
         // Register writer if needed
-        if !self_.write_buffer.borrow().is_empty() {
-            let fd = self_.fd;
-            let loop_ = self_.loop_.clone_ref(slf.py());
-            drop(self_);
+        let wants_writer = self_.wants_writer();
+        let (fd, loop_) = (self_.fd, self_.loop_.clone_ref(slf.py()));
+        drop(self_);
+        if wants_writer {
             loop_
                 .bind(slf.py())
                 .borrow()
                 .add_tcp_writer(fd, slf.clone().unbind())?;
         }
-        Ok(())
+        Self::_flow_control(slf)
+    }
+
+    /// Write an iterable of bytes-like objects as one joined buffer
+    fn writelines(slf: &Bound<'_, Self>, lines: &Bound<'_, PyAny>) -> PyResult<()> {
+        Self::write(slf, &super::join_lines(lines)?)
     }
 
     // Internal callback called by loop when writable
@@ -999,6 +1411,12 @@ impl TcpTransport {
     /// 3. Minimal RefCell borrows
     #[inline(always)]
     pub(crate) fn _read_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        // Data read ahead before a pause goes first; reading carries on after it
+        let stashed = slf.borrow_mut().stashed.take();
+        if let Some(buf) = stashed {
+            return Self::deliver_prefetched(slf, PrefetchedRead::Data { buf, full: true });
+        }
+
         // Lock-free guard against re-entrance
         if slf.borrow().reading.swap(true, Ordering::Acquire) {
             return Ok(()); // Already reading
@@ -1007,11 +1425,14 @@ impl TcpTransport {
         let py = slf.py();
 
         // OPTIMIZATION 1: Single borrow, extract what we need (including cached method ptrs)
-        let (has_reader, reader_py, stream_ptr, cached_data_ptr, cached_eof_ptr) = {
+        let (has_reader, reader_py, fd, data_received, chunk) = {
             let self_ = slf.borrow();
 
             if self_.state.intersects(
-                TransportState::CLOSING | TransportState::CLOSED | TransportState::READING_PAUSED,
+                TransportState::CLOSING
+                    | TransportState::CLOSED
+                    | TransportState::READING_PAUSED
+                    | TransportState::EOF_RECEIVED,
             ) {
                 self_.reading.store(false, Ordering::Release);
                 return Ok(());
@@ -1020,68 +1441,66 @@ impl TcpTransport {
             let has_reader = self_.reader.is_some();
             let reader = self_.reader.as_ref().map(|r| r.clone_ref(py));
 
-            // Extract raw pointers to cached protocol methods.
-            // These are valid as long as the TcpTransport (and its Py<PyAny> fields) is alive,
-            // which is guaranteed by `slf` holding the PyCell.
-            let data_ptr = self_.cached_data_received.as_ref().map(|m| m.as_ptr());
-            let eof_ptr = self_.cached_eof_received.as_ref().map(|m| m.as_ptr());
-
-            let stream_ptr = self_
-                .stream
+            // Our own reference: data_received may call set_protocol(),
+            // which drops the transport's cached method
+            let data_received = self_
+                .methods
+                .data_received
                 .as_ref()
-                .map(|s| s as *const std::net::TcpStream as usize);
+                .map(|m| m.clone_ref(py));
 
-            (has_reader, reader, stream_ptr, data_ptr, eof_ptr)
+            let fd = self_.stream.as_ref().map(|s| s.as_raw_fd());
+
+            (has_reader, reader, fd, data_received, self_.read_chunk_size)
         }; // Drop borrow immediately
 
-        if stream_ptr.is_none() {
+        let Some(fd) = fd else {
             slf.borrow().reading.store(false, Ordering::Release);
             return Ok(());
-        }
+        };
 
+        // One chunk per wakeup, handed over once: whatever else is waiting
+        // makes the re-armed poll fire again next iteration, after the
+        // callbacks this read set off have unwound
         if has_reader {
-            // FAST PATH: Direct StreamReader — loop with 256KB buffer, zero Python calls
+            // FAST PATH: Direct StreamReader — one chunk-sized read, zero Python calls
             RECV_BUF.with(|buf_cell| -> PyResult<()> {
-                let mut buf = buf_cell.borrow_mut();
+                let mut buf = recv_buf(buf_cell, chunk);
                 let reader_obj = reader_py.as_ref().unwrap().bind(py).borrow();
                 let mut should_wakeup = false;
                 let mut eof_reached = false;
 
-                loop {
-                    let n = unsafe {
-                        let stream = &*(stream_ptr.unwrap() as *const std::net::TcpStream);
-                        let mut s = stream;
-                        std::io::Read::read(&mut s, &mut buf[..])
+                // Borrowed only for the syscall and the count: no Python runs
+                let n = {
+                    let self_ = slf.borrow();
+                    let n = match self_.stream.as_ref() {
+                        Some(mut stream) => std::io::Read::read(&mut stream, &mut buf[..chunk]),
+                        None => Err(io::ErrorKind::WouldBlock.into()),
                     };
+                    if let Ok(n @ 1..) = n {
+                        self_.stats.add_bytes_in(n);
+                    }
+                    n
+                };
 
-                    match n {
-                        Ok(0) => {
-                            eof_reached = true;
-                            break;
-                        }
-                        Ok(n) => {
-                            reader_obj
-                                .inner
-                                .borrow_mut()
-                                .buffer
-                                .extend_from_slice(&buf[..n]);
-                            should_wakeup = true;
-
-                            // Partial read — socket drained
-                            if n < RECV_BUF_SIZE {
-                                break;
-                            }
-                        }
-                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
-                        Err(e) => {
-                            drop(reader_obj);
-                            slf.borrow().reading.store(false, Ordering::Release);
-                            return Err(e.into());
-                        }
+                match n {
+                    Ok(0) => eof_reached = true,
+                    Ok(n) => {
+                        reader_obj.inner.borrow_mut().feed_data(&buf[..n]);
+                        should_wakeup = true;
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => {
+                        drop(reader_obj);
+                        slf.borrow().reading.store(false, Ordering::Release);
+                        return Self::_fatal_error_reported(
+                            slf,
+                            crate::utils::os_error_to_pyerr(e),
+                            "Fatal read error on socket transport",
+                        );
                     }
                 }
 
-                // Wake waiters ONCE after all reads (not per read)
                 if should_wakeup {
                     reader_obj._wakeup_waiters(py)?;
                 }
@@ -1090,76 +1509,61 @@ impl TcpTransport {
                 if eof_reached {
                     drop(reader_obj);
                     reader_py.unwrap().bind(py).borrow().feed_eof_native(py)?;
-                    Self::close(slf)?;
+                    Self::_on_read_eof(slf)?;
                 }
 
                 Ok(())
             })?;
         } else {
-            // PROTOCOL PATH: Loop with 256KB buffer + vectorcall via cached methods
+            // PROTOCOL PATH: one chunk-sized read + vectorcall via cached methods
             // Reading 100KB in one syscall instead of 7× 16KB = 7× fewer event loop iterations
+            let mut callback_error = None;
+            let direct = slf.borrow().read_direct;
             RECV_BUF.with(|buf_cell| -> PyResult<()> {
-                let mut buf = buf_cell.borrow_mut();
+                let mut buf = recv_buf(buf_cell, chunk);
 
-                loop {
-                    let n = unsafe {
-                        let stream = &*(stream_ptr.unwrap() as *const std::net::TcpStream);
-                        let mut s = stream;
-                        std::io::Read::read(&mut s, &mut buf[..])
-                    };
-
-                    match n {
-                        Ok(0) => {
-                            // EOF — call cached eof_received (no attribute lookup)
-                            if let Some(eof_ptr) = cached_eof_ptr {
-                                let result = unsafe { pyo3::ffi::PyObject_CallNoArgs(eof_ptr) };
-                                if !result.is_null() {
-                                    let keep_open = unsafe {
-                                        let val = pyo3::ffi::PyObject_IsTrue(result);
-                                        pyo3::ffi::Py_DECREF(result);
-                                        val == 1
-                                    };
-                                    if !keep_open {
-                                        Self::close(slf)?;
-                                    }
-                                } else {
-                                    unsafe { pyo3::ffi::PyErr_Clear() };
-                                    Self::close(slf)?;
-                                }
-                            } else {
-                                Self::close(slf)?;
-                            }
-                            break;
+                match read_chunk(py, fd, &mut buf, chunk, direct)? {
+                    Ok((_, 0)) => {
+                        // Everything before the FIN has been delivered already
+                        Self::_on_read_eof(slf)?;
+                    }
+                    Ok((py_data, n)) => {
+                        {
+                            let mut self_ = slf.borrow_mut();
+                            self_.stats.add_bytes_in(n);
+                            self_.read_direct = n == chunk;
                         }
-                        Ok(n) => {
-                            // Zero-copy PyBytes via C API + vectorcall data_received
-                            let py_data =
-                                unsafe { crate::ffi_utils::bytes_from_slice(py, &buf[..n]) };
-                            if let Some(data_ptr) = cached_data_ptr {
-                                unsafe {
-                                    crate::ffi_utils::vectorcall_one_arg(
-                                        py,
-                                        data_ptr,
-                                        py_data.as_ptr(),
-                                    )?;
-                                }
+                        // PyBytes via C API + vectorcall data_received
+                        if let Some(data_received) = data_received.as_ref()
+                            && let Err(e) = unsafe {
+                                crate::ffi_utils::vectorcall_one_arg(
+                                    py,
+                                    data_received.as_ptr(),
+                                    py_data.as_ptr(),
+                                )
                             }
-
-                            // Partial read — socket drained, no need to loop
-                            if n < RECV_BUF_SIZE {
-                                break;
-                            }
-                        }
-                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
-                        Err(e) => {
-                            slf.borrow().reading.store(false, Ordering::Release);
-                            return Err(e.into());
+                        {
+                            callback_error = Some(e);
                         }
                     }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => {
+                        slf.borrow().reading.store(false, Ordering::Release);
+                        return Self::_fatal_error_reported(
+                            slf,
+                            crate::utils::os_error_to_pyerr(e),
+                            "Fatal read error on socket transport",
+                        );
+                    }
                 }
 
                 Ok(())
             })?;
+
+            if let Some(err) = callback_error {
+                slf.borrow().reading.store(false, Ordering::Release);
+                return Self::_protocol_failed(slf, "data_received", err);
+            }
         }
 
         slf.borrow().reading.store(false, Ordering::Release);
@@ -1183,10 +1587,7 @@ impl TcpTransport {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set TCP_NODELAY: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set TCP_NODELAY"));
                 }
             }
         }
@@ -1210,10 +1611,7 @@ impl TcpTransport {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set SO_KEEPALIVE: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set SO_KEEPALIVE"));
                 }
             }
         }
@@ -1237,10 +1635,7 @@ impl TcpTransport {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set SO_REUSEADDR: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set SO_REUSEADDR"));
                 }
             }
         }
@@ -1265,10 +1660,7 @@ impl TcpTransport {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set TCP_KEEPIDLE: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set TCP_KEEPIDLE"));
                 }
             }
         }
@@ -1293,10 +1685,7 @@ impl TcpTransport {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set TCP_KEEPINTVL: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set TCP_KEEPINTVL"));
                 }
             }
         }
@@ -1321,42 +1710,204 @@ impl TcpTransport {
                     std::mem::size_of_val(&optval) as libc::socklen_t,
                 );
                 if ret != 0 {
-                    return Err(PyErr::new::<pyo3::exceptions::PyOSError, _>(format!(
-                        "Failed to set TCP_KEEPCNT: {}",
-                        std::io::Error::last_os_error()
-                    )));
+                    return Err(crate::utils::last_os_error("Failed to set TCP_KEEPCNT"));
                 }
             }
         }
         Ok(())
     }
+
+    /// Enable SO_KEEPALIVE and set whichever probe parameters are given
+    #[pyo3(signature = (idle=None, interval=None, count=None))]
+    fn set_keepalive_params(
+        &self,
+        idle: Option<u32>,
+        interval: Option<u32>,
+        count: Option<u32>,
+    ) -> PyResult<()> {
+        if let Some(stream) = self.stream.as_ref() {
+            KeepaliveParams {
+                idle,
+                interval,
+                count,
+            }
+            .apply(stream.as_raw_fd())?;
+        }
+        Ok(())
+    }
+
+    /// Current keep-alive settings: {"enabled", "idle", "interval", "count"};
+    /// parameters the platform lacks are None
+    fn get_keepalive_params(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let stream = self
+            .stream
+            .as_ref()
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Socket closed"))?;
+        let (enabled, params) = KeepaliveParams::read(stream.as_raw_fd())?;
+        let dict = pyo3::types::PyDict::new(py);
+        dict.set_item("enabled", enabled)?;
+        dict.set_item("idle", params.idle)?;
+        dict.set_item("interval", params.interval)?;
+        dict.set_item("count", params.count)?;
+        Ok(dict.into_any().unbind())
+    }
 }
 
 impl TcpServer {
     pub fn new(
-        listener: std::net::TcpListener,
+        listeners: Vec<std::net::TcpListener>,
         loop_: Py<VeloxLoop>,
         protocol_factory: Py<PyAny>,
+        keepalive: Option<KeepaliveParams>,
     ) -> Self {
         Self {
-            listener: Some(listener),
+            listeners,
             loop_,
             protocol_factory,
-            active: true,
+            active: false,
             serve_forever_future: Mutex::new(None),
+            entered: false,
+            keepalive,
         }
     }
 
+    pub(crate) fn listener_fds(&self) -> Vec<RawFd> {
+        self.listeners.iter().map(|l| l.as_raw_fd()).collect()
+    }
+
+    fn set_tuning(&self, py: Python<'_>, option: TcpTuning, value: u32) -> PyResult<()> {
+        let debug = self.loop_.bind(py).borrow().debug_enabled();
+        for listener in &self.listeners {
+            option.set_or_warn(py, listener.as_raw_fd(), value, debug)?;
+        }
+        Ok(())
+    }
+
     pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
-        if let Some(l) = self.listener.as_ref() {
-            l.accept()
-        } else {
+        for listener in &self.listeners {
+            match super::accept_connection(listener, self.keepalive.as_ref()) {
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e),
+                Ok(accepted) => {
+                    let accepted = accepted.map_err(io::Error::other)?;
+                    let addr = accepted.peer.ok_or_else(|| {
+                        io::Error::other("accepted connection has no peer address")
+                    })?;
+                    return Ok((accepted.stream, addr));
+                }
+            }
+        }
+        if self.listeners.is_empty() {
             Err(io::Error::new(io::ErrorKind::Other, "Closed"))
+        } else {
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+
+    /// Put the listeners on the loop; a no-op if already serving or closed
+    pub(crate) fn begin_serving(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let (fds, loop_) = {
+            let mut self_ = slf.borrow_mut();
+            // Closed servers have no listeners left to serve
+            if self_.active || self_.listeners.is_empty() {
+                return Ok(());
+            }
+            self_.active = true;
+            (self_.listener_fds(), self_.loop_.clone_ref(py))
+        };
+        let loop_ = loop_.bind(py).borrow();
+        for fd in fds {
+            // Register the accept callback (native path)
+            let slf_clone = slf.clone().unbind();
+            let on_accept = Arc::new(move |py: Python<'_>| Self::_on_accept(slf_clone.bind(py)));
+            loop_.add_reader_native(fd, on_accept)?;
+            loop_.set_fd_priority(fd, true)?;
+        }
+        Ok(())
+    }
+
+    /// Drain listener `index`'s backlog, but leave the rest of a burst to the
+    /// next tick. Checked before every accept, so a close() from a protocol
+    /// callback stops the batch instead of accepting into a closed server
+    fn accept_from(slf: &Bound<'_, Self>, index: usize) -> PyResult<()> {
+        let py = slf.py();
+        for _ in 0..crate::constants::ACCEPT_BATCH {
+            let (accepted, fd) = {
+                let self_ = slf.borrow();
+                match self_.listeners.get(index) {
+                    Some(listener) if self_.active => (
+                        super::accept_connection(listener, self_.keepalive.as_ref()),
+                        listener.as_raw_fd(),
+                    ),
+                    _ => return Ok(()),
+                }
+            };
+            match accepted {
+                // One bad connection must not stop the server: report and drop it
+                Ok(accepted) => {
+                    if let Err(e) = accepted.and_then(|a| Self::accept_one(slf, a.stream)) {
+                        let loop_ = slf.borrow().loop_.clone_ref(py);
+                        ExceptionContext::new(
+                            "Error on transport creation for incoming connection",
+                        )
+                        .exception(e.value(py))
+                        .report(py, &loop_.bind(py).borrow())?;
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => {
+                    let loop_ = slf.borrow().loop_.clone_ref(py);
+                    if !super::accept_failed(py, &loop_, slf.as_any(), fd, e)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the protocol and transport of an accepted connection and start
+    /// reading. The server isn't borrowed while its protocol runs, so that
+    /// may close it
+    fn accept_one(slf: &Bound<'_, Self>, stream: std::net::TcpStream) -> PyResult<()> {
+        let py = slf.py();
+        let (protocol_factory, loop_) = {
+            let self_ = slf.borrow();
+            (
+                self_.protocol_factory.clone_ref(py),
+                self_.loop_.clone_ref(py),
+            )
+        };
+        // Create protocol
+        let protocol = protocol_factory.call0(py)?;
+        // Create Transport using the loop's factory
+        let factory = loop_.bind(py).borrow().transport_factory(py);
+        let loop_py = loop_.clone_ref(py).into_any();
+        let fd = stream.as_raw_fd();
+
+        let transport_py = factory.create_tcp(py, loop_py, stream, protocol.clone_ref(py))?;
+
+        // Connection made
+        protocol.call_method1(py, "connection_made", (transport_py.clone_ref(py),))?;
+
+        // Attempt to link StreamReader for direct path if it's a StreamReaderProtocol
+        if let Ok(reader_attr) = protocol.getattr(py, "_reader")
+            && let Ok(reader) = reader_attr.extract::<Py<crate::streams::StreamReader>>(py)
+            && let Ok(tcp_transport) = transport_py.extract::<Py<TcpTransport>>(py)
+        {
+            tcp_transport.bind(py).borrow_mut()._link_reader(reader);
         }
+        // Start reading (native path unless the factory returned its own transport)
+        factory::start_reading(py, &loop_, transport_py.bind(py), fd)
     }
 }
 
 impl TcpTransport {
+    fn tcp_info(&self) -> Option<TcpInfo> {
+        self.stream.as_ref().and_then(|s| TcpInfo::read(s.as_raw_fd()))
+    }
+
     pub fn new(
         loop_: Py<VeloxLoop>,
         stream: std::net::TcpStream,
@@ -1382,14 +1933,13 @@ impl TcpTransport {
         // Cache protocol methods at creation time.
         // This avoids a Python attribute lookup (tp_getattr → dict search → descriptor __get__)
         // on every single read/write event. The cached Py<PyAny> is a bound method object.
-        let cached_data_received = Python::attach(|py| {
-            protocol.getattr(py, "data_received").ok()
-        });
-        let cached_eof_received = Python::attach(|py| {
-            protocol.getattr(py, "eof_received").ok()
-        });
-        let cached_connection_lost = Python::attach(|py| {
-            protocol.getattr(py, "connection_lost").ok()
+        let methods = Python::attach(|py| ProtocolMethods::resolve(py, &protocol));
+        let (read_chunk_size, error_monitoring) = Python::attach(|py| {
+            let loop_ = loop_.bind(py).borrow();
+            (
+                loop_.read_chunk_size.get(),
+                loop_.monitor_idle_connections.get(),
+            )
         });
 
         Ok(Self {
@@ -1402,10 +1952,572 @@ impl TcpTransport {
             write_buffer_high: DEFAULT_HIGH,
             write_buffer_low: DEFAULT_LOW,
             reader: None,
-            cached_data_received,
-            cached_eof_received,
-            cached_connection_lost,
+            methods,
             reading: AtomicBool::new(false),
+            socket: OnceLock::new(),
+            read_chunk_size,
+            stats: TransportStats::new(),
+            coalescing: None,
+            splice_out: None,
+            splice_in: None,
+            error_monitoring,
+            rate_limit: None,
+            idle: None,
+            stashed: None,
+            read_direct: false,
+            pause_due: false,
+            resume_due: false,
+            in_flow_control: false,
         })
     }
+
+    /// Buffered data is waiting for writability rather than for the rate
+    /// limit's refill timer
+    /// Inside `_read_ready` or `deliver_prefetched`, possibly in the protocol
+    /// code they called: `_write_ready` is left to the writer callback then
+    #[inline]
+    fn in_read_dispatch(&self) -> bool {
+        self.reading.load(Ordering::Acquire)
+    }
+
+    fn wants_writer(&self) -> bool {
+        !self.write_buffer.borrow().is_empty()
+            && !self.rate_limit.as_ref().is_some_and(RateLimit::is_waiting)
+    }
+
+    /// Put the writer back for buffered data, e.g. once a paced transport's
+    /// bucket has refilled; a closing transport keeps flushing this way
+    pub(crate) fn resume_paced(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if let Some(limit) = self_.rate_limit.as_mut() {
+            limit.timer_fired();
+        }
+        if self_.state.contains(TransportState::CLOSED)
+            || self_.splice_in.is_some()
+            || !self_.wants_writer()
+        {
+            return Ok(());
+        }
+        let (fd, loop_) = (self_.fd, self_.loop_.clone_ref(py));
+        drop(self_);
+        loop_
+            .bind(py)
+            .borrow()
+            .add_tcp_writer(fd, slf.clone().unbind())
+    }
+
+    /// Apply the loop's `TransportFactoryConfig` to a freshly created transport
+    pub(crate) fn configure(&mut self, config: &TransportFactoryConfig) -> PyResult<()> {
+        if let Some(stream) = self.stream.as_ref() {
+            config.apply_socket_options(stream)?;
+        }
+        if let Some(size) = config.read_chunk_size {
+            self.read_chunk_size = size;
+        }
+        self.write_buffer
+            .replace(BytesMut::with_capacity(config.write_buffer_size));
+        if let Some((high, low)) = config.write_buffer_limits {
+            self.write_buffer_high = high;
+            self.write_buffer_low = low;
+        }
+        Ok(())
+    }
+
+    /// Append to the pending write buffer
+    fn buffer_write(&mut self, py: Python<'_>, data: &[u8]) {
+        self.write_buffer.borrow_mut().extend_from_slice(data);
+        self.maybe_pause_protocol(py);
+    }
+
+    /// `pause_writing()` once the buffer grows past the high mark; the call
+    /// itself waits for `_flow_control`, so the protocol can use the
+    /// transport. Reported to the observer once per crossing: not again until
+    /// it has drained to the low mark and resumed.
+    fn maybe_pause_protocol(&mut self, py: Python<'_>) {
+        let size = self.write_buffer.borrow().len();
+        if self.write_buffer_high == 0
+            || size <= self.write_buffer_high
+            || self.state.contains(TransportState::WRITING_PAUSED)
+        {
+            return;
+        }
+        self.state.insert(TransportState::WRITING_PAUSED);
+        stats::emit_write_buffer_high(py, &self.loop_, self.fd, size);
+        self.pause_due = true;
+    }
+
+    /// `resume_writing()` once a paused buffer drains to the low mark; the
+    /// call itself waits for `_flow_control`, so the protocol can write
+    fn maybe_resume_protocol(&mut self) {
+        if !self.state.contains(TransportState::WRITING_PAUSED)
+            || self.write_buffer.borrow().len() > self.write_buffer_low
+        {
+            return;
+        }
+        self.state.remove(TransportState::WRITING_PAUSED);
+        self.resume_due = true;
+    }
+
+    /// Call the `pause_writing()` / `resume_writing()` a write or flush made
+    /// due, with the transport no longer borrowed. One made due from inside
+    /// either callback runs after it returns rather than nested in it.
+    /// Failures are reported but leave the connection up.
+    fn _flow_control(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        loop {
+            let (method, name, loop_, protocol) = {
+                let mut self_ = slf.borrow_mut();
+                if self_.in_flow_control {
+                    return Ok(());
+                }
+                let (method, name) = if std::mem::take(&mut self_.pause_due) {
+                    (self_.methods.pause_writing.as_ref(), "pause_writing")
+                } else if std::mem::take(&mut self_.resume_due)
+                    // Paused again since, by a write the flush was part of
+                    && !self_.state.contains(TransportState::WRITING_PAUSED)
+                {
+                    (self_.methods.resume_writing.as_ref(), "resume_writing")
+                } else {
+                    return Ok(());
+                };
+                let Some(method) = method.map(|m| m.clone_ref(py)) else {
+                    continue;
+                };
+                self_.in_flow_control = true;
+                (
+                    method,
+                    name,
+                    self_.loop_.clone_ref(py),
+                    self_.protocol.clone_ref(py),
+                )
+            };
+            let result = unsafe { crate::ffi_utils::call_no_args(py, method.as_ptr()) };
+            slf.borrow_mut().in_flow_control = false;
+            if let Err(e) = result {
+                super::report_protocol_error(
+                    py,
+                    &loop_,
+                    &format!("protocol.{name}() failed"),
+                    &e,
+                    None,
+                    &protocol,
+                )?;
+            }
+        }
+    }
+
+    /// The writer callback: flush, then any `resume_writing()` that made due
+    pub(crate) fn _on_writable(slf: &Bound<'_, Self>) -> PyResult<()> {
+        slf.borrow_mut()._write_ready(slf.py())?;
+        Self::_flow_control(slf)
+    }
+
+    /// Give reading (`source`) or writing over to a splice
+    pub(crate) fn begin_splice(
+        slf: &Bound<'_, Self>,
+        splice: &Py<Splice>,
+        source: bool,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_
+            .state
+            .intersects(TransportState::CLOSING | TransportState::CLOSED)
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Transport is closing or closed",
+            ));
+        }
+        let slot = if source {
+            &self_.splice_out
+        } else {
+            &self_.splice_in
+        };
+        if slot.is_some() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "Transport is already splicing",
+            ));
+        }
+        if source {
+            self_.splice_out = Some(splice.clone_ref(py));
+            self_.loop_.bind(py).borrow().remove_reader(py, self_.fd)?;
+        } else {
+            if !self_.write_buffer.borrow().is_empty() {
+                return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                    "Cannot splice into a transport with buffered writes",
+                ));
+            }
+            self_.splice_in = Some(splice.clone_ref(py));
+        }
+        Ok(())
+    }
+
+    /// The splice is over: resume reading, or send what was written meanwhile
+    pub(crate) fn end_splice(slf: &Bound<'_, Self>, source: bool) -> PyResult<()> {
+        if source {
+            slf.borrow_mut().splice_out = None;
+            Self::start_reading(slf)
+        } else {
+            slf.borrow_mut().splice_in = None;
+            Self::_trigger_write(slf)
+        }
+    }
+
+    /// Watch for reads, unless `connection_made()` already paused reading or
+    /// handed it to a splice
+    pub(crate) fn start_reading(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let self_ = slf.borrow();
+        if self_.splice_out.is_some()
+            || self_.state.intersects(
+                TransportState::CLOSING
+                    | TransportState::CLOSED
+                    | TransportState::READING_PAUSED
+                    | TransportState::EOF_RECEIVED,
+            )
+        {
+            return Ok(());
+        }
+        let fd = self_.fd;
+        let loop_ = self_.loop_.clone_ref(py);
+        drop(self_);
+        loop_
+            .bind(py)
+            .borrow()
+            .add_tcp_reader(fd, slf.clone().unbind())
+    }
+
+    /// Reading is paused on a live socket, so an error monitor stands in for
+    /// the reader
+    fn is_monitor_due(&self) -> bool {
+        self.state.contains(TransportState::READING_PAUSED)
+            && self.splice_out.is_none()
+            && !self
+                .state
+                .intersects(TransportState::CLOSED | TransportState::EOF_RECEIVED)
+    }
+
+    /// The socket reported an error or hangup with no read pending: close with
+    /// the exception for SO_ERROR. A hangup without an error (both sides shut
+    /// down) only stops the monitor, the level-triggered poll would spin
+    pub(crate) fn _error_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        if self_.state.contains(TransportState::CLOSED) {
+            return Ok(());
+        }
+        let error = match self_.stream.as_ref().map(|s| s.take_error()) {
+            Some(Ok(Some(e))) | Some(Err(e)) => e,
+            _ => {
+                let fd = self_.fd;
+                let loop_ = self_.loop_.clone_ref(py);
+                drop(self_);
+                loop_.bind(py).borrow().remove_reader(py, fd)?;
+                return Ok(());
+            }
+        };
+        self_.fatal_error(py, error.into())
+    }
+
+    /// Closing: a splice through this socket is cancelled before the fd goes away
+    fn cancel_splices(&mut self, py: Python<'_>) -> PyResult<()> {
+        for splice in [self.splice_out.take(), self.splice_in.take()]
+            .into_iter()
+            .flatten()
+        {
+            Splice::cancel(py, &splice, self.fd)?;
+        }
+        Ok(())
+    }
+
+    /// The idle timeout wheel reached this transport at tick `now`: close it
+    /// if a timeout ran out, else the tick to check again
+    pub(crate) fn idle_expired(slf: &Bound<'_, Self>, now: u64) -> PyResult<Option<u64>> {
+        let py = slf.py();
+        let mut self_ = slf.borrow_mut();
+        let this = &mut *self_;
+        let reading = this.is_reading() && !this.state.contains(TransportState::EOF_RECEIVED);
+        let writing = !this.write_buffer.borrow().is_empty();
+        let Some(idle) = this.idle.as_mut() else {
+            return Ok(None);
+        };
+        match idle.check(&this.stats, now, reading, writing) {
+            Ok(deadline) => Ok(Some(deadline)),
+            Err(err) => {
+                self_.fatal_error(py, err)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Drop the socket and report `connection_lost` to the transport observer
+    fn teardown(&mut self, py: Python<'_>, exc: Option<&Bound<'_, PyAny>>) -> PyResult<()> {
+        if self.state.contains(TransportState::CLOSED) {
+            return Ok(());
+        }
+
+        let fd = self.fd;
+        self.cancel_splices(py)?;
+        self.state.insert(TransportState::CLOSED);
+        self.state.remove(TransportState::ACTIVE);
+        self.state.remove(TransportState::CLOSING);
+
+        let loop_ = self.loop_.bind(py).borrow();
+        let _ = loop_.remove_reader(py, fd);
+        let _ = loop_.remove_writer(py, fd);
+        // Also breaks the transport -> wakeup -> transport cycle
+        if let Some(mut limit) = self.rate_limit.take() {
+            limit.cancel(&loop_);
+        }
+        if let Some(idle) = self.idle.take() {
+            idle.disarm(&loop_);
+        }
+        drop(loop_);
+
+        stats::emit_connection_lost(py, &self.loop_, self, exc);
+        SocketWrapper::invalidate(&mut self.socket);
+        self.stream = None;
+        self.reader = None;
+        Ok(())
+    }
+
+    /// The protocol's `connection_lost`, or None when it has already been called
+    fn claim_connection_lost(&mut self, py: Python<'_>) -> Option<Py<PyAny>> {
+        if !self.state.claim_connection_lost() {
+            return None;
+        }
+        self.methods
+            .connection_lost
+            .as_ref()
+            .map(|cached| cached.clone_ref(py))
+    }
+
+    /// A read or write failed: drop the connection and pass the error to
+    /// `connection_lost` (scheduled, since callers may hold the transport)
+    fn fatal_error(&mut self, py: Python<'_>, err: PyErr) -> PyResult<()> {
+        if let Some(reader) = self.reader.as_ref() {
+            // Pending reads on a linked StreamReader fail instead of hanging
+            let reader = reader.bind(py).borrow();
+            reader.set_exception(err.to_string())?;
+            reader._wakeup_waiters(py)?;
+        }
+        let exc = err.into_value(py).into_any();
+        self.teardown(py, Some(exc.bind(py)))?;
+        if let Some(connection_lost) = self.claim_connection_lost(py) {
+            self.loop_
+                .bind(py)
+                .borrow()
+                .call_soon(connection_lost, vec![exc], None);
+        }
+        Ok(())
+    }
+
+    /// First pass of a batched dispatch: read one chunk without calling into
+    /// Python. None when there is nothing to read or the transport isn't
+    /// reading; the loop runs `_read_ready` as usual then.
+    pub(crate) fn prefetch_read(slf: &Bound<'_, Self>) -> Option<PrefetchedRead> {
+        let self_ = slf.borrow();
+        if self_.state.intersects(
+            TransportState::CLOSING
+                | TransportState::CLOSED
+                | TransportState::READING_PAUSED
+                | TransportState::EOF_RECEIVED,
+        ) || self_.stashed.is_some()
+            || self_.reading.load(Ordering::Acquire)
+        {
+            return None;
+        }
+        let mut stream = self_.stream.as_ref()?;
+        let chunk = self_.read_chunk_size;
+        let mut buf = BufferPool::acquire_sized(chunk);
+        let slice = unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr(), chunk) };
+        match stream.read(slice) {
+            Ok(0) => {
+                BufferPool::release(buf);
+                Some(PrefetchedRead::Eof)
+            }
+            Ok(n) => {
+                self_.stats.add_bytes_in(n);
+                unsafe { buf.set_len(n) };
+                Some(PrefetchedRead::Data {
+                    buf,
+                    full: n == chunk,
+                })
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                BufferPool::release(buf);
+                None
+            }
+            Err(e) => {
+                BufferPool::release(buf);
+                Some(PrefetchedRead::Failed(e))
+            }
+        }
+    }
+
+    /// Second pass: hand a prefetched read to the protocol or StreamReader.
+    /// Callbacks of other transports ran in between, so a transport closed
+    /// since drops the data and one paused since keeps it until it resumes.
+    pub(crate) fn deliver_prefetched(slf: &Bound<'_, Self>, read: PrefetchedRead) -> PyResult<()> {
+        let py = slf.py();
+        let state = slf.borrow().state;
+        if state.intersects(TransportState::CLOSING | TransportState::CLOSED) {
+            if let PrefetchedRead::Data { buf, .. } = read {
+                BufferPool::release(buf);
+            }
+            return Ok(());
+        }
+        let paused = state.contains(TransportState::READING_PAUSED);
+
+        let (buf, full) = match read {
+            PrefetchedRead::Data { buf, .. } if paused => {
+                slf.borrow_mut().stashed = Some(buf);
+                return Ok(());
+            }
+            PrefetchedRead::Data { buf, full } => (buf, full),
+            // The FIN stays readable and is seen again once reading resumes
+            PrefetchedRead::Eof if paused => return Ok(()),
+            PrefetchedRead::Eof => {
+                let reader = slf.borrow().reader.as_ref().map(|r| r.clone_ref(py));
+                if let Some(reader) = reader {
+                    reader.bind(py).borrow().feed_eof_native(py)?;
+                }
+                return Self::_on_read_eof(slf);
+            }
+            PrefetchedRead::Failed(e) => {
+                return Self::_fatal_error_reported(
+                    slf,
+                    crate::utils::os_error_to_pyerr(e),
+                    "Fatal read error on socket transport",
+                );
+            }
+        };
+
+        let (reader, data_received) = {
+            let self_ = slf.borrow();
+            self_.reading.store(true, Ordering::Release);
+            (
+                self_.reader.as_ref().map(|r| r.clone_ref(py)),
+                self_
+                    .methods
+                    .data_received
+                    .as_ref()
+                    .map(|m| m.clone_ref(py)),
+            )
+        };
+        let result = match (reader, data_received) {
+            (Some(reader), _) => {
+                let reader = reader.bind(py).borrow();
+                reader.inner.borrow_mut().feed_data(&buf);
+                reader._wakeup_waiters(py)
+            }
+            (None, Some(data_received)) => {
+                let py_data = match crate::ffi_utils::bytes_from_large_slice(py, &buf) {
+                    Ok(py_data) => py_data,
+                    Err(err) => {
+                        slf.borrow().reading.store(false, Ordering::Release);
+                        BufferPool::release(buf);
+                        return Err(err);
+                    }
+                };
+                match unsafe {
+                    crate::ffi_utils::vectorcall_one_arg(
+                        py,
+                        data_received.as_ptr(),
+                        py_data.as_ptr(),
+                    )
+                } {
+                    Ok(()) => Ok(()),
+                    Err(err) => {
+                        slf.borrow().reading.store(false, Ordering::Release);
+                        BufferPool::release(buf);
+                        return Self::_protocol_failed(slf, "data_received", err);
+                    }
+                }
+            }
+            (None, None) => Ok(()),
+        };
+        slf.borrow().reading.store(false, Ordering::Release);
+        BufferPool::release(buf);
+        // A full chunk may have more behind it, read next iteration like
+        // `_read_ready` leaves it
+        slf.borrow_mut().read_direct = full;
+        result
+    }
+
+    /// Peer sent FIN: stop watching for reads and let `eof_received()` decide.
+    /// Only a falsy result starts closing; otherwise the write side stays
+    /// usable until the protocol calls `close()` itself. If it raises, the
+    /// transport is torn down as a fatal error.
+    fn _on_read_eof(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let eof_received = {
+            let mut self_ = slf.borrow_mut();
+            if self_.state.contains(TransportState::EOF_RECEIVED) {
+                return Ok(());
+            }
+            self_.state.insert(TransportState::EOF_RECEIVED);
+            let fd = self_.fd;
+            self_.loop_.bind(py).borrow().remove_reader(py, fd)?;
+            self_.methods.eof_received.as_ref().map(|m| m.clone_ref(py))
+        };
+
+        let keep_open = match eof_received {
+            Some(method) => match method.call0(py).and_then(|res| res.bind(py).is_truthy()) {
+                Ok(keep_open) => keep_open,
+                Err(err) => return Self::_protocol_failed(slf, "eof_received", err),
+            },
+            None => false,
+        };
+        if !keep_open {
+            Self::close(slf)?;
+        }
+        Ok(())
+    }
+
+    /// A protocol callback raised: report it once, then drop the connection
+    /// so the same exception cannot fire again for the next chunk
+    fn _protocol_failed(slf: &Bound<'_, Self>, callback: &str, err: PyErr) -> PyResult<()> {
+        let py = slf.py();
+        let (loop_, protocol) = {
+            let self_ = slf.borrow();
+            (self_.loop_.clone_ref(py), self_.protocol.clone_ref(py))
+        };
+        super::report_protocol_error(
+            py,
+            &loop_,
+            &format!("Fatal error: protocol.{callback}() call failed."),
+            &err,
+            Some(slf.as_any()),
+            &protocol,
+        )?;
+        slf.borrow_mut().fatal_error(py, err)
+    }
+
+    /// A socket error ends the connection. Like asyncio's `_fatal_error`,
+    /// OSErrors only reach the exception handler in debug mode; anything
+    /// else always does.
+    fn _fatal_error_reported(slf: &Bound<'_, Self>, err: PyErr, message: &str) -> PyResult<()> {
+        let py = slf.py();
+        let (loop_, protocol) = {
+            let self_ = slf.borrow();
+            (self_.loop_.clone_ref(py), self_.protocol.clone_ref(py))
+        };
+        if !err.is_instance_of::<pyo3::exceptions::PyOSError>(py)
+            || loop_.bind(py).borrow().debug_enabled()
+        {
+            super::report_protocol_error(py, &loop_, message, &err, Some(slf.as_any()), &protocol)?;
+        }
+        slf.borrow_mut().fatal_error(py, err)
+    }
+
+    /// End-of-iteration flush of data held back by write coalescing
+    pub(crate) fn _flush_coalesced(slf: &Bound<'_, Self>) -> PyResult<()> {
+        if let Some(c) = slf.borrow_mut().coalescing.as_mut() {
+            c.queued = false;
+            c.pending_since = None;
+        }
+        Self::_trigger_write(slf)
+    }
 }
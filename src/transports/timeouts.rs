@@ -0,0 +1,179 @@
+use pyo3::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::ssl::SSLTransport;
+use super::stats::TransportStats;
+use super::stream_server::StreamTransport;
+use super::tcp::TcpTransport;
+use crate::constants::IDLE_TIMEOUT_TICK_NS;
+use crate::event_loop::VeloxLoop;
+use crate::timeout_wheel::WheelKey;
+
+/// A transport armed in the loop's idle timeout wheel
+pub(crate) enum TimedTransport {
+    Tcp(Py<TcpTransport>),
+    Stream(Py<StreamTransport>),
+    Ssl(Py<SSLTransport>),
+}
+
+impl TimedTransport {
+    pub(crate) fn clone_ref(&self, py: Python<'_>) -> Self {
+        match self {
+            Self::Tcp(transport) => Self::Tcp(transport.clone_ref(py)),
+            Self::Stream(transport) => Self::Stream(transport.clone_ref(py)),
+            Self::Ssl(transport) => Self::Ssl(transport.clone_ref(py)),
+        }
+    }
+
+    /// The wheel reached the transport's deadline at tick `now`: the tick
+    /// to check again, or None once it has closed
+    pub(crate) fn expire(&self, py: Python<'_>, now: u64) -> PyResult<Option<u64>> {
+        match self {
+            Self::Tcp(transport) => TcpTransport::idle_expired(transport.bind(py), now),
+            Self::Stream(transport) => StreamTransport::idle_expired(transport.bind(py), now),
+            Self::Ssl(transport) => SSLTransport::idle_expired(transport.bind(py), now),
+        }
+    }
+}
+
+/// `transport.set_timeouts()` settings and the transport's wheel entry.
+/// Reads and sends only stamp a tick in `TransportStats`; the deadlines are
+/// worked out from those stamps when the entry comes due.
+pub(crate) struct IdleTimeouts {
+    read: Option<f64>,
+    write: Option<f64>,
+    key: WheelKey,
+    clock: Arc<AtomicU64>,
+    /// Silence and stalled writes are counted from these ticks at the
+    /// earliest: arming, resumed reading, a write buffer found empty
+    read_since: u64,
+    write_since: u64,
+}
+
+fn check_timeout(name: &str, value: Option<f64>) -> PyResult<Option<f64>> {
+    match value {
+        Some(secs) if !(secs > 0.0 && secs.is_finite()) => {
+            Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "{name} timeout should be a positive number, got {secs}"
+            )))
+        }
+        value => Ok(value),
+    }
+}
+
+/// Ticks from the one activity was stamped in until `secs` have surely passed
+fn ticks(secs: f64) -> u64 {
+    (secs * 1e9 / IDLE_TIMEOUT_TICK_NS as f64).ceil() as u64 + 1
+}
+
+impl IdleTimeouts {
+    /// `set_timeouts(read, write)` on the transport holding `slot`: arms,
+    /// restarts both deadlines, or disarms when both are None
+    pub(crate) fn set(
+        slot: &mut Option<Self>,
+        loop_: &VeloxLoop,
+        stats: &TransportStats,
+        owner: impl FnOnce() -> TimedTransport,
+        read: Option<f64>,
+        write: Option<f64>,
+    ) -> PyResult<()> {
+        let read = check_timeout("read", read)?;
+        let write = check_timeout("write", write)?;
+        if read.is_none() && write.is_none() {
+            if let Some(old) = slot.take() {
+                old.disarm(loop_);
+            }
+            return Ok(());
+        }
+
+        let now = loop_.idle_tick();
+        stats.watch_idle(&loop_.idle_clock);
+        let mut wheel = loop_.idle_timeouts.borrow_mut();
+        match slot {
+            Some(timeouts) => {
+                timeouts.read = read;
+                timeouts.write = write;
+                timeouts.read_since = now;
+                timeouts.write_since = now;
+                wheel.schedule(timeouts.key, timeouts.first_deadline(now));
+            }
+            None => {
+                let mut timeouts = Self {
+                    read,
+                    write,
+                    key: 0,
+                    clock: loop_.idle_clock.clone(),
+                    read_since: now,
+                    write_since: now,
+                };
+                timeouts.key = wheel.insert(owner(), timeouts.first_deadline(now), now);
+                *slot = Some(timeouts);
+            }
+        }
+        Ok(())
+    }
+
+    /// `(read, write)` as given to `set_timeouts`
+    pub(crate) fn settings(&self) -> (Option<f64>, Option<f64>) {
+        (self.read, self.write)
+    }
+
+    /// Leave the wheel, when disarmed or when the transport closes
+    pub(crate) fn disarm(self, loop_: &VeloxLoop) {
+        loop_.idle_timeouts.borrow_mut().remove(self.key);
+    }
+
+    /// Reading resumed: the peer's silence while paused doesn't count
+    pub(crate) fn restart_read(&mut self) {
+        self.read_since = self.clock.load(Ordering::Relaxed);
+    }
+
+    fn first_deadline(&self, now: u64) -> u64 {
+        [self.read, self.write]
+            .into_iter()
+            .flatten()
+            .map(|secs| now + ticks(secs))
+            .min()
+            .unwrap_or(now)
+    }
+
+    /// Check at tick `now`: TimeoutError for the connection when a timeout ran
+    /// out, else the tick of the next deadline. A paused reader or an empty
+    /// write buffer keeps its timeout from running.
+    pub(crate) fn check(
+        &mut self,
+        stats: &TransportStats,
+        now: u64,
+        reading: bool,
+        writing: bool,
+    ) -> Result<u64, PyErr> {
+        let (last_read, last_sent) = stats.last_activity();
+        let mut next = u64::MAX;
+        if let Some(secs) = self.read {
+            if !reading {
+                self.read_since = now;
+            }
+            let deadline = self.read_since.max(last_read) + ticks(secs);
+            if deadline <= now {
+                return Err(PyErr::new::<pyo3::exceptions::PyTimeoutError, _>(format!(
+                    "No data received for {secs} seconds"
+                )));
+            }
+            next = next.min(deadline);
+        }
+        if let Some(secs) = self.write {
+            if !writing {
+                self.write_since = now;
+            }
+            let deadline = self.write_since.max(last_sent) + ticks(secs);
+            if deadline <= now {
+                return Err(PyErr::new::<pyo3::exceptions::PyTimeoutError, _>(format!(
+                    "Buffered writes made no progress for {secs} seconds"
+                )));
+            }
+            next = next.min(deadline);
+        }
+        Ok(next)
+    }
+}
@@ -1,13 +1,23 @@
-use parking_lot::Mutex;
 use pyo3::prelude::*;
+use pyo3::types::PyFrozenSet;
+use std::cell::RefCell;
 use std::io;
-use std::net::{SocketAddr, UdpSocket};
-use std::os::fd::{AsRawFd, RawFd};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::os::fd::{AsRawFd, BorrowedFd, RawFd};
 
-use super::TransportState;
+use super::{TransportState, call_connection_lost};
+use super::stats::{self, TransportStats};
+use crate::constants::{MAX_DATAGRAM_SIZE, UDP_RECV_BATCH};
 use crate::event_loop::VeloxLoop;
 use crate::utils::VeloxResult;
 
+// One batch of receive slots shared by the thread's UDP transports; every
+// datagram is copied out before a protocol runs. Allocated zeroed, so only
+// the pages the datagrams reach are ever touched.
+thread_local! {
+    static RECV_BATCH_BUF: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
 #[pyclass(module = "veloxloop._veloxloop")]
 pub struct UdpSocketWrapper {
     fd: RawFd,
@@ -16,8 +26,16 @@ pub struct UdpSocketWrapper {
 
 #[pymethods]
 impl UdpSocketWrapper {
-    fn getsockname(&self) -> PyResult<(String, u16)> {
-        Ok((self.addr.ip().to_string(), self.addr.port()))
+    fn getsockname(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        // Asked of the fd each time: a socket bound to port 0 (or not bound
+        // yet) only gets its real address once the kernel assigns one
+        let fd = unsafe { BorrowedFd::borrow_raw(self.fd) };
+        let addr = socket2::SockRef::from(&fd)
+            .local_addr()
+            .ok()
+            .and_then(|a| a.as_socket())
+            .unwrap_or(self.addr);
+        crate::utils::ipv6::socket_addr_to_tuple(py, addr)
     }
 
     fn fileno(&self) -> RawFd {
@@ -35,14 +53,24 @@ impl UdpSocketWrapper {
 #[pyclass(module = "veloxloop._veloxloop")]
 pub struct UdpTransport {
     fd: RawFd,
-    socket: Mutex<Option<UdpSocket>>,
+    // Only touched on the loop thread, like TcpTransport's stream
+    socket: Option<UdpSocket>,
     protocol: Py<PyAny>,
+    // Bound protocol callbacks looked up once; None when the protocol lacks one
+    cached_datagram_received: Option<Py<PyAny>>,
+    cached_error_received: Option<Py<PyAny>>,
     loop_: Py<VeloxLoop>,
     state: TransportState,
     local_addr: Option<SocketAddr>,
     remote_addr: Option<SocketAddr>,
+    stats: TransportStats,
+    // Largest datagram delivered whole; longer ones go to error_received
+    max_datagram_size: usize,
 }
 
+// Safety: only touched from the event loop thread with the GIL held
+unsafe impl Sync for UdpTransport {}
+
 impl crate::transports::Transport for UdpTransport {
     fn get_extra_info(
         &self,
@@ -52,13 +80,13 @@ impl crate::transports::Transport for UdpTransport {
     ) -> PyResult<Py<PyAny>> {
         match name {
             "addr" => {
-                if let Some(addr) = self.local_addr {
+                if let Some(addr) = self.current_local_addr() {
                     return Ok(crate::utils::ipv6::socket_addr_to_tuple(py, addr)?.into_any());
                 }
                 Ok(default.unwrap_or_else(|| py.None()))
             }
             "sockname" => {
-                if let Some(addr) = self.local_addr {
+                if let Some(addr) = self.current_local_addr() {
                     return Ok(crate::utils::ipv6::socket_addr_to_tuple(py, addr)?.into_any());
                 }
                 Ok(default.unwrap_or_else(|| py.None()))
@@ -70,12 +98,13 @@ impl crate::transports::Transport for UdpTransport {
                 Ok(default.unwrap_or_else(|| py.None()))
             }
             "socket" => {
-                if let Some(addr) = self.local_addr {
+                if let Some(addr) = self.current_local_addr() {
                     let socket_wrapper = UdpSocketWrapper::new(self.fd, addr);
                     return Ok(Py::new(py, socket_wrapper)?.into_any());
                 }
                 Ok(default.unwrap_or_else(|| py.None()))
             }
+            "veloxloop_stats" => self.stats.to_dict(py),
             _ => Ok(default.unwrap_or_else(|| py.None())),
         }
     }
@@ -87,36 +116,40 @@ impl crate::transports::Transport for UdpTransport {
     fn get_fd(&self) -> RawFd {
         self.fd
     }
+
+    fn stats(&self) -> &TransportStats {
+        &self.stats
+    }
 }
 
 #[pymethods]
 impl UdpTransport {
-    fn close(&mut self, py: Python<'_>) -> PyResult<()> {
-        if self.is_closing() {
-            return Ok(());
-        }
-        self.state.insert(TransportState::CLOSING);
-        self.abort(py)
+    /// Close the transport; `connection_lost(None)` runs once the borrow is
+    /// released, so the protocol may call close() or abort() from it
+    fn close(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let connection_lost = {
+            let mut self_ = slf.borrow_mut();
+            if self_.is_closing() {
+                return Ok(());
+            }
+            self_.state.insert(TransportState::CLOSING);
+            // Nothing is ever queued: sendto() either sends or raises
+            self_._force_close_internal(py);
+            self_.claim_connection_lost(py)
+        };
+        Self::notify_connection_lost(slf, connection_lost)
     }
 
-    fn abort(&mut self, py: Python<'_>) -> PyResult<()> {
-        if self.state.contains(TransportState::CLOSED) {
-            return Ok(());
-        }
-        self.state.insert(TransportState::CLOSED);
-        self.state.remove(TransportState::ACTIVE);
-        self.state.remove(TransportState::CLOSING);
-
-        if let Some(socket) = self.socket.lock().take() {
-            let loop_ = self.loop_.bind(py).borrow();
-            let _ = loop_.remove_reader(py, self.fd);
-            drop(socket);
-        }
-
-        let protocol = self.protocol.clone_ref(py);
-        let _ = protocol.call_method1(py, "connection_lost", (py.None(),));
-
-        Ok(())
+    /// Close at once. The same as close() while nothing can be queued
+    fn abort(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let connection_lost = {
+            let mut self_ = slf.borrow_mut();
+            self_._force_close_internal(py);
+            self_.claim_connection_lost(py)
+        };
+        Self::notify_connection_lost(slf, connection_lost)
     }
 
     #[pyo3(signature = (data, addr=None))]
@@ -141,28 +174,52 @@ impl UdpTransport {
         let len = buf_view.len_bytes();
         let data_slice = unsafe { std::slice::from_raw_parts(ptr, len) };
 
-        let socket_guard = self.socket.lock();
-        if let Some(socket) = socket_guard.as_ref() {
-            match addr {
-                Some((host, port)) => {
-                    let target_addr = format!("{}:{}", host, port);
-                    socket.send_to(data_slice, target_addr)?;
-                }
-                None => {
-                    if let Some(_remote) = self.remote_addr {
-                        socket.send(data_slice)?;
-                    } else {
-                        return Err(pyo3::exceptions::PyValueError::new_err(
-                            "Sendto requires an address for unconnected sockets",
-                        ));
+        let target = match (addr, self.remote_addr) {
+            (None, None) => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "Sendto requires an address for unconnected sockets",
+                ));
+            }
+            // Connected sockets go through send(), so the kernel reports
+            // errors queued by the connect (e.g. ICMP port unreachable)
+            (None, Some(_)) => None,
+            (Some((host, port)), remote) => {
+                let target_addr = parse_target(&host, port)?;
+                if let Some(remote) = remote {
+                    if target_addr != remote {
+                        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                            "Invalid address: must be None or {}",
+                            remote
+                        )));
                     }
+                    None
+                } else {
+                    Some(target_addr)
                 }
             }
-            Ok(())
-        } else {
-            Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+        };
+
+        let Some(socket) = self.socket.as_ref() else {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
                 "Socket is closed",
-            ))
+            ));
+        };
+        self.stats.add_write_call();
+        let result = match target {
+            Some(target_addr) => socket.send_to(data_slice, target_addr),
+            None => socket.send(data_slice),
+        };
+
+        match result {
+            Ok(n) => {
+                self.stats.add_bytes_out(n);
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                Err(crate::utils::os_error_to_pyerr(e))
+            }
+            // Like asyncio, send errors go to the protocol instead of the caller
+            Err(e) => self.error_received(data.py(), e),
         }
     }
 
@@ -170,10 +227,25 @@ impl UdpTransport {
         0 // UDP has no write buffer in this implementation
     }
 
+    /// Names of the set state flags, e.g. `frozenset({'ACTIVE'})`; the bits
+    /// are in `veloxloop._veloxloop.constants.TransportState`
+    fn get_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyFrozenSet>> {
+        self.state.to_frozenset(py)
+    }
+
+    fn __repr__(&self) -> String {
+        super::transport_repr("UdpTransport", self.fd, self.state, 0)
+    }
+
     fn is_closing(&self) -> bool {
         self.state.contains(TransportState::CLOSING) || self.state.contains(TransportState::CLOSED)
     }
 
+    /// Datagrams have no stream to half-close
+    fn can_write_eof(&self) -> bool {
+        false
+    }
+
     fn fileno(&self) -> RawFd {
         self.fd
     }
@@ -182,6 +254,23 @@ impl UdpTransport {
         self.loop_.clone_ref(py)
     }
 
+    fn get_protocol(&self, py: Python<'_>) -> Py<PyAny> {
+        self.protocol.clone_ref(py)
+    }
+
+    /// The `max_datagram_size` given to create_datagram_endpoint
+    #[getter]
+    fn max_datagram_size(&self) -> usize {
+        self.max_datagram_size
+    }
+
+    /// Switch to another protocol; its callbacks are looked up here once
+    fn set_protocol(&mut self, py: Python<'_>, protocol: Py<PyAny>) {
+        self.cached_datagram_received = protocol.getattr(py, "datagram_received").ok();
+        self.cached_error_received = protocol.getattr(py, "error_received").ok();
+        self.protocol = protocol;
+    }
+
     #[pyo3(signature = (name, default=None))]
     fn get_extra_info(
         &self,
@@ -191,12 +280,13 @@ impl UdpTransport {
     ) -> Option<Py<PyAny>> {
         match name {
             "socket" => {
-                let socket_guard = self.socket.lock();
-                if let Some(socket) = socket_guard.as_ref() {
+                if let Some(socket) = self.socket.as_ref() {
                     let fd = socket.as_raw_fd();
                     let addr = socket
                         .local_addr()
-                        .unwrap_or(self.local_addr.unwrap_or("0.0.0.0:0".parse().unwrap()));
+                        .ok()
+                        .or(self.local_addr)
+                        .unwrap_or_else(|| "0.0.0.0:0".parse().unwrap());
                     let socket_wrapper = crate::transports::udp::UdpSocketWrapper { fd, addr };
                     Py::new(py, socket_wrapper).ok().map(|s| s.into_any())
                 } else {
@@ -204,7 +294,7 @@ impl UdpTransport {
                 }
             }
             "sockname" => {
-                if let Some(addr) = self.local_addr {
+                if let Some(addr) = self.current_local_addr() {
                     crate::utils::ipv6::socket_addr_to_tuple(py, addr)
                         .ok()
                         .map(|t| t.into_any())
@@ -221,48 +311,101 @@ impl UdpTransport {
                     default
                 }
             }
+            "veloxloop_stats" => self.stats.to_dict(py).ok(),
             _ => default,
         }
     }
 }
 
 impl UdpTransport {
-    pub(crate) fn _read_ready(&self, py: Python<'_>) -> PyResult<()> {
-        if self.is_closing() {
-            return Ok(());
-        }
-
-        let socket_guard = self.socket.lock();
-        if let Some(socket) = socket_guard.as_ref() {
-            let mut pbuf = crate::buffer_pool::BufferPool::acquire();
-            pbuf.reserve(65536);
-            let len = pbuf.len();
-            let cap = pbuf.capacity();
-            let slice =
-                unsafe { std::slice::from_raw_parts_mut(pbuf.as_mut_ptr().add(len), cap - len) };
-
-            match socket.recv_from(slice) {
-                Ok((n, addr)) => {
-                    unsafe { pbuf.set_len(len + n) };
-                    let addr_tuple = crate::utils::ipv6::socket_addr_to_tuple(py, addr)?;
-                    let protocol = self.protocol.clone_ref(py);
-                    drop(socket_guard);
-
-                    // Create VeloxBuffer for zero-copy data passing
-                    let velox_buf = crate::streams::VeloxBuffer::from_bytes_mut(pbuf);
-                    let py_buf = Py::new(py, velox_buf)?;
-
-                    protocol.call_method1(py, "datagram_received", (py_buf, addr_tuple))?;
+    /// Read up to a batch of datagrams and hand each to `datagram_received`,
+    /// or report it to `error_received` if it was longer than max_datagram_size.
+    /// No borrow is held while the protocol runs, so it may sendto() or close().
+    /// A callback that raises is reported and the rest of the batch still goes out
+    pub(crate) fn _read_ready(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let (received, error, datagram_received, error_received, loop_, protocol) = {
+            let this = slf.borrow();
+            let Some(socket) = this.socket.as_ref().filter(|_| !this.is_closing()) else {
+                return Ok(());
+            };
+            let max_size = this.max_datagram_size;
+            // No datagram is longer than MAX_DATAGRAM_SIZE, whatever the limit
+            let slot = max_size.min(MAX_DATAGRAM_SIZE);
+            let mut received = Vec::new();
+            let error = RECV_BATCH_BUF.with(|buf_cell| {
+                let mut buf = buf_cell.borrow_mut();
+                let size = UDP_RECV_BATCH * slot;
+                if buf.len() < size {
+                    *buf = vec![0u8; size];
                 }
-                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                    crate::buffer_pool::BufferPool::release(pbuf);
+                recv_batch(socket, &mut buf[..size], slot, |data, len, addr| {
+                    if len > data.len() {
+                        received.push(Err(truncated_error(len, max_size)));
+                        return;
+                    }
+                    this.stats.add_bytes_in(len);
+                    let data = unsafe { crate::ffi_utils::bytes_from_slice(py, data) };
+                    received.push(Ok((data, addr)));
+                })
+            });
+            let callback = |c: &Option<Py<PyAny>>| c.as_ref().map(|c| c.clone_ref(py));
+            (
+                received,
+                error.map(crate::utils::os_error_to_pyerr),
+                callback(&this.cached_datagram_received),
+                callback(&this.cached_error_received),
+                this.loop_.clone_ref(py),
+                this.protocol.clone_ref(py),
+            )
+        };
+
+        for item in received.into_iter().chain(error.map(Err)) {
+            // A callback that closed the transport ends the batch
+            if slf.borrow().is_closing() {
+                break;
+            }
+            let (name, result) = match item {
+                Ok((data, addr)) => {
+                    let Some(datagram_received) = datagram_received.as_ref() else {
+                        continue;
+                    };
+                    let addr = match addr {
+                        Some(addr) => crate::utils::ipv6::socket_addr_to_tuple(py, addr)?,
+                        None => py.None(),
+                    };
+                    let result = unsafe {
+                        crate::ffi_utils::call_callback(
+                            py,
+                            datagram_received.as_ptr(),
+                            &[data, addr],
+                        )
+                    };
+                    ("datagram_received", result)
                 }
                 Err(e) => {
-                    crate::buffer_pool::BufferPool::release(pbuf);
-                    drop(socket_guard);
-                    let protocol = self.protocol.clone_ref(py);
-                    let _ = protocol.call_method1(py, "error_received", (e.to_string(),));
+                    let Some(error_received) = error_received.as_ref() else {
+                        continue;
+                    };
+                    let result = unsafe {
+                        crate::ffi_utils::vectorcall_one_arg(
+                            py,
+                            error_received.as_ptr(),
+                            e.value(py).as_ptr(),
+                        )
+                    };
+                    ("error_received", result)
                 }
+            };
+            if let Err(e) = result {
+                super::report_protocol_error(
+                    py,
+                    &loop_,
+                    &format!("protocol.{name}() failed"),
+                    &e,
+                    Some(slf.as_any()),
+                    &protocol,
+                )?;
             }
         }
         Ok(())
@@ -277,19 +420,192 @@ impl UdpTransport {
         socket.set_nonblocking(true)?;
         let fd = socket.as_raw_fd();
         let local_addr = socket.local_addr().ok();
+        let (cached_datagram_received, cached_error_received) = Python::attach(|py| {
+            (
+                protocol.getattr(py, "datagram_received").ok(),
+                protocol.getattr(py, "error_received").ok(),
+            )
+        });
 
         Ok(Self {
             fd,
-            socket: Mutex::new(Some(socket)),
+            socket: Some(socket),
             protocol,
+            cached_datagram_received,
+            cached_error_received,
             loop_,
             state: TransportState::ACTIVE,
             local_addr,
             remote_addr,
+            stats: TransportStats::new(),
+            max_datagram_size: MAX_DATAGRAM_SIZE,
         })
     }
 
+    /// Release the socket and its reader; connection_lost is up to the caller
+    fn _force_close_internal(&mut self, py: Python<'_>) {
+        if self.state.contains(TransportState::CLOSED) {
+            return;
+        }
+        self.state.insert(TransportState::CLOSED);
+        self.state.remove(TransportState::ACTIVE);
+        self.state.remove(TransportState::CLOSING);
+
+        if let Some(socket) = self.socket.take() {
+            let loop_ = self.loop_.bind(py).borrow();
+            let _ = loop_.remove_reader(py, self.fd);
+            drop(socket);
+        }
+        stats::emit_connection_lost(py, &self.loop_, self, None);
+    }
+
+    /// The protocol's `connection_lost`, or None when it has already been called
+    fn claim_connection_lost(&mut self, py: Python<'_>) -> Option<Py<PyAny>> {
+        if !self.state.claim_connection_lost() {
+            return None;
+        }
+        self.protocol.getattr(py, "connection_lost").ok()
+    }
+
+    fn notify_connection_lost(
+        slf: &Bound<'_, Self>,
+        connection_lost: Option<Py<PyAny>>,
+    ) -> PyResult<()> {
+        let Some(callback) = connection_lost else {
+            return Ok(());
+        };
+        let loop_ = slf.borrow().loop_.clone_ref(slf.py());
+        call_connection_lost(slf.py(), &loop_, &callback, None)
+    }
+
+    /// Deliver datagrams of up to `size` bytes; longer ones go to error_received
+    pub fn set_max_datagram_size(&mut self, size: usize) {
+        self.max_datagram_size = size;
+    }
+
     pub fn fd(&self) -> RawFd {
         self.fd
     }
+
+    /// The socket's address as the kernel reports it now, falling back to the
+    /// one seen at construction once the socket is closed
+    fn current_local_addr(&self) -> Option<SocketAddr> {
+        self.socket
+            .as_ref()
+            .and_then(|socket| socket.local_addr().ok())
+            .or(self.local_addr)
+    }
+
+    /// Pass a socket error (e.g. ConnectionRefusedError) to `protocol.error_received`
+    fn error_received(&self, py: Python<'_>, err: io::Error) -> PyResult<()> {
+        self.report_error(py, crate::utils::os_error_to_pyerr(err))
+    }
+
+    fn report_error(&self, py: Python<'_>, exc: PyErr) -> PyResult<()> {
+        let Some(error_received) = self.cached_error_received.as_ref() else {
+            return Ok(());
+        };
+        unsafe {
+            crate::ffi_utils::vectorcall_one_arg(
+                py,
+                error_received.as_ptr(),
+                exc.value(py).as_ptr(),
+            )
+        }
+    }
+}
+
+/// The OSError for a datagram of `len` bytes that didn't fit `max_size`
+fn truncated_error(len: usize, max_size: usize) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyOSError, _>((
+        libc::EMSGSIZE,
+        format!(
+            "datagram of {} bytes exceeds max_datagram_size={} and was dropped",
+            len, max_size
+        ),
+    ))
+}
+
+/// Receive up to `UDP_RECV_BATCH` datagrams with one recvmmsg() call, each into
+/// its own `slot`-byte part of `buf`. `on_datagram` gets what was stored and the
+/// datagram's real length, which MSG_TRUNC makes the kernel report even when
+/// it didn't fit. Returns the error that stopped reading, if it wasn't EAGAIN
+#[cfg(target_os = "linux")]
+fn recv_batch(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+    slot: usize,
+    mut on_datagram: impl FnMut(&[u8], usize, Option<SocketAddr>),
+) -> Option<io::Error> {
+    let mut addrs: [libc::sockaddr_storage; UDP_RECV_BATCH] = unsafe { std::mem::zeroed() };
+    let mut iovecs: [libc::iovec; UDP_RECV_BATCH] = unsafe { std::mem::zeroed() };
+    let mut msgs: [libc::mmsghdr; UDP_RECV_BATCH] = unsafe { std::mem::zeroed() };
+    for (i, chunk) in buf.chunks_exact_mut(slot).enumerate() {
+        iovecs[i] = libc::iovec {
+            iov_base: chunk.as_mut_ptr() as *mut libc::c_void,
+            iov_len: chunk.len(),
+        };
+        let hdr = &mut msgs[i].msg_hdr;
+        hdr.msg_name = &mut addrs[i] as *mut _ as *mut libc::c_void;
+        hdr.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        hdr.msg_iov = &mut iovecs[i];
+        hdr.msg_iovlen = 1;
+    }
+
+    let n = unsafe {
+        libc::recvmmsg(
+            socket.as_raw_fd(),
+            msgs.as_mut_ptr(),
+            UDP_RECV_BATCH as libc::c_uint,
+            libc::MSG_DONTWAIT | libc::MSG_TRUNC,
+            std::ptr::null_mut(),
+        )
+    };
+    if n < 0 {
+        let e = io::Error::last_os_error();
+        return (e.kind() != io::ErrorKind::WouldBlock).then_some(e);
+    }
+    for (i, msg) in msgs.iter().take(n as usize).enumerate() {
+        let len = msg.msg_len as usize;
+        let start = i * slot;
+        let data = &buf[start..start + len.min(slot)];
+        let addr =
+            crate::utils::ipv6::sockaddr_storage_to_socket_addr(&addrs[i], msg.msg_hdr.msg_namelen);
+        on_datagram(data, len, addr);
+    }
+    None
+}
+
+/// `recv_from` until the socket is drained or a batch has been read. Without
+/// MSG_TRUNC the real length of a longer datagram is unknown, so one spare
+/// byte only tells that it was cut
+#[cfg(not(target_os = "linux"))]
+fn recv_batch(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+    slot: usize,
+    mut on_datagram: impl FnMut(&[u8], usize, Option<SocketAddr>),
+) -> Option<io::Error> {
+    let end = buf.len().min(slot + 1);
+    let buf = &mut buf[..end];
+    for _ in 0..UDP_RECV_BATCH {
+        match socket.recv_from(buf) {
+            Ok((n, addr)) => on_datagram(&buf[..n.min(slot)], n, Some(addr)),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return None,
+            Err(e) => return Some(e),
+        }
+    }
+    None
+}
+
+/// A `(host, port)` datagram target, accepting IPv6 literals without brackets.
+/// Only IP literals: a host name would need a blocking lookup per datagram
+fn parse_target(host: &str, port: u16) -> PyResult<SocketAddr> {
+    match host.parse::<IpAddr>() {
+        Ok(ip) => Ok(SocketAddr::new(ip, port)),
+        Err(_) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "sendto() needs an IP address, not a host name: {}; resolve it with getaddrinfo() first",
+            host
+        ))),
+    }
 }
@@ -20,7 +20,7 @@ pub enum VeloxError {
 impl From<VeloxError> for PyErr {
     fn from(err: VeloxError) -> PyErr {
         match err {
-            VeloxError::Io(e) => PyOSError::new_err(e.to_string()),
+            VeloxError::Io(e) => os_error_to_pyerr(e),
             VeloxError::Python(e) => e,
             VeloxError::ValueError(s) => PyValueError::new_err(s),
             VeloxError::RuntimeError(s) => PyRuntimeError::new_err(s),
@@ -28,14 +28,49 @@ impl From<VeloxError> for PyErr {
     }
 }
 
+/// `OSError(errno, strerror)` for `err`, which Python narrows to the matching
+/// subclass (`ConnectionRefusedError`, `BrokenPipeError`, ...) with `.errno`
+/// set. Errors that never had an errno keep PyO3's mapping by kind.
+pub fn os_error_to_pyerr(err: io::Error) -> PyErr {
+    match err.raw_os_error() {
+        Some(errno) => PyOSError::new_err((errno, strerror(errno))),
+        None => err.into(),
+    }
+}
+
+/// Like `os_error_to_pyerr`, with `message` in place of the bare strerror
+pub fn os_error_with_message(err: &io::Error, message: impl Into<String>) -> PyErr {
+    match err.raw_os_error() {
+        Some(errno) => PyOSError::new_err((errno, message.into())),
+        None => PyOSError::new_err(message.into()),
+    }
+}
+
+/// `os_error_with_message` for what the last libc call left in errno,
+/// worded "{context}: {error}"
+pub fn last_os_error(context: &str) -> PyErr {
+    let err = io::Error::last_os_error();
+    os_error_with_message(&err, format!("{context}: {err}"))
+}
+
+/// The C library's description of `errno`, as `os.strerror()` gives it
+pub fn strerror(errno: i32) -> String {
+    unsafe { std::ffi::CStr::from_ptr(libc::strerror(errno)) }
+        .to_string_lossy()
+        .into_owned()
+}
+
 /// IPv6 helper utilities for improved address handling
 /// These utilities are planned for future IPv6 enhancements
 /// socket_addr_to_tuple() is actively used in transports
 #[allow(dead_code)]
 pub mod ipv6 {
     use super::*;
-    use pyo3::types::{PyInt, PyString, PyTuple};
-    use std::net::{Ipv6Addr, SocketAddr};
+    use pyo3::IntoPyObjectExt;
+    use pyo3::types::{PyBytes, PyInt, PyString, PyTuple};
+    use std::ffi::{CString, OsStr};
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+    use std::os::unix::ffi::OsStrExt;
 
     /// Normalize an IPv6 address string to standard representation
     /// Removes leading zeros and expands :: notation properly
@@ -134,4 +169,182 @@ pub mod ipv6 {
             }
         }
     }
+
+    /// Decode an AF_INET / AF_INET6 `sockaddr_storage` into a SocketAddr.
+    /// Ports are stored in network order; flowinfo is converted like CPython's socket module.
+    pub fn sockaddr_storage_to_socket_addr(
+        storage: &libc::sockaddr_storage,
+        len: libc::socklen_t,
+    ) -> Option<SocketAddr> {
+        let len = len as usize;
+        match storage.ss_family as libc::c_int {
+            libc::AF_INET if len >= std::mem::size_of::<libc::sockaddr_in>() => {
+                let sin = unsafe { &*(storage as *const _ as *const libc::sockaddr_in) };
+                let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+                Some(SocketAddr::V4(SocketAddrV4::new(ip, u16::from_be(sin.sin_port))))
+            }
+            libc::AF_INET6 if len >= std::mem::size_of::<libc::sockaddr_in6>() => {
+                let sin6 = unsafe { &*(storage as *const _ as *const libc::sockaddr_in6) };
+                Some(SocketAddr::V6(SocketAddrV6::new(
+                    Ipv6Addr::from(sin6.sin6_addr.s6_addr),
+                    u16::from_be(sin6.sin6_port),
+                    u32::from_be(sin6.sin6_flowinfo),
+                    sin6.sin6_scope_id,
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    /// Convert a `sockaddr_storage` filled in by accept()/recvfrom() to the Python form:
+    /// AF_INET → (ip, port), AF_INET6 → (ip, port, flowinfo, scope_id),
+    /// AF_UNIX → path str (bytes for abstract names, "" when unnamed).
+    /// Unknown families yield ("", 0).
+    pub fn parse_sockaddr_storage(
+        py: Python<'_>,
+        storage: &libc::sockaddr_storage,
+        len: libc::socklen_t,
+    ) -> PyResult<Py<PyAny>> {
+        if let Some(addr) = sockaddr_storage_to_socket_addr(storage, len) {
+            return socket_addr_to_tuple(py, addr);
+        }
+
+        if storage.ss_family as libc::c_int == libc::AF_UNIX {
+            let sun = unsafe { &*(storage as *const _ as *const libc::sockaddr_un) };
+            let path_offset = std::mem::offset_of!(libc::sockaddr_un, sun_path);
+            let path_len = (len as usize)
+                .saturating_sub(path_offset)
+                .min(sun.sun_path.len());
+            let path: Vec<u8> = sun.sun_path[..path_len].iter().map(|&c| c as u8).collect();
+
+            if path.first() == Some(&0) {
+                // Linux abstract namespace: keep the leading NUL, like CPython
+                return Ok(PyBytes::new(py, &path).into_any().unbind());
+            }
+            let end = path.iter().position(|&c| c == 0).unwrap_or(path.len());
+            return OsStr::from_bytes(&path[..end]).into_py_any(py);
+        }
+
+        ("", 0).into_py_any(py)
+    }
+
+    /// Test hook: decode raw `struct sockaddr` bytes with `parse_sockaddr_storage`
+    #[pyfunction]
+    pub fn _parse_sockaddr(py: Python<'_>, data: &[u8]) -> PyResult<Py<PyAny>> {
+        let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+        let len = data.len().min(std::mem::size_of::<libc::sockaddr_storage>());
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                &mut storage as *mut _ as *mut u8,
+                len,
+            );
+        }
+        parse_sockaddr_storage(py, &storage, len as libc::socklen_t)
+    }
+
+    /// Address families with an address configured on some interface other
+    /// than loopback, which is what AI_ADDRCONFIG checks for. Read with
+    /// getifaddrs() so it holds on a libc that ignores the flag (musl).
+    /// IPv6 link-local addresses don't count: a container with only those
+    /// has no IPv6 route out either.
+    #[pyfunction]
+    pub fn configured_address_families() -> PyResult<Vec<i32>> {
+        let mut head: *mut libc::ifaddrs = std::ptr::null_mut();
+        if unsafe { libc::getifaddrs(&mut head) } < 0 {
+            return Err(os_error_to_pyerr(std::io::Error::last_os_error()));
+        }
+        let (mut v4, mut v6) = (false, false);
+        let mut cur = head;
+        while !cur.is_null() {
+            let ifa = unsafe { &*cur };
+            cur = ifa.ifa_next;
+            if ifa.ifa_addr.is_null() || ifa.ifa_flags & libc::IFF_LOOPBACK as u32 != 0 {
+                continue;
+            }
+            match unsafe { (*ifa.ifa_addr).sa_family } as i32 {
+                libc::AF_INET => v4 = true,
+                libc::AF_INET6 => {
+                    let sin6 = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in6) };
+                    let addr = Ipv6Addr::from(sin6.sin6_addr.s6_addr);
+                    v6 |= !addr.is_loopback() && !is_link_local(&addr);
+                }
+                _ => {}
+            }
+        }
+        unsafe { libc::freeifaddrs(head) };
+        Ok([(v4, libc::AF_INET), (v6, libc::AF_INET6)]
+            .into_iter()
+            .filter_map(|(seen, family)| seen.then_some(family))
+            .collect())
+    }
+
+    /// Split "fe80::1%2" / "fe80::1%eth0" into the address and its scope id
+    pub fn split_scope_id(host: &str) -> VeloxResult<(&str, u32)> {
+        let Some((addr, scope)) = host.split_once('%') else {
+            return Ok((host, 0));
+        };
+        if let Ok(id) = scope.parse::<u32>() {
+            return Ok((addr, id));
+        }
+        let name = CString::new(scope)
+            .map_err(|_| VeloxError::ValueError(format!("Invalid scope id: {}", scope)))?;
+        let id = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if id == 0 {
+            return Err(VeloxError::ValueError(format!("Unknown interface: {}", scope)));
+        }
+        Ok((addr, id))
+    }
+
+    /// Turn a Python address tuple into a SocketAddr for a socket of `family`
+    /// (AF_UNSPEC accepts either). Accepts (host, port) and
+    /// (host, port, flowinfo, scope_id); the host may carry a `%scope` suffix.
+    /// IPv4 hosts are mapped to ::ffff:a.b.c.d for AF_INET6 sockets. The host
+    /// must be an address literal: names are looked up through `getaddrinfo()`
+    /// before getting here, never on the loop thread.
+    pub fn parse_address_tuple(address: &Bound<'_, PyTuple>, family: i32) -> PyResult<SocketAddr> {
+        if !(2..=4).contains(&address.len()) {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "address must be (host, port) or (host, port, flowinfo, scope_id)",
+            ));
+        }
+        let host: String = address.get_item(0)?.extract()?;
+        let port: u16 = address.get_item(1)?.extract()?;
+        let flowinfo: u32 = match address.len() {
+            3.. => address.get_item(2)?.extract()?,
+            _ => 0,
+        };
+        let tuple_scope: u32 = match address.len() {
+            4 => address.get_item(3)?.extract()?,
+            _ => 0,
+        };
+
+        let (host_part, suffix_scope) = split_scope_id(&host)?;
+        let scope_id = if tuple_scope != 0 { tuple_scope } else { suffix_scope };
+
+        let ip = match host_part.parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(_) => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid IP address: {}",
+                    host_part
+                )));
+            }
+        };
+
+        let addr = match (ip, family) {
+            (IpAddr::V4(v4), libc::AF_INET6) => {
+                SocketAddr::V6(SocketAddrV6::new(v4.to_ipv6_mapped(), port, flowinfo, scope_id))
+            }
+            (IpAddr::V6(_), libc::AF_INET) => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "IPv6 address {} used with an AF_INET socket",
+                    host_part
+                )));
+            }
+            (IpAddr::V4(v4), _) => SocketAddr::V4(SocketAddrV4::new(v4, port)),
+            (IpAddr::V6(v6), _) => SocketAddr::V6(SocketAddrV6::new(v6, port, flowinfo, scope_id)),
+        };
+        Ok(addr)
+    }
 }
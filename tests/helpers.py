@@ -0,0 +1,32 @@
+"""Socket helpers shared by the test modules"""
+
+import socket
+
+
+def socket_pair():
+    """A connected, non-blocking socketpair"""
+    a, b = socket.socketpair()
+    a.setblocking(False)
+    b.setblocking(False)
+    return a, b
+
+
+def closed_port():
+    """A local port with nothing listening on it"""
+    with socket.socket() as sock:
+        sock.bind(('127.0.0.1', 0))
+        return sock.getsockname()[1]
+
+
+def blackhole(host='127.0.0.1'):
+    """A listener whose accept queue is full, so further SYNs are dropped.
+    Returns (listener, fillers, port); the caller closes the sockets"""
+    listener = socket.create_server((host, 0), backlog=0)
+    port = listener.getsockname()[1]
+    fillers = []
+    for _ in range(3):
+        sock = socket.socket()
+        sock.setblocking(False)
+        sock.connect_ex((host, port))
+        fillers.append(sock)
+    return listener, fillers, port
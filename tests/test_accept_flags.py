@@ -0,0 +1,97 @@
+"""Tests that accepted connections are non-blocking and close-on-exec"""
+
+import asyncio
+import os
+import socket
+import subprocess
+import sys
+
+import pytest
+
+import veloxloop
+
+
+def _child_fds():
+    """fds a child spawned without close_fds inherits"""
+    result = subprocess.run(
+        [sys.executable, '-c', 'import os; print(*os.listdir("/proc/self/fd"))'],
+        close_fds=False,
+        capture_output=True,
+        text=True,
+        check=True,
+    )
+    return {int(fd) for fd in result.stdout.split()}
+
+
+def _assert_not_inherited(fd):
+    assert not os.get_inheritable(fd)
+    assert not os.get_blocking(fd)
+    assert fd not in _child_fds()
+
+
+@pytest.mark.skipif(not os.path.isdir('/proc/self/fd'), reason='needs /proc')
+class TestAcceptFlags:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_sock_accept(self):
+        """Test sock_accept's connections, accepted at once or after waiting,
+        stay out of subprocesses"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            listener = socket.create_server(('127.0.0.1', 0))
+            listener.setblocking(False)
+            address = listener.getsockname()
+
+            early = socket.create_connection(address)
+            conn, _ = await loop.sock_accept(listener)
+            _assert_not_inherited(conn.fileno())
+            conn.close()
+
+            waiting = loop.sock_accept(listener)
+            late = socket.create_connection(address)
+            conn, _ = await waiting
+            _assert_not_inherited(conn.fileno())
+            conn.close()
+
+            for sock in (early, late, listener):
+                sock.close()
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('kind', ['create_server', 'start_server'])
+    def test_server_connections(self, kind):
+        """Test connections a server accepts stay out of subprocesses"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            accepted = loop.create_future()
+
+            if kind == 'create_server':
+
+                class Server(asyncio.Protocol):
+                    def connection_made(self, transport):
+                        accepted.set_result(transport)
+
+                server = await loop.create_server(Server, '127.0.0.1', 0)
+            else:
+
+                async def on_client(reader, writer):
+                    accepted.set_result(writer.transport)
+
+                server = await loop.start_server(on_client, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            client = socket.create_connection(('127.0.0.1', port))
+            transport = await asyncio.wait_for(accepted, 5)
+            fd = transport.fileno()
+            _assert_not_inherited(fd)
+            transport.close()
+            client.close()
+            server.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
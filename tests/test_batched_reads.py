@@ -0,0 +1,197 @@
+"""Tests for reading several ready TCP sockets before delivering their data"""
+
+import asyncio
+import socket
+import time
+
+import pytest
+
+import veloxloop
+
+
+class Collector(asyncio.Protocol):
+    """Records what arrives; `on_data(self)` runs after each chunk"""
+
+    def __init__(self, peers, on_data=None):
+        self.peers = peers
+        self.on_data = on_data
+        self.chunks = []
+        self.lost = False
+        self.data_after_close = False
+
+    def connection_made(self, transport):
+        self.transport = transport
+        self.peers.append(self)
+
+    def data_received(self, data):
+        if self.transport.is_closing():
+            self.data_after_close = True
+        self.chunks.append(data)
+        if self.on_data:
+            self.on_data(self)
+
+    def connection_lost(self, exc):
+        self.lost = True
+
+    @property
+    def data(self):
+        return b''.join(self.chunks)
+
+
+async def _until(predicate, timeout=5):
+    deadline = time.monotonic() + timeout
+    while not predicate():
+        assert time.monotonic() < deadline, 'timed out waiting'
+        await asyncio.sleep(0.01)
+
+
+async def _connected(count, on_data=None):
+    """`count` raw client sockets and the server side protocols they reached"""
+    loop = asyncio.get_running_loop()
+    peers = []
+    server = await loop.create_server(
+        lambda: Collector(peers, on_data), '127.0.0.1', 0
+    )
+    port = server.sockets[0].getsockname()[1]
+    clients = [socket.create_connection(('127.0.0.1', port)) for _ in range(count)]
+    await _until(lambda: len(peers) == count)
+    return server, clients, peers
+
+
+def _send_all(clients, payloads):
+    """Send from every client while the loop isn't looking, so the data is
+    waiting on all the sockets by the next poll"""
+    for client, payload in zip(clients, payloads):
+        client.sendall(payload)
+    time.sleep(0.05)
+
+
+class TestBatchedReads:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_many_connections_in_order(self):
+        """Test rounds of data on many sockets at once arrive whole and in order"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server, clients, peers = await _connected(50)
+            # Server-side order is accept order; match peers to clients by port
+            by_port = {p.transport.get_extra_info('peername')[1]: p for p in peers}
+            peers = [by_port[c.getsockname()[1]] for c in clients]
+            expected = [b''] * len(clients)
+            for round_ in range(20):
+                payloads = [
+                    f'{i}:{round_};'.encode() * (1 + (i * round_) % 50)
+                    for i in range(len(clients))
+                ]
+                _send_all(clients, payloads)
+                expected = [e + p for e, p in zip(expected, payloads)]
+                await _until(
+                    lambda: all(p.data == e for p, e in zip(peers, expected))
+                )
+            for client in clients:
+                client.close()
+            server.close()
+            return loop.get_stats()
+
+        stats = asyncio.run(main())
+        assert stats['batched_read_ticks'] > 0
+        assert stats['batched_reads'] >= 2 * stats['batched_read_ticks']
+
+    def test_large_chunks_keep_reading(self):
+        """Test more than a read chunk per socket is delivered completely"""
+
+        async def main():
+            server, clients, peers = await _connected(8)
+            for peer in peers:
+                peer.transport.set_read_chunk_size(1024)
+            payloads = [bytes([65 + i]) * 100_000 for i in range(len(clients))]
+            for client, payload in zip(clients, payloads):
+                client.setblocking(False)
+                client.send(payload)
+            await _until(lambda: sum(len(p.data) for p in peers) == 8 * 100_000)
+            # Every socket got only its own bytes, in one run per peer
+            assert sorted(p.data for p in peers) == sorted(payloads)
+            for client in clients:
+                client.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_pause_from_another_callback(self):
+        """Test data read ahead for a transport another protocol paused waits
+        for resume_reading and then arrives once, in order"""
+
+        def pause_others(peer):
+            for other in peer.peers:
+                if other is not peer:
+                    other.transport.pause_reading()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server, clients, peers = await _connected(10, pause_others)
+            _send_all(clients, [b'first-%d;' % i for i in range(len(clients))])
+            await _until(lambda: any(p.chunks for p in peers))
+            await asyncio.sleep(0.1)
+            assert loop.get_stats()['batched_reads'] > 1
+            # Only the first protocol to be called got anything
+            assert sum(1 for p in peers if p.chunks) == 1
+            for peer in peers:
+                peer.on_data = None
+                peer.transport.resume_reading()
+            # Data read before the pause comes first, then what followed it
+            _send_all(clients, [b'second-%d;' % i for i in range(len(clients))])
+            await _until(lambda: all(p.data.count(b';') == 2 for p in peers))
+            for peer in peers:
+                assert peer.data.startswith(b'first-')
+                assert peer.data.endswith(b';')
+                assert b'second-' in peer.data
+            for client in clients:
+                client.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_close_from_another_callback(self):
+        """Test a transport closed by another protocol's data_received gets
+        no more data, even if it was already read"""
+
+        def close_others(peer):
+            for other in peer.peers:
+                if other is not peer:
+                    other.transport.close()
+
+        async def main():
+            server, clients, peers = await _connected(10, close_others)
+            _send_all(clients, [b'data'] * len(clients))
+            await _until(lambda: all(p.lost or p.chunks for p in peers))
+            await asyncio.sleep(0.1)
+            assert sum(1 for p in peers if p.chunks) == 1
+            assert not any(p.data_after_close for p in peers)
+            for client in clients:
+                client.close()
+            server.close()
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('count', [2, 20])
+    def test_eof_on_many_sockets(self, count):
+        """Test FINs arriving together each reach eof_received after the data"""
+
+        async def main():
+            server, clients, peers = await _connected(count)
+            for client in clients:
+                client.sendall(b'bye')
+                client.shutdown(socket.SHUT_WR)
+            await _until(lambda: all(p.transport.is_closing() for p in peers))
+            assert all(p.data == b'bye' for p in peers)
+            for client in clients:
+                client.close()
+            server.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
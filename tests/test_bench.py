@@ -0,0 +1,153 @@
+"""Tests for the bundled loop benchmarks (veloxloop._veloxloop.bench and
+python -m veloxloop.bench)"""
+
+import asyncio
+import contextlib
+import io
+import json
+import os
+
+import pytest
+
+import veloxloop
+from veloxloop import bench
+from veloxloop._veloxloop import bench as native_bench
+
+LOOPS = [veloxloop.VeloxLoop, asyncio.SelectorEventLoop]
+
+
+def _open_fds():
+    return len(os.listdir('/proc/self/fd'))
+
+
+class TestBenchScenarios:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('make_loop', LOOPS)
+    def test_echo_throughput(self, make_loop):
+        """Test the echo scenario reports round trips on any loop, over the
+        native streams only for a VeloxLoop"""
+        loop = make_loop()
+        try:
+            result = native_bench.echo_throughput(
+                loop, connections=3, message_size=512, duration=0.2
+            )
+        finally:
+            loop.close()
+        assert result['native'] is isinstance(loop, veloxloop.VeloxLoop)
+        assert result['connections'] == 3
+        assert result['message_size'] == 512
+        assert result['messages'] > 0
+        assert result['messages_per_sec'] > 0
+        assert 0 < result['p50_us'] <= result['p99_us'] <= result['max_us']
+        assert result['duration'] >= 0.2
+
+    @pytest.mark.parametrize('make_loop', LOOPS)
+    def test_timer_storm(self, make_loop):
+        """Test every timer of the storm fires and its lateness is reported"""
+        loop = make_loop()
+        try:
+            result = native_bench.timer_storm(loop, 2000, spread=0.02)
+        finally:
+            loop.close()
+        assert result['count'] == 2000
+        assert result['schedule_per_sec'] > result['timers_per_sec'] > 0
+        assert result['lateness_p50_us'] <= result['lateness_p99_us']
+        assert result['lateness_p99_us'] <= result['lateness_max_us']
+        # Due across 20ms, nowhere near the scenario's timeout
+        assert result['duration'] < 5
+
+    @pytest.mark.parametrize('make_loop', LOOPS)
+    def test_callback_chain(self, make_loop):
+        """Test depth x width callbacks run and their rate is reported"""
+        loop = make_loop()
+        try:
+            result = native_bench.callback_chain(loop, depth=500, width=4)
+        finally:
+            loop.close()
+        assert result['callbacks'] == 2000
+        assert result['callbacks_per_sec'] > 0
+
+    @pytest.mark.parametrize('make_loop', LOOPS)
+    def test_nothing_left_behind(self, make_loop):
+        """Test the scenarios close every socket and leave no task, so the
+        loop runs them again and keeps working afterwards"""
+        loop = make_loop()
+        try:
+            # The first run creates what the loop keeps for its lifetime
+            native_bench.echo_throughput(loop, 2, 64, 0.05)
+            fds = _open_fds()
+            for _ in range(3):
+                native_bench.echo_throughput(loop, 2, 64, 0.05)
+                native_bench.timer_storm(loop, 100, 0.001)
+                native_bench.callback_chain(loop, 10, 2)
+            assert _open_fds() == fds
+            assert not asyncio.all_tasks(loop)
+            assert not loop.is_running()
+            assert loop.run_until_complete(asyncio.sleep(0, 'still usable')) == (
+                'still usable'
+            )
+        finally:
+            loop.close()
+
+    def test_rejects_empty_scenarios(self):
+        """Test zero-sized scenarios raise ValueError before touching the loop"""
+        loop = veloxloop.VeloxLoop()
+        try:
+            for call in (
+                lambda: native_bench.echo_throughput(loop, connections=0),
+                lambda: native_bench.echo_throughput(loop, message_size=0),
+                lambda: native_bench.echo_throughput(loop, duration=0),
+                lambda: native_bench.timer_storm(loop, 0),
+                lambda: native_bench.timer_storm(loop, 10, spread=-1),
+                lambda: native_bench.callback_chain(loop, depth=0),
+                lambda: native_bench.callback_chain(loop, width=0),
+            ):
+                with pytest.raises(ValueError):
+                    call()
+        finally:
+            loop.close()
+
+
+class TestBenchCommand:
+    def setup_method(self):
+        veloxloop.install()
+
+    def _main(self, *argv):
+        out = io.StringIO()
+        with contextlib.redirect_stdout(out):
+            assert bench.main(list(argv)) == 0
+        return out.getvalue()
+
+    def test_json_output(self):
+        """Test --json prints every chosen scenario for every chosen loop"""
+        results = json.loads(
+            self._main(
+                '--json',
+                '--loop=veloxloop',
+                '--loop=asyncio',
+                '--duration=0.05',
+                '--count=100',
+                '--depth=50',
+                '--width=2',
+            )
+        )
+        assert set(results) == {'echo', 'timers', 'callbacks'}
+        for per_loop in results.values():
+            assert set(per_loop) == {'veloxloop', 'asyncio'}
+        assert results['echo']['veloxloop']['native'] is True
+        assert results['echo']['asyncio']['native'] is False
+        assert results['timers']['asyncio']['count'] == 100
+        assert results['callbacks']['veloxloop']['callbacks'] == 100
+
+    def test_table_output(self):
+        """Test the default output is one table per scenario"""
+        text = self._main('--scenario=callbacks', '--loop=veloxloop', '--depth=10')
+        assert text.startswith('## callbacks')
+        assert '| veloxloop |' in text
+        assert 'callbacks_per_sec' in text
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,304 @@
+"""Tests for connection_lost ordering: called once, with the error that closed the transport"""
+
+import asyncio
+import socket
+import struct
+
+import pytest
+
+import veloxloop
+
+
+class Recorder(asyncio.Protocol):
+    def __init__(self):
+        self.lost = []
+        self.transport = None
+
+    def connection_made(self, transport):
+        self.transport = transport
+
+    def connection_lost(self, exc):
+        self.lost.append(exc)
+
+
+def _reset(sock):
+    """Close with SO_LINGER 0 so the peer gets an RST instead of a FIN"""
+    sock.setsockopt(socket.SOL_SOCKET, socket.SO_LINGER, struct.pack('ii', 1, 0))
+    sock.close()
+
+
+CLOSE_SEQUENCES = [
+    ('close', 'close'),
+    ('close', 'abort'),
+    ('abort', 'close'),
+    ('abort', 'abort'),
+    ('_force_close', 'close'),
+    ('close', '_force_close'),
+    ('write_close', 'close'),
+    ('write_close', 'abort'),
+]
+
+
+class TestConnectionLostOnce:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('first, second', CLOSE_SEQUENCES)
+    def test_tcp_close_entry_points_twice(self, first, second):
+        """Test every pair of close entry points notifies the protocol exactly once"""
+
+        def drive(transport, step):
+            if step == 'write_close':
+                # Leaves data queued, so close() finishes from the writer callback
+                transport.write(b'x' * (4 * 1024 * 1024))
+                transport.close()
+            else:
+                getattr(transport, step)()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, proto = await loop.create_connection(Recorder, '127.0.0.1', port)
+
+            drive(transport, first)
+            drive(transport, second)
+            await asyncio.sleep(0.1)
+            transport.close()
+            transport.abort()
+            await asyncio.sleep(0.05)
+
+            server.close()
+            await server.wait_closed()
+            return proto.lost
+
+        lost = asyncio.run(main())
+        assert len(lost) == 1, lost
+        assert lost[0] is None
+
+    def test_udp_close_and_abort(self):
+        """Test UDP close()/abort() in any order notify once"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, proto = await loop.create_datagram_endpoint(
+                Recorder, local_addr=('127.0.0.1', 0)
+            )
+            transport.close()
+            transport.close()
+            transport.abort()
+            await asyncio.sleep(0.01)
+            return proto.lost
+
+        assert asyncio.run(main()) == [None]
+
+    def test_connection_lost_exception_goes_to_handler(self):
+        """Test an exception raised by connection_lost reaches the exception handler"""
+        contexts = []
+
+        class Raising(asyncio.Protocol):
+            def connection_lost(self, exc):
+                raise ValueError('boom')
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_exception_handler(lambda lp, ctx: contexts.append(ctx))
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(Raising, '127.0.0.1', port)
+            transport.close()
+            transport.abort()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+        assert len(contexts) == 1, contexts
+        assert isinstance(contexts[0]['exception'], ValueError)
+
+
+    def test_eof_received_exception_goes_to_handler(self):
+        """Test an exception raised by eof_received reaches the exception
+        handler and ends the connection with it"""
+        contexts = []
+        lost = []
+
+        class Raising(asyncio.Protocol):
+            def eof_received(self):
+                raise ValueError('boom')
+
+            def connection_lost(self, exc):
+                lost.append(exc)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_exception_handler(lambda lp, ctx: contexts.append(ctx))
+            listener = socket.create_server(('127.0.0.1', 0))
+            listener.setblocking(False)
+            transport, _ = await loop.create_connection(
+                Raising, '127.0.0.1', listener.getsockname()[1]
+            )
+            conn, _ = await loop.sock_accept(listener)
+            listener.close()
+            conn.shutdown(socket.SHUT_WR)
+            deadline = loop.time() + 5
+            while not lost and loop.time() < deadline:
+                await asyncio.sleep(0.01)
+            conn.close()
+
+        asyncio.run(main())
+        assert len(contexts) == 1, contexts
+        assert isinstance(contexts[0]['exception'], ValueError)
+        assert len(lost) == 1 and isinstance(lost[0], ValueError), lost
+
+
+class TestConnectionLostError:
+    def setup_method(self):
+        veloxloop.install()
+
+    def _reset_by_peer(self, pause_reading):
+        """Connect to a plain listener that resets the connection"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            listener = socket.socket()
+            listener.bind(('127.0.0.1', 0))
+            listener.listen(1)
+            listener.setblocking(False)
+            port = listener.getsockname()[1]
+
+            transport, proto = await loop.create_connection(Recorder, '127.0.0.1', port)
+            if pause_reading:
+                transport.pause_reading()
+            conn, _ = await loop.sock_accept(listener)
+            _reset(conn)
+            listener.close()
+            await asyncio.sleep(0.05)
+
+            if pause_reading:
+                # The failed send closes the transport rather than raising
+                transport.write(b'after reset')
+            await asyncio.sleep(0.05)
+            transport.close()
+            transport.abort()
+            await asyncio.sleep(0.01)
+            return proto.lost, transport.is_closing()
+
+        return asyncio.run(main())
+
+    def test_read_reset_passes_connection_reset_error(self):
+        """Test ECONNRESET on read closes the transport and reaches connection_lost"""
+        lost, closing = self._reset_by_peer(pause_reading=False)
+        assert closing
+        assert len(lost) == 1, lost
+        assert isinstance(lost[0], ConnectionResetError)
+
+    def test_write_after_reset_passes_connection_error(self):
+        """Test a send failure closes the transport and reaches connection_lost"""
+        lost, closing = self._reset_by_peer(pause_reading=True)
+        assert closing
+        assert len(lost) == 1, lost
+        # ECONNRESET on the first send after the RST, EPIPE afterwards
+        assert isinstance(lost[0], (ConnectionResetError, BrokenPipeError))
+
+    def test_stream_transport_reports_reset_once(self):
+        """Test start_server transports report one connection_lost event with the error"""
+        events = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_transport_observer(events.append)
+            handled = loop.create_future()
+
+            async def handler(reader, writer):
+                await asyncio.sleep(0.1)
+                writer.close()
+                handled.set_result(None)
+
+            server = await asyncio.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            client = socket.create_connection(('127.0.0.1', port))
+            await asyncio.sleep(0.02)
+            _reset(client)
+            await asyncio.wait_for(handled, 5)
+            await asyncio.sleep(0.01)
+            loop.set_transport_observer(None)
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+        lost = [e for e in events if e[0] == 'connection_lost']
+        assert len(lost) == 1, events
+        assert isinstance(lost[0][2], ConnectionResetError)
+
+
+class HangupRecorder(Recorder):
+    """Records data, eof_received and connection_lost in the order they ran"""
+
+    def __init__(self):
+        super().__init__()
+        self.events = []
+
+    def data_received(self, data):
+        self.events.append(('data', data))
+
+    def eof_received(self):
+        self.events.append(('eof',))
+
+    def connection_lost(self, exc):
+        super().connection_lost(exc)
+        self.events.append(('lost', type(exc)))
+
+
+class TestPeerHangup:
+    """The peer goes away while reading is paused: once it resumes, what was
+    sent before comes first, then EOF or the reset"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def _hangup_while_paused(self, hang_up):
+        async def main():
+            loop = asyncio.get_running_loop()
+            listener = socket.create_server(('127.0.0.1', 0))
+            listener.setblocking(False)
+            transport, proto = await loop.create_connection(
+                HangupRecorder, '127.0.0.1', listener.getsockname()[1]
+            )
+            conn, _ = await loop.sock_accept(listener)
+            listener.close()
+            transport.pause_reading()
+            hang_up(conn)
+            await asyncio.sleep(0.1)
+            assert proto.events == []
+            transport.resume_reading()
+            deadline = loop.time() + 5
+            while not proto.lost and loop.time() < deadline:
+                await asyncio.sleep(0.01)
+            conn.close()
+            return proto.events
+
+        return asyncio.run(main())
+
+    def test_shutdown_without_data(self):
+        """Test a peer's SHUT_WR with nothing sent reaches eof_received"""
+        events = self._hangup_while_paused(lambda conn: conn.shutdown(socket.SHUT_WR))
+        assert events == [('eof',), ('lost', type(None))]
+
+    def test_close_with_unread_data(self):
+        """Test data sent before the peer closed is delivered before EOF"""
+
+        def send_and_close(conn):
+            conn.sendall(b'last words')
+            conn.close()
+
+        events = self._hangup_while_paused(send_and_close)
+        assert events == [('data', b'last words'), ('eof',), ('lost', type(None))]
+
+    def test_reset(self):
+        """Test a reset while paused closes with ConnectionResetError on resume"""
+        events = self._hangup_while_paused(_reset)
+        assert events == [('lost', ConnectionResetError)]
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -30,6 +30,32 @@ class TestCorePollingBasic:
         assert isinstance(loop, veloxloop.VeloxLoop)
         loop.close()
 
+    def test_backend_capabilities(self):
+        """Test get_backend_capabilities reports the probed io_uring opcodes"""
+        loop = veloxloop.new_event_loop()
+        try:
+            caps = loop.get_backend_capabilities()
+            assert caps['backend'] == 'io_uring'
+            flags = {
+                k: v
+                for k, v in caps.items()
+                if k not in ('backend', 'max_callbacks_per_tick')
+            }
+            assert {
+                'has_splice',
+                'has_accept',
+                'has_connect',
+                'has_timeout_abs',
+                'has_multishot_poll',
+                'has_multishot_accept',
+            } <= set(flags)
+            assert all(isinstance(v, bool) for v in flags.values())
+            # A copy: changing it doesn't change the loop
+            caps['has_splice'] = None
+            assert isinstance(loop.get_backend_capabilities()['has_splice'], bool)
+        finally:
+            loop.close()
+
     def test_loop_time_basic(self):
         """Test loop.time() returns a value"""
 
@@ -161,6 +187,132 @@ class TestCorePollingBasic:
         loop.close()
         assert loop.is_closed()
 
+    def test_state_from_callbacks(self):
+        """Test is_running() stays true after stop() until the loop returns"""
+        loop = asyncio.new_event_loop()
+        seen = []
+
+        def callback():
+            loop.stop()
+            seen.append(loop.is_running())
+            with pytest.raises(RuntimeError):
+                loop.close()
+            seen.append(loop.is_closed())
+
+        try:
+            loop.call_soon(callback)
+            loop.run_forever()
+            assert seen == [True, False]
+            assert not loop.is_running()
+        finally:
+            loop.close()
+        assert loop.is_closed()
+
+    @pytest.mark.parametrize(
+        'schedule',
+        [
+            lambda loop: loop.call_soon(print),
+            lambda loop: loop.call_soon_threadsafe(print),
+            lambda loop: loop.call_later(1, print),
+            lambda loop: loop.call_at(loop.time() + 1, print),
+            lambda loop: loop.add_reader(0, print),
+            lambda loop: loop.add_writer(1, print),
+            lambda loop: loop.run_forever(),
+        ],
+    )
+    def test_closed_loop_refuses_work(self, schedule):
+        """Test scheduling on a closed loop raises instead of queuing"""
+        loop = asyncio.new_event_loop()
+        loop.close()
+        with pytest.raises(RuntimeError, match='Event loop is closed'):
+            schedule(loop)
+
+    def test_call_soon_threadsafe_after_close_from_thread(self):
+        """Test a producer thread learns its callback won't run once closed"""
+        loop = asyncio.new_event_loop()
+        loop.run_until_complete(asyncio.sleep(0))
+        loop.close()
+        errors = []
+
+        def producer():
+            try:
+                loop.call_soon_threadsafe(print, 'lost')
+            except RuntimeError as e:
+                errors.append(str(e))
+
+        thread = threading.Thread(target=producer)
+        thread.start()
+        thread.join()
+        assert errors == ['Event loop is closed']
+
+    def test_callbacks_scheduled_before_first_run(self):
+        """Test callbacks queued before run_forever run on its first iteration"""
+        loop = asyncio.new_event_loop()
+        ran = []
+        try:
+            loop.call_soon(ran.append, 'soon')
+            thread = threading.Thread(
+                target=loop.call_soon_threadsafe, args=(ran.append, 'threadsafe')
+            )
+            thread.start()
+            thread.join()
+            loop.call_soon(loop.stop)
+            loop.run_forever()
+            assert ran == ['soon', 'threadsafe']
+        finally:
+            loop.close()
+
+    def test_slow_callback_duration(self):
+        """Test slow_callback_duration defaults to asyncio's 0.1s and round-trips"""
+        loop = asyncio.new_event_loop()
+        try:
+            assert loop.slow_callback_duration == 0.1
+            loop.slow_callback_duration = 0.25
+            assert loop.slow_callback_duration == 0.25
+            loop.slow_callback_duration = 1
+            assert loop.slow_callback_duration == 1.0
+            with pytest.raises(TypeError):
+                loop.slow_callback_duration = 'slow'
+        finally:
+            loop.close()
+
+    def test_debug_round_trip(self):
+        """Test set_debug() reaches the loop's own state"""
+        loop = veloxloop.VeloxLoop(debug=True)
+        try:
+            assert loop.get_debug()
+            loop.set_debug(False)
+            assert not loop.get_debug()
+            assert 'debug=False' in repr(loop)
+        finally:
+            loop.close()
+
+    def test_repr(self):
+        """Test repr reports running/closed/debug, including from callbacks"""
+        loop = asyncio.new_event_loop()
+        seen = []
+        chains = [10]
+
+        def callback(n):
+            seen.append(repr(loop))
+            if n:
+                loop.call_soon(callback, n - 1)
+                return
+            chains[0] -= 1
+            if not chains[0]:
+                loop.stop()
+
+        try:
+            assert repr(loop) == '<VeloxLoop running=False closed=False debug=False>'
+            for n in range(10):
+                loop.call_soon(callback, n)
+            loop.run_forever()
+            assert len(seen) == sum(range(1, 11))
+            assert set(seen) == {'<VeloxLoop running=True closed=False debug=False>'}
+        finally:
+            loop.close()
+        assert repr(loop) == '<VeloxLoop running=False closed=True debug=False>'
+
     def test_callback_with_args(self):
         """Test callbacks with multiple arguments"""
         result = []
@@ -202,6 +354,58 @@ class TestCorePollingBasic:
 
         asyncio.run(main())
 
+    def test_call_soon_fifo_across_threads(self):
+        """Test call_soon and call_soon_threadsafe run in global enqueue order"""
+        per_thread = 2000
+        lock = threading.Lock()
+        seq = [0]
+        observed = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            done = loop.create_future()
+            loop_done = loop.create_future()
+
+            def record(source, n, global_seq):
+                observed.append((source, n, global_seq))
+
+            def enqueue(schedule, source, n):
+                # Hold the lock so Python-side sequence matches enqueue order
+                with lock:
+                    schedule(record, source, n, seq[0])
+                    seq[0] += 1
+
+            def worker(name):
+                for n in range(per_thread):
+                    enqueue(loop.call_soon_threadsafe, name, n)
+
+            def loop_side(n):
+                enqueue(loop.call_soon, 'loop', n)
+                if n + 1 < per_thread:
+                    loop.call_soon(loop_side, n + 1)
+                else:
+                    loop_done.set_result(None)
+
+            threads = [threading.Thread(target=worker, args=(name,)) for name in ('t1', 't2')]
+            loop.call_soon(loop_side, 0)
+            for t in threads:
+                t.start()
+
+            await loop.run_in_executor(None, lambda: [t.join() for t in threads])
+            await loop_done
+            # Everything enqueued so far runs before this sentinel
+            loop.call_soon_threadsafe(done.set_result, None)
+            await done
+
+        asyncio.run(main())
+
+        assert len(observed) == 3 * per_thread
+        for source in ('t1', 't2', 'loop'):
+            ns = [n for s, n, _ in observed if s == source]
+            assert ns == list(range(per_thread))
+        global_order = [g for _, _, g in observed]
+        assert global_order == sorted(global_order)
+
     def test_create_task(self):
         """Test creating and awaiting tasks"""
 
@@ -5,15 +5,22 @@ Tests for Core Event Loop Features:
 - Exception handler API
 - Task factory API
 - Async generator shutdown
+- Internal future pool
+- loop.shutdown()
 """
 
 import asyncio
+import concurrent.futures
+import gc
+import os
+import socket
 import threading
 import time
 
 import pytest
 
-from veloxloop import VeloxLoopPolicy
+import veloxloop
+from veloxloop import VeloxLoop, VeloxLoopPolicy
 
 
 class TestExecutor:
@@ -110,6 +117,122 @@ class TestExecutor:
 
         loop.run_until_complete(test())
 
+    def test_set_default_executor_is_used(self, loop):
+        """Test a user executor set as default runs the jobs (max_workers=1 serializes them)"""
+        executor = concurrent.futures.ThreadPoolExecutor(max_workers=1)
+        loop.set_default_executor(executor)
+        spans = []
+
+        def job(name):
+            start = time.monotonic()
+            time.sleep(0.05)
+            spans.append((start, time.monotonic(), threading.current_thread().name))
+            if name == 'bad':
+                raise ValueError('from executor')
+            return name
+
+        async def test():
+            results = await asyncio.gather(
+                loop.run_in_executor(None, job, 'a'), loop.run_in_executor(None, job, 'b')
+            )
+            assert results == ['a', 'b']
+            with pytest.raises(ValueError, match='from executor'):
+                await loop.run_in_executor(None, job, 'bad')
+            await loop.shutdown_default_executor()
+
+        loop.run_until_complete(test())
+        (s1, e1, t1), (s2, e2, t2) = sorted(spans[:2])
+        assert s2 >= e1
+        assert t1 == t2 and t1.startswith('ThreadPoolExecutor')
+        # Left running for its owner
+        assert executor.submit(lambda: 7).result(timeout=5) == 7
+        executor.shutdown()
+
+    def test_set_default_executor_type_check(self, loop):
+        with pytest.raises(TypeError):
+            loop.set_default_executor(object())
+
+    def test_executor_cancellation(self, loop):
+        """Test cancelling either side of a submitted job"""
+        executor = concurrent.futures.ThreadPoolExecutor(max_workers=1)
+        started, gate = threading.Event(), threading.Event()
+        ran = []
+
+        def block():
+            started.set()
+            return gate.wait(5)
+
+        async def test():
+            blocker = loop.run_in_executor(executor, block)
+            queued = loop.run_in_executor(executor, ran.append, 'queued')
+            # The loop future cancels the queued job...
+            assert queued.cancel()
+            gate.set()
+            assert await blocker is True
+            assert ran == []
+
+            # ...and a job cancelled by the executor cancels the loop future
+            started.clear()
+            gate.clear()
+            blocker = loop.run_in_executor(executor, block)
+            queued = loop.run_in_executor(executor, ran.append, 'queued')
+            await loop.run_in_executor(None, started.wait, 5)
+            executor.shutdown(wait=False, cancel_futures=True)
+            gate.set()
+            await blocker
+            for _ in range(100):
+                if queued.done():
+                    break
+                await asyncio.sleep(0.01)
+            assert queued.cancelled()
+
+        loop.run_until_complete(test())
+        executor.shutdown()
+
+    def test_shutdown_default_executor_waits_for_jobs(self, loop):
+        """Test shutting down the internal pool lets queued jobs finish"""
+        finished = []
+
+        def job(i):
+            time.sleep(0.02)
+            finished.append(i)
+            return i
+
+        async def test():
+            futures = [loop.run_in_executor(None, job, i) for i in range(8)]
+            await loop.shutdown_default_executor()
+            assert sorted(finished) == list(range(8))
+            assert [f.result() for f in futures] == list(range(8))
+            # A new pool is created on demand
+            assert await loop.run_in_executor(None, job, 8) == 8
+
+        loop.run_until_complete(test())
+
+    def test_loop_dropped_with_job_in_flight(self):
+        """Test dropping a loop mid-job neither waits for the job nor crashes when it ends"""
+        started = threading.Event()
+        finished = threading.Event()
+
+        def job():
+            started.set()
+            time.sleep(2)
+            finished.set()
+            return 'late'
+
+        loop = VeloxLoop()
+        future = loop.run_in_executor(None, job)
+        assert started.wait(5)
+
+        start = time.monotonic()
+        del loop
+        gc.collect()
+        assert time.monotonic() - start < 1
+
+        # The result has nowhere to go and is dropped
+        assert finished.wait(5)
+        time.sleep(0.1)
+        assert not future.done()
+
 
 class TestExceptionHandler:
     """Test exception handler API"""
@@ -283,6 +406,436 @@ class TestAsyncGeneratorShutdown:
         loop.run_until_complete(test())
 
 
+    def test_asyncgen_hooks_track_automatically(self, loop):
+        """Test generators started on the loop are closed without manual tracking"""
+        import sys
+
+        closed = []
+
+        async def async_gen():
+            try:
+                yield 1
+                yield 2
+            finally:
+                closed.append(True)
+
+        async def test():
+            gen = async_gen()
+            await gen.__anext__()
+            return gen
+
+        hooks_before = sys.get_asyncgen_hooks()
+        gen = loop.run_until_complete(test())
+        # Hooks only apply while the loop runs
+        assert sys.get_asyncgen_hooks() == hooks_before
+        loop.run_until_complete(loop.shutdown_asyncgens())
+        assert closed == [True]
+        assert gen.ag_frame is None
+
+    def test_hook_restore_error_does_not_mask_run_error(self, loop):
+        """Test a failed hook restore surfaces only when the run itself succeeded"""
+        import sys
+
+        set_hooks = sys.set_asyncgen_hooks
+        hooks_before = sys.get_asyncgen_hooks()
+
+        def broken_set_hooks(*args, **kwargs):
+            raise RuntimeError('restore failed')
+
+        def fail_run():
+            sys.set_asyncgen_hooks = broken_set_hooks
+            raise ValueError('run failed')
+
+        def raise_context(context):
+            raise context['exception']
+
+        def stop_run():
+            sys.set_asyncgen_hooks = broken_set_hooks
+            loop.stop()
+
+        try:
+            # The sink's error ends the run
+            loop._set_test_exception_sink(raise_context)
+            loop.call_soon(fail_run)
+            with pytest.raises(ValueError, match='run failed'):
+                loop.run_forever()
+            loop._set_test_exception_sink(None)
+
+            loop.call_soon(stop_run)
+            with pytest.raises(RuntimeError, match='restore failed'):
+                loop.run_forever()
+        finally:
+            sys.set_asyncgen_hooks = set_hooks
+            set_hooks(*hooks_before)
+
+    def test_shutdown_asyncgens_reports_aclose_errors(self, loop):
+        """Test one failing aclose() is reported and doesn't stop the others"""
+        contexts = []
+        closed = []
+        loop.set_exception_handler(lambda lp, ctx: contexts.append(ctx))
+
+        async def bad_gen():
+            try:
+                yield 1
+            finally:
+                raise ValueError('boom')
+
+        async def good_gen():
+            try:
+                yield 1
+            finally:
+                await asyncio.sleep(0)
+                closed.append(True)
+
+        async def test():
+            gens = [bad_gen(), good_gen()]
+            for gen in gens:
+                await gen.__anext__()
+            await loop.shutdown_asyncgens()
+            return gens
+
+        gens = loop.run_until_complete(test())
+        assert closed == [True]
+        assert len(contexts) == 1
+        assert contexts[0]['message'].startswith(
+            'an error occurred during closing of asynchronous generator'
+        )
+        assert isinstance(contexts[0]['exception'], ValueError)
+        assert contexts[0]['asyncgen'] is gens[0]
+
+    def test_shutdown_asyncgens_without_running_loop(self, loop):
+        """Test the returned future completes by stepping the loop directly"""
+        from veloxloop._veloxloop import VeloxLoop as RawLoop
+
+        closed = []
+
+        async def async_gen():
+            try:
+                yield 1
+            finally:
+                closed.append(True)
+
+        gen = async_gen()
+        loop._track_async_generator(gen)
+        # Start the generator without any asyncio loop involved
+        with pytest.raises(StopIteration):
+            gen.__anext__().send(None)
+
+        fut = RawLoop.shutdown_asyncgens(loop)
+        for _ in range(10):
+            if fut.done():
+                break
+            loop._run_once()
+        assert fut.done()
+        assert fut.result() is None
+        assert closed == [True]
+
+    def test_finalizer_hook_closes_dropped_generator(self, loop):
+        """Test a generator dropped mid-iteration is closed by the loop"""
+        import gc
+
+        closed = []
+
+        async def async_gen():
+            try:
+                yield 1
+                yield 2
+            finally:
+                closed.append(True)
+
+        async def test():
+            gen = async_gen()
+            await gen.__anext__()
+            del gen
+            gc.collect()
+            for _ in range(5):
+                await asyncio.sleep(0)
+
+        loop.run_until_complete(test())
+        assert closed == [True]
+
+class TestFuturePool:
+    """Test the opt-in pool of loop-created futures"""
+
+    @pytest.fixture
+    def loop(self):
+        """Create a VeloxLoop with a small future pool"""
+        loop = VeloxLoop(future_pool_size=8)
+        asyncio.set_event_loop(loop)
+        yield loop
+        loop.close()
+
+    def test_pool_disabled_by_default(self):
+        """Test default loops never recycle futures"""
+        loop = VeloxLoop()
+        try:
+
+            async def test():
+                for i in range(10):
+                    assert await loop.run_in_executor(None, abs, -i) == i
+
+            loop.run_until_complete(test())
+            stats = loop.future_pool_stats()
+            assert stats['capacity'] == 0
+            assert stats['reused'] == 0
+        finally:
+            loop.close()
+
+    def test_retained_futures_keep_results(self, loop):
+        """Test futures held by user code are never recycled"""
+        retained = []
+
+        async def test():
+            for i in range(60):
+                fut = loop.run_in_executor(None, lambda x: x * 10, i)
+                assert await fut == i * 10
+                if i % 4 == 0:
+                    retained.append((i, fut))
+                del fut
+                await asyncio.sleep(0)
+
+        loop.run_until_complete(test())
+
+        stats = loop.future_pool_stats()
+        assert stats['reused'] > 0
+        assert stats['in_use'] + stats['free'] <= stats['capacity']
+        for i, fut in retained:
+            assert fut.done()
+            assert fut.result() == i * 10
+
+    def test_retained_exception_futures(self, loop):
+        """Test failed futures keep their exception while others are recycled"""
+        failed = []
+
+        def boom(i):
+            raise ValueError(i)
+
+        async def test():
+            for i in range(20):
+                fut = loop.run_in_executor(None, boom, i)
+                with pytest.raises(ValueError):
+                    await fut
+                if i % 5 == 0:
+                    failed.append((i, fut))
+                del fut
+                assert await loop.run_in_executor(None, abs, -i) == i
+
+        loop.run_until_complete(test())
+        for i, fut in failed:
+            with pytest.raises(ValueError) as exc_info:
+                fut.result()
+            assert exc_info.value.args == (i,)
+
+
+class TestBufferRetention:
+    """Test the loop's reused buffers shrink back after a spike"""
+
+    def test_spike_capacity_returns_to_baseline(self):
+        """Test one huge batch of callbacks and I/O events isn't kept for good"""
+        loop = VeloxLoop()
+        pairs = [socket.socketpair() for _ in range(200)]
+        try:
+            base = loop.get_stats()
+            assert base['callback_buffer_capacity'] == 1024
+            assert base['pending_ios_capacity'] == 128
+
+            async def test():
+                ran = []
+                ready = []
+                for i, (a, _) in enumerate(pairs):
+                    loop.add_reader(a, lambda a=a: ready.append(a.recv(1)))
+                    if i % 50 == 49:
+                        # Let the registrations go out before the ring fills
+                        await asyncio.sleep(0)
+                for _, b in pairs:
+                    b.send(b'x')
+                for _ in range(200_000):
+                    loop.call_soon(ran.append, None)
+                while len(ready) < len(pairs):
+                    await asyncio.sleep(0)
+                for a, _ in pairs:
+                    loop.remove_reader(a)
+                # Stats cover the buffers as of the last finished iteration
+                await asyncio.sleep(0)
+
+                spike = loop.get_stats()
+                assert spike['callback_buffer_capacity'] >= 200_000
+                assert spike['pending_ios_capacity'] >= len(pairs)
+                assert spike['retained_bytes'] > base['retained_bytes']
+                # The spike's window, then a quiet one
+                for _ in range(600):
+                    await asyncio.sleep(0)
+                return spike
+
+            spike = loop.run_until_complete(test())
+            stats = loop.get_stats()
+            assert stats['callback_buffer_capacity'] == 1024
+            assert stats['pending_ios_capacity'] == 128
+            assert stats['retained_bytes'] < spike['retained_bytes']
+        finally:
+            for a, b in pairs:
+                a.close()
+                b.close()
+            loop.close()
+
+    def test_handle_slots_reused(self):
+        """Test reader/writer churn takes freed handle slots instead of new ones"""
+        loop = VeloxLoop()
+        a, b = socket.socketpair()
+        try:
+            base = loop.get_stats()
+
+            async def churn():
+                for _ in range(1000):
+                    loop.add_reader(a, lambda: None)
+                    loop.add_writer(a, lambda: None)
+                    # Replaced in place
+                    loop.add_reader(a, lambda: None)
+                    loop.remove_reader(a)
+                    loop.remove_writer(a)
+                    # Let the poll operations go out before the ring fills
+                    await asyncio.sleep(0)
+
+            loop.run_until_complete(churn())
+            stats = loop.get_stats()
+            assert stats['handles_created'] - base['handles_created'] <= 2
+            assert stats['handles_reused'] - base['handles_reused'] >= 2998
+        finally:
+            a.close()
+            b.close()
+            loop.close()
+
+
+def _native_threads(prefix):
+    """Names of this process's threads starting with prefix, Rust ones included"""
+    names = []
+    for tid in os.listdir('/proc/self/task'):
+        try:
+            with open(f'/proc/self/task/{tid}/comm') as f:
+                name = f.read().strip()
+        except FileNotFoundError:
+            continue
+        if name.startswith(prefix):
+            names.append(name)
+    return names
+
+
+class TestLoopShutdown:
+    """Test loop.shutdown(), asyncio.run()'s teardown for a loop driven by hand"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_shutdown_tears_everything_down(self):
+        """Test tasks are cancelled, the stubborn one reported and no workers left"""
+        workers_before = len(_native_threads('veloxloop-'))
+        loop = VeloxLoop()
+        contexts = []
+        loop.set_exception_handler(lambda _, context: contexts.append(context))
+        cancelled = []
+        closed_gens = []
+
+        async def handler(reader, writer):
+            await reader.read()
+
+        async def idle(i):
+            try:
+                await asyncio.sleep(3600)
+            except asyncio.CancelledError:
+                cancelled.append(i)
+                raise
+
+        async def stubborn():
+            try:
+                await asyncio.sleep(3600)
+            except asyncio.CancelledError:
+                cancelled.append('stubborn')
+            await asyncio.sleep(3600)
+
+        async def ticker():
+            try:
+                while True:
+                    yield
+                    await asyncio.sleep(0)
+            finally:
+                closed_gens.append(True)
+
+        async def start():
+            server = await asyncio.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            await asyncio.open_connection('127.0.0.1', port)
+            assert await loop.run_in_executor(None, sum, [1, 2]) == 3
+            gen = ticker()
+            await gen.__anext__()
+            tasks = [loop.create_task(idle(i)) for i in range(3)]
+            tasks.append(loop.create_task(stubborn()))
+            await asyncio.sleep(0.01)
+            return server, gen, tasks
+
+        server, gen, tasks = loop.run_until_complete(start())
+        assert len(_native_threads('veloxloop-')) > workers_before
+        start_time = time.monotonic()
+        loop.shutdown(timeout=0.2)
+
+        assert 0.2 <= time.monotonic() - start_time < 2
+        assert loop.is_closed()
+        assert not loop.is_running()
+        assert sorted(cancelled, key=str) == [0, 1, 2, 'stubborn']
+        assert closed_gens == [True]
+        assert len(_native_threads('veloxloop-')) == workers_before
+        assert [c['task'] for c in contexts] == [tasks[-1]]
+        assert 'abandoned' in contexts[0]['message']
+        server.close()
+
+    def test_shutdown_without_tasks(self):
+        """Test an idle loop shuts down straight away and reports nothing"""
+        loop = VeloxLoop()
+        contexts = []
+        loop.set_exception_handler(lambda _, context: contexts.append(context))
+        loop.shutdown()
+        assert loop.is_closed()
+        assert contexts == []
+        with pytest.raises(RuntimeError, match='closed'):
+            loop.shutdown()
+
+    def test_shutdown_reports_task_errors(self):
+        """Test a task failing while it handles the cancel is reported like asyncio.run()"""
+        loop = VeloxLoop()
+        contexts = []
+        loop.set_exception_handler(lambda _, context: contexts.append(context))
+
+        async def failing():
+            try:
+                await asyncio.sleep(3600)
+            except asyncio.CancelledError:
+                raise ValueError('during cleanup')
+
+        async def start():
+            task = loop.create_task(failing())
+            await asyncio.sleep(0)
+            return task
+
+        task = loop.run_until_complete(start())
+        loop.shutdown()
+        assert len(contexts) == 1
+        assert contexts[0]['task'] is task
+        assert isinstance(contexts[0]['exception'], ValueError)
+
+    def test_shutdown_from_running_loop(self):
+        """Test shutdown() refuses to run inside the loop it would tear down"""
+        loop = VeloxLoop()
+
+        async def main():
+            with pytest.raises(RuntimeError, match='running'):
+                loop.shutdown()
+
+        loop.run_until_complete(main())
+        with pytest.raises(ValueError):
+            loop.shutdown(timeout=-1)
+        loop.shutdown()
+        assert loop.is_closed()
+
+
 class TestIntegration:
     """Integration tests for core features"""
 
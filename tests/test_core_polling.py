@@ -272,5 +272,252 @@ class TestCorePolling:
         asyncio.run(main())
 
 
+    def test_add_reader_invalid_fds(self):
+        """Test add/remove reader and writer reject bad fds like asyncio"""
+        import socket
+
+        loop = asyncio.new_event_loop()
+        try:
+            for add in (loop.add_reader, loop.add_writer):
+                with pytest.raises(ValueError):
+                    add(-1, lambda: None)
+                with pytest.raises(ValueError):
+                    add(object(), lambda: None)
+                with pytest.raises(OSError):
+                    add(100000, lambda: None)
+                with pytest.raises(OverflowError):
+                    add(2**40, lambda: None)
+            for remove in (loop.remove_reader, loop.remove_writer):
+                with pytest.raises(ValueError):
+                    remove(-1)
+                assert remove(100000) is False
+                assert remove(2**40) is False
+
+            # Objects with fileno() work like their fd
+            a, b = socket.socketpair()
+            loop.add_reader(a, lambda: None)
+            assert loop.remove_reader(a.fileno()) is True
+            a.close()
+            b.close()
+        finally:
+            loop.close()
+
+    def test_fd_priority_dispatch_order(self):
+        """Test a high-priority fd is dispatched first among fds ready in one tick"""
+        import socket
+
+        order = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            pairs = [socket.socketpair() for _ in range(8)]
+            urgent = pairs[-1][0]
+
+            def make_cb(r):
+                def cb():
+                    r.recv(16)
+                    order.append(r)
+                    loop.remove_reader(r.fileno())
+
+                return cb
+
+            for r, _ in pairs:
+                r.setblocking(False)
+                loop.add_reader(r.fileno(), make_cb(r))
+            assert loop.set_fd_priority(urgent.fileno(), True) is True
+            for _, w in pairs:
+                w.send(b'x')
+
+            await asyncio.sleep(0.05)
+            assert loop.set_fd_priority(urgent.fileno(), False) is False
+            assert loop.set_fd_priority(2**40, True) is False
+            for r, w in pairs:
+                r.close()
+                w.close()
+            return urgent
+
+        urgent = asyncio.run(main())
+        assert len(order) == 8
+        assert order[0] is urgent
+
+
+    def test_wakeup_fd(self):
+        """Test the self-pipe is installed as the signal wakeup fd and drained"""
+        import os
+        import signal
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            fd = loop.get_wakeup_fd()
+            assert fd == loop.get_wakeup_fd()
+
+            current = signal.set_wakeup_fd(-1)
+            signal.set_wakeup_fd(current)
+            assert current == fd
+
+            # Close to the pipe capacity, so the second write only fits if the
+            # loop read the first one out
+            for _ in range(2):
+                writer = threading.Thread(target=os.write, args=(fd, b'x' * 60000))
+                writer.start()
+                writer.join()
+                await asyncio.sleep(0.02)
+
+        asyncio.run(main())
+        assert signal.set_wakeup_fd(-1) == -1
+
+    def test_wakeup_restore_error_does_not_mask_run_error(self):
+        """Test a failed wakeup fd restore surfaces only when the run itself succeeded"""
+        import signal
+        import sys
+
+        set_wakeup_fd = signal.set_wakeup_fd
+        hooks_before = sys.get_asyncgen_hooks()
+        loop = asyncio.new_event_loop()
+
+        def broken_set_wakeup_fd(*args, **kwargs):
+            raise RuntimeError('restore failed')
+
+        def fail_run():
+            signal.set_wakeup_fd = broken_set_wakeup_fd
+            raise ValueError('run failed')
+
+        def stop_run():
+            signal.set_wakeup_fd = broken_set_wakeup_fd
+            loop.stop()
+
+        def raise_context(context):
+            raise context['exception']
+
+        try:
+            # The sink's error ends the run
+            loop._set_test_exception_sink(raise_context)
+            loop.call_soon(fail_run)
+            with pytest.raises(ValueError, match='run failed'):
+                loop.run_forever()
+            # The rest of the teardown still ran
+            assert sys.get_asyncgen_hooks() == hooks_before
+            loop._set_test_exception_sink(None)
+
+            signal.set_wakeup_fd = set_wakeup_fd
+            loop.call_soon(stop_run)
+            with pytest.raises(RuntimeError, match='restore failed'):
+                loop.run_forever()
+            assert sys.get_asyncgen_hooks() == hooks_before
+        finally:
+            signal.set_wakeup_fd = set_wakeup_fd
+            set_wakeup_fd(-1)
+            loop.close()
+
+    def test_sigint_on_other_thread_interrupts_poll(self):
+        """Test Ctrl+C delivered to a non-loop thread stops a loop blocked in poll"""
+        import signal
+
+        loop = asyncio.new_event_loop()
+        previous = signal.signal(signal.SIGINT, signal.default_int_handler)
+        try:
+            # Only timer is far away, so the loop sits in one long poll
+            loop.call_later(5, loop.stop)
+
+            def interrupt():
+                time.sleep(0.1)
+                signal.pthread_kill(threading.get_ident(), signal.SIGINT)
+
+            threading.Thread(target=interrupt).start()
+            start = time.monotonic()
+            with pytest.raises(KeyboardInterrupt):
+                loop.run_forever()
+            assert time.monotonic() - start < 1
+            assert not loop.is_running()
+        finally:
+            signal.signal(signal.SIGINT, previous)
+            loop.close()
+
+    def test_callback_ping_pong_does_not_starve_io(self):
+        """Test a ready reader runs within a few iterations of a call_soon ping-pong"""
+        import socket
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            with a, b:
+                a.setblocking(False)
+                rounds = 0
+                dispatched = loop.create_future()
+
+                def on_readable():
+                    a.recv(16)
+                    loop.remove_reader(a)
+                    dispatched.set_result(rounds)
+
+                # Each side schedules the other, so the loop never runs dry
+                def ping():
+                    nonlocal rounds
+                    rounds += 1
+                    if not dispatched.done() and rounds < 10000:
+                        loop.call_soon(pong)
+
+                def pong():
+                    loop.call_soon(ping)
+
+                loop.add_reader(a, on_readable)
+                await asyncio.sleep(0.01)
+                b.send(b'x')
+                loop.call_soon(ping)
+                return await dispatched
+
+        # One round is two iterations; the first poll after the send sees it
+        assert asyncio.run(main()) <= 4
+
+    def test_callback_ping_pong_does_not_starve_timers(self):
+        """Test an expired timer fires while callbacks keep the loop busy"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            fired = loop.create_future()
+            busy_until = time.monotonic() + 2
+
+            def spin():
+                if not fired.done() and time.monotonic() < busy_until:
+                    loop.call_soon(spin)
+
+            loop.call_later(0.01, fired.set_result, None)
+            start = time.monotonic()
+            loop.call_soon(spin)
+            await fired
+            return time.monotonic() - start
+
+        assert asyncio.run(main()) < 0.5
+
+    def test_idle_loop_cpu(self):
+        """Test a loop idling with 1k quiet registered fds uses next to no CPU"""
+        import resource
+        import socket
+
+        loop = asyncio.new_event_loop()
+        pairs = [socket.socketpair() for _ in range(500)]
+        try:
+            for i, pair in enumerate(pairs):
+                for sock in pair:
+                    loop.add_reader(sock, lambda: None)
+                if i % 50 == 49:
+                    # Let the registrations go out before the ring fills
+                    loop.run_until_complete(asyncio.sleep(0))
+
+            # No timers, so the loop only wakes for its default poll timeout
+            stopper = threading.Timer(2, loop.call_soon_threadsafe, (loop.stop,))
+            before = resource.getrusage(resource.RUSAGE_THREAD).ru_utime
+            stopper.start()
+            loop.run_forever()
+            used = resource.getrusage(resource.RUSAGE_THREAD).ru_utime - before
+            assert used < 0.1
+        finally:
+            for pair in pairs:
+                for sock in pair:
+                    loop.remove_reader(sock)
+                    sock.close()
+            loop.close()
+
+
 if __name__ == '__main__':
     pytest.main([__file__, '-v'])
@@ -0,0 +1,303 @@
+"""Tests for create_connection and open_connection address selection and error reporting"""
+
+import asyncio
+import socket
+
+import pytest
+
+import veloxloop
+from tests.helpers import blackhole, closed_port
+
+
+class StaticResolver:
+    """Answers every lookup with the same addresses, in order"""
+
+    def __init__(self, *addresses):
+        self.addresses = addresses
+
+    async def resolve(self, host, port, family):
+        return [
+            (socket.AF_INET6 if ':' in addr else socket.AF_INET, 0, 0, '', (addr, port))
+            for addr in self.addresses
+        ]
+
+
+class TestCreateConnectionAddresses:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_family_restricts_candidates(self):
+        """Test family= limits which resolved addresses are tried"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.addresses()[0][1]
+
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, 'localhost', port, family=socket.AF_INET
+            )
+            assert transport.get_extra_info('peername')[:2] == ('127.0.0.1', port)
+            transport.close()
+
+            with pytest.raises(OSError):
+                await loop.create_connection(
+                    asyncio.Protocol, '127.0.0.1', port, family=socket.AF_INET6
+                )
+            server.close()
+
+        asyncio.run(main())
+
+    def test_falls_through_to_next_address(self):
+        """Test a failed address moves on to the next candidate"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.addresses()[0][1]
+            # Nothing listens on the IPv6 loopback at this port
+            loop.set_resolver(StaticResolver('::1', '127.0.0.1'))
+
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, 'dual.test', port
+            )
+            assert transport.get_extra_info('peername')[:2] == ('127.0.0.1', port)
+            transport.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_local_addr_host_name(self):
+        """Test a local_addr host name is resolved before binding"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.addresses()[0][1]
+
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port, local_addr=('localhost', 0)
+            )
+            assert transport.get_extra_info('sockname')[0] == '127.0.0.1'
+            transport.close()
+
+            # The native connect takes address literals only
+            with pytest.raises(ValueError):
+                await veloxloop._veloxloop.VeloxLoop.create_connection(
+                    loop, asyncio.Protocol, '127.0.0.1', port, local_addr=('localhost', 0)
+                )
+            server.close()
+
+        asyncio.run(main())
+
+    def test_last_error_names_address(self):
+        """Test without all_errors the last OSError is raised with its address"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            port = closed_port()
+            loop.set_resolver(StaticResolver('::1', '127.0.0.1'))
+
+            with pytest.raises(OSError) as info:
+                await loop.create_connection(asyncio.Protocol, 'dual.test', port)
+            assert not isinstance(info.value, socket.gaierror)
+            assert f"('127.0.0.1', {port})" in str(info.value)
+
+            with pytest.raises(ConnectionRefusedError) as info:
+                await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            assert f"('127.0.0.1', {port})" in str(info.value)
+
+        asyncio.run(main())
+
+    def test_all_errors_groups_each_address(self):
+        """Test all_errors=True raises one OSError per attempted address"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            port = closed_port()
+            loop.set_resolver(StaticResolver('::1', '127.0.0.1'))
+
+            with pytest.raises(ExceptionGroup) as info:
+                await loop.create_connection(
+                    asyncio.Protocol, 'dual.test', port, all_errors=True
+                )
+            errors = info.value.exceptions
+            assert len(errors) == 2
+            assert all(isinstance(exc, OSError) for exc in errors)
+            assert isinstance(errors[1], ConnectionRefusedError)
+            assert f"('127.0.0.1', {port})" in str(errors[1])
+
+            # A single literal address still comes back grouped
+            with pytest.raises(ExceptionGroup) as info:
+                await loop.create_connection(
+                    asyncio.Protocol, '127.0.0.1', port, all_errors=True
+                )
+            assert len(info.value.exceptions) == 1
+
+        asyncio.run(main())
+
+    def test_connect_timeout(self):
+        """Test timeout= fails an unanswered connect with TimeoutError"""
+        listener, fillers, port = blackhole()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            start = loop.time()
+            with pytest.raises(TimeoutError) as info:
+                await loop.create_connection(
+                    asyncio.Protocol, '127.0.0.1', port, timeout=0.2
+                )
+            assert 0.2 <= loop.time() - start < 0.3
+            assert f"('127.0.0.1', {port})" in str(info.value)
+
+            with pytest.raises(ValueError):
+                await loop.create_connection(
+                    asyncio.Protocol, '127.0.0.1', port, timeout=-1
+                )
+
+        try:
+            asyncio.run(main())
+        finally:
+            for sock in fillers:
+                sock.close()
+            listener.close()
+
+    def test_timeout_cancelled_on_connect(self):
+        """Test a connect that succeeds in time leaves nothing behind to fire"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.addresses()[0][1]
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port, timeout=0.05
+            )
+            await asyncio.sleep(0.1)
+            assert not transport.is_closing()
+            transport.close()
+            server.close()
+
+        asyncio.run(main())
+
+
+class TestOpenConnection:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_falls_through_to_next_address(self):
+        """Test a host name is looked up and each address tried in turn"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.start_server(lambda r, w: w.close(), '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            loop.set_resolver(StaticResolver('::1', '127.0.0.1'))
+
+            reader, writer = await loop.open_connection('dual.test', port)
+            assert writer.transport.get_extra_info('peername')[:2] == ('127.0.0.1', port)
+            writer.close()
+
+            with pytest.raises(ConnectionRefusedError) as info:
+                await loop.open_connection('dual.test', closed_port())
+            assert "('127.0.0.1', " in str(info.value)
+            server.close()
+
+        asyncio.run(main())
+
+    def test_connect_does_not_block_loop(self):
+        """Test an unanswered connect leaves the loop running until timeout="""
+        listener, fillers, port = blackhole()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            ticks = []
+            loop.call_later(0.05, ticks.append, 1)
+            start = loop.time()
+            with pytest.raises(TimeoutError):
+                await loop.open_connection('127.0.0.1', port, timeout=0.2)
+            assert 0.2 <= loop.time() - start < 0.3
+            assert ticks == [1]
+
+        try:
+            asyncio.run(main())
+        finally:
+            for sock in fillers:
+                sock.close()
+            listener.close()
+
+
+class TestHappyEyeballs:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_races_past_stalled_address(self):
+        """Test happy_eyeballs_delay starts the next address while one hangs"""
+        listener, fillers, port = blackhole('127.0.0.2')
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', port)
+            loop.set_resolver(StaticResolver('127.0.0.2', '127.0.0.1'))
+            start = loop.time()
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, 'dual.test', port, happy_eyeballs_delay=0.05
+            )
+            assert loop.time() - start < 1
+            assert transport.get_extra_info('peername')[:2] == ('127.0.0.1', port)
+            transport.close()
+            server.close()
+
+        try:
+            asyncio.run(main())
+        finally:
+            for sock in fillers:
+                sock.close()
+            listener.close()
+
+    def test_interleave_alternates_families(self):
+        """Test interleave= tries one address of each family in turn"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            port = closed_port()
+            loop.set_resolver(StaticResolver('127.0.0.2', '127.0.0.3', '::1'))
+
+            with pytest.raises(ExceptionGroup) as info:
+                await loop.create_connection(
+                    asyncio.Protocol, 'dual.test', port, interleave=1, all_errors=True
+                )
+            hosts = [str(exc).split("'")[1] for exc in info.value.exceptions]
+            assert hosts == ['127.0.0.2', '::1', '127.0.0.3']
+
+            with pytest.raises(ExceptionGroup) as info:
+                await loop.create_connection(
+                    asyncio.Protocol,
+                    'dual.test',
+                    port,
+                    happy_eyeballs_delay=0.01,
+                    all_errors=True,
+                )
+            assert len(info.value.exceptions) == 3
+
+        asyncio.run(main())
+
+    def test_rejects_socket_shaping_options(self):
+        """Test options set by the native connect are refused with a race"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_resolver(StaticResolver('127.0.0.1'))
+            with pytest.raises(ValueError, match='happy_eyeballs_delay'):
+                await loop.create_connection(
+                    asyncio.Protocol,
+                    'dual.test',
+                    1,
+                    happy_eyeballs_delay=0.1,
+                    fastopen=True,
+                )
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,180 @@
+"""Tests for debug mode: picked up from the environment, coroutine origin
+tracking, and slow callback reports"""
+
+import asyncio
+import os
+import subprocess
+import sys
+import textwrap
+import time
+
+import pytest
+
+import veloxloop
+
+# Run in a fresh interpreter: reports the loop's debug flag, the coroutine
+# origin tracking depth while it runs and after, and the exception handler
+# contexts a slow callback produced
+CHILD = textwrap.dedent(
+    """
+    import asyncio, sys, time
+    import veloxloop
+
+    veloxloop.install()
+    loop = asyncio.new_event_loop()
+    contexts = []
+    loop.set_exception_handler(lambda loop, context: contexts.append(context))
+    loop.call_soon(time.sleep, 0.2)
+
+    async def tracking_depth():
+        await asyncio.sleep(0)
+        return sys.get_coroutine_origin_tracking_depth()
+
+    print(loop.get_debug())
+    print(loop.run_until_complete(tracking_depth()))
+    print(sys.get_coroutine_origin_tracking_depth())
+    for context in contexts:
+        print(context['message'])
+    loop.close()
+    """
+)
+
+
+def _run_child(*flags, **env):
+    """Run CHILD with interpreter `flags` and extra environment; its output lines"""
+    package_root = os.path.dirname(os.path.dirname(veloxloop.__file__))
+    child_env = {k: v for k, v in os.environ.items() if k != 'PYTHONASYNCIODEBUG'}
+    child_env['PYTHONPATH'] = package_root
+    child_env.update(env)
+    result = subprocess.run(
+        [sys.executable, *flags, '-c', CHILD],
+        env=child_env,
+        capture_output=True,
+        text=True,
+        timeout=30,
+    )
+    assert result.returncode == 0, result.stderr
+    return result.stdout.splitlines()
+
+
+class TestDebugMode:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_env_var_enables_debug(self):
+        """Test PYTHONASYNCIODEBUG turns debug mode and its features on"""
+        debug, depth, after, *messages = _run_child(PYTHONASYNCIODEBUG='1')
+        assert debug == 'True'
+        assert depth == '10'
+        assert after == '0'
+        assert len(messages) == 1
+        assert messages[0].startswith('Executing <built-in function sleep> took 0.2')
+        assert messages[0].endswith(' seconds')
+
+    def test_dev_mode_enables_debug(self):
+        """Test -X dev turns debug mode on like asyncio"""
+        debug, _, _, *messages = _run_child('-X', 'dev')
+        assert debug == 'True'
+        assert len(messages) == 1
+
+    def test_off_by_default(self):
+        """Test debug stays off without the env var, or when -E ignores it"""
+        for flags, env in [((), {}), (('-E',), {'PYTHONASYNCIODEBUG': '1'})]:
+            debug, depth, after, *messages = _run_child(*flags, **env)
+            assert debug == 'False'
+            assert depth == after == '0'
+            assert messages == []
+
+    def test_explicit_argument_wins(self):
+        """Test VeloxLoop(debug=False) ignores the environment"""
+        previous = os.environ.get('PYTHONASYNCIODEBUG')
+        os.environ['PYTHONASYNCIODEBUG'] = '1'
+        try:
+            from_env = veloxloop.VeloxLoop()
+            assert from_env.get_debug()
+            from_env.set_debug(False)
+            from_env.close()
+            loop = veloxloop.VeloxLoop(debug=False)
+            assert not loop.get_debug()
+        finally:
+            if previous is None:
+                del os.environ['PYTHONASYNCIODEBUG']
+            else:
+                os.environ['PYTHONASYNCIODEBUG'] = previous
+        loop.close()
+
+    def test_set_debug_tracks_coroutine_origin(self):
+        """Test a debug loop raises the tracking depth while it runs, and
+        set_debug() and run_forever() returning put the previous one back"""
+        previous = sys.get_coroutine_origin_tracking_depth()
+        sys.set_coroutine_origin_tracking_depth(3)
+        loop = veloxloop.VeloxLoop(debug=False)
+        depths = []
+
+        async def never_awaited():
+            pass
+
+        async def main():
+            depths.append(sys.get_coroutine_origin_tracking_depth())
+            loop.set_debug(True)
+            depths.append(sys.get_coroutine_origin_tracking_depth())
+            coro = never_awaited()
+            assert coro.cr_origin is not None
+            assert coro.cr_origin[0][2] == 'main'
+            coro.close()
+            # Turning it on twice doesn't lose the depth to restore
+            loop.set_debug(True)
+            loop.set_debug(False)
+            depths.append(sys.get_coroutine_origin_tracking_depth())
+            loop.set_debug(True)
+
+        try:
+            # Only a running loop tracks origins
+            loop.set_debug(True)
+            assert sys.get_coroutine_origin_tracking_depth() == 3
+            loop.set_debug(False)
+            loop.run_until_complete(main())
+            assert depths == [3, 10, 3]
+            assert sys.get_coroutine_origin_tracking_depth() == 3
+            # A debug loop raises it from the start of the next run
+            loop.run_until_complete(main())
+            assert depths[3] == 10
+            assert sys.get_coroutine_origin_tracking_depth() == 3
+        finally:
+            loop.close()
+            sys.set_coroutine_origin_tracking_depth(previous)
+
+    @pytest.mark.parametrize('debug', [True, False])
+    def test_slow_callbacks_reported_in_debug_only(self, debug):
+        """Test call_soon and call_later callbacks over slow_callback_duration
+        reach the exception handler in debug mode, with the callback"""
+        loop = veloxloop.VeloxLoop(debug=debug)
+        contexts = []
+        loop.set_exception_handler(lambda loop, context: contexts.append(context))
+        loop.slow_callback_duration = 0.05
+
+        def slow():
+            time.sleep(0.08)
+
+        def fast():
+            pass
+
+        try:
+            loop.call_soon(slow)
+            loop.call_soon(fast)
+            loop.call_later(0.01, slow)
+            loop.run_until_complete(asyncio.sleep(0.05))
+        finally:
+            loop.set_debug(False)
+            loop.close()
+        if not debug:
+            assert contexts == []
+            return
+        assert len(contexts) == 2
+        for context in contexts:
+            assert context['callback'] is slow
+            assert context['message'].startswith(f'Executing {slow!r} took 0.0')
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
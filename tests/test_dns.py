@@ -6,10 +6,37 @@ Tests for DNS Resolution Features:
 
 import asyncio
 import socket
+import threading
+import time
 
 import pytest
 
-from veloxloop import VeloxLoopPolicy
+import veloxloop
+from veloxloop import VeloxLoop, VeloxLoopPolicy
+
+ADDRESS = [(socket.AF_INET, socket.SOCK_STREAM, 6, '', ('127.0.0.1', 80))]
+
+
+class _StuckResolver:
+    """Lookup hook standing in for a dead DNS server: every call blocks its
+    pool thread until released"""
+
+    def __init__(self):
+        self.release = threading.Event()
+        self.lock = threading.Lock()
+        self.hosts = []
+        self.running = 0
+        self.most_running = 0
+
+    def __call__(self, host, port):
+        with self.lock:
+            self.hosts.append(host)
+            self.running += 1
+            self.most_running = max(self.most_running, self.running)
+        self.release.wait(10)
+        with self.lock:
+            self.running -= 1
+        return ADDRESS
 
 
 class TestDNSResolution:
@@ -147,6 +174,27 @@ class TestDNSResolution:
 
         loop.run_until_complete(test())
 
+    def test_lookup_errors_are_gaierror(self, loop):
+        """Test failed lookups raise socket.gaierror with the EAI code, like the socket module"""
+
+        async def test():
+            with pytest.raises(socket.gaierror) as info:
+                await loop.getaddrinfo('not-an-ip', 80, flags=socket.AI_NUMERICHOST)
+            assert info.value.errno == socket.EAI_NONAME
+
+            with pytest.raises(socket.gaierror) as info:
+                await loop.getaddrinfo('127.0.0.1', 80, family=12345)
+            assert info.value.errno == socket.EAI_FAMILY
+
+            with pytest.raises(socket.gaierror) as info:
+                await loop.getnameinfo(('127.0.0.1', 80), 0x7FFF)
+            assert info.value.errno == socket.EAI_BADFLAGS
+            with pytest.raises(socket.gaierror) as expected:
+                socket.getnameinfo(('127.0.0.1', 80), 0x7FFF)
+            assert info.value.args == expected.value.args
+
+        loop.run_until_complete(test())
+
     def test_getnameinfo_basic(self, loop):
         """Test basic getnameinfo functionality"""
 
@@ -295,3 +343,180 @@ class TestDNSResolution:
             assert len(families) >= 1  # At least one address family
 
         loop.run_until_complete(test())
+
+    @pytest.mark.parametrize(
+        'host, port, kwargs',
+        [
+            ('127.0.0.1', 80, {}),
+            ('localhost', 'http', {'type': socket.SOCK_STREAM}),
+            (None, 8080, {'family': socket.AF_INET, 'flags': socket.AI_PASSIVE}),
+            ('::1', 443, {'family': socket.AF_INET6, 'proto': socket.IPPROTO_TCP}),
+        ],
+    )
+    def test_getaddrinfo_matches_socket_module(self, loop, host, port, kwargs):
+        """Test results match socket.getaddrinfo in both types and values"""
+        try:
+            expected = socket.getaddrinfo(host, port, **kwargs)
+        except OSError:
+            pytest.skip(f'{host!r} does not resolve on this system')
+
+        results = loop.run_until_complete(loop.getaddrinfo(host, port, **kwargs))
+        assert sorted(results) == sorted(expected)
+        for (family, kind, proto, _, _), (e_family, e_kind, e_proto, _, _) in zip(
+            sorted(results), sorted(expected)
+        ):
+            assert type(family) is type(e_family) is socket.AddressFamily
+            assert type(kind) is type(e_kind) is socket.SocketKind
+            assert type(proto) is type(e_proto)
+
+    def test_getaddrinfo_enum_identity(self, loop):
+        """Test the family of an IPv4 result is socket.AF_INET itself"""
+        results = loop.run_until_complete(
+            loop.getaddrinfo('127.0.0.1', 80, type=socket.SOCK_DGRAM)
+        )
+        family, kind, _, _, _ = results[0]
+        assert family is socket.AF_INET
+        assert kind is socket.SOCK_DGRAM
+        assert repr(family) == repr(socket.AF_INET)
+
+    def test_getaddrinfo_port_values(self, loop):
+        """Test port None, 0 and False the way socket.getaddrinfo takes them"""
+
+        async def test():
+            kwargs = {'family': socket.AF_INET, 'type': socket.SOCK_STREAM}
+            zero = await loop.getaddrinfo('127.0.0.1', 0, **kwargs)
+            assert zero[0][4] == ('127.0.0.1', 0)
+            assert await loop.getaddrinfo('127.0.0.1', False, **kwargs) == zero
+            assert await loop.getaddrinfo('127.0.0.1', None, **kwargs) == zero
+            assert await loop.getaddrinfo('127.0.0.1', b'0', **kwargs) == zero
+
+            with pytest.raises(TypeError):
+                await loop.getaddrinfo('127.0.0.1', 80, family='inet')
+            with pytest.raises(TypeError):
+                await loop.getaddrinfo('127.0.0.1', 1.5)
+
+        loop.run_until_complete(test())
+
+
+class TestLookupLimits:
+    """Test the concurrent-lookup limit, cancellation of queued lookups and
+    the resolver timeout"""
+
+    def setup_method(self):
+        veloxloop.install()
+        self.loop = VeloxLoop()
+        self.resolver = _StuckResolver()
+        self.loop._set_lookup_hook(self.resolver)
+
+    def teardown_method(self):
+        self.resolver.release.set()
+        self.loop.close()
+
+    def _stats(self):
+        stats = self.loop.get_stats()
+        return stats['lookups_in_flight'], stats['lookups_queued']
+
+    def test_excess_lookups_queue(self):
+        """Test lookups past the limit wait on the loop, not on pool threads,
+        and all resolve once the stuck ones return"""
+        loop = self.loop
+        loop.set_lookup_limit(2)
+
+        async def main():
+            lookups = [loop.getaddrinfo(f'host{i}', 80) for i in range(6)]
+            await asyncio.sleep(0.05)
+            stalled = self._stats()
+            self.resolver.release.set()
+            results = await asyncio.wait_for(asyncio.gather(*lookups), 5)
+            return stalled, results
+
+        stalled, results = loop.run_until_complete(main())
+        assert stalled == (2, 4)
+        assert results == [ADDRESS] * 6
+        assert self.resolver.most_running == 2
+        assert self._stats() == (0, 0)
+
+    def test_cancelled_queued_lookup_never_runs(self):
+        """Test cancelling a queued lookup removes it before it reaches a thread"""
+        loop = self.loop
+        loop.set_lookup_limit(1)
+
+        async def main():
+            first = loop.getaddrinfo('first', 80)
+            abandoned = [asyncio.ensure_future(loop.getaddrinfo(f'gone{i}', 80)) for i in range(3)]
+            last = loop.getaddrinfo('last', 80)
+            await asyncio.sleep(0.05)
+            for task in abandoned:
+                task.cancel()
+            await asyncio.sleep(0)
+            queued = self._stats()
+            self.resolver.release.set()
+            await asyncio.wait_for(first, 5)
+            await asyncio.wait_for(last, 5)
+            return queued, [task.cancelled() for task in abandoned]
+
+        queued, cancelled = loop.run_until_complete(main())
+        assert queued == (1, 1)
+        assert cancelled == [True] * 3
+        assert self.resolver.hosts == ['first', 'last']
+
+    def test_timeout_fails_stuck_lookup(self):
+        """Test a lookup stuck past the resolver timeout fails with
+        TimeoutError and its late result is discarded"""
+        loop = self.loop
+        loop.set_resolver_timeout(0.05)
+        assert loop.get_resolver_timeout() == 0.05
+
+        async def main():
+            lookup = loop.getaddrinfo('stuck', 80)
+            with pytest.raises(TimeoutError):
+                await asyncio.wait_for(lookup, 5)
+            # The pool thread is still stuck and keeps its slot
+            stuck = self._stats()
+            self.resolver.release.set()
+            while self._stats()[0]:
+                await asyncio.sleep(0.01)
+            with pytest.raises(TimeoutError):
+                lookup.result()
+            return stuck
+
+        assert loop.run_until_complete(main()) == (1, 0)
+        assert loop.get_stats()['lookups_timed_out'] == 1
+
+        loop.set_resolver_timeout(None)
+        assert loop.get_resolver_timeout() is None
+
+    def test_stalled_native_lookup_leaves_loop_running(self):
+        """Test a libc lookup stuck on its pool thread doesn't hold the GIL:
+        the loop keeps running and the resolver timeout fires on time"""
+        loop = self.loop
+        loop._set_lookup_hook(None)
+        loop._set_lookup_stall(1.0)
+        loop.set_resolver_timeout(0.05)
+
+        async def main():
+            for lookup in (
+                loop.getaddrinfo('127.0.0.1', 80),
+                loop.getnameinfo(('127.0.0.1', 80)),
+            ):
+                start = time.monotonic()
+                with pytest.raises(TimeoutError):
+                    await asyncio.wait_for(lookup, 5)
+                assert time.monotonic() - start < 0.5
+            while self._stats()[0]:
+                await asyncio.sleep(0.01)
+
+        loop.run_until_complete(main())
+        loop._set_lookup_stall(None)
+        assert loop.run_until_complete(loop.getnameinfo(('127.0.0.1', 80)))
+
+    def test_settings_validated(self):
+        """Test the limit and timeout reject values that can't work"""
+        from veloxloop._veloxloop import constants
+
+        assert constants.DEFAULT_LOOKUP_LIMIT > 0
+        with pytest.raises(ValueError):
+            self.loop.set_lookup_limit(0)
+        for timeout in (0, -1.0, float('nan')):
+            with pytest.raises(ValueError):
+                self.loop.set_resolver_timeout(timeout)
@@ -0,0 +1,126 @@
+"""Tests for transport.enable_error_monitoring() on paused connections"""
+
+import asyncio
+import socket
+import struct
+
+import pytest
+
+import veloxloop
+
+
+class PausedProtocol(asyncio.Protocol):
+    """Pauses reading as soon as it is connected and records what happens"""
+
+    def __init__(self, monitor):
+        self.monitor = monitor
+        self.received = []
+        self.lost = asyncio.get_running_loop().create_future()
+
+    def connection_made(self, transport):
+        self.transport = transport
+        transport.pause_reading()
+        if self.monitor:
+            transport.enable_error_monitoring()
+
+    def data_received(self, data):
+        self.received.append(data)
+
+    def connection_lost(self, exc):
+        self.lost.set_result(exc)
+
+
+def _reset(sock):
+    """Close with SO_LINGER 0 so the peer gets an RST instead of a FIN"""
+    sock.setsockopt(socket.SOL_SOCKET, socket.SO_LINGER, struct.pack('ii', 1, 0))
+    sock.close()
+
+
+async def _paused_connection(monitor):
+    loop = asyncio.get_running_loop()
+    protocols = []
+
+    def factory():
+        protocols.append(PausedProtocol(monitor))
+        return protocols[-1]
+
+    server = await loop.create_server(factory, '127.0.0.1', 0)
+    port = server.sockets[0].getsockname()[1]
+    client = socket.create_connection(('127.0.0.1', port))
+    while not protocols:
+        await asyncio.sleep(0.01)
+    return server, client, protocols[0]
+
+
+class TestErrorMonitoring:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_reset_reported_while_paused(self):
+        """Test an RST on a paused, monitored connection calls connection_lost"""
+
+        async def main():
+            server, client, protocol = await _paused_connection(monitor=True)
+            assert protocol.transport.is_error_monitoring()
+            # Data waiting on a paused transport neither wakes it nor is read
+            client.sendall(b'queued')
+            await asyncio.sleep(0.1)
+            assert not protocol.lost.done()
+
+            _reset(client)
+            exc = await asyncio.wait_for(protocol.lost, 5)
+            assert isinstance(exc, ConnectionResetError)
+            assert protocol.received == []
+            assert protocol.transport.is_closing()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_off_by_default(self):
+        """Test without monitoring a paused connection only learns of the RST later"""
+
+        async def main():
+            server, client, protocol = await _paused_connection(monitor=False)
+            assert not protocol.transport.is_error_monitoring()
+            _reset(client)
+            await asyncio.sleep(0.2)
+            assert not protocol.lost.done()
+
+            protocol.transport.resume_reading()
+            exc = await asyncio.wait_for(protocol.lost, 5)
+            assert isinstance(exc, ConnectionResetError)
+            server.close()
+
+        asyncio.run(main())
+
+    def test_loop_default_and_resume(self):
+        """Test the per-loop default, and that resuming reads normally again"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            assert loop.get_monitor_idle_connections() is False
+            loop.set_monitor_idle_connections(True)
+            server, client, protocol = await _paused_connection(monitor=False)
+            assert protocol.transport.is_error_monitoring()
+
+            client.sendall(b'hello')
+            protocol.transport.resume_reading()
+            for _ in range(100):
+                if protocol.received:
+                    break
+                await asyncio.sleep(0.01)
+            assert b''.join(protocol.received) == b'hello'
+
+            protocol.transport.pause_reading()
+            protocol.transport.disable_error_monitoring()
+            protocol.transport.enable_error_monitoring()
+            _reset(client)
+            exc = await asyncio.wait_for(protocol.lost, 5)
+            assert isinstance(exc, ConnectionResetError)
+            server.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
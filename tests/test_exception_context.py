@@ -0,0 +1,214 @@
+"""Tests for the contexts the loop passes to its exception handler"""
+
+import asyncio
+import concurrent.futures
+import errno
+import socket
+import struct
+import threading
+
+import pytest
+
+import veloxloop
+from veloxloop import VeloxLoop
+
+
+def _run_with_sink(main, debug=False):
+    """Run `main(loop, contexts)` on a fresh loop whose exception contexts are
+    collected by the test sink; returns (result, contexts)"""
+    loop = VeloxLoop()
+    loop.set_debug(debug)
+    contexts = []
+    loop._set_test_exception_sink(contexts.append)
+    try:
+        return loop.run_until_complete(main(loop, contexts)), contexts
+    finally:
+        loop.close()
+
+
+async def _until(predicate, timeout=5):
+    async with asyncio.timeout(timeout):
+        while not predicate():
+            await asyncio.sleep(0.01)
+
+
+class TestExceptionContext:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_sink_replaces_handler(self):
+        """Test the sink gets every context and the handler none of them"""
+        handled = []
+
+        async def main(loop, contexts):
+            loop.set_exception_handler(lambda loop, context: handled.append(context))
+            loop.call_exception_handler({'message': 'direct', 'extra': 1})
+            loop._set_test_exception_sink(None)
+            loop.call_exception_handler({'message': 'after'})
+
+        _, contexts = _run_with_sink(main)
+        assert contexts == [{'message': 'direct', 'extra': 1}]
+        assert [c['message'] for c in handled] == ['after']
+
+    def test_protocol_callback_raises(self):
+        """Test a raising data_received reports message, exception, transport and protocol"""
+
+        class Broken(asyncio.Protocol):
+            def connection_made(self, transport):
+                self.transport = transport
+
+            def data_received(self, data):
+                raise ValueError('bad data')
+
+        async def main(loop, contexts):
+            a, b = socket.socketpair()
+            transport, protocol = await loop.create_connection(Broken, sock=a)
+            b.send(b'x')
+            await _until(lambda: contexts)
+            b.close()
+            return transport, protocol
+
+        (transport, protocol), contexts = _run_with_sink(main)
+        [context] = contexts
+        assert context['message'] == 'Fatal error: protocol.data_received() call failed.'
+        assert isinstance(context['exception'], ValueError)
+        assert context['transport'] is transport
+        assert context['protocol'] is protocol
+
+    def test_refused_connect_in_writer(self):
+        """Test a writer callback raising the connect failure names its fd"""
+
+        async def main(loop, contexts):
+            closed = socket.socket()
+            closed.bind(('127.0.0.1', 0))
+            port = closed.getsockname()[1]
+            closed.close()
+
+            sock = socket.socket()
+            sock.setblocking(False)
+            try:
+                sock.connect(('127.0.0.1', port))
+            except BlockingIOError:
+                pass
+
+            def on_writable():
+                loop.remove_writer(sock)
+                err = sock.getsockopt(socket.SOL_SOCKET, socket.SO_ERROR)
+                raise OSError(err, 'connect failed')
+
+            loop.add_writer(sock, on_writable)
+            await _until(lambda: contexts)
+            sock.close()
+            return sock.fileno(), on_writable
+
+        (_, callback), contexts = _run_with_sink(main)
+        [context] = contexts
+        assert context['message'].startswith('Exception in callback for fd ')
+        assert isinstance(context['fd'], int)
+        assert context['callback'] is callback
+        assert context['exception'].errno == errno.ECONNREFUSED
+
+    def test_fatal_read_error_in_debug(self):
+        """Test a reset connection is reported with its transport in debug mode"""
+        lost = []
+
+        class Client(asyncio.Protocol):
+            def connection_lost(self, exc):
+                lost.append(exc)
+
+        async def main(loop, contexts):
+            listener = socket.create_server(('127.0.0.1', 0))
+            transport, protocol = await loop.create_connection(
+                Client, *listener.getsockname()
+            )
+            conn, _ = listener.accept()
+            # Closing with SO_LINGER 0 sends RST instead of FIN
+            conn.setsockopt(socket.SOL_SOCKET, socket.SO_LINGER, struct.pack('ii', 1, 0))
+            conn.close()
+            listener.close()
+            await _until(lambda: lost)
+            return transport, protocol
+
+        (transport, protocol), contexts = _run_with_sink(main, debug=True)
+        [context] = contexts
+        assert context['message'] == 'Fatal read error on socket transport'
+        assert isinstance(context['exception'], ConnectionResetError)
+        assert context['transport'] is transport
+        assert context['protocol'] is protocol
+        assert isinstance(lost[0], ConnectionResetError)
+
+    def test_fatal_read_error_quiet_without_debug(self):
+        """Test a reset connection is not reported outside debug mode"""
+        lost = []
+
+        class Client(asyncio.Protocol):
+            def connection_lost(self, exc):
+                lost.append(exc)
+
+        async def main(loop, contexts):
+            listener = socket.create_server(('127.0.0.1', 0))
+            await loop.create_connection(Client, *listener.getsockname())
+            conn, _ = listener.accept()
+            conn.setsockopt(socket.SOL_SOCKET, socket.SO_LINGER, struct.pack('ii', 1, 0))
+            conn.close()
+            listener.close()
+            await _until(lambda: lost)
+
+        _, contexts = _run_with_sink(main)
+        assert contexts == []
+
+    def test_server_protocol_factory_raises(self):
+        """Test a raising protocol factory is reported and the server keeps accepting"""
+        calls = []
+
+        def factory():
+            calls.append(None)
+            if len(calls) == 1:
+                raise RuntimeError('no protocol for you')
+            return asyncio.Protocol()
+
+        async def main(loop, contexts):
+            server = await loop.create_server(factory, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            clients = [socket.create_connection(('127.0.0.1', port)) for _ in range(2)]
+            await _until(lambda: len(calls) == 2)
+            for client in clients:
+                client.close()
+            server.close()
+
+        _, contexts = _run_with_sink(main)
+        [context] = contexts
+        assert context['message'] == 'Error on transport creation for incoming connection'
+        assert isinstance(context['exception'], RuntimeError)
+
+    def test_executor_job_fails_after_close(self):
+        """Test a job failing once the loop is closed still reaches the handler"""
+        loop = VeloxLoop()
+        contexts = []
+        loop._set_test_exception_sink(contexts.append)
+        release = threading.Event()
+
+        def job():
+            release.wait(5)
+            raise KeyError('late')
+
+        async def main():
+            return loop.run_in_executor(executor, job)
+
+        executor = concurrent.futures.ThreadPoolExecutor(1)
+        try:
+            fut = loop.run_until_complete(main())
+            loop.close()
+        finally:
+            # The job fails, and its done callback runs, before the worker exits
+            release.set()
+            executor.shutdown(wait=True)
+        assert not fut.done()
+        [context] = contexts
+        assert context['message'] == 'Executor job failed after the event loop was closed'
+        assert isinstance(context['exception'], KeyError)
+        assert context['future'].exception() is context['exception']
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,115 @@
+"""Tests for add_reader()/add_writer() on FIFOs, ptys and regular files"""
+
+import asyncio
+import errno
+import os
+import pty
+import tempfile
+import tty
+
+import pytest
+
+import veloxloop
+
+
+async def _read_until_eof(fd, on_read=None):
+    """Chunks read from `fd` through add_reader until EOF. A pty master
+    reports its hung up slave with EIO rather than an empty read"""
+    loop = asyncio.get_running_loop()
+    chunks = []
+    done = loop.create_future()
+
+    def readable():
+        try:
+            data = os.read(fd, 1024)
+        except BlockingIOError:
+            return
+        except OSError as e:
+            assert e.errno == errno.EIO
+            data = b''
+        if not data:
+            loop.remove_reader(fd)
+            done.set_result(None)
+            return
+        chunks.append(data)
+        if on_read:
+            on_read(len(chunks))
+
+    loop.add_reader(fd, readable)
+    await asyncio.wait_for(done, 5)
+    return chunks
+
+
+class TestFdKinds:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_fifo_reads_and_writer_close_is_eof(self):
+        """Test a named FIFO's data arrives and closing its writer reads as EOF"""
+
+        async def main():
+            with tempfile.TemporaryDirectory() as tmp:
+                path = os.path.join(tmp, 'fifo')
+                os.mkfifo(path)
+                read_fd = os.open(path, os.O_RDONLY | os.O_NONBLOCK)
+                write_fd = os.open(path, os.O_WRONLY | os.O_NONBLOCK)
+                loop = asyncio.get_running_loop()
+
+                def on_read(count):
+                    if count < 3:
+                        os.write(write_fd, b'chunk-%d;' % count)
+                    else:
+                        os.close(write_fd)
+
+                os.write(write_fd, b'chunk-0;')
+                chunks = await _read_until_eof(read_fd, on_read)
+                assert b''.join(chunks) == b'chunk-0;chunk-1;chunk-2;'
+                assert not loop.remove_reader(read_fd)
+                os.close(read_fd)
+
+        asyncio.run(main())
+
+    def test_pty_partial_reads_and_hangup(self):
+        """Test a raw mode pty delivers an escape sequence written in pieces,
+        and the slave closing ends the reads"""
+
+        async def main():
+            master, slave = pty.openpty()
+            tty.setraw(slave)
+            os.set_blocking(master, False)
+            loop = asyncio.get_running_loop()
+            pieces = [b'\x1b', b'[', b'1;5', b'A']
+
+            def on_read(count):
+                if pieces:
+                    loop.call_later(0.01, os.write, slave, pieces.pop(0))
+                else:
+                    os.close(slave)
+
+            os.write(slave, pieces.pop(0))
+            chunks = await _read_until_eof(master, on_read)
+            assert b''.join(chunks) == b'\x1b[1;5A'
+            assert len(chunks) == 4
+            os.close(master)
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('method', ['add_reader', 'add_writer'])
+    def test_regular_file_refused(self, method):
+        """Test a regular file is refused with EPERM like epoll, pointing at
+        the async file API, and nothing is left registered"""
+        loop = asyncio.new_event_loop()
+        try:
+            with tempfile.TemporaryFile() as f:
+                with pytest.raises(PermissionError, match='open_file') as info:
+                    getattr(loop, method)(f.fileno(), lambda: None)
+                assert info.value.errno == errno.EPERM
+                assert not loop.remove_reader(f.fileno())
+                assert not loop.remove_writer(f.fileno())
+            loop.run_until_complete(asyncio.sleep(0))
+        finally:
+            loop.close()
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
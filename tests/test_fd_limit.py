@@ -0,0 +1,211 @@
+"""Tests for running out of file descriptors: error messages, the spare fd
+that keeps listeners draining, and loop.get_fd_usage()"""
+
+import asyncio
+import contextlib
+import errno
+import gc
+import os
+import resource
+import socket
+
+import pytest
+
+import veloxloop
+from veloxloop import VeloxLoop
+
+
+@contextlib.contextmanager
+def _out_of_fds():
+    """Lower RLIMIT_NOFILE a little above the fds in use and open /dev/null
+    until it is reached; everything is given back on exit"""
+    # Loops of earlier tests left to the cycle collector would otherwise
+    # hand their fds back halfway through
+    gc.collect()
+    soft, hard = resource.getrlimit(resource.RLIMIT_NOFILE)
+    highest = max(int(fd) for fd in os.listdir('/proc/self/fd'))
+    resource.setrlimit(resource.RLIMIT_NOFILE, (highest + 32, hard))
+    filler = []
+    try:
+        while True:
+            try:
+                filler.append(os.open('/dev/null', os.O_RDONLY))
+            except OSError as e:
+                assert e.errno == errno.EMFILE
+                break
+        yield
+    finally:
+        for fd in filler:
+            os.close(fd)
+        resource.setrlimit(resource.RLIMIT_NOFILE, (soft, hard))
+
+
+async def _until(predicate, timeout=5):
+    async with asyncio.timeout(timeout):
+        while not predicate():
+            await asyncio.sleep(0.01)
+
+
+async def _start(loop, kind, accepted):
+    """A server of `kind` that appends to `accepted` for each connection it takes"""
+    if kind == 'create_server':
+
+        def factory():
+            accepted.append(None)
+            return asyncio.Protocol()
+
+        server = await loop.create_server(factory, '127.0.0.1', 0)
+    else:
+
+        async def on_client(reader, writer):
+            accepted.append(None)
+
+        server = await loop.start_server(on_client, '127.0.0.1', 0)
+    return server, server.sockets[0].getsockname()[1]
+
+
+def _connect_later(port):
+    """A client socket made now (while fds are free) that connects on call"""
+    client = socket.socket()
+
+    def connect():
+        client.connect(('127.0.0.1', port))
+        client.setblocking(False)
+        return client
+
+    return connect
+
+
+def _run(main, **options):
+    loop = VeloxLoop(**options)
+    contexts = []
+    loop._set_test_exception_sink(contexts.append)
+    try:
+        loop.run_until_complete(main(loop, contexts))
+    finally:
+        loop.close()
+    return contexts
+
+
+class TestFdLimit:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_fd_usage(self):
+        """Test get_fd_usage counts registered fds and reports the soft limit"""
+
+        async def main(loop, contexts):
+            registered, soft_limit = loop.get_fd_usage()
+            assert soft_limit == resource.getrlimit(resource.RLIMIT_NOFILE)[0]
+            a, b = socket.socketpair()
+            with a, b:
+                loop.add_reader(a, lambda: None)
+                assert loop.get_fd_usage()[0] == registered + 1
+                loop.remove_reader(a)
+            assert loop.get_fd_usage()[0] == registered
+
+        _run(main)
+
+    def test_close_releases_spare_fd(self):
+        """Test close() gives the reserved fd back"""
+
+        def devnull_fds():
+            count = 0
+            for fd in os.listdir('/proc/self/fd'):
+                with contextlib.suppress(OSError):
+                    count += os.readlink(f'/proc/self/fd/{fd}') == '/dev/null'
+            return count
+
+        before = devnull_fds()
+        loop = VeloxLoop()
+        assert devnull_fds() == before + 1
+        loop.close()
+        assert devnull_fds() == before
+
+    def test_create_connection_names_operation(self):
+        """Test EMFILE from create_connection and open_connection says where it came from"""
+
+        async def main(loop, contexts):
+            listener = socket.create_server(('127.0.0.1', 0))
+            port = listener.getsockname()[1]
+            with listener, _out_of_fds():
+                with pytest.raises(OSError) as info:
+                    await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+                assert info.value.errno == errno.EMFILE
+                assert 'create_connection() failed' in str(info.value)
+                assert 'registered with the event loop' in str(info.value)
+
+                with pytest.raises(OSError, match=r'open_connection\(\) failed') as info:
+                    await loop.open_connection('127.0.0.1', port)
+                assert info.value.errno == errno.EMFILE
+
+        _run(main)
+
+    def test_sock_accept_names_operation(self):
+        """Test EMFILE from sock_accept says where it came from"""
+
+        async def main(loop, contexts):
+            listener = socket.create_server(('127.0.0.1', 0))
+            listener.setblocking(False)
+            client = socket.create_connection(listener.getsockname())
+            with listener, client, _out_of_fds():
+                with pytest.raises(OSError, match=r'sock_accept\(\) failed') as info:
+                    await loop.sock_accept(listener)
+                assert info.value.errno == errno.EMFILE
+
+        _run(main)
+
+    @pytest.mark.parametrize('kind', ['create_server', 'start_server'])
+    def test_spare_fd_drains_backlog(self, kind):
+        """Test a listener out of fds drops pending connections instead of spinning"""
+
+        async def main(loop, contexts):
+            accepted = []
+            server, port = await _start(loop, kind, accepted)
+            connect = _connect_later(port)
+            with _out_of_fds():
+                client = connect()
+                await _until(lambda: contexts)
+                # Dropped: the client sees the connection closed
+                await _until(lambda: client.recv(1) == b'', timeout=1)
+                await asyncio.sleep(0.1)
+            client.close()
+            assert accepted == []
+            [context] = contexts
+            assert context['message'] == 'Out of file descriptors: dropped an incoming connection'
+            assert context['exception'].errno == errno.EMFILE
+
+            # With fds back the server accepts as usual
+            with socket.create_connection(('127.0.0.1', port)):
+                await _until(lambda: accepted)
+            server.close()
+
+        _run(main)
+
+    @pytest.mark.parametrize('kind', ['create_server', 'start_server'])
+    def test_pause_without_spare_fd(self, kind):
+        """Test without a spare fd the listener pauses and resumes later"""
+
+        async def main(loop, contexts):
+            accepted = []
+            server, port = await _start(loop, kind, accepted)
+            connect = _connect_later(port)
+            with _out_of_fds():
+                client = connect()
+                await _until(lambda: contexts)
+                await asyncio.sleep(0.2)
+            # One report, not one per loop iteration
+            [context] = contexts
+            assert context['message'].startswith('Out of file descriptors: pausing accept()')
+            assert accepted == []
+
+            # The pending connection is taken once the pause is over
+            await _until(lambda: accepted, timeout=3)
+            client.close()
+            server.close()
+
+        _run(main, reserve_fd=False)
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,136 @@
+"""Tests for loop.remove_reader()/remove_writer() on fds the loop owns"""
+
+import asyncio
+import socket
+
+import pytest
+
+import veloxloop
+
+
+class Echo(asyncio.Protocol):
+    def connection_made(self, transport):
+        self.transport = transport
+
+    def data_received(self, data):
+        self.transport.write(data)
+
+
+async def _stream_echo(reader, writer):
+    writer.write(await reader.read(100))
+    await writer.drain()
+    writer.close()
+
+
+async def _start(kind):
+    loop = asyncio.get_running_loop()
+    if kind == 'protocol':
+        return await loop.create_server(Echo, '127.0.0.1', 0)
+    return await asyncio.start_server(_stream_echo, '127.0.0.1', 0)
+
+
+async def _roundtrip(port, payload=b'ping'):
+    reader, writer = await asyncio.open_connection('127.0.0.1', port)
+    writer.write(payload)
+    data = await asyncio.wait_for(reader.read(100), 5)
+    writer.close()
+    return data
+
+
+class TestFdOwnership:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_never_registered(self):
+        """Test removing from unregistered fds returns False and never raises"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            fd = a.fileno()
+            with a, b:
+                assert loop.remove_reader(fd) is False
+                assert loop.remove_writer(a) is False
+                loop.add_reader(a, lambda: None)
+                assert loop.remove_reader(a) is True
+                assert loop.remove_reader(a) is False
+            # Closed and out-of-range descriptors just aren't registered either
+            assert loop.remove_reader(fd) is False
+            assert loop.remove_writer(2**40) is False
+
+        asyncio.run(main())
+
+    def test_removed_user_reader_is_not_called(self):
+        """Test a reader removed before its fd is ready never runs"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            called = []
+            with a, b:
+                loop.add_reader(a, lambda: called.append('read'))
+                assert loop.remove_reader(a)
+                b.send(b'x')
+                await asyncio.sleep(0.05)
+            assert called == []
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('kind', ['protocol', 'streams'])
+    def test_server_shutdown_sequence(self, kind):
+        """Test aiohttp's shutdown: remove_reader on the listener, then close"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await _start(kind)
+            sock = server.sockets[0]
+            port = sock.getsockname()[1]
+            fd = sock.fileno()
+
+            # The accept handler belongs to the server, not to the caller
+            assert loop.remove_reader(fd) is False
+            assert loop.remove_writer(fd) is False
+            with pytest.raises(RuntimeError):
+                loop.add_reader(fd, lambda: None)
+            assert server.is_serving()
+            assert await _roundtrip(port) == b'ping'
+
+            server.close()
+            await asyncio.wait_for(server.wait_closed(), 5)
+            assert not server.is_serving()
+            assert loop.remove_reader(fd) is False
+            with pytest.raises(OSError):
+                await _roundtrip(port)
+
+        asyncio.run(main())
+
+    def test_transport_keeps_its_handlers(self):
+        """Test remove_reader on a connected transport's fd leaves it reading"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(Echo, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            received = loop.create_future()
+
+            class Client(asyncio.Protocol):
+                def data_received(self, data):
+                    received.set_result(bytes(data))
+
+            transport, _ = await loop.create_connection(Client, '127.0.0.1', port)
+            fd = transport.get_extra_info('socket').fileno()
+            assert loop.remove_reader(fd) is False
+            with pytest.raises(RuntimeError):
+                loop.add_reader(fd, lambda: None)
+
+            transport.write(b'still here')
+            assert await asyncio.wait_for(received, 5) == b'still here'
+            transport.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,180 @@
+"""Tests for loop.open_file() and io_uring backed AsyncFile reads and writes"""
+
+import asyncio
+import contextlib
+import gc
+import hashlib
+import os
+import pathlib
+import tempfile
+
+import pytest
+
+import veloxloop
+
+CHUNK = 64 * 1024
+
+
+async def _read_all(f):
+    digest = hashlib.sha256()
+    offset = 0
+    while data := await f.read(CHUNK, offset):
+        digest.update(data)
+        offset += len(data)
+    return digest.hexdigest(), offset
+
+
+class TestFileIO:
+    def setup_method(self):
+        veloxloop.install()
+        self._tmp = tempfile.TemporaryDirectory()
+        self.tmp = pathlib.Path(self._tmp.name)
+
+    def teardown_method(self):
+        self._tmp.cleanup()
+
+    def test_read_ten_megabytes(self):
+        """Test reading a 10 MB file in 64 KB chunks matches its checksum"""
+        path = self.tmp / 'blob'
+        payload = os.urandom(10 * 1024 * 1024)
+        path.write_bytes(payload)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            f = await loop.open_file(path)
+            assert f.name == str(path)
+            digest, size = await _read_all(f)
+            await f.close()
+            assert f.closed
+            return digest, size
+
+        digest, size = asyncio.run(main())
+        assert size == len(payload)
+        assert digest == hashlib.sha256(payload).hexdigest()
+
+    def test_write_fsync_and_read_back(self):
+        """Test positional and sequential writes, fsync and short reads at EOF"""
+        path = self.tmp / 'out'
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            f = await loop.open_file(path, os.O_RDWR | os.O_CREAT | os.O_TRUNC, 0o600)
+            assert await f.write(b'hello ') == 6
+            assert await f.write(bytearray(b'world')) == 5
+            assert await f.write(memoryview(b'J'), 0) == 1
+            assert await f.fsync() is None
+            # Sequential reads use the file position, left at the end
+            assert await f.read(10) == b''
+            assert await f.read(100, 0) == b'Jello world'
+            assert await f.read(100, 6) == b'world'
+            await f.close()
+
+            with pytest.raises(ValueError):
+                await f.read(1)
+
+        asyncio.run(main())
+        assert path.read_bytes() == b'Jello world'
+        assert path.stat().st_mode & 0o777 == 0o600
+
+    def test_dropped_file_closes_fd(self):
+        """Test an AsyncFile garbage collected without close() closes its fd"""
+        path = self.tmp / 'dropped'
+        path.write_bytes(b'x')
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            f = await loop.open_file(path)
+            fd = f.fileno()
+            del f
+            return fd
+
+        fd = asyncio.run(main())
+        gc.collect()
+        # Gone, or reused for something else by now
+        with contextlib.suppress(FileNotFoundError):
+            assert os.readlink(f'/proc/self/fd/{fd}') != str(path)
+
+    def test_open_errors(self):
+        """Test open failures raise the matching OSError with the filename"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            missing = self.tmp / 'missing'
+            with pytest.raises(FileNotFoundError) as info:
+                await loop.open_file(missing)
+            assert info.value.filename == str(missing)
+            with pytest.raises(IsADirectoryError):
+                await loop.open_file(self.tmp, os.O_WRONLY)
+            with pytest.raises(ValueError):
+                await loop.open_file('bad\0name')
+
+        asyncio.run(main())
+
+    def test_cancelled_read_is_withdrawn(self):
+        """Test cancelling a blocked read aborts it, so it can't take later data"""
+        fifo = self.tmp / 'fifo'
+        os.mkfifo(fifo)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            # O_RDWR so opening doesn't wait for a writer
+            f = await loop.open_file(fifo, os.O_RDWR)
+            with pytest.raises(TimeoutError):
+                await asyncio.wait_for(f.read(100), 0.1)
+
+            writer = os.open(fifo, os.O_WRONLY | os.O_NONBLOCK)
+            os.write(writer, b'after')
+            assert await asyncio.wait_for(f.read(100), 5) == b'after'
+            os.close(writer)
+            await f.close()
+
+        asyncio.run(main())
+
+    def test_loop_not_blocked(self):
+        """Test an echo connection keeps round-tripping while a file is read"""
+        path = self.tmp / 'blob'
+        payload = os.urandom(10 * 1024 * 1024)
+        path.write_bytes(payload)
+
+        class Echo(asyncio.Protocol):
+            def connection_made(self, transport):
+                self.transport = transport
+
+            def data_received(self, data):
+                self.transport.write(data)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(Echo, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await asyncio.open_connection('127.0.0.1', port)
+            done = False
+            pings = 0
+
+            async def ping():
+                nonlocal pings
+                while not done:
+                    writer.write(b'ping')
+                    assert await reader.readexactly(4) == b'ping'
+                    pings += 1
+
+            pinger = asyncio.create_task(ping())
+            digests = []
+            for _ in range(5):
+                f = await loop.open_file(path)
+                digests.append((await _read_all(f))[0])
+                await f.close()
+            done = True
+            await pinger
+            writer.close()
+            server.close()
+            return digests, pings
+
+        digests, pings = asyncio.run(main())
+        assert set(digests) == {hashlib.sha256(payload).hexdigest()}
+        # Each chunk read yields to the loop, so the echo keeps pace with it
+        assert pings >= 100
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,141 @@
+"""Tests for transport.flush() and get_kernel_write_queue()"""
+
+import asyncio
+import socket
+import struct
+import threading
+import time
+
+import pytest
+
+import veloxloop
+
+TOTAL = 4 * 1024 * 1024
+
+
+class SlowPeer:
+    """Plain-socket server that reads 16 KB every few milliseconds"""
+
+    def __init__(self):
+        self.sock = socket.socket()
+        # A small receive window keeps the backlog on the sending side
+        self.sock.setsockopt(socket.SOL_SOCKET, socket.SO_RCVBUF, 32 * 1024)
+        self.sock.bind(('127.0.0.1', 0))
+        self.sock.listen()
+        self.port = self.sock.getsockname()[1]
+        self.received = 0
+        self.thread = threading.Thread(target=self._run, daemon=True)
+        self.thread.start()
+
+    def _run(self):
+        conn, _ = self.sock.accept()
+        with conn:
+            while chunk := conn.recv(16 * 1024):
+                self.received += len(chunk)
+                time.sleep(0.003)
+        self.sock.close()
+
+
+class TestFlush:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_flush_waits_for_slow_peer(self):
+        """Test flush() resolves only once the peer has taken the data"""
+        peer = SlowPeer()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', peer.port
+            )
+            transport.write(b'x' * TOTAL)
+
+            with pytest.raises(TimeoutError):
+                await transport.flush(timeout=0.1)
+            assert transport.get_kernel_write_queue() > 0
+            assert peer.received < TOTAL
+
+            await transport.flush(timeout=30)
+            assert transport.get_write_buffer_size() == 0
+            assert transport.get_kernel_write_queue() == 0
+            # Everything but what fits in the peer's receive buffer was read
+            assert peer.received >= TOTAL - 512 * 1024
+
+            transport.close()
+            assert transport.get_kernel_write_queue() is None
+
+        asyncio.run(main())
+        peer.thread.join(10)
+        assert peer.received == TOTAL
+
+    def test_stream_transport_flush(self):
+        """Test flush() on the streams-API transport"""
+        peer = SlowPeer()
+
+        async def main():
+            _, writer = await asyncio.open_connection('127.0.0.1', peer.port)
+            writer.write(b'y' * TOTAL)
+            await writer.transport.flush(timeout=30)
+            assert writer.transport.get_write_buffer_size() == 0
+            assert writer.transport.get_kernel_write_queue() == 0
+            assert peer.received >= TOTAL - 512 * 1024
+            writer.close()
+
+        asyncio.run(main())
+        peer.thread.join(10)
+
+    def test_idle_flush_and_validation(self):
+        """Test an idle transport flushes at once and bad timeouts are rejected"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port
+            )
+            assert await asyncio.wait_for(transport.flush(), 1) is None
+            with pytest.raises(ValueError):
+                transport.flush(timeout=-1)
+            transport.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_flush_fails_when_peer_resets(self):
+        """Test a reset with data still in the kernel queue fails flush()"""
+        listener = socket.socket()
+        listener.setsockopt(socket.SOL_SOCKET, socket.SO_RCVBUF, 32 * 1024)
+        listener.bind(('127.0.0.1', 0))
+        listener.listen()
+        port = listener.getsockname()[1]
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            conn, _ = listener.accept()
+            # All of it fits in the kernel send queue, not in the peer's window
+            transport.write(b'x' * (256 * 1024))
+            while transport.get_write_buffer_size():
+                await asyncio.sleep(0.01)
+            assert transport.get_kernel_write_queue() > 0
+            flushed = asyncio.ensure_future(transport.flush(timeout=10))
+            await asyncio.sleep(0.05)
+            assert not flushed.done()
+
+            # Close unread with SO_LINGER 0, so the peer answers with a RST
+            conn.setsockopt(socket.SOL_SOCKET, socket.SO_LINGER, struct.pack('ii', 1, 0))
+            conn.close()
+            with pytest.raises(ConnectionError):
+                await flushed
+            transport.close()
+
+        try:
+            asyncio.run(main())
+        finally:
+            listener.close()
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
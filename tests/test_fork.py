@@ -0,0 +1,122 @@
+"""Tests that a loop can't be reused across fork() and a child can start its own"""
+
+import asyncio
+import os
+import traceback
+
+import pytest
+
+import veloxloop
+
+
+def _in_child(body):
+    """Run `body()` in a forked child; returns its exit status and traceback"""
+    read_fd, write_fd = os.pipe()
+    pid = os.fork()
+    if pid == 0:
+        os.close(read_fd)
+        status = 0
+        try:
+            body()
+        except BaseException:
+            os.write(write_fd, traceback.format_exc().encode())
+            status = 1
+        finally:
+            os._exit(status)
+    os.close(write_fd)
+    with os.fdopen(read_fd, 'rb') as pipe:
+        report = pipe.read().decode()
+    _, status = os.waitpid(pid, 0)
+    return os.waitstatus_to_exitcode(status), report
+
+
+async def _echo_roundtrip(payload):
+    """A TCP echo round trip plus an executor job on the running loop"""
+    loop = asyncio.get_running_loop()
+
+    async def echo(reader, writer):
+        writer.write(await reader.readexactly(len(payload)))
+        await writer.drain()
+        writer.close()
+
+    server = await asyncio.start_server(echo, '127.0.0.1', 0)
+    port = server.sockets[0].getsockname()[1]
+    reader, writer = await asyncio.open_connection('127.0.0.1', port)
+    writer.write(payload)
+    data = await reader.readexactly(len(payload))
+    writer.close()
+    server.close()
+    assert await loop.run_in_executor(None, sum, [1, 2]) == 3
+    return data
+
+
+@pytest.mark.skipif(not hasattr(os, 'fork'), reason='needs fork()')
+class TestFork:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_reuse_after_fork_is_refused(self):
+        """Test an inherited loop raises a clear RuntimeError and its fds are closed"""
+        policy = asyncio.get_event_loop_policy()
+        loop = policy.new_event_loop()
+        policy.set_event_loop(loop)
+        try:
+            # Start the executor threads and the self-pipe before forking
+            assert loop.run_until_complete(loop.run_in_executor(None, sum, [1])) == 1
+            wakeup_fd = loop.get_wakeup_fd()
+            parent = os.getpid()
+
+            def child():
+                with pytest.raises(OSError):
+                    os.fstat(wakeup_fd)
+                assert loop._forked()
+                message = f'created in a different process \\(pid {parent}\\)'
+                with pytest.raises(RuntimeError, match=message):
+                    loop.call_soon(print)
+                with pytest.raises(RuntimeError, match=message):
+                    loop.run_until_complete(loop.create_future())
+                with pytest.raises(RuntimeError, match=message):
+                    loop.run_in_executor(None, sum, [1])
+                with pytest.raises(RuntimeError, match=message):
+                    loop.call_soon_threadsafe(print)
+                loop.close()
+
+            status, report = _in_child(child)
+            assert status == 0, report
+
+            # The parent's loop is untouched by the child
+            assert not loop._forked()
+            assert loop.run_until_complete(_echo_roundtrip(b'parent')) == b'parent'
+        finally:
+            policy.set_event_loop(None)
+            loop.close()
+
+    def test_child_gets_a_fresh_loop(self):
+        """Test get_event_loop() in a child builds a new loop that works end to end"""
+        policy = asyncio.get_event_loop_policy()
+        loop = policy.new_event_loop()
+        policy.set_event_loop(loop)
+        try:
+            loop.run_until_complete(asyncio.sleep(0))
+
+            def child():
+                fresh = policy.get_event_loop()
+                assert fresh is not loop
+                assert not fresh._forked()
+                assert policy.get_event_loop() is fresh
+                data = fresh.run_until_complete(_echo_roundtrip(b'child'))
+                assert data == b'child'
+                fresh.close()
+                # A new loop through asyncio.run() works as well
+                assert asyncio.run(_echo_roundtrip(b'run')) == b'run'
+
+            status, report = _in_child(child)
+            assert status == 0, report
+            assert policy.get_event_loop() is loop
+        finally:
+            policy.set_event_loop(None)
+            loop.close()
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,224 @@
+"""Tests that loop APIs return the same kind of future whether they finished
+synchronously or had to wait"""
+
+import asyncio
+import os
+import socket
+import sys
+import tempfile
+
+import pytest
+
+import veloxloop
+from veloxloop import _veloxloop
+
+
+async def _check_future(fut):
+    """Await `fut` and exercise done/result/add_done_callback on it"""
+    for name in ('done', 'result', 'add_done_callback', '__await__'):
+        assert hasattr(fut, name), name
+    result = await fut
+    assert fut.done()
+    assert fut.result() is result
+    called = []
+    fut.add_done_callback(called.append)
+    await asyncio.sleep(0)
+    assert len(called) == 1
+    return result
+
+
+class TestFutureInterface:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_sock_accept_sync_and_async(self):
+        """Test sock_accept returns one future type with or without a waiting peer"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            listener = socket.create_server(('127.0.0.1', 0))
+            listener.setblocking(False)
+            address = listener.getsockname()
+
+            # A connection already queued: accepted on the spot
+            early = socket.create_connection(address)
+            ready = loop.sock_accept(listener)
+            assert ready.done()
+            conn, _ = await _check_future(ready)
+            conn.close()
+
+            # Nobody there yet: resolved once a peer connects
+            waiting = loop.sock_accept(listener)
+            assert not waiting.done()
+            late = socket.create_connection(address)
+            conn, _ = await _check_future(waiting)
+            conn.close()
+
+            assert type(ready) is type(waiting) is _veloxloop.PendingFuture
+            for sock in (early, late, listener):
+                sock.close()
+
+        asyncio.run(main())
+
+    def test_sock_connect_sync_and_async(self):
+        """Test a connect done at once and one that is in progress both resolve
+        to None through the future interface"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with tempfile.TemporaryDirectory() as tmp:
+                path = os.path.join(tmp, 'sock')
+                unix_listener = socket.socket(socket.AF_UNIX)
+                unix_listener.bind(path)
+                unix_listener.listen()
+                # AF_UNIX connects complete synchronously
+                unix_client = socket.socket(socket.AF_UNIX)
+                unix_client.setblocking(False)
+                immediate = loop.sock_connect(unix_client, path)
+                assert immediate.done()
+                assert await _check_future(immediate) is None
+                assert type(immediate) is _veloxloop.PendingFuture
+                unix_client.close()
+                unix_listener.close()
+
+            listener = socket.create_server(('127.0.0.1', 0))
+            client = socket.socket()
+            client.setblocking(False)
+            pending = loop.sock_connect(client, listener.getsockname())
+            assert await _check_future(pending) is None
+            client.close()
+            listener.close()
+
+        asyncio.run(main())
+
+    def test_sock_sendall_try(self):
+        """Test the in-progress side of sock_sendall is a future like the rest"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            a.setblocking(False)
+            assert loop._sock_sendall_try(a, b'small') is None
+            pending = loop._sock_sendall_try(a, b'x' * (16 * 1024 * 1024))
+            assert type(pending) is _veloxloop.PendingFuture
+
+            async def drain_peer():
+                b.setblocking(False)
+                total = 5
+                while total < 5 + 16 * 1024 * 1024:
+                    total += len(await loop.sock_recv(b, 1 << 20))
+
+            reader = asyncio.ensure_future(drain_peer())
+            await _check_future(pending)
+            await reader
+            a.close()
+            b.close()
+
+        asyncio.run(main())
+
+    def test_server_and_connection_returns(self):
+        """Test create_server, start_server, open_connection, create_connection
+        and the servers' wait_closed and async with share the interface"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await _check_future(
+                loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            )
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await _check_future(
+                loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            )
+            transport.close()
+            async with server:
+                pass
+            entered = server.__aenter__()
+            assert await _check_future(entered) is server
+            await server.__aexit__(None, None, None)
+            await _check_future(server.wait_closed())
+
+            async def on_client(reader, writer):
+                writer.close()
+
+            stream_server = await _check_future(
+                loop.start_server(on_client, '127.0.0.1', 0)
+            )
+            port = stream_server.sockets[0].getsockname()[1]
+            reader, writer = await _check_future(loop.open_connection('127.0.0.1', port))
+            writer.close()
+            stream_server.close()
+            await _check_future(stream_server.wait_closed())
+            assert type(stream_server.wait_closed()) is type(entered)
+
+        asyncio.run(main())
+
+    def test_stream_writer_drain_and_wait_closed(self):
+        """Test drain() and wait_closed() return the same type done or not"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            peer = []
+            accepted = asyncio.Event()
+
+            async def on_client(reader, writer):
+                peer.append((reader, writer))
+                accepted.set()
+
+            server = await loop.start_server(on_client, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            await accepted.wait()
+
+            drained = writer.drain()
+            assert drained.done()
+            await _check_future(drained)
+
+            writer.write(b'x' * (32 * 1024 * 1024))
+            backed_up = writer.drain()
+            assert not backed_up.done()
+            peer_reader, peer_writer = peer[0]
+            data = peer_reader.readexactly(32 * 1024 * 1024)
+            if not isinstance(data, bytes):
+                # Only a future when the bytes aren't all buffered yet
+                data = await data
+            await _check_future(backed_up)
+            assert type(drained) is type(backed_up)
+
+            open_wait = writer.wait_closed()
+            assert not open_wait.done()
+            writer.close()
+            await _check_future(open_wait)
+            closed_wait = writer.wait_closed()
+            assert closed_wait.done()
+            await _check_future(closed_wait)
+            assert type(open_wait) is type(closed_wait)
+
+            peer_writer.close()
+            server.close()
+
+        asyncio.run(main())
+
+    @pytest.mark.skipif(sys.platform != 'linux', reason='pidfd based subprocesses')
+    def test_subprocess_exec(self):
+        """Test subprocess_exec resolves to (transport, protocol) like the rest"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, _ = await _check_future(
+                loop.subprocess_exec(asyncio.SubprocessProtocol, 'true')
+            )
+            transport.close()
+
+        asyncio.run(main())
+
+    def test_done_callback_on_finished_future(self):
+        """Test add_done_callback on a finished PendingFuture runs the callback"""
+        future = _veloxloop.PendingFuture()
+        future.set_result(7)
+        called = []
+        future.add_done_callback(called.append)
+        assert len(called) == 1
+        assert future.result() == 7
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
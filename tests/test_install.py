@@ -0,0 +1,92 @@
+"""Tests for install()/uninstall() and new_event_loop() as a loop factory"""
+
+import asyncio
+import sys
+import threading
+
+import pytest
+
+import veloxloop
+
+
+async def _echo_on_running_loop():
+    """An echo round trip; returns the type of the loop it ran on"""
+
+    async def echo(reader, writer):
+        writer.write(await reader.readexactly(5))
+        await writer.drain()
+        writer.close()
+
+    server = await asyncio.start_server(echo, '127.0.0.1', 0)
+    port = server.sockets[0].getsockname()[1]
+    reader, writer = await asyncio.open_connection('127.0.0.1', port)
+    writer.write(b'hello')
+    assert await reader.readexactly(5) == b'hello'
+    writer.close()
+    server.close()
+    await server.wait_closed()
+    return type(asyncio.get_running_loop())
+
+
+class TestInstall:
+    def setup_method(self):
+        veloxloop.install()
+
+    def teardown_method(self):
+        veloxloop.install()
+
+    def test_install_uninstall_round_trip(self):
+        """Test uninstall() restores the policy from before install(), however
+        many times it was installed"""
+        veloxloop.uninstall()
+        before = asyncio.get_event_loop_policy()
+        assert not isinstance(before, veloxloop.VeloxLoopPolicy)
+        veloxloop.install()
+        veloxloop.install()
+        assert isinstance(asyncio.get_event_loop_policy(), veloxloop.VeloxLoopPolicy)
+        veloxloop.uninstall()
+        assert asyncio.get_event_loop_policy() is before
+        # Nothing left to restore
+        veloxloop.uninstall()
+        assert asyncio.get_event_loop_policy() is before
+
+    def test_runner_loop_factory_without_policy(self):
+        """Test asyncio.Runner(loop_factory=new_event_loop) runs on VeloxLoop
+        with the default policy installed"""
+        veloxloop.uninstall()
+        with asyncio.Runner(loop_factory=veloxloop.new_event_loop) as runner:
+            assert runner.run(_echo_on_running_loop()) is veloxloop.VeloxLoop
+            loop = runner.get_loop()
+        assert loop.is_closed()
+
+    @pytest.mark.skipif(sys.version_info < (3, 12), reason='asyncio.run() loop_factory')
+    def test_run_loop_factory(self):
+        """Test asyncio.run(..., loop_factory=new_event_loop) runs on VeloxLoop"""
+        veloxloop.uninstall()
+        loop_type = asyncio.run(
+            _echo_on_running_loop(), loop_factory=veloxloop.new_event_loop
+        )
+        assert loop_type is veloxloop.VeloxLoop
+
+    def test_factory_in_another_thread(self):
+        """Test each factory call builds a fresh loop that works off the main thread"""
+        results = []
+
+        def run():
+            with asyncio.Runner(loop_factory=veloxloop.new_event_loop) as runner:
+                results.append(runner.run(_echo_on_running_loop()))
+
+        threads = [threading.Thread(target=run) for _ in range(2)]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join(10)
+        assert results == [veloxloop.VeloxLoop, veloxloop.VeloxLoop]
+        first, second = veloxloop.new_event_loop(), veloxloop.new_event_loop()
+        assert first is not second
+        first.close()
+        second.close()
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
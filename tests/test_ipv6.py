@@ -0,0 +1,245 @@
+"""Tests for IPv6 / dual-stack / AF_UNIX socket address handling"""
+
+import asyncio
+import os
+import socket
+import struct
+import tempfile
+
+import pytest
+
+import veloxloop
+from veloxloop import _veloxloop
+from veloxloop._veloxloop import _parse_sockaddr
+
+
+def _has_ipv6():
+    try:
+        with socket.socket(socket.AF_INET6, socket.SOCK_STREAM) as s:
+            s.bind(('::1', 0))
+        return True
+    except OSError:
+        return False
+
+
+requires_ipv6 = pytest.mark.skipif(not _has_ipv6(), reason='IPv6 loopback not available')
+
+
+def sockaddr_in(ip, port):
+    return struct.pack('=H', socket.AF_INET) + struct.pack('!H', port) + socket.inet_aton(ip) + bytes(8)
+
+
+def sockaddr_in6(ip, port, flowinfo=0, scope_id=0):
+    return (
+        struct.pack('=H', socket.AF_INET6)
+        + struct.pack('!HI', port, flowinfo)
+        + socket.inet_pton(socket.AF_INET6, ip)
+        + struct.pack('=I', scope_id)
+    )
+
+
+def sockaddr_un(path):
+    return struct.pack('=H', socket.AF_UNIX) + path
+
+
+class TestParseSockaddr:
+    """Decode hand-constructed sockaddr storage"""
+
+    @pytest.mark.parametrize('port', [0x1234, 0x3412, 1, 0xFF00])
+    def test_ipv4(self, port):
+        assert _parse_sockaddr(sockaddr_in('192.168.1.20', port)) == ('192.168.1.20', port)
+
+    @pytest.mark.parametrize('port', [0x1234, 0x3412, 1, 0xFF00])
+    def test_ipv6(self, port):
+        raw = sockaddr_in6('fe80::1:2', port, flowinfo=0x00012345, scope_id=3)
+        assert _parse_sockaddr(raw) == ('fe80::1:2', port, 0x00012345, 3)
+
+    def test_ipv4_mapped_ipv6(self):
+        assert _parse_sockaddr(sockaddr_in6('::ffff:10.0.0.1', 80)) == ('::ffff:10.0.0.1', 80, 0, 0)
+
+    def test_unix_path(self):
+        assert _parse_sockaddr(sockaddr_un(b'/tmp/velox.sock\x00')) == '/tmp/velox.sock'
+
+    def test_unix_abstract(self):
+        assert _parse_sockaddr(sockaddr_un(b'\x00velox')) == b'\x00velox'
+
+    def test_unix_unnamed(self):
+        assert _parse_sockaddr(sockaddr_un(b'')) == ''
+
+    def test_truncated_and_unknown(self):
+        assert _parse_sockaddr(sockaddr_in('1.2.3.4', 80)[:6]) == ('', 0)
+        assert _parse_sockaddr(struct.pack('=H', 255) + bytes(14)) == ('', 0)
+
+
+class TestSockAcceptConnect:
+    """sock_accept / sock_connect across address families"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def _accept_connect(self, family, listen_addr, connect_addr=None):
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = socket.socket(family, socket.SOCK_STREAM)
+            server.setblocking(False)
+            server.bind(listen_addr)
+            server.listen(1)
+            target = connect_addr(server.getsockname()) if connect_addr else server.getsockname()
+
+            client = socket.socket(family, socket.SOCK_STREAM)
+            client.setblocking(False)
+            try:
+                accept_fut = loop.sock_accept(server)
+                await loop.sock_connect(client, target)
+                conn, peer = await asyncio.wait_for(accept_fut, 5)
+                try:
+                    assert conn.family == family
+                    return peer, client.getsockname(), conn.getsockname()
+                finally:
+                    conn.close()
+            finally:
+                client.close()
+                server.close()
+
+        return asyncio.run(main())
+
+    @requires_ipv6
+    def test_ipv6_accept_peer_address(self):
+        peer, client_name, _ = self._accept_connect(socket.AF_INET6, ('::1', 0))
+        assert len(peer) == 4
+        assert peer[0] == '::1'
+        assert peer[1] == client_name[1]
+
+    @requires_ipv6
+    def test_ipv6_connect_four_tuple_and_scope(self):
+        lo_index = socket.if_nametoindex('lo') if hasattr(socket, 'if_nametoindex') else 1
+        for make_target in (
+            lambda addr: ('::1', addr[1], 0, 0),
+            lambda addr: ('::1%%%d' % lo_index, addr[1]),
+            lambda addr: ('::1%lo', addr[1]),
+        ):
+            peer, _, _ = self._accept_connect(socket.AF_INET6, ('::1', 0), make_target)
+            assert peer[0] == '::1'
+
+    @requires_ipv6
+    def test_dual_stack_ipv4_client(self):
+        peer, _, _ = self._accept_connect(
+            socket.AF_INET6, ('::', 0), lambda addr: ('127.0.0.1', addr[1])
+        )
+        assert peer[0] == '::ffff:127.0.0.1'
+
+    def test_ipv4_hostname(self):
+        peer, client_name, _ = self._accept_connect(
+            socket.AF_INET, ('127.0.0.1', 0), lambda addr: ('localhost', addr[1])
+        )
+        assert peer == client_name
+
+    def test_native_connect_needs_numeric_host(self):
+        """Test the native sock_connect refuses names instead of resolving
+        them on the loop thread; the loop's sock_connect looks them up first"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as s:
+                s.setblocking(False)
+                with pytest.raises(ValueError):
+                    _veloxloop.VeloxLoop.sock_connect(loop, s, ('localhost', 80))
+
+        asyncio.run(main())
+
+    def test_unix_accept(self):
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, 'velox.sock')
+            peer, _, server_name = self._accept_connect(socket.AF_UNIX, path)
+            assert peer == ''
+            assert server_name == path
+
+    def test_invalid_scope(self):
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.socket(socket.AF_INET6, socket.SOCK_STREAM) as s:
+                s.setblocking(False)
+                with pytest.raises(ValueError):
+                    await loop.sock_connect(s, ('fe80::1%no-such-if0', 80))
+
+        asyncio.run(main())
+
+
+class TestServerSockets:
+    """Server.sockets entries behave like socket objects"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def _check_sockets(self, server, family, host):
+        sockets = server.sockets
+        assert len(sockets) == 1
+        wrapper = sockets[0]
+        assert wrapper.family == family
+        assert wrapper.type == socket.SOCK_STREAM
+        name = wrapper.getsockname()
+        assert name[0] == host
+        assert len(name) == (4 if family == socket.AF_INET6 else 2)
+
+        # The fd is usable by the socket module for the server's lifetime
+        sock = socket.socket(fileno=wrapper.fileno())
+        try:
+            assert sock.family == family
+            assert sock.type == socket.SOCK_STREAM
+            assert sock.getsockname() == name
+            assert sock.getsockopt(socket.SOL_SOCKET, socket.SO_ACCEPTCONN)
+        finally:
+            sock.detach()
+        return name
+
+    @requires_ipv6
+    def test_ipv6_wildcard(self):
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '::', 0)
+            name = self._check_sockets(server, socket.AF_INET6, '::')
+            with socket.create_connection(('::1', name[1]), timeout=5):
+                pass
+            server.close()
+            await server.wait_closed()
+
+            server = await loop.start_server(lambda r, w: w.close(), '::', 0)
+            self._check_sockets(server, socket.AF_INET6, '::')
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    def test_ipv4(self):
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            self._check_sockets(server, socket.AF_INET, '127.0.0.1')
+            server.close()
+            await server.wait_closed()
+
+            server = await loop.start_server(lambda r, w: w.close(), '127.0.0.1', 0)
+            self._check_sockets(server, socket.AF_INET, '127.0.0.1')
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+
+class TestGetnameinfoIPv6:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_getnameinfo_ipv6_numeric(self):
+        async def main():
+            loop = asyncio.get_running_loop()
+            flags = socket.NI_NUMERICHOST | socket.NI_NUMERICSERV
+            host, port = await loop.getnameinfo(('::1', 8080, 0, 0), flags)
+            assert host == '::1'
+            assert port == '8080'
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,480 @@
+"""Tests for the keyword arguments of the endpoint-creating methods: unknown
+names, invalid combinations, and the asyncio options they implement"""
+
+import asyncio
+import os
+import socket
+import ssl
+
+import pytest
+
+import veloxloop
+from veloxloop import _veloxloop
+
+
+def _call(loop, method, **kwargs):
+    """`method` on a local address, with `kwargs` on top"""
+    if method == 'create_connection':
+        return loop.create_connection(asyncio.Protocol, '127.0.0.1', 1, **kwargs)
+    if method == 'open_connection':
+        return loop.open_connection('127.0.0.1', 1, **kwargs)
+    if method == 'create_server':
+        return loop.create_server(asyncio.Protocol, '127.0.0.1', 0, **kwargs)
+    if method == 'start_server':
+        return loop.start_server(lambda r, w: w.close(), '127.0.0.1', 0, **kwargs)
+    return loop.create_datagram_endpoint(
+        asyncio.DatagramProtocol, local_addr=('127.0.0.1', 0), **kwargs
+    )
+
+
+METHODS = [
+    'create_connection',
+    'open_connection',
+    'create_server',
+    'start_server',
+    'create_datagram_endpoint',
+]
+
+
+class TestUnknownKwargs:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('method', METHODS)
+    def test_typo_raises_type_error(self, method):
+        """Test an unknown keyword fails like a Python function would"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(TypeError) as info:
+                await _call(loop, method, ssl_handshake_timout=10)
+            assert str(info.value) == (
+                f"{method}() got an unexpected keyword argument 'ssl_handshake_timout'"
+            )
+
+        asyncio.run(main())
+
+    def test_checked_before_name_lookup(self):
+        """Test the wrappers refuse a typo before resolving the host"""
+
+        class Resolver:
+            async def resolve(self, host, port, family):
+                raise AssertionError('resolved')
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_resolver(Resolver())
+            with pytest.raises(TypeError, match='keepalve'):
+                await loop.create_connection(asyncio.Protocol, 'name.test', 80, keepalve=True)
+            with pytest.raises(TypeError, match='allow_brodcast'):
+                await loop.create_datagram_endpoint(
+                    asyncio.DatagramProtocol,
+                    remote_addr=('name.test', 53),
+                    allow_brodcast=True,
+                )
+
+        asyncio.run(main())
+
+    def test_asyncio_helpers(self):
+        """Test asyncio.start_server/open_connection pass keywords through to the checks"""
+
+        async def main():
+            unexpected = r"\(\) got an unexpected keyword argument 'bogus'"
+            with pytest.raises(TypeError, match='create_server' + unexpected):
+                await asyncio.start_server(lambda r, w: None, '127.0.0.1', 0, bogus=1)
+            with pytest.raises(TypeError, match='create_connection' + unexpected):
+                await asyncio.open_connection('127.0.0.1', 1, bogus=1)
+
+        asyncio.run(main())
+
+
+class TestSSLKwargs:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize(
+        'method', ['create_connection', 'open_connection', 'create_server', 'start_server']
+    )
+    @pytest.mark.parametrize('name', ['ssl_handshake_timeout', 'ssl_shutdown_timeout'])
+    def test_timeout_without_ssl(self, method, name):
+        """Test a TLS timeout without ssl is a ValueError, not a plaintext endpoint"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(ValueError, match=f'{name} is only meaningful with ssl'):
+                await _call(loop, method, **{name: 10})
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('value', [0, -1, float('nan')])
+    def test_shutdown_timeout_must_be_positive(self, value):
+        """Test ssl_shutdown_timeout is checked like ssl_handshake_timeout"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(ValueError, match='ssl_shutdown_timeout should be a positive'):
+                await _call(loop, 'create_connection', ssl=True, ssl_shutdown_timeout=value)
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('method', ['create_server', 'start_server'])
+    def test_server_ssl_refused(self, method):
+        """Test servers refuse ssl= instead of listening in plaintext"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            context = _veloxloop.SSLContext.create_server_context()
+            with pytest.raises(NotImplementedError, match='does not support ssl'):
+                await _call(loop, method, ssl=context)
+            # Falsy ssl is plain TCP, as in asyncio
+            server = await _call(loop, method, ssl=None)
+            server.close()
+
+        asyncio.run(main())
+
+    def test_stdlib_context_refused(self):
+        """Test an ssl.SSLContext is a TypeError instead of a plaintext connection"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(TypeError, match='veloxloop SSLContext'):
+                await _call(loop, 'create_connection', ssl=ssl.create_default_context())
+            with pytest.raises(NotImplementedError, match='asyncio.open_connection'):
+                await _call(loop, 'open_connection', ssl=True)
+
+        asyncio.run(main())
+
+    def test_ssl_true_uses_default_context(self):
+        """Test ssl=True starts a TLS handshake"""
+        listener = socket.create_server(('127.0.0.1', 0))
+        port = listener.getsockname()[1]
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            # A peer that never answers the ClientHello
+            with pytest.raises(ConnectionAbortedError, match='SSL handshake'):
+                await loop.create_connection(
+                    asyncio.Protocol, '127.0.0.1', port, ssl=True, ssl_handshake_timeout=0.2
+                )
+
+        try:
+            asyncio.run(main())
+        finally:
+            listener.close()
+
+    def test_server_hostname_checks(self):
+        """Test server_hostname needs ssl, and ssl without a host needs server_hostname"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(ValueError, match='server_hostname is only meaningful with ssl'):
+                await _call(loop, 'create_connection', server_hostname='example.com')
+            a, b = socket.socketpair()
+            with a, b, pytest.raises(ValueError, match='You must set server_hostname'):
+                await loop.create_connection(asyncio.Protocol, sock=a, ssl=True)
+
+        asyncio.run(main())
+
+
+class TestSockKwarg:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('method', ['create_connection', 'open_connection', 'create_server'])
+    def test_sock_with_address(self, method):
+        """Test sock= together with host/port is a ValueError"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.socket() as sock:
+                with pytest.raises(ValueError, match='host/port and sock'):
+                    await _call(loop, method, sock=sock)
+
+        asyncio.run(main())
+
+    def test_connection_needs_an_endpoint(self):
+        """Test create_connection/open_connection need host/port or sock"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(ValueError, match='no sock specified'):
+                await loop.create_connection(asyncio.Protocol)
+            with pytest.raises(ValueError, match='no sock specified'):
+                await loop.open_connection()
+
+        asyncio.run(main())
+
+    def test_wrong_socket_type(self):
+        """Test a datagram socket for a stream endpoint and vice versa"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.socket(type=socket.SOCK_DGRAM) as udp, socket.socket() as tcp:
+                with pytest.raises(ValueError, match='A Stream Socket was expected'):
+                    await loop.create_connection(asyncio.Protocol, sock=udp)
+                with pytest.raises(ValueError, match='A Stream Socket was expected'):
+                    await loop.create_server(asyncio.Protocol, sock=udp)
+                with pytest.raises(ValueError, match='A UDP Socket was expected'):
+                    await loop.create_datagram_endpoint(asyncio.DatagramProtocol, sock=tcp)
+
+        asyncio.run(main())
+
+    def test_socket_modifiers_refused(self):
+        """Test options that shape a socket we create are refused with sock="""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            with a, b:
+                with pytest.raises(ValueError, match=r'sock is specified. \(local_addr='):
+                    await loop.create_connection(
+                        asyncio.Protocol, sock=a, local_addr=('127.0.0.1', 0)
+                    )
+            with socket.socket(type=socket.SOCK_DGRAM) as udp:
+                with pytest.raises(ValueError, match=r'\(local_addr=\(.*allow_broadcast=True\)'):
+                    await loop.create_datagram_endpoint(
+                        asyncio.DatagramProtocol,
+                        local_addr=('127.0.0.1', 0),
+                        sock=udp,
+                        allow_broadcast=True,
+                    )
+            with socket.create_server(('127.0.0.1', 0)) as listener:
+                with pytest.raises(ValueError, match='reuse_port=True'):
+                    await loop.create_server(asyncio.Protocol, sock=listener, reuse_port=True)
+
+        asyncio.run(main())
+
+    def test_server_from_sock(self):
+        """Test create_server/start_server serve a caller's listening socket"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            listener = socket.socket()
+            listener.bind(('127.0.0.1', 0))
+            port = listener.getsockname()[1]
+            accepted = loop.create_future()
+
+            async def on_client(reader, writer):
+                data = reader.readexactly(4)
+                if not isinstance(data, bytes):
+                    data = await data
+                accepted.set_result(data)
+                writer.close()
+
+            with listener:
+                server = await loop.start_server(on_client, sock=listener, backlog=8)
+                assert server.sockets[0].getsockname()[1] == port
+                reader, writer = await loop.open_connection('127.0.0.1', port)
+                writer.write(b'ping')
+                assert await asyncio.wait_for(accepted, 5) == b'ping'
+                writer.close()
+                server.close()
+            # The server had its own duplicate
+            assert listener.fileno() == -1
+
+        asyncio.run(main())
+
+    def test_open_connection_from_sock(self):
+        """Test open_connection wraps a connected socket"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            with a, b:
+                reader, writer = await loop.open_connection(sock=a)
+                b.sendall(b'hello')
+                data = reader.readexactly(5)
+                if not isinstance(data, bytes):
+                    data = await data
+                assert data == b'hello'
+                writer.close()
+
+        asyncio.run(main())
+
+    def test_datagram_from_sock(self):
+        """Test create_datagram_endpoint wraps a caller's UDP socket"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            received = loop.create_future()
+
+            class Receiver(asyncio.DatagramProtocol):
+                def datagram_received(self, data, addr):
+                    received.set_result(data)
+
+            with socket.socket(type=socket.SOCK_DGRAM) as sock, socket.socket(
+                type=socket.SOCK_DGRAM
+            ) as peer:
+                sock.bind(('127.0.0.1', 0))
+                transport, _ = await loop.create_datagram_endpoint(Receiver, sock=sock)
+                peer.sendto(b'datagram', sock.getsockname())
+                assert await asyncio.wait_for(received, 5) == b'datagram'
+                transport.close()
+
+        asyncio.run(main())
+
+
+class TestServerKwargs:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_reuse_options(self):
+        """Test reuse_address defaults on and can be turned off; reuse_port shares a port"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            [sock] = server.sockets
+            assert sock.getsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR) != 0
+            server.close()
+
+            server = await loop.create_server(
+                asyncio.Protocol, '127.0.0.1', 0, reuse_address=False
+            )
+            [sock] = server.sockets
+            assert sock.getsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR) == 0
+            server.close()
+
+            first = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0, reuse_port=True)
+            port = first.sockets[0].getsockname()[1]
+            second = await loop.start_server(
+                lambda r, w: None, '127.0.0.1', port, reuse_port=True
+            )
+            assert second.sockets[0].getsockopt(socket.SOL_SOCKET, socket.SO_REUSEPORT) == 1
+            first.close()
+            second.close()
+
+        asyncio.run(main())
+
+    def test_family_and_flags(self):
+        """Test family= picks the addresses bound and flags= takes AI_PASSIVE only"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(
+                asyncio.Protocol,
+                ['127.0.0.1', '::1'],
+                0,
+                family=socket.AF_INET6,
+                flags=socket.AI_PASSIVE,
+            )
+            assert [s.family for s in server.sockets] == [socket.AF_INET6]
+            server.close()
+
+            with pytest.raises(ValueError, match='unsupported address family'):
+                await loop.create_server(asyncio.Protocol, '127.0.0.1', 0, family=socket.AF_UNIX)
+            with pytest.raises(ValueError, match='AI_PASSIVE'):
+                await loop.create_server(
+                    asyncio.Protocol, '127.0.0.1', 0, flags=socket.AI_CANONNAME
+                )
+
+        asyncio.run(main())
+
+    def test_keep_alive(self):
+        """Test asyncio's keep_alive=True sets SO_KEEPALIVE on accepted connections"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            accepted = loop.create_future()
+
+            class Accepting(asyncio.Protocol):
+                def connection_made(self, transport):
+                    accepted.set_result(transport)
+
+            server = await loop.create_server(Accepting, '127.0.0.1', 0, keep_alive=True)
+            port = server.sockets[0].getsockname()[1]
+            with socket.create_connection(('127.0.0.1', port)):
+                transport = await asyncio.wait_for(accepted, 5)
+                sock = transport.get_extra_info('socket')
+                assert sock.getsockopt(socket.SOL_SOCKET, socket.SO_KEEPALIVE) == 1
+            server.close()
+
+            with pytest.raises(ValueError, match='keep_alive and keepalive'):
+                await loop.create_server(
+                    asyncio.Protocol, '127.0.0.1', 0, keep_alive=True, keepalive=True
+                )
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('method', ['create_server', 'start_server'])
+    def test_start_serving_false(self, method):
+        """Test start_serving=False binds but accepts only after start_serving()"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            accepted = []
+            if method == 'create_server':
+
+                def factory():
+                    accepted.append(None)
+                    return asyncio.Protocol()
+
+                server = await loop.create_server(factory, '127.0.0.1', 0, start_serving=False)
+            else:
+
+                async def on_client(reader, writer):
+                    accepted.append(None)
+
+                server = await loop.start_server(
+                    on_client, '127.0.0.1', 0, start_serving=False
+                )
+            assert not server.is_serving()
+            port = server.sockets[0].getsockname()[1]
+            with socket.create_connection(('127.0.0.1', port)):
+                await asyncio.sleep(0.05)
+                assert accepted == []
+                server.start_serving()
+                assert server.is_serving()
+                async with asyncio.timeout(5):
+                    while not accepted:
+                        await asyncio.sleep(0.01)
+            server.close()
+            assert not server.is_serving()
+
+        asyncio.run(main())
+
+
+class TestDatagramKwargs:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_refused_options(self):
+        """Test reuse_address=True and non-UDP protocols are refused as in asyncio"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(ValueError, match='reuse_address=True'):
+                await _call(loop, 'create_datagram_endpoint', reuse_address=True)
+            with pytest.raises(ValueError, match='only supports UDP'):
+                await _call(loop, 'create_datagram_endpoint', proto=socket.IPPROTO_TCP)
+            with pytest.raises(ValueError, match='unsupported address family'):
+                await _call(loop, 'create_datagram_endpoint', family=socket.AF_UNIX)
+            transport, _ = await _call(
+                loop, 'create_datagram_endpoint', reuse_address=False, proto=socket.IPPROTO_UDP
+            )
+            transport.close()
+
+        asyncio.run(main())
+
+    def test_family_and_reuse_port(self):
+        """Test family=AF_INET6 without addresses and reuse_port=True"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, _ = await loop.create_datagram_endpoint(
+                asyncio.DatagramProtocol, family=socket.AF_INET6
+            )
+            assert len(transport.get_extra_info('sockname')) == 4
+            transport.close()
+
+            transport, _ = await _call(loop, 'create_datagram_endpoint', reuse_port=True)
+            fd = transport.get_extra_info('socket').fileno()
+            with socket.socket(fileno=os.dup(fd)) as sock:
+                assert sock.getsockopt(socket.SOL_SOCKET, socket.SO_REUSEPORT) == 1
+            transport.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
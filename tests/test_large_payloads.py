@@ -0,0 +1,123 @@
+"""Tests for multi-megabyte reads, which fill their bytes objects in place and
+trim them to what actually arrived"""
+
+import asyncio
+import os
+import socket
+import threading
+
+import pytest
+
+import veloxloop
+
+LARGE = 4 * 1024 * 1024
+
+
+class Collecting(asyncio.Protocol):
+    def __init__(self, expected, done):
+        self.expected = expected
+        self.done = done
+        self.chunks = []
+        self.received = 0
+
+    def connection_made(self, transport):
+        transport.set_read_chunk_size(LARGE)
+
+    def data_received(self, data):
+        self.chunks.append(data)
+        self.received += len(data)
+        if self.received >= self.expected and not self.done.done():
+            self.done.set_result(None)
+
+
+def _send_in_pieces(port, payload, piece):
+    with socket.create_connection(('127.0.0.1', port)) as sock:
+        for start in range(0, len(payload), piece):
+            sock.sendall(payload[start : start + piece])
+
+
+class TestLargePayloads:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('nbytes', [2 * 1024 * 1024, LARGE])
+    def test_sock_recv_short_read(self, nbytes):
+        """Test sock_recv with a huge nbytes returns exactly what arrived,
+        whether the data was waiting or came later"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            a.setblocking(False)
+            b.setblocking(False)
+            b.send(b'ready')
+            data = await loop.sock_recv(a, nbytes)
+            assert data == b'ready'
+            assert type(data) is bytes
+
+            waiting = asyncio.ensure_future(loop.sock_recv(a, nbytes))
+            await asyncio.sleep(0.01)
+            b.send(b'later')
+            assert await waiting == b'later'
+
+            b.close()
+            assert await loop.sock_recv(a, nbytes) == b''
+            a.close()
+
+        asyncio.run(main())
+
+    def test_protocol_large_chunks_intact(self):
+        """Test data read in 4 MB chunks arrives whole and in order, with the
+        chunks that came up short trimmed"""
+        payload = os.urandom(3 * LARGE + 12345)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            done = loop.create_future()
+            protocols = []
+
+            def factory():
+                protocols.append(Collecting(len(payload), done))
+                return protocols[-1]
+
+            server = await loop.create_server(factory, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            sender = threading.Thread(
+                target=_send_in_pieces, args=(port, payload, 1024 * 1024)
+            )
+            sender.start()
+            await asyncio.wait_for(done, 10)
+            await loop.run_in_executor(None, sender.join)
+            server.close()
+            return protocols[0].chunks
+
+        chunks = asyncio.run(main())
+        assert b''.join(chunks) == payload
+        assert all(0 < len(chunk) <= LARGE for chunk in chunks)
+
+    def test_stream_large_reads(self):
+        """Test readexactly and read of several megabytes return the right bytes"""
+        payload = os.urandom(LARGE + 777)
+
+        async def main():
+            async def send(reader, writer):
+                writer.write(payload)
+                await writer.drain()
+                writer.close()
+
+            server = await asyncio.start_server(send, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await asyncio.open_connection('127.0.0.1', port)
+            head = await reader.readexactly(3 * 1024 * 1024)
+            rest = await reader.read()
+            writer.close()
+            server.close()
+            return head, rest
+
+        head, rest = asyncio.run(main())
+        assert head == payload[: 3 * 1024 * 1024]
+        assert rest == payload[3 * 1024 * 1024 :]
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,142 @@
+"""Tests for the uring_sqpoll and max_callbacks_per_tick loop options"""
+
+import asyncio
+import socket
+import time
+
+import pytest
+
+import veloxloop
+from veloxloop import VeloxLoop
+
+
+def _storm_with_reader(loop, storm):
+    """Run `storm` callbacks, the first of which makes a reader's fd ready;
+    return the order everything ran in"""
+    order = []
+
+    async def main():
+        a, b = socket.socketpair()
+        with a, b:
+            done = loop.create_future()
+            loop.add_reader(a, lambda: order.append('io') or loop.remove_reader(a))
+            # Let the registration reach the ring before the storm
+            await asyncio.sleep(0.01)
+            loop.call_soon(b.send, b'x')
+            for i in range(storm):
+                loop.call_soon(order.append, i)
+            loop.call_soon(done.set_result, None)
+            await done
+            while 'io' not in order:
+                await asyncio.sleep(0)
+
+    loop.run_until_complete(main())
+    return order
+
+
+class TestCallbackBudget:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_unbounded_by_default(self):
+        """Test without a budget the whole storm runs before the reader"""
+        loop = VeloxLoop()
+        try:
+            assert loop.get_stats()['max_callbacks_per_tick'] is None
+            assert loop.get_backend_capabilities()['max_callbacks_per_tick'] is None
+            order = _storm_with_reader(loop, 5000)
+            assert order.index('io') == 5000
+            assert loop.get_stats()['deferred_callback_ticks'] == 0
+        finally:
+            loop.close()
+
+    def test_budget_lets_io_through(self):
+        """Test a budget defers the storm so a ready reader runs in between"""
+        loop = VeloxLoop(max_callbacks_per_tick=100)
+        try:
+            assert loop.get_stats()['max_callbacks_per_tick'] == 100
+            assert loop.get_backend_capabilities()['max_callbacks_per_tick'] == 100
+            order = _storm_with_reader(loop, 5000)
+            assert order.index('io') < 5000
+            # Deferred callbacks keep their FIFO order
+            assert [x for x in order if x != 'io'] == list(range(5000))
+            assert loop.get_stats()['deferred_callback_ticks'] >= 5000 // 100 - 1
+        finally:
+            loop.close()
+
+    def test_budget_does_not_wait_for_timeouts(self):
+        """Test deferred callbacks run on the next iteration, not after a poll wait"""
+        loop = VeloxLoop(max_callbacks_per_tick=1)
+
+        async def main():
+            ran = []
+            for i in range(200):
+                loop.call_soon(ran.append, i)
+            start = time.monotonic()
+            while len(ran) < 200:
+                await asyncio.sleep(0)
+            return ran, time.monotonic() - start
+
+        try:
+            ran, elapsed = loop.run_until_complete(main())
+            assert ran == list(range(200))
+            assert elapsed < 0.5
+        finally:
+            loop.close()
+
+    def test_invalid_options(self):
+        """Test a zero budget or an idle time without SQPOLL is refused"""
+        with pytest.raises(ValueError):
+            VeloxLoop(max_callbacks_per_tick=0)
+        with pytest.raises(ValueError, match='uring_sqpoll'):
+            VeloxLoop(uring_sqpoll_idle_ms=10)
+
+
+class TestSqpoll:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_off_by_default(self):
+        """Test a default loop reports SQPOLL as off"""
+        loop = VeloxLoop()
+        try:
+            stats = loop.get_stats()
+            assert stats['uring_sqpoll'] is False
+            assert stats['uring_sqpoll_idle_ms'] is None
+            assert loop.get_backend_capabilities()['uring_sqpoll'] is False
+        finally:
+            loop.close()
+
+    def test_sqpoll_ring_does_io(self):
+        """Test an SQPOLL ring serves I/O and wakes its idle kernel thread"""
+        with pytest.warns(RuntimeWarning, match='SQPOLL'):
+            loop = VeloxLoop(uring_sqpoll=True, uring_sqpoll_idle_ms=10)
+        try:
+            if not loop.get_stats()['uring_sqpoll']:
+                pytest.skip('kernel or permissions refuse io_uring SQPOLL')
+            assert loop.get_stats()['uring_sqpoll_idle_ms'] == 10
+            assert loop.get_backend_capabilities()['uring_sqpoll'] is True
+
+            async def main():
+                for _ in range(3):
+                    a, b = socket.socketpair()
+                    with a, b:
+                        # Long enough for the kernel thread to go to sleep
+                        time.sleep(0.05)
+                        ready = loop.create_future()
+                        loop.add_reader(
+                            a, lambda: ready.done() or ready.set_result(a.recv(1))
+                        )
+                        b.send(b'x')
+                        assert await asyncio.wait_for(ready, 5) == b'x'
+                        loop.remove_reader(a)
+                await asyncio.sleep(0.01)
+
+            loop.run_until_complete(main())
+            assert loop.get_stats()['uring_sqpoll_wakeups'] > 0
+        finally:
+            loop.close()
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
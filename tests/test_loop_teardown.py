@@ -0,0 +1,102 @@
+"""Tests for closing a loop: its io_uring backend is torn down in order, and
+wakeups sent to it afterwards go nowhere"""
+
+import asyncio
+import socket
+import threading
+import time
+
+import pytest
+
+import veloxloop
+from veloxloop import VeloxLoop
+
+
+class TestLoopTeardown:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_closed_loop_ignores_threadsafe_wakeups(self):
+        """Test call_soon_threadsafe() on a closed loop raises without
+        writing a wakeup"""
+        loop = VeloxLoop()
+        failures = loop.get_stats()['notify_failures']
+        loop.close()
+        with pytest.raises(RuntimeError):
+            loop.call_soon_threadsafe(lambda: None)
+        assert loop.get_stats()['notify_failures'] == failures
+
+    def test_closed_loop_refuses_io(self):
+        """Test registering an fd after close() raises instead of reaching the ring"""
+        a, b = socket.socketpair()
+        loop = VeloxLoop()
+        try:
+            loop.add_reader(a, lambda: None)
+            loop.run_until_complete(asyncio.sleep(0))
+            loop.close()
+            # Withdrawn already, so removing is quiet
+            assert loop.remove_reader(a)
+            with pytest.raises(RuntimeError):
+                loop.add_reader(b, lambda: None)
+        finally:
+            a.close()
+            b.close()
+
+    def test_back_to_back_loops_under_threadsafe_load(self):
+        """Test 500 loops created and closed in a row while another thread
+        keeps calling call_soon_threadsafe() on whichever one is current:
+        every accepted callback runs and no wakeup write fails"""
+        lock = threading.Lock()
+        current = [None]
+        accepted = [0]
+        ran = [0]
+        waiter = [None]
+        done = threading.Event()
+
+        def bump():
+            ran[0] += 1
+            if waiter[0] is not None and not waiter[0].done():
+                waiter[0].set_result(None)
+
+        def hammer():
+            while not done.is_set():
+                with lock:
+                    loop = current[0]
+                    if loop is not None:
+                        try:
+                            loop.call_soon_threadsafe(bump)
+                            accepted[0] += 1
+                        except RuntimeError:
+                            pass
+                time.sleep(0)
+
+        async def drain():
+            while ran[0] < accepted[0]:
+                await asyncio.sleep(0.001)
+
+        probe = VeloxLoop()
+        failures = probe.get_stats()['notify_failures']
+        probe.close()
+        thread = threading.Thread(target=hammer)
+        thread.start()
+        try:
+            for _ in range(500):
+                loop = VeloxLoop()
+                waiter[0] = loop.create_future()
+                with lock:
+                    current[0] = loop
+                loop.run_until_complete(asyncio.wait_for(waiter[0], 10))
+                with lock:
+                    current[0] = None
+                loop.run_until_complete(asyncio.wait_for(drain(), 10))
+                waiter[0] = None
+                loop.close()
+        finally:
+            done.set()
+            thread.join()
+        assert ran[0] == accepted[0] >= 500
+        assert loop.get_stats()['notify_failures'] == failures
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
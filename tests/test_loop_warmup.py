@@ -0,0 +1,189 @@
+"""Tests for loop warm-up, the expected_connections hint, and running one
+loop many times without rebuilding its backend"""
+
+import asyncio
+import socket
+import time
+
+import pytest
+
+import veloxloop
+from veloxloop import VeloxLoop
+
+
+class _Echo(asyncio.Protocol):
+    def connection_made(self, transport):
+        self.transport = transport
+
+    def data_received(self, data):
+        self.transport.write(data)
+
+
+def _first_echo(warm):
+    """Seconds from loop construction to the first echoed round trip"""
+    start = time.perf_counter()
+    loop = VeloxLoop(expected_connections=16)
+    try:
+        if warm:
+            loop.warm_up()
+
+        async def main():
+            server = await loop.create_server(_Echo, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await asyncio.open_connection('127.0.0.1', port)
+            writer.write(b'ping')
+            assert await reader.readexactly(4) == b'ping'
+            elapsed = time.perf_counter() - start
+            writer.close()
+            await writer.wait_closed()
+            server.close()
+            await server.wait_closed()
+            return elapsed
+
+        return loop.run_until_complete(main())
+    finally:
+        loop.close()
+
+
+class TestWarmUp:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_expected_connections_presizes(self):
+        """Test the hint sizes the per-iteration buffers up front"""
+        loop = VeloxLoop(expected_connections=4096)
+        try:
+            stats = loop.get_stats()
+            assert stats['expected_connections'] == 4096
+            assert stats['event_buffer_capacity'] >= 4096
+            assert stats['pending_ios_capacity'] >= 4096
+        finally:
+            loop.close()
+        loop = VeloxLoop()
+        try:
+            assert loop.get_stats()['expected_connections'] == 0
+        finally:
+            loop.close()
+
+    def test_warm_up_fills_pool_and_cycles_ring(self):
+        """Test warm_up() pools read buffers and leaves the loop usable"""
+        loop = VeloxLoop(read_chunk_size=16 * 1024, expected_connections=4)
+        try:
+            loop.warm_up()
+            assert loop.get_stats()['buffer_pool_bytes'] >= 4 * 16 * 1024
+            # Twice is harmless
+            loop.warm_up()
+            ran = []
+            loop.call_soon(ran.append, 1)
+            loop.call_later(0.01, ran.append, 2)
+            loop.run_until_complete(asyncio.sleep(0.02))
+            assert ran == [1, 2]
+        finally:
+            loop.close()
+
+    def test_warm_up_only_before_running(self):
+        """Test warm_up() is refused while running and after close()"""
+        loop = VeloxLoop()
+
+        async def main():
+            with pytest.raises(RuntimeError):
+                loop.warm_up()
+
+        loop.run_until_complete(main())
+        loop.close()
+        with pytest.raises(RuntimeError):
+            loop.warm_up()
+
+    def test_warm_up_keeps_ready_fds(self):
+        """Test readiness collected by warm_up() still reaches its reader"""
+        loop = VeloxLoop()
+        a, b = socket.socketpair()
+        try:
+            got = []
+            loop.add_reader(a, lambda: got.append(a.recv(16)))
+            loop.run_until_complete(asyncio.sleep(0.01))
+            b.send(b'x')
+            time.sleep(0.01)
+            loop.warm_up()
+            loop.run_until_complete(asyncio.sleep(0.01))
+            assert got == [b'x']
+        finally:
+            loop.remove_reader(a)
+            loop.close()
+            a.close()
+            b.close()
+
+    def test_first_echo_round_trip(self):
+        """Test construction to first echo stays fast with and without warm_up()"""
+        for warm in (False, True):
+            _first_echo(warm)
+        cold = min(_first_echo(False) for _ in range(5))
+        warm = min(_first_echo(True) for _ in range(5))
+        assert cold < 1.0
+        assert warm < 1.0
+
+
+class TestRunCycles:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_backend_built_once_per_loop(self):
+        """Test stop()/run_forever() cycles reuse the loop's ring"""
+        first = VeloxLoop()
+        before = first.get_stats()['backend_builds']
+        first.close()
+        loop = VeloxLoop()
+        try:
+            built = loop.get_stats()['backend_builds']
+            assert built == before + 1
+            for _ in range(20):
+                loop.call_soon(loop.stop)
+                loop.run_forever()
+            loop.warm_up()
+            assert loop.get_stats()['backend_builds'] == built
+        finally:
+            loop.close()
+
+    def test_cycles_interleave_timers_and_io(self):
+        """Test timers and a reader carry over between run_forever() calls"""
+        loop = VeloxLoop()
+        a, b = socket.socketpair()
+        a.setblocking(False)
+        received = []
+        fired = []
+        try:
+            loop.add_reader(a, lambda: received.append(a.recv(64)))
+            # Due in a later cycle than the one it was scheduled in
+            loop.call_later(0.03, fired.append, 'late')
+            for i in range(6):
+                loop.call_soon(b.send, b'%d' % i)
+                loop.call_later(0.005, fired.append, i)
+                loop.call_later(0.01, loop.stop)
+                loop.run_forever()
+                assert b''.join(received).endswith(b'%d' % i)
+                assert fired[-1] in (i, 'late')
+            assert b''.join(received) == b'012345'
+            assert 'late' in fired
+            assert [x for x in fired if x != 'late'] == list(range(6))
+
+            async def echo():
+                server = await loop.create_server(_Echo, '127.0.0.1', 0)
+                port = server.sockets[0].getsockname()[1]
+                reader, writer = await asyncio.open_connection('127.0.0.1', port)
+                writer.write(b'after cycles')
+                data = await reader.readexactly(12)
+                writer.close()
+                server.close()
+                await server.wait_closed()
+                return data
+
+            assert loop.run_until_complete(echo()) == b'after cycles'
+        finally:
+            loop.remove_reader(a)
+            loop.close()
+            a.close()
+            b.close()
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,143 @@
+"""Tests that socket failures surface as the OSError subclass matching their errno"""
+
+import asyncio
+import errno
+import signal
+import socket
+import struct
+
+import pytest
+
+import veloxloop
+from tests.helpers import closed_port
+
+
+class TestOSErrorMapping:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_create_connection_refused(self):
+        """Test a refused create_connection is ConnectionRefusedError with its errno"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(ConnectionRefusedError) as info:
+                await loop.create_connection(
+                    asyncio.Protocol, '127.0.0.1', closed_port()
+                )
+            assert info.value.errno == errno.ECONNREFUSED
+
+        asyncio.run(main())
+
+    def test_sock_connect_refused(self):
+        """Test a refused sock_connect is ConnectionRefusedError with its errno"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            sock = socket.socket()
+            sock.setblocking(False)
+            try:
+                with pytest.raises(ConnectionRefusedError) as info:
+                    await loop.sock_connect(sock, ('127.0.0.1', closed_port()))
+                assert info.value.errno == errno.ECONNREFUSED
+            finally:
+                sock.close()
+
+        asyncio.run(main())
+
+    def test_create_server_address_in_use(self):
+        """Test binding a listening port keeps EADDRINUSE on the OSError"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            holder = socket.socket()
+            holder.bind(('127.0.0.1', 0))
+            holder.listen(1)
+            try:
+                with pytest.raises(OSError) as info:
+                    await loop.create_server(
+                        asyncio.Protocol,
+                        '127.0.0.1',
+                        holder.getsockname()[1],
+                        reuse_address=False,
+                    )
+                assert info.value.errno == errno.EADDRINUSE
+                assert info.value.strerror
+            finally:
+                holder.close()
+
+        asyncio.run(main())
+
+    def test_sock_sendall_to_closed_peer(self):
+        """Test writing to a reset peer is BrokenPipeError or ConnectionResetError"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            a.setblocking(False)
+            b.close()
+            try:
+                with pytest.raises((BrokenPipeError, ConnectionResetError)) as info:
+                    for _ in range(100):
+                        await loop.sock_sendall(a, b'x' * 65536)
+                assert info.value.errno in (errno.EPIPE, errno.ECONNRESET)
+            finally:
+                a.close()
+
+        asyncio.run(main())
+
+    def test_transport_reset_reaches_connection_lost(self):
+        """Test a peer's RST is passed to connection_lost as ConnectionResetError with errno"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            lost = loop.create_future()
+
+            class Protocol(asyncio.Protocol):
+                def connection_lost(self, exc):
+                    lost.set_result(exc)
+
+            listener = socket.socket()
+            listener.bind(('127.0.0.1', 0))
+            listener.listen(1)
+            transport, _ = await loop.create_connection(
+                Protocol, '127.0.0.1', listener.getsockname()[1]
+            )
+            peer, _ = listener.accept()
+            listener.close()
+            peer.setsockopt(
+                socket.SOL_SOCKET, socket.SO_LINGER, struct.pack('ii', 1, 0)
+            )
+            peer.close()
+            exc = await asyncio.wait_for(lost, 5)
+            assert isinstance(exc, ConnectionResetError)
+            assert exc.errno == errno.ECONNRESET
+            transport.close()
+
+        asyncio.run(main())
+
+    def test_sock_recv_survives_signals(self):
+        """Test EINTR from a frequent signal is retried, not raised as InterruptedError"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket.socketpair()
+            a.setblocking(False)
+            try:
+                loop.call_later(0.3, b.send, b'done')
+                assert await loop.sock_recv(a, 16) == b'done'
+            finally:
+                a.close()
+                b.close()
+
+        previous = signal.signal(signal.SIGALRM, lambda *args: None)
+        signal.setitimer(signal.ITIMER_REAL, 0.001, 0.001)
+        try:
+            asyncio.run(main())
+        finally:
+            signal.setitimer(signal.ITIMER_REAL, 0)
+            signal.signal(signal.SIGALRM, previous)
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
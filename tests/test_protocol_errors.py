@@ -0,0 +1,160 @@
+"""Tests for exceptions raised by protocol callbacks: fatal for data/EOF, reported for flow control"""
+
+import asyncio
+import socket
+
+import pytest
+
+import veloxloop
+
+
+class Boom(Exception):
+    pass
+
+
+class Failing(asyncio.Protocol):
+    """Raises from data_received on the chunk numbered `fail_on`"""
+
+    def __init__(self, fail_on=2, fail_eof=False):
+        self.fail_on = fail_on
+        self.fail_eof = fail_eof
+        self.chunks = []
+        self.lost = []
+        self.error = None
+
+    def connection_made(self, transport):
+        self.transport = transport
+
+    def data_received(self, data):
+        self.chunks.append(data)
+        if len(self.chunks) == self.fail_on:
+            self.error = Boom('chunk %d' % len(self.chunks))
+            raise self.error
+
+    def eof_received(self):
+        if self.fail_eof:
+            self.error = Boom('eof')
+            raise self.error
+
+    def connection_lost(self, exc):
+        self.lost.append(exc)
+
+
+async def _connected_pair(loop, side, protocol):
+    """The loop's transport for `protocol` plus a plain socket as its peer"""
+    if side == 'client':
+        listener = socket.socket()
+        listener.bind(('127.0.0.1', 0))
+        listener.listen(1)
+        transport, _ = await loop.create_connection(
+            lambda: protocol, '127.0.0.1', listener.getsockname()[1]
+        )
+        peer, _ = listener.accept()
+        listener.close()
+        return transport, peer, None
+
+    server = await loop.create_server(lambda: protocol, '127.0.0.1', 0)
+    peer = socket.create_connection(server.addresses()[0])
+    for _ in range(100):
+        if getattr(protocol, 'transport', None) is not None:
+            break
+        await asyncio.sleep(0.01)
+    return protocol.transport, peer, server
+
+
+async def _settle():
+    for _ in range(5):
+        await asyncio.sleep(0.02)
+
+
+class TestProtocolErrors:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('side', ['client', 'server'])
+    def test_data_received_error_is_fatal(self, side):
+        """Test a raising data_received is reported once and closes the transport"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            contexts = []
+            loop.set_exception_handler(lambda loop, ctx: contexts.append(ctx))
+            protocol = Failing(fail_on=2)
+            transport, peer, server = await _connected_pair(loop, side, protocol)
+
+            for chunk in (b'one', b'two', b'three', b'four'):
+                try:
+                    peer.send(chunk)
+                except OSError:
+                    break
+                await _settle()
+
+            assert protocol.chunks == [b'one', b'two']
+            assert protocol.lost == [protocol.error]
+            assert transport.is_closing()
+            assert len(contexts) == 1
+            context = contexts[0]
+            assert context['message'] == 'Fatal error: protocol.data_received() call failed.'
+            assert context['exception'] is protocol.error
+            assert context['transport'] is transport
+            assert context['protocol'] is protocol
+
+            peer.close()
+            if server is not None:
+                server.close()
+
+        asyncio.run(main())
+
+    def test_eof_received_error_is_fatal(self):
+        """Test a raising eof_received is reported and passed to connection_lost"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            contexts = []
+            loop.set_exception_handler(lambda loop, ctx: contexts.append(ctx))
+            protocol = Failing(fail_on=0, fail_eof=True)
+            transport, peer, _ = await _connected_pair(loop, 'client', protocol)
+
+            peer.shutdown(socket.SHUT_WR)
+            await _settle()
+
+            assert protocol.lost == [protocol.error]
+            assert [c['message'] for c in contexts] == [
+                'Fatal error: protocol.eof_received() call failed.'
+            ]
+            peer.close()
+
+        asyncio.run(main())
+
+    def test_pause_writing_error_is_reported_only(self):
+        """Test a raising pause_writing is reported and the transport stays open"""
+
+        class BadFlowControl(Failing):
+            def pause_writing(self):
+                raise Boom('pause')
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            contexts = []
+            loop.set_exception_handler(lambda loop, ctx: contexts.append(ctx))
+            protocol = BadFlowControl(fail_on=0)
+            transport, peer, _ = await _connected_pair(loop, 'client', protocol)
+
+            # The peer never reads, so most of this stays queued
+            transport.write(b'x' * (8 * 1024 * 1024))
+            transport.set_write_buffer_limits(high=1024)
+
+            assert [c['message'] for c in contexts] == ['protocol.pause_writing() failed']
+            assert isinstance(contexts[0]['exception'], Boom)
+            assert contexts[0]['protocol'] is protocol
+            assert not transport.is_closing()
+            assert protocol.lost == []
+
+            transport.abort()
+            peer.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,160 @@
+"""Tests for transport.set_rate_limit() write pacing"""
+
+import asyncio
+import time
+
+import pytest
+
+import veloxloop
+
+MB = 1024 * 1024
+
+
+class Sink(asyncio.Protocol):
+    """Counts what arrives and resolves `done` once `expected` bytes are in"""
+
+    def __init__(self, expected, done):
+        self.expected = expected
+        self.done = done
+        self.received = 0
+
+    def data_received(self, data):
+        self.received += len(data)
+        if self.received >= self.expected and not self.done.done():
+            self.done.set_result(time.monotonic())
+
+
+async def _sink_server(expected):
+    """A server whose one connection counts `expected` bytes; returns
+    (server, port, future of the monotonic time they were all received)"""
+    loop = asyncio.get_running_loop()
+    done = loop.create_future()
+    server = await loop.create_server(lambda: Sink(expected, done), '127.0.0.1', 0)
+    return server, server.sockets[0].getsockname()[1], done
+
+
+async def _send(port, payload, rate=None):
+    """Connect, pace to `rate` and write `payload`; returns (transport, start)"""
+    loop = asyncio.get_running_loop()
+    transport, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+    if rate is not None:
+        transport.set_rate_limit(rate)
+    start = time.monotonic()
+    transport.write(payload)
+    return transport, start
+
+
+class TestRateLimit:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_paced_and_unlimited_side_by_side(self):
+        """Test 5 MB at 1 MB/s takes ~5s while an unlimited peer is not slowed"""
+        size = 5 * MB
+
+        async def main():
+            paced_server, paced_port, paced_done = await _sink_server(size)
+            free_server, free_port, free_done = await _sink_server(size)
+            paced, paced_start = await _send(paced_port, b'p' * size, rate=MB)
+            assert paced.get_rate_limit() == (MB, MB // 10)
+            free, free_start = await _send(free_port, b'f' * size)
+            assert free.get_rate_limit() is None
+
+            free_elapsed = await asyncio.wait_for(free_done, 10) - free_start
+            assert paced.get_write_buffer_size() > 0
+            paced_elapsed = await asyncio.wait_for(paced_done, 15) - paced_start
+            for transport in (paced, free):
+                transport.close()
+            for server in (paced_server, free_server):
+                server.close()
+            return paced_elapsed, free_elapsed
+
+        paced_elapsed, free_elapsed = asyncio.run(main())
+        # The first burst (100ms worth) goes out at once
+        assert 4.9 * 0.8 <= paced_elapsed <= 4.9 * 1.2
+        assert free_elapsed < 1.0
+
+    def test_remove_limit_flushes(self):
+        """Test set_rate_limit(None) sends the rest of the buffer at full speed"""
+        size = 2 * MB
+
+        async def main():
+            server, port, done = await _sink_server(size)
+            transport, start = await _send(port, b'x' * size, rate=64 * 1024)
+            await asyncio.sleep(0.2)
+            assert transport.get_write_buffer_size() > 0
+            transport.set_rate_limit(None)
+            assert transport.get_rate_limit() is None
+            elapsed = await asyncio.wait_for(done, 5) - start
+            transport.close()
+            server.close()
+            return elapsed
+
+        assert asyncio.run(main()) < 1.5
+
+    def test_close_flushes_paced_data(self):
+        """Test close() waits for paced data and then loses the connection"""
+        size = 256 * 1024
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server, port, done = await _sink_server(size)
+            lost = loop.create_future()
+
+            class Client(asyncio.Protocol):
+                def connection_lost(self, exc):
+                    lost.set_result(exc)
+
+            transport, _ = await loop.create_connection(Client, '127.0.0.1', port)
+            transport.set_rate_limit(512 * 1024, burst=16 * 1024)
+            start = time.monotonic()
+            transport.write(b'c' * size)
+            transport.close()
+            assert await asyncio.wait_for(lost, 5) is None
+            elapsed = await asyncio.wait_for(done, 5) - start
+            server.close()
+            return elapsed
+
+        elapsed = asyncio.run(main())
+        assert 0.5 * 0.8 <= elapsed <= 0.5 * 1.5
+
+    def test_stream_transport(self):
+        """Test the streams API paces the same way and drain() still works"""
+        size = MB
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server, port, done = await _sink_server(size)
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            writer.transport.set_rate_limit(2 * MB, burst=64 * 1024)
+            start = time.monotonic()
+            for _ in range(16):
+                writer.write(b's' * (size // 16))
+                await writer.drain()
+            elapsed = await asyncio.wait_for(done, 5) - start
+            writer.close()
+            server.close()
+            return elapsed
+
+        elapsed = asyncio.run(main())
+        assert 0.5 * 0.8 <= elapsed <= 0.5 * 1.5
+
+    def test_invalid_limits(self):
+        """Test zero rates and bursts are refused"""
+
+        async def main():
+            server, port, _ = await _sink_server(1)
+            transport, _ = await _send(port, b'')
+            with pytest.raises(ValueError):
+                transport.set_rate_limit(0)
+            with pytest.raises(ValueError):
+                transport.set_rate_limit(1000, burst=0)
+            assert transport.get_rate_limit() is None
+            transport.close()
+            server.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,467 @@
+"""Tests for loop APIs re-entered from callbacks dispatched by _run_once"""
+
+import asyncio
+import socket
+import sys
+
+import pytest
+
+import veloxloop
+
+
+def _loop_api_calls(loop, own_fd):
+    """(name, fn) pairs touching every piece of loop state from inside a callback"""
+    spare_r, spare_w = socket.socketpair()
+    spare_r.setblocking(False)
+    spare_w.setblocking(False)
+    noop = lambda: None  # noqa: E731
+
+    def add_remove_reader():
+        loop.add_reader(spare_r.fileno(), noop)
+        assert loop.remove_reader(spare_r.fileno())
+
+    def add_remove_writer():
+        loop.add_writer(spare_w.fileno(), noop)
+        assert loop.remove_writer(spare_w.fileno())
+
+    def own_fd_writer():
+        # Same fd as the reader being dispatched
+        loop.add_writer(own_fd, noop)
+        loop.remove_writer(own_fd)
+
+    def timers():
+        handle = loop.call_later(10, noop)
+        handle.cancel()
+        loop.call_at(loop.time() + 10, noop).cancel()
+
+    def nested_run():
+        coro = asyncio.sleep(0)
+        with pytest.raises(RuntimeError, match='already running'):
+            loop.run_until_complete(coro)
+        coro.close()
+
+    def exception_handler():
+        handler = loop.get_exception_handler()
+        loop.set_exception_handler(lambda lp, ctx: None)
+        loop.call_exception_handler({'message': 'from callback'})
+        loop.set_exception_handler(handler)
+
+    calls = [
+        ('add_remove_reader', add_remove_reader),
+        ('add_remove_writer', add_remove_writer),
+        ('own_fd_writer', own_fd_writer),
+        ('call_soon', lambda: loop.call_soon(noop)),
+        ('call_soon_threadsafe', lambda: loop.call_soon_threadsafe(noop)),
+        ('timers', timers),
+        ('create_future', lambda: loop.create_future()),
+        ('time', lambda: loop.time()),
+        ('nested_run', nested_run),
+        ('exception_handler', exception_handler),
+        ('debug', lambda: loop.set_debug(loop.get_debug())),
+        ('read_buffer_size', lambda: loop.set_read_buffer_size(loop.get_read_buffer_size())),
+        ('future_pool_stats', lambda: loop.future_pool_stats()),
+        ('is_running', lambda: loop.is_running()),
+    ]
+    return calls, (spare_r, spare_w)
+
+
+class TestReentrantCallbacks:
+    """Every loop API must be callable from reader, timer and done callbacks"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def _run_in(self, context):
+        errors = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            done = loop.create_future()
+            rsock, wsock = socket.socketpair()
+            rsock.setblocking(False)
+            calls, spares = _loop_api_calls(loop, rsock.fileno())
+
+            def run_all():
+                for name, fn in calls:
+                    try:
+                        fn()
+                    except BaseException as e:  # noqa: BLE001 - collect everything, including panics
+                        errors.append((name, repr(e)))
+                if not done.done():
+                    done.set_result(None)
+
+            if context == 'reader':
+
+                def on_readable():
+                    loop.remove_reader(rsock.fileno())
+                    rsock.recv(16)
+                    run_all()
+
+                loop.add_reader(rsock.fileno(), on_readable)
+                wsock.send(b'x')
+            elif context == 'timer':
+                loop.call_later(0.001, run_all)
+            else:
+                fut = loop.create_future()
+                fut.add_done_callback(lambda f: run_all())
+                loop.call_soon(fut.set_result, None)
+
+            try:
+                await asyncio.wait_for(done, 5)
+            finally:
+                loop.remove_reader(rsock.fileno())
+                for s in (rsock, wsock, *spares):
+                    s.close()
+
+        asyncio.run(main())
+        assert errors == [], errors
+
+    @pytest.mark.parametrize('context', ['reader', 'timer', 'done'])
+    def test_loop_api_from_callback(self, context):
+        self._run_in(context)
+
+    def test_reader_removes_itself_during_multi_event_dispatch(self):
+        """Test callbacks removing/adding readers while several fds fire at once"""
+        fired = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            pairs = [socket.socketpair() for _ in range(4)]
+            done = loop.create_future()
+
+            def make_cb(i, r):
+                def cb():
+                    r.recv(16)
+                    fired.append(i)
+                    # Remove every reader, including ones not dispatched yet
+                    for _, (rr, _) in enumerate(pairs):
+                        loop.remove_reader(rr.fileno())
+                    if not fired[1:]:
+                        loop.call_soon(done.set_result, None)
+
+                return cb
+
+            for i, (r, w) in enumerate(pairs):
+                r.setblocking(False)
+                loop.add_reader(r.fileno(), make_cb(i, r))
+            for _, w in pairs:
+                w.send(b'x')
+
+            await asyncio.wait_for(done, 5)
+            await asyncio.sleep(0.01)
+            for r, w in pairs:
+                r.close()
+                w.close()
+
+        asyncio.run(main())
+        assert len(fired) == 1
+
+    def test_transport_used_from_connection_lost_after_flush(self):
+        """Test connection_lost dispatched from the write path can use the transport"""
+        seen = []
+
+        class Writer(asyncio.Protocol):
+            def __init__(self, done):
+                self.done = done
+                self.transport = None
+
+            def connection_made(self, transport):
+                self.transport = transport
+                # Large enough to leave data queued, so close() finishes from the writer callback
+                transport.write(b'x' * (4 * 1024 * 1024))
+                transport.close()
+
+            def connection_lost(self, exc):
+                try:
+                    seen.append(self.transport.get_write_buffer_size())
+                    seen.append(self.transport.is_closing())
+                except BaseException as e:  # noqa: BLE001
+                    seen.append(repr(e))
+                if not self.done.done():
+                    self.done.set_result(exc)
+
+        class Drain(asyncio.Protocol):
+            def data_received(self, data):
+                pass
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            done = loop.create_future()
+            server = await loop.create_server(Drain, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            await loop.create_connection(lambda: Writer(done), '127.0.0.1', port)
+            assert await asyncio.wait_for(done, 10) is None
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+        assert seen == [0, True], seen
+
+    @pytest.mark.parametrize('trigger', ['write', 'set_write_buffer_limits'])
+    def test_transport_used_from_pause_writing(self, trigger):
+        """Test pause_writing() runs once the transport is free to use again"""
+        seen = []
+        errors = []
+
+        class Writer(asyncio.Protocol):
+            def __init__(self, done):
+                self.done = done
+                self.transport = None
+
+            def connection_made(self, transport):
+                self.transport = transport
+                if trigger == 'set_write_buffer_limits':
+                    transport.set_write_buffer_limits(high=256 * 1024 * 1024)
+                # The peer never reads: write until the kernel buffers are full
+                while transport.get_write_buffer_size() < 1024 * 1024:
+                    transport.write(b'x' * (1024 * 1024))
+                if trigger == 'set_write_buffer_limits':
+                    assert not seen
+                    transport.set_write_buffer_limits(high=1024)
+
+            def pause_writing(self):
+                try:
+                    seen.append(self.transport.get_write_buffer_size() > 1024)
+                    self.transport.set_write_buffer_limits(high=1024)
+                except BaseException as e:  # noqa: BLE001
+                    seen.append(repr(e))
+                if not self.done.done():
+                    self.done.set_result(None)
+
+        held = []
+
+        class Held(asyncio.Protocol):
+            def connection_made(self, transport):
+                transport.pause_reading()
+                held.append(transport)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_exception_handler(lambda lp, ctx: errors.append(ctx))
+            done = loop.create_future()
+            server = await loop.create_server(Held, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(lambda: Writer(done), '127.0.0.1', port)
+            await asyncio.wait_for(done, 10)
+            transport.abort()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+        assert seen == [True], seen
+        assert not errors, errors
+
+
+    def test_replaced_reader_never_runs_again_same_tick(self):
+        """Test add_reader replacement takes effect for events already collected"""
+        ran = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            (a_r, a_w), (b_r, b_w) = socket.socketpair(), socket.socketpair()
+            for sock in (a_r, b_r):
+                sock.setblocking(False)
+
+            def new_cb(name, sock):
+                def cb():
+                    sock.recv(16)
+                    ran.append(f'new_{name}')
+
+                return cb
+
+            # Each old callback replaces the other one, so whichever runs
+            # first, the other old callback must not run afterwards
+            def old_a():
+                a_r.recv(16)
+                ran.append('old_a')
+                loop.add_reader(b_r.fileno(), new_cb('b', b_r))
+
+            def old_b():
+                b_r.recv(16)
+                ran.append('old_b')
+                loop.add_reader(a_r.fileno(), new_cb('a', a_r))
+
+            loop.add_reader(a_r.fileno(), old_a)
+            loop.add_reader(b_r.fileno(), old_b)
+            a_w.send(b'x')
+            b_w.send(b'x')
+            await asyncio.sleep(0.05)
+
+            for sock in (a_r, b_r):
+                loop.remove_reader(sock.fileno())
+            for sock in (a_r, a_w, b_r, b_w):
+                sock.close()
+
+        asyncio.run(main())
+        assert sorted(ran) in (['new_b', 'old_a'], ['new_a', 'old_b']), ran
+
+    def test_reader_replacing_writer_on_same_fd(self):
+        """Test a reader swapping the writer of its own fd stops the old writer"""
+        ran = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            rsock, wsock = socket.socketpair()
+            rsock.setblocking(False)
+            fd = rsock.fileno()
+
+            def new_writer():
+                ran.append('new_writer')
+                loop.remove_writer(fd)
+
+            def old_writer():
+                ran.append('old_writer')
+
+            def reader():
+                ran.append('reader')
+                loop.remove_reader(fd)
+                loop.add_writer(fd, new_writer)
+
+            loop.add_writer(fd, old_writer)
+            loop.add_reader(fd, reader)
+            # Replace the writer before its first dispatch can happen
+            loop.add_writer(fd, old_writer)
+            wsock.send(b'x')
+            await asyncio.sleep(0.05)
+            loop.remove_writer(fd)
+            rsock.close()
+            wsock.close()
+
+        asyncio.run(main())
+        assert 'reader' in ran
+        # The old writer may run before the reader, but never after it
+        assert 'old_writer' not in ran[ran.index('reader'):], ran
+
+
+def _depth():
+    frame, depth = sys._getframe(1), 0
+    while frame is not None:
+        frame, depth = frame.f_back, depth + 1
+    return depth
+
+
+class _Echo(asyncio.Protocol):
+    """Echoes every chunk `factor` times, queueing while writing is paused and
+    sending the queue from resume_writing()"""
+
+    def __init__(self, factor, depths, coalescing):
+        self.factor = factor
+        self.depths = depths
+        self.coalescing = coalescing
+        self.queue = []
+        self.paused = False
+
+    def connection_made(self, transport):
+        self.transport = transport
+        transport.set_write_buffer_limits(high=64 * 1024)
+        if self.coalescing:
+            transport.set_write_coalescing()
+
+    def data_received(self, data):
+        self.depths.append(_depth())
+        self.send(data * self.factor)
+
+    def send(self, data):
+        if self.paused:
+            self.queue.append(data)
+        else:
+            self.transport.write(data)
+
+    def pause_writing(self):
+        self.paused = True
+
+    def resume_writing(self):
+        self.depths.append(_depth())
+        self.paused = False
+        queue, self.queue = self.queue, []
+        for data in queue:
+            self.send(data)
+
+
+class _Layer(asyncio.Protocol):
+    """Middleware forwarding every callback to the protocol it wraps"""
+
+    def __init__(self, inner):
+        self.inner = inner
+
+    def connection_made(self, transport):
+        self.inner.connection_made(transport)
+
+    def data_received(self, data):
+        self.inner.data_received(data)
+
+    def pause_writing(self):
+        self.inner.pause_writing()
+
+    def resume_writing(self):
+        self.inner.resume_writing()
+
+    def eof_received(self):
+        return self.inner.eof_received()
+
+    def connection_lost(self, exc):
+        self.inner.connection_lost(exc)
+
+
+class TestNestedWrites:
+    """Writes from protocol callbacks must not nest further callbacks"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('coalescing', [False, True])
+    def test_layered_echo_stack_stays_flat(self, coalescing):
+        """Test a large echo written through many middleware layers, with
+        pause/resume_writing cycling all along, keeps a bounded stack"""
+        layers, factor, total = 60, 4, 4 * 1024 * 1024
+        depths, errors = [], []
+
+        def wrapped():
+            protocol = _Echo(factor, depths, coalescing)
+            for _ in range(layers):
+                protocol = _Layer(protocol)
+            return protocol
+
+        class Client(asyncio.Protocol):
+            def __init__(self, done):
+                self.done = done
+                self.received = 0
+
+            def data_received(self, data):
+                self.received += len(data)
+                if self.received >= total * factor and not self.done.done():
+                    self.done.set_result(self.received)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_exception_handler(lambda lp, ctx: errors.append(ctx))
+            server = await loop.create_server(wrapped, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            done = loop.create_future()
+            transport, _ = await loop.create_connection(
+                lambda: Client(done), '127.0.0.1', port
+            )
+            chunk = b'e' * (64 * 1024)
+            for _ in range(total // len(chunk)):
+                transport.write(chunk)
+            received = await asyncio.wait_for(done, 30)
+            transport.close()
+            server.close()
+            await server.wait_closed()
+            return received
+
+        old_limit = sys.getrecursionlimit()
+        # Roughly what a few nested rounds through the layers would take
+        sys.setrecursionlimit(_depth() + 3 * layers + 100)
+        try:
+            received = asyncio.run(main())
+        finally:
+            sys.setrecursionlimit(old_limit)
+        assert received == total * factor
+        assert errors == [], errors
+        assert len(depths) > 2
+        # Each callback runs straight from the loop, never inside another
+        assert max(depths) - min(depths) <= 5, (min(depths), max(depths))
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,338 @@
+"""Tests for the pluggable async resolver (set_resolver)"""
+
+import asyncio
+import socket
+
+import pytest
+
+import veloxloop
+
+
+class FakeResolver:
+    """Answers from a fixed table and records every lookup"""
+
+    def __init__(self, table=None, error=None):
+        self.table = table or {}
+        self.error = error
+        self.calls = []
+
+    async def resolve(self, host, port, family):
+        self.calls.append((host, port, family))
+        await asyncio.sleep(0)
+        if self.error is not None:
+            raise self.error
+        # Like most DNS resolvers, no socket type or protocol is filled in
+        return [(fam, 0, 0, '', (addr, port)) for fam, addr in self.table.get(host, [])]
+
+
+def _dual_stack(v4='127.0.0.1', v6='::1'):
+    return {'fake.test': [(socket.AF_INET6, v6), (socket.AF_INET, v4)]}
+
+
+class TestResolver:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_getaddrinfo_uses_resolver(self):
+        """Test getaddrinfo awaits the resolver and returns its entries"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            resolver = FakeResolver(_dual_stack())
+            loop.set_resolver(resolver)
+            assert loop.get_resolver() is resolver
+
+            infos = await loop.getaddrinfo('fake.test', 80)
+            assert resolver.calls == [('fake.test', 80, 0)]
+            assert [info[4] for info in infos] == [('::1', 80), ('127.0.0.1', 80)]
+            assert all(len(info) == 5 for info in infos)
+            assert [info[0] for info in infos] == [socket.AF_INET6, socket.AF_INET]
+            assert all(type(info[0]) is socket.AddressFamily for info in infos)
+
+        asyncio.run(main())
+
+    def test_family_filtering(self):
+        """Test entries of another family are dropped from the resolver's answer"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_resolver(FakeResolver(_dual_stack()))
+            infos = await loop.getaddrinfo('fake.test', 80, family=socket.AF_INET)
+            assert [(info[0], info[4]) for info in infos] == [
+                (socket.AF_INET, ('127.0.0.1', 80))
+            ]
+
+        asyncio.run(main())
+
+    def test_numeric_and_passive_bypass(self):
+        """Test literal addresses and AI_PASSIVE lookups never reach the resolver"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            resolver = FakeResolver(_dual_stack())
+            loop.set_resolver(resolver)
+            await loop.getaddrinfo('127.0.0.1', 80)
+            await loop.getaddrinfo('::1', 80)
+            await loop.getaddrinfo(None, 80, flags=socket.AI_PASSIVE)
+            await loop.getaddrinfo('localhost', 80, flags=socket.AI_PASSIVE)
+            assert resolver.calls == []
+
+        asyncio.run(main())
+
+    def test_create_connection_consults_resolver(self):
+        """Test create_connection connects to the address the resolver returns"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            accepted = loop.create_future()
+
+            class Accept(asyncio.Protocol):
+                def connection_made(self, transport):
+                    if not accepted.done():
+                        accepted.set_result(True)
+
+            server = await loop.create_server(Accept, '127.0.0.1', 0)
+            port = server.addresses()[0][1]
+            # Only the IPv4 entry is listening, so family must reach the filter
+            resolver = FakeResolver(_dual_stack(v6='::2'))
+            loop.set_resolver(resolver)
+
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, 'fake.test', port, family=socket.AF_INET
+            )
+            assert resolver.calls == [('fake.test', port, socket.AF_INET)]
+            assert transport.get_extra_info('peername')[:2] == ('127.0.0.1', port)
+            assert await asyncio.wait_for(accepted, 5)
+            transport.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_create_datagram_endpoint_consults_resolver(self):
+        """Test a named remote_addr is resolved through the resolver"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            resolver = FakeResolver(_dual_stack())
+            loop.set_resolver(resolver)
+            transport, _ = await loop.create_datagram_endpoint(
+                asyncio.DatagramProtocol,
+                remote_addr=('fake.test', 9999),
+                family=socket.AF_INET,
+            )
+            assert resolver.calls == [('fake.test', 9999, socket.AF_INET)]
+            assert transport.get_extra_info('peername') == ('127.0.0.1', 9999)
+            transport.close()
+
+        asyncio.run(main())
+
+    def test_datagram_remote_addr_matches_local_family(self):
+        """Test a named remote_addr takes the looked-up address of
+        local_addr's family, not just the first one"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_resolver(FakeResolver(_dual_stack()))
+            transport, _ = await loop.create_datagram_endpoint(
+                asyncio.DatagramProtocol,
+                local_addr=('127.0.0.1', 0),
+                remote_addr=('fake.test', 9999),
+            )
+            assert transport.get_extra_info('peername') == ('127.0.0.1', 9999)
+            transport.close()
+
+            loop.set_resolver(FakeResolver({'fake.test': [(socket.AF_INET6, '::1')]}))
+            with pytest.raises(OSError, match='no matching local address'):
+                await loop.create_datagram_endpoint(
+                    asyncio.DatagramProtocol,
+                    local_addr=('127.0.0.1', 0),
+                    remote_addr=('fake.test', 9999),
+                )
+
+        asyncio.run(main())
+
+    def test_sock_connect_tries_each_address(self):
+        """Test sock_connect moves on when the first looked-up address refuses"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.create_server(('127.0.0.1', 0)) as listener:
+                port = listener.getsockname()[1]
+                # Nothing listens on 127.0.0.2 at this port
+                table = {'fake.test': [(socket.AF_INET, '127.0.0.2'), (socket.AF_INET, '127.0.0.1')]}
+                loop.set_resolver(FakeResolver(table))
+                with socket.socket() as sock:
+                    sock.setblocking(False)
+                    await loop.sock_connect(sock, ('fake.test', port))
+                    assert sock.getpeername() == ('127.0.0.1', port)
+
+                loop.set_resolver(FakeResolver({'fake.test': [(socket.AF_INET, '127.0.0.2')]}))
+                with socket.socket() as sock:
+                    sock.setblocking(False)
+                    with pytest.raises(ConnectionRefusedError):
+                        await loop.sock_connect(sock, ('fake.test', port))
+
+        asyncio.run(main())
+
+    def test_errors_without_fallback(self):
+        """Test resolver errors and empty answers surface when fallback is off"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_resolver(FakeResolver(error=LookupError('boom')))
+            with pytest.raises(LookupError):
+                await loop.getaddrinfo('localhost', 80)
+
+            loop.set_resolver(FakeResolver())
+            with pytest.raises(socket.gaierror):
+                await loop.getaddrinfo('localhost', 80)
+
+        asyncio.run(main())
+
+    def test_fallback_to_libc(self):
+        """Test fallback=True hands failed or empty lookups to libc"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            for resolver in (FakeResolver(error=LookupError('boom')), FakeResolver()):
+                loop.set_resolver(resolver, fallback=True)
+                infos = await loop.getaddrinfo(
+                    'localhost', 80, family=socket.AF_INET, type=socket.SOCK_STREAM
+                )
+                assert resolver.calls == [('localhost', 80, socket.AF_INET)]
+                assert ('127.0.0.1', 80) in [info[4] for info in infos]
+
+        asyncio.run(main())
+
+    def test_none_restores_default(self):
+        """Test set_resolver(None) goes back to libc getaddrinfo"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            resolver = FakeResolver(_dual_stack())
+            loop.set_resolver(resolver)
+            loop.set_resolver(None)
+            assert loop.get_resolver() is None
+            infos = await loop.getaddrinfo('localhost', 80, family=socket.AF_INET)
+            assert infos
+            assert resolver.calls == []
+
+        asyncio.run(main())
+
+    def test_resolver_type_check(self):
+        """Test objects without resolve() are rejected"""
+        loop = veloxloop.new_event_loop()
+        try:
+            with pytest.raises(TypeError):
+                loop.set_resolver(object())
+        finally:
+            loop.close()
+
+
+class TestAddrConfig:
+    """create_connection's AI_ADDRCONFIG handling, on a host pretending to
+    have only IPv4 connectivity"""
+
+    # Routable-looking but unreachable, like an AAAA record on a v4-only host
+    V6 = '2001:db8::1'
+
+    def setup_method(self):
+        veloxloop.install()
+        self.saved = veloxloop._addrconfig_families
+        veloxloop._addrconfig_families = lambda: frozenset({socket.AF_INET})
+
+    def teardown_method(self):
+        veloxloop._addrconfig_families = self.saved
+
+    def test_v6_candidates_skipped(self):
+        """Test the AAAA answer isn't attempted and the A answer connects"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.addresses()[0][1]
+            loop.set_resolver(FakeResolver(_dual_stack(v6=self.V6)))
+            transport, _ = await asyncio.wait_for(
+                loop.create_connection(asyncio.Protocol, 'fake.test', port), 5
+            )
+            assert transport.get_extra_info('peername')[:2] == ('127.0.0.1', port)
+            transport.close()
+            server.close()
+
+            # With nothing listening the only error is the IPv4 one
+            with pytest.raises(ExceptionGroup) as info:
+                await loop.create_connection(
+                    asyncio.Protocol, 'fake.test', port, all_errors=True
+                )
+            assert [type(e) for e in info.value.exceptions] == [ConnectionRefusedError]
+
+        asyncio.run(main())
+
+    def test_lookup_error_retried_only_on_loopback_host(self):
+        """Test a failed AI_ADDRCONFIG lookup is retried without the flag
+        only when no non-loopback address is configured"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            resolver = FakeResolver(error=socket.gaierror(socket.EAI_NONAME, 'no such host'))
+            loop.set_resolver(resolver)
+            with pytest.raises(socket.gaierror):
+                await loop.create_connection(asyncio.Protocol, 'fake.test', 80)
+            assert len(resolver.calls) == 1
+
+            veloxloop._addrconfig_families = frozenset
+            with pytest.raises(socket.gaierror):
+                await loop.create_connection(asyncio.Protocol, 'fake.test', 80)
+            assert len(resolver.calls) == 3
+
+        asyncio.run(main())
+
+    def test_configured_families_cached(self):
+        """Test interfaces aren't listed again for every connection"""
+        first = self.saved()
+        assert self.saved() is first
+        assert not hasattr(veloxloop, 'configured_address_families')
+
+    def test_explicit_flags_are_verbatim(self):
+        """Test flags= turns the filtering off, so the IPv6 answer is tried"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.addresses()[0][1]
+            server.close()
+            loop.set_resolver(FakeResolver(_dual_stack(v6=self.V6)))
+            with pytest.raises(ExceptionGroup) as info:
+                await loop.create_connection(
+                    asyncio.Protocol,
+                    'fake.test',
+                    port,
+                    flags=0,
+                    all_errors=True,
+                    timeout=0.2,
+                )
+            assert len(info.value.exceptions) == 2
+
+        asyncio.run(main())
+
+    def test_only_family_left_is_kept(self):
+        """Test filtering never leaves nothing to try, and local_addr's
+        family goes first"""
+        infos = [
+            (socket.AF_INET6, socket.SOCK_STREAM, 6, '', (self.V6, 80)),
+            (socket.AF_INET, socket.SOCK_STREAM, 6, '', ('127.0.0.1', 80)),
+        ]
+        assert veloxloop._addrconfig_filter(infos[:1]) == infos[:1]
+        assert veloxloop._addrconfig_filter(infos) == infos[1:]
+        assert veloxloop._prefer_family_of(infos, ('0.0.0.0', 0)) == infos[::-1]
+        assert veloxloop._prefer_family_of(infos, ('::', 0)) == infos
+        assert veloxloop._prefer_family_of(infos, ('localhost', 0)) == infos
+
+    def test_configured_families(self):
+        """Test the native check reports only real address families"""
+        families = self.saved()
+        assert families <= {socket.AF_INET, socket.AF_INET6}
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,90 @@
+"""Tests for the host self-check: self_check() and is_io_uring_available()"""
+
+import os
+import platform
+import resource
+import sys
+
+import pytest
+
+import veloxloop
+from veloxloop import VeloxLoop
+from veloxloop._veloxloop import is_io_uring_available, self_check
+
+pytestmark = pytest.mark.skipif(
+    not sys.platform.startswith('linux'), reason='io_uring is Linux only'
+)
+
+
+class TestSelfCheck:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_report_shape(self):
+        """Test the report has every key, without a loop having been created"""
+        report = self_check()
+        assert set(report) == {
+            'kernel',
+            'kernel_version',
+            'io_uring',
+            'io_uring_reason',
+            'io_uring_remediation',
+            'io_uring_disabled',
+            'opcodes',
+            'sqpoll',
+            'sqpoll_reason',
+            'eventfd',
+            'epoll',
+            'nofile',
+        }
+        assert report['eventfd'] is True
+        assert report['epoll'] is True
+        assert (report['sqpoll_reason'] is None) == report['sqpoll']
+
+    def test_kernel_version(self):
+        """Test kernel and kernel_version come from uname"""
+        report = self_check()
+        assert report['kernel'] == platform.release()
+        major, minor, patch = report['kernel_version']
+        assert platform.release().startswith(f'{major}.{minor}')
+
+    def test_nofile_matches_resource(self):
+        """Test nofile is RLIMIT_NOFILE as resource.getrlimit() reports it"""
+        assert self_check()['nofile'] == resource.getrlimit(resource.RLIMIT_NOFILE)
+
+    def test_io_uring_disabled_sysctl(self):
+        """Test io_uring_disabled mirrors the sysctl, None where it doesn't exist"""
+        path = '/proc/sys/kernel/io_uring_disabled'
+        expected = int(open(path).read()) if os.path.exists(path) else None
+        assert self_check()['io_uring_disabled'] == expected
+
+    def test_available_io_uring_matches_loop(self):
+        """Test the probe agrees with what a loop finds"""
+        report = self_check()
+        assert report['io_uring'] is is_io_uring_available() is True
+        assert report['io_uring_reason'] is None
+        assert report['io_uring_remediation'] is None
+        loop = VeloxLoop()
+        try:
+            capabilities = loop.get_backend_capabilities()
+            for name, supported in report['opcodes'].items():
+                assert capabilities[name] == supported
+        finally:
+            loop.close()
+
+    def test_sqpoll_matches_loop(self):
+        """Test sqpoll predicts whether uring_sqpoll=True falls back with a warning"""
+        import warnings
+
+        with warnings.catch_warnings(record=True) as caught:
+            warnings.simplefilter('always')
+            loop = VeloxLoop(uring_sqpoll=True)
+        try:
+            fell_back = any('unavailable' in str(w.message) for w in caught)
+            assert fell_back is not self_check()['sqpoll']
+        finally:
+            loop.close()
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,203 @@
+"""Tests for create_server/start_server binding several hosts"""
+
+import asyncio
+import errno
+import socket
+
+import pytest
+
+import veloxloop
+
+
+def _has_ipv6():
+    try:
+        with socket.socket(socket.AF_INET6, socket.SOCK_STREAM) as s:
+            s.bind(('::1', 0))
+        return True
+    except OSError:
+        return False
+
+
+requires_ipv6 = pytest.mark.skipif(not _has_ipv6(), reason='IPv6 loopback not available')
+
+
+class Accepted:
+    """Records every connection a server hands over, whichever API created it"""
+
+    def __init__(self):
+        self.count = 0
+
+    def protocol(self):
+        accepted = self
+
+        class Counting(asyncio.Protocol):
+            def connection_made(self, transport):
+                accepted.count += 1
+                transport.close()
+
+        return Counting()
+
+    def stream_handler(self, reader, writer):
+        self.count += 1
+        writer.close()
+
+    async def connect(self, host, port):
+        before = self.count
+        _, writer = await asyncio.open_connection(host, port)
+        for _ in range(100):
+            if self.count > before:
+                break
+            await asyncio.sleep(0.01)
+        writer.close()
+        return self.count - before
+
+
+def _stream_noop(reader, writer):
+    writer.close()
+
+
+class TestServerHosts:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('api', ['create_server', 'start_server'])
+    def test_two_ipv4_hosts(self, api):
+        """Test a host list gets one listener per address, each accepting"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            hosts = ['127.0.0.1', '127.0.0.2']
+            accepted = Accepted()
+            if api == 'create_server':
+                server = await loop.create_server(accepted.protocol, hosts, 0)
+            else:
+                server = await loop.start_server(accepted.stream_handler, hosts, 0)
+
+            addresses = server.addresses()
+            assert [a[0] for a in addresses] == hosts
+            assert addresses == [s.getsockname() for s in server.sockets]
+            for host, port in addresses:
+                assert await accepted.connect(host, port) == 1
+
+            server.close()
+            await server.wait_closed()
+            assert server.sockets == []
+
+        asyncio.run(main())
+
+    @requires_ipv6
+    def test_ipv4_and_ipv6(self):
+        """Test a mixed host list reports IPv6 addresses in 4-tuple form"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            accepted = Accepted()
+            server = await loop.create_server(accepted.protocol, ['127.0.0.1', '::1'], 0)
+            v4, v6 = server.addresses()
+            assert len(v4) == 2 and v4[0] == '127.0.0.1'
+            assert len(v6) == 4 and v6[0] == '::1'
+            assert await accepted.connect('127.0.0.1', v4[1]) == 1
+            assert await accepted.connect('::1', v6[1]) == 1
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    @requires_ipv6
+    @pytest.mark.parametrize('api', ['create_server', 'start_server'])
+    def test_dual_stack_wildcards_share_fixed_port(self, api):
+        """Test '0.0.0.0' and '::' bind the same fixed port, the v6 listener
+        being IPv6-only as in asyncio"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.socket() as probe:
+                probe.bind(('127.0.0.1', 0))
+                port = probe.getsockname()[1]
+            accepted = Accepted()
+            hosts = ['0.0.0.0', '::']
+            if api == 'create_server':
+                server = await loop.create_server(accepted.protocol, hosts, port)
+            else:
+                server = await loop.start_server(accepted.stream_handler, hosts, port)
+            assert [a[1] for a in server.addresses()] == [port, port]
+            v6 = server.sockets[1]
+            assert v6.getsockopt(socket.IPPROTO_IPV6, socket.IPV6_V6ONLY) == 1
+            assert await accepted.connect('127.0.0.1', port) == 1
+            assert await accepted.connect('::1', port) == 1
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    def test_duplicate_hosts_bind_once(self):
+        """Test hosts resolving to the same address share one listener"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.socket() as probe:
+                probe.bind(('127.0.0.1', 0))
+                port = probe.getsockname()[1]
+            server = await loop.create_server(
+                asyncio.Protocol, ['127.0.0.1', '127.0.0.1'], port
+            )
+            assert server.addresses() == [('127.0.0.1', port)]
+            server.close()
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('api', ['create_server', 'start_server'])
+    def test_bind_failure_closes_earlier_listeners(self, api):
+        """Test a failed bind releases the addresses already bound"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            busy = socket.socket()
+            busy.bind(('127.0.0.1', 0))
+            busy.listen(1)
+            port = busy.getsockname()[1]
+            try:
+                with pytest.raises(OSError) as info:
+                    if api == 'create_server':
+                        await loop.create_server(asyncio.Protocol, ['127.0.0.2', '127.0.0.1'], port)
+                    else:
+                        await loop.start_server(_stream_noop, ['127.0.0.2', '127.0.0.1'], port)
+                assert info.value.errno == errno.EADDRINUSE
+                assert "('127.0.0.1', %d)" % port in str(info.value)
+
+                # 127.0.0.2 was bound first and must be free again
+                with socket.socket() as again:
+                    again.bind(('127.0.0.2', port))
+                    again.listen(1)
+            finally:
+                busy.close()
+
+        asyncio.run(main())
+
+    def test_single_str_host_unchanged(self):
+        """Test a plain str host still binds one listener"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            assert len(server.sockets) == 1
+            assert server.addresses()[0][0] == '127.0.0.1'
+            server.close()
+
+        asyncio.run(main())
+
+    def test_invalid_host_type(self):
+        """Test hosts that are neither str nor a sequence of str are rejected"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(TypeError):
+                await loop.create_server(asyncio.Protocol, 12345, 0)
+            with pytest.raises(TypeError):
+                await loop.start_server(_stream_noop, ['127.0.0.1', 1], 0)
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
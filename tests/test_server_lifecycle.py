@@ -0,0 +1,201 @@
+"""Tests for driving a server by hand: awaitable start_serving(),
+serve_forever() and close() racing in-flight accepts"""
+
+import asyncio
+import socket
+import threading
+import time
+
+import pytest
+
+import veloxloop
+
+METHODS = ['create_server', 'start_server']
+
+
+async def _server(method, on_accept, **kwargs):
+    """A server of either kind calling `on_accept(server)` synchronously for
+    each accepted connection"""
+    loop = asyncio.get_running_loop()
+    server = None
+    if method == 'create_server':
+
+        def factory():
+            on_accept(server)
+            return asyncio.Protocol()
+
+        server = await loop.create_server(factory, '127.0.0.1', 0, **kwargs)
+    else:
+
+        def on_client(reader, writer):
+            on_accept(server)
+            writer.close()
+
+        server = await loop.start_server(on_client, '127.0.0.1', 0, **kwargs)
+    return server
+
+
+class TestServerLifecycle:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('method', METHODS)
+    def test_start_serving_is_awaitable(self, method):
+        """Test start_serving() registers at once and returns an awaitable"""
+
+        async def main():
+            server = await _server(method, lambda server: None, start_serving=False)
+            assert not server.is_serving()
+            waiter = server.start_serving()
+            assert server.is_serving()
+            assert await waiter is None
+            # Already serving: still awaitable, still a no-op
+            assert await server.start_serving() is None
+            server.close()
+            await server.wait_closed()
+            assert await server.start_serving() is None
+            assert not server.is_serving()
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('method', METHODS)
+    def test_serve_forever_starts_serving(self, method):
+        """Test serve_forever() starts a server made with start_serving=False
+        and returns once it is closed"""
+
+        async def main():
+            accepted = []
+            server = await _server(method, accepted.append, start_serving=False)
+            port = server.sockets[0].getsockname()[1]
+            task = asyncio.ensure_future(server.serve_forever())
+            await asyncio.sleep(0)
+            assert server.is_serving()
+            with socket.create_connection(('127.0.0.1', port)):
+                async with asyncio.timeout(5):
+                    while not accepted:
+                        await asyncio.sleep(0.01)
+            assert not task.done()
+            server.close()
+            async with asyncio.timeout(5):
+                assert await task is None
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('method', METHODS)
+    def test_serve_forever_refusals(self, method):
+        """Test serve_forever() raises while already awaited and once closed"""
+
+        async def main():
+            server = await _server(method, lambda server: None)
+            task = asyncio.ensure_future(server.serve_forever())
+            await asyncio.sleep(0)
+            with pytest.raises(RuntimeError, match='already being awaited'):
+                server.serve_forever()
+            server.close()
+            await task
+            # Closing twice leaves the finished future alone
+            server.close()
+            with pytest.raises(RuntimeError, match='closed'):
+                server.serve_forever()
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('method', METHODS)
+    def test_close_during_accept_batch(self, method):
+        """Test close() from the first accepted connection's callback stops
+        the connections queued behind it from being accepted"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            errors = []
+            loop.set_exception_handler(lambda loop, context: errors.append(context))
+            accepted = []
+
+            def on_accept(server):
+                accepted.append(None)
+                server.close()
+
+            server = await _server(method, on_accept, start_serving=False)
+            port = server.sockets[0].getsockname()[1]
+            # Completed by the kernel, so all four are in the backlog at once
+            clients = [socket.create_connection(('127.0.0.1', port)) for _ in range(4)]
+            try:
+                await server.start_serving()
+                await asyncio.sleep(0.05)
+                assert accepted == [None]
+                assert not server.is_serving()
+                assert errors == []
+            finally:
+                for client in clients:
+                    client.close()
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('method', METHODS)
+    def test_signal_shutdown_under_connect_load(self, method):
+        """Test the uvicorn shutdown sequence: start_serving=False, awaited
+        start_serving() and serve_forever(), then a SIGTERM handler calling
+        close() while clients keep connecting. Nothing is accepted after the
+        close and nothing is reported to the exception handler"""
+        stop = threading.Event()
+        attempts = [0]
+
+        def connect_forever(port):
+            while not stop.is_set():
+                try:
+                    with socket.create_connection(('127.0.0.1', port), timeout=1) as sock:
+                        sock.sendall(b'GET / HTTP/1.1\r\n\r\n')
+                except OSError:
+                    pass
+                attempts[0] += 1
+                time.sleep(0.001)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            errors = []
+            loop.set_exception_handler(lambda loop, context: errors.append(context))
+            shutting_down = [False]
+            accepted = []
+            stray = []
+
+            def on_accept(server):
+                (stray if shutting_down[0] else accepted).append(None)
+
+            server = await _server(method, on_accept, start_serving=False)
+            port = server.sockets[0].getsockname()[1]
+            await server.start_serving()
+
+            def handle_exit():
+                shutting_down[0] = True
+                server.close()
+
+            threads = [threading.Thread(target=connect_forever, args=(port,)) for _ in range(4)]
+            for thread in threads:
+                thread.start()
+            try:
+                async with asyncio.timeout(5):
+                    while len(accepted) < 20:
+                        await asyncio.sleep(0.005)
+                # As a signal handler would run it: a plain callback on the loop
+                loop.call_soon(handle_exit)
+                async with asyncio.timeout(5):
+                    assert await server.serve_forever() is None
+                await server.wait_closed()
+                # Clients still knocking must find the listener gone
+                seen = attempts[0]
+                async with asyncio.timeout(5):
+                    while attempts[0] < seen + 20:
+                        await asyncio.sleep(0.005)
+            finally:
+                stop.set()
+                for thread in threads:
+                    thread.join()
+            assert stray == []
+            assert errors == []
+            assert not server.is_serving()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,79 @@
+"""Tests for loop.sock_connect() reporting the connect outcome"""
+
+import asyncio
+import errno
+import socket
+
+import pytest
+
+import veloxloop
+from tests.helpers import blackhole, closed_port
+
+
+class TestSockConnect:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_connect_succeeds(self):
+        """Test a connect to a listener resolves with None"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.create_server(('127.0.0.1', 0)) as listener:
+                with socket.socket() as sock:
+                    sock.setblocking(False)
+                    addr = listener.getsockname()
+                    registered = loop.get_fd_usage()[0]
+                    assert await loop.sock_connect(sock, addr) is None
+                    assert sock.getpeername() == addr
+                    # Its writer is off the socket once it resolves
+                    assert loop.get_fd_usage()[0] == registered
+
+        asyncio.run(main())
+
+    def test_refused_raises(self):
+        """Test a closed port raises ConnectionRefusedError from the await"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            port = closed_port()
+            with socket.socket() as sock:
+                sock.setblocking(False)
+                registered = loop.get_fd_usage()[0]
+                with pytest.raises(ConnectionRefusedError) as info:
+                    await loop.sock_connect(sock, ('127.0.0.1', port))
+                assert info.value.errno == errno.ECONNREFUSED
+                assert f"('127.0.0.1', {port})" in str(info.value)
+                assert loop.get_fd_usage()[0] == registered
+
+        asyncio.run(main())
+
+    def test_unanswered_fails_within_timeout(self):
+        """Test a connect nobody answers times out rather than resolving"""
+        listener, fillers, port = blackhole()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with socket.socket() as sock:
+                sock.setblocking(False)
+                registered = loop.get_fd_usage()[0]
+                start = loop.time()
+                connect = asyncio.ensure_future(loop.sock_connect(sock, ('127.0.0.1', port)))
+                await asyncio.sleep(0.01)
+                assert loop.get_fd_usage()[0] == registered + 1
+                with pytest.raises(TimeoutError):
+                    await asyncio.wait_for(connect, 0.2)
+                assert loop.time() - start < 0.5
+                # Cancelling the connect took the writer off the socket
+                assert loop.get_fd_usage()[0] == registered
+
+        try:
+            asyncio.run(main())
+        finally:
+            for sock in fillers:
+                sock.close()
+            listener.close()
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,173 @@
+"""Tests for sock_recv()/sock_sendall() waits that get cancelled: their poll is
+parked for a retry on the same socket, and never eats data or starves another
+handler on the fd"""
+
+import asyncio
+import time
+
+import pytest
+
+import veloxloop
+from tests.helpers import socket_pair
+
+
+async def _timed_out_recv(sock, timeout=0.002):
+    loop = asyncio.get_running_loop()
+    with pytest.raises(asyncio.TimeoutError):
+        await asyncio.wait_for(loop.sock_recv(sock, 100), timeout)
+
+
+class TestSockOpReuse:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_cancelled_recv_leaves_data(self):
+        """Test data arriving after a sock_recv timed out goes to the next one"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            await _timed_out_recv(a, 0.05)
+            b.send(b'hello')
+            await asyncio.sleep(0.05)
+            assert await asyncio.wait_for(loop.sock_recv(a, 100), 1) == b'hello'
+            a.close()
+            b.close()
+
+        asyncio.run(main())
+
+    def test_timeout_loop_reuses_poll(self):
+        """Test retrying a timed out sock_recv takes over the parked poll
+        instead of cancelling it and submitting another"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            before = loop.get_stats()
+            for _ in range(20):
+                await _timed_out_recv(a)
+            b.send(b'done')
+            assert await asyncio.wait_for(loop.sock_recv(a, 100), 1) == b'done'
+            after = loop.get_stats()
+            a.close()
+            b.close()
+            return before, after
+
+        before, after = asyncio.run(main())
+        assert after['sock_op_reuses'] - before['sock_op_reuses'] >= 15
+        assert after['poller_registers'] - before['poller_registers'] <= 5
+        assert after['poller_deletes'] - before['poller_deletes'] <= 5
+
+    def test_remove_reader_drops_parked_poll(self):
+        """Test a user reader added after a cancelled sock_recv is the one
+        called, and remove_reader leaves nothing behind"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            registered = loop.get_fd_usage()[0]
+            await _timed_out_recv(a)
+            # The parked poll is no handler of the fd's
+            assert loop.get_fd_usage()[0] == registered
+            ready = asyncio.Event()
+            loop.add_reader(a, ready.set)
+            assert loop.get_fd_usage()[0] == registered + 1
+            b.send(b'x')
+            await asyncio.wait_for(ready.wait(), 1)
+            assert loop.remove_reader(a)
+            assert loop.get_fd_usage()[0] == registered
+            assert a.recv(10) == b'x'
+            a.close()
+            b.close()
+
+        asyncio.run(main())
+
+    def test_closed_socket_and_reused_fd(self):
+        """Test closing a socket with a parked poll reaches the peer, and a
+        new socket given the same fd number waits on its own file"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            await _timed_out_recv(a)
+            fd = a.fileno()
+            a.close()
+            c, d = socket_pair()
+            assert fd in (c.fileno(), d.fileno())
+            reader, peer = (c, d) if c.fileno() == fd else (d, c)
+            # The old peer sees EOF once the parked poll expires
+            deadline = time.monotonic() + 2
+            while True:
+                try:
+                    assert b.recv(10) == b''
+                    break
+                except BlockingIOError:
+                    assert time.monotonic() < deadline
+                    await asyncio.sleep(0.01)
+            waiting = asyncio.ensure_future(loop.sock_recv(reader, 100))
+            await asyncio.sleep(0.01)
+            peer.send(b'new')
+            assert await asyncio.wait_for(waiting, 1) == b'new'
+            for sock in (b, c, d):
+                sock.close()
+
+        asyncio.run(main())
+
+    def test_writer_not_starved_by_recv(self):
+        """Test a writer on the fd still runs while a sock_recv waits on it"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            writable = asyncio.Event()
+            loop.add_writer(a, writable.set)
+            waiting = asyncio.ensure_future(loop.sock_recv(a, 100))
+            await asyncio.sleep(0.01)
+            await asyncio.wait_for(writable.wait(), 1)
+            assert loop.remove_writer(a)
+            b.send(b'x')
+            assert await asyncio.wait_for(waiting, 1) == b'x'
+            a.close()
+            b.close()
+
+        asyncio.run(main())
+
+    def test_cancelled_sendall_then_retry(self):
+        """Test sock_sendall cancelled on a full socket, then retried once the
+        peer drains, sends the retried data whole"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            big = b'x' * (8 * 1024 * 1024)
+            with pytest.raises(asyncio.TimeoutError):
+                await asyncio.wait_for(loop.sock_sendall(a, big), 0.01)
+            received = 0
+            while True:
+                try:
+                    chunk = b.recv(1 << 20)
+                except BlockingIOError:
+                    break
+                assert set(chunk) == {ord('x')}
+                received += len(chunk)
+            assert 0 < received < len(big)
+
+            async def drain():
+                data = b''
+                while not data.endswith(b'end'):
+                    data += await loop.sock_recv(b, 1 << 20)
+                return data
+
+            reader = asyncio.ensure_future(drain())
+            await loop.sock_sendall(a, b'y' * 100_000 + b'end')
+            data = await asyncio.wait_for(reader, 5)
+            assert data.endswith(b'y' * 100_000 + b'end')
+            assert set(data[:-100_003]) <= {ord('x')}
+            a.close()
+            b.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
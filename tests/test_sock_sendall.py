@@ -0,0 +1,113 @@
+"""Tests for sock_sendall with large and zero-copy buffers"""
+
+import asyncio
+
+import pytest
+
+import veloxloop
+from tests.helpers import socket_pair
+
+
+class TestSockSendall:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_buffer_types(self):
+        """Test bytes, bytearray and memoryview slices are all accepted"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            payload = bytearray(range(256)) * 16
+            await loop.sock_sendall(a, bytes(payload[:10]))
+            await loop.sock_sendall(a, payload)
+            await loop.sock_sendall(a, memoryview(payload)[100:200])
+            expected = bytes(payload[:10]) + bytes(payload) + bytes(payload[100:200])
+
+            received = b''
+            while len(received) < len(expected):
+                received += await loop.sock_recv(b, 65536)
+            assert received == expected
+
+            with pytest.raises(BufferError):
+                await loop.sock_sendall(a, memoryview(payload)[::2])
+            with pytest.raises(TypeError):
+                await loop.sock_sendall(a, 'text')
+            a.close()
+            b.close()
+
+        asyncio.run(main())
+
+    def test_large_payload_keeps_loop_responsive(self):
+        """Test a 100 MB bytearray to a slow reader doesn't stall timers"""
+        size = 100 * 1024 * 1024
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            payload = bytearray(size)
+            payload[-4:] = b'tail'
+
+            gaps = []
+            running = True
+
+            async def ticker():
+                last = loop.time()
+                while running:
+                    await asyncio.sleep(0.005)
+                    now = loop.time()
+                    gaps.append(now - last)
+                    last = now
+
+            async def slow_reader():
+                total = 0
+                tail = b''
+                while total < size:
+                    chunk = await loop.sock_recv(b, 256 * 1024)
+                    total += len(chunk)
+                    tail = (tail + chunk)[-4:]
+                    await asyncio.sleep(0)
+                return total, tail
+
+            tick = asyncio.ensure_future(ticker())
+            reader = asyncio.ensure_future(slow_reader())
+            await asyncio.wait_for(loop.sock_sendall(a, payload), 60)
+            total, tail = await asyncio.wait_for(reader, 60)
+            running = False
+            await tick
+
+            assert total == size
+            assert tail == b'tail'
+            assert gaps
+            # 5 ms timer; without the per-iteration budget a single sendall
+            # callback could hold the loop for the whole transfer
+            assert max(gaps) < 0.25, max(gaps)
+            a.close()
+            b.close()
+
+        asyncio.run(main())
+
+    def test_cancel_removes_writer(self):
+        """Test cancelling a blocked sendall drops its writer callback"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            registered = loop.get_fd_usage()[0]
+            task = asyncio.ensure_future(loop.sock_sendall(a, bytearray(16 * 1024 * 1024)))
+            await asyncio.sleep(0.05)
+            assert not task.done()
+            assert loop.get_fd_usage()[0] == registered + 1
+
+            task.cancel()
+            with pytest.raises(asyncio.CancelledError):
+                await task
+            assert loop.get_fd_usage()[0] == registered
+            a.close()
+            b.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,161 @@
+"""Tests for loop.sock_sendfile()"""
+
+import asyncio
+import hashlib
+import io
+import os
+import socket
+import tempfile
+
+import pytest
+
+import veloxloop
+from tests.helpers import socket_pair
+
+
+def _temp_file(data):
+    f = tempfile.TemporaryFile()
+    f.write(data)
+    f.seek(0)
+    return f
+
+
+async def _drain(sock, nbytes):
+    """Read exactly `nbytes` (or until EOF) and return them"""
+    loop = asyncio.get_running_loop()
+    chunks = []
+    received = 0
+    while received < nbytes:
+        data = await loop.sock_recv(sock, 1 << 20)
+        if not data:
+            break
+        chunks.append(data)
+        received += len(data)
+    return b''.join(chunks)
+
+
+class TestSockSendfile:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_large_file_over_socketpair(self):
+        """Test a 20 MB file arrives whole and the total is returned"""
+        payload = os.urandom(20 * 1024 * 1024)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            with a, b, _temp_file(payload) as f:
+                receiver = asyncio.create_task(_drain(b, len(payload)))
+                sent = await loop.sock_sendfile(a, f)
+                received = await asyncio.wait_for(receiver, 30)
+                assert sent == len(payload)
+                assert f.tell() == len(payload)
+            return received
+
+        received = asyncio.run(main())
+        assert hashlib.sha256(received).digest() == hashlib.sha256(payload).digest()
+
+    def test_offset_and_count(self):
+        """Test offset/count select the range, independent of the file position"""
+        payload = bytes(range(256)) * 100
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            with a, b, _temp_file(payload) as f:
+                f.seek(123)
+                sent = await loop.sock_sendfile(a, f, 1000, 5000)
+                assert sent == 5000
+                assert f.tell() == 6000
+                assert await _drain(b, 5000) == payload[1000:6000]
+
+                # count past the end stops at EOF
+                sent = await loop.sock_sendfile(a, f, len(payload) - 10, 100)
+                assert sent == 10
+                assert await _drain(b, 10) == payload[-10:]
+
+        asyncio.run(main())
+
+    def test_empty_file(self):
+        """Test a zero-length file resolves with 0 and sends nothing"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            with a, b, _temp_file(b'') as f:
+                assert await loop.sock_sendfile(a, f) == 0
+                assert f.tell() == 0
+                with pytest.raises(BlockingIOError):
+                    b.recv(1)
+
+        asyncio.run(main())
+
+    def test_fallback_for_non_regular_file(self):
+        """Test BytesIO raises SendfileNotAvailableError or falls back to sends"""
+        payload = b'in memory' * 1000
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            with a, b:
+                with pytest.raises(asyncio.SendfileNotAvailableError):
+                    await loop.sock_sendfile(a, io.BytesIO(payload), fallback=False)
+
+                f = io.BytesIO(payload)
+                sent = await loop.sock_sendfile(a, f, 9, 90)
+                assert sent == 90
+                assert f.tell() == 99
+                assert await _drain(b, 90) == payload[9:99]
+
+        asyncio.run(main())
+
+    def test_argument_checks(self):
+        """Test asyncio's validation of the socket, offset and count"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with _temp_file(b'data') as f:
+                with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as udp:
+                    udp.setblocking(False)
+                    with pytest.raises(ValueError, match='SOCK_STREAM'):
+                        await loop.sock_sendfile(udp, f)
+                a, b = socket.socketpair()
+                with a, b:
+                    with pytest.raises(ValueError, match='non-blocking'):
+                        await loop.sock_sendfile(a, f)
+                    a.setblocking(False)
+                    with pytest.raises(ValueError):
+                        await loop.sock_sendfile(a, f, count=0)
+                    with pytest.raises(ValueError):
+                        await loop.sock_sendfile(a, f, offset=-1)
+                    with pytest.raises(TypeError):
+                        await loop.sock_sendfile(a, f, offset=1.5)
+
+        asyncio.run(main())
+
+    def test_cancel_stops_sending(self):
+        """Test cancelling a blocked sendfile deregisters its writer"""
+        payload = b'x' * (8 * 1024 * 1024)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            a, b = socket_pair()
+            with a, b, _temp_file(payload) as f:
+                task = asyncio.create_task(loop.sock_sendfile(a, f))
+                await asyncio.sleep(0.05)
+                # Nobody reads, so the send is parked on writability
+                assert not task.done()
+                task.cancel()
+                with pytest.raises(asyncio.CancelledError):
+                    await task
+
+                # The fd is free for a user writer again
+                loop.add_writer(a, lambda: None)
+                assert loop.remove_writer(a) is True
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -1,6 +1,7 @@
 """Test socket options functionality."""
 
 import asyncio
+import os
 import socket
 
 import pytest
@@ -421,6 +422,202 @@ class TestSocketOptions:
         assert opts.get_reuse_address() is None
 
 
+KEEPALIVE = {'idle': 60, 'interval': 10, 'count': 3}
+
+
+class Accepting(SimpleProtocol):
+    """Hands the server side transport to the test"""
+
+    def __init__(self, accepted):
+        super().__init__()
+        self.accepted = accepted
+
+    def connection_made(self, transport):
+        super().connection_made(transport)
+        self.accepted.set_result(transport)
+
+
+@pytest.mark.skipif(
+    not hasattr(socket, 'TCP_KEEPINTVL'),
+    reason='keep-alive parameters not supported on this platform',
+)
+class TestKeepaliveParams:
+    """Test the composite keep-alive setter and the keepalive= kwarg."""
+
+    def setup_method(self):
+        """Setup VeloxLoop for each test"""
+        veloxloop.install()
+
+    def test_set_keepalive_params(self):
+        """Test set_keepalive_params enables probing and reads back."""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(SimpleProtocol, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(SimpleProtocol, '127.0.0.1', port)
+
+            assert transport.get_keepalive_params()['enabled'] is False
+            transport.set_keepalive_params(idle=60, interval=10, count=3)
+            assert transport.get_keepalive_params() == {'enabled': True, **KEEPALIVE}
+
+            # Only the given parameter changes
+            transport.set_keepalive_params(count=5)
+            assert transport.get_keepalive_params() == {
+                'enabled': True,
+                **KEEPALIVE,
+                'count': 5,
+            }
+
+            transport.close()
+            server.close()
+
+        asyncio.run(run_test())
+
+    @pytest.mark.parametrize('keepalive', [True, KEEPALIVE])
+    def test_create_server_keepalive(self, keepalive):
+        """Test create_server(keepalive=...) applies to accepted connections."""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            accepted = loop.create_future()
+            server = await loop.create_server(
+                lambda: Accepting(accepted), '127.0.0.1', 0, keepalive=keepalive
+            )
+            port = server.sockets[0].getsockname()[1]
+            client, _ = await loop.create_connection(SimpleProtocol, '127.0.0.1', port)
+            transport = await asyncio.wait_for(accepted, 5)
+
+            params = transport.get_keepalive_params()
+            assert params['enabled'] is True
+            if keepalive is not True:
+                assert params == {'enabled': True, **KEEPALIVE}
+            # The client did not ask for it
+            assert client.get_keepalive_params()['enabled'] is False
+
+            client.close()
+            server.close()
+
+        asyncio.run(run_test())
+
+    @pytest.mark.skipif(not os.path.isdir('/proc/self/fd'), reason='needs /proc to find the server socket')
+    def test_start_server_keepalive(self):
+        """Test start_server(keepalive=...) applies to accepted connections."""
+
+        def accepted_socket(client_addr):
+            # The native StreamWriter does not expose its socket, so find the
+            # server end of the connection among this process's fds
+            for name in os.listdir('/proc/self/fd'):
+                try:
+                    sock = socket.socket(fileno=os.dup(int(name)))
+                except OSError:
+                    continue
+                try:
+                    if sock.type == socket.SOCK_STREAM and sock.getpeername() == client_addr:
+                        return sock
+                except OSError:
+                    pass
+                sock.close()
+            raise AssertionError('server socket not found')
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            accepted = loop.create_future()
+            server = await loop.start_server(
+                lambda reader, writer: accepted.set_result(None),
+                '127.0.0.1',
+                0,
+                keepalive=KEEPALIVE,
+            )
+            port = server.sockets[0].getsockname()[1]
+            client, _ = await loop.create_connection(SimpleProtocol, '127.0.0.1', port)
+            await asyncio.wait_for(accepted, 5)
+
+            with accepted_socket(client.get_extra_info('sockname')) as sock:
+                assert sock.getsockopt(socket.SOL_SOCKET, socket.SO_KEEPALIVE) == 1
+                assert sock.getsockopt(socket.IPPROTO_TCP, socket.TCP_KEEPIDLE) == 60
+                assert sock.getsockopt(socket.IPPROTO_TCP, socket.TCP_KEEPINTVL) == 10
+                assert sock.getsockopt(socket.IPPROTO_TCP, socket.TCP_KEEPCNT) == 3
+                # Set with the server's other options right after accept
+                assert sock.getsockopt(socket.IPPROTO_TCP, socket.TCP_NODELAY) == 1
+
+            client.close()
+            server.close()
+
+        asyncio.run(run_test())
+
+    def test_create_connection_keepalive(self):
+        """Test create_connection(keepalive=...) applies to the client socket."""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(SimpleProtocol, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(
+                SimpleProtocol, '127.0.0.1', port, keepalive=KEEPALIVE
+            )
+            assert transport.get_keepalive_params() == {'enabled': True, **KEEPALIVE}
+            transport.close()
+            server.close()
+
+        asyncio.run(run_test())
+
+    @pytest.mark.parametrize('api', ['create_server', 'start_server'])
+    def test_keepalive_failure_drops_only_that_connection(self, api):
+        """Test a keepalive setting the kernel refuses is reported per
+        connection, which is closed, while the rest of the batch is accepted"""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            contexts = []
+            loop.set_exception_handler(lambda lp, ctx: contexts.append(ctx))
+            # TCP_KEEPCNT above 127 is EINVAL, for every accepted socket
+            keepalive = {'count': 1000}
+            if api == 'create_server':
+                server = await loop.create_server(
+                    SimpleProtocol, '127.0.0.1', 0, keepalive=keepalive
+                )
+            else:
+                server = await loop.start_server(
+                    lambda reader, writer: writer.close(),
+                    '127.0.0.1',
+                    0,
+                    keepalive=keepalive,
+                )
+            port = server.sockets[0].getsockname()[1]
+            clients = []
+            for _ in range(3):
+                client = socket.create_connection(('127.0.0.1', port))
+                client.setblocking(False)
+                clients.append(client)
+            try:
+                for client in clients:
+                    # Closed by the server rather than left hanging
+                    data = await asyncio.wait_for(loop.sock_recv(client, 1), 5)
+                    assert data == b''
+            finally:
+                for client in clients:
+                    client.close()
+            assert len(contexts) == 3, contexts
+            assert all(isinstance(c['exception'], OSError) for c in contexts)
+            assert server.is_serving()
+            server.close()
+
+        asyncio.run(run_test())
+
+    def test_invalid_keepalive(self):
+        """Test keepalive must be a bool or a dict of known parameters."""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(ValueError):
+                await loop.create_server(SimpleProtocol, '127.0.0.1', 0, keepalive={'idel': 5})
+            with pytest.raises(TypeError):
+                await loop.create_server(SimpleProtocol, '127.0.0.1', 0, keepalive='on')
+
+        asyncio.run(run_test())
+
+
 class TestSocketOptionsWithContext:
     """Test socket options with async context manager."""
 
@@ -0,0 +1,236 @@
+"""Tests for transport.splice_to() kernel-side proxying"""
+
+import asyncio
+import hashlib
+import random
+import socket
+import struct
+import threading
+
+import pytest
+
+import veloxloop
+
+CHUNK = 1024 * 1024
+
+
+class Collector:
+    """Plain-socket server that keeps (or hashes) everything one client sends"""
+
+    def __init__(self, keep=True):
+        self.sock = socket.create_server(('127.0.0.1', 0))
+        self.port = self.sock.getsockname()[1]
+        self.keep = keep
+        self.data = bytearray()
+        self.digest = hashlib.sha256()
+        self.total = 0
+        self.thread = threading.Thread(target=self._run, daemon=True)
+        self.thread.start()
+
+    def _run(self):
+        conn, _ = self.sock.accept()
+        buf = bytearray(CHUNK)
+        with conn:
+            while n := conn.recv_into(buf):
+                self.total += n
+                self.digest.update(memoryview(buf)[:n])
+                if self.keep:
+                    self.data += buf[:n]
+        self.sock.close()
+
+    def join(self):
+        self.thread.join(30)
+        assert not self.thread.is_alive()
+
+
+def _send_chunks(port, chunks, digest):
+    """Send 1 MB chunks, each stamped with its index so loss or reordering shows"""
+    base = random.Random(0).randbytes(CHUNK)
+    with socket.create_connection(('127.0.0.1', port)) as sock:
+        for i in range(chunks):
+            chunk = struct.pack('>Q', i) + base[8:]
+            digest.update(chunk)
+            sock.sendall(chunk)
+
+
+class Front(asyncio.Protocol):
+    """Server side of the proxy: splices into `upstream` as soon as it connects"""
+
+    def __init__(self, upstream, accepted, count=None):
+        self.upstream = upstream
+        self.accepted = accepted
+        self.count = count
+        self.received = []
+        self.eof = False
+
+    def connection_made(self, transport):
+        self.transport = transport
+        # Started before the first read so no data can slip past the splice
+        self.spliced = transport.splice_to(self.upstream, self.count)
+        self.accepted.set_result(self)
+
+    def data_received(self, data):
+        self.received.append(data)
+
+    def eof_received(self):
+        self.eof = True
+
+
+async def _proxy(loop, collector, count=None):
+    upstream, _ = await loop.create_connection(
+        asyncio.Protocol, '127.0.0.1', collector.port
+    )
+    accepted = loop.create_future()
+    server = await loop.create_server(
+        lambda: Front(upstream, accepted, count), '127.0.0.1', 0
+    )
+    return upstream, accepted, server, server.sockets[0].getsockname()[1]
+
+
+class TestSplice:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_proxy_one_gigabyte(self):
+        """Test 1 GB goes through intact with no data_received calls on the way"""
+        chunks = 1024
+        sent = hashlib.sha256()
+        collector = Collector(keep=False)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            upstream, accepted, server, port = await _proxy(loop, collector)
+            sender = threading.Thread(target=_send_chunks, args=(port, chunks, sent))
+            sender.start()
+            front = await accepted
+
+            moved = await asyncio.wait_for(front.spliced, 120)
+            await loop.run_in_executor(None, sender.join)
+            assert moved == chunks * CHUNK
+            assert front.received == []
+            upstream.close()
+            front.transport.close()
+            server.close()
+
+        asyncio.run(main())
+        collector.join()
+        assert collector.total == chunks * CHUNK
+        assert collector.digest.hexdigest() == sent.hexdigest()
+
+    def test_count_then_normal_dispatch(self):
+        """Test the splice stops after count bytes and both transports resume"""
+        collector = Collector()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            upstream, accepted, server, port = await _proxy(loop, collector, 4096)
+            client = socket.create_connection(('127.0.0.1', port))
+            front = await accepted
+            # Held back while the splice owns the upstream socket
+            upstream.write(b'queued;')
+            client.sendall(b'x' * 4096 + b'tail')
+
+            assert await asyncio.wait_for(front.spliced, 5) == 4096
+            for _ in range(100):
+                if front.received:
+                    break
+                await asyncio.sleep(0.01)
+            assert b''.join(front.received) == b'tail'
+
+            upstream.write(b'after')
+            upstream.close()
+            client.close()
+            front.transport.close()
+            server.close()
+
+        asyncio.run(main())
+        collector.join()
+        assert bytes(collector.data) == b'x' * 4096 + b'queued;after'
+
+    def test_close_cancels(self):
+        """Test closing the sink cancels the splice and gives reading back"""
+        collector = Collector()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            upstream, accepted, server, port = await _proxy(loop, collector)
+            client = socket.create_connection(('127.0.0.1', port))
+            front = await accepted
+            await asyncio.sleep(0.01)
+
+            upstream.close()
+            assert front.spliced.cancelled()
+            client.sendall(b'direct')
+            for _ in range(100):
+                if front.received:
+                    break
+                await asyncio.sleep(0.01)
+            assert b''.join(front.received) == b'direct'
+            client.close()
+            front.transport.close()
+            server.close()
+
+        asyncio.run(main())
+        collector.join()
+
+    def test_invalid_targets(self):
+        """Test splicing to itself, to a busy sink or to a non-transport fails"""
+        collector = Collector()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            upstream, accepted, server, port = await _proxy(loop, collector)
+            client = socket.create_connection(('127.0.0.1', port))
+            front = await accepted
+
+            with pytest.raises(ValueError):
+                upstream.splice_to(upstream)
+            with pytest.raises(TypeError):
+                upstream.splice_to(object())
+            with pytest.raises(RuntimeError):
+                front.transport.splice_to(upstream)
+            with pytest.raises(RuntimeError):
+                upstream.write_eof()
+
+            client.close()
+            assert await asyncio.wait_for(front.spliced, 5) == 0
+            upstream.close()
+            front.transport.close()
+            server.close()
+
+        asyncio.run(main())
+        collector.join()
+
+    def test_stream_transport(self):
+        """Test splice_to between the streams-API transports"""
+        collector = Collector()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            _, upstream = await loop.open_connection('127.0.0.1', collector.port)
+            spliced = loop.create_future()
+
+            async def handler(reader, writer):
+                spliced.set_result(
+                    await writer.transport.splice_to(upstream.transport)
+                )
+                writer.write(b'done')
+
+            server = await loop.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            with socket.create_connection(('127.0.0.1', port)) as client:
+                client.sendall(b'stream' * 1000)
+                client.shutdown(socket.SHUT_WR)
+                assert await asyncio.wait_for(spliced, 5) == 6000
+                client.settimeout(5)
+                assert await loop.run_in_executor(None, client.recv, 16) == b'done'
+            upstream.transport.close()
+            server.close()
+
+        asyncio.run(main())
+        collector.join()
+        assert bytes(collector.data) == b'stream' * 1000
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -2,7 +2,11 @@
 
 import asyncio
 import os
+import resource
+import socket
+import ssl
 import subprocess
+import threading
 from pathlib import Path
 
 import pytest
@@ -42,6 +46,10 @@ if not (Path(SERVER_CERT).exists() and Path(SERVER_KEY).exists()):
                 SERVER_CERT,
                 '-subj',
                 '/CN=localhost',
+                '-addext',
+                'subjectAltName=DNS:localhost,IP:127.0.0.1',
+                '-addext',
+                'basicConstraints=critical,CA:FALSE',
             ]
         )
     except Exception:
@@ -468,5 +476,480 @@ class TestSSLContextConfiguration:
             ctx.load_cert_chain('/nonexistent/cert.pem', '/nonexistent/key.pem')
 
 
+class TestSSLSessionResumption:
+    """Test TLS session resumption against a local stdlib TLS echo server"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def _start_echo_server(self, connections):
+        """Serve `connections` TLS echo sessions; returns (port, reused flags, thread)"""
+        server_ctx = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        server_ctx.load_cert_chain(SERVER_CERT, SERVER_KEY)
+        listener = socket.socket(socket.AF_INET, socket.SOCK_STREAM)
+        listener.bind(('127.0.0.1', 0))
+        listener.listen(connections)
+        port = listener.getsockname()[1]
+        reused = []
+
+        def serve():
+            with listener:
+                for _ in range(connections):
+                    conn, _ = listener.accept()
+                    with server_ctx.wrap_socket(conn, server_side=True) as tls:
+                        data = tls.recv(1024)
+                        tls.sendall(data)
+                        reused.append(tls.session_reused)
+
+        thread = threading.Thread(target=serve, daemon=True)
+        thread.start()
+        return port, reused, thread
+
+    async def _echo_once(self, ssl_context, port):
+        loop = asyncio.get_running_loop()
+        done = loop.create_future()
+        info = {}
+
+        class EchoClient(asyncio.Protocol):
+            def connection_made(self, transport):
+                self.transport = transport
+                transport.write(b'ping')
+
+            def data_received(self, data):
+                info['data'] = bytes(data)
+                for key in ('session_reused', 'cipher', 'compression', 'peercert'):
+                    info[key] = self.transport.get_extra_info(key)
+                self.transport.close()
+                if not done.done():
+                    done.set_result(None)
+
+        await loop.create_connection(
+            EchoClient, '127.0.0.1', port, ssl=ssl_context, server_hostname='localhost'
+        )
+        await asyncio.wait_for(done, timeout=5.0)
+        return info
+
+    def test_second_connection_resumes_session(self):
+        """Reconnecting to the same host:port reuses the TLS session"""
+        port, server_reused, thread = self._start_echo_server(2)
+
+        async def run_test():
+            ssl_context = _veloxloop.SSLContext.create_client_context()
+            ssl_context.load_verify_locations(cafile=SERVER_CERT)
+            first = await self._echo_once(ssl_context, port)
+            second = await self._echo_once(ssl_context, port)
+            return first, second
+
+        first, second = asyncio.run(run_test())
+        thread.join(timeout=5.0)
+
+        assert first['data'] == b'ping'
+        assert second['data'] == b'ping'
+        assert first['session_reused'] is False
+        assert second['session_reused'] is True
+        # The server saw the abbreviated handshake too
+        assert server_reused == [False, True]
+
+        name, protocol, bits = second['cipher']
+        assert name.startswith('TLS_')
+        assert protocol.startswith('TLSv1.')
+        # Bits of the negotiated suite's key, not a guess from its name
+        assert bits == (128 if 'AES_128' in name else 256)
+        assert second['compression'] is None
+        assert isinstance(second['peercert'], bytes)
+
+    def test_session_cache_disabled(self):
+        """With the session cache disabled every connection is a full handshake"""
+        port, server_reused, thread = self._start_echo_server(2)
+
+        async def run_test():
+            ssl_context = _veloxloop.SSLContext.create_client_context()
+            ssl_context.load_verify_locations(cafile=SERVER_CERT)
+            ssl_context.set_session_cache(False)
+            first = await self._echo_once(ssl_context, port)
+            second = await self._echo_once(ssl_context, port)
+            return first, second
+
+        first, second = asyncio.run(run_test())
+        thread.join(timeout=5.0)
+
+        assert first['session_reused'] is False
+        assert second['session_reused'] is False
+        assert server_reused == [False, False]
+
+    def test_session_cache_size_validation(self):
+        ctx = _veloxloop.SSLContext.create_client_context()
+        with pytest.raises(ValueError):
+            ctx.set_session_cache(True, 0)
+        ctx.set_session_cache(True, 16)
+
+    def test_server_session_ticket_knobs(self):
+        ctx = _veloxloop.SSLContext.create_server_context()
+        with pytest.raises(ValueError):
+            ctx.rotate_session_ticket_key()
+        ctx.load_cert_chain(SERVER_CERT, SERVER_KEY)
+        ctx.rotate_session_ticket_key()
+        ctx.set_session_tickets(False)
+        ctx.set_session_tickets(True)
+
+
+class TestSSLConnectionLost:
+    """connection_lost ordering on SSL transports, against a local stdlib TLS server"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_close_and_abort_notify_once(self):
+        """close(), abort() and close() again deliver a single connection_lost(None)"""
+        server_ctx = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        server_ctx.load_cert_chain(SERVER_CERT, SERVER_KEY)
+        listener = socket.socket(socket.AF_INET, socket.SOCK_STREAM)
+        listener.bind(('127.0.0.1', 0))
+        listener.listen(1)
+        port = listener.getsockname()[1]
+
+        def serve():
+            with listener:
+                conn, _ = listener.accept()
+                with server_ctx.wrap_socket(conn, server_side=True) as tls:
+                    tls.sendall(tls.recv(1024))
+                    try:
+                        tls.recv(1024)
+                    except (OSError, ssl.SSLError):
+                        pass
+
+        thread = threading.Thread(target=serve, daemon=True)
+        thread.start()
+        lost = []
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            done = loop.create_future()
+            ssl_context = _veloxloop.SSLContext.create_client_context()
+            ssl_context.load_verify_locations(cafile=SERVER_CERT)
+
+            class Client(asyncio.Protocol):
+                def connection_made(self, transport):
+                    self.transport = transport
+                    transport.write(b'ping')
+
+                def data_received(self, data):
+                    self.transport.close()
+                    self.transport.abort()
+                    self.transport.close()
+                    if not done.done():
+                        done.set_result(None)
+
+                def connection_lost(self, exc):
+                    lost.append(exc)
+
+            await loop.create_connection(
+                Client, '127.0.0.1', port, ssl=ssl_context, server_hostname='localhost'
+            )
+            await asyncio.wait_for(done, timeout=5.0)
+            await asyncio.sleep(0.05)
+
+        asyncio.run(run_test())
+        thread.join(timeout=5.0)
+        assert lost == [None]
+
+
+class TestSSLHandshakeTimeout:
+    """create_connection waits for the TLS handshake, bounded by ssl_handshake_timeout"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def _client_context(self):
+        ssl_context = _veloxloop.SSLContext.create_client_context()
+        ssl_context.load_verify_locations(cafile=SERVER_CERT)
+        return ssl_context
+
+    def test_silent_peer_times_out(self):
+        """A TCP peer that never speaks TLS fails the connect with asyncio's error"""
+        listener = socket.create_server(('127.0.0.1', 0))
+        port = listener.getsockname()[1]
+        made = []
+
+        class Client(asyncio.Protocol):
+            def connection_made(self, transport):
+                made.append(transport)
+
+            def connection_lost(self, exc):
+                made.append(exc)
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            start = loop.time()
+            with pytest.raises(ConnectionAbortedError) as info:
+                await loop.create_connection(
+                    Client,
+                    '127.0.0.1',
+                    port,
+                    ssl=self._client_context(),
+                    server_hostname='localhost',
+                    ssl_handshake_timeout=0.2,
+                )
+            assert loop.time() - start < 0.3
+            assert str(info.value) == (
+                'SSL handshake is taking longer than 0.2 seconds: '
+                'aborting the connection'
+            )
+            await asyncio.sleep(0.05)
+
+        try:
+            asyncio.run(run_test())
+        finally:
+            listener.close()
+        # The protocol never saw the connection
+        assert made == []
+
+    def test_waits_for_handshake(self):
+        """The future resolves after connection_made, with the handshake done"""
+        server_ctx = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        server_ctx.load_cert_chain(SERVER_CERT, SERVER_KEY)
+        listener = socket.create_server(('127.0.0.1', 0))
+        port = listener.getsockname()[1]
+
+        def serve():
+            with listener:
+                conn, _ = listener.accept()
+                with server_ctx.wrap_socket(conn, server_side=True) as tls:
+                    tls.sendall(tls.recv(1024))
+
+        thread = threading.Thread(target=serve, daemon=True)
+        thread.start()
+        made = []
+
+        class Client(asyncio.Protocol):
+            def connection_made(self, transport):
+                made.append(transport)
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            transport, protocol = await loop.create_connection(
+                Client,
+                '127.0.0.1',
+                port,
+                ssl=self._client_context(),
+                server_hostname='localhost',
+                ssl_handshake_timeout=5,
+            )
+            assert made == [transport]
+            assert isinstance(protocol, Client)
+            assert transport.get_extra_info('cipher') is not None
+            transport.close()
+
+        asyncio.run(run_test())
+        thread.join(timeout=5.0)
+
+    def test_timeout_validation(self):
+        """ssl_handshake_timeout needs ssl and a positive value"""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(ValueError):
+                await loop.create_connection(
+                    asyncio.Protocol, '127.0.0.1', 1, ssl_handshake_timeout=1
+                )
+            with pytest.raises(ValueError):
+                await loop.create_connection(
+                    asyncio.Protocol,
+                    '127.0.0.1',
+                    1,
+                    ssl=self._client_context(),
+                    server_hostname='localhost',
+                    ssl_handshake_timeout=0,
+                )
+
+        asyncio.run(run_test())
+
+
+class TestSSLWriteThroughput:
+    """Bulk writes over TLS to a local stdlib TLS sink"""
+
+    TOTAL = 500 * 1024 * 1024
+    CHUNK = 64 * 1024
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def _start_sink(self):
+        """Count every plaintext byte one TLS connection sends until it closes"""
+        server_ctx = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        server_ctx.load_cert_chain(SERVER_CERT, SERVER_KEY)
+        listener = socket.create_server(('127.0.0.1', 0))
+        port = listener.getsockname()[1]
+        received = []
+
+        def serve():
+            with listener:
+                conn, _ = listener.accept()
+                with server_ctx.wrap_socket(conn, server_side=True) as tls:
+                    total = 0
+                    buf = bytearray(1 << 20)
+                    while True:
+                        try:
+                            n = tls.recv_into(buf)
+                        except (OSError, ssl.SSLError):
+                            break
+                        if not n:
+                            break
+                        total += n
+                    received.append(total)
+
+        thread = threading.Thread(target=serve, daemon=True)
+        thread.start()
+        return port, received, thread
+
+    def test_bulk_write_is_flow_controlled(self):
+        """500 MB through write() arrives whole, with bounded buffering and RSS"""
+        port, received, thread = self._start_sink()
+        peaks = []
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            ssl_context = _veloxloop.SSLContext.create_client_context()
+            ssl_context.load_verify_locations(cafile=SERVER_CERT)
+            lost = loop.create_future()
+
+            class Source(asyncio.Protocol):
+                paused = None
+                pauses = 0
+
+                def pause_writing(self):
+                    self.pauses += 1
+                    self.paused = loop.create_future()
+
+                def resume_writing(self):
+                    self.paused.set_result(None)
+                    self.paused = None
+
+                def connection_lost(self, exc):
+                    lost.set_result(exc)
+
+            transport, protocol = await loop.create_connection(
+                Source, '127.0.0.1', port, ssl=ssl_context, server_hostname='localhost'
+            )
+            high = transport.get_write_buffer_limits()[1]
+            rss_before = resource.getrusage(resource.RUSAGE_SELF).ru_maxrss
+            chunk = b'x' * self.CHUNK
+            for _ in range(self.TOTAL // self.CHUNK):
+                transport.write(chunk)
+                # Plaintext and records waiting for the socket both count
+                peaks.append(transport.get_write_buffer_size())
+                if protocol.paused is not None:
+                    await protocol.paused
+            rss_after = resource.getrusage(resource.RUSAGE_SELF).ru_maxrss
+
+            # close() waits for everything buffered to reach the socket
+            transport.close()
+            assert await asyncio.wait_for(lost, 30) is None
+            return protocol.pauses, high, rss_after - rss_before
+
+        pauses, high, rss_growth_kib = asyncio.run(run_test())
+        thread.join(timeout=30.0)
+
+        assert received == [self.TOTAL]
+        assert pauses > 0
+        assert max(peaks) <= high + 2 * self.CHUNK
+        assert rss_growth_kib < 64 * 1024
+
+
+
+class TestSSLConnectRegistration:
+    """The connect callback hands the fd to the TLS transport's reader and
+    writer within one dispatch; the handshake must never wait on a poll that
+    was cancelled along the way"""
+
+    ROUNDS = 200
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def _start_echo_server(self):
+        """TLS echo server, a thread per connection; returns (port, stop)"""
+        server_ctx = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        server_ctx.load_cert_chain(SERVER_CERT, SERVER_KEY)
+        listener = socket.create_server(('127.0.0.1', 0), backlog=64)
+        port = listener.getsockname()[1]
+
+        def echo(conn):
+            try:
+                with server_ctx.wrap_socket(conn, server_side=True) as tls:
+                    while data := tls.recv(1024):
+                        tls.sendall(data)
+            except (OSError, ssl.SSLError):
+                pass
+
+        def serve():
+            while True:
+                try:
+                    conn, _ = listener.accept()
+                except OSError:
+                    return
+                threading.Thread(target=echo, args=(conn,), daemon=True).start()
+
+        threading.Thread(target=serve, daemon=True).start()
+        return port, listener.close
+
+    async def _echo(self, ssl_context, port, payload):
+        loop = asyncio.get_running_loop()
+        received = loop.create_future()
+
+        class Client(asyncio.Protocol):
+            def connection_made(self, transport):
+                transport.write(payload)
+
+            def data_received(self, data):
+                if not received.done():
+                    received.set_result(bytes(data))
+
+        transport, _ = await asyncio.wait_for(
+            loop.create_connection(
+                Client, '127.0.0.1', port, ssl=ssl_context, server_hostname='localhost'
+            ),
+            timeout=5.0,
+        )
+        try:
+            return await asyncio.wait_for(received, timeout=5.0)
+        finally:
+            transport.close()
+
+    def test_sequential_handshakes_never_stall(self):
+        """Test 200 connects in a row, each alone on the loop"""
+        port, stop = self._start_echo_server()
+
+        async def run_test():
+            ssl_context = _veloxloop.SSLContext.create_client_context()
+            ssl_context.load_verify_locations(cafile=SERVER_CERT)
+            for i in range(self.ROUNDS):
+                payload = b'ping %d' % i
+                assert await self._echo(ssl_context, port, payload) == payload
+
+        try:
+            asyncio.run(run_test())
+        finally:
+            stop()
+
+    def test_concurrent_handshakes_never_stall(self):
+        """Test connects completing in the same tick as other ready fds"""
+        port, stop = self._start_echo_server()
+
+        async def run_test():
+            ssl_context = _veloxloop.SSLContext.create_client_context()
+            ssl_context.load_verify_locations(cafile=SERVER_CERT)
+            for round_ in range(self.ROUNDS // 20):
+                payloads = [b'ping %d.%d' % (round_, i) for i in range(20)]
+                echoed = await asyncio.gather(
+                    *(self._echo(ssl_context, port, p) for p in payloads)
+                )
+                assert echoed == payloads
+
+        try:
+            asyncio.run(run_test())
+        finally:
+            stop()
+
 if __name__ == '__main__':
     pytest.main([__file__, '-v'])
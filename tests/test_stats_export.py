@@ -0,0 +1,149 @@
+"""Tests for the shared-memory stats export read from other processes"""
+
+import asyncio
+import json
+import os
+import subprocess
+import sys
+import tempfile
+import textwrap
+from pathlib import Path
+
+import pytest
+
+import veloxloop
+
+# Reads the record `count` times as fast as it can, checking the counters
+# never go backwards, and prints the last one
+READER = textwrap.dedent(
+    """
+    import json, sys
+    from veloxloop import VeloxLoop
+
+    path, count = sys.argv[1], int(sys.argv[2])
+    last = None
+    for _ in range(count):
+        record = VeloxLoop.read_stats_file(path)
+        if last is not None:
+            for name in ('updated_ns', 'iterations', 'events', 'bytes_in', 'bytes_out'):
+                assert record[name] >= last[name], (name, last, record)
+        last = record
+    print(json.dumps(last))
+    """
+)
+
+
+def _reader(path, count=1):
+    package_root = os.path.dirname(os.path.dirname(veloxloop.__file__))
+    env = dict(os.environ, PYTHONPATH=package_root)
+    return subprocess.Popen(
+        [sys.executable, '-c', READER, str(path), str(count)],
+        env=env,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.PIPE,
+        text=True,
+    )
+
+
+def _finish(reader):
+    out, err = reader.communicate(timeout=60)
+    assert reader.returncode == 0, err
+    return json.loads(out)
+
+
+class TestStatsExport:
+    def setup_method(self):
+        veloxloop.install()
+        self.tmp = tempfile.TemporaryDirectory()
+        self.dir = Path(self.tmp.name)
+
+    def teardown_method(self):
+        self.tmp.cleanup()
+
+    def test_counters_seen_by_another_process(self):
+        """Test the loop's counters, traffic included, reach a reader process"""
+        path = self.dir / 'worker-0.stats'
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.enable_stats_export(path)
+
+            async def echo(reader, writer):
+                writer.write(await reader.readexactly(1000))
+                await writer.drain()
+                await reader.read()
+                writer.close()
+
+            server = await asyncio.start_server(echo, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await asyncio.open_connection('127.0.0.1', port)
+            writer.write(b'x' * 1000)
+            await reader.readexactly(1000)
+            pending = [loop.call_later(60, lambda: None) for _ in range(3)]
+            await asyncio.sleep(0.01)
+            during = _finish(await asyncio.to_thread(_reader, path))
+            writer.close()
+            await writer.wait_closed()
+            server.close()
+            await server.wait_closed()
+            for handle in pending:
+                handle.cancel()
+            return during
+
+        during = asyncio.run(main())
+        assert during['pid'] == os.getpid()
+        assert during['iterations'] > 0
+        assert during['events'] > 0
+        # Both ends of the connection are this loop's transports
+        assert during['bytes_in'] >= 2000
+        assert during['bytes_out'] >= 2000
+        assert during['active_connections'] == 2
+        assert during['timers'] >= 3
+        after = veloxloop.VeloxLoop.read_stats_file(path)
+        assert after['active_connections'] == 0
+        assert after['iterations'] > during['iterations']
+
+    def test_rapid_updates_read_consistently(self):
+        """Test a reader hammering the file while the loop spins reads a
+        consistent, increasing record every time"""
+        path = self.dir / 'busy.stats'
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.enable_stats_export(path)
+            reader = _reader(path, 20000)
+            deadline = loop.time() + 30
+            while reader.poll() is None and loop.time() < deadline:
+                await asyncio.sleep(0)
+            return _finish(reader), loop
+
+        last, _ = asyncio.run(main())
+        assert last['iterations'] > 0
+
+    def test_export_moves_and_keeps_counts(self):
+        """Test enabling again switches files and carries the counts over"""
+        loop = veloxloop.VeloxLoop()
+        try:
+            loop.enable_stats_export(self.dir / 'first')
+            loop.run_until_complete(asyncio.sleep(0.01))
+            first = veloxloop.VeloxLoop.read_stats_file(self.dir / 'first')
+            loop.enable_stats_export(self.dir / 'second')
+            second = veloxloop.VeloxLoop.read_stats_file(self.dir / 'second')
+            assert second['iterations'] == first['iterations'] > 0
+            loop.run_until_complete(asyncio.sleep(0))
+            assert veloxloop.VeloxLoop.read_stats_file(self.dir / 'first') == first
+        finally:
+            loop.close()
+
+    def test_not_a_stats_file(self):
+        """Test reading a missing or foreign file raises OSError"""
+        with pytest.raises(FileNotFoundError):
+            veloxloop.VeloxLoop.read_stats_file(self.dir / 'missing')
+        other = self.dir / 'other'
+        other.write_bytes(b'\0' * 4096)
+        with pytest.raises(OSError, match='not a veloxloop stats file'):
+            veloxloop.VeloxLoop.read_stats_file(other)
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
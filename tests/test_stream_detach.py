@@ -0,0 +1,240 @@
+"""Tests for StreamTransport.detach(): handing a stream connection's socket
+over mid-connection, e.g. to upgrade it to TLS (STARTTLS)"""
+
+import asyncio
+import concurrent.futures
+import inspect
+import ssl
+import subprocess
+import tempfile
+from pathlib import Path
+
+import pytest
+
+import veloxloop
+from veloxloop import _veloxloop
+
+
+def _make_cert(directory):
+    cert, key = str(directory / 'cert.pem'), str(directory / 'key.pem')
+    try:
+        subprocess.run(
+            [
+                'openssl',
+                'req',
+                '-x509',
+                '-nodes',
+                '-newkey',
+                'rsa:2048',
+                '-days',
+                '1',
+                '-keyout',
+                key,
+                '-out',
+                cert,
+                '-subj',
+                '/CN=localhost',
+                '-addext',
+                'subjectAltName=DNS:localhost,IP:127.0.0.1',
+                '-addext',
+                'basicConstraints=critical,CA:FALSE',
+            ],
+            check=True,
+            capture_output=True,
+        )
+    except (OSError, subprocess.CalledProcessError):
+        pytest.skip('openssl is not available')
+    return cert, key
+
+
+async def _result(value):
+    """The native stream reads return the data itself when it's buffered"""
+    return await value if inspect.isawaitable(value) else value
+
+
+def _tls_echo(sock, context):
+    """Blocking TLS echo of one line over a detached socket"""
+    sock.setblocking(True)
+    with context.wrap_socket(sock, server_side=True) as tls:
+        data = b''
+        while not data.endswith(b'\n'):
+            chunk = tls.recv(1024)
+            if not chunk:
+                break
+            data += chunk
+        tls.sendall(data.upper())
+
+
+class TestStreamDetach:
+    def setup_method(self):
+        veloxloop.install()
+        self.tmp = tempfile.TemporaryDirectory()
+        self.dir = Path(self.tmp.name)
+        # Both ends block in a TLS handshake with each other at once
+        self.pool = concurrent.futures.ThreadPoolExecutor(2)
+
+    def teardown_method(self):
+        self.pool.shutdown()
+        self.tmp.cleanup()
+
+    def _contexts(self):
+        cert, key = _make_cert(self.dir)
+        server = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        server.load_cert_chain(cert, key)
+        client = ssl.SSLContext(ssl.PROTOCOL_TLS_CLIENT)
+        client.load_verify_locations(cert)
+        return server, client
+
+    def test_starttls_with_ssl_module(self):
+        """Test both ends detach after a plaintext STARTTLS exchange and talk
+        TLS over the raw sockets with Python's ssl module"""
+        server_ctx, client_ctx = self._contexts()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            handled = loop.create_future()
+
+            async def handle(reader, writer):
+                assert await _result(reader.readline()) == b'STARTTLS\n'
+                writer.write(b'OK\n')
+                sock = await writer.transport.detach()
+                await loop.run_in_executor(self.pool, _tls_echo, sock, server_ctx)
+                handled.set_result(None)
+
+            server = await loop.start_server(handle, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            writer.write(b'STARTTLS\n')
+            assert await _result(reader.readline()) == b'OK\n'
+            sock = await writer.transport.detach()
+            assert writer.is_closing()
+            assert reader.read() == b''
+            assert reader.at_eof()
+
+            def talk():
+                sock.setblocking(True)
+                with client_ctx.wrap_socket(sock, server_hostname='localhost') as tls:
+                    tls.sendall(b'secret\n')
+                    return tls.recv(1024)
+
+            assert await loop.run_in_executor(self.pool, talk) == b'SECRET\n'
+            await asyncio.wait_for(handled, 5)
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    def test_starttls_back_on_the_loop(self):
+        """Test a detached socket upgraded with asyncio.open_connection(ssl=)
+        keeps exchanging data through the loop"""
+        server_ctx, _ = self._contexts()
+        client_ctx = _veloxloop.SSLContext.create_client_context()
+        client_ctx.load_verify_locations(cafile=str(self.dir / 'cert.pem'))
+
+        async def main():
+            loop = asyncio.get_running_loop()
+
+            async def handle(reader, writer):
+                await _result(reader.readline())
+                writer.write(b'OK\n')
+                sock = await writer.transport.detach()
+                await loop.run_in_executor(self.pool, _tls_echo, sock, server_ctx)
+
+            server = await loop.start_server(handle, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            writer.write(b'STARTTLS\n')
+            await _result(reader.readline())
+            sock = await writer.transport.detach()
+            reader, writer = await asyncio.open_connection(
+                sock=sock, ssl=client_ctx, server_hostname='localhost'
+            )
+            writer.write(b'over tls\n')
+            line = await asyncio.wait_for(_result(reader.readline()), 5)
+            assert line == b'OVER TLS\n'
+            writer.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    def test_buffered_data_and_pending_writes(self):
+        """Test detaching keeps bytes the reader already has readable, sends
+        every pending write first, and the socket carries on from there"""
+        big = b'w' * (4 * 1024 * 1024)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            received = loop.create_future()
+
+            async def handle(reader, writer):
+                writer.write(b'early bytes')
+                data = await _result(reader.readexactly(len(big)))
+                rest = await _result(reader.readline())
+                received.set_result((data, rest))
+                writer.write(b'after\n')
+                await _result(reader.readline())
+                writer.close()
+
+            server = await loop.start_server(handle, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            await asyncio.sleep(0.05)
+            writer.write(big)
+            assert writer.transport.get_write_buffer_size() > 0
+            sock = await asyncio.wait_for(writer.transport.detach(), 10)
+            assert reader.read() == b'early bytes'
+            assert reader.at_eof()
+            with pytest.raises(RuntimeError):
+                writer.write(b'late')
+
+            reader2, writer2 = await loop.wrap_socket_streams(sock)
+            assert sock.fileno() == -1
+            writer2.write(b'continued\n')
+            data, rest = await asyncio.wait_for(received, 10)
+            assert data == big
+            assert rest == b'continued\n'
+            assert await _result(reader2.readline()) == b'after\n'
+            writer2.close()
+            await writer2.wait_closed()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    def test_detach_errors(self):
+        """Test detaching twice or after close raises, and a connection lost
+        during the flush fails the detach"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+
+            async def handle(reader, writer):
+                await _result(reader.readline())
+                writer.close()
+
+            server = await loop.start_server(handle, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            writer.close()
+            with pytest.raises(RuntimeError):
+                writer.transport.detach()
+
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            writer.write(b'x' * (16 * 1024 * 1024))
+            waiter = writer.transport.detach()
+            with pytest.raises(RuntimeError):
+                writer.transport.detach()
+            writer.transport.abort()
+            with pytest.raises(ConnectionAbortedError):
+                await waiter
+            await writer.wait_closed()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -3,11 +3,34 @@ Pytest test cases for StreamReader and StreamWriter
 Pure Rust implementation - zero Python function calls
 """
 
+import asyncio
+import gc
+import inspect
+import socket
+import sys
+import threading
+import time
+
 import pytest
 
+import veloxloop
 import veloxloop._veloxloop as _veloxloop
 
 
+async def _result(value):
+    """The native stream reads return the data itself when it's buffered"""
+    return await value if inspect.isawaitable(value) else value
+
+
+async def _read_to_eof(reader):
+    """The native StreamReader.read() only returns what is buffered"""
+    data = b''
+    while not reader.at_eof():
+        data += reader.read()
+        await asyncio.sleep(0.001)
+    return data
+
+
 class TestStreamReader:
     """Test cases for StreamReader"""
 
@@ -45,6 +68,18 @@ class TestStreamReader:
         reader.feed_data(b'')
         assert reader.buffer_size() == 0
 
+    def test_feed_data_buffer_protocol(self):
+        """Test feed_data takes any contiguous bytes-like object"""
+        reader = _veloxloop.StreamReader()
+        reader.feed_data(bytearray(b'ab'))
+        reader.feed_data(memoryview(b'xcdx')[1:3])
+        assert reader.read() == b'abcd'
+
+        with pytest.raises(TypeError):
+            reader.feed_data('text')
+        with pytest.raises(BufferError):
+            reader.feed_data(memoryview(b'abcd')[::2])
+
     def test_feed_eof(self):
         """Test EOF signal"""
         reader = _veloxloop.StreamReader()
@@ -115,6 +150,102 @@ class TestStreamReader:
         with pytest.raises(ValueError, match='Not enough data'):
             reader.readexactly(10)
 
+    def test_readexactly_pending_at_eof(self):
+        """Test a readexactly waiting when EOF arrives fails the same way,
+        while an earlier waiter that EOF leaves satisfied still gets its data"""
+        reader = _veloxloop.StreamReader()
+        first = reader.readexactly(2)
+        second = reader.readexactly(10)
+        reader.feed_data(b'Hi there')
+        reader.feed_eof()
+        assert first.result() == b'Hi'
+        with pytest.raises(ValueError, match='Not enough data'):
+            second.result()
+
+    def test_read_nowait(self):
+        """Test read_nowait hands out buffered bytes as a memoryview"""
+        reader = _veloxloop.StreamReader()
+        view = reader.read_nowait()
+        assert isinstance(view, memoryview) and len(view) == 0
+        reader.feed_data(b'Hello, World!')
+        view = reader.read_nowait(5)
+        assert isinstance(view, memoryview)
+        assert view == b'Hello'
+        assert reader.read_nowait() == b', World!'
+        assert reader.buffer_size() == 0
+
+        reader.set_exception('broken')
+        with pytest.raises(RuntimeError, match='broken'):
+            reader.read_nowait()
+        # Unlike read(), the exception stays
+        assert reader.exception() == 'broken'
+
+    def test_readexactly_into_buffered(self):
+        """Test readexactly_into fills the buffer at once when the data is there"""
+        reader = _veloxloop.StreamReader()
+        reader.feed_data(b'0123456789')
+        target = bytearray(4)
+        assert reader.readexactly_into(target) == 4
+        assert target == b'0123'
+        view = memoryview(bytearray(10))
+        assert reader.readexactly_into(view[2:8]) == 6
+        assert view[2:8] == b'456789'
+        with pytest.raises(TypeError):
+            reader.readexactly_into(b'read-only')
+
+    def test_readexactly_into_pending(self):
+        """Test a pending readexactly_into fills across feeds and resolves with the count"""
+        reader = _veloxloop.StreamReader()
+        reader.feed_data(b'abc')
+        target = bytearray(8)
+        future = reader.readexactly_into(target)
+        assert not future.done()
+        assert target[:3] == b'abc'
+        reader.feed_data(b'defgh-rest')
+        assert future.result() == 8
+        assert target == b'abcdefgh'
+        assert reader.read() == b'-rest'
+
+    def test_readexactly_into_eof(self):
+        """Test EOF mid-fill raises IncompleteReadError with a memoryview partial"""
+        reader = _veloxloop.StreamReader()
+        target = bytearray(8)
+        future = reader.readexactly_into(target)
+        reader.feed_data(b'abc')
+        reader.feed_eof()
+        with pytest.raises(asyncio.IncompleteReadError) as info:
+            future.result()
+        assert isinstance(info.value.partial, memoryview)
+        assert info.value.partial == b'abc'
+        assert info.value.expected == 8
+
+        with pytest.raises(asyncio.IncompleteReadError):
+            reader.readexactly_into(bytearray(1))
+
+    def test_readexactly_into_exception(self):
+        """Test a reader error mid-fill carries the filled part as .partial"""
+        reader = _veloxloop.StreamReader()
+        target = bytearray(8)
+        future = reader.readexactly_into(target)
+        reader.feed_data(b'ab')
+        reader.set_exception('reset')
+        reader.feed_eof()
+        with pytest.raises(RuntimeError, match='reset') as info:
+            future.result()
+        assert info.value.partial == b'ab'
+
+    def test_cancelled_waiter_takes_no_data(self):
+        """Test a readexactly() waiter cancelled between two others is skipped
+        without consuming bytes, and the others are settled in order"""
+        reader = _veloxloop.StreamReader()
+        first, middle, last = (reader.readexactly(3) for _ in range(3))
+        assert middle.cancel()
+        reader.feed_data(b'abcdef')
+        assert first.result() == b'abc'
+        assert last.result() == b'def'
+        assert middle.cancelled()
+        assert reader.read() == b''
+
     def test_readline_simple(self):
         """Test reading a single line"""
         reader = _veloxloop.StreamReader()
@@ -192,6 +323,115 @@ class TestStreamReader:
         item2 = reader.readuntil(b'||')
         assert item2 == b'world||'
 
+    def test_readuntil_tuple_earliest_match(self):
+        """Test a tuple of separators returns data up to whichever comes first"""
+        reader = _veloxloop.StreamReader()
+        reader.feed_data(b'one\ntwo\r\nthree;four')
+
+        seps = (b'\r\n', b'\n', b';')
+        assert reader.readuntil(seps) == b'one\n'
+        assert reader.readuntil_with_separator(seps) == (b'two\r\n', b'\r\n')
+        assert reader.readuntil_with_separator(seps) == (b'three;', b';')
+
+        # As in asyncio, the match that ends first wins
+        assert reader.read() == b'four'
+        reader.feed_data(b'abcd')
+        assert reader.readuntil_with_separator((b'abcd', b'c')) == (b'abc', b'c')
+
+    def test_readuntil_tuple_interleaved_feeds(self):
+        """Test a pending tuple readuntil wakes on the separator that arrives first"""
+        reader = _veloxloop.StreamReader()
+        future = reader.readuntil_with_separator((b'END', b'|'))
+        reader.feed_data(b'head EN')
+        assert not future.done()
+        # The later-listed separator completes before b'END' does
+        reader.feed_data(b'x|tail END')
+        assert future.result() == (b'head ENx|', b'|')
+        assert reader.readuntil((b'END', b'|')) == b'tail END'
+
+        reader.feed_data(b'rest')
+        reader.feed_eof()
+        assert reader.readuntil_with_separator((b'END', b'|')) == (b'rest', None)
+
+    @pytest.mark.parametrize(
+        'separator', [b'\n', b'\r\n\r\n', (b'\r\n\r\n', b'\n\n')], ids=repr
+    )
+    def test_readuntil_separator_split_across_feeds(self, separator):
+        """Test a separator split across two feeds is found at every split
+        point, by a pending waiter and by a retried immediate readuntil()"""
+        sep = separator[0] if isinstance(separator, tuple) else separator
+        # Near misses ahead of the separator must not match or hide it
+        head = b'GET / HTTP/1.1\r\nHost: x\r\n\r'
+        if sep == b'\n':
+            head = head.replace(b'\n', b'')
+        message = head + sep + b'body'
+        end = len(head) + len(sep)
+        for split in range(1, len(message)):
+            reader = _veloxloop.StreamReader()
+            future = reader.readuntil(separator)
+            reader.feed_data(message[:split])
+            reader.feed_data(message[split:])
+            assert future.result() == message[:end], split
+
+            reader = _veloxloop.StreamReader()
+            reader.feed_data(message[:split])
+            result = reader.readuntil(separator)
+            if split < end:
+                result.cancel()
+                reader.feed_data(message[split:])
+                result = reader.readuntil(separator)
+            else:
+                reader.feed_data(message[split:])
+            assert result == message[:end], split
+            assert reader.read() == b'body'
+
+    def test_readuntil_resumes_after_front_consumed(self):
+        """Test a retried readuntil() still finds the separator after read()
+        took bytes off the front since the last search"""
+        reader = _veloxloop.StreamReader()
+        reader.feed_data(b'abc\r\n\r')
+        reader.readuntil(b'\r\n\r\n').cancel()
+        assert reader.read(2) == b'ab'
+        reader.feed_data(b'\nrest')
+        assert reader.readuntil(b'\r\n\r\n') == b'c\r\n\r\n'
+
+        future = reader.readline()
+        assert reader.read(2) == b're'
+        reader.feed_data(b'\n')
+        assert future.result() == b'st\n'
+
+    def test_readuntil_trickled_body_is_linear(self):
+        """Test a body fed a byte at a time ahead of the separator costs time
+        linear in its size rather than a rescan of the buffer per feed"""
+
+        def parse(size):
+            reader = _veloxloop.StreamReader(limit=2 * size)
+            future = reader.readuntil(b'\r\n\r\n')
+            start = time.perf_counter()
+            for _ in range(size):
+                reader.feed_data(b'x')
+            reader.feed_data(b'\r\n\r\n')
+            elapsed = time.perf_counter() - start
+            assert len(future.result()) == size + 4
+            return elapsed
+
+        small = parse(1 << 20)
+        large = parse(4 << 20)
+        # A rescan per feed would take hours at 4 MB and 16x the 1 MB time
+        assert large < 60
+        assert large < 10 * small + 1
+
+    def test_readuntil_tuple_validation(self):
+        """Test empty tuples and empty separators inside a tuple are rejected"""
+        reader = _veloxloop.StreamReader()
+        reader.feed_data(b'data')
+        with pytest.raises(ValueError, match='at least one element'):
+            reader.readuntil(())
+        with pytest.raises(ValueError, match='Separator cannot be empty'):
+            reader.readuntil((b'\n', b''))
+        with pytest.raises(TypeError):
+            reader.readuntil('\n')
+
     def test_exception_set_and_get(self):
         """Test setting and getting exception"""
         reader = _veloxloop.StreamReader()
@@ -239,8 +479,8 @@ class TestStreamWriter:
         writer = _veloxloop.StreamWriter()
         assert not writer.is_closing()
         assert writer.get_write_buffer_size() == 0
-        assert writer.get_high_water() == 128 * 1024  # 128 KB (DEFAULT_HIGH)
-        assert writer.get_low_water() == 32 * 1024  # 32 KB (DEFAULT_LOW)
+        assert writer.get_high_water() == 64 * 1024  # 64 KiB (DEFAULT_HIGH)
+        assert writer.get_low_water() == 16 * 1024  # 16 KiB (DEFAULT_LOW)
 
     def test_creation_custom_limits(self):
         """Test StreamWriter creation with custom limits"""
@@ -315,6 +555,73 @@ class TestStreamWriter:
         with pytest.raises(RuntimeError):
             writer.writelines([b'line'])
 
+    def test_writelines_buffer_protocol(self):
+        """Test writelines takes any iterable of bytes-like objects"""
+        writer = _veloxloop.StreamWriter()
+        data = b'0123456789'
+        writer.writelines(memoryview(data)[i : i + 2] for i in range(0, 10, 2))
+        writer.writelines([bytearray(b'ab'), b'cd', memoryview(b'ef')])
+        assert writer._clear_buffer() == data + b'abcdef'
+
+        with pytest.raises(TypeError):
+            writer.writelines([b'ok', 'not bytes'])
+        with pytest.raises(BufferError):
+            writer.writelines([memoryview(b'abcd')[::2]])
+        # A failed batch leaves nothing behind
+        assert writer.get_write_buffer_size() == 0
+
+    def test_write_buffer_protocol(self):
+        """Test write takes any contiguous bytes-like object"""
+        writer = _veloxloop.StreamWriter()
+        writer.write(bytearray(b'ab'))
+        writer.write(memoryview(b'xcdx')[1:3])
+        assert writer._clear_buffer() == b'abcd'
+        with pytest.raises(BufferError):
+            writer.write(memoryview(b'abcd')[::2])
+
+    def test_transport_delegation(self):
+        """Test transport, get_extra_info and close go through the attached transport"""
+
+        class Probe:
+            closed = False
+
+            def get_extra_info(self, name, default=None):
+                return ('127.0.0.1', 1) if name == 'peername' else default
+
+            def close(self):
+                self.closed = True
+
+        writer = _veloxloop.StreamWriter()
+        assert writer.transport is None
+        assert writer.get_extra_info('peername') is None
+        assert writer.get_extra_info('peername', 'none') == 'none'
+
+        probe = Probe()
+        writer._set_transport(probe)
+        assert writer.transport is probe
+        assert writer.get_extra_info('peername') == ('127.0.0.1', 1)
+        assert writer.get_extra_info('sockname', 'none') == 'none'
+        writer.close()
+        assert probe.closed
+        assert writer.is_closing()
+
+    def test_writelines_triggers_once(self):
+        """Test a large batch is buffered at once with a single transport trigger"""
+
+        class Probe:
+            triggers = 0
+
+            def _trigger_write(self):
+                self.triggers += 1
+                assert writer.needs_drain()
+
+        writer = _veloxloop.StreamWriter()
+        probe = Probe()
+        writer._set_transport(probe)
+        writer.writelines([b'line %05d\n' % i for i in range(10_000)])
+        assert probe.triggers == 1
+        assert writer.get_write_buffer_size() == 110_000
+
     def test_needs_drain_false(self):
         """Test needs_drain returns false for small buffer"""
         writer = _veloxloop.StreamWriter(high_water=100, low_water=20)
@@ -396,6 +703,22 @@ class TestStreamWriter:
         assert writer.is_drained()
         assert not writer.needs_drain()
 
+    def test_drain_waiters_skip_cancelled(self):
+        """Test a drain() waiter cancelled between two others doesn't keep
+        them waiting once the buffer drains"""
+        writer = _veloxloop.StreamWriter(high_water=100, low_water=20)
+        writer.write(b'x' * 200)
+        first, middle, last = writer.drain(), writer.drain(), writer.drain()
+        held = sys.getrefcount(middle)
+        assert middle.cancel()
+        # Dropped from the writer's waiters right away
+        assert sys.getrefcount(middle) == held - 1
+        writer._clear_buffer()
+        writer._wakeup_drain_waiters()
+        assert first.done() and last.done()
+        assert first.result() is None and last.result() is None
+        assert middle.cancelled()
+
     def test_repr(self):
         """Test string representation"""
         writer = _veloxloop.StreamWriter()
@@ -435,6 +758,77 @@ class TestStreamReaderEdgeCases:
         assert result == b'data1' + sep
 
 
+class TestStreamReaderIteration:
+    """Test `async for line in reader`"""
+
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_iterates_lines_fed_incrementally(self):
+        """Test lines split across feeds arrive whole, the unterminated tail
+        last, and iteration ends cleanly at EOF"""
+
+        async def main():
+            reader = _veloxloop.StreamReader()
+
+            async def feed():
+                for chunk in (b'one\ntw', b'o\n', b'', b'thr', b'ee\nfour\nta', b'il'):
+                    await asyncio.sleep(0.005)
+                    reader.feed_data(chunk)
+                reader.feed_eof()
+
+            feeder = asyncio.ensure_future(feed())
+            lines = [line async for line in reader]
+            await feeder
+            return lines
+
+        assert asyncio.run(main()) == [b'one\n', b'two\n', b'three\n', b'four\n', b'tail']
+
+    def test_break_keeps_next_line(self):
+        """Test leaving the loop early loses nothing for readline()"""
+
+        async def main():
+            reader = _veloxloop.StreamReader()
+            reader.feed_data(b'first\nsecond\n')
+            async for line in reader:
+                assert line == b'first\n'
+                break
+            return await _result(reader.readline())
+
+        assert asyncio.run(main()) == b'second\n'
+
+    def test_cancelled_next_takes_no_line(self):
+        """Test a next line abandoned by a timed-out await leaves no waiter
+        behind to take the line that arrives later"""
+
+        async def main():
+            reader = _veloxloop.StreamReader()
+            pending = reader.__anext__()
+            with pytest.raises(asyncio.TimeoutError):
+                await asyncio.wait_for(pending, 0.01)
+            assert pending.cancelled()
+            reader.feed_data(b'late\n')
+            return await _result(reader.readline())
+
+        assert asyncio.run(main()) == b'late\n'
+
+    def test_eof_without_data(self):
+        """Test an empty stream yields nothing, whether EOF comes before the
+        loop starts or while it waits"""
+
+        async def main():
+            reader = _veloxloop.StreamReader()
+            reader.feed_eof()
+            before = [line async for line in reader]
+
+            reader = _veloxloop.StreamReader()
+            asyncio.get_running_loop().call_later(0.01, reader.feed_eof)
+            during = [line async for line in reader]
+            return before, during
+
+        assert asyncio.run(main()) == ([], [])
+
+
 class TestStreamWriterEdgeCases:
     """Edge cases and stress tests for StreamWriter"""
 
@@ -460,5 +854,391 @@ class TestStreamWriterEdgeCases:
         assert writer.get_write_buffer_size() == 5000
 
 
+class TestStreamServer:
+    """Test the native start_server accept path"""
+
+    @staticmethod
+    def _connect(port, payload=b''):
+        with socket.create_connection(('127.0.0.1', port), timeout=5) as sock:
+            if payload:
+                sock.sendall(payload)
+            sock.settimeout(0.5)
+            try:
+                return sock.recv(100)
+            except (socket.timeout, ConnectionError):
+                return b''
+
+    def test_handler_task_retained(self):
+        """Test handler tasks survive garbage collection until they finish"""
+        veloxloop.install()
+        finished = []
+
+        async def handler(reader, writer):
+            await asyncio.sleep(0.05)
+            finished.append(True)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            await loop.run_in_executor(None, self._connect, port)
+            gc.collect()
+            for _ in range(100):
+                if finished:
+                    break
+                await asyncio.sleep(0.01)
+            assert server.active_tasks() == 0
+            server.close()
+
+        asyncio.run(main())
+        assert finished == [True]
+
+    def test_mass_wakeup_spans_iterations(self):
+        """Test many readline() waiters satisfied by one feed run their done
+        callbacks in FIFO order, a batch per loop iteration rather than all
+        inside the read"""
+        veloxloop.install()
+        waiters = 1000
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            ticks = 0
+            stop = False
+
+            def tick():
+                nonlocal ticks
+                ticks += 1
+                if not stop:
+                    loop.call_soon(tick)
+
+            readers = loop.create_future()
+
+            async def handler(reader, writer):
+                readers.set_result(reader)
+                await asyncio.sleep(5)
+                writer.close()
+
+            server = await loop.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            _, writer = await loop.open_connection('127.0.0.1', port)
+            reader = await readers
+
+            resumed = []
+            all_resumed = loop.create_future()
+
+            def make_callback(future):
+                def callback(_):
+                    resumed.append((future.result(), ticks))
+                    if len(resumed) == waiters:
+                        all_resumed.set_result(None)
+
+                return callback
+
+            for _ in range(waiters):
+                future = reader.readline()
+                future.add_done_callback(make_callback(future))
+            # Awaited directly, with no done callback to run, it resumes at once
+            awaited = asyncio.ensure_future(reader.readline())
+            await asyncio.sleep(0.01)
+
+            loop.call_soon(tick)
+            writer.write(b''.join(b'%d\n' % i for i in range(waiters + 1)))
+            assert await asyncio.wait_for(awaited, 5) == b'%d\n' % waiters
+            await asyncio.wait_for(all_resumed, 10)
+            stop = True
+            writer.close()
+            server.close()
+            return resumed
+
+        resumed = asyncio.run(main())
+        assert [line for line, _ in resumed] == [b'%d\n' % i for i in range(waiters)]
+        resumed_at = [tick for _, tick in resumed]
+        assert resumed_at == sorted(resumed_at)
+        assert resumed_at[-1] - resumed_at[0] >= waiters // 64 - 1
+
+    def test_asyncio_style_writer(self):
+        """Test the pattern aiohttp/websockets use: peername from the writer,
+        then close() and await wait_closed()"""
+        veloxloop.install()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            peers = loop.create_future()
+            server_closed = loop.create_future()
+
+            async def handler(reader, writer):
+                peer = writer.get_extra_info('peername')
+                peers.set_result(peer)
+                writer.write(b'%s:%d' % (peer[0].encode(), peer[1]))
+                await writer.drain()
+                writer.close()
+                await writer.wait_closed()
+                server_closed.set_result(writer.transport.is_closing())
+
+            server = await loop.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            assert writer.get_extra_info('peername') == ('127.0.0.1', port)
+            sockname = writer.transport.get_extra_info('sockname')
+            assert writer.get_extra_info('sockname') == sockname
+
+            # The server closing its writer reaches us as EOF
+            reply = await asyncio.wait_for(_read_to_eof(reader), 5)
+            assert reply == b'%s:%d' % (sockname[0].encode(), sockname[1])
+            assert await peers == sockname
+            assert await asyncio.wait_for(server_closed, 5)
+
+            writer.close()
+            await asyncio.wait_for(writer.wait_closed(), 5)
+            assert writer.is_closing()
+            # Already closed: resolves at once
+            await writer.wait_closed()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_callback_errors_do_not_stop_server(self):
+        """Test sync and async handler failures are reported per connection"""
+        veloxloop.install()
+        errors = []
+        served = []
+
+        def handler(reader, writer):
+            n = len(served) + len(errors)
+            if n == 0:
+                raise ValueError('sync failure')
+            if n == 1:
+                return failing()
+            served.append(n)
+            return None
+
+        async def failing():
+            errors.append('async')
+            raise RuntimeError('async failure')
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_exception_handler(lambda loop, ctx: errors.append(ctx))
+            server = await loop.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            for _ in range(3):
+                await loop.run_in_executor(None, self._connect, port)
+                await asyncio.sleep(0.02)
+            server.close()
+
+        asyncio.run(main())
+        contexts = [e for e in errors if isinstance(e, dict)]
+        assert [type(c['exception']) for c in contexts] == [ValueError, RuntimeError]
+        assert all(c['message'] == 'Unhandled exception in client_connected_cb' for c in contexts)
+        assert served == [3]
+
+    def test_start_server_on_native_loop(self):
+        """Test start_server on the bare Rust loop, which has no create_task"""
+        loop = _veloxloop.VeloxLoop()
+        assert not hasattr(loop, 'create_task')
+        handled = []
+
+        async def handler(reader, writer):
+            handled.append(True)
+            loop.stop()
+
+        server = loop.start_server(handler, '127.0.0.1', 0).result()
+        port = server.sockets[0].getsockname()[1]
+        client = threading.Thread(target=self._connect, args=(port,))
+        client.start()
+        timeout = loop.call_later(5, loop.stop)
+        loop.run_forever()
+        loop._cancel_timer(timeout)
+        client.join()
+        server.close()
+        loop.close()
+        assert handled == [True]
+
+    def test_readexactly_into_frames(self):
+        """Test large frames land in caller buffers read straight from the socket"""
+        veloxloop.install()
+        frame = 8 * 1024 * 1024
+        frames = [bytes([i]) * frame for i in range(4)]
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            received = loop.create_future()
+
+            async def handler(reader, writer):
+                got = []
+                target = bytearray(frame)
+                for _ in frames:
+                    result = reader.readexactly_into(target)
+                    if not isinstance(result, int):
+                        result = await result
+                    assert result == frame
+                    got.append(bytes(target))
+                received.set_result(got)
+                writer.close()
+
+            server = await loop.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            _, writer = await loop.open_connection('127.0.0.1', port)
+            for data in frames:
+                writer.write(data)
+                await writer.drain()
+            assert await asyncio.wait_for(received, 30) == frames
+            writer.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_shared_writer_drain_with_cancelled_waiter(self):
+        """Test tasks sharing a writer all get through drain() when one of
+        them gives up waiting or cancels its future"""
+        veloxloop.install()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            peer = loop.create_future()
+
+            async def handler(reader, writer):
+                peer.set_result(reader)
+
+            server = await loop.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            _, writer = await loop.open_connection('127.0.0.1', port)
+            peer_reader = await peer
+
+            writer.write(b'x' * (32 * 1024 * 1024))
+            waiters = [writer.drain() for _ in range(3)]
+            assert not any(w.done() for w in waiters)
+            assert waiters[1].cancel()
+
+            async def broadcast(waiter):
+                await waiter
+                return True
+
+            tasks = [asyncio.ensure_future(broadcast(w)) for w in waiters[::2]]
+            # Another task times out waiting, leaving its future behind
+            with pytest.raises(asyncio.TimeoutError):
+                await asyncio.wait_for(writer.drain(), 0.01)
+
+            data = peer_reader.readexactly(32 * 1024 * 1024)
+            if not isinstance(data, bytes):
+                await data
+            assert await asyncio.wait_for(asyncio.gather(*tasks), 10) == [True, True]
+            writer.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_write_eof_half_close(self):
+        """Test write_eof sends FIN after buffered data while reading continues"""
+        veloxloop.install()
+        payload = b'request-' * 100_000
+
+        async def handler(reader, writer):
+            data = await _read_to_eof(reader)
+            assert writer.can_write_eof()
+            writer.write(b'got %d' % len(data))
+            writer.write_eof()
+            assert not writer.can_write_eof()
+            with pytest.raises(RuntimeError, match='Cannot write after write_eof'):
+                writer.write(b'late')
+            writer.close()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.start_server(handler, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+
+            def client():
+                with socket.create_connection(('127.0.0.1', port), timeout=5) as sock:
+                    sock.sendall(payload)
+                    sock.shutdown(socket.SHUT_WR)
+                    response = b''
+                    while chunk := sock.recv(100):
+                        response += chunk
+                    return response
+
+            response = await asyncio.wait_for(loop.run_in_executor(None, client), 10)
+            assert response == b'got %d' % len(payload)
+            server.close()
+
+        asyncio.run(main())
+
+    def test_open_connection_write_eof(self):
+        """Test the client side half-closes and still reads the reply"""
+        veloxloop.install()
+        payload = b'x' * (1024 * 1024)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            listener = socket.create_server(('127.0.0.1', 0))
+            port = listener.getsockname()[1]
+
+            def serve():
+                conn, _ = listener.accept()
+                with conn:
+                    total = 0
+                    while chunk := conn.recv(65536):
+                        total += len(chunk)
+                    conn.sendall(b'%d' % total)
+
+            served = loop.run_in_executor(None, serve)
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            # Large enough that part of it is still buffered when write_eof is called
+            writer.write(payload)
+            writer.write_eof()
+            with pytest.raises(RuntimeError, match='Already closed'):
+                writer.write_eof()
+            reply = await asyncio.wait_for(_read_to_eof(reader), 10)
+            assert reply == b'%d' % len(payload)
+            await served
+            writer.close()
+            listener.close()
+
+        asyncio.run(main())
+
+
+    def test_drain_after_writelines(self):
+        """Test drain() right after a large writelines waits for the peer"""
+        veloxloop.install()
+        chunk = b'z' * 65536
+        total = 256 * len(chunk)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            listener = socket.create_server(('127.0.0.1', 0))
+            listener.setsockopt(socket.SOL_SOCKET, socket.SO_RCVBUF, 32 * 1024)
+            port = listener.getsockname()[1]
+            start_reading = threading.Event()
+
+            def serve():
+                conn, _ = listener.accept()
+                with conn:
+                    start_reading.wait(10)
+                    received = 0
+                    while received < total and (data := conn.recv(65536)):
+                        received += len(data)
+                    return received
+
+            served = loop.run_in_executor(None, serve)
+            _, writer = await loop.open_connection('127.0.0.1', port)
+            writer.writelines(chunk for _ in range(256))
+            assert writer.needs_drain()
+
+            async def drain():
+                await writer.drain()
+
+            drained = asyncio.ensure_future(drain())
+            await asyncio.sleep(0.1)
+            assert not drained.done()
+
+            start_reading.set()
+            await asyncio.wait_for(drained, 30)
+            assert writer.is_drained()
+            assert await served == total
+            writer.close()
+            listener.close()
+
+        asyncio.run(main())
+
 if __name__ == '__main__':
     pytest.main([__file__, '-v'])
@@ -0,0 +1,182 @@
+"""Tests for subprocess_exec / subprocess_shell (pidfd based, Linux only)"""
+
+import asyncio
+import signal
+import subprocess
+import sys
+
+import pytest
+
+import veloxloop
+
+pytestmark = pytest.mark.skipif(not sys.platform.startswith('linux'), reason='pidfd is Linux only')
+
+
+class RecordingProtocol(asyncio.SubprocessProtocol):
+    def __init__(self, loop):
+        self.events = []
+        self.data = {1: b'', 2: b''}
+        self.done = loop.create_future()
+        self.transport = None
+
+    def connection_made(self, transport):
+        self.transport = transport
+        self.events.append('connection_made')
+
+    def pipe_data_received(self, fd, data):
+        self.data[fd] += data
+
+    def pipe_connection_lost(self, fd, exc):
+        self.events.append(('pipe_connection_lost', fd))
+
+    def process_exited(self):
+        self.events.append('process_exited')
+
+    def connection_lost(self, exc):
+        self.events.append('connection_lost')
+        self.done.set_result(None)
+
+
+class TestSubprocess:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_cat_roundtrip(self):
+        """Test stdin/stdout pipes end-to-end through the streams API"""
+
+        async def main():
+            proc = await asyncio.create_subprocess_exec(
+                'cat', stdin=subprocess.PIPE, stdout=subprocess.PIPE
+            )
+            assert proc.pid > 0
+            out, err = await asyncio.wait_for(proc.communicate(b'hello velox\n' * 1000), 5)
+            assert out == b'hello velox\n' * 1000
+            assert err is None
+            assert proc.returncode == 0
+
+        asyncio.run(main())
+
+    def test_shell_exit_status(self):
+        """Test the exit status is reported even with an unclosed stdin pipe"""
+
+        async def main():
+            proc = await asyncio.create_subprocess_shell('exit 3', stdin=subprocess.PIPE)
+            assert await asyncio.wait_for(proc.wait(), 5) == 3
+            assert proc.returncode == 3
+
+        asyncio.run(main())
+
+    def test_protocol_events_and_write_eof(self):
+        """Test callback order and closing stdin via write_eof on its pipe transport"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, proto = await loop.subprocess_exec(
+                lambda: RecordingProtocol(loop), 'cat', stderr=subprocess.DEVNULL
+            )
+            assert proto.events == ['connection_made']
+            assert transport.get_pipe_transport(2) is None
+            stdin = transport.get_pipe_transport(0)
+            assert stdin.can_write_eof()
+            stdin.write(b'abc')
+            stdin.write_eof()
+
+            await asyncio.wait_for(proto.done, 5)
+            assert proto.data[1] == b'abc'
+            assert transport.get_returncode() == 0
+            assert proto.events[-1] == 'connection_lost'
+            assert set(proto.events[1:-1]) == {
+                ('pipe_connection_lost', 0),
+                ('pipe_connection_lost', 1),
+                'process_exited',
+            }
+            transport.close()
+
+        asyncio.run(main())
+
+    def test_get_extra_info(self):
+        """Test the pid and pidfd keys, and the default for anything else"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, proto = await loop.subprocess_exec(
+                lambda: RecordingProtocol(loop), 'true', stdin=subprocess.DEVNULL
+            )
+            assert transport.get_extra_info('pid') == transport.get_pid()
+            assert transport.get_extra_info('pidfd') >= 0
+            assert transport.get_extra_info('subprocess') is None
+            assert transport.get_extra_info('peername', 'none') == 'none'
+
+            await asyncio.wait_for(proto.done, 5)
+            # The pidfd is closed once the exit is collected
+            assert transport.get_extra_info('pidfd', 'gone') == 'gone'
+            assert transport.get_extra_info('pid') == transport.get_pid()
+            transport.close()
+
+        asyncio.run(main())
+
+    def test_stderr_to_stdout(self):
+        """Test stderr=STDOUT shares the stdout pipe"""
+
+        async def main():
+            proc = await asyncio.create_subprocess_shell(
+                'echo out; echo err >&2',
+                stdout=subprocess.PIPE,
+                stderr=subprocess.STDOUT,
+            )
+            out, _ = await asyncio.wait_for(proc.communicate(), 5)
+            assert out.split() == [b'out', b'err']
+
+            proc = await asyncio.create_subprocess_shell(
+                'echo hidden', stdout=subprocess.DEVNULL, stderr=subprocess.STDOUT
+            )
+            assert await asyncio.wait_for(proc.wait(), 5) == 0
+
+        asyncio.run(main())
+
+    def test_kill_and_send_signal(self):
+        """Test signals go to the child and fail once it is gone"""
+
+        async def main():
+            proc = await asyncio.create_subprocess_exec('sleep', '30')
+            proc.send_signal(signal.SIGTERM)
+            assert await asyncio.wait_for(proc.wait(), 5) == -signal.SIGTERM
+            with pytest.raises(ProcessLookupError):
+                proc.kill()
+
+            proc = await asyncio.create_subprocess_exec('sleep', '30')
+            proc.kill()
+            assert await asyncio.wait_for(proc.wait(), 5) == -signal.SIGKILL
+
+        asyncio.run(main())
+
+    def test_close_kills_running_child(self):
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, proto = await loop.subprocess_exec(
+                lambda: RecordingProtocol(loop), 'sleep', '30'
+            )
+            transport.close()
+            assert transport.is_closing()
+            await asyncio.wait_for(proto.done, 5)
+            assert transport.get_returncode() == -signal.SIGKILL
+
+        asyncio.run(main())
+
+    def test_invalid_arguments(self):
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(FileNotFoundError):
+                await loop.subprocess_exec(asyncio.SubprocessProtocol, 'no-such-program-velox')
+            with pytest.raises(ValueError):
+                await loop.subprocess_exec(asyncio.SubprocessProtocol, 'true', stdin=subprocess.STDOUT)
+            with pytest.raises(ValueError):
+                await loop.subprocess_exec(asyncio.SubprocessProtocol, 'true', bufsize=1)
+            with pytest.raises(ValueError):
+                await loop.subprocess_shell(asyncio.SubprocessProtocol, 'true', shell=False)
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -192,6 +192,52 @@ class TestTCPTransport:
 
         asyncio.run(main())
 
+    @pytest.mark.parametrize('native_streams', [False, True])
+    def test_server_async_with_exception(self, native_streams):
+        """Test an exception in an async with body closes the server and propagates"""
+
+        async def handler(reader, writer):
+            writer.close()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            start = loop.start_server if native_streams else asyncio.start_server
+            server = await start(handler, '127.0.0.1', 0)
+            with pytest.raises(ValueError, match='in body'):
+                async with server as entered:
+                    assert entered is server
+                    assert server.is_serving()
+                    raise ValueError('in body')
+            assert not server.is_serving()
+            assert server.sockets == []
+
+            # The native server also works written as asyncio documents it
+            async with await start(handler, '127.0.0.1', 0) as other:
+                assert other.is_serving()
+            assert not other.is_serving()
+
+        asyncio.run(main())
+
+    @pytest.mark.parametrize('native_streams', [False, True])
+    def test_server_nested_async_with(self, native_streams):
+        """Test entering a server's async with twice raises instead of closing it early"""
+
+        async def handler(reader, writer):
+            writer.close()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            start = loop.start_server if native_streams else asyncio.start_server
+            server = await start(handler, '127.0.0.1', 0)
+            async with server:
+                with pytest.raises(RuntimeError, match='async with'):
+                    async with server:
+                        pass
+                assert server.is_serving()
+            assert not server.is_serving()
+
+        asyncio.run(main())
+
     def test_tcp_reader_readline(self):
         """Test reading line-by-line from TCP"""
 
@@ -455,5 +501,445 @@ class TestTCPTransport:
         asyncio.run(main())
 
 
+    def test_transport_observer_lifecycle(self):
+        """Test the transport observer sees connection_made/lost with byte counts"""
+        events = []
+
+        class Echo(asyncio.Protocol):
+            def connection_made(self, transport):
+                self.transport = transport
+
+            def data_received(self, data):
+                self.transport.write(data)
+
+        class Client(asyncio.Protocol):
+            def __init__(self, done):
+                self.done = done
+                self.received = b''
+
+            def connection_made(self, transport):
+                self.transport = transport
+
+            def data_received(self, data):
+                self.received += data
+                if self.received == b'ping!':
+                    self.transport.close()
+
+            def connection_lost(self, exc):
+                if not self.done.done():
+                    self.done.set_result(None)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            assert loop.get_transport_observer() is None
+            loop.set_transport_observer(lambda event: events.append(event))
+
+            server = await loop.create_server(Echo, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            done = loop.create_future()
+            transport, client = await loop.create_connection(lambda: Client(done), '127.0.0.1', port)
+
+            transport.pause_reading()
+            transport.resume_reading()
+            transport.write(b'ping!')
+            await asyncio.wait_for(done, 5)
+
+            stats = transport.get_extra_info('veloxloop_stats')
+            assert stats['bytes_out'] == 5
+            assert stats['bytes_in'] == 5
+            assert stats['duration'] >= 0.0
+
+            # Let queued observer callbacks run
+            await asyncio.sleep(0.05)
+            loop.set_transport_observer(None)
+            assert loop.get_transport_observer() is None
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+        names = [e[0] for e in events]
+        assert 'connection_made' in names
+        assert 'pause_reading' in names
+        assert 'resume_reading' in names
+        lost = [e for e in events if e[0] == 'connection_lost' and e[3] == 5 and e[4] == 5]
+        assert lost, events
+        assert lost[0][2] is None
+        made = [e for e in events if e[0] == 'connection_made']
+        assert any(e[2] is not None and e[2][1] > 0 for e in made)
+
+    @pytest.mark.parametrize('opener', ['create_connection', 'open_connection'])
+    def test_write_buffer_high_once_per_crossing(self, opener):
+        """Test the observer hears write_buffer_high once per crossing of the
+        high mark, not on every write above it"""
+        events = []
+        held = []
+
+        class Held(asyncio.Protocol):
+            def connection_made(self, transport):
+                transport.pause_reading()
+                held.append(transport)
+
+        async def connect(loop, port):
+            if opener == 'open_connection':
+                _, writer = await loop.open_connection('127.0.0.1', port)
+                return writer.transport
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port
+            )
+            return transport
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_transport_observer(events.append)
+            server = await loop.create_server(Held, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport = await connect(loop, port)
+            transport.set_write_buffer_limits(high=1024, low=0)
+
+            def crossings():
+                return [e for e in events if e[0] == 'write_buffer_high']
+
+            for round in (1, 2):
+                # Well past the mark, however much the kernel takes first
+                async with asyncio.timeout(10):
+                    while transport.get_write_buffer_size() < 1024 * 1024:
+                        transport.write(b'x' * 256 * 1024)
+                        await asyncio.sleep(0)
+                for _ in range(8):
+                    transport.write(b'x' * 1024)
+                await asyncio.sleep(0.05)
+                assert len(crossings()) == round, crossings()
+                # Draining to the low mark re-arms it
+                held[0].resume_reading()
+                async with asyncio.timeout(10):
+                    while transport.get_write_buffer_size():
+                        await asyncio.sleep(0.01)
+                held[0].pause_reading()
+                await asyncio.sleep(0.05)
+            transport.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    def test_transport_observer_errors_reported(self):
+        """Test observer exceptions go to the exception handler"""
+        errors = []
+
+        async def handle(reader, writer):
+            writer.close()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_exception_handler(lambda loop, ctx: errors.append(ctx))
+
+            def observer(event):
+                raise RuntimeError('observer failed')
+
+            loop.set_transport_observer(observer)
+            server = await asyncio.start_server(handle, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await asyncio.open_connection('127.0.0.1', port)
+            assert await reader.read() == b''
+            writer.close()
+            await asyncio.sleep(0.05)
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+        assert errors
+        assert any(isinstance(ctx.get('exception'), RuntimeError) for ctx in errors)
+
+    def _run_small_writes(self, coalesce, count=50, finish='close'):
+        """Write `count` small messages in one loop iteration; return (stats, received)"""
+        received = []
+
+        class Sink(asyncio.Protocol):
+            def __init__(self, done):
+                self.done = done
+                self.data = b''
+
+            def data_received(self, data):
+                self.data += data
+
+            def eof_received(self):
+                received.append(self.data)
+                if not self.done.done():
+                    self.done.set_result(None)
+
+            def connection_lost(self, exc):
+                if not self.done.done():
+                    received.append(self.data)
+                    self.done.set_result(None)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            done = loop.create_future()
+            server = await loop.create_server(lambda: Sink(done), '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            if coalesce:
+                transport.set_write_coalescing(max_delay_us=100_000, max_bytes=16384)
+
+            for i in range(count):
+                transport.write(b'msg%03d;' % i)
+            if finish == 'close':
+                transport.close()
+            elif finish == 'write_eof':
+                transport.write_eof()
+            else:
+                await asyncio.sleep(0)
+
+            stats = transport.get_extra_info('veloxloop_stats')
+            if finish == 'tick':
+                transport.write_eof()
+            await asyncio.wait_for(done, 5)
+            transport.close()
+            server.close()
+            await server.wait_closed()
+            return stats
+
+        stats = asyncio.run(main())
+        return stats, received[0]
+
+    def test_write_coalescing_batches_small_writes(self):
+        """Test coalesced small writes go out in at most two send calls"""
+        expected = b''.join(b'msg%03d;' % i for i in range(50))
+
+        stats, data = self._run_small_writes(coalesce=True, finish='tick')
+        assert data == expected
+        assert 1 <= stats['write_calls'] <= 2
+
+        stats, data = self._run_small_writes(coalesce=False, finish='tick')
+        assert data == expected
+        assert stats['write_calls'] == 50
+
+    def test_write_coalescing_flushed_by_close_and_write_eof(self):
+        """Test close() and write_eof() send coalesced data in order"""
+        expected = b''.join(b'msg%03d;' % i for i in range(20))
+        for finish in ('close', 'write_eof'):
+            _, data = self._run_small_writes(coalesce=True, count=20, finish=finish)
+            assert data == expected
+
+    def test_write_coalescing_max_bytes(self):
+        """Test the byte threshold forces a flush within the iteration"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+
+            assert transport.get_write_coalescing() is None
+            with pytest.raises(ValueError):
+                transport.set_write_coalescing(max_bytes=0)
+            transport.set_write_coalescing(max_delay_us=1_000_000, max_bytes=64)
+            assert transport.get_write_coalescing() == (1_000_000, 64)
+
+            for _ in range(3):
+                transport.write(b'x' * 30)
+            # Third write crossed 64 bytes and went out immediately
+            assert transport.get_extra_info('veloxloop_stats')['write_calls'] == 1
+            assert transport.get_extra_info('veloxloop_stats')['bytes_out'] == 90
+
+            transport.write(b'y')
+            transport.disable_write_coalescing()
+            assert transport.get_write_coalescing() is None
+            assert transport.get_extra_info('veloxloop_stats')['bytes_out'] == 91
+
+            transport.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    def test_read_chunk_size_per_transport(self):
+        """Test reads never exceed the transport chunk size and changes apply live"""
+        chunks = []
+
+        class Recorder(asyncio.Protocol):
+            def __init__(self, ready):
+                self.ready = ready
+                self.total = 0
+                self.waiter = None
+
+            def connection_made(self, transport):
+                transport.set_read_chunk_size(1024)
+                self.ready.set_result(transport)
+
+            def data_received(self, data):
+                chunks.append(len(data))
+                self.total += len(data)
+                if self.waiter and self.total >= self.waiter[0] and not self.waiter[1].done():
+                    self.waiter[1].set_result(None)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            ready = loop.create_future()
+            proto = Recorder(ready)
+            server = await loop.create_server(lambda: proto, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            client, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            server_transport = await ready
+
+            for chunk_size, total in ((1024, 65536), (8192, 131072)):
+                server_transport.set_read_chunk_size(chunk_size)
+                assert server_transport.get_read_chunk_size() == chunk_size
+                chunks.clear()
+                proto.waiter = (total, loop.create_future())
+                client.write(b'x' * (total - proto.total))
+                await asyncio.wait_for(proto.waiter[1], 5)
+                assert max(chunks) <= chunk_size
+
+            with pytest.raises(ValueError):
+                server_transport.set_read_chunk_size(3000)
+            assert server_transport.get_read_chunk_size() == 8192
+
+            client.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+
+    def test_loop_read_buffer_size(self):
+        """Test the loop-wide read chunk default and its validation"""
+        for bad in (0, 512, 3000, 8 * 1024 * 1024):
+            with pytest.raises(ValueError):
+                veloxloop.VeloxLoop(read_chunk_size=bad)
+
+        loop = veloxloop.VeloxLoop(read_chunk_size=4096)
+        try:
+            assert loop.get_read_buffer_size() == 4096
+
+            async def main():
+                server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+                port = server.sockets[0].getsockname()[1]
+                transport, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+                assert transport.get_read_chunk_size() == 4096
+
+                with pytest.raises(ValueError):
+                    loop.set_read_buffer_size(1 << 23)
+                loop.set_read_buffer_size(1 << 20)
+                # Existing transports keep their size, new ones pick up the default
+                assert transport.get_read_chunk_size() == 4096
+                other, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+                assert other.get_read_chunk_size() == 1 << 20
+
+                transport.close()
+                other.close()
+                server.close()
+                await server.wait_closed()
+
+            loop.run_until_complete(main())
+        finally:
+            loop.close()
+
+    @pytest.mark.parametrize('keep_open', [True, False])
+    def test_half_close_echo(self, keep_open):
+        """Test a peer that shuts down its write side still gets the full response"""
+        import socket
+
+        payload = bytes(range(256)) * 4096  # 1 MiB, more than one send() can take
+        events = []
+
+        class HalfCloseEcho(asyncio.Protocol):
+            def connection_made(self, transport):
+                self.transport = transport
+                self.data = bytearray()
+
+            def data_received(self, data):
+                self.data += data
+
+            def eof_received(self):
+                events.append('eof')
+                self.transport.write(bytes(self.data))
+                if keep_open:
+                    # The write side must stay usable until we close it ourselves
+                    loop = asyncio.get_running_loop()
+                    loop.call_later(0.05, self.finish)
+                    return True
+                return False
+
+            def finish(self):
+                events.append(('closing', self.transport.is_closing()))
+                self.transport.write(b'!')
+                self.transport.close()
+
+            def connection_lost(self, exc):
+                events.append(('lost', exc))
+
+        def client(port):
+            with socket.create_connection(('127.0.0.1', port)) as s:
+                s.sendall(payload)
+                s.shutdown(socket.SHUT_WR)
+                received = bytearray()
+                while chunk := s.recv(65536):
+                    received += chunk
+                return bytes(received)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(HalfCloseEcho, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            response = await asyncio.wait_for(loop.run_in_executor(None, client, port), 10)
+            await asyncio.sleep(0.01)
+            server.close()
+            await server.wait_closed()
+            return response
+
+        response = asyncio.run(main())
+        if keep_open:
+            assert response == payload + b'!'
+            assert events == ['eof', ('closing', False), ('lost', None)], events
+        else:
+            assert response == payload
+            assert events == ['eof', ('lost', None)], events
+
+    def test_accept_dispatched_ahead_of_data(self):
+        """Test a pending accept runs before data events that became ready in the same tick"""
+        import socket
+
+        order = []
+
+        class Recorder(asyncio.Protocol):
+            def connection_made(self, transport):
+                order.append('made')
+
+            def data_received(self, data):
+                order.append('data')
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(Recorder, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            busy = [socket.create_connection(('127.0.0.1', port)) for _ in range(100)]
+            while order.count('made') < len(busy):
+                await asyncio.sleep(0.01)
+            order.clear()
+
+            def make_everything_ready():
+                # Runs inside one callback, so the next poll sees all of it at once.
+                # The new connection lands mid-way, so the accept going first
+                # isn't down to the order the ready events came in
+                for i, sock in enumerate(list(busy)):
+                    if i == len(busy) // 2:
+                        busy.append(socket.create_connection(('127.0.0.1', port)))
+                    sock.sendall(b'x')
+
+            loop.call_soon(make_everything_ready)
+            while order.count('data') < len(busy) - 1 or 'made' not in order:
+                await asyncio.sleep(0.01)
+            for sock in busy:
+                sock.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(main())
+        assert order.count('made') == 1
+        assert order.count('data') == 100
+        assert order[0] == 'made', order[:5]
+
+
 if __name__ == '__main__':
     pytest.main([__file__, '-v'])
@@ -0,0 +1,133 @@
+"""Tests for TCP Fast Open and deferred accept options"""
+
+import asyncio
+import socket
+import sys
+
+import pytest
+
+import veloxloop
+
+pytestmark = pytest.mark.skipif(
+    not sys.platform.startswith('linux'), reason='TCP_DEFER_ACCEPT/TCP_FASTOPEN are Linux options'
+)
+
+TCP_FASTOPEN_CONNECT = getattr(socket, 'TCP_FASTOPEN_CONNECT', 30)
+
+
+def _tcp_opt(sock, name):
+    return sock.getsockopt(socket.IPPROTO_TCP, name)
+
+
+class TestTcpTuning:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('api', ['create_server', 'start_server'])
+    def test_server_kwargs_set_listener_options(self, api):
+        """Test tcp_defer_accept/tcp_fastopen are set on every listener"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            hosts = ['127.0.0.1', '127.0.0.2']
+            if api == 'create_server':
+                server = await loop.create_server(
+                    asyncio.Protocol, hosts, 0, tcp_defer_accept=5, tcp_fastopen=16
+                )
+            else:
+                server = await loop.start_server(
+                    lambda r, w: w.close(), hosts, 0, tcp_defer_accept=5, tcp_fastopen=16
+                )
+            for sock in server.sockets:
+                # The kernel rounds the timeout up to a whole SYN-ACK retransmit
+                assert _tcp_opt(sock, socket.TCP_DEFER_ACCEPT) >= 5
+                assert _tcp_opt(sock, socket.TCP_FASTOPEN) == 16
+            server.close()
+
+        asyncio.run(main())
+
+    def test_options_default_off(self):
+        """Test listeners are untouched without the kwargs"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            sock = server.sockets[0]
+            assert _tcp_opt(sock, socket.TCP_DEFER_ACCEPT) == 0
+            assert _tcp_opt(sock, socket.TCP_FASTOPEN) == 0
+            server.close()
+
+        asyncio.run(main())
+
+    def test_post_hoc_setters(self):
+        """Test set_defer_accept/set_fastopen retune a running server"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0, tcp_defer_accept=5)
+            sock = server.sockets[0]
+            server.set_defer_accept(0)
+            server.set_fastopen(3)
+            assert _tcp_opt(sock, socket.TCP_DEFER_ACCEPT) == 0
+            assert _tcp_opt(sock, socket.TCP_FASTOPEN) == 3
+            server.close()
+
+        asyncio.run(main())
+
+    def test_server_still_accepts(self):
+        """Test a tuned server accepts and reads from a client"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            received = loop.create_future()
+
+            class Echo(asyncio.Protocol):
+                def data_received(self, data):
+                    if not received.done():
+                        received.set_result(data)
+
+            server = await loop.create_server(
+                Echo, '127.0.0.1', 0, tcp_defer_accept=1, tcp_fastopen=8
+            )
+            port = server.addresses()[0][1]
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port, fastopen=True
+            )
+            transport.write(b'hello')
+            assert await asyncio.wait_for(received, 5) == b'hello'
+            transport.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_client_fastopen(self):
+        """Test fastopen=True sets TCP_FASTOPEN_CONNECT on the client socket only"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.addresses()[0][1]
+
+            fast, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port, fastopen=True)
+            plain, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            assert _tcp_opt(fast.get_extra_info('socket'), TCP_FASTOPEN_CONNECT) == 1
+            assert _tcp_opt(plain.get_extra_info('socket'), TCP_FASTOPEN_CONNECT) == 0
+            fast.close()
+            plain.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_negative_value_rejected(self):
+        """Test option values must be non-negative integers"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            with pytest.raises(OverflowError):
+                await loop.create_server(asyncio.Protocol, '127.0.0.1', 0, tcp_fastopen=-1)
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
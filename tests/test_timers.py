@@ -1,6 +1,10 @@
 """Tests for Timers & Scheduling"""
 
 import asyncio
+import math
+import random
+import statistics
+import time
 
 import pytest
 
@@ -213,6 +217,21 @@ class TestTimersScheduling:
 
         asyncio.run(main())
 
+    def test_timer_after_zero_delay_keeps_its_deadline(self):
+        """Test a timer due after a zero-delay one still wakes the loop on
+        time when a later timer is added in between"""
+        loop = asyncio.new_event_loop()
+        try:
+            fired = loop.create_future()
+            start = time.perf_counter()
+            loop.call_later(0, lambda: None)
+            loop.call_later(0.025, fired.set_result, None)
+            # wait_for() schedules its 5s timeout after the first timer ran
+            loop.run_until_complete(asyncio.wait_for(fired, 5))
+            assert time.perf_counter() - start < 1
+        finally:
+            loop.close()
+
     def test_call_later_with_args_kwargs(self):
         """Test call_later with multiple arguments"""
         result = []
@@ -335,6 +354,60 @@ class TestTimersScheduling:
 
         asyncio.run(main())
 
+    def test_when_is_exact_deadline(self):
+        """Test when() is the deadline the loop fires at, never after it runs"""
+        seen = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            before = loop.time()
+            handle = loop.call_later(0.02, lambda: seen.append(loop.time()))
+            assert before + 0.02 <= handle.when() < loop.time() + 0.02 + 1e-6
+            await asyncio.sleep(0.05)
+            assert seen[0] >= handle.when()
+
+            when = loop.time() + 0.01
+            assert loop.call_at(when, lambda: None).when() == when
+
+        asyncio.run(main())
+
+    def test_never_fires_early(self):
+        """Test 10k timers with random small delays: none early, lateness bounded"""
+        rng = random.Random(1234)
+        count = 10_000
+        early = []
+        lateness = []
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            done = loop.create_future()
+
+            def fired(scheduled_ns, delay, handle_box):
+                fired_ns = time.monotonic_ns()
+                if fired_ns - scheduled_ns < math.ceil(delay * 1e9):
+                    early.append((delay, fired_ns - scheduled_ns))
+                if loop.time() < handle_box[0].when():
+                    early.append((delay, 'loop time before when()'))
+                lateness.append((fired_ns - scheduled_ns) / 1e9 - delay)
+                if len(lateness) == count:
+                    done.set_result(None)
+
+            for i in range(count):
+                delay = rng.uniform(0, 0.05)
+                box = []
+                now = time.monotonic_ns()
+                box.append(loop.call_later(delay, fired, now, delay, box))
+                if i % 500 == 0:
+                    await asyncio.sleep(0)
+            await asyncio.wait_for(done, 10)
+
+        asyncio.run(main())
+        assert early == []
+        assert len(lateness) == count
+        # Late only by the time it takes to run 10k callbacks
+        assert statistics.median(lateness) < 0.02
+        assert max(lateness) < 0.5
+
 
 if __name__ == '__main__':
     pytest.main([__file__, '-v'])
@@ -0,0 +1,219 @@
+"""Tests for transparent proxy support: IP_TRANSPARENT/IP_FREEBIND and
+get_extra_info('original_dst')"""
+
+import asyncio
+import os
+import shutil
+import socket
+import subprocess
+import sys
+
+import pytest
+
+import veloxloop
+from veloxloop._veloxloop import SocketOptions
+
+pytestmark = pytest.mark.skipif(
+    not sys.platform.startswith('linux'), reason='IP_TRANSPARENT/SO_ORIGINAL_DST are Linux options'
+)
+
+SOL_IP = getattr(socket, 'SOL_IP', 0)
+IP_FREEBIND = getattr(socket, 'IP_FREEBIND', 15)
+IP_TRANSPARENT = getattr(socket, 'IP_TRANSPARENT', 19)
+CAP_NET_ADMIN = 12
+CAP_NET_RAW = 13
+# An address from TEST-NET-1, configured on no interface
+UNASSIGNED = '192.0.2.1'
+
+
+def _has_cap(*caps):
+    with open('/proc/self/status') as status:
+        for line in status:
+            if line.startswith('CapEff:'):
+                effective = int(line.split()[1], 16)
+                return any(effective >> cap & 1 for cap in caps)
+    return False
+
+
+# IP_TRANSPARENT takes either capability
+can_be_transparent = _has_cap(CAP_NET_ADMIN, CAP_NET_RAW)
+needs_transparent = pytest.mark.skipif(
+    not can_be_transparent, reason='needs CAP_NET_ADMIN or CAP_NET_RAW'
+)
+
+
+async def _start(api, host, **kwargs):
+    loop = asyncio.get_running_loop()
+    if api == 'create_server':
+        return await loop.create_server(asyncio.Protocol, host, 0, **kwargs)
+    return await loop.start_server(lambda r, w: w.close(), host, 0, **kwargs)
+
+
+class Accepting(asyncio.Protocol):
+    """Hands the server side transport to the test"""
+
+    def __init__(self, accepted):
+        self.accepted = accepted
+
+    def connection_made(self, transport):
+        self.accepted.set_result(transport)
+
+
+class TestTransparentProxy:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_socket_options_builder(self):
+        """Test SocketOptions carries ip_transparent/ip_freebind"""
+        opts = SocketOptions()
+        assert opts.get_ip_transparent() is None
+        assert opts.get_ip_freebind() is None
+        opts.set_ip_transparent(True)
+        opts.set_ip_freebind(False)
+        assert opts.get_ip_transparent() is True
+        assert opts.get_ip_freebind() is False
+        assert 'ip_transparent=Some(true)' in repr(opts)
+        opts.reset()
+        assert opts.get_ip_transparent() is None
+
+    @pytest.mark.parametrize('api', ['create_server', 'start_server'])
+    def test_freebind_listener(self, api):
+        """Test freebind=True binds a listener to an address no interface has"""
+
+        async def main():
+            with pytest.raises(OSError, match='error while attempting to bind'):
+                await _start(api, UNASSIGNED)
+            server = await _start(api, UNASSIGNED, freebind=True)
+            [sock] = server.sockets
+            assert sock.getsockname()[0] == UNASSIGNED
+            assert sock.getsockopt(SOL_IP, IP_FREEBIND) == 1
+            assert sock.getsockopt(SOL_IP, IP_TRANSPARENT) == 0
+            server.close()
+
+        asyncio.run(main())
+
+    @needs_transparent
+    @pytest.mark.parametrize('api', ['create_server', 'start_server'])
+    def test_transparent_listener(self, api):
+        """Test transparent=True sets IP_TRANSPARENT before the listener binds"""
+
+        async def main():
+            server = await _start(api, UNASSIGNED, transparent=True)
+            [sock] = server.sockets
+            assert sock.getsockopt(SOL_IP, IP_TRANSPARENT) == 1
+            server.close()
+
+        asyncio.run(main())
+
+    @pytest.mark.skipif(can_be_transparent, reason='needs to run without CAP_NET_ADMIN/CAP_NET_RAW')
+    def test_transparent_without_privilege(self):
+        """Test EPERM from IP_TRANSPARENT names the missing capability"""
+
+        async def main():
+            with pytest.raises(PermissionError, match='CAP_NET_ADMIN'):
+                await _start('create_server', '127.0.0.1', transparent=True)
+
+        asyncio.run(main())
+
+    def test_create_connection_local_addr(self):
+        """Test local_addr= sets the source address of the upstream leg"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            accepted = loop.create_future()
+            server = await loop.create_server(lambda: Accepting(accepted), '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            client, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port, local_addr=('127.0.0.2', 0)
+            )
+            assert client.get_extra_info('sockname')[0] == '127.0.0.2'
+            transport = await asyncio.wait_for(accepted, 5)
+            assert transport.get_extra_info('peername')[0] == '127.0.0.2'
+            client.close()
+            server.close()
+
+        asyncio.run(main())
+
+    @needs_transparent
+    def test_create_connection_transparent(self):
+        """Test transparent=True is set on the outbound socket before it binds"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(asyncio.Protocol, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            client, _ = await loop.create_connection(
+                asyncio.Protocol,
+                '127.0.0.1',
+                port,
+                local_addr=('127.0.0.3', 0),
+                transparent=True,
+            )
+            sock = socket.socket(fileno=os.dup(client.get_extra_info('socket').fileno()))
+            with sock:
+                assert sock.getsockopt(SOL_IP, IP_TRANSPARENT) == 1
+            client.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_original_dst_without_redirect(self):
+        """Test original_dst falls back to the default for traffic that was not redirected"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            accepted = loop.create_future()
+            server = await loop.create_server(lambda: Accepting(accepted), '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            client, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            transport = await asyncio.wait_for(accepted, 5)
+            # With conntrack loaded an untranslated connection reports its own address
+            assert transport.get_extra_info('original_dst', 'none') in (
+                'none',
+                ('127.0.0.1', port),
+            )
+
+            reader, writer = await loop.open_connection('127.0.0.1', port)
+            assert writer.transport.get_extra_info('original_dst') in (
+                None,
+                ('127.0.0.1', port),
+            )
+            writer.close()
+            client.close()
+            server.close()
+
+        asyncio.run(main())
+
+    @pytest.mark.skipif(not _has_cap(CAP_NET_ADMIN), reason='needs CAP_NET_ADMIN')
+    @pytest.mark.skipif(
+        not (shutil.which('iptables') and os.environ.get('VELOXLOOP_TEST_IPTABLES')),
+        reason='changes iptables rules; set VELOXLOOP_TEST_IPTABLES=1 to run',
+    )
+    def test_original_dst_redirected(self):
+        """Test original_dst reports where a REDIRECTed connection was headed"""
+        target = ('192.0.2.10', 9)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            accepted = loop.create_future()
+            server = await loop.create_server(lambda: Accepting(accepted), '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            rule = [
+                'OUTPUT', '-t', 'nat', '-p', 'tcp', '-d', target[0],
+                '--dport', str(target[1]), '-j', 'REDIRECT', '--to-ports', str(port),
+            ]  # fmt: skip
+            subprocess.run(['iptables', '-A', *rule], check=True)
+            try:
+                client, _ = await loop.create_connection(asyncio.Protocol, *target)
+                transport = await asyncio.wait_for(accepted, 5)
+                assert transport.get_extra_info('original_dst') == target
+                client.close()
+            finally:
+                subprocess.run(['iptables', '-D', *rule], check=True)
+            server.close()
+
+        asyncio.run(main())
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
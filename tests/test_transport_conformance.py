@@ -0,0 +1,634 @@
+"""Conformance of every transport with its asyncio ABC.
+
+Each entry in KINDS opens one live transport of that kind. The tests check
+that the ABC's whole public API exists and that the simple cases behave like
+asyncio. A new transport gets checked by adding an opener and a Kind entry.
+"""
+
+import asyncio
+import io
+import socket
+import ssl
+import subprocess
+import threading
+from dataclasses import dataclass
+from pathlib import Path
+
+import pytest
+
+import veloxloop
+from veloxloop import _veloxloop
+
+SSL_CERT_DIR = Path(__file__).parent / 'ssl_certs'
+SERVER_CERT = str(SSL_CERT_DIR / 'server-cert.pem')
+SERVER_KEY = str(SSL_CERT_DIR / 'server-key.pem')
+
+
+class RecordingProtocol(
+    asyncio.Protocol, asyncio.DatagramProtocol, asyncio.SubprocessProtocol
+):
+    """Records what any kind of transport reports to it"""
+
+    def __init__(self):
+        self.transport = None
+        self.data = bytearray()
+        self.events = []
+
+    def connection_made(self, transport):
+        self.transport = transport
+        self.events.append('connection_made')
+
+    def data_received(self, data):
+        self.data += data
+
+    def datagram_received(self, data, addr):
+        self.data += data
+
+    def pipe_data_received(self, fd, data):
+        self.data += data
+
+    def eof_received(self):
+        self.events.append('eof_received')
+
+    def connection_lost(self, exc):
+        self.events.append('connection_lost')
+
+    def pipe_connection_lost(self, fd, exc):
+        self.events.append(('pipe_connection_lost', fd))
+
+    def process_exited(self):
+        self.events.append('process_exited')
+
+
+@dataclass
+class Conn:
+    """A live transport and how to observe it"""
+
+    transport: object
+    # What the transport reports events to, None when it has no protocol
+    protocol: object
+    # How many times the transport reported losing its connection
+    lost: object
+    # Wait for `n` bytes of echoed data and return them
+    echoed: object = None
+    cleanup: object = None
+
+
+async def _until(predicate, timeout=5):
+    async with asyncio.timeout(timeout):
+        while not predicate():
+            await asyncio.sleep(0.01)
+
+
+def _protocol_echo(protocol):
+    async def echoed(n):
+        await _until(lambda: len(protocol.data) >= n)
+        data = bytes(protocol.data[:n])
+        del protocol.data[:n]
+        return data
+
+    return echoed
+
+
+def _echo_peer(tls=False):
+    """A thread echoing one TCP (or TLS) connection until EOF; returns its port"""
+    listener = socket.create_server(('127.0.0.1', 0))
+    context = None
+    if tls:
+        context = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        context.load_cert_chain(SERVER_CERT, SERVER_KEY)
+
+    def serve():
+        with listener:
+            conn, _ = listener.accept()
+            try:
+                if context is not None:
+                    conn = context.wrap_socket(conn, server_side=True)
+                with conn:
+                    while data := conn.recv(65536):
+                        conn.sendall(data)
+            except (OSError, ssl.SSLError):
+                pass
+
+    threading.Thread(target=serve, daemon=True).start()
+    return listener.getsockname()[1]
+
+
+async def open_tcp():
+    loop = asyncio.get_running_loop()
+    port = _echo_peer()
+    transport, protocol = await loop.create_connection(
+        RecordingProtocol, '127.0.0.1', port
+    )
+    return Conn(
+        transport,
+        protocol,
+        lambda: protocol.events.count('connection_lost'),
+        _protocol_echo(protocol),
+    )
+
+
+async def open_ssl():
+    loop = asyncio.get_running_loop()
+    if not Path(SERVER_KEY).exists() or 'placeholder' in Path(SERVER_KEY).read_text():
+        pytest.skip('no test certificate')
+    port = _echo_peer(tls=True)
+    context = _veloxloop.SSLContext.create_client_context()
+    context.load_verify_locations(cafile=SERVER_CERT)
+    transport, protocol = await loop.create_connection(
+        RecordingProtocol, '127.0.0.1', port, ssl=context, server_hostname='localhost'
+    )
+    return Conn(
+        transport,
+        protocol,
+        lambda: protocol.events.count('connection_lost'),
+        _protocol_echo(protocol),
+    )
+
+
+async def open_stream():
+    loop = asyncio.get_running_loop()
+    port = _echo_peer()
+    reader, writer = await loop.open_connection('127.0.0.1', port)
+
+    async def echoed(n):
+        # The native StreamReader.read() hands back whatever is buffered
+        data = bytearray()
+        await _until(lambda: data.extend(reader.read(n - len(data))) or len(data) >= n)
+        return bytes(data)
+
+    # No protocol: a lost connection shows as EOF on the reader
+    return Conn(writer.transport, None, lambda: int(reader.at_eof()), echoed)
+
+
+async def open_udp():
+    loop = asyncio.get_running_loop()
+    peer = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
+    peer.bind(('127.0.0.1', 0))
+    transport, protocol = await loop.create_datagram_endpoint(
+        RecordingProtocol, remote_addr=peer.getsockname()
+    )
+    return Conn(
+        transport,
+        protocol,
+        lambda: protocol.events.count('connection_lost'),
+        cleanup=peer.close,
+    )
+
+
+async def _open_cat():
+    loop = asyncio.get_running_loop()
+    return await loop.subprocess_exec(
+        RecordingProtocol, 'cat', stderr=subprocess.DEVNULL
+    )
+
+
+async def open_subprocess():
+    transport, protocol = await _open_cat()
+    return Conn(
+        transport,
+        protocol,
+        lambda: protocol.events.count('connection_lost'),
+        cleanup=transport.close,
+    )
+
+
+def _open_pipe(fd):
+    async def opener():
+        transport, protocol = await _open_cat()
+        return Conn(
+            transport.get_pipe_transport(fd),
+            protocol,
+            lambda: protocol.events.count(('pipe_connection_lost', fd)),
+            _protocol_echo(protocol),
+            cleanup=transport.close,
+        )
+
+    return opener
+
+
+@dataclass
+class Kind:
+    open: object
+    abc: type
+    # is_reading()/pause_reading()/resume_reading() control incoming data
+    reads: bool = False
+    # Written data comes back through `Conn.echoed`
+    echoes: bool = False
+    # set_protocol() takes effect; otherwise it raises NotImplementedError
+    swaps_protocol: bool = True
+    # write_eof() half-closes a socket; False for kinds that never can
+    half_closes: bool = False
+    # get_extra_info() key -> type of the answer; keys in EXTRA_KEYS left
+    # out here give back the caller's default
+    extra: dict = None
+
+
+# The keys asyncio and uvloop transports answer between them
+EXTRA_KEYS = (
+    'socket',
+    'sockname',
+    'peername',
+    'pipe',
+    'compression',
+    'cipher',
+    'peercert',
+    'sslcontext',
+    'ssl_object',
+)
+SOCKET_EXTRA = {
+    'socket': _veloxloop.SocketWrapper,
+    'sockname': tuple,
+    'peername': tuple,
+}
+
+KINDS = {
+    'tcp': Kind(
+        open_tcp,
+        asyncio.Transport,
+        reads=True,
+        echoes=True,
+        half_closes=True,
+        extra=SOCKET_EXTRA,
+    ),
+    'ssl': Kind(
+        open_ssl,
+        asyncio.Transport,
+        reads=True,
+        echoes=True,
+        extra={
+            **SOCKET_EXTRA,
+            # 'compression' is never negotiated, so it's the default
+            'cipher': tuple,
+            'peercert': bytes,
+            'sslcontext': _veloxloop.SSLContext,
+        },
+    ),
+    'stream': Kind(
+        open_stream,
+        asyncio.Transport,
+        reads=True,
+        echoes=True,
+        swaps_protocol=False,
+        half_closes=True,
+        extra=SOCKET_EXTRA,
+    ),
+    'udp': Kind(
+        open_udp,
+        asyncio.DatagramTransport,
+        extra={**SOCKET_EXTRA, 'socket': _veloxloop.UdpSocketWrapper},
+    ),
+    'subprocess': Kind(open_subprocess, asyncio.SubprocessTransport),
+    'stdin_pipe': Kind(
+        _open_pipe(0),
+        asyncio.Transport,
+        swaps_protocol=False,
+        extra={'pipe': io.FileIO},
+    ),
+    'stdout_pipe': Kind(
+        _open_pipe(1),
+        asyncio.Transport,
+        reads=True,
+        swaps_protocol=False,
+        extra={'pipe': io.FileIO},
+    ),
+}
+
+
+def _abc_methods(abc):
+    return sorted(
+        name
+        for cls in abc.__mro__
+        for name, value in vars(cls).items()
+        if callable(value) and not name.startswith('_')
+    )
+
+
+def _run(kind_name, check):
+    """Open a transport of `kind_name` and run `await check(kind, conn)`"""
+    kind = KINDS[kind_name]
+
+    async def main():
+        conn = await kind.open()
+        try:
+            await check(kind, conn)
+        finally:
+            if not conn.transport.is_closing():
+                conn.transport.close()
+            if conn.cleanup is not None:
+                conn.cleanup()
+            await asyncio.sleep(0.01)
+
+    asyncio.run(main())
+
+
+ALL = list(KINDS)
+HALF_CLOSING = [k for k in ALL if KINDS[k].half_closes]
+
+
+class TestTransportConformance:
+    def setup_method(self):
+        veloxloop.install()
+
+    @pytest.mark.parametrize('kind', ALL)
+    def test_abc_methods_present(self, kind):
+        """Test every public method of the matching asyncio ABC is there"""
+
+        async def check(kind, conn):
+            missing = [
+                name
+                for name in _abc_methods(kind.abc)
+                if not callable(getattr(conn.transport, name, None))
+            ]
+            assert missing == []
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', ALL)
+    def test_extra_info_default(self, kind):
+        """Test get_extra_info falls back to the default for unknown keys"""
+
+        async def check(kind, conn):
+            assert conn.transport.get_extra_info('no-such-key') is None
+            assert conn.transport.get_extra_info('no-such-key', 'dflt') == 'dflt'
+            assert conn.transport.is_closing() is False
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', ALL)
+    def test_extra_info_keys(self, kind):
+        """Test which of the common extra-info keys each kind answers, and
+        with what type; the rest give back the caller's default"""
+
+        async def check(kind, conn):
+            default = object()
+            expected = kind.extra or {}
+            for key in EXTRA_KEYS:
+                value = conn.transport.get_extra_info(key, default)
+                if key in expected:
+                    assert isinstance(value, expected[key]), key
+                else:
+                    assert value is default, key
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', ['stdin_pipe', 'stdout_pipe'])
+    def test_extra_info_pipe_is_cached(self, kind):
+        """Test 'pipe' is one file over the transport's fd, closed with it"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            pipe = t.get_extra_info('pipe')
+            assert t.get_extra_info('pipe') is pipe
+            assert pipe.fileno() == t.fileno()
+            t.close()
+            assert pipe.closed
+            assert t.get_extra_info('pipe') is None
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', ['tcp', 'ssl', 'stream'])
+    def test_extra_info_socket_is_live(self, kind):
+        """Test 'socket' is one wrapper per transport that reads the socket
+        when asked and stops working once the transport closes"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            sock = t.get_extra_info('socket')
+            assert t.get_extra_info('socket') is sock
+            assert sock.getsockname() == t.get_extra_info('sockname')
+            assert sock.getpeername() == t.get_extra_info('peername')
+            assert sock.family == socket.AF_INET
+            assert sock.type == socket.SOCK_STREAM
+            fd = sock.fileno()
+            assert fd >= 0
+            for value in (1, 0):
+                sock.setsockopt(socket.IPPROTO_TCP, socket.TCP_NODELAY, value)
+                nodelay = sock.getsockopt(socket.IPPROTO_TCP, socket.TCP_NODELAY)
+                assert bool(nodelay) is bool(value)
+            t.abort()
+            await _until(lambda: conn.lost() == 1)
+            assert sock.fileno() == -1
+            with pytest.raises(OSError):
+                sock.getsockname()
+            with pytest.raises(OSError):
+                sock.getsockopt(socket.IPPROTO_TCP, socket.TCP_NODELAY)
+            assert t.get_extra_info('socket') is None
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', [k for k in ALL if KINDS[k].reads])
+    def test_is_reading_follows_pause_and_resume(self, kind):
+        """Test is_reading follows idempotent pause/resume and ends on close"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            assert t.is_reading() is True
+            t.pause_reading()
+            t.pause_reading()
+            assert t.is_reading() is False
+            t.resume_reading()
+            t.resume_reading()
+            assert t.is_reading() is True
+            t.close()
+            assert t.is_reading() is False
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', [k for k in ALL if KINDS[k].echoes])
+    def test_paused_reads_wait_for_resume(self, kind):
+        """Test nothing is delivered while paused and everything after resuming"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            t.pause_reading()
+            t.write(b'held')
+            await asyncio.sleep(0.1)
+            t.resume_reading()
+            assert await conn.echoed(4) == b'held'
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', [k for k in ALL if KINDS[k].echoes])
+    def test_writelines_joins_buffers(self, kind):
+        """Test writelines takes any iterable of bytes-like objects"""
+
+        async def check(kind, conn):
+            chunks = [b'one ', bytearray(b'two '), memoryview(b'three')]
+            conn.transport.writelines(iter(chunks))
+            assert await conn.echoed(13) == b'one two three'
+            conn.transport.writelines([])
+            conn.transport.write(b'!')
+            assert await conn.echoed(1) == b'!'
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', ALL)
+    def test_get_and_set_protocol(self, kind):
+        """Test set_protocol swaps the protocol, or refuses where there is none"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            assert t.get_protocol() is conn.protocol
+            replacement = RecordingProtocol()
+            if not kind.swaps_protocol:
+                with pytest.raises(NotImplementedError):
+                    t.set_protocol(replacement)
+                assert t.get_protocol() is conn.protocol
+                return
+
+            t.set_protocol(replacement)
+            assert t.get_protocol() is replacement
+            if kind.echoes:
+                t.write(b'to the new one')
+                await _until(lambda: len(replacement.data) >= 14)
+                assert replacement.data == b'to the new one'
+                assert conn.protocol.data == b''
+            t.set_protocol(conn.protocol)
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', [k for k in ALL if 'abort' in dir(KINDS[k].abc)])
+    def test_abort_loses_connection_once(self, kind):
+        """Test abort closes at once and reports the loss exactly once"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            if kind.echoes:
+                t.write(b'dropped' * 1000)
+            t.abort()
+            assert t.is_closing() is True
+            t.abort()
+            t.close()
+            await asyncio.sleep(0.05)
+            assert conn.lost() == 1
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize(
+        'kind', [k for k in ALL if 'can_write_eof' in dir(KINDS[k].abc)]
+    )
+    def test_can_write_eof_is_bool(self, kind):
+        """Test can_write_eof answers rather than raising NotImplementedError"""
+
+        async def check(kind, conn):
+            assert isinstance(conn.transport.can_write_eof(), bool)
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', HALF_CLOSING)
+    def test_write_eof_matrix(self, kind):
+        """Test write_eof flips can_write_eof, then refuses writes and a second
+        write_eof, while the read side keeps delivering"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            assert t.can_write_eof() is True
+            t.write(b'before eof')
+            t.write_eof()
+            assert t.can_write_eof() is False
+            assert 'EOF_WRITTEN' in t.get_state()
+            with pytest.raises(RuntimeError):
+                t.write(b'after eof')
+            with pytest.raises(RuntimeError):
+                t.write_eof()
+            assert await conn.echoed(10) == b'before eof'
+            # The peer closes once it has seen our EOF
+            await _until(lambda: conn.lost() == 1)
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('kind', HALF_CLOSING)
+    def test_write_eof_after_close_raises(self, kind):
+        """Test a closing transport can't write EOF and says so"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            t.close()
+            assert t.can_write_eof() is False
+            with pytest.raises(RuntimeError):
+                t.write_eof()
+
+        _run(kind, check)
+
+    @pytest.mark.parametrize('opener', ['create_connection', 'open_connection'])
+    def test_half_close_end_to_end(self, opener):
+        """Test a peer sees every byte buffered before write_eof() and then
+        EOF, its reply still arrives, and close() right after write_eof()
+        flushes before closing"""
+        payload = b'x' * (4 * 1024 * 1024)
+        listener = socket.create_server(('127.0.0.1', 0))
+        port = listener.getsockname()[1]
+        counts = []
+
+        def serve():
+            with listener:
+                for reply in (True, False):
+                    conn, _ = listener.accept()
+                    with conn:
+                        total = 0
+                        while data := conn.recv(65536):
+                            total += len(data)
+                        counts.append(total)
+                        if reply:
+                            conn.sendall(b'got %d' % total)
+
+        thread = threading.Thread(target=serve, daemon=True)
+        thread.start()
+
+        async def connect():
+            loop = asyncio.get_running_loop()
+            if opener == 'open_connection':
+                reader, writer = await loop.open_connection('127.0.0.1', port)
+                return writer.transport, reader.read
+            transport, protocol = await loop.create_connection(
+                RecordingProtocol, '127.0.0.1', port
+            )
+            return transport, protocol.data
+
+        async def main():
+            t, read = await connect()
+            t.write(payload)
+            t.write_eof()
+            expected = b'got %d' % len(payload)
+            if callable(read):
+                got = bytearray()
+                await _until(lambda: got.extend(read(64)) or len(got) >= len(expected))
+            else:
+                await _until(lambda: len(read) >= len(expected))
+                got = read
+            assert bytes(got) == expected
+            t.close()
+
+            t, _ = await connect()
+            t.write(payload)
+            t.write_eof()
+            t.close()
+            assert t.is_closing() is True
+            with pytest.raises(RuntimeError):
+                t.write_eof()
+            await _until(lambda: len(counts) == 2, timeout=10)
+
+        asyncio.run(main())
+        thread.join(5)
+        assert counts == [len(payload), len(payload)]
+
+    @pytest.mark.parametrize('kind', ['ssl', 'udp'])
+    def test_no_half_close(self, kind):
+        """Test TLS and datagram transports never offer a half-close, and TLS
+        refuses write_eof like asyncio's"""
+
+        async def check(kind, conn):
+            t = conn.transport
+            assert t.can_write_eof() is False
+            if kind.abc is asyncio.Transport:
+                with pytest.raises(NotImplementedError):
+                    t.write_eof()
+                t.write(b'still open')
+                assert await conn.echoed(10) == b'still open'
+
+        _run(kind, check)
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,173 @@
+"""Tests for loop.set_transport_factory() and TransportFactoryConfig"""
+
+import asyncio
+import socket
+
+import pytest
+
+import veloxloop
+
+
+class Echo(asyncio.Protocol):
+    def connection_made(self, transport):
+        self.transport = transport
+
+    def data_received(self, data):
+        self.transport.write(data)
+
+
+class CountingTransport:
+    """Wraps a native transport and counts the bytes written through it"""
+
+    def __init__(self, inner):
+        self._inner = inner
+        self.written = 0
+
+    def write(self, data):
+        self.written += len(data)
+        self._inner.write(data)
+
+    def _read_ready(self):
+        self._inner._read_ready()
+
+    def __getattr__(self, name):
+        return getattr(self._inner, name)
+
+
+async def _echo_roundtrip(port, payload):
+    reader, writer = await asyncio.open_connection('127.0.0.1', port)
+    writer.write(payload)
+    data = await reader.readexactly(len(payload))
+    writer.close()
+    return data
+
+
+class TestTransportFactory:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_default_path_unchanged(self):
+        """Test without a factory the loop keeps creating native transports"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            assert loop.get_transport_factory() is None
+            server = await loop.create_server(Echo, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port
+            )
+            assert type(transport) is veloxloop._veloxloop.TcpTransport
+            transport.close()
+
+            config = veloxloop.TransportFactoryConfig()
+            loop.set_transport_factory(config)
+            assert loop.get_transport_factory() is config
+            loop.set_transport_factory(None)
+            assert loop.get_transport_factory() is None
+            with pytest.raises(TypeError):
+                loop.set_transport_factory(42)
+            server.close()
+
+        asyncio.run(main())
+
+    def test_wrapping_factory_sees_all_server_connections(self):
+        """Test a Python factory wrapping the native transport counts every byte"""
+        native = veloxloop.TransportFactoryConfig()
+        made = []
+
+        def factory(kind, loop, sock_fd, protocol, extra):
+            transport = CountingTransport(native(kind, loop, sock_fd, protocol, extra))
+            made.append((kind, extra, transport))
+            return transport
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_transport_factory(factory)
+            server = await loop.create_server(Echo, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            payloads = [bytes([i]) * (1000 * (i + 1)) for i in range(5)]
+            # open_connection's client transports come from the factory as well
+            results = await asyncio.gather(
+                *(_echo_roundtrip(port, payload) for payload in payloads)
+            )
+            assert results == payloads
+            server.close()
+            return port, payloads
+
+        port, payloads = asyncio.run(main())
+        assert all(kind == 'tcp' for kind, _, _ in made)
+        served = [t for _, extra, t in made if extra['sockname'][1] == port]
+        clients = [t for _, extra, t in made if extra['peername'][1] == port]
+        assert len(served) == len(clients) == len(payloads)
+        assert sum(t.written for t in served) == sum(map(len, payloads))
+        assert sorted(t.written for t in clients) == sorted(map(len, payloads))
+
+    def test_config_tunes_native_transports(self):
+        """Test TransportFactoryConfig settings land on the native transports"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_transport_factory(
+                veloxloop.TransportFactoryConfig(
+                    nodelay=False,
+                    keepalive=True,
+                    read_chunk_size=16384,
+                    write_buffer_high=1024,
+                )
+            )
+            server = await loop.create_server(Echo, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port
+            )
+            assert type(transport) is veloxloop._veloxloop.TcpTransport
+            sock = transport.get_extra_info('socket')
+            assert sock.getsockopt(socket.IPPROTO_TCP, socket.TCP_NODELAY) == 0
+            assert sock.getsockopt(socket.SOL_SOCKET, socket.SO_KEEPALIVE) == 1
+            assert transport.get_read_chunk_size() == 16384
+            assert transport.get_write_buffer_limits() == (256, 1024)
+            transport.close()
+            server.close()
+
+        asyncio.run(main())
+
+        with pytest.raises(ValueError):
+            veloxloop.TransportFactoryConfig(write_buffer_high=1, write_buffer_low=2)
+        with pytest.raises(ValueError):
+            veloxloop.TransportFactoryConfig(read_chunk_size=1000)
+
+    def test_datagram_endpoint_uses_factory(self):
+        """Test create_datagram_endpoint goes through a Python factory too"""
+        native = veloxloop.TransportFactoryConfig()
+        kinds = []
+
+        def factory(kind, loop, sock_fd, protocol, extra):
+            kinds.append((kind, extra['peername']))
+            return native(kind, loop, sock_fd, protocol, extra)
+
+        class Receiver(asyncio.DatagramProtocol):
+            def __init__(self):
+                self.received = asyncio.get_running_loop().create_future()
+
+            def datagram_received(self, data, addr):
+                self.received.set_result(data)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_transport_factory(factory)
+            transport, protocol = await loop.create_datagram_endpoint(
+                Receiver, local_addr=('127.0.0.1', 0)
+            )
+            assert type(transport) is veloxloop._veloxloop.UdpTransport
+            with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as sender:
+                sender.sendto(b'ping', transport.get_extra_info('sockname'))
+                assert await asyncio.wait_for(protocol.received, 5) == b'ping'
+            transport.close()
+
+        asyncio.run(main())
+        assert kinds == [('udp', None)]
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -106,6 +106,37 @@ class TestTransportMethods:
 
         asyncio.run(run_test())
 
+    def test_tcp_info(self):
+        """Test get_extra_info('tcp_info') and get_rtt() on a loopback connection."""
+
+        async def run_test():
+            server = await asyncio.start_server(lambda r, w: None, '127.0.0.1', 0)
+            _, port = server.sockets[0].getsockname()
+            transport, _ = await asyncio.get_event_loop().create_connection(
+                SimpleProtocol, '127.0.0.1', port
+            )
+            transport.write(b'ping')
+            await asyncio.sleep(0.01)
+
+            info = transport.get_extra_info('tcp_info')
+            assert set(info) >= {'rtt', 'rttvar', 'snd_cwnd', 'retrans', 'total_retrans', 'state'}
+            assert info['state'] == 1  # TCP_ESTABLISHED
+            assert info['snd_cwnd'] > 0
+            # Loopback RTT is microseconds, well under a second
+            assert 0 < info['rtt'] < 1_000_000
+
+            rtt = transport.get_rtt()
+            assert isinstance(rtt, float)
+            assert 0 < rtt < 1
+
+            transport.close()
+            assert transport.get_extra_info('tcp_info', 'gone') == 'gone'
+            assert transport.get_rtt() is None
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(run_test())
+
     def test_set_write_buffer_limits(self):
         """Test set_write_buffer_limits configuration."""
 
@@ -127,21 +158,38 @@ class TestTransportMethods:
                 lambda: client_protocol, '127.0.0.1', port
             )
 
-            # Test setting write buffer limits
+            # asyncio's defaults
+            assert transport.get_write_buffer_limits() == (16 * 1024, 64 * 1024)
+
             transport.set_write_buffer_limits(high=128 * 1024, low=32 * 1024)
+            assert transport.get_write_buffer_limits() == (32 * 1024, 128 * 1024)
 
-            # Test with only high
+            # low defaults to high / 4
             transport.set_write_buffer_limits(high=256 * 1024)
+            assert transport.get_write_buffer_limits() == (64 * 1024, 256 * 1024)
 
-            # Test with only low
+            # high defaults to 4 * low
             transport.set_write_buffer_limits(low=16 * 1024)
+            assert transport.get_write_buffer_limits() == (16 * 1024, 64 * 1024)
 
-            # Test with neither (should use defaults)
             transport.set_write_buffer_limits()
+            assert transport.get_write_buffer_limits() == (16 * 1024, 64 * 1024)
+
+            transport.set_write_buffer_limits(high=0, low=0)
+            assert transport.get_write_buffer_limits() == (0, 0)
+
+            transport.set_write_buffer_limits(high=1024, low=1024)
+            assert transport.get_write_buffer_limits() == (1024, 1024)
 
-            # Test invalid limits (low >= high)
             with pytest.raises(ValueError):  # noqa: PT011
                 transport.set_write_buffer_limits(high=1024, low=2048)
+            with pytest.raises(ValueError):  # noqa: PT011
+                transport.set_write_buffer_limits(high=0, low=1)
+            with pytest.raises(ValueError):  # noqa: PT011
+                transport.set_write_buffer_limits(high=-1)
+            with pytest.raises(ValueError):  # noqa: PT011
+                transport.set_write_buffer_limits(high=1024, low=-1)
+            assert transport.get_write_buffer_limits() == (1024, 1024)
 
             # Cleanup
             transport.close()
@@ -150,6 +198,29 @@ class TestTransportMethods:
 
         asyncio.run(run_test())
 
+    def test_stream_transport_write_buffer_limits(self):
+        """Test the streams-API transport maps the limits onto its StreamWriter"""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            server = await loop.start_server(lambda r, w: None, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            _, writer = await loop.open_connection('127.0.0.1', port)
+            transport = writer.transport
+
+            assert transport.get_write_buffer_limits() == (16 * 1024, 64 * 1024)
+            transport.set_write_buffer_limits(low=1000)
+            assert transport.get_write_buffer_limits() == (1000, 4000)
+            transport.set_write_buffer_limits(high=0, low=0)
+            assert transport.get_write_buffer_limits() == (0, 0)
+            with pytest.raises(ValueError):  # noqa: PT011
+                transport.set_write_buffer_limits(high=-1)
+
+            transport.close()
+            server.close()
+
+        asyncio.run(run_test())
+
     def test_get_write_buffer_size(self):
         """Test get_write_buffer_size method."""
 
@@ -181,6 +252,168 @@ class TestTransportMethods:
 
         asyncio.run(run_test())
 
+    def test_set_protocol_redirects_callbacks(self):
+        """Test data after set_protocol() reaches the new protocol's data_received"""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            second = SimpleProtocol()
+            second_data = loop.create_future()
+            second.data_received = second_data.set_result
+            first_data = []
+
+            class Handoff(SimpleProtocol):
+                def data_received(self, data):
+                    first_data.append(data)
+                    self.transport.set_protocol(second)
+
+            server = await loop.create_server(Handoff, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            reader, writer = await asyncio.open_connection('127.0.0.1', port)
+
+            writer.write(b'one')
+            await writer.drain()
+            while not first_data:
+                await asyncio.sleep(0.01)
+            writer.write(b'two')
+            await writer.drain()
+            assert await asyncio.wait_for(second_data, 5) == b'two'
+            assert first_data == [b'one']
+
+            writer.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(run_test())
+
+    def test_flow_control_pause_and_resume(self):
+        """Test pause_writing/resume_writing fire around the buffer limits"""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            peer = loop.create_future()
+
+            class Stalled(SimpleProtocol):
+                def connection_made(self, transport):
+                    transport.pause_reading()
+                    peer.set_result(transport)
+
+            server = await loop.create_server(Stalled, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            client = SimpleProtocol()
+            transport, _ = await loop.create_connection(
+                lambda: client, '127.0.0.1', port
+            )
+            server_transport = await peer
+            transport.set_write_buffer_limits(high=64 * 1024, low=16 * 1024)
+
+            for _ in range(128):
+                transport.write(b'x' * 64 * 1024)
+                if client.pause_writing_called:
+                    break
+            assert client.pause_writing_called
+            assert not client.resume_writing_called
+
+            server_transport.resume_reading()
+            for _ in range(500):
+                if client.resume_writing_called:
+                    break
+                await asyncio.sleep(0.01)
+            assert client.resume_writing_called
+            assert transport.get_write_buffer_size() <= 16 * 1024
+
+            transport.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(run_test())
+
+    def test_pause_reading_from_data_received(self):
+        """Test pause_reading() called inside data_received stops delivery
+        right there, even with more data already waiting on the socket"""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            peer = loop.create_future()
+            chunks = []
+
+            class Pausing(SimpleProtocol):
+                def connection_made(self, transport):
+                    super().connection_made(transport)
+                    transport.set_read_chunk_size(1024)
+                    peer.set_result(transport)
+
+                def data_received(self, data):
+                    chunks.append(data)
+                    self.transport.pause_reading()
+
+            server = await loop.create_server(Pausing, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(
+                SimpleProtocol, '127.0.0.1', port
+            )
+            server_transport = await peer
+            payload = bytes(range(256)) * 64
+            transport.write(payload)
+            await asyncio.sleep(0.05)
+
+            for expected in range(1, 6):
+                await asyncio.sleep(0.05)
+                assert len(chunks) == expected
+                assert not server_transport.is_reading()
+                server_transport.resume_reading()
+            while sum(map(len, chunks)) < len(payload):
+                await asyncio.sleep(0.01)
+                server_transport.resume_reading()
+            assert b''.join(chunks) == payload
+
+            transport.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(run_test())
+
+    def test_missing_optional_protocol_methods(self):
+        """Test protocols without flow control or eof methods are simply skipped"""
+
+        async def run_test():
+            loop = asyncio.get_running_loop()
+            errors = []
+            loop.set_exception_handler(lambda _loop, context: errors.append(context))
+            peer = loop.create_future()
+
+            class Stalled(SimpleProtocol):
+                def connection_made(self, transport):
+                    transport.pause_reading()
+                    peer.set_result(transport)
+
+            class Bare:
+                def connection_made(self, transport):
+                    self.transport = transport
+
+                def data_received(self, data):
+                    pass
+
+                def connection_lost(self, exc):
+                    pass
+
+            server = await loop.create_server(Stalled, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(Bare, '127.0.0.1', port)
+            server_transport = await peer
+            transport.set_write_buffer_limits(high=1024)
+            for _ in range(64):
+                transport.write(b'x' * 64 * 1024)
+            server_transport.resume_reading()
+            await asyncio.sleep(0.1)
+            assert errors == []
+
+            transport.close()
+            server.close()
+            await server.wait_closed()
+
+        asyncio.run(run_test())
+
 
 if __name__ == '__main__':
     pytest.main([__file__, '-v'])
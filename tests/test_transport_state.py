@@ -0,0 +1,121 @@
+"""Tests for the constants submodule and transport state introspection"""
+
+import asyncio
+import enum
+import importlib
+import re
+
+import pytest
+
+import veloxloop
+from veloxloop import _veloxloop
+
+REPR = re.compile(r'<(\w+) fd=(\d+) state=([A-Z_|0]+) buf=(\d+)>')
+
+
+class _Sink(asyncio.Protocol):
+    def data_received(self, data):
+        pass
+
+
+class TestConstants:
+    def test_defaults(self):
+        """Test the documented defaults are exported"""
+        from veloxloop._veloxloop import constants
+
+        assert constants.DEFAULT_HIGH == 64 * 1024
+        assert constants.DEFAULT_LOW == 16 * 1024
+        assert constants.DEFAULT_LIMIT == 128 * 1024
+        assert constants.DEFAULT_READ_CHUNK_SIZE == 256 * 1024
+        assert constants.MIN_READ_CHUNK_SIZE <= constants.DEFAULT_READ_CHUNK_SIZE
+        assert constants.DEFAULT_READ_CHUNK_SIZE <= constants.MAX_READ_CHUNK_SIZE
+        assert constants.SQ_SIZE > 0
+        assert constants.CQ_SIZE >= constants.SQ_SIZE
+        assert importlib.import_module('veloxloop._veloxloop.constants') is constants
+
+    def test_transport_state_flag(self):
+        """Test the state bits come as an IntFlag and a plain mapping"""
+        from veloxloop._veloxloop.constants import TRANSPORT_STATE_BITS, TransportState
+
+        assert issubclass(TransportState, enum.IntFlag)
+        assert TransportState.ACTIVE == 1
+        for name, bit in TRANSPORT_STATE_BITS.items():
+            assert TransportState[name] == bit
+            assert bin(bit).count('1') == 1
+        assert {'ACTIVE', 'CLOSING', 'CLOSED', 'READING_PAUSED'} <= set(
+            TRANSPORT_STATE_BITS
+        )
+
+
+class TestTransportState:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_tcp_state_follows_transport(self):
+        """Test get_state() and the repr track pause_reading() and close()"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server = await loop.create_server(_Sink, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            transport, _ = await loop.create_connection(_Sink, '127.0.0.1', port)
+            states = [transport.get_state()]
+            transport.pause_reading()
+            states.append(transport.get_state())
+            text = repr(transport)
+            transport.close()
+            states.append(transport.get_state())
+            server.close()
+            await server.wait_closed()
+            return states, text, transport.fileno()
+
+        states, text, fd = asyncio.run(main())
+        assert states[0] == frozenset({'ACTIVE'})
+        assert states[1] == frozenset({'ACTIVE', 'READING_PAUSED'})
+        assert 'CLOSED' in states[2] and 'ACTIVE' not in states[2]
+        kind, repr_fd, state, buf = REPR.fullmatch(text).groups()
+        assert kind == 'TcpTransport'
+        assert int(repr_fd) == fd
+        assert state == 'ACTIVE|READING_PAUSED'
+        assert buf == '0'
+
+    def test_stream_and_udp_transports(self):
+        """Test the stream and datagram transports expose their state too"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+
+            async def handle(reader, writer):
+                writer.close()
+
+            server = await loop.start_server(handle, '127.0.0.1', 0)
+            port = server.sockets[0].getsockname()[1]
+            _, writer = await loop.open_connection('127.0.0.1', port)
+            stream_state = writer.transport.get_state()
+            stream_repr = repr(writer.transport)
+            writer.close()
+            server.close()
+            await server.wait_closed()
+
+            udp, _ = await loop.create_datagram_endpoint(
+                asyncio.DatagramProtocol, local_addr=('127.0.0.1', 0)
+            )
+            udp_state = udp.get_state()
+            udp_repr = repr(udp)
+            udp.close()
+            return stream_state, stream_repr, udp_state, udp_repr, udp.get_state()
+
+        stream_state, stream_repr, udp_state, udp_repr, closed = asyncio.run(main())
+        assert 'ACTIVE' in stream_state
+        assert REPR.fullmatch(stream_repr).group(1) == 'StreamTransport'
+        assert udp_state == frozenset({'ACTIVE'})
+        assert REPR.fullmatch(udp_repr).groups()[::2] == ('UdpTransport', 'ACTIVE')
+        assert 'ACTIVE' not in closed
+
+    def test_ssl_transport_has_state(self):
+        """Test the TLS transport offers the same introspection"""
+        assert callable(_veloxloop.SSLTransport.get_state)
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -0,0 +1,318 @@
+"""Tests for transport.set_timeouts() read/write idle timeouts"""
+
+import asyncio
+import os
+import resource
+import socket
+import ssl
+import threading
+import time
+from pathlib import Path
+
+import pytest
+
+import veloxloop
+from veloxloop import _veloxloop
+
+SSL_CERT_DIR = Path(__file__).parent / 'ssl_certs'
+SERVER_CERT = str(SSL_CERT_DIR / 'server-cert.pem')
+SERVER_KEY = str(SSL_CERT_DIR / 'server-key.pem')
+
+
+class TimedServerProtocol(asyncio.Protocol):
+    """Arms `timeouts` on connection and records when and how it was lost"""
+
+    def __init__(self, lost, timeouts):
+        self.lost = lost
+        self.timeouts = timeouts
+
+    def connection_made(self, transport):
+        self.transport = transport
+        transport.set_timeouts(**self.timeouts)
+
+    def connection_lost(self, exc):
+        self.lost.append((time.monotonic(), exc))
+
+
+async def _timed_server(**timeouts):
+    """A server arming `timeouts` on every connection; returns (server, port, lost)"""
+    loop = asyncio.get_running_loop()
+    lost = []
+    server = await loop.create_server(
+        lambda: TimedServerProtocol(lost, timeouts), '127.0.0.1', 0
+    )
+    return server, server.sockets[0].getsockname()[1], lost
+
+
+async def _until(predicate, timeout=5):
+    deadline = time.monotonic() + timeout
+    while not predicate():
+        assert time.monotonic() < deadline, 'timed out waiting'
+        await asyncio.sleep(0.01)
+
+
+class TestTransportTimeouts:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_idle_client_closed_active_client_kept(self):
+        """Test read=0.2 closes a silent client in ~0.2-0.4s but not a chatty one"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server, port, lost = await _timed_server(read=0.2)
+            idle, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            active, _ = await loop.create_connection(
+                asyncio.Protocol, '127.0.0.1', port
+            )
+            start = time.monotonic()
+            while time.monotonic() - start < 0.8:
+                active.write(b'ping')
+                await asyncio.sleep(0.05)
+            # Only the idle client went
+            assert len(lost) == 1
+            assert not active.is_closing()
+            for transport in (idle, active):
+                transport.close()
+            server.close()
+            return start, lost[0]
+
+        start, (closed_at, exc) = asyncio.run(main())
+        assert isinstance(exc, TimeoutError)
+        assert 0.2 <= closed_at - start <= 0.4
+
+    def test_get_and_disarm(self):
+        """Test get_timeouts reports the settings and None for both disarms"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server, port, lost = await _timed_server()
+            client, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            assert client.get_timeouts() == (None, None)
+            client.set_timeouts(read=0.1, write=2)
+            assert client.get_timeouts() == (0.1, 2.0)
+            client.set_timeouts()
+            assert client.get_timeouts() == (None, None)
+            await asyncio.sleep(0.5)
+            assert not client.is_closing()
+            assert not lost
+            client.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_invalid_values(self):
+        """Test zero, negative and non-finite timeouts are rejected"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            server, port, _ = await _timed_server()
+            client, _ = await loop.create_connection(asyncio.Protocol, '127.0.0.1', port)
+            for bad in (0, -1, float('inf'), float('nan')):
+                with pytest.raises(ValueError):
+                    client.set_timeouts(read=bad)
+                with pytest.raises(ValueError):
+                    client.set_timeouts(write=bad)
+            assert client.get_timeouts() == (None, None)
+            client.close()
+            server.close()
+
+        asyncio.run(main())
+
+    def test_paused_reading_does_not_time_out(self):
+        """Test silence while reading is paused doesn't count against read"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            lost = loop.create_future()
+
+            class Protocol(asyncio.Protocol):
+                def connection_lost(self, exc):
+                    lost.set_result(exc)
+
+            listener = socket.create_server(('127.0.0.1', 0))
+            client, _ = await loop.create_connection(
+                Protocol, '127.0.0.1', listener.getsockname()[1]
+            )
+            peer, _ = listener.accept()
+            listener.close()
+            client.set_timeouts(read=0.2)
+            client.pause_reading()
+            await asyncio.sleep(0.5)
+            assert not lost.done()
+            start = time.monotonic()
+            client.resume_reading()
+            exc = await asyncio.wait_for(lost, 5)
+            elapsed = time.monotonic() - start
+            peer.close()
+            return exc, elapsed
+
+        exc, elapsed = asyncio.run(main())
+        assert isinstance(exc, TimeoutError)
+        assert 0.2 <= elapsed <= 0.4
+
+    def test_write_timeout_with_stalled_peer(self):
+        """Test write= closes once a peer that never reads blocks buffered writes"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            lost = loop.create_future()
+
+            class Protocol(asyncio.Protocol):
+                def connection_lost(self, exc):
+                    lost.set_result(exc)
+
+            listener = socket.create_server(('127.0.0.1', 0))
+            client, _ = await loop.create_connection(
+                Protocol, '127.0.0.1', listener.getsockname()[1]
+            )
+            peer, _ = listener.accept()
+            listener.close()
+            client.set_timeouts(write=0.3)
+            # An empty buffer never times out
+            await asyncio.sleep(0.5)
+            assert not lost.done()
+            client.write(b'x' * (64 * 1024 * 1024))
+            assert client.get_write_buffer_size() > 0
+            exc = await asyncio.wait_for(lost, 5)
+            peer.close()
+            return exc
+
+        exc = asyncio.run(main())
+        assert isinstance(exc, TimeoutError)
+        assert 'no progress' in str(exc)
+
+    def test_stream_transport(self):
+        """Test a StreamTransport read timeout fails the pending read and closes"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            listener = socket.create_server(('127.0.0.1', 0))
+            reader, writer = await loop.open_connection(
+                '127.0.0.1', listener.getsockname()[1]
+            )
+            peer, _ = listener.accept()
+            listener.close()
+            writer.transport.set_timeouts(read=0.2)
+            assert writer.transport.get_timeouts() == (0.2, None)
+            start = time.monotonic()
+            with pytest.raises(Exception, match='No data received'):
+                await asyncio.wait_for(reader.readline(), 5)
+            elapsed = time.monotonic() - start
+            assert writer.transport.is_closing()
+            peer.close()
+            return elapsed
+
+        elapsed = asyncio.run(main())
+        assert 0.2 <= elapsed <= 0.4
+
+    def test_ssl_transport(self):
+        """Test an SSL transport's read timeout closes it with TimeoutError"""
+        if 'placeholder' in Path(SERVER_KEY).read_text():
+            pytest.skip('no test certificate')
+        listener = socket.create_server(('127.0.0.1', 0))
+        context = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+        context.load_cert_chain(SERVER_CERT, SERVER_KEY)
+        done = threading.Event()
+
+        def silent_peer():
+            # Completes the handshake, then sends nothing
+            with listener:
+                conn, _ = listener.accept()
+                with context.wrap_socket(conn, server_side=True):
+                    done.wait(5)
+
+        threading.Thread(target=silent_peer, daemon=True).start()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            lost = loop.create_future()
+
+            class Protocol(asyncio.Protocol):
+                def connection_lost(self, exc):
+                    lost.set_result(exc)
+
+            client_context = _veloxloop.SSLContext.create_client_context()
+            client_context.load_verify_locations(cafile=SERVER_CERT)
+            client, _ = await loop.create_connection(
+                Protocol,
+                '127.0.0.1',
+                listener.getsockname()[1],
+                ssl=client_context,
+                server_hostname='localhost',
+            )
+            client.set_timeouts(read=0.2)
+            assert client.get_timeouts() == (0.2, None)
+            start = time.monotonic()
+            exc = await asyncio.wait_for(lost, 5)
+            return exc, time.monotonic() - start
+
+        try:
+            exc, elapsed = asyncio.run(main())
+        finally:
+            done.set()
+        assert isinstance(exc, TimeoutError)
+        assert 0.2 <= elapsed <= 0.4
+
+    def test_many_connections_overhead(self):
+        """Test thousands of armed connections leave the loop iterating and
+        none of them timed out early"""
+        soft, hard = resource.getrlimit(resource.RLIMIT_NOFILE)
+        if soft < hard:
+            resource.setrlimit(resource.RLIMIT_NOFILE, (hard, hard))
+        # Two descriptors a connection, within what the process has left,
+        # and some headroom for the loop
+        in_use = len(os.listdir('/proc/self/fd'))
+        count = min(10_000, (hard - in_use - 256) // 2)
+        if count < 1000:
+            resource.setrlimit(resource.RLIMIT_NOFILE, (soft, hard))
+            pytest.skip('descriptor limit too low')
+
+        async def spin(n):
+            for _ in range(n):
+                await asyncio.sleep(0)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transports = []
+
+            class Accepted(asyncio.Protocol):
+                def connection_made(self, transport):
+                    transports.append(transport)
+
+            server = await loop.create_server(Accepted, '127.0.0.1', 0, backlog=1024)
+            port = server.sockets[0].getsockname()[1]
+            clients = []
+            try:
+                for start in range(0, count, 500):
+                    for _ in range(start, min(start + 500, count)):
+                        client = socket.socket()
+                        clients.append(client)
+                        client.setblocking(False)
+                        client.connect_ex(('127.0.0.1', port))
+                    await _until(lambda: len(transports) == len(clients))
+                for transport in transports:
+                    transport.set_timeouts(read=60, write=60)
+                await spin(2000)
+                armed = [transport.get_timeouts() for transport in transports]
+                closing = sum(transport.is_closing() for transport in transports)
+            finally:
+                server.close()
+                for transport in transports:
+                    transport.close()
+                for client in clients:
+                    client.close()
+                await asyncio.sleep(0.1)
+            return armed, closing
+
+        try:
+            armed, closing = asyncio.run(main())
+        finally:
+            resource.setrlimit(resource.RLIMIT_NOFILE, (soft, hard))
+        assert len(armed) == count
+        assert set(armed) == {(60, 60)}
+        assert closing == 0
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
@@ -3,6 +3,9 @@ Test UDP/Datagram transport implementation with VeloxLoop
 """
 
 import asyncio
+import errno
+import resource
+import socket
 
 import pytest
 
@@ -170,6 +173,22 @@ class TestUDPTransport:
 
         asyncio.run(main())
 
+    def test_udp_sendto_host_name_rejected(self):
+        """Test sendto() takes IP literals only, never resolving on the loop"""
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, _ = await loop.create_datagram_endpoint(
+                asyncio.DatagramProtocol, local_addr=('127.0.0.1', 0)
+            )
+            port = transport.get_extra_info('sockname')[1]
+            with pytest.raises(ValueError, match='host name'):
+                transport.sendto(b'x', ('localhost', port))
+            transport.sendto(b'x', ('127.0.0.1', port))
+            transport.close()
+
+        asyncio.run(main())
+
     def test_udp_get_extra_info(self):
         """Test get_extra_info on UDP transport"""
 
@@ -284,6 +303,42 @@ class TestUDPTransport:
 
         asyncio.run(main())
 
+    @pytest.mark.parametrize('first', ['close', 'abort'])
+    def test_udp_connection_lost_once(self, first):
+        """Test connection_lost runs once however often the transport is closed
+        or aborted, including from connection_lost itself"""
+
+        class ClosesAgain(asyncio.DatagramProtocol):
+            def __init__(self):
+                self.lost = []
+
+            def connection_made(self, transport):
+                self.transport = transport
+
+            def connection_lost(self, exc):
+                self.lost.append(exc)
+                self.transport.close()
+                self.transport.abort()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            errors = []
+            loop.set_exception_handler(lambda loop, context: errors.append(context))
+            transport, protocol = await loop.create_datagram_endpoint(
+                ClosesAgain, local_addr=('127.0.0.1', 0)
+            )
+            getattr(transport, first)()
+            transport.close()
+            transport.abort()
+            await asyncio.sleep(0.05)
+            assert protocol.lost == [None]
+            assert transport.is_closing()
+            assert errors == []
+            with pytest.raises(RuntimeError):
+                transport.sendto(b'late', ('127.0.0.1', 9))
+
+        asyncio.run(main())
+
     def test_udp_multiple_messages(self):
         """Test sending and receiving multiple UDP messages"""
 
@@ -408,5 +463,238 @@ class TestUDPTransport:
         asyncio.run(main())
 
 
+    def test_udp_connected_error_received(self):
+        """Test a connected endpoint sees ConnectionRefusedError once its peer is gone"""
+
+        async def main():
+            loop = asyncio.get_event_loop()
+
+            server_transport, _ = await loop.create_datagram_endpoint(
+                EchoDatagramProtocol, local_addr=('127.0.0.1', 0)
+            )
+            server_addr = server_transport.get_extra_info('sockname')
+
+            client_protocol = EchoDatagramProtocol()
+            client_transport, _ = await loop.create_datagram_endpoint(
+                lambda: client_protocol, remote_addr=server_addr
+            )
+            server_transport.close()
+
+            # The first send draws the ICMP error, the next one (or the reader) reports it
+            for _ in range(50):
+                client_transport.sendto(b'ping')
+                await asyncio.sleep(0.01)
+                if client_protocol.errors:
+                    break
+
+            assert client_protocol.errors
+            assert isinstance(client_protocol.errors[0], ConnectionRefusedError)
+            assert not client_transport.is_closing()
+
+            with pytest.raises(ValueError):
+                client_transport.sendto(b'x', ('127.0.0.1', 9))
+            client_transport.close()
+
+        asyncio.run(main())
+
+    def test_udp_sockname_reports_assigned_port(self):
+        """Test sockname and socket.getsockname() show the ephemeral port"""
+
+        async def main():
+            loop = asyncio.get_event_loop()
+
+            transport, _ = await loop.create_datagram_endpoint(
+                EchoDatagramProtocol, local_addr=('127.0.0.1', 0)
+            )
+            host, port = transport.get_extra_info('sockname')
+            assert host == '127.0.0.1'
+            assert port != 0
+            assert transport.get_extra_info('socket').getsockname() == (host, port)
+            transport.close()
+
+        asyncio.run(main())
+
+    def test_udp_backlog_delivered_in_order(self):
+        """Test a backlog larger than one read batch arrives whole and in order"""
+
+        class Collector(asyncio.DatagramProtocol):
+            def __init__(self):
+                self.received = []
+
+            def datagram_received(self, data, addr):
+                self.received.append((data, addr))
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, protocol = await loop.create_datagram_endpoint(
+                Collector, local_addr=('127.0.0.1', 0)
+            )
+            addr = transport.get_extra_info('sockname')
+            with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as sender:
+                sender.bind(('127.0.0.1', 0))
+                # Queued before the loop runs again, so several batches are read
+                payloads = [b'%03d' % i for i in range(100)] + [b'z' * 60000]
+                for payload in payloads:
+                    sender.sendto(payload, addr)
+                for _ in range(100):
+                    if len(protocol.received) == len(payloads):
+                        break
+                    await asyncio.sleep(0.01)
+                assert [data for data, _ in protocol.received] == payloads
+                assert all(type(data) is bytes for data, _ in protocol.received)
+                assert {src for _, src in protocol.received} == {sender.getsockname()}
+            transport.close()
+
+        asyncio.run(main())
+
+    def test_udp_close_inside_batch(self):
+        """Test closing in datagram_received stops the rest of the batch"""
+
+        class CloseOnFirst(asyncio.DatagramProtocol):
+            def __init__(self):
+                self.received = []
+
+            def connection_made(self, transport):
+                self.transport = transport
+
+            def datagram_received(self, data, addr):
+                self.received.append(data)
+                self.transport.close()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, protocol = await loop.create_datagram_endpoint(
+                CloseOnFirst, local_addr=('127.0.0.1', 0)
+            )
+            addr = transport.get_extra_info('sockname')
+            with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as sender:
+                for i in range(10):
+                    sender.sendto(b'%d' % i, addr)
+            await asyncio.sleep(0.1)
+            assert protocol.received == [b'0']
+            assert transport.is_closing()
+
+        asyncio.run(main())
+
+    def test_udp_callback_error_keeps_batch(self):
+        """Test a datagram_received that raises is reported and the batch goes on"""
+        errors = []
+
+        class FailOnOdd(asyncio.DatagramProtocol):
+            def __init__(self):
+                self.received = []
+
+            def datagram_received(self, data, addr):
+                self.received.append(data)
+                if int(data) % 2:
+                    raise ValueError(data)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            loop.set_exception_handler(lambda lp, ctx: errors.append(ctx))
+            transport, protocol = await loop.create_datagram_endpoint(
+                FailOnOdd, local_addr=('127.0.0.1', 0)
+            )
+            addr = transport.get_extra_info('sockname')
+            with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as sender:
+                for i in range(10):
+                    sender.sendto(b'%d' % i, addr)
+            await asyncio.sleep(0.1)
+            assert protocol.received == [b'%d' % i for i in range(10)]
+            assert not transport.is_closing()
+            transport.close()
+
+        asyncio.run(main())
+        assert [ctx['exception'].args[0] for ctx in errors] == [b'1', b'3', b'5', b'7', b'9']
+        assert all('datagram_received' in ctx['message'] for ctx in errors)
+
+    def test_udp_endpoints_share_receive_buffer(self):
+        """Test each endpoint that reads doesn't hold a batch-sized buffer of its own"""
+        count = 64
+
+        class Counter(asyncio.DatagramProtocol):
+            received = 0
+
+            def datagram_received(self, data, addr):
+                Counter.received += 1
+
+        def rss():
+            with open('/proc/self/statm') as f:
+                return int(f.read().split()[1]) * resource.getpagesize()
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            endpoints = [
+                await loop.create_datagram_endpoint(Counter, local_addr=('127.0.0.1', 0))
+                for _ in range(count)
+            ]
+            before = rss()
+            with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as sender:
+                for transport, _ in endpoints:
+                    sender.sendto(b'x', transport.get_extra_info('sockname'))
+            for _ in range(100):
+                if Counter.received == count:
+                    break
+                await asyncio.sleep(0.01)
+            assert Counter.received == count
+            # A zero-filled 2 MB batch each would be 128 MB
+            assert rss() - before < 16 * 1024 * 1024
+            for transport, _ in endpoints:
+                transport.close()
+
+        asyncio.run(main())
+
+    def test_udp_max_datagram_size(self):
+        """Test a datagram at max_datagram_size arrives and a longer one is reported"""
+
+        class Recorder(asyncio.DatagramProtocol):
+            def __init__(self):
+                self.received = []
+                self.errors = []
+
+            def datagram_received(self, data, addr):
+                self.received.append(data)
+
+            def error_received(self, exc):
+                self.errors.append(exc)
+
+        async def main():
+            loop = asyncio.get_running_loop()
+            transport, protocol = await loop.create_datagram_endpoint(
+                Recorder, local_addr=('127.0.0.1', 0), max_datagram_size=1024
+            )
+            assert transport.max_datagram_size == 1024
+            addr = transport.get_extra_info('sockname')
+            with socket.socket(socket.AF_INET, socket.SOCK_DGRAM) as sender:
+                for payload in (b'a' * 1024, b'b' * 1025, b'c' * 10):
+                    sender.sendto(payload, addr)
+                for _ in range(100):
+                    if len(protocol.received) == 2:
+                        break
+                    await asyncio.sleep(0.01)
+            assert protocol.received == [b'a' * 1024, b'c' * 10]
+            assert len(protocol.errors) == 1
+            exc = protocol.errors[0]
+            assert isinstance(exc, OSError)
+            assert exc.errno == errno.EMSGSIZE
+            assert '1025 bytes' in str(exc)
+            assert not transport.is_closing()
+            transport.close()
+
+            default, _ = await loop.create_datagram_endpoint(
+                Recorder, local_addr=('127.0.0.1', 0)
+            )
+            assert default.max_datagram_size == 65536
+            default.close()
+
+            for bad in (0, -1, 'big'):
+                with pytest.raises(ValueError):
+                    await loop.create_datagram_endpoint(
+                        Recorder, local_addr=('127.0.0.1', 0), max_datagram_size=bad
+                    )
+
+        asyncio.run(main())
+
+
 if __name__ == '__main__':
     pytest.main([__file__, '-v'])
@@ -0,0 +1,159 @@
+"""Tests for test_mode=True: a virtual loop clock driven by loop.advance_time()"""
+
+import asyncio
+import socket
+import time
+
+import pytest
+
+import veloxloop
+from veloxloop import VeloxLoop
+
+
+def _run(main):
+    loop = VeloxLoop(test_mode=True)
+    try:
+        return loop.run_until_complete(main(loop))
+    finally:
+        loop.close()
+
+
+async def _drive(loop, task, step):
+    """Advance the clock by `step` whenever `task` is waiting, until it is done"""
+    while True:
+        await asyncio.sleep(0)
+        if task.done():
+            return await task
+        loop.advance_time(step)
+
+
+class TestVirtualClock:
+    def setup_method(self):
+        veloxloop.install()
+
+    def test_clock_only_moves_on_advance(self):
+        """Test time() starts at 0 and follows advance_time() alone"""
+
+        async def main(loop):
+            assert loop.time() == 0.0
+            time.sleep(0.05)
+            assert loop.time() == 0.0
+            assert loop.advance_time(1.5) == 0
+            assert loop.time() == 1.5
+
+        _run(main)
+
+    def test_advance_time_guardrails(self):
+        """Test advance_time refuses real-clock loops, negative steps and closed loops"""
+        loop = VeloxLoop()
+        with pytest.raises(RuntimeError, match='test_mode=True'):
+            loop.advance_time(1)
+        loop.close()
+
+        loop = VeloxLoop(test_mode=True)
+        with pytest.raises(ValueError):
+            loop.advance_time(-1)
+        with pytest.raises(ValueError):
+            loop.advance_time(float('nan'))
+        loop.close()
+        with pytest.raises(RuntimeError, match='closed'):
+            loop.advance_time(1)
+
+    def test_advance_fires_due_timers_in_order(self):
+        """Test due timers run inside advance_time, by deadline, and later ones wait"""
+
+        async def main(loop):
+            fired = []
+            loop.call_later(3, fired.append, 'c')
+            loop.call_later(1, fired.append, 'a')
+            loop.call_at(2, fired.append, 'b')
+            cancelled = loop.call_later(1.5, fired.append, 'x')
+            cancelled.cancel()
+            loop.call_later(10, fired.append, 'late')
+
+            assert loop.advance_time(0.5) == 0
+            assert loop.advance_time(2.5) == 3
+            assert fired == ['a', 'b', 'c']
+            loop.advance_time(7)
+            assert fired == ['a', 'b', 'c', 'late']
+
+        _run(main)
+
+    def test_run_once_does_not_sleep(self):
+        """Test an iteration with only a far timer returns at once"""
+        loop = VeloxLoop(test_mode=True)
+        fired = []
+        loop.call_later(3600, fired.append, True)
+        start = time.perf_counter()
+        for _ in range(10):
+            loop._run_once()
+        assert time.perf_counter() - start < 0.5
+        assert fired == []
+        loop.advance_time(3600)
+        assert fired == [True]
+        loop.close()
+
+    def test_backoff_replays_in_milliseconds(self):
+        """Test a retry loop with exponential backoff runs in virtual time"""
+
+        async def main(loop):
+            attempts = []
+
+            async def flaky():
+                attempts.append(loop.time())
+                if len(attempts) < 6:
+                    raise ConnectionError('try again')
+                return 'ok'
+
+            async def retry(delay=1.0, factor=2.0):
+                while True:
+                    try:
+                        return await flaky()
+                    except ConnectionError:
+                        await asyncio.sleep(delay)
+                        delay *= factor
+
+            task = loop.create_task(retry())
+            return await _drive(loop, task, 0.25), attempts
+
+        start = time.perf_counter()
+        result, attempts = _run(main)
+        assert time.perf_counter() - start < 1.0
+        assert result == 'ok'
+        assert attempts == [0.0, 1.0, 3.0, 7.0, 15.0, 31.0]
+
+    def test_timeout_uses_virtual_clock(self):
+        """Test asyncio.timeout() expires on virtual time"""
+
+        async def main(loop):
+            async def wait_forever():
+                async with asyncio.timeout(30):
+                    await loop.create_future()
+
+            task = loop.create_task(wait_forever())
+            with pytest.raises(TimeoutError):
+                await _drive(loop, task, 1)
+            return loop.time()
+
+        assert _run(main) == 30.0
+
+    def test_io_is_real(self):
+        """Test readers fire on real readiness while the clock stands still"""
+
+        async def main(loop):
+            a, b = socket.socketpair()
+            with a, b:
+                a.setblocking(False)
+                ready = loop.create_future()
+                loop.add_reader(a, lambda: ready.set_result(a.recv(16)))
+                loop.call_later(5, b.send, b'late')
+                b.send(b'ping')
+                assert await ready == b'ping'
+                loop.remove_reader(a)
+            assert loop.time() == 0.0
+
+        _run(main)
+
+
+if __name__ == '__main__':
+    pytest.main([__file__, '-v'])
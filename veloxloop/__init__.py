@@ -1,30 +1,41 @@
 """VeloxLoop: An asyncio-compatible event loop implemented in Rust."""
 import asyncio
+import functools
+import ipaddress
+import itertools
+import socket
+from asyncio import staggered
 from ._veloxloop import VeloxLoop as _VeloxLoopImpl
 from ._veloxloop import VeloxLoopPolicy as _VeloxLoopPolicyImpl
 from ._veloxloop import StreamReader, StreamWriter
+from ._veloxloop import TransportFactoryConfig
+from . import _veloxloop
 import threading
+import time
+import warnings
 
 __version__ = '0.2.0'
 
+# Block size for sock_sendfile() when it falls back to reads and sends
+_SENDFILE_FALLBACK_BLOCK = 256 * 1024
 
-class VeloxLoop(_VeloxLoopImpl, asyncio.AbstractEventLoop):
-    """An asyncio-compatible event loop implemented in Rust.""" 
+# Seconds a getifaddrs() answer stands for the families AI_ADDRCONFIG allows
+_ADDRCONFIG_TTL = 1.0
+_addrconfig_cache = (float('-inf'), frozenset())
 
-    def get_debug(self):
-        """Get or get the debug mode of the event loop."""
-        return getattr(self, '_debug', False)
 
-    def set_debug(self, enabled):
-        """Set the debug mode of the event loop."""
-        self._debug = enabled
+class VeloxLoop(_VeloxLoopImpl, asyncio.AbstractEventLoop):
+    """An asyncio-compatible event loop implemented in Rust.""" 
 
     def create_task(self, coro, *, name=None, context=None):
         """Create a Task for the given coroutine object."""
+        self._check_closed()
         return asyncio.Task(coro, loop=self, name=name, context=context)
 
     def run_until_complete(self, future):
         """Run the event loop until the Future is done."""
+        self._check_closed()
+        self._check_running()
         future = asyncio.ensure_future(future, loop=self)
         future.add_done_callback(lambda f: self.stop())
         self.run_forever()
@@ -32,8 +43,15 @@ class VeloxLoop(_VeloxLoopImpl, asyncio.AbstractEventLoop):
             raise RuntimeError('Event loop stopped before Future completed.')
         return future.result()
 
+    def _check_running(self):
+        # Checked before touching the running-loop context or the future, so a
+        # nested call from a callback can't stop or clobber the outer run
+        if self.is_running():
+            raise RuntimeError('This event loop is already running')
+
     def run_forever(self):
         """Run the event loop until stop() is called."""
+        self._check_running()
         # Set running loop context
         events = asyncio.events
         events._set_running_loop(self)
@@ -53,7 +71,8 @@ class VeloxLoop(_VeloxLoopImpl, asyncio.AbstractEventLoop):
     def call_later(self, delay, callback, *args, context=None):
         """Schedule a callback to be called after a given delay."""
         timer_id = super().call_later(delay, callback, *args, context=context)
-        when = self.time() + delay
+        # The deadline the loop actually uses, not a second reading of time()
+        when = self._timer_when(timer_id)
         return VeloxTimerHandle(timer_id, when, self, callback, args, context)
 
     def call_at(self, when, callback, *args, context=None):
@@ -67,16 +86,338 @@ class VeloxLoop(_VeloxLoopImpl, asyncio.AbstractEventLoop):
         return await super().shutdown_asyncgens()
 
     async def shutdown_default_executor(self, timeout=None):
-        """Shutdown the default executor - for compatibility with asyncio.run."""
-        # asyncio.run calls this with a timeout parameter
-        # For now, we ignore the timeout and just return immediately
-        # In a full implementation, this would shutdown the thread pool executor
-        pass
+        """Shut down the internal thread pool once its queued jobs finish.
+
+        An executor passed to set_default_executor() is left to its owner.
+        """
+        try:
+            await asyncio.wait_for(self._shutdown_default_executor(), timeout)
+        except asyncio.TimeoutError:
+            warnings.warn(
+                f'The executor did not finishing joining its threads within {timeout} seconds.',
+                RuntimeWarning,
+                stacklevel=2,
+            )
+
+    def getaddrinfo(self, host, port, *, family=0, type=0, proto=0, flags=0):
+        """Resolve host/port, through the resolver given to set_resolver() if any.
+
+        Numeric hosts and AI_PASSIVE lookups always take the libc path.
+        """
+        resolver = self.get_resolver()
+        if resolver is None or flags & socket.AI_PASSIVE or _is_numeric_host(host):
+            return super().getaddrinfo(
+                host, port, family=family, type=type, proto=proto, flags=flags
+            )
+        return self._resolve(resolver, host, port, family, type, proto, flags)
+
+    async def _resolve(self, resolver, host, port, family, type, proto, flags):
+        try:
+            infos = await resolver.resolve(host, port, family)
+            infos = _filter_resolved(infos, family, type, proto)
+        except Exception:
+            if not self._resolver_fallback():
+                raise
+            infos = []
+        if infos:
+            return infos
+        if not self._resolver_fallback():
+            raise socket.gaierror(
+                socket.EAI_NONAME, f'resolver found no addresses for {host!r}'
+            )
+        return await super().getaddrinfo(
+            host, port, family=family, type=type, proto=proto, flags=flags
+        )
+
+    def sock_connect(self, sock, address):
+        """Connect sock to address. A host name is looked up with getaddrinfo()
+        first, as asyncio does, rather than blocking the loop thread.
+        """
+        if (
+            sock.family in (socket.AF_INET, socket.AF_INET6)
+            and isinstance(address, tuple)
+            and len(address) >= 2
+            and not _is_numeric_host(address[0])
+        ):
+            return self._sock_connect_resolved(sock, address)
+        return super().sock_connect(sock, address)
+
+    async def _sock_connect_resolved(self, sock, address):
+        host, port = address[:2]
+        infos = await self.getaddrinfo(
+            host, port, family=sock.family, type=sock.type, proto=sock.proto
+        )
+        # Each address in turn, as socket.create_connection() does
+        error = None
+        for info in infos:
+            try:
+                return await super().sock_connect(sock, info[4])
+            except ConnectionAbortedError as exc:
+                if error is None:
+                    raise
+                error = exc
+            except OSError as exc:
+                error = exc
+                continue
+            # Linux answers the first connect() after a failed one with
+            # ECONNABORTED as it resets the socket; the next one goes out
+            try:
+                return await super().sock_connect(sock, info[4])
+            except OSError as exc:
+                error = exc
+        raise error
+
+    def create_connection(
+        self,
+        protocol_factory,
+        host=None,
+        port=None,
+        *,
+        family=0,
+        proto=0,
+        flags=None,
+        happy_eyeballs_delay=None,
+        interleave=None,
+        all_errors=False,
+        **kwargs,
+    ):
+        """Open a TCP connection, trying each resolved address in turn.
+
+        family/proto/flags restrict the lookup as in getaddrinfo(). Without
+        flags the lookup uses AI_ADDRCONFIG, and addresses of a family the
+        host has no non-loopback address for are skipped even where libc
+        ignores the flag; flags are passed on verbatim otherwise. Addresses
+        of local_addr's family are tried first; a local_addr host name is
+        looked up with getaddrinfo() too, and each of its addresses of the
+        remote address's family tried in turn. If every
+        address fails, the last OSError is raised, or with all_errors=True an
+        ExceptionGroup holding one OSError per address. happy_eyeballs_delay
+        races the addresses (RFC 8305), starting the next attempt after that
+        many seconds; interleave alternates address families, leading with
+        that many addresses of the first one.
+        """
+        self._check_kwargs('create_connection', kwargs)
+        if happy_eyeballs_delay is not None and interleave is None:
+            interleave = 1
+        if kwargs.get('sock') is not None or (
+            not (family or proto or flags or all_errors)
+            and happy_eyeballs_delay is None
+            and _is_numeric_host(host)
+            and _is_numeric_local_addr(kwargs.get('local_addr'))
+        ):
+            # A single known address: nothing to resolve or aggregate
+            return super().create_connection(protocol_factory, host, port, **kwargs)
+        return self._create_resolved_connection(
+            protocol_factory,
+            host,
+            port,
+            family,
+            proto,
+            flags,
+            happy_eyeballs_delay,
+            interleave,
+            all_errors,
+            kwargs,
+        )
+
+    async def _create_resolved_connection(
+        self,
+        protocol_factory,
+        host,
+        port,
+        family,
+        proto,
+        flags,
+        happy_eyeballs_delay,
+        interleave,
+        all_errors,
+        kwargs,
+    ):
+        if flags is None:
+            try:
+                infos = await self.getaddrinfo(
+                    host,
+                    port,
+                    family=family,
+                    type=socket.SOCK_STREAM,
+                    proto=proto,
+                    flags=socket.AI_ADDRCONFIG,
+                )
+            except socket.gaierror:
+                # glibc finds nothing with only loopback configured; any
+                # other failure is the lookup's own
+                if _addrconfig_families():
+                    raise
+                infos = await self.getaddrinfo(
+                    host, port, family=family, type=socket.SOCK_STREAM, proto=proto
+                )
+            infos = _addrconfig_filter(infos)
+        else:
+            infos = await self.getaddrinfo(
+                host, port, family=family, type=socket.SOCK_STREAM, proto=proto, flags=flags
+            )
+        local_infos = await self._resolve_local_addr(
+            kwargs.get('local_addr'), family, proto, flags
+        )
+        infos = _prefer_family_of(infos, kwargs.get('local_addr'), local_infos)
+        if kwargs.get('ssl') and kwargs.get('server_hostname') is None:
+            kwargs['server_hostname'] = host
+        unique = {}
+        for info in infos:
+            unique.setdefault(info[4][:2], info)
+        infos = list(unique.values())
+        if interleave:
+            infos = _interleave_addrinfos(infos, interleave)
+
+        if happy_eyeballs_delay is None:
+            errors = []
+            for info in infos:
+                local_addrs = _local_addrs_for(info, kwargs.get('local_addr'), local_infos)
+                if not local_addrs:
+                    errors.append(_no_local_addr(info[0]))
+                for local_addr in local_addrs:
+                    try:
+                        return await super().create_connection(
+                            protocol_factory, *info[4][:2], **dict(kwargs, local_addr=local_addr)
+                        )
+                    except OSError as exc:
+                        errors.append(exc)
+        else:
+            for name in ('fastopen', 'transparent', 'freebind'):
+                if kwargs.get(name):
+                    raise ValueError(f'{name} can not be combined with happy_eyeballs_delay')
+            local_addr = kwargs.pop('local_addr', None)
+            timeout = kwargs.pop('timeout', None)
+            sock, _, errors = await staggered.staggered_race(
+                [
+                    functools.partial(
+                        self._connect_sock,
+                        info,
+                        _local_addrs_for(info, local_addr, local_infos),
+                        timeout,
+                    )
+                    for info in infos
+                ],
+                happy_eyeballs_delay,
+                loop=self,
+            )
+            if sock is not None:
+                # The native side works on its own duplicate of the fd
+                with sock:
+                    return await super().create_connection(
+                        protocol_factory, sock=sock, **kwargs
+                    )
+            errors = [exc for exc in errors if exc is not None]
+        if all_errors:
+            raise ExceptionGroup('create_connection failed', errors)
+        raise errors[-1]
+
+    async def _connect_sock(self, info, local_addrs, timeout):
+        """One happy eyeballs attempt: a connected socket for `info`, bound to
+        the first of `local_addrs` that takes"""
+        family, _, proto, _, address = info
+        sock = socket.socket(family, socket.SOCK_STREAM, proto)
+        try:
+            sock.setblocking(False)
+            if not local_addrs:
+                raise _no_local_addr(family)
+            for local_addr in local_addrs:
+                if local_addr is None:
+                    break
+                try:
+                    sock.bind(local_addr)
+                    break
+                except OSError as exc:
+                    bind_error = exc
+            else:
+                raise bind_error
+            async with asyncio.timeout(timeout):
+                await self.sock_connect(sock, address)
+            return sock
+        except BaseException:
+            sock.close()
+            raise
+
+    async def _resolve_local_addr(self, local_addr, family, proto, flags):
+        """getaddrinfo() results for a local_addr host name; None when there
+        is nothing to look up"""
+        if _is_numeric_local_addr(local_addr):
+            return None
+        return await self.getaddrinfo(
+            *local_addr[:2],
+            family=family,
+            type=socket.SOCK_STREAM,
+            proto=proto,
+            flags=flags or 0,
+        )
+
+    def open_connection(self, host=None, port=None, limit=None, **kwargs):
+        """Open a (reader, writer) stream pair to host/port. A host name, or
+        a local_addr one, is looked up with getaddrinfo() and each address
+        tried in turn; if every address fails, the last OSError is raised.
+        """
+        if kwargs.get('sock') is not None or (
+            _is_numeric_host(host) and _is_numeric_local_addr(kwargs.get('local_addr'))
+        ):
+            return super().open_connection(host, port, limit, **kwargs)
+        return self._open_resolved_connection(host, port, limit, kwargs)
+
+    async def _open_resolved_connection(self, host, port, limit, kwargs):
+        self._check_kwargs('open_connection', kwargs)
+        infos = await self.getaddrinfo(host, port, type=socket.SOCK_STREAM)
+        local_infos = await self._resolve_local_addr(kwargs.get('local_addr'), 0, 0, None)
+        infos = _prefer_family_of(infos, kwargs.get('local_addr'), local_infos)
+        errors = []
+        for info in infos:
+            local_addrs = _local_addrs_for(info, kwargs.get('local_addr'), local_infos)
+            if not local_addrs:
+                errors.append(_no_local_addr(info[0]))
+            for local_addr in local_addrs:
+                try:
+                    return await super().open_connection(
+                        *info[4][:2], limit, **dict(kwargs, local_addr=local_addr)
+                    )
+                except OSError as exc:
+                    errors.append(exc)
+        raise errors[-1]
 
     async def create_datagram_endpoint(
         self, protocol_factory, local_addr=None, remote_addr=None, **kwargs
     ):
         """Create datagram endpoint - delegates to Rust implementation."""
+        self._check_kwargs('create_datagram_endpoint', kwargs)
+        if (
+            remote_addr is not None
+            and self.get_resolver() is not None
+            and not _is_numeric_host(remote_addr[0])
+        ):
+            host, port = remote_addr
+            infos = await self.getaddrinfo(
+                host,
+                port,
+                family=kwargs.get('family', 0),
+                type=socket.SOCK_DGRAM,
+                proto=kwargs.get('proto', 0),
+                flags=kwargs.get('flags', 0),
+            )
+            family = _family_of(local_addr)
+            if family:
+                # The socket takes local_addr's family; only its addresses fit
+                infos = [info for info in infos if info[0] == family]
+                if not infos:
+                    raise _no_local_addr(family)
+            # Each address in turn; the last error if none can be connected to
+            for info in infos:
+                try:
+                    return await super().create_datagram_endpoint(
+                        protocol_factory,
+                        local_addr=local_addr,
+                        remote_addr=(info[4][0], port),
+                        **kwargs,
+                    )
+                except OSError as exc:
+                    error = exc
+            raise error
         # Call the Rust implementation
         return await super().create_datagram_endpoint(
             protocol_factory, local_addr=local_addr, remote_addr=remote_addr, **kwargs
@@ -94,7 +435,13 @@ class VeloxLoop(_VeloxLoopImpl, asyncio.AbstractEventLoop):
         data = self._sock_recv_try(sock, nbytes)
         if data is not None:
             return data
-        return await self._sock_recv_wait(sock, nbytes)
+        waiter = self._sock_recv_wait(sock, nbytes)
+        try:
+            return await waiter
+        except asyncio.CancelledError:
+            # Parks the wait so unread data stays for the next sock_recv
+            waiter.cancel()
+            raise
 
     async def sock_sendall(self, sock, data):
         """Send all data to socket — fast-path avoids data copy and Future creation."""
@@ -102,7 +449,68 @@ class VeloxLoop(_VeloxLoopImpl, asyncio.AbstractEventLoop):
         if result is None:
             return
         # result is a PendingFuture for async completion
-        return await result
+        try:
+            return await result
+        except asyncio.CancelledError:
+            # Drops the writer callback and releases the buffer
+            result.cancel()
+            raise
+
+    async def wrap_socket_streams(self, sock, *, limit=2**16):
+        """Build a (StreamReader, StreamWriter) pair around a connected socket,
+        such as one returned by StreamTransport.detach(). The streams take the
+        socket over: it is closed here once they hold their own handle."""
+        streams = await self.open_connection(sock=sock, limit=limit)
+        sock.close()
+        return streams
+
+    async def sock_sendfile(self, sock, file, offset=0, count=None, *, fallback=True):
+        """Send a file over a socket with sendfile(), like asyncio's."""
+        _check_sendfile_params(sock, file, offset, count)
+        try:
+            return await self._sock_sendfile_native(sock, file, offset, count)
+        except asyncio.SendfileNotAvailableError:
+            if not fallback:
+                raise
+        return await self._sock_sendfile_fallback(sock, file, offset, count)
+
+    async def _sock_sendfile_native(self, sock, file, offset, count):
+        result = self._sock_sendfile_try(sock, file, offset, count)
+        if not isinstance(result, int):
+            try:
+                result = await result
+            except asyncio.CancelledError:
+                # Drops the writer callback
+                result.cancel()
+                raise
+        # sendfile() works on offsets; leave the file where the data ended
+        if result > 0:
+            file.seek(offset + result)
+        return result
+
+    async def _sock_sendfile_fallback(self, sock, file, offset, count):
+        """Read the file in blocks and sock_sendall() each one."""
+        if offset:
+            file.seek(offset)
+        blocksize = min(count or _SENDFILE_FALLBACK_BLOCK, _SENDFILE_FALLBACK_BLOCK)
+        buf = bytearray(blocksize)
+        total_sent = 0
+        try:
+            while True:
+                if count:
+                    blocksize = min(count - total_sent, blocksize)
+                    if blocksize <= 0:
+                        break
+                view = memoryview(buf)[:blocksize]
+                read = await self.run_in_executor(None, file.readinto, view)
+                if not read:
+                    break
+                await self.sock_sendall(sock, view[:read])
+                total_sent += read
+            return total_sent
+        finally:
+            if total_sent > 0 and hasattr(file, 'seek'):
+                file.seek(offset + total_sent)
 
 
 class VeloxTimerHandle(asyncio.TimerHandle):
@@ -137,6 +545,10 @@ class VeloxLoopPolicy(_VeloxLoopPolicyImpl, asyncio.AbstractEventLoopPolicy):
     def get_event_loop(self):
         """Get the current event loop for the thread, creating one if necessary."""
         loop = getattr(self._local, 'loop', None)
+        if isinstance(loop, _VeloxLoopImpl) and loop._forked():
+            # Inherited through fork(): its ring and threads belong to the
+            # parent, so the child starts over with a loop of its own
+            loop = None
         if loop is None:
             # For strict asyncio compliance, get_event_loop only creates on main thread 
             # or raises RuntimeError?
@@ -154,26 +566,192 @@ class VeloxLoopPolicy(_VeloxLoopPolicyImpl, asyncio.AbstractEventLoopPolicy):
 
     def new_event_loop(self):
         """Create a new VeloxLoop event loop instance."""
-        return VeloxLoop(debug=False)
+        return VeloxLoop()
+
+
+def _is_numeric_host(host):
+    """True for None and literal IPv4/IPv6 addresses, which need no lookup"""
+    if host is None:
+        return True
+    if isinstance(host, bytes):
+        host = host.decode('idna')
+    try:
+        ipaddress.ip_address(host.partition('%')[0])
+    except ValueError:
+        return False
+    return True
+
+
+def _check_sendfile_params(sock, file, offset, count):
+    """asyncio's argument checks for sock_sendfile()"""
+    if 'b' not in getattr(file, 'mode', 'b'):
+        raise ValueError('file should be opened in binary mode')
+    if sock.type != socket.SOCK_STREAM:
+        raise ValueError('only SOCK_STREAM type sockets are supported')
+    if sock.gettimeout() != 0:
+        raise ValueError('the socket must be non-blocking')
+    if count is not None:
+        if not isinstance(count, int):
+            raise TypeError(f'count must be a positive integer (got {count!r})')
+        if count <= 0:
+            raise ValueError(f'count must be a positive integer (got {count!r})')
+    if not isinstance(offset, int):
+        raise TypeError(f'offset must be a non-negative integer (got {offset!r})')
+    if offset < 0:
+        raise ValueError(f'offset must be a non-negative integer (got {offset!r})')
+
+
+def _interleave_addrinfos(infos, first_family_count):
+    """Alternate address families as RFC 8305 asks: `first_family_count`
+    addresses of the first family, then one of each family in turn"""
+    by_family = {}
+    for info in infos:
+        by_family.setdefault(info[0], []).append(info)
+    lists = list(by_family.values())
+    ordered = lists[0][: first_family_count - 1]
+    del lists[0][: first_family_count - 1]
+    ordered.extend(
+        info for info in itertools.chain(*itertools.zip_longest(*lists)) if info is not None
+    )
+    return ordered
+
+
+def _addrconfig_families():
+    """Families the host has a non-loopback address configured for, read
+    again once the last answer is _ADDRCONFIG_TTL seconds old"""
+    global _addrconfig_cache
+    checked, families = _addrconfig_cache
+    now = time.monotonic()
+    if now - checked >= _ADDRCONFIG_TTL:
+        families = frozenset(_veloxloop.configured_address_families())
+        _addrconfig_cache = (now, families)
+    return families
+
+
+def _addrconfig_filter(infos):
+    """AI_ADDRCONFIG applied to resolved addresses: drop those of families
+    the host can't reach, unless that would leave none"""
+    families = _addrconfig_families()
+    return [info for info in infos if info[0] in families] or infos
+
+
+def _is_numeric_local_addr(local_addr):
+    """True when `local_addr` is absent or its host needs no lookup"""
+    return local_addr is None or _is_numeric_host(local_addr[0])
+
+
+def _family_of(local_addr):
+    """The address family of a numeric `local_addr`; 0 if absent or a name"""
+    if local_addr is None:
+        return 0
+    try:
+        version = ipaddress.ip_address(local_addr[0].partition('%')[0]).version
+    except ValueError:
+        return 0
+    return socket.AF_INET6 if version == 6 else socket.AF_INET
+
+
+def _prefer_family_of(infos, local_addr, local_infos=None):
+    """Addresses of the family of `local_addr` first: that of a numeric one,
+    or those its looked-up addresses (`local_infos`) have"""
+    if local_infos is not None:
+        families = {info[0] for info in local_infos}
+        return sorted(infos, key=lambda info: info[0] not in families)
+    family = _family_of(local_addr)
+    if not family:
+        return infos
+    return sorted(infos, key=lambda info: info[0] != family)
+
+
+def _local_addrs_for(info, local_addr, local_infos):
+    """The local addresses to try binding for a connect to `info`: the
+    looked-up ones of its family, else `local_addr` as given (None included)"""
+    if local_infos is None:
+        return [local_addr]
+    return [local[4] for local in local_infos if local[0] == info[0]]
+
+
+def _no_local_addr(family):
+    """asyncio's error for a remote address no local_addr address matches"""
+    return OSError(f'no matching local address with family={family!r} found')
+
+
+def _filter_resolved(infos, family, type, proto):
+    """Normalize resolver results to getaddrinfo 5-tuples matching the request"""
+    result = []
+    for fam, typ, pro, canonname, sockaddr in infos:
+        if family and fam != family:
+            continue
+        if type and typ and typ != type:
+            continue
+        result.append(
+            (
+                _socket_enum(socket.AddressFamily, fam),
+                _socket_enum(socket.SocketKind, typ or type),
+                pro or proto,
+                canonname or '',
+                tuple(sockaddr),
+            )
+        )
+    return result
+
+
+def _socket_enum(cls, value):
+    """cls(value), or the plain int when the enum has no such member"""
+    try:
+        return cls(value)
+    except ValueError:
+        return value
+
+
+# The policy install() replaced, put back by uninstall()
+_previous_policy = None
+_policy_lock = threading.Lock()
 
 
 def install():
-    """Install VeloxLoop as the default event loop policy."""
-    asyncio.set_event_loop_policy(VeloxLoopPolicy())
+    """Install VeloxLoop as the default event loop policy.
+
+    Installing again replaces the VeloxLoopPolicy but uninstall() still
+    restores the policy that was there before the first install().
+    """
+    global _previous_policy
+    with _policy_lock:
+        current = asyncio.get_event_loop_policy()
+        if not isinstance(current, VeloxLoopPolicy):
+            _previous_policy = current
+        asyncio.set_event_loop_policy(VeloxLoopPolicy())
+
+
+def uninstall():
+    """Restore the event loop policy install() replaced. Does nothing when
+    VeloxLoop isn't the installed policy."""
+    global _previous_policy
+    with _policy_lock:
+        if isinstance(asyncio.get_event_loop_policy(), VeloxLoopPolicy):
+            asyncio.set_event_loop_policy(_previous_policy)
+            _previous_policy = None
 
 
 def new_event_loop():
-    """Create a new VeloxLoop event loop instance."""
-    return VeloxLoop(debug=False)
+    """Create a new VeloxLoop event loop instance.
+
+    Also a loop factory needing no policy, from any thread:
+    `asyncio.Runner(loop_factory=veloxloop.new_event_loop)`, or
+    `asyncio.run(main(), loop_factory=veloxloop.new_event_loop)` on 3.12+.
+    """
+    return VeloxLoop()
 
 
 __all__ = [
     'StreamReader',
     'StreamWriter',
+    'TransportFactoryConfig',
     'VeloxLoop',
     'VeloxLoopPolicy',
     'VeloxTimerHandle',
     '__version__',
     'install',
     'new_event_loop',
+    'uninstall',
 ]
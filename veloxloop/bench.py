@@ -0,0 +1,132 @@
+"""Side-by-side loop benchmarks: ``python -m veloxloop.bench``.
+
+The scenarios live in the extension (``veloxloop._veloxloop.bench``) and time
+themselves in Rust; this module picks the loops, runs every scenario on a
+fresh loop of each kind and formats the results.
+"""
+
+import argparse
+import asyncio
+import json
+import sys
+
+from ._veloxloop.bench import callback_chain, echo_throughput, timer_storm
+
+__all__ = ['LOOPS', 'callback_chain', 'echo_throughput', 'main', 'run', 'timer_storm']
+
+
+def _veloxloop():
+    from . import VeloxLoop
+
+    return VeloxLoop()
+
+
+def _uvloop():
+    import uvloop
+
+    return uvloop.new_event_loop()
+
+
+# Built directly, so an installed policy can't swap in another loop
+LOOPS = {
+    'veloxloop': _veloxloop,
+    'asyncio': asyncio.SelectorEventLoop,
+    'uvloop': _uvloop,
+}
+
+# Scenario, the columns shown for it, and the options it takes
+SCENARIOS = {
+    'echo': (
+        echo_throughput,
+        ('messages_per_sec', 'p50_us', 'p99_us'),
+        ('connections', 'message_size', 'duration'),
+    ),
+    'timers': (
+        timer_storm,
+        ('timers_per_sec', 'schedule_per_sec', 'lateness_p50_us', 'lateness_p99_us'),
+        ('count', 'spread'),
+    ),
+    'callbacks': (
+        callback_chain,
+        ('callbacks_per_sec',),
+        ('depth', 'width'),
+    ),
+}
+
+
+def run(loops=None, scenarios=None, **options):
+    """Run `scenarios` on each of `loops` (names in LOOPS, all by default).
+
+    Returns ``{scenario: {loop: result}}``; loops that can't be created (say,
+    uvloop isn't installed) are left out. Options not taken by a scenario are
+    ignored by it.
+    """
+    results = {}
+    for scenario in scenarios or SCENARIOS:
+        function, _, accepted = SCENARIOS[scenario]
+        kwargs = {k: v for k, v in options.items() if k in accepted and v is not None}
+        per_loop = results[scenario] = {}
+        for name in loops or LOOPS:
+            try:
+                loop = LOOPS[name]()
+            except ImportError:
+                continue
+            try:
+                per_loop[name] = function(loop, **kwargs)
+            finally:
+                loop.close()
+    return results
+
+
+def format_results(results):
+    """One table per scenario, loops as rows"""
+    lines = []
+    for scenario, per_loop in results.items():
+        columns = SCENARIOS[scenario][1]
+        lines.append(f'## {scenario}')
+        lines.append('| loop | ' + ' | '.join(columns) + ' |')
+        lines.append('| --- ' * (len(columns) + 1) + '|')
+        for name, result in per_loop.items():
+            cells = [f'{result[column]:,.1f}' for column in columns]
+            lines.append(f'| {name} | ' + ' | '.join(cells) + ' |')
+        lines.append('')
+    return '\n'.join(lines)
+
+
+def main(argv=None):
+    parser = argparse.ArgumentParser(
+        prog='python -m veloxloop.bench', description=__doc__.splitlines()[0]
+    )
+    parser.add_argument(
+        '--loop', action='append', choices=list(LOOPS), help='repeat for several (default: all)'
+    )
+    parser.add_argument(
+        '--scenario',
+        action='append',
+        choices=list(SCENARIOS),
+        help='repeat for several (default: all)',
+    )
+    parser.add_argument('--connections', type=int)
+    parser.add_argument('--message-size', type=int)
+    parser.add_argument('--duration', type=float)
+    parser.add_argument('--count', type=int, help='timers in the storm')
+    parser.add_argument('--spread', type=float, help='seconds the timers are due across')
+    parser.add_argument('--depth', type=int)
+    parser.add_argument('--width', type=int)
+    parser.add_argument('--json', action='store_true', help='print the raw results as JSON')
+    args = parser.parse_args(argv)
+
+    options = {
+        k: v for k, v in vars(args).items() if k not in ('loop', 'scenario', 'json')
+    }
+    results = run(args.loop, args.scenario, **options)
+    if args.json:
+        json.dump(results, sys.stdout, indent=2)
+        sys.stdout.write('\n')
+    else:
+        print(format_results(results))
+    return 0
+
+
+if __name__ == '__main__':
+    sys.exit(main())